@@ -0,0 +1,59 @@
+/*
+ * Print the playback timeline of an .ani through the C API
+ *
+ *   cargo build --release --features ffi
+ *   cc examples/ffi/ani_info.c -Iinclude -Ltarget/release -lcursor_handler -o ani_info
+ *   LD_LIBRARY_PATH=target/release ./ani_info assets/aero_busy.ani
+ */
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "cursor_handler.h"
+
+int main(int argc, char **argv) {
+    if (argc != 2) {
+        fprintf(stderr, "usage: %s FILE.ani\n", argv[0]);
+        return 2;
+    }
+
+    FILE *file = fopen(argv[1], "rb");
+    if (!file) {
+        perror(argv[1]);
+        return 1;
+    }
+    fseek(file, 0, SEEK_END);
+    long size = ftell(file);
+    fseek(file, 0, SEEK_SET);
+    uint8_t *bytes = malloc(size);
+    if (fread(bytes, 1, size, file) != (size_t)size) {
+        perror(argv[1]);
+        return 1;
+    }
+    fclose(file);
+
+    ch_ani *ani = NULL;
+    int32_t status = ch_ani_decode(bytes, size, &ani);
+    free(bytes);
+    if (status != CH_OK) {
+        fprintf(stderr, "%s: decode failed (%d)\n", argv[1], status);
+        return 1;
+    }
+
+    for (size_t i = 0; i < ch_ani_frame_count(ani); i++) {
+        uint8_t *rgba;
+        size_t len;
+        uint32_t width, height;
+        if (ch_ani_frame_rgba(ani, i, &rgba, &len, &width, &height) == CH_OK) {
+            printf("frame %zu: %ux%u\n", i, width, height);
+            ch_rgba_free(rgba, len);
+        }
+    }
+    for (size_t i = 0; i < ch_ani_step_count(ani); i++) {
+        uint32_t frame, duration_ms;
+        ch_ani_step(ani, i, &frame, &duration_ms);
+        printf("step %zu: frame %u for %u ms\n", i, frame, duration_ms);
+    }
+
+    ch_ani_free(ani);
+    return 0;
+}
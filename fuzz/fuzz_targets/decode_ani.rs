@@ -0,0 +1,17 @@
+// Seed with the repo's fixtures: cargo +nightly fuzz run decode_ani corpus/decode_ani ../assets
+// Crashing inputs go in ../tests/fuzz_regressions, which `cargo test` runs
+#![no_main]
+
+use std::io::Cursor;
+
+use cursor_handler::{AniFile, DecodeOptions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = AniFile::decode_with(Cursor::new(data), &DecodeOptions::strict());
+    let _ = AniFile::validate_bytes(data);
+    if let Ok(ani) = AniFile::decode(Cursor::new(data)) {
+        let _ = ani.steps().count();
+        let _ = ani.to_string();
+    }
+});
@@ -0,0 +1,16 @@
+// cargo +nightly fuzz run decode_cur
+// Crashing inputs go in ../tests/fuzz_regressions, which `cargo test` runs
+#![no_main]
+
+use std::io::Cursor;
+
+use cursor_handler::{CursorFile, DecodeOptions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = CursorFile::decode(Cursor::new(data));
+    let _ = CursorFile::decode_with(Cursor::new(data), &DecodeOptions::strict());
+    if let Ok(cursor) = CursorFile::decode(Cursor::new(data)) {
+        let _ = cursor.validate();
+    }
+});
@@ -0,0 +1,17 @@
+// DIB/PNG payload to RGBA: cargo +nightly fuzz run decode_image
+#![no_main]
+
+use cursor_handler::{CursorFrame, FrameSize};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&dir_bytes, payload)) = data.split_first_chunk::<2>() else {
+        return;
+    };
+    let frame = CursorFrame::new(
+        FrameSize::from_dir_bytes(dir_bytes),
+        (0, 0),
+        payload.to_vec(),
+    );
+    let _ = frame.decode_image();
+});
@@ -0,0 +1,158 @@
+#ifndef CURSOR_HANDLER_H
+#define CURSOR_HANDLER_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define CH_OK 0
+
+#define CH_ERR_NULL 1
+
+#define CH_ERR_INVALID_DATA 2
+
+#define CH_ERR_OUT_OF_RANGE 3
+
+#define CH_ERR_PANIC 4
+
+/*
+ Opaque handle to a decoded .ani
+ */
+typedef struct ch_ani ch_ani;
+
+/*
+ Opaque handle to a decoded .cur
+ */
+typedef struct ch_cursor ch_cursor;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Decode a .cur from `len` bytes at `data`
+
+ # Safety
+ `data` must point to `len` readable bytes and `out` must be writable.
+ */
+int32_t ch_cursor_decode(const uint8_t *data, size_t len, ch_cursor **out);
+
+/*
+ Number of frames in `cursor`, or 0 if it is NULL
+
+ # Safety
+ `cursor` must be NULL or a live handle from `ch_cursor_decode`.
+ */
+size_t ch_cursor_frame_count(const ch_cursor *cursor);
+
+/*
+ Size of frame `index` in pixels
+
+ # Safety
+ `cursor` must be NULL or a live handle; the outputs must be writable.
+ */
+int32_t ch_cursor_frame_size(const ch_cursor *cursor,
+                             size_t index,
+                             uint32_t *out_width,
+                             uint32_t *out_height);
+
+/*
+ Hotspot of frame `index`
+
+ # Safety
+ `cursor` must be NULL or a live handle; the outputs must be writable.
+ */
+int32_t ch_cursor_frame_hotspot(const ch_cursor *cursor,
+                                size_t index,
+                                uint16_t *out_x,
+                                uint16_t *out_y);
+
+/*
+ Decode frame `index` of `cursor` into a width * height * 4 byte RGBA buffer
+
+ The buffer must be released with `ch_rgba_free`.
+
+ # Safety
+ `cursor` must be NULL or a live handle; the outputs must be writable.
+ */
+int32_t ch_frame_rgba(const ch_cursor *cursor,
+                      size_t index,
+                      uint8_t **out_data,
+                      size_t *out_len,
+                      uint32_t *out_width,
+                      uint32_t *out_height);
+
+/*
+ # Safety
+ `cursor` must be NULL or a handle that has not been freed yet.
+ */
+void ch_cursor_free(ch_cursor *cursor);
+
+/*
+ Decode an .ani from `len` bytes at `data`
+
+ # Safety
+ `data` must point to `len` readable bytes and `out` must be writable.
+ */
+int32_t ch_ani_decode(const uint8_t *data, size_t len, ch_ani **out);
+
+/*
+ Number of stored frames in `ani`, or 0 if it is NULL
+
+ # Safety
+ `ani` must be NULL or a live handle from `ch_ani_decode`.
+ */
+size_t ch_ani_frame_count(const ch_ani *ani);
+
+/*
+ Number of playback steps in `ani`, or 0 if it is NULL
+
+ # Safety
+ `ani` must be NULL or a live handle from `ch_ani_decode`.
+ */
+size_t ch_ani_step_count(const ch_ani *ani);
+
+/*
+ Stored frame index and duration of playback step `index`
+
+ # Safety
+ `ani` must be NULL or a live handle; the outputs must be writable.
+ */
+int32_t ch_ani_step(const ch_ani *ani, size_t index, uint32_t *out_frame, uint32_t *out_duration_ms);
+
+/*
+ Decode stored frame `index` of `ani`, see `ch_frame_rgba`
+
+ # Safety
+ `ani` must be NULL or a live handle; the outputs must be writable.
+ */
+int32_t ch_ani_frame_rgba(const ch_ani *ani,
+                          size_t index,
+                          uint8_t **out_data,
+                          size_t *out_len,
+                          uint32_t *out_width,
+                          uint32_t *out_height);
+
+/*
+ # Safety
+ `ani` must be NULL or a handle that has not been freed yet.
+ */
+void ch_ani_free(ch_ani *ani);
+
+/*
+ Release a buffer returned by `ch_frame_rgba` or `ch_ani_frame_rgba`
+
+ # Safety
+ `data` and `len` must be exactly as returned, and not freed before.
+ */
+void ch_rgba_free(uint8_t *data, size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CURSOR_HANDLER_H */
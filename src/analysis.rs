@@ -0,0 +1,183 @@
+// Measurements of the pixel content across whole cursor files
+use std::{fmt, io};
+
+use image::RgbaImage;
+
+use crate::{cur::CursorFile, file::CursorRef, size::FrameSize};
+
+/// A rectangle of pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn right(self) -> u32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(self) -> u32 {
+        self.y + self.height
+    }
+
+    /// The smallest rectangle holding both
+    pub fn union(self, other: Self) -> Self {
+        let (x, y) = (self.x.min(other.x), self.y.min(other.y));
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        Self::new(x, y, right - x, bottom - y)
+    }
+
+    /// The same area on an image `factor` times the size, rounded outward so
+    /// no covered pixel is lost
+    pub(crate) fn scaled(self, factor: f32) -> Self {
+        let (x, y) = (
+            (self.x as f32 * factor).floor() as u32,
+            (self.y as f32 * factor).floor() as u32,
+        );
+        let right = ((self.right() as f32 * factor).ceil() as u32).max(x + 1);
+        let bottom = ((self.bottom() as f32 * factor).ceil() as u32).max(y + 1);
+        Self::new(x, y, right - x, bottom - y)
+    }
+}
+
+impl fmt::Display for Rect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} at ({}, {})",
+            self.width, self.height, self.x, self.y
+        )
+    }
+}
+
+/// Bounding box of the pixels with any alpha, `None` for a fully transparent
+/// image
+pub fn alpha_bounds(image: &RgbaImage) -> Option<Rect> {
+    let mut bounds: Option<Rect> = None;
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel[3] > 0 {
+            let pixel = Rect::new(x, y, 1, 1);
+            bounds = Some(bounds.map_or(pixel, |bounds| bounds.union(pixel)));
+        }
+    }
+    bounds
+}
+
+/// The box holding the content of every frame at every size, in pixels of
+/// the largest frame
+///
+/// Smaller sizes are scaled up, rounding outward. Fully transparent frames
+/// add nothing; `None` when every frame is. Cropping an animation to this
+/// with `AniFile::crop_to` keeps the frames the same size and in register,
+/// which cropping each frame to its own content would not.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::analysis::{Rect, content_bounds};
+/// use cursor_handler::{AniFile, AniFrame, FrameSize, Hotspot};
+/// use image::{Rgba, RgbaImage};
+///
+/// // 8x8 frames with a 2x2 glyph in a different place, and one left blank
+/// let mut ani = AniFile::empty();
+/// for corner in [Some((2, 1)), None, Some((4, 3))] {
+///     let mut image = RgbaImage::new(8, 8);
+///     if let Some((x, y)) = corner {
+///         for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+///             image.put_pixel(x + dx, y + dy, Rgba([0, 0, 0, 255]));
+///         }
+///     }
+///     ani.push_frame(AniFrame::from_image(&image, Hotspot::new(3, 2))?);
+/// }
+///
+/// assert_eq!(content_bounds(&ani)?, Some(Rect::new(2, 1, 4, 4)));
+/// assert_eq!(ani.autocrop()?, Some(Rect::new(2, 1, 4, 4)));
+/// assert!(ani.frames().all(|frame| frame.size == FrameSize::square(4).unwrap()));
+/// assert_eq!(ani.uniform_hotspot(), Some(Hotspot::new(1, 1)));
+///
+/// // Every size of a cursor counts: the 64px image alone spans 11..53, but
+/// // the 48px and 32px ones reach a little further once scaled up
+/// let bytes = std::fs::read("assets/aero_busy.ani")?;
+/// let cursor = AniFile::decode(Cursor::new(bytes))?.extract_frame(0)?;
+/// assert_eq!(content_bounds(&cursor)?, Some(Rect::new(10, 10, 44, 44)));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn content_bounds<'a>(file: impl Into<CursorRef<'a>>) -> io::Result<Option<Rect>> {
+    let cursors = match file.into() {
+        CursorRef::Cur(cursor) => vec![cursor.clone()],
+        CursorRef::Ani(ani) => ani
+            .frames()
+            .map(|frame| frame.to_cursor_file())
+            .collect::<io::Result<_>>()?,
+    };
+    let largest = largest_side(&cursors);
+
+    let mut bounds: Option<Rect> = None;
+    for frame in cursors.iter().flat_map(|cursor| cursor.frames()) {
+        let Some(content) = alpha_bounds(&frame.decode_image()?) else {
+            continue;
+        };
+        let content = content.scaled(largest as f32 / frame.size.max_side() as f32);
+        bounds = Some(bounds.map_or(content, |bounds| bounds.union(content)));
+    }
+    Ok(bounds)
+}
+
+/// Every size that `FrameSize::is_standard` turns down, across all frames and
+/// their embedded sizes, smallest first
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::{AniFile, analysis::nonstandard_sizes};
+///
+/// let bytes = std::fs::read("assets/aero_busy.ani")?;
+/// let mut ani = AniFile::decode(Cursor::new(bytes))?;
+/// assert!(nonstandard_sizes(&ani).is_empty());
+/// ani.crop_to(cursor_handler::analysis::Rect::new(0, 0, 40, 40))?;
+/// assert_eq!(nonstandard_sizes(&ani), [(20, 20), (30, 30), (40, 40)]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn nonstandard_sizes<'a>(file: impl Into<CursorRef<'a>>) -> Vec<(u32, u32)> {
+    let mut sizes: Vec<FrameSize> = match file.into() {
+        CursorRef::Cur(cursor) => cursor.frames().map(|frame| frame.size).collect(),
+        CursorRef::Ani(ani) => ani
+            .frames()
+            .flat_map(|frame| match frame.embedded_cursor() {
+                Some(view) => view.frames.iter().map(|view| view.size).collect(),
+                None => vec![frame.size],
+            })
+            .collect(),
+    };
+    sizes.retain(|size| !size.is_standard());
+    let mut sizes: Vec<_> = sizes
+        .iter()
+        .map(|size| (size.width(), size.height()))
+        .collect();
+    sizes.sort_unstable_by_key(|&(width, height)| (width.max(height), width, height));
+    sizes.dedup();
+    sizes
+}
+
+/// The longest side of any frame in `cursors`
+pub(crate) fn largest_side(cursors: &[CursorFile]) -> u32 {
+    cursors
+        .iter()
+        .flat_map(|cursor| cursor.frames())
+        .map(|frame| frame.size.max_side())
+        .max()
+        .unwrap_or(1)
+}
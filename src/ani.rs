@@ -1,61 +1,456 @@
 // lib.rs
 #![allow(dead_code)]
 use std::{
-    fmt::Display,
-    io::{self, Read, Seek, SeekFrom, Write},
+    fmt::{Debug, Display},
+    io::{self, Read, Seek, Write},
+    ops::{BitOr, Index, IndexMut},
+    slice,
 };
 
+use image::{ImageFormat, RgbaImage, imageops};
+
+use crate::{
+    concat::resized,
+    cur::{CursorFile, CursorFrame, container_len, payload_size, stored_size},
+    debug::{BytesSummary, FramesSummary},
+    decode::{
+        DecodeOptions, DecodeStats, Decoded, Diagnostics, FrameErrorPolicy, fourcc, invalid_chunk,
+        read_chunk, truncated_chunk,
+    },
+    embed::CursorView,
+    encode::{Discard, EncodeOptions, FileLayout, Section, record, write_chunk},
+    generate::cursor_frame,
+    hotspot::{Hotspot, HotspotPolicy},
+    riff::{Chunk, ChunkReader, ChunkWriter, FourCC},
+    size::FrameSize,
+    trace,
+    transform::{fit_hotspot, fit_square},
+};
+
+/// `AniHeader::flags` bit: frames are .cur/.ico containers rather than raw DIBs
+pub const AF_ICON: u32 = 0x1;
+/// `AniHeader::flags` bit: the file has a `seq ` chunk
+pub const AF_SEQUENCE: u32 = 0x2;
+
 /// A single frame in an animated cursor
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct AniFrame {
-    pub width: u32,
-    pub height: u32,
-    pub hotspot_x: u16,
-    pub hotspot_y: u16,
+    pub size: FrameSize,
+    pub hotspot: Hotspot,
     pub image_data: Vec<u8>,
     pub duration: Option<u32>, // Duration in 1/60th of a second (jiffies)
 }
 
 impl AniFrame {
     pub fn new(
-        width: u32,
-        height: u32,
-        hotspot_x: u16,
-        hotspot_y: u16,
+        size: FrameSize,
+        hotspot: impl Into<Hotspot>,
         image_data: Vec<u8>,
         duration: Option<u32>,
     ) -> Self {
         Self {
-            width,
-            height,
-            hotspot_x,
-            hotspot_y,
+            size,
+            hotspot: hotspot.into(),
             image_data,
             duration,
         }
     }
+
+    /// A frame holding `image` as a single-frame .cur with a PNG payload,
+    /// the form Windows expects inside an animation
+    pub fn from_image(image: &RgbaImage, hotspot: Hotspot) -> io::Result<Self> {
+        cursor_frame(image, hotspot)
+    }
+
+    /// Decode the embedded cursor data into an RGBA image
+    ///
+    /// When the embedded cursor holds several resolutions the largest is used.
+    pub fn decode_image(&self) -> io::Result<RgbaImage> {
+        // The ICO decoder misreads channel masks and RLE, which the frame's
+        // own decoding handles
+        if let Ok(cursor) = self.to_cursor_file()
+            && let Some(largest) = cursor
+                .frames()
+                .max_by_key(|frame| (frame.size.max_side(), frame.bit_count))
+        {
+            return largest.decode_image();
+        }
+
+        image::load_from_memory_with_format(&self.image_data, ImageFormat::Ico)
+            .map(|image| image.to_rgba8())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Payload format of the embedded cursor, e.g. `DIB-32 (3 sizes)`
+    pub fn payload_format(&self) -> String {
+        match CursorFile::decode(io::Cursor::new(&self.image_data)) {
+            Ok(cursor) if cursor.frames.len() > 1 => {
+                format!(
+                    "{} ({} sizes)",
+                    cursor.frames[0].payload_format(),
+                    cursor.frames.len()
+                )
+            }
+            Ok(cursor) => cursor.frames[0].payload_format(),
+            Err(_) if self.image_data.starts_with(&[0, 0, 1, 0]) => "ICO".to_string(),
+            Err(_) => "unknown".to_string(),
+        }
+    }
+
+    /// The embedded .cur, borrowing every payload from `image_data`
+    ///
+    /// `None` when the frame isn't a well-formed .cur (e.g. ICO data).
+    ///
+    /// ```
+    /// use cursor_handler::{AniFrame, Hotspot};
+    /// use image::{Rgba, RgbaImage};
+    ///
+    /// let image = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 255, 255]));
+    /// let frame = AniFrame::from_image(&image, Hotspot::new(1, 1))?;
+    ///
+    /// let embedded = frame.embedded_cursor().unwrap();
+    /// assert_eq!(embedded.frames[0].to_frame().decode_image()?, image);
+    /// let served = embedded.frames[0].png_data().unwrap();
+    /// // The slice points into the frame's own buffer, nothing was copied
+    /// let buffer = frame.image_data.as_ptr_range();
+    /// assert!(buffer.contains(&served.as_ptr()));
+    /// assert_eq!(served.as_ptr_range().end, buffer.end);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn embedded_cursor(&self) -> Option<CursorView<'_>> {
+        CursorView::parse(&self.image_data).ok()
+    }
+
+    /// Whether the frame embeds an icon (type 1) container, which holds no
+    /// hotspot, rather than a cursor
+    pub fn is_icon(&self) -> bool {
+        self.image_data.get(2..4) == Some(&[1, 0])
+    }
+
+    /// Whether the frame embeds a .cur or .ico container, as `AF_ICON`
+    /// says, rather than a raw image
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, AniFlags, AniFrame, FrameSize};
+    ///
+    /// let flags = |ani: &AniFile| -> std::io::Result<AniFlags> {
+    ///     let mut bytes = Cursor::new(Vec::new());
+    ///     ani.encode(&mut bytes)?;
+    ///     // The last anih field, after the RIFF header and eight other fields
+    ///     let at = 12 + 8 + 32;
+    ///     let word = bytes.get_ref()[at..at + 4].try_into().unwrap();
+    ///     Ok(AniFlags::from_bits_retain(u32::from_le_bytes(word)))
+    /// };
+    ///
+    /// let aero = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// assert!(aero.frames().all(AniFrame::is_container));
+    /// assert!(flags(&aero)?.contains(AniFlags::FRAMES_ARE_ICONS));
+    ///
+    /// // A bare BITMAPINFOHEADER is no container, so the flag is left off
+    /// let raw = AniFrame::new(FrameSize::square(32)?, (0, 0), vec![40, 0, 0, 0], None);
+    /// assert!(!raw.is_container());
+    /// assert!(!flags(&AniFile::new(vec![raw]))?.contains(AniFlags::FRAMES_ARE_ICONS));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn is_container(&self) -> bool {
+        matches!(self.image_data.get(..4), Some([0, 0, 1 | 2, 0]))
+    }
+
+    /// The hotspot the embedded cursor's first entry holds, which is the one
+    /// Windows uses; `None` for icons and raw images
+    pub fn payload_hotspot(&self) -> Option<Hotspot> {
+        if self.image_data.get(..4) != Some(&[0, 0, 2, 0]) {
+            return None;
+        }
+        let word = |at: usize| u16::from_le_bytes([self.image_data[at], self.image_data[at + 1]]);
+        (self.image_data.len() >= 22).then(|| Hotspot::new(word(10), word(12)))
+    }
+
+    /// Move the hotspot, rewriting the payload so Windows sees the change
+    ///
+    /// Every directory entry of the embedded container gets `hotspot`, scaled
+    /// from `size` to its own size; an icon container becomes a cursor. A
+    /// raw image is wrapped in a cursor holding the hotspot.
+    pub fn set_hotspot(&mut self, hotspot: Hotspot) -> io::Result<()> {
+        if !hotspot.is_within(self.size.width(), self.size.height()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("hotspot {hotspot} is outside the {} frame", self.size),
+            ));
+        }
+        match self.image_data.get(..4) {
+            Some([0, 0, 1 | 2, 0]) => self.patch_directory(hotspot)?,
+            _ if payload_size(&self.image_data).is_some() => {
+                let frame = CursorFrame::new(self.size, hotspot, self.image_data.clone());
+                let mut image_data = Vec::new();
+                CursorFile::single(frame).encode(&mut image_data)?;
+                self.image_data = image_data;
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "frame holds neither a cursor container nor an image",
+                ));
+            }
+        }
+        self.hotspot = hotspot;
+        Ok(())
+    }
+
+    fn patch_directory(&mut self, hotspot: Hotspot) -> io::Result<()> {
+        let data = &mut self.image_data;
+        let Some(&[low, high]) = data.get(4..6) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Truncated directory",
+            ));
+        };
+        let count = u16::from_le_bytes([low, high]) as usize;
+        if data.len() < 6 + count * 16 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Truncated directory",
+            ));
+        }
+        for index in 0..count {
+            let entry = 6 + index * 16;
+            let word = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+            let (len, offset) = (word(entry + 8) as usize, word(entry + 12) as usize);
+            let payload = data
+                .get(offset..offset.saturating_add(len))
+                .unwrap_or_default();
+            let dir_size = FrameSize::from_dir_bytes([data[entry], data[entry + 1]]);
+            let size = stored_size(dir_size, payload);
+            let (width, height) = (size.width(), size.height());
+            let factor = width as f32 / self.size.width() as f32;
+            let scaled = hotspot.scaled(factor).clamp_to(width, height);
+            data[entry + 4..entry + 6].copy_from_slice(&scaled.x.to_le_bytes());
+            data[entry + 6..entry + 8].copy_from_slice(&scaled.y.to_le_bytes());
+        }
+        data[2..4].copy_from_slice(&2u16.to_le_bytes());
+        Ok(())
+    }
+
+    /// The embedded container as a cursor; an icon's entries get this
+    /// frame's hotspot, scaled to their size
+    pub(crate) fn to_cursor_file(&self) -> io::Result<CursorFile> {
+        let options = DecodeOptions {
+            allow_ico: true,
+            ..DecodeOptions::default()
+        };
+        let data = io::Cursor::new(&self.image_data);
+        let mut cursor = CursorFile::decode_with(data, &options)?.value;
+        if cursor.is_icon() {
+            for frame in cursor.frames_mut() {
+                let (width, height) = (frame.size.width(), frame.size.height());
+                let factor = width as f32 / self.size.width() as f32;
+                frame.hotspot = self.hotspot.scaled(factor).clamp_to(width, height);
+            }
+            cursor = CursorFile::new(cursor.frames);
+        }
+        Ok(cursor)
+    }
+}
+
+/// `raw` as the one of `known` it spells with trailing NULs or spaces,
+/// recording the variant; other ids come back unchanged
+fn known_id(
+    diagnostics: &mut Diagnostics,
+    variants: &mut Vec<IdVariant>,
+    raw: FourCC,
+    known: &[FourCC],
+    offset: u64,
+) -> io::Result<FourCC> {
+    let trimmed = |id: &FourCC| {
+        let end = id
+            .iter()
+            .rposition(|&byte| byte != 0 && byte != b' ')
+            .map_or(0, |at| at + 1);
+        id[..end].to_vec()
+    };
+    if known.contains(&raw) {
+        return Ok(raw);
+    }
+    let Some(&id) = known.iter().find(|id| trimmed(id) == trimmed(&raw)) else {
+        return Ok(raw);
+    };
+    let message = format!(
+        "Chunk id '{}' read as '{}'",
+        raw.escape_ascii(),
+        fourcc(&id)
+    );
+    diagnostics.tolerate(&fourcc(&raw), offset, message)?;
+    if !variants.iter().any(|variant| variant.id == id) {
+        variants.push(IdVariant { id, raw });
+    }
+    Ok(id)
+}
+
+/// Little-endian bytes of a `seq ` or `rate` table
+fn words(values: &[u32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// Read a `seq ` or `rate` table, tolerating a trailing partial entry
+fn table_words(
+    diagnostics: &mut Diagnostics,
+    id: &str,
+    offset: u64,
+    data: &[u8],
+) -> io::Result<Vec<u32>> {
+    if !data.len().is_multiple_of(4) {
+        let message = format!("{} chunk size is not a multiple of 4", id.trim_end());
+        diagnostics.tolerate(id, offset, message)?;
+    }
+    Ok(data
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        .collect())
+}
+
+/// Convert jiffies (1/60 s) to whole milliseconds, rounding to nearest
+pub fn jiffies_to_ms(jiffies: u32) -> u64 {
+    (u64::from(jiffies) * 1000 + 30) / 60
+}
+
+/// Convert milliseconds to jiffies, rounding to nearest but at least 1
+pub fn ms_to_jiffies(ms: u32) -> u32 {
+    ((u64::from(ms) * 60 + 500) / 1000).max(1) as u32
+}
+
+/// Typed view of the `anih` flags word
+///
+/// Bits without a name are kept as they are, so a decoded header encodes back
+/// unchanged.
+///
+/// ```
+/// use cursor_handler::AniFlags;
+///
+/// let flags = AniFlags::FRAMES_ARE_ICONS | AniFlags::HAS_SEQUENCE;
+/// assert_eq!(flags.to_string(), "FRAMES_ARE_ICONS | HAS_SEQUENCE");
+/// assert_eq!(AniFlags::from_bits_retain(0x9).to_string(), "FRAMES_ARE_ICONS | 0x8");
+/// assert_eq!(AniFlags::empty().to_string(), "none");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AniFlags(u32);
+
+impl AniFlags {
+    /// Frames are .cur/.ico containers rather than raw DIBs
+    pub const FRAMES_ARE_ICONS: Self = Self(AF_ICON);
+    /// The file has a `seq ` chunk
+    pub const HAS_SEQUENCE: Self = Self(AF_SEQUENCE);
+
+    const NAMED: [(Self, &str); 2] = [
+        (Self::FRAMES_ARE_ICONS, "FRAMES_ARE_ICONS"),
+        (Self::HAS_SEQUENCE, "HAS_SEQUENCE"),
+    ];
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Wrap a raw flags word, keeping bits this crate doesn't know about
+    pub const fn from_bits_retain(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Bits set that have no named flag
+    pub const fn unknown_bits(self) -> u32 {
+        self.0 & !(AF_ICON | AF_SEQUENCE)
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+
+    /// Insert `other` if `value` is true, remove it otherwise
+    pub fn set(&mut self, other: Self, value: bool) {
+        if value {
+            self.insert(other);
+        } else {
+            self.remove(other);
+        }
+    }
+}
+
+impl BitOr for AniFlags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl Display for AniFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names = Self::NAMED
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| name.to_string())
+            .collect::<Vec<_>>();
+        if self.unknown_bits() != 0 {
+            names.push(format!("{:#x}", self.unknown_bits()));
+        }
+        if names.is_empty() {
+            return f.write_str("none");
+        }
+        f.write_str(&names.join(" | "))
+    }
 }
 
 /// Animation header information
+///
+/// Frame and step counts are not stored here: `encode` always writes
+/// `AniFile::num_frames` and `AniFile::num_steps`, so the header cannot
+/// disagree with the tables. What a decoded file declared is kept for
+/// diagnostics only, and can't be set:
+///
+/// ```compile_fail
+/// let mut ani = cursor_handler::AniFile::new(Vec::new());
+/// ani.header.num_frames = Some(5);
+/// ```
 #[derive(Debug, Clone)]
 pub struct AniHeader {
-    pub num_frames: u32,
-    pub num_steps: u32,
-    pub width: u32,
-    pub height: u32,
-    pub bit_count: u32,
-    pub planes: u32,
-    pub default_rate: u32,
-    pub flags: u32,
+    pub(crate) num_frames: Option<u32>, // As declared by a decoded file
+    pub(crate) num_steps: Option<u32>,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) bit_count: u32,
+    pub(crate) planes: u32,
+    pub(crate) default_rate: u32,
+    pub(crate) flags: u32,
+}
+
+impl Default for AniHeader {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AniHeader {
     const SIZE: usize = 36;
-    
+
     fn new() -> Self {
         Self {
-            num_frames: 0,
-            num_steps: 0,
+            num_frames: None,
+            num_steps: None,
             width: 0,
             height: 0,
             bit_count: 0,
@@ -64,313 +459,1687 @@ impl AniHeader {
             flags: 0,
         }
     }
+
+    /// Frame count in the `anih` chunk this header was decoded from
+    pub fn declared_frames(&self) -> Option<u32> {
+        self.num_frames
+    }
+
+    /// Step count in the `anih` chunk this header was decoded from
+    pub fn declared_steps(&self) -> Option<u32> {
+        self.num_steps
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn bit_count(&self) -> u32 {
+        self.bit_count
+    }
+
+    pub fn planes(&self) -> u32 {
+        self.planes
+    }
+
+    /// Duration in jiffies of steps without their own rate
+    pub fn default_rate(&self) -> u32 {
+        self.default_rate
+    }
+
+    /// Set the default step duration in jiffies
+    ///
+    /// Zero is rejected: Windows has no defined behaviour for a zero-length
+    /// step, and depending on the version the animation either stalls or
+    /// spins through its steps as fast as the cursor can be redrawn.
+    pub fn set_default_rate(&mut self, rate: u32) -> io::Result<()> {
+        if rate == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "default rate must be at least 1 jiffy",
+            ));
+        }
+        self.default_rate = rate;
+        Ok(())
+    }
+
+    /// Flags as stored; `encode` sets `FRAMES_ARE_ICONS` and `HAS_SEQUENCE`
+    /// from the frames and tables it writes
+    pub fn ani_flags(&self) -> AniFlags {
+        AniFlags::from_bits_retain(self.flags)
+    }
+
+    /// Replace the flags, e.g. to set bits this crate has no name for
+    pub fn set_ani_flags(&mut self, flags: AniFlags) {
+        self.flags = flags.bits();
+    }
 }
 
-/// An animated cursor file
+/// A tag from the `LIST INFO` chunk (e.g. `INAM` title, `IART` artist)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfoEntry {
+    pub id: [u8; 4],
+    pub value: Vec<u8>,
+}
+
+impl InfoEntry {
+    pub fn new(id: [u8; 4], value: impl Into<Vec<u8>>) -> Self {
+        Self {
+            id,
+            value: value.into(),
+        }
+    }
+
+    /// A tag holding `text` with the NUL terminator INFO strings end in
+    ///
+    /// ```
+    /// use cursor_handler::InfoEntry;
+    ///
+    /// let entry = InfoEntry::from_text(*b"IART", "Ayman");
+    /// assert_eq!((entry.value.as_slice(), entry.text()), (&b"Ayman\0"[..], "Ayman".into()));
+    /// ```
+    pub fn from_text(id: [u8; 4], text: &str) -> Self {
+        let mut value = text.as_bytes().to_vec();
+        value.push(0);
+        Self::new(id, value)
+    }
+
+    /// Value as text, with the trailing NUL terminator removed
+    pub fn text(&self) -> String {
+        let value = self.value.strip_suffix(&[0]).unwrap_or(&self.value);
+        String::from_utf8_lossy(value).into_owned()
+    }
+}
+
+/// A chunk the decoder does not use, kept so that re-encoding preserves it
+///
+/// Only chunks found between the icons of a `LIST fram` are kept (`JUNK`
+/// padding, vendor data); `encode` writes each one back in front of frame
+/// `before_frame`, or after the last frame if there are fewer frames.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::riff::ChunkWriter;
+/// use cursor_handler::{AniFile, AniFrame, Hotspot};
+/// use image::{Rgba, RgbaImage};
+///
+/// let image = RgbaImage::from_pixel(1, 1, Rgba([0, 0, 0, 255]));
+/// let cur = AniFrame::from_image(&image, Hotspot::default())?.image_data;
+///
+/// // A frame list with padding between its two icons
+/// let mut riff = ChunkWriter::new(Cursor::new(Vec::new()));
+/// riff.begin_list(*b"RIFF", *b"ACON")?;
+/// let anih = [36u32, 2, 2, 1, 1, 0, 0, 6, 1].map(u32::to_le_bytes).concat();
+/// riff.write_chunk(*b"anih", &anih)?;
+/// riff.begin_list(*b"LIST", *b"fram")?;
+/// riff.write_chunk(*b"icon", &cur)?;
+/// riff.write_chunk(*b"JUNK", &[0; 3])?;
+/// riff.write_chunk(*b"icon", &cur)?;
+/// let bytes = riff.finish()?.into_inner();
+///
+/// let ani = AniFile::decode(Cursor::new(&bytes))?;
+/// assert_eq!(ani.num_frames(), 2);
+/// assert_eq!(ani.unknown_chunks[0].id, *b"JUNK");
+/// assert_eq!(ani.unknown_chunks[0].before_frame, 1);
+///
+/// let mut out = Cursor::new(Vec::new());
+/// ani.encode(&mut out)?;
+/// let again = AniFile::decode(Cursor::new(out.into_inner()))?;
+/// assert_eq!(again.unknown_chunks, ani.unknown_chunks);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct UnknownChunk {
+    pub id: FourCC,
+    pub data: Vec<u8>,
+    pub before_frame: usize,
+}
+
+/// A chunk id or the form type as a file spelled it, when lenient decoding
+/// took it for a standard one
+///
+/// `encode` writes `raw` wherever it would write `id`, so the file
+/// round-trips; `sanitize` drops these (ANI014). Form types match
+/// case-insensitively, chunk ids and list types once trailing NULs and
+/// spaces are ignored. Strict decoding accepts only the exact ids.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::{AniFile, DecodeOptions};
+///
+/// for (name, raw, id) in [
+///     ("lowercase_form", *b"acon", *b"ACON"),
+///     ("seq_nul", *b"seq\0", *b"seq "),
+///     ("garbage_chunk", *b"acon", *b"ACON"),
+/// ] {
+///     let bytes = std::fs::read(format!("assets/sloppy/{name}.ani"))?;
+///     assert!(AniFile::decode_with(Cursor::new(&bytes), &DecodeOptions::strict()).is_err());
+///
+///     let decoded = AniFile::decode_with(Cursor::new(&bytes), &DecodeOptions::lenient())?;
+///     let mut ani = decoded.value;
+///     assert_eq!((ani.id_variants[0].raw, ani.id_variants[0].id), (raw, id));
+///     assert!(ani.validate().iter().any(|issue| issue.code == "ANI014"));
+///     assert!(!decoded.warnings.is_empty());
+///     assert_eq!((ani.num_frames(), &ani.sequence[..]), (2, &[0, 1, 0][..]));
+///
+///     let mut out = Cursor::new(Vec::new());
+///     ani.encode(&mut out)?;
+///     assert!(out.get_ref().windows(4).any(|id| id == raw));
+///
+///     ani.sanitize();
+///     let mut out = Cursor::new(Vec::new());
+///     ani.encode(&mut out)?;
+///     assert!(!out.get_ref().windows(4).any(|id| id == raw));
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdVariant {
+    pub id: FourCC,
+    pub raw: FourCC,
+}
+
+/// How many times an animation plays before stopping
+///
+/// ANI has no field for this, as Windows always loops cursors. It is kept
+/// for conversions: `to_apng` and `to_gif` write it, `from_apng` reads it,
+/// and `encode_with` can store it in a vendor `LOOP` chunk that other
+/// readers skip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum LoopBehavior {
+    #[default]
+    Infinite,
+    /// Play this many times, then hold the last step; 0 is treated as 1
+    Count(u16),
+    /// Play once, then hold the last step
+    PlayOnceHoldLast,
+}
+
+impl LoopBehavior {
+    /// Number of plays, or `None` when looping forever
+    pub fn plays(self) -> Option<u16> {
+        match self {
+            Self::Infinite => None,
+            Self::Count(count) => Some(count.max(1)),
+            Self::PlayOnceHoldLast => Some(1),
+        }
+    }
+
+    /// `LOOP` chunk data: a u16 kind (0 = count, 1 = play once) and a u16 count
+    fn to_chunk(self) -> Option<[u8; 4]> {
+        let (kind, count) = match self {
+            Self::Infinite => return None,
+            Self::Count(count) => (0u16, count),
+            Self::PlayOnceHoldLast => (1, 1),
+        };
+        let [k0, k1] = kind.to_le_bytes();
+        let [c0, c1] = count.to_le_bytes();
+        Some([k0, k1, c0, c1])
+    }
+
+    fn from_chunk(data: &[u8]) -> Option<Self> {
+        match data {
+            [0, 0, c0, c1] => Some(Self::Count(u16::from_le_bytes([*c0, *c1]))),
+            [1, 0, _, _] => Some(Self::PlayOnceHoldLast),
+            _ => None,
+        }
+    }
+}
+
+/// What `AniFile::from_frames` does with frames of different sizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MixedSizePolicy {
+    /// Refuse them
+    #[default]
+    Error,
+    /// Draw every frame on a transparent canvas with the hotspots lined up,
+    /// which is the largest frame's size unless the hotspots sit at
+    /// different spots (see `AniFile::align_frames`)
+    PadToLargest,
+    /// Scale every frame to the first one's size, hotspots along
+    ResizeToFirst,
+}
+
+/// One playback step, resolved through the sequence and rate tables
+#[derive(Debug, Clone, Copy)]
+pub struct AniStep<'a> {
+    pub frame_index: usize,
+    pub frame: &'a AniFrame,
+    pub duration: u32, // Duration in jiffies
+}
+
+/// One playback step rendered at a single size, see `AniFile::steps_at_size`
 #[derive(Debug, Clone)]
+pub struct SizedStep {
+    pub frame_index: usize,
+    pub image: RgbaImage,
+    pub hotspot: Hotspot,
+    pub duration: u32,   // Duration in jiffies
+    pub resampled: bool, // Scaled from the frame's closest size
+}
+
+/// What `AniFile::align_frames` did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AlignReport {
+    pub hotspot: Hotspot,        // Shared by every frame afterwards
+    pub shifts: Vec<(u16, u16)>, // Per frame, how far right and down it moved
+}
+
+/// An animated cursor file
+#[derive(Clone, Default)]
 pub struct AniFile {
     pub header: AniHeader,
-    pub frames: Vec<AniFrame>,
-    pub sequence: Vec<u32>, // Frame sequence indices
-    pub rates: Vec<u32>,    // Individual frame rates (optional)
+    pub(crate) frames: Vec<AniFrame>,
+    pub sequence: Vec<u32>,   // Frame sequence indices
+    pub rates: Vec<u32>,      // Individual frame rates (optional)
+    pub info: Vec<InfoEntry>, // LIST INFO metadata
+    pub unknown_chunks: Vec<UnknownChunk>,
+    pub loop_behavior: LoopBehavior,
+    pub id_variants: Vec<IdVariant>, // Nonstandard ids kept from a decoded file
 }
 
 impl AniFile {
+    /// An animation of `frames` as they are, one step each; `from_frames`
+    /// handles frames of different sizes
     pub fn new(frames: Vec<AniFrame>) -> Self {
-        let num_frames = frames.len() as u32;
-        let sequence: Vec<u32> = (0..num_frames).collect();
-        
+        let sequence: Vec<u32> = (0..frames.len() as u32).collect();
+
         let mut header = AniHeader::new();
-        header.num_frames = num_frames;
-        header.num_steps = num_frames;
-        
         if let Some(first_frame) = frames.first() {
-            header.width = first_frame.width;
-            header.height = first_frame.height;
+            header.width = first_frame.size.width();
+            header.height = first_frame.size.height();
             header.bit_count = 32; // Assume 32-bit
             header.planes = 1;
         }
-        
+
         Self {
             header,
             frames,
             sequence,
             rates: Vec::new(),
+            info: Vec::new(),
+            unknown_chunks: Vec::new(),
+            loop_behavior: LoopBehavior::Infinite,
+            id_variants: Vec::new(),
+        }
+    }
+
+    /// An animation of `frames`, one step each, making them one size first
+    ///
+    /// `new` takes frames of any size and describes the first in the
+    /// header, which many players then apply to every frame. Here mixed
+    /// sizes go through `policy`, re-encoding every frame as one cursor
+    /// image, and the header gets the size they share.
+    ///
+    /// ```
+    /// use cursor_handler::{AniFile, AniFrame, FrameSize, Hotspot, MixedSizePolicy};
+    /// use image::{Rgba, RgbaImage};
+    ///
+    /// let render = |side: u32, (x, y): (u16, u16)| {
+    ///     let image = RgbaImage::from_pixel(side, side, Rgba([0, 0, 0, 255]));
+    ///     AniFrame::from_image(&image, Hotspot::new(x, y))
+    /// };
+    /// let frames = || -> std::io::Result<_> {
+    ///     Ok(vec![render(32, (4, 4))?, render(48, (6, 6))?])
+    /// };
+    ///
+    /// assert!(AniFile::from_frames(frames()?, MixedSizePolicy::Error).is_err());
+    ///
+    /// // The 32px frame moves 2px right and down so the hotspots meet
+    /// let padded = AniFile::from_frames(frames()?, MixedSizePolicy::PadToLargest)?;
+    /// assert!(padded.frames().all(|frame| frame.size == FrameSize::square(48).unwrap()));
+    /// assert_eq!(padded.uniform_hotspot(), Some(Hotspot::new(6, 6)));
+    /// assert_eq!((padded.header.width(), padded.header.height()), (48, 48));
+    ///
+    /// // (6, 6) at 48px is pixel centre 6.5 * 2/3 = 4.33 at 32px
+    /// let resized = AniFile::from_frames(frames()?, MixedSizePolicy::ResizeToFirst)?;
+    /// assert!(resized.frames().all(|frame| frame.size == FrameSize::square(32).unwrap()));
+    /// assert_eq!(resized.uniform_hotspot(), Some(Hotspot::new(4, 4)));
+    /// assert_eq!((resized.header.width(), resized.header.height()), (32, 32));
+    ///
+    /// // Frames of one size are taken as they are
+    /// let same = vec![render(32, (4, 4))?, render(32, (1, 1))?];
+    /// assert!(AniFile::from_frames(same, MixedSizePolicy::Error).is_ok());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_frames(frames: Vec<AniFrame>, policy: MixedSizePolicy) -> io::Result<Self> {
+        let mut ani = Self::new(frames);
+        let Some(first) = ani.frames.first().map(|frame| frame.size) else {
+            return Ok(ani);
+        };
+        if let Some(other) = ani.frames().find(|frame| frame.size != first) {
+            match policy {
+                MixedSizePolicy::Error => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("frames are both {first} and {}", other.size),
+                    ));
+                }
+                MixedSizePolicy::PadToLargest => {
+                    ani.align_frames()?;
+                }
+                MixedSizePolicy::ResizeToFirst => {
+                    for frame in ani.frames.iter_mut().filter(|frame| frame.size != first) {
+                        *frame = resized(frame, first)?;
+                    }
+                }
+            }
         }
+        Ok(ani)
+    }
+
+    /// An animation with no frames yet, to be filled with `push_frame`
+    pub fn empty() -> Self {
+        Self::new(Vec::new())
+    }
+
+    /// Append a frame, playing it as a new step after the current ones
+    ///
+    /// The header size is taken from the first frame. A step is only added
+    /// while the sequence is the default one; a custom sequence is left for
+    /// the caller to extend. When a rate table is present the new step gets
+    /// the frame's own duration, or the table's rate if every step shares
+    /// one, or the default rate.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, AniFrame, Hotspot};
+    /// use image::{DynamicImage, Rgba, RgbaImage};
+    ///
+    /// let base = DynamicImage::from(RgbaImage::from_pixel(32, 32, Rgba([255, 0, 0, 255])));
+    ///
+    /// let mut ani = AniFile::empty().with_default_rate(4)?;
+    /// for step in 0..12 {
+    ///     let image = base.huerotate(step * 30).to_rgba8();
+    ///     ani.push_frame(AniFrame::from_image(&image, Hotspot::default())?);
+    /// }
+    ///
+    /// assert_eq!((ani.num_frames(), ani.num_steps()), (12, 12));
+    /// let mut out = Cursor::new(Vec::new());
+    /// ani.encode(&mut out)?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn push_frame(&mut self, frame: AniFrame) {
+        let index = self.frames.len() as u32;
+        if index == 0 {
+            self.header.width = frame.size.width();
+            self.header.height = frame.size.height();
+            self.header.bit_count = 32; // Assume 32-bit
+            self.header.planes = 1;
+        }
+
+        let default_sequence = self.sequence.iter().copied().eq(0..index);
+        if default_sequence {
+            if !self.rates.is_empty() {
+                let uniform = self.rates.windows(2).all(|pair| pair[0] == pair[1]);
+                let rate = match frame.duration {
+                    Some(duration) => duration,
+                    None if uniform => self.rates[0],
+                    None => self.header.default_rate,
+                };
+                self.rates.push(rate);
+            }
+            self.sequence.push(index);
+        }
+
+        self.frames.push(frame);
+        // Anything declared by a decoded file no longer applies
+        self.header.num_frames = None;
+        self.header.num_steps = None;
+    }
+
+    /// Stored frames in file order, see `steps` for playback order
+    pub fn frames(&self) -> slice::Iter<'_, AniFrame> {
+        self.frames.iter()
+    }
+
+    pub fn frames_mut(&mut self) -> slice::IterMut<'_, AniFrame> {
+        self.frames.iter_mut()
     }
 
     pub fn with_sequence(mut self, sequence: Vec<u32>) -> Self {
-        self.header.num_steps = sequence.len() as u32;
         self.sequence = sequence;
         self
     }
 
+    /// A one-frame, one-step animation showing `cursor`, for roles a theme
+    /// must fill with an .ani
+    ///
+    /// The icon chunk holds `cursor` encoded as is, every resolution included,
+    /// so `extract_frame(0)` gives it back. The header size comes from the
+    /// largest resolution and `rate` is the step duration in jiffies.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::test_utils::{GlyphStyle, synthetic_cur};
+    /// use cursor_handler::{AniFile, CursorFile};
+    ///
+    /// let cursor = synthetic_cur(&[32, 48], GlyphStyle::Arrow)?;
+    ///
+    /// let ani = AniFile::from_cursor_static(&cursor, 60)?;
+    /// assert_eq!(ani.header.width(), 48);
+    ///
+    /// let mut out = Cursor::new(Vec::new());
+    /// ani.encode(&mut out)?;
+    /// let decoded = AniFile::decode(Cursor::new(out.into_inner()))?;
+    ///
+    /// let (mut original, mut extracted) = (Vec::new(), Vec::new());
+    /// cursor.encode(&mut original)?;
+    /// decoded.extract_frame(0)?.encode(&mut extracted)?;
+    /// assert_eq!(extracted, original);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_cursor_static(cursor: &CursorFile, rate: u32) -> io::Result<Self> {
+        let (Some(first), Some(largest)) = (
+            cursor.frames.first(),
+            cursor
+                .frames
+                .iter()
+                .max_by_key(|frame| frame.size.max_side()),
+        ) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No frames"));
+        };
+
+        let mut image_data = Vec::new();
+        cursor.encode(&mut image_data)?;
+        // Decoding reads the size and hotspot from the first directory entry
+        let frame = AniFrame::new(first.size, first.hotspot, image_data, None);
+
+        let mut ani = Self::new(vec![frame]).with_default_rate(rate)?;
+        ani.header.width = largest.size.width();
+        ani.header.height = largest.size.height();
+        ani.header.set_ani_flags(AniFlags::FRAMES_ARE_ICONS);
+        Ok(ani)
+    }
+
+    /// Set the default step duration, see `AniHeader::set_default_rate`
+    pub fn with_default_rate(mut self, rate: u32) -> io::Result<Self> {
+        self.header.set_default_rate(rate)?;
+        Ok(self)
+    }
+
+    /// Number of stored frames, as `encode` writes it to the header
+    pub fn num_frames(&self) -> u32 {
+        self.frames.len() as u32
+    }
+
+    /// Number of playback steps, as `encode` writes it to the header
+    pub fn num_steps(&self) -> u32 {
+        self.sequence.len() as u32
+    }
+
     pub fn with_rates(mut self, rates: Vec<u32>) -> Self {
         self.rates = rates;
         self
     }
 
+    /// Iterate over the playback steps in order
+    ///
+    /// A step's duration comes from the rate table, then the frame's own
+    /// duration, then the header's default rate. Sequence entries pointing
+    /// past the end of `frames` are skipped.
+    pub fn steps(&self) -> impl Iterator<Item = AniStep<'_>> {
+        self.sequence
+            .iter()
+            .enumerate()
+            .filter_map(move |(step, &index)| {
+                let frame = self.frames.get(index as usize)?;
+                let duration = self
+                    .rates
+                    .get(step)
+                    .copied()
+                    .or(frame.duration)
+                    .unwrap_or(self.header.default_rate);
+
+                Some(AniStep {
+                    frame_index: index as usize,
+                    frame,
+                    duration,
+                })
+            })
+    }
+
+    /// Every playback step as a `size` x `size` image
+    ///
+    /// Each frame's image is the one `CursorFile::best_frame` picks from its
+    /// embedded cursor. Where that isn't exactly `size` square it is scaled
+    /// and centred to fit, hotspot included, so all steps match. Frames are
+    /// decoded once however many steps show them.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, AniFrame};
+    ///
+    /// // Frame 0 at 32 and 48px, frame 1 at 32px only
+    /// let source = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// let mut ani = AniFile::empty();
+    /// for (index, sizes) in [(0, &[32, 48][..]), (1, &[32][..])] {
+    ///     let mut cursor = source.extract_frame(index)?;
+    ///     cursor.retain_sizes(sizes);
+    ///     let mut cur = Vec::new();
+    ///     cursor.encode(&mut cur)?;
+    ///     ani.push_frame(AniFrame::new(cursor[0].size, cursor[0].hotspot, cur, None));
+    /// }
+    ///
+    /// let exact = ani.steps_at_size(32)?;
+    /// assert!(exact.iter().all(|step| step.image.dimensions() == (32, 32) && !step.resampled));
+    ///
+    /// let mixed = ani.steps_at_size(48)?;
+    /// assert!(mixed.iter().all(|step| step.image.dimensions() == (48, 48)));
+    /// assert_eq!(mixed.iter().map(|step| step.resampled).collect::<Vec<_>>(), [false, true]);
+    /// assert_eq!(mixed[1].hotspot, exact[1].hotspot.scaled(1.5));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn steps_at_size(&self, size: u32) -> io::Result<Vec<SizedStep>> {
+        if size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Size must be non-zero",
+            ));
+        }
+
+        let mut rendered: Vec<Option<(RgbaImage, Hotspot, bool)>> = vec![None; self.frames.len()];
+        self.steps()
+            .map(|step| {
+                let slot = &mut rendered[step.frame_index];
+                if slot.is_none() {
+                    let cursor = self.extract_frame(step.frame_index)?;
+                    let frame = cursor
+                        .best_frame(size)
+                        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Empty frame"))?;
+                    let image = frame.decode_image()?;
+                    *slot = Some(if image.dimensions() == (size, size) {
+                        (image, frame.hotspot, false)
+                    } else {
+                        let hotspot = fit_hotspot(frame.hotspot, image.dimensions(), size);
+                        (fit_square(&image, size), hotspot, true)
+                    });
+                }
+                let (image, hotspot, resampled) = slot.clone().expect("rendered above");
+                Ok(SizedStep {
+                    frame_index: step.frame_index,
+                    image,
+                    hotspot,
+                    duration: step.duration,
+                    resampled,
+                })
+            })
+            .collect()
+    }
+
+    /// Frame `index` as a standalone cursor, e.g. the static fallback shown
+    /// when animations are turned off
+    ///
+    /// An embedded .cur is returned as decoded, so its payloads stay byte for
+    /// byte the same; an embedded .ico gets the frame's hotspot. Other data is
+    /// wrapped as the single image of a new cursor with the frame's hotspot.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::AniFile;
+    ///
+    /// let ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// let cursor = ani.extract_step(0)?;
+    ///
+    /// let mut cur = Vec::new();
+    /// cursor.encode(&mut cur)?;
+    /// assert_eq!(cur, ani[0].image_data);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn extract_frame(&self, index: usize) -> io::Result<CursorFile> {
+        let frame = self.frames.get(index).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "frame {index} is out of range, the animation has {}",
+                    self.frames.len()
+                ),
+            )
+        })?;
+
+        match frame.to_cursor_file() {
+            Ok(cursor) => Ok(cursor),
+            Err(_) => Ok(CursorFile::single(CursorFrame::new(
+                frame.size,
+                frame.hotspot,
+                frame.image_data.clone(),
+            ))),
+        }
+    }
+
+    /// Rewrite frames that embed icons as cursors carrying the frame's
+    /// hotspot, returning how many were rewritten
+    ///
+    /// Icon frames decode with a hotspot from `DecodeOptions::icon_hotspot`;
+    /// after this, encoding writes it into the file.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, DecodeOptions, Hotspot, HotspotPolicy};
+    ///
+    /// // Two frames, each the 32x32 Link.cur saved as an icon
+    /// let bytes = std::fs::read("assets/ico_frames.ani")?;
+    /// let lenient = DecodeOptions::lenient();
+    /// let options = DecodeOptions { icon_hotspot: HotspotPolicy::Center, ..lenient.clone() };
+    /// let decoded = AniFile::decode_with(Cursor::new(&bytes), &options)?;
+    /// assert_eq!(decoded.warnings.len(), 2);
+    /// let mut ani = decoded.value;
+    /// assert!(ani.frames().all(|frame| frame.is_icon()));
+    /// assert_eq!(ani.uniform_hotspot(), Some(Hotspot::new(16, 16)));
+    /// let origin = AniFile::decode(Cursor::new(&bytes))?.uniform_hotspot();
+    /// assert_eq!(origin, Some(Hotspot::new(0, 0)));
+    ///
+    /// assert_eq!(ani.fix_embedded_type()?, 2);
+    /// assert!(ani.frames().all(|frame| !frame.is_icon()));
+    /// let mut fixed = Cursor::new(Vec::new());
+    /// ani.encode(&mut fixed)?;
+    /// let decoded = AniFile::decode_with(Cursor::new(fixed.get_ref()), &lenient)?;
+    /// assert!(decoded.warnings.is_empty());
+    /// assert_eq!(decoded.value.uniform_hotspot(), Some(Hotspot::new(16, 16)));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn fix_embedded_type(&mut self) -> io::Result<usize> {
+        let mut fixed = 0;
+        for frame in self.frames.iter_mut().filter(|frame| frame.is_icon()) {
+            let mut image_data = Vec::new();
+            frame.to_cursor_file()?.encode(&mut image_data)?;
+            frame.image_data = image_data;
+            fixed += 1;
+        }
+        Ok(fixed)
+    }
+
+    /// The frame shown at playback step `step`, see `extract_frame`
+    pub fn extract_step(&self, step: usize) -> io::Result<CursorFile> {
+        let index = self.sequence.get(step).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "step {step} is out of range, the animation has {}",
+                    self.sequence.len()
+                ),
+            )
+        })?;
+        self.extract_frame(*index as usize)
+    }
+
+    /// Every stored frame as a standalone cursor, in file order
+    pub fn extract_all(&self) -> io::Result<Vec<CursorFile>> {
+        (0..self.frames.len())
+            .map(|index| self.extract_frame(index))
+            .collect()
+    }
+
+    /// The hotspot of each playback step, following `steps`
+    ///
+    /// Frames may each carry their own hotspot, e.g. in a cursor that morphs
+    /// from an arrow into a hand.
+    pub fn step_hotspots(&self) -> impl Iterator<Item = Hotspot> + '_ {
+        self.steps().map(|step| step.frame.hotspot)
+    }
+
+    /// The hotspot shared by every step, or `None` if they differ or no
+    /// step plays
+    pub fn uniform_hotspot(&self) -> Option<Hotspot> {
+        let mut hotspots = self.step_hotspots();
+        let first = hotspots.next()?;
+        hotspots.all(|hotspot| hotspot == first).then_some(first)
+    }
+
+    /// Move frame `index`'s hotspot, see `AniFrame::set_hotspot`
+    pub fn set_hotspot(&mut self, index: usize, hotspot: Hotspot) -> io::Result<()> {
+        let Some(frame) = self.frames.get_mut(index) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no frame {index}"),
+            ));
+        };
+        frame.set_hotspot(hotspot)
+    }
+
+    /// Give every frame the same hotspot, rewriting each payload, see
+    /// `AniFrame::set_hotspot`; no frame changes if it lies outside any of
+    /// them or a payload can't be rewritten
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, AniFrame, DecodeOptions, FrameSize, Hotspot};
+    ///
+    /// let mut ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// assert!(ani.set_hotspot_all(Hotspot::new(64, 0)).is_err());
+    /// ani.set_hotspot_all(Hotspot::new(40, 20))?;
+    ///
+    /// let mut bytes = Cursor::new(Vec::new());
+    /// ani.encode(&mut bytes)?;
+    /// let decoded = AniFile::decode(Cursor::new(bytes.into_inner()))?;
+    /// for (index, frame) in decoded.frames().enumerate() {
+    ///     assert_eq!(frame.payload_hotspot(), Some(Hotspot::new(40, 20)));
+    ///     // Smaller entries get the hotspot scaled to their size
+    ///     let cursor = decoded.extract_frame(index)?;
+    ///     let hotspots: Vec<_> = cursor.frames().map(|frame| frame.hotspot).collect();
+    ///     let expected = [(40, 20), (30, 15), (20, 10)].map(|(x, y)| Hotspot::new(x, y));
+    ///     assert_eq!(hotspots, expected);
+    /// }
+    /// // Moving only the field leaves the payload behind
+    /// let mut stale = decoded.clone();
+    /// stale[0].hotspot = Hotspot::new(1, 1);
+    /// assert!(stale.validate().iter().any(|issue| issue.code == "ANI015"));
+    ///
+    /// // Icon frames become cursors holding the hotspot
+    /// let bytes = std::fs::read("assets/ico_frames.ani")?;
+    /// let mut ani = AniFile::decode_with(Cursor::new(bytes), &DecodeOptions::default())?.value;
+    /// ani.set_hotspot(1, Hotspot::new(3, 4))?;
+    /// assert!(ani[0].is_icon() && !ani[1].is_icon());
+    /// assert_eq!(ani[1].payload_hotspot(), Some(Hotspot::new(3, 4)));
+    /// assert_eq!(ani.extract_frame(1)?[0].hotspot, Hotspot::new(3, 4));
+    ///
+    /// // A frame whose directory is cut short fails, leaving the others alone
+    /// let truncated = AniFrame::new(FrameSize::square(32)?, (0, 0), vec![0, 0, 2, 0], None);
+    /// let before = ani.clone();
+    /// ani.push_frame(truncated);
+    /// assert!(ani[2].set_hotspot(Hotspot::new(1, 1)).is_err());
+    /// assert!(ani.set_hotspot_all(Hotspot::new(1, 1)).is_err());
+    /// let payloads = |ani: &AniFile| -> Vec<Vec<u8>> {
+    ///     ani.frames().take(2).map(|frame| frame.image_data.clone()).collect()
+    /// };
+    /// assert_eq!(payloads(&ani), payloads(&before));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn set_hotspot_all(&mut self, hotspot: Hotspot) -> io::Result<()> {
+        if let Some((index, frame)) = self
+            .frames
+            .iter()
+            .enumerate()
+            .find(|(_, frame)| !hotspot.is_within(frame.size.width(), frame.size.height()))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "hotspot {hotspot} is outside frame {index} ({})",
+                    frame.size
+                ),
+            ));
+        }
+        let mut frames = self.frames.clone();
+        for frame in &mut frames {
+            frame.set_hotspot(hotspot)?;
+        }
+        self.frames = frames;
+        Ok(())
+    }
+
+    /// Move every frame so that its hotspot lands on `target`
+    ///
+    /// Each frame is drawn at `target` minus its hotspot on a canvas that grows
+    /// right and down to fit them all, and re-encoded as a single PNG-payload
+    /// cursor (frames holding several sizes keep only the largest). Nothing is
+    /// cut off, so `target` must be at least every hotspot on both axes; the
+    /// per-axis maximum of the hotspots is the smallest canvas.
+    ///
+    /// ```
+    /// use cursor_handler::{AniFile, AniFrame, FrameSize, Hotspot};
+    /// use image::{Rgba, RgbaImage};
+    ///
+    /// let image = RgbaImage::from_pixel(2, 2, Rgba([0, 0, 0, 255]));
+    /// let mut ani = AniFile::empty();
+    /// for hotspot in [Hotspot::new(0, 0), Hotspot::new(1, 1)] {
+    ///     ani.push_frame(AniFrame::from_image(&image, hotspot)?);
+    /// }
+    /// assert_eq!(ani.uniform_hotspot(), None);
+    ///
+    /// ani.normalize_hotspots(Hotspot::new(1, 1))?;
+    /// assert_eq!(ani.uniform_hotspot(), Some(Hotspot::new(1, 1)));
+    /// assert_eq!(ani[0].size, FrameSize::square(3)?);
+    /// assert!(ani.normalize_hotspots(Hotspot::new(0, 1)).is_err());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn normalize_hotspots(&mut self, target: Hotspot) -> io::Result<()> {
+        let images = self.hotspot_images()?;
+        self.place_on(images, target).map(|_| ())
+    }
+
+    /// Line every frame up on its hotspot, so a glyph drawn at a different
+    /// spot of each frame's canvas stops jittering
+    ///
+    /// This is `normalize_hotspots` with the smallest target that works, the
+    /// per-axis maximum of the hotspots. The report gives that hotspot and
+    /// how far right and down each frame was moved.
+    ///
+    /// ```
+    /// use cursor_handler::{AniFile, AniFrame, FrameSize, Hotspot};
+    /// use image::{Rgba, RgbaImage};
+    ///
+    /// // A 2x2 glyph at a different spot of each 6x6 frame, hotspot on its corner
+    /// let corners = [(1, 1), (3, 2), (2, 0)];
+    /// let mut ani = AniFile::empty();
+    /// for (x, y) in corners {
+    ///     let mut image = RgbaImage::new(6, 6);
+    ///     for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+    ///         image.put_pixel(x + dx, y + dy, Rgba([0, 0, 0, 255]));
+    ///     }
+    ///     ani.push_frame(AniFrame::from_image(&image, Hotspot::new(x as u16, y as u16))?);
+    /// }
+    ///
+    /// // Sum of squared distances of the glyph centroids from their mean
+    /// let spread = |ani: &AniFile| -> std::io::Result<f32> {
+    ///     let mut centroids = Vec::new();
+    ///     for frame in ani.frames() {
+    ///         let image = frame.decode_image()?;
+    ///         let glyph: Vec<_> = image.enumerate_pixels().filter(|(.., p)| p[3] > 0).collect();
+    ///         let n = glyph.len() as f32;
+    ///         let x = glyph.iter().map(|(x, ..)| *x as f32).sum::<f32>() / n;
+    ///         let y = glyph.iter().map(|(_, y, _)| *y as f32).sum::<f32>() / n;
+    ///         centroids.push((x, y));
+    ///     }
+    ///     let n = centroids.len() as f32;
+    ///     let mx = centroids.iter().map(|c| c.0).sum::<f32>() / n;
+    ///     let my = centroids.iter().map(|c| c.1).sum::<f32>() / n;
+    ///     Ok(centroids.iter().map(|c| (c.0 - mx).powi(2) + (c.1 - my).powi(2)).sum())
+    /// };
+    /// let before = spread(&ani)?;
+    ///
+    /// let report = ani.align_frames()?;
+    /// assert_eq!(report.hotspot, Hotspot::new(3, 2));
+    /// assert_eq!(report.shifts, [(2, 1), (0, 0), (1, 2)]);
+    /// assert_eq!(ani.uniform_hotspot(), Some(Hotspot::new(3, 2)));
+    /// assert_eq!(ani[0].size, FrameSize::square(8)?);
+    /// assert!(before > 0.0 && spread(&ani)? < before);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn align_frames(&mut self) -> io::Result<AlignReport> {
+        let images = self.hotspot_images()?;
+        let mut target = Hotspot::default();
+        for (_, hotspot) in &images {
+            target = Hotspot::new(target.x.max(hotspot.x), target.y.max(hotspot.y));
+        }
+        let shifts = self.place_on(images, target)?;
+        Ok(AlignReport {
+            hotspot: target,
+            shifts,
+        })
+    }
+
+    /// Each frame's largest image with the hotspot scaled onto it
+    fn hotspot_images(&self) -> io::Result<Vec<(RgbaImage, Hotspot)>> {
+        let mut images = Vec::with_capacity(self.frames.len());
+        for frame in &self.frames {
+            let image = frame.decode_image()?;
+            // The hotspot belongs to the first embedded size, which may not be the largest
+            let hotspot = frame
+                .hotspot
+                .scaled(image.width() as f32 / frame.size.width() as f32);
+            images.push((image, hotspot));
+        }
+        Ok(images)
+    }
+
+    /// Redraw every frame with its hotspot at `target`, returning how far
+    /// each one moved
+    fn place_on(
+        &mut self,
+        images: Vec<(RgbaImage, Hotspot)>,
+        target: Hotspot,
+    ) -> io::Result<Vec<(u16, u16)>> {
+        let mut placed = Vec::with_capacity(self.frames.len());
+        for (index, (image, hotspot)) in images.into_iter().enumerate() {
+            if hotspot.x > target.x || hotspot.y > target.y {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("frame {index} hotspot {hotspot} is past the target {target}"),
+                ));
+            }
+            let offset = (target.x - hotspot.x, target.y - hotspot.y);
+            placed.push((image, offset));
+        }
+
+        let width = placed
+            .iter()
+            .map(|(image, (x, _))| *x as u32 + image.width())
+            .max();
+        let height = placed
+            .iter()
+            .map(|(image, (_, y))| *y as u32 + image.height())
+            .max();
+        let (Some(width), Some(height)) = (width, height) else {
+            return Ok(Vec::new());
+        };
+
+        let shifts = placed.iter().map(|(_, offset)| *offset).collect();
+        for (frame, (image, (x, y))) in self.frames.iter_mut().zip(placed) {
+            let mut canvas = RgbaImage::new(width, height);
+            imageops::replace(&mut canvas, &image, x.into(), y.into());
+            let duration = frame.duration;
+            *frame = cursor_frame(&canvas, target)?;
+            frame.duration = duration;
+        }
+        self.header.width = width;
+        self.header.height = height;
+        Ok(shifts)
+    }
+
+    /// Look up an INFO tag by its id
+    pub fn info_entry(&self, id: &[u8; 4]) -> Option<&InfoEntry> {
+        self.info.iter().find(|entry| &entry.id == id)
+    }
+
+    /// Title from the `INAM` INFO tag
+    pub fn title(&self) -> Option<String> {
+        self.info_entry(b"INAM").map(InfoEntry::text)
+    }
+
+    /// Artist from the `IART` INFO tag
+    pub fn artist(&self) -> Option<String> {
+        self.info_entry(b"IART").map(InfoEntry::text)
+    }
+
     /// Encode ANI file to writer
-    pub fn encode<W: Write + Seek>(&self, mut writer: W) -> io::Result<()> {
+    pub fn encode<W: Write + Seek>(&self, writer: W) -> io::Result<()> {
+        self.encode_with(writer, &EncodeOptions::default())
+    }
+
+    /// Encode ANI file to writer, applying `options`
+    pub fn encode_with<W: Write + Seek>(
+        &self,
+        writer: W,
+        options: &EncodeOptions,
+    ) -> io::Result<()> {
+        self.encode_into(writer, options, None)
+    }
+
+    /// Where `encode_with` would put each chunk, without writing anything
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, EncodeOptions, Section};
+    ///
+    /// let bytes = std::fs::read("assets/aero_busy.ani")?;
+    /// let ani = AniFile::decode(Cursor::new(bytes))?;
+    /// let layout = ani.layout(&EncodeOptions::default())?;
+    /// assert_eq!(layout.entries[..3], [
+    ///     (Section::Header, 0, 12),
+    ///     (Section::Chunk(*b"anih"), 12, 44),
+    ///     (Section::List(*b"fram"), 56, 12),
+    /// ]);
+    ///
+    /// let mut encoded = Cursor::new(Vec::new());
+    /// ani.encode(&mut encoded)?;
+    /// let encoded = encoded.into_inner();
+    /// assert_eq!(layout.total, encoded.len() as u64);
+    /// let (offset, len) = layout.find(Section::Frame(17)).unwrap();
+    /// let icon = &encoded[offset as usize..][..len as usize];
+    /// assert_eq!((&icon[..4], &icon[8..]), (&b"icon"[..], &ani[17].image_data[..]));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn layout(&self, options: &EncodeOptions) -> io::Result<FileLayout> {
+        let mut layout = FileLayout::default();
+        self.encode_into(Discard::default(), options, Some(&mut layout))?;
+        Ok(layout)
+    }
+
+    fn encode_into<W: Write + Seek>(
+        &self,
+        mut writer: W,
+        options: &EncodeOptions,
+        mut layout: Option<&mut FileLayout>,
+    ) -> io::Result<()> {
+        if let Some(profile) = &options.profile {
+            let options = EncodeOptions {
+                profile: None,
+                ..options.clone()
+            };
+            return self.conform(profile)?.encode_into(writer, &options, layout);
+        }
+        let _span = trace::span!(DEBUG, "encode_ani");
         if self.frames.is_empty() {
             return Err(io::Error::new(io::ErrorKind::InvalidInput, "No frames"));
         }
+        trace::debug!(
+            frames = self.frames.len(),
+            steps = self.sequence.len(),
+            "encoding ANI"
+        );
+        if let Some((step, index)) = self
+            .sequence
+            .iter()
+            .enumerate()
+            .find(|&(_, &index)| index as usize >= self.frames.len())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "step {step} plays frame {index}, the animation has {}",
+                    self.frames.len()
+                ),
+            ));
+        }
 
-        // Write RIFF header
-        writer.write_all(b"RIFF")?;
-        let file_size_pos = writer.stream_position()?;
-        writer.write_all(&[0u8; 4])?; // Placeholder for file size
-        writer.write_all(b"ACON")?;
+        // Counts and flags follow the tables actually written
+        let num_frames = self.num_frames();
+        let has_sequence = self.sequence != (0..num_frames).collect::<Vec<_>>();
+        let frames_are_icons = self.frames.iter().all(AniFrame::is_container);
+        let mut flags = self.header.ani_flags();
+        flags.set(AniFlags::FRAMES_ARE_ICONS, frames_are_icons);
+        flags.set(AniFlags::HAS_SEQUENCE, has_sequence);
 
-        let start_pos = writer.stream_position()?;
+        // Ids a decoded file spelled its own way are written back as read
+        let id = |id: FourCC| {
+            let variant = self.id_variants.iter().find(|variant| variant.id == id);
+            variant.map_or(id, |variant| variant.raw)
+        };
+        let mut riff = ChunkWriter::new(&mut writer);
+        riff.begin_list(*b"RIFF", id(*b"ACON"))?;
+        record(&mut layout, |layout| layout.push(Section::Header, 12));
 
         // Write animation header
-        writer.write_all(b"anih")?;
-        writer.write_all(&(AniHeader::SIZE as u32).to_le_bytes())?;
-        writer.write_all(&(AniHeader::SIZE as u32).to_le_bytes())?; // Structure size
-        writer.write_all(&self.header.num_frames.to_le_bytes())?;
-        writer.write_all(&self.header.num_steps.to_le_bytes())?;
-        writer.write_all(&self.header.width.to_le_bytes())?;
-        writer.write_all(&self.header.height.to_le_bytes())?;
-        writer.write_all(&self.header.bit_count.to_le_bytes())?;
-        writer.write_all(&self.header.planes.to_le_bytes())?;
-        writer.write_all(&self.header.default_rate.to_le_bytes())?;
-        writer.write_all(&self.header.flags.to_le_bytes())?;
+        let mut anih = Vec::with_capacity(AniHeader::SIZE);
+        for field in [
+            AniHeader::SIZE as u32, // Structure size
+            num_frames,
+            self.num_steps(),
+            self.header.width,
+            self.header.height,
+            self.header.bit_count,
+            self.header.planes,
+            self.header.default_rate,
+            flags.bits(),
+        ] {
+            anih.extend_from_slice(&field.to_le_bytes());
+        }
+        write_chunk(&mut riff, &mut layout, id(*b"anih"), &anih)?;
 
         // Write sequence if different from default
-        if self.sequence != (0..self.header.num_frames).collect::<Vec<_>>() {
-            writer.write_all(b"seq ")?;
-            let seq_size = (self.sequence.len() * 4) as u32;
-            writer.write_all(&seq_size.to_le_bytes())?;
-            for &index in &self.sequence {
-                writer.write_all(&index.to_le_bytes())?;
-            }
+        if has_sequence {
+            write_chunk(&mut riff, &mut layout, id(*b"seq "), &words(&self.sequence))?;
         }
 
         // Write rates if provided
         if !self.rates.is_empty() {
-            writer.write_all(b"rate")?;
-            let rates_size = (self.rates.len() * 4) as u32;
-            writer.write_all(&rates_size.to_le_bytes())?;
-            for &rate in &self.rates {
-                writer.write_all(&rate.to_le_bytes())?;
-            }
+            write_chunk(&mut riff, &mut layout, id(*b"rate"), &words(&self.rates))?;
         }
 
-        // Write LIST chunk with icons
-        writer.write_all(b"LIST")?;
-        let list_size_pos = writer.stream_position()?;
-        writer.write_all(&[0u8; 4])?; // Placeholder for LIST size
-        writer.write_all(b"fram")?;
-        let list_start = writer.stream_position()?;
+        if options.write_loop_chunk
+            && let Some(data) = self.loop_behavior.to_chunk()
+        {
+            write_chunk(&mut riff, &mut layout, id(*b"LOOP"), &data)?;
+        }
 
-        // Write each frame as an icon
-        for frame in &self.frames {
-            writer.write_all(b"icon")?;
-            writer.write_all(&(frame.image_data.len() as u32).to_le_bytes())?;
-            writer.write_all(&frame.image_data)?;
-            
-            // Pad to even boundary
-            if frame.image_data.len() % 2 != 0 {
-                writer.write_all(&[0u8])?;
+        // Write LIST chunk with metadata
+        if !self.info.is_empty() {
+            riff.begin_list(id(*b"LIST"), id(*b"INFO"))?;
+            record(&mut layout, |layout| {
+                layout.push(Section::List(id(*b"INFO")), 12)
+            });
+            for entry in &self.info {
+                write_chunk(&mut riff, &mut layout, entry.id, &entry.value)?;
             }
+            riff.end_list()?;
         }
 
-        // Update LIST size
-        let list_end = writer.stream_position()?;
-        let list_size = (list_end - list_start) as u32;
-        writer.seek(SeekFrom::Start(list_size_pos))?;
-        writer.write_all(&list_size.to_le_bytes())?;
-
-        // Update file size
-        let file_end = writer.stream_position()?;
-        let file_size = (file_end - start_pos + 4) as u32; // +4 for ACON
-        writer.seek(SeekFrom::Start(file_size_pos))?;
-        writer.write_all(&file_size.to_le_bytes())?;
-
+        // Write LIST chunk with each frame as an icon
+        riff.begin_list(id(*b"LIST"), id(*b"fram"))?;
+        record(&mut layout, |layout| {
+            layout.push(Section::List(id(*b"fram")), 12)
+        });
+        for (index, frame) in self.frames.iter().enumerate() {
+            for chunk in self
+                .unknown_chunks
+                .iter()
+                .filter(|chunk| chunk.before_frame == index)
+            {
+                write_chunk(&mut riff, &mut layout, chunk.id, &chunk.data)?;
+            }
+            riff.write_chunk(id(*b"icon"), &frame.image_data)?;
+            record(&mut layout, |layout| {
+                layout.push_chunk(Section::Frame(index), frame.image_data.len())
+            });
+        }
+        for chunk in &self.unknown_chunks {
+            if chunk.before_frame >= self.frames.len() {
+                write_chunk(&mut riff, &mut layout, chunk.id, &chunk.data)?;
+            }
+        }
+        riff.finish()?;
         Ok(())
     }
 
     /// Decode ANI file from reader
-    pub fn decode<R: Read + Seek>(mut reader: R) -> io::Result<Self> {
+    ///
+    /// Repeated chunks are resolved the same way every time:
+    /// - a second `anih`, `seq ` or `rate` chunk is ignored, the first one wins
+    /// - frames from several `LIST fram` chunks are concatenated, and if the
+    ///   total disagrees with the header's frame count this is reported and
+    ///   frames past that count are dropped
+    /// - `seq ` and `rate` apply wherever they appear relative to the frames
+    ///
+    /// Chunks may come in any order; only a missing `anih` is an error.
+    /// Without a `seq ` chunk, every decoded frame is played once in order.
+    /// `decode_with` in strict mode rejects each of these instead. `encode`
+    /// always writes a single frame list.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::riff::ChunkWriter;
+    /// use cursor_handler::{AniFile, AniFrame, DecodeOptions, Hotspot};
+    /// use image::{Rgba, RgbaImage};
+    ///
+    /// let mut icons = Vec::new();
+    /// for (side, shade) in [(1, 0), (2, 255)] {
+    ///     let image = RgbaImage::from_pixel(side, side, Rgba([shade, shade, shade, 255]));
+    ///     icons.push(AniFrame::from_image(&image, Hotspot::default())?.image_data);
+    /// }
+    ///
+    /// // Two frame lists of one icon each, with a sequence reaching into the second
+    /// let write = |declared: u32| -> std::io::Result<Vec<u8>> {
+    ///     let mut anih = vec![36, 0, 0, 0];
+    ///     for field in [declared, 3, 0, 0, 0, 0, 6, 3] {
+    ///         anih.extend_from_slice(&u32::to_le_bytes(field));
+    ///     }
+    ///     let mut riff = ChunkWriter::new(Cursor::new(Vec::new()));
+    ///     riff.begin_list(*b"RIFF", *b"ACON")?;
+    ///     // The sequence may come before the header it is checked against
+    ///     riff.write_chunk(*b"seq ", &[1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0])?;
+    ///     riff.write_chunk(*b"anih", &anih)?;
+    ///     for icon in &icons {
+    ///         riff.begin_list(*b"LIST", *b"fram")?;
+    ///         riff.write_chunk(*b"icon", icon)?;
+    ///         riff.end_list()?;
+    ///     }
+    ///     Ok(riff.finish()?.into_inner())
+    /// };
+    ///
+    /// let ani = AniFile::decode(Cursor::new(write(2)?))?;
+    /// let shown: Vec<_> = ani.steps().map(|step| step.frame.size.width()).collect();
+    /// assert_eq!(shown, [2, 1, 2]);
+    ///
+    /// // One frame more than declared, but the sequence plays it: kept, or an
+    /// // error in strict mode
+    /// let ani = AniFile::decode(Cursor::new(write(1)?))?;
+    /// assert_eq!(ani.num_frames(), 2);
+    /// let shown: Vec<_> = ani.steps().map(|step| step.frame.size.width()).collect();
+    /// assert_eq!(shown, [2, 1, 2]);
+    /// assert!(AniFile::decode_with(Cursor::new(write(1)?), &DecodeOptions::strict()).is_err());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn decode<R: Read + Seek>(reader: R) -> io::Result<Self> {
+        Self::decode_with(reader, &DecodeOptions::default()).map(|decoded| decoded.value)
+    }
+
+    /// Decode ANI file from reader with default options, collecting
+    /// `DecodeStats` on the way
+    pub fn decode_with_stats<R: Read + Seek>(reader: R) -> io::Result<(Self, DecodeStats)> {
+        let options = DecodeOptions {
+            collect_stats: true,
+            ..DecodeOptions::default()
+        };
+        let decoded = Self::decode_with(reader, &options)?;
+        Ok((decoded.value, decoded.stats.unwrap_or_default()))
+    }
+
+    /// Decode ANI file from reader, applying `options`
+    ///
+    /// With the `tracing` feature the decode runs in a `decode_ani` span,
+    /// with a `frame` span per icon, a trace event per chunk, a debug
+    /// summary and a warning for each problem lenient mode lets through.
+    ///
+    /// ```
+    /// # #[cfg(feature = "tracing")]
+    /// # {
+    /// use std::{io::Cursor, sync::{Arc, Mutex}};
+    ///
+    /// use cursor_handler::AniFile;
+    /// use tracing::{Event, Subscriber, span};
+    /// use tracing_subscriber::{Layer, Registry, layer::{Context, SubscriberExt}};
+    ///
+    /// // Records span names and event levels
+    /// struct Record(Arc<Mutex<Vec<String>>>);
+    /// impl<S: Subscriber> Layer<S> for Record {
+    ///     fn on_new_span(&self, attrs: &span::Attributes<'_>, _: &span::Id, _: Context<'_, S>) {
+    ///         self.0.lock().unwrap().push(attrs.metadata().name().to_string());
+    ///     }
+    ///     fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+    ///         self.0.lock().unwrap().push(event.metadata().level().to_string());
+    ///     }
+    /// }
+    ///
+    /// let bytes = std::fs::read("assets/aero_busy.ani")?;
+    /// let log = Arc::new(Mutex::new(Vec::new()));
+    /// let subscriber = Registry::default().with(Record(log.clone()));
+    /// tracing::subscriber::with_default(subscriber, || AniFile::decode(Cursor::new(&bytes)))?;
+    ///
+    /// let log = log.lock().unwrap();
+    /// assert_eq!(log[0], "decode_ani");
+    /// assert_eq!(log.iter().filter(|name| *name == "frame").count(), 18);
+    /// assert!(log.iter().any(|name| name == "TRACE"));
+    /// assert_eq!(log.last().unwrap(), "DEBUG");
+    /// # }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn decode_with<R: Read + Seek>(
+        mut reader: R,
+        options: &DecodeOptions,
+    ) -> io::Result<Decoded<Self>> {
+        let _span = trace::span!(DEBUG, "decode_ani");
+        let mut diagnostics = Diagnostics::new(options);
+        diagnostics.check_decoded_size("RIFF", &mut reader)?;
+
         // Read RIFF header
-        let mut riff_header = [0u8; 12];
-        reader.read_exact(&mut riff_header)?;
+        let riff_header = read_chunk(&mut reader, "RIFF", None, 0, 12)?;
 
         if &riff_header[0..4] != b"RIFF" {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Not a RIFF file",
-            ));
+            return Err(invalid_chunk("RIFF", None, 0, "Not a RIFF file"));
         }
 
-        if &riff_header[8..12] != b"ACON" {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Not an ANI file",
-            ));
+        let mut id_variants = Vec::new();
+        let form_type: FourCC = riff_header[8..12].try_into().unwrap();
+        if &form_type != b"ACON" {
+            if !form_type.eq_ignore_ascii_case(b"ACON") {
+                return Err(invalid_chunk("RIFF", None, 8, "Not an ANI file"));
+            }
+            let message = format!("Form type '{}' read as 'ACON'", form_type.escape_ascii());
+            diagnostics.tolerate("RIFF", 8, message)?;
+            id_variants.push(IdVariant {
+                id: *b"ACON",
+                raw: form_type,
+            });
         }
 
-        let mut header = AniHeader::new();
-        let mut sequence = Vec::new();
-        let mut rates = Vec::new();
+        // Gather every chunk first and resolve them once the walk is done, so
+        // `seq ` and `rate` may come before `anih`
+        let mut anih = None;
+        let mut seq = None;
+        let mut rate = None;
+        let mut loop_chunk = None;
+        let mut frame_lists = Vec::new();
         let mut frames = Vec::new();
+        let mut damaged = Vec::new(); // Index and offset of each unreadable frame
+        let mut info = Vec::new();
+        let mut unknown_chunks = Vec::new();
 
         // Read chunks
-        loop {
-            let mut chunk_header = [0u8; 8];
-            if reader.read_exact(&mut chunk_header).is_err() {
-                break;
+        let mut chunks = ChunkReader::new(&mut reader)?;
+        while let Some(mut chunk) = chunks.next_chunk()? {
+            let chunk_offset = chunk.offset();
+            diagnostics.chunk(chunk.id());
+            let chunk_id = known_id(
+                &mut diagnostics,
+                &mut id_variants,
+                chunk.id(),
+                &[*b"anih", *b"seq ", *b"rate", *b"LOOP", *b"LIST"],
+                chunk_offset,
+            )?;
+            let id = fourcc(&chunk_id);
+            let chunk_size = chunk.size();
+            trace::trace!(id = %id, size = chunk_size, offset = chunk_offset, "chunk");
+            diagnostics.check_size(&id, chunk_offset, chunk_size as usize)?;
+            // A short frame list is reported at the first incomplete frame instead
+            let available = chunk.available();
+            if chunk.is_truncated() && &chunk_id != b"LIST" {
+                return Err(truncated_chunk(
+                    &id,
+                    None,
+                    chunk_offset,
+                    chunk_size as u64,
+                    available,
+                ));
             }
 
-            let chunk_id = &chunk_header[0..4];
-            let chunk_size = u32::from_le_bytes([
-                chunk_header[4],
-                chunk_header[5],
-                chunk_header[6],
-                chunk_header[7],
-            ]);
-
-            match chunk_id {
-                b"anih" => {
-                    let mut header_data = vec![0u8; chunk_size as usize];
-                    reader.read_exact(&mut header_data)?;
-
-                    if header_data.len() >= 36 {
-                        header.num_frames = u32::from_le_bytes([
-                            header_data[4],
-                            header_data[5],
-                            header_data[6],
-                            header_data[7],
-                        ]);
-                        header.num_steps = u32::from_le_bytes([
-                            header_data[8],
-                            header_data[9],
-                            header_data[10],
-                            header_data[11],
-                        ]);
-                        header.width = u32::from_le_bytes([
-                            header_data[12],
-                            header_data[13],
-                            header_data[14],
-                            header_data[15],
-                        ]);
-                        header.height = u32::from_le_bytes([
-                            header_data[16],
-                            header_data[17],
-                            header_data[18],
-                            header_data[19],
-                        ]);
-                        header.bit_count = u32::from_le_bytes([
-                            header_data[20],
-                            header_data[21],
-                            header_data[22],
-                            header_data[23],
-                        ]);
-                        header.planes = u32::from_le_bytes([
-                            header_data[24],
-                            header_data[25],
-                            header_data[26],
-                            header_data[27],
-                        ]);
-                        header.default_rate = u32::from_le_bytes([
-                            header_data[28],
-                            header_data[29],
-                            header_data[30],
-                            header_data[31],
-                        ]);
-                        header.flags = u32::from_le_bytes([
-                            header_data[32],
-                            header_data[33],
-                            header_data[34],
-                            header_data[35],
-                        ]);
-                    }
+            let table = match &chunk_id {
+                b"anih" => Some(&mut anih),
+                b"seq " => Some(&mut seq),
+                b"rate" => Some(&mut rate),
+                b"LOOP" => Some(&mut loop_chunk),
+                _ => None,
+            };
+            if let Some(table) = table {
+                if table.is_some() {
+                    diagnostics.tolerate(&id, chunk_offset, format!("Duplicate '{id}' chunk"))?;
+                    diagnostics.skipped(chunk_size as u64);
+                } else {
+                    let data = read_chunk(&mut chunk, &id, None, chunk_offset, chunk_size as u64)?;
+                    *table = Some((chunk_offset, data));
                 }
-                b"seq " => {
-                    let mut seq_data = vec![0u8; chunk_size as usize];
-                    reader.read_exact(&mut seq_data)?;
-                    
-                    for chunk in seq_data.chunks_exact(4) {
-                        sequence.push(u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+                continue;
+            }
+
+            match &chunk_id {
+                b"LIST" => {
+                    if chunk_size < 4 {
+                        return Err(invalid_chunk(&id, None, chunk_offset, "Missing LIST type"));
                     }
-                }
-                b"rate" => {
-                    let mut rate_data = vec![0u8; chunk_size as usize];
-                    reader.read_exact(&mut rate_data)?;
-                    
-                    for chunk in rate_data.chunks_exact(4) {
-                        rates.push(u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+                    if available < 4 {
+                        return Err(truncated_chunk(&id, None, chunk_offset, 4, available));
                     }
-                }
-                b"LIST" => {
-                    let mut list_type = [0u8; 4];
-                    reader.read_exact(&mut list_type)?;
-                    
+                    let (list_type, mut items) = chunk.list()?;
+                    let list_type = known_id(
+                        &mut diagnostics,
+                        &mut id_variants,
+                        list_type,
+                        &[*b"fram", *b"INFO"],
+                        chunk_offset + 8,
+                    )?;
+
+                    let mut damaged_list = false;
                     if &list_type == b"fram" {
-                        let remaining_size = (chunk_size - 4) as u64;
-                        let list_start = reader.stream_position()?;
-                        
-                        while reader.stream_position()? < list_start + remaining_size {
-                            let mut icon_header = [0u8; 8];
-                            if reader.read_exact(&mut icon_header).is_err() {
-                                break;
-                            }
-                            
-                            if &icon_header[0..4] == b"icon" {
-                                let icon_size = u32::from_le_bytes([
-                                    icon_header[4],
-                                    icon_header[5],
-                                    icon_header[6],
-                                    icon_header[7],
-                                ]);
-                                
-                                let mut icon_data = vec![0u8; icon_size as usize];
-                                reader.read_exact(&mut icon_data)?;
-                                
-                                // Parse ICO/CUR data to get dimensions and hotspot
-                                let frame = Self::parse_cursor_data(&icon_data)?;
-                                frames.push(frame);
-                                
-                                // Skip padding
-                                if icon_size % 2 != 0 {
-                                    let mut pad = [0u8; 1];
-                                    let _ = reader.read_exact(&mut pad);
+                        frame_lists.push(chunk_offset);
+                        while let Some(mut item) = items.next_chunk()? {
+                            let icon_offset = item.offset();
+                            diagnostics.chunk(item.id());
+                            let item_id = known_id(
+                                &mut diagnostics,
+                                &mut id_variants,
+                                item.id(),
+                                &[*b"icon"],
+                                icon_offset,
+                            )?;
+                            let icon_id = fourcc(&item_id);
+                            let icon_size = item.size();
+                            let index = frames.len();
+                            let _frame = trace::span!(TRACE, "frame", index);
+                            trace::trace!(
+                                id = %icon_id,
+                                size = icon_size,
+                                offset = icon_offset,
+                                "chunk"
+                            );
+                            diagnostics.check_size(&icon_id, icon_offset, icon_size as usize)?;
+
+                            if &item_id == b"icon" {
+                                let read = Self::read_icon(&mut item, &mut diagnostics, index);
+                                match (read, options.frame_error_policy) {
+                                    (Ok(frame), _) => frames.push(Some(frame)),
+                                    (Err(err), FrameErrorPolicy::Fail) => return Err(err),
+                                    (Err(err), _) => {
+                                        diagnostics.note(
+                                            icon_offset,
+                                            format!("Frame {index} is damaged: {err}"),
+                                        );
+                                        damaged.push((index, icon_offset));
+                                        damaged_list = true;
+                                        frames.push(None);
+                                    }
                                 }
+                                diagnostics.check_frames(&icon_id, icon_offset, frames.len())?;
+                            } else if item.is_truncated() {
+                                return Err(truncated_chunk(
+                                    &icon_id,
+                                    Some(index),
+                                    icon_offset,
+                                    icon_size as u64,
+                                    item.available(),
+                                ));
+                            } else {
+                                diagnostics.tolerate(
+                                    &icon_id,
+                                    icon_offset,
+                                    format!("Unexpected '{icon_id}' chunk in frame list"),
+                                )?;
+                                diagnostics.unknown_chunk();
+                                unknown_chunks.push(UnknownChunk {
+                                    id: item.id(),
+                                    data: item.read_data()?,
+                                    before_frame: index,
+                                });
                             }
                         }
+                    } else if &list_type == b"INFO" {
+                        while let Some(item) = items.next_chunk()? {
+                            // A truncated tag keeps whatever text is present
+                            let id = item.id();
+                            diagnostics.chunk(id);
+                            info.push(InfoEntry::new(id, item.read_data()?));
+                        }
                     } else {
                         // Skip unknown LIST
-                        reader.seek(SeekFrom::Current((chunk_size - 4) as i64))?;
+                        diagnostics.tolerate(
+                            &id,
+                            chunk_offset,
+                            format!("Unknown LIST type '{}'", fourcc(&list_type)),
+                        )?;
+                        diagnostics.unknown_chunk();
+                        diagnostics.skipped(chunk_size as u64 - 4);
+                    }
+
+                    // A damaged frame already accounts for the missing bytes
+                    if chunk_size as u64 > available && !damaged_list {
+                        return Err(truncated_chunk(
+                            &id,
+                            None,
+                            chunk_offset,
+                            chunk_size as u64,
+                            available,
+                        ));
                     }
                 }
                 _ => {
                     // Skip unknown chunk
-                    reader.seek(SeekFrom::Current(chunk_size as i64))?;
+                    diagnostics.tolerate(&id, chunk_offset, format!("Unknown chunk '{id}'"))?;
+                    diagnostics.unknown_chunk();
+                    diagnostics.skipped(chunk_size as u64);
                 }
             }
+        }
 
-            // Handle padding
-            if chunk_size % 2 != 0 {
-                let mut pad = [0u8; 1];
-                let _ = reader.read_exact(&mut pad);
+        let Some((anih_offset, header_data)) = anih else {
+            return Err(invalid_chunk("anih", None, 12, "Missing anih chunk"));
+        };
+        let mut header = AniHeader::new();
+        if header_data.len() < AniHeader::SIZE {
+            diagnostics.tolerate("anih", anih_offset, "anih chunk is shorter than 36 bytes")?;
+        } else {
+            let field = |index: usize| {
+                let at = 4 + index * 4;
+                u32::from_le_bytes([
+                    header_data[at],
+                    header_data[at + 1],
+                    header_data[at + 2],
+                    header_data[at + 3],
+                ])
+            };
+            header.num_frames = Some(field(0));
+            header.num_steps = Some(field(1));
+            header.width = field(2);
+            header.height = field(3);
+            header.bit_count = field(4);
+            header.planes = field(5);
+            header.default_rate = field(6);
+            header.flags = field(7);
+
+            let unknown = header.ani_flags().unknown_bits();
+            if unknown != 0 {
+                let message = format!("anih flags have unknown bits {unknown:#x}");
+                diagnostics.tolerate("anih", anih_offset, message)?;
             }
         }
 
-        // Use default sequence if none provided
-        if sequence.is_empty() {
-            sequence = (0..header.num_frames).collect();
+        let mut rates = match rate {
+            Some((offset, data)) => table_words(&mut diagnostics, "rate", offset, &data)?,
+            None => Vec::new(),
+        };
+
+        let mut loop_behavior = LoopBehavior::Infinite;
+        if let Some((offset, data)) = loop_chunk {
+            match LoopBehavior::from_chunk(&data) {
+                Some(behavior) => loop_behavior = behavior,
+                None => diagnostics.tolerate("LOOP", offset, "Unrecognised LOOP chunk")?,
+            }
+        }
+
+        let table = match seq {
+            Some((offset, data)) => Some(table_words(&mut diagnostics, "seq ", offset, &data)?),
+            None => None,
+        };
+
+        let declared_frames = header.num_frames.unwrap_or(frames.len() as u32);
+        if frame_lists.len() > 1 && frames.len() != declared_frames as usize {
+            diagnostics.tolerate(
+                "LIST",
+                frame_lists[1],
+                format!(
+                    "{} frame lists hold {} frames, anih declares {declared_frames}",
+                    frame_lists.len(),
+                    frames.len()
+                ),
+            )?;
+            // Frames past the declared count are a later list's leftovers,
+            // unless the sequence plays them
+            let played = table
+                .iter()
+                .flatten()
+                .map(|&index| index as usize + 1)
+                .max();
+            frames.truncate(played.unwrap_or(0).max(declared_frames as usize));
         }
 
-        Ok(Self {
+        // Use default sequence if none provided
+        let mut sequence = table.unwrap_or_else(|| (0..frames.len() as u32).collect());
+        damaged.retain(|&(index, _)| index < frames.len());
+        let frames = match options.frame_error_policy {
+            FrameErrorPolicy::Placeholder => {
+                let size = frames
+                    .iter()
+                    .flatten()
+                    .next()
+                    .map(|frame| frame.size)
+                    .unwrap_or(FrameSize::square(32)?);
+                let size = FrameSize::new(header.width, header.height).unwrap_or(size);
+                let blank = RgbaImage::new(size.width(), size.height());
+                let mut filled = Vec::with_capacity(frames.len());
+                for frame in frames {
+                    match frame {
+                        Some(frame) => filled.push(frame),
+                        None => filled.push(cursor_frame(&blank, Hotspot::default())?),
+                    }
+                }
+                filled
+            }
+            _ => {
+                Self::drop_steps(&mut diagnostics, &damaged, &mut sequence, &mut rates);
+                frames.into_iter().flatten().collect()
+            }
+        };
+        trace::debug!(
+            frames = frames.len(),
+            steps = sequence.len(),
+            rates = rates.len(),
+            "decoded ANI"
+        );
+
+        Ok(diagnostics.finish(Self {
             header,
             frames,
             sequence,
             rates,
-        })
+            info,
+            unknown_chunks,
+            loop_behavior,
+            id_variants,
+        }))
     }
 
-    fn parse_cursor_data(data: &[u8]) -> io::Result<AniFrame> {
+    /// Read a frame list's `icon` chunk as a frame
+    fn read_icon<T: Read + Seek>(
+        item: &mut Chunk<'_, T>,
+        diagnostics: &mut Diagnostics,
+        index: usize,
+    ) -> io::Result<AniFrame> {
+        let icon_offset = item.offset();
+        let icon_id = fourcc(&item.id());
+        let icon_size = item.size() as u64;
+        if item.is_truncated() {
+            let available = item.available();
+            return Err(truncated_chunk(
+                &icon_id,
+                Some(index),
+                icon_offset,
+                icon_size,
+                available,
+            ));
+        }
+
+        let mut icon_data = read_chunk(item, &icon_id, Some(index), icon_offset, icon_size)?;
+        let expected = container_len(&icon_data);
+        diagnostics.check_payload(&icon_id, Some(index), icon_offset, &mut icon_data, expected)?;
+
+        // Parse ICO/CUR data to get dimensions and hotspot
+        let policy = diagnostics.options().icon_hotspot;
+        let frame = Self::parse_cursor_data(&icon_data, policy)
+            .map_err(|err| invalid_chunk(&icon_id, Some(index), icon_offset, err.to_string()))?;
+        if frame.is_icon() {
+            diagnostics.note(
+                icon_offset,
+                format!(
+                    "Frame {index} holds an icon, which has no hotspot; using {}",
+                    frame.hotspot
+                ),
+            );
+        }
+        diagnostics.payload(&icon_data);
+        Ok(frame)
+    }
+
+    /// Drop the steps that play a skipped frame, with their rates, and
+    /// renumber the rest to the frames that remain
+    fn drop_steps(
+        diagnostics: &mut Diagnostics,
+        skipped: &[(usize, u64)],
+        sequence: &mut Vec<u32>,
+        rates: &mut Vec<u32>,
+    ) {
+        if skipped.is_empty() {
+            return;
+        }
+        let mut kept = Vec::with_capacity(sequence.len());
+        for (step, &index) in sequence.iter().enumerate() {
+            match skipped
+                .iter()
+                .find(|&&(skipped, _)| skipped == index as usize)
+            {
+                Some(&(_, offset)) => {
+                    diagnostics.note(
+                        offset,
+                        format!("Dropped step {step}, which played skipped frame {index}"),
+                    );
+                    kept.push(false);
+                }
+                None => kept.push(true),
+            }
+        }
+
+        let mut step = 0;
+        rates.retain(|_| {
+            step += 1;
+            kept.get(step - 1).copied().unwrap_or(true)
+        });
+        let mut step = 0;
+        sequence.retain(|_| {
+            step += 1;
+            kept[step - 1]
+        });
+        for index in sequence.iter_mut() {
+            let below = skipped
+                .iter()
+                .filter(|&&(skipped, _)| skipped < *index as usize);
+            *index -= below.count() as u32;
+        }
+    }
+
+    fn parse_cursor_data(data: &[u8], icon_hotspot: HotspotPolicy) -> io::Result<AniFrame> {
         if data.len() < 22 {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -378,42 +2147,164 @@ impl AniFile {
             ));
         }
 
-        // Skip ICO header (6 bytes) and read first directory entry (16 bytes)
-        let width = if data[6] == 0 { 256 } else { data[6] as u32 };
-        let height = if data[7] == 0 { 256 } else { data[7] as u32 };
-        let hotspot_x = u16::from_le_bytes([data[10], data[11]]);
-        let hotspot_y = u16::from_le_bytes([data[12], data[13]]);
+        if data[2..4] != [1, 0] && data[2..4] != [2, 0] {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a cursor or icon",
+            ));
+        }
+
+        // Skip ICO header (6 bytes) and read first directory entry (16 bytes);
+        // an icon keeps planes and bit count where a cursor keeps its hotspot
+        let word = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap()) as usize;
+        let (len, offset) = (word(14), word(18));
+        let payload = data
+            .get(offset..offset.saturating_add(len))
+            .unwrap_or_default();
+        let size = stored_size(FrameSize::from_dir_bytes([data[6], data[7]]), payload);
+        let hotspot = if data[2..4] == [1, 0] {
+            icon_hotspot.resolve(size.width(), size.height())
+        } else {
+            Hotspot::new(
+                u16::from_le_bytes([data[10], data[11]]),
+                u16::from_le_bytes([data[12], data[13]]),
+            )
+        };
 
         Ok(AniFrame {
-            width,
-            height,
-            hotspot_x,
-            hotspot_y,
+            size,
+            hotspot,
             image_data: data.to_vec(),
             duration: None,
         })
     }
 }
 
+impl Index<usize> for AniFile {
+    type Output = AniFrame;
+
+    fn index(&self, index: usize) -> &AniFrame {
+        &self.frames[index]
+    }
+}
+
+impl IndexMut<usize> for AniFile {
+    fn index_mut(&mut self, index: usize) -> &mut AniFrame {
+        &mut self.frames[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a AniFile {
+    type Item = &'a AniFrame;
+    type IntoIter = slice::Iter<'a, AniFrame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut AniFile {
+    type Item = &'a mut AniFrame;
+    type IntoIter = slice::IterMut<'a, AniFrame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.iter_mut()
+    }
+}
+
+impl Debug for AniFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AniFrame")
+            .field("size", &self.size)
+            .field("hotspot", &self.hotspot)
+            .field("image_data", &BytesSummary(&self.image_data))
+            .field("duration", &self.duration)
+            .finish()
+    }
+}
+
+impl Debug for UnknownChunk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UnknownChunk")
+            .field("id", &self.id)
+            .field("data", &BytesSummary(&self.data))
+            .field("before_frame", &self.before_frame)
+            .finish()
+    }
+}
+
+impl Debug for AniFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AniFile")
+            .field("header", &self.header)
+            .field("frames", &FramesSummary(&self.frames))
+            .field("sequence", &self.sequence)
+            .field("rates", &self.rates)
+            .field("info", &self.info)
+            .field("unknown_chunks", &self.unknown_chunks)
+            .field("loop_behavior", &self.loop_behavior)
+            .field("id_variants", &self.id_variants)
+            .finish()
+    }
+}
+
 impl Display for AniFile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Animated Cursor with {} frame(s):", self.frames.len())?;
-        writeln!(f, "  Steps: {}", self.header.num_steps)?;
+        if let Some(title) = self.title() {
+            writeln!(f, "  Title: {title}")?;
+        }
+        if let Some(artist) = self.artist() {
+            writeln!(f, "  Artist: {artist}")?;
+        }
+        writeln!(f, "  Steps: {}", self.num_steps())?;
         writeln!(f, "  Size: {}x{}", self.header.width, self.header.height)?;
         writeln!(f, "  Default Rate: {} jiffies", self.header.default_rate)?;
-        writeln!(f, "  Sequence: {:?}", self.sequence)?;
-        
-        if !self.rates.is_empty() {
-            writeln!(f, "  Individual Rates: {:?}", self.rates)?;
-        }
-        
+        writeln!(f, "  Flags: {}", self.header.ani_flags())?;
+
+        // Wide enough for any number of steps at any rate
+        let total: u64 = self.steps().map(|step| u64::from(step.duration)).sum();
+        let ms = total.saturating_mul(1000).saturating_add(30) / 60;
+        writeln!(f, "  Duration: {total} jiffies ({ms} ms)")?;
+        let custom = self.sequence != (0..self.frames.len() as u32).collect::<Vec<_>>();
+        writeln!(
+            f,
+            "  Sequence: {}",
+            if custom { "custom" } else { "default" }
+        )?;
+        writeln!(
+            f,
+            "  Rates: {}",
+            if self.rates.is_empty() {
+                "default"
+            } else {
+                "per step"
+            }
+        )?;
+
         for (i, frame) in self.frames.iter().enumerate() {
             writeln!(
                 f,
-                "  Frame {i}:\n    Size:    {}x{}\n    Hotspot: ({}, {})",
-                frame.width, frame.height, frame.hotspot_x, frame.hotspot_y
+                "  Frame {i}:\n    Size:    {}\n    Hotspot: {}",
+                frame.size, frame.hotspot
+            )?;
+            writeln!(
+                f,
+                "    Payload: {}, {} bytes",
+                frame.payload_format(),
+                frame.image_data.len()
+            )?;
+        }
+
+        for (i, step) in self.steps().enumerate() {
+            writeln!(
+                f,
+                "  Step {i}: frame {}, {} jiffies ({} ms)",
+                step.frame_index,
+                step.duration,
+                jiffies_to_ms(step.duration)
             )?;
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
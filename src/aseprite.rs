@@ -0,0 +1,292 @@
+// Importing Aseprite sprite sheet exports (sheet image + JSON data), read
+// with serde_json
+use std::{fmt, io};
+
+use image::{DynamicImage, RgbaImage, imageops};
+use serde::{
+    Deserialize, Deserializer,
+    de::{IgnoredAny, MapAccess, SeqAccess, Visitor},
+};
+
+use crate::{
+    ani::{AniFile, ms_to_jiffies},
+    generate::cursor_frame,
+    hotspot::HotspotSpec,
+};
+
+/// Options for `AniFile::from_aseprite`
+#[derive(Debug, Clone, Default)]
+pub struct AsepriteOptions {
+    /// Import only the frames of this tag, played in its direction
+    pub tag: Option<String>,
+    pub hotspot: HotspotSpec, // Resolved at each frame's canvas size
+}
+
+/// The parts of an Aseprite JSON export the importer reads
+#[derive(Deserialize)]
+struct Export {
+    #[serde(deserialize_with = "frame_list")]
+    frames: Vec<ExportFrame>,
+    #[serde(default)]
+    meta: Meta,
+}
+
+/// One entry of the `frames` list or hash
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportFrame {
+    frame: Rect,
+    #[serde(default)]
+    rotated: bool,
+    #[serde(default)]
+    trimmed: bool,
+    sprite_source_size: Option<Point>, // Where the trimmed rect sits on the canvas
+    source_size: Option<Size>,
+    duration: u32,
+}
+
+#[derive(Deserialize)]
+struct Rect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+#[derive(Deserialize)]
+struct Size {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Meta {
+    #[serde(default)]
+    frame_tags: Vec<FrameTag>,
+}
+
+#[derive(Deserialize)]
+struct FrameTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: Direction,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Direction {
+    #[default]
+    Forward,
+    Reverse,
+    Pingpong,
+    PingpongReverse,
+}
+
+impl AniFile {
+    /// Build an animation from an Aseprite sheet and its JSON data
+    ///
+    /// Both the "hash" and "array" frame layouts are read. Trimmed frames
+    /// are put back at their place on the full canvas. With a tag, only its
+    /// frames are kept and the sequence follows its direction (`forward`,
+    /// `reverse`, `pingpong` or `pingpong_reverse`). Durations are converted
+    /// from ms to jiffies.
+    ///
+    /// ```
+    /// use cursor_handler::{AniFile, aseprite::AsepriteOptions};
+    ///
+    /// let sheet = image::open("assets/aseprite_sheet.png").map_err(std::io::Error::other)?;
+    /// let json = std::fs::read_to_string("assets/aseprite_sheet.json")?;
+    ///
+    /// let ani = AniFile::from_aseprite(&sheet, &json, &AsepriteOptions::default())?;
+    /// assert_eq!(ani.num_frames(), 4);
+    /// let steps: Vec<_> = ani.steps().map(|step| (step.frame_index, step.duration)).collect();
+    /// assert_eq!(steps, [(0, 6), (1, 6), (2, 12), (3, 3)]);
+    ///
+    /// // The trimmed frame is back at its offset on the 8x8 canvas
+    /// let trimmed = ani.extract_frame(1)?[0].decode_image()?;
+    /// assert_eq!(trimmed.dimensions(), (8, 8));
+    /// assert_eq!(trimmed.get_pixel(0, 0).0[3], 0);
+    /// assert_eq!(trimmed.get_pixel(2, 3).0, [0, 255, 0, 255]);
+    ///
+    /// let options = AsepriteOptions { tag: Some("spin".into()), ..Default::default() };
+    /// let spin = AniFile::from_aseprite(&sheet, &json, &options)?;
+    /// assert_eq!(spin.num_frames(), 3);
+    /// let steps: Vec<_> = spin.steps().map(|step| step.frame_index).collect();
+    /// assert_eq!(steps, [0, 1, 2, 1]);
+    ///
+    /// let array = r#"{"frames": [{"frame": {"x": 16, "y": 0, "w": 8, "h": 8}, "duration": 40}]}"#;
+    /// let blue = AniFile::from_aseprite(&sheet, array, &AsepriteOptions::default())?;
+    /// assert_eq!((blue.num_frames(), blue.header.default_rate()), (1, 2));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_aseprite(
+        sheet: &DynamicImage,
+        json: &str,
+        options: &AsepriteOptions,
+    ) -> io::Result<Self> {
+        let export: Export = serde_json::from_str(json).map_err(io::Error::from)?;
+        let frames = &export.frames;
+        if frames.is_empty() {
+            return Err(invalid_data("no frames"));
+        }
+        if frames.iter().any(|frame| frame.rotated) {
+            return Err(invalid_data("rotated frames are not supported"));
+        }
+
+        // Indices into `frames`, in playback order
+        let (first, order) = match &options.tag {
+            None => (0, (0..frames.len()).collect::<Vec<_>>()),
+            Some(name) => tag_order(&export.meta, name, frames.len())?,
+        };
+        let last = order.iter().copied().max().unwrap_or(first);
+
+        let sheet = sheet.to_rgba8();
+        let mut ani = AniFile::empty();
+        for frame in &frames[first..=last] {
+            let Rect {
+                x,
+                y,
+                w: width,
+                h: height,
+            } = frame.frame;
+            let outside = |start: u32, len: u32, limit: u32| {
+                start.checked_add(len).is_none_or(|end| end > limit)
+            };
+            if outside(x, width, sheet.width()) || outside(y, height, sheet.height()) {
+                return Err(invalid_data(format!(
+                    "frame rect {x},{y} {width}x{height} is outside the {}x{} sheet",
+                    sheet.width(),
+                    sheet.height()
+                )));
+            }
+            let (offset, size) =
+                match (frame.trimmed, &frame.sprite_source_size, &frame.source_size) {
+                    (false, _, _) => ((0, 0), (width, height)),
+                    (true, Some(offset), Some(size)) => ((offset.x, offset.y), (size.w, size.h)),
+                    (true, _, _) => {
+                        return Err(invalid_data(
+                            "trimmed frame is missing 'spriteSourceSize' or 'sourceSize'",
+                        ));
+                    }
+                };
+            let mut canvas = RgbaImage::new(size.0, size.1);
+            let cell = imageops::crop_imm(&sheet, x, y, width, height).to_image();
+            imageops::replace(&mut canvas, &cell, offset.0 as i64, offset.1 as i64);
+
+            let hotspot = options.hotspot.resolve(canvas.width(), canvas.height());
+            if !hotspot.is_within(canvas.width(), canvas.height()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "hotspot {hotspot} is outside the {}x{} canvas",
+                        canvas.width(),
+                        canvas.height()
+                    ),
+                ));
+            }
+            ani.push_frame(cursor_frame(&canvas, hotspot)?);
+        }
+
+        let rates: Vec<u32> = order
+            .iter()
+            .map(|&index| ms_to_jiffies(frames[index].duration))
+            .collect();
+        let sequence = order.iter().map(|&index| (index - first) as u32).collect();
+        let is_identity = order.iter().copied().eq(first..=last);
+
+        let mut ani = if is_identity {
+            ani
+        } else {
+            ani.with_sequence(sequence)
+        };
+        if rates.iter().all(|&rate| rate == rates[0]) {
+            ani = ani.with_default_rate(rates[0])?;
+        } else {
+            ani = ani.with_rates(rates);
+        }
+        Ok(ani)
+    }
+}
+
+/// Reads `frames` from either layout: an array, or an object keyed by file
+/// name whose members are taken in document order
+fn frame_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<ExportFrame>, D::Error> {
+    struct FrameList;
+
+    impl<'de> Visitor<'de> for FrameList {
+        type Value = Vec<ExportFrame>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a frames array or object")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut frames = Vec::new();
+            while let Some(frame) = seq.next_element()? {
+                frames.push(frame);
+            }
+            Ok(frames)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut frames = Vec::new();
+            while let Some((_, frame)) = map.next_entry::<IgnoredAny, _>()? {
+                frames.push(frame);
+            }
+            Ok(frames)
+        }
+    }
+
+    deserializer.deserialize_any(FrameList)
+}
+
+/// The first frame of tag `name` and its frames in playback order
+fn tag_order(meta: &Meta, name: &str, count: usize) -> io::Result<(usize, Vec<usize>)> {
+    let tag = meta
+        .frame_tags
+        .iter()
+        .find(|tag| tag.name == name)
+        .ok_or_else(|| invalid_data(format!("no tag named '{name}'")))?;
+
+    let bound = |index: usize, key: &str| match index < count {
+        true => Ok(index),
+        false => Err(invalid_data(format!("tag '{name}' has a bad '{key}'"))),
+    };
+    let (from, to) = (bound(tag.from, "from")?, bound(tag.to, "to")?);
+    if from > to {
+        return Err(invalid_data(format!("tag '{name}' ends before it starts")));
+    }
+
+    let forward: Vec<usize> = (from..=to).collect();
+    // A ping-pong doesn't repeat the frames it turns around on
+    let order = match tag.direction {
+        Direction::Forward => forward.clone(),
+        Direction::Reverse => forward.iter().rev().copied().collect(),
+        Direction::Pingpong => {
+            let back = forward
+                .iter()
+                .rev()
+                .skip(1)
+                .take((to - from).saturating_sub(1));
+            forward.iter().chain(back).copied().collect()
+        }
+        Direction::PingpongReverse => {
+            let back = forward.iter().skip(1).take((to - from).saturating_sub(1));
+            forward.iter().rev().chain(back).copied().collect()
+        }
+    };
+    Ok((from, order))
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
@@ -0,0 +1,66 @@
+// Value parsers shared between subcommands
+use cursor_handler::{Hotspot, HotspotSpec, transform::ResizeHotspot};
+
+/// Parse a step duration into jiffies (1/60 s)
+///
+/// Accepts `100ms`, `0.5s`, `6j` or a bare number of jiffies.
+pub fn parse_rate(value: &str) -> Result<u32, String> {
+    let value = value.trim();
+    let jiffies = if let Some(ms) = value.strip_suffix("ms") {
+        parse_number(ms)? * 60.0 / 1000.0
+    } else if let Some(seconds) = value.strip_suffix('s') {
+        parse_number(seconds)? * 60.0
+    } else {
+        parse_number(value.strip_suffix('j').unwrap_or(value))?
+    };
+
+    let jiffies = jiffies.round();
+    if jiffies < 1.0 {
+        return Err(format!("{value} is shorter than 1 jiffy (1/60 s)"));
+    }
+    if jiffies > u32::MAX as f64 {
+        return Err(format!("{value} is too long"));
+    }
+    Ok(jiffies as u32)
+}
+
+/// Parse an `X,Y` hotspot, `center` or `top-left`
+pub fn parse_hotspot(value: &str) -> Result<HotspotSpec, String> {
+    match value.trim() {
+        "center" => return Ok(HotspotSpec::Center),
+        "top-left" => return Ok(HotspotSpec::TopLeft),
+        _ => {}
+    }
+    parse_point(value).map(HotspotSpec::from)
+}
+
+/// Parse a resize hotspot: `keep-fraction`, `keep-pixel` or an `X,Y` used at
+/// every size
+pub fn parse_resize_hotspot(value: &str) -> Result<ResizeHotspot, String> {
+    match value.trim() {
+        "keep-fraction" => Ok(ResizeHotspot::KeepFraction),
+        "keep-pixel" => Ok(ResizeHotspot::KeepPixel),
+        _ => parse_point(value).map(ResizeHotspot::Absolute),
+    }
+}
+
+fn parse_point(value: &str) -> Result<Hotspot, String> {
+    let (x, y) = value
+        .split_once(',')
+        .ok_or_else(|| format!("expected X,Y but got {value}"))?;
+    let coordinate = |part: &str| {
+        part.trim()
+            .parse::<u16>()
+            .map_err(|err| format!("invalid coordinate {part}: {err}"))
+    };
+    Ok(Hotspot::new(coordinate(x)?, coordinate(y)?))
+}
+
+fn parse_number(value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite() && *number >= 0.0)
+        .ok_or_else(|| format!("invalid duration {value}"))
+}
@@ -0,0 +1,280 @@
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+    process::ExitCode,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use clap::Args;
+use rayon::prelude::*;
+
+/// Options shared by every subcommand that can run over many files
+#[derive(Args)]
+pub struct BatchArgs {
+    /// Descend into directory inputs
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Mirror the input tree into this directory instead of writing in place
+    #[arg(long, value_name = "DIR")]
+    out_dir: Option<PathBuf>,
+
+    /// Number of files to process in parallel (defaults to the CPU count)
+    #[arg(short, long, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Stop at the first file that fails
+    #[arg(long)]
+    fail_fast: bool,
+}
+
+/// One file to process and where its result goes
+pub struct Job {
+    pub input: PathBuf,
+    pub output: PathBuf,
+}
+
+/// What happened to a single file
+#[derive(Default)]
+pub struct FileOutcome {
+    pub bytes_saved: i64,
+    pub warnings: Vec<String>,
+    /// Lines for stdout, printed in input order whatever order files finish in
+    pub report: Vec<String>,
+    /// At least `Warn` when there are `warnings`
+    pub status: Status,
+}
+
+/// How a file counts in the summary
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    #[default]
+    Ok,
+    Warn,
+    /// Processed but not passing, like a file `validate` finds errors in
+    Fail,
+}
+
+const EXTENSIONS: [&str; 2] = ["cur", "ani"];
+
+impl BatchArgs {
+    /// Expand globs and directories into jobs
+    ///
+    /// `output` only applies when the inputs expand to exactly one file.
+    pub fn jobs(&self, inputs: &[PathBuf], output: Option<&Path>) -> io::Result<Vec<Job>> {
+        let mut files = Vec::new();
+        for input in inputs {
+            expand(input, self.recursive, &mut files)?;
+        }
+
+        if output.is_some() && (files.len() != 1 || self.out_dir.is_some()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--output needs exactly one input file and no --out-dir",
+            ));
+        }
+
+        Ok(files
+            .into_iter()
+            .map(|(input, relative)| {
+                let output = match (&self.out_dir, output) {
+                    (Some(dir), _) => dir.join(relative),
+                    (None, Some(output)) => output.to_path_buf(),
+                    (None, None) => input.clone(),
+                };
+                Job { input, output }
+            })
+            .collect())
+    }
+
+    /// Run `process` over every job, print a summary and pick the exit code
+    ///
+    /// Reports, warnings and errors come out in input order once every file
+    /// is done. The summary goes to stderr so stdout stays machine-readable.
+    pub fn run<F>(&self, jobs: Vec<Job>, process: F) -> io::Result<ExitCode>
+    where
+        F: Fn(&Job) -> io::Result<FileOutcome> + Sync,
+    {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.jobs.unwrap_or(0))
+            .build()
+            .map_err(io::Error::other)?;
+
+        let stop = AtomicBool::new(false);
+        let results: Vec<_> = pool.install(|| {
+            jobs.par_iter()
+                .map(|job| {
+                    if stop.load(Ordering::Relaxed) {
+                        return None;
+                    }
+                    let result = create_parent(job).and_then(|()| process(job));
+                    let failed = result
+                        .as_ref()
+                        .map_or(true, |outcome| outcome.status == Status::Fail);
+                    if failed && self.fail_fast {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                    Some(result)
+                })
+                .collect()
+        });
+
+        let mut summary = Summary::default();
+        for (job, result) in jobs.iter().zip(results) {
+            let input = job.input.display();
+            match result {
+                None => summary.skipped += 1,
+                Some(Ok(outcome)) => {
+                    for line in &outcome.report {
+                        println!("{line}");
+                    }
+                    for warning in &outcome.warnings {
+                        eprintln!("{input}: warning: {warning}");
+                    }
+                    let warned = if outcome.warnings.is_empty() {
+                        Status::Ok
+                    } else {
+                        Status::Warn
+                    };
+                    match outcome.status.max(warned) {
+                        Status::Ok => summary.ok += 1,
+                        Status::Warn => summary.warn += 1,
+                        Status::Fail => summary.fail += 1,
+                    }
+                    summary.bytes_saved += outcome.bytes_saved;
+                }
+                Some(Err(err)) => {
+                    eprintln!("{input}: error: {err}");
+                    summary.fail += 1;
+                }
+            }
+        }
+        if jobs.len() > 1 || summary.fail > 0 {
+            summary.print();
+        }
+
+        Ok(if summary.fail > 0 {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        })
+    }
+}
+
+#[derive(Default)]
+struct Summary {
+    ok: usize,
+    warn: usize,
+    fail: usize,
+    skipped: usize,
+    bytes_saved: i64,
+}
+
+impl Summary {
+    fn print(&self) {
+        eprintln!();
+        eprintln!("  {:<8}{:>8}", "status", "files");
+        eprintln!("  {:<8}{:>8}", "ok", self.ok);
+        eprintln!("  {:<8}{:>8}", "warn", self.warn);
+        eprintln!("  {:<8}{:>8}", "fail", self.fail);
+        if self.skipped > 0 {
+            eprintln!("  {:<8}{:>8}", "skipped", self.skipped);
+        }
+        eprintln!("  total bytes saved: {}", self.bytes_saved);
+    }
+}
+
+/// Add the files named by `input` with their path relative to the input root
+fn expand(input: &Path, recursive: bool, files: &mut Vec<(PathBuf, PathBuf)>) -> io::Result<()> {
+    if input.is_dir() {
+        if !recursive {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is a directory (use --recursive)", input.display()),
+            ));
+        }
+        return walk(input, input, files);
+    }
+
+    // A missing file still gets a job, and fails on its own
+    let pattern = input.to_string_lossy();
+    if input.exists() || !pattern.contains(['*', '?', '[']) {
+        let name = input.file_name().map(PathBuf::from).unwrap_or_default();
+        files.push((input.to_path_buf(), name));
+        return Ok(());
+    }
+
+    let root = literal_prefix(input);
+    let paths =
+        glob::glob(&pattern).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+    let before = files.len();
+    for path in paths {
+        let path = path.map_err(io::Error::from)?;
+        if path.is_dir() {
+            if recursive {
+                walk(&root, &path, files)?;
+            }
+        } else {
+            let relative = path.strip_prefix(&root).unwrap_or(&path).to_path_buf();
+            files.push((path, relative));
+        }
+    }
+
+    if files.len() == before {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} matched no files", input.display()),
+        ));
+    }
+    Ok(())
+}
+
+fn walk(root: &Path, dir: &Path, files: &mut Vec<(PathBuf, PathBuf)>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            walk(root, &path, files)?;
+        } else if has_cursor_extension(&path) {
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            files.push((path, relative));
+        }
+    }
+    Ok(())
+}
+
+fn has_cursor_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            EXTENSIONS
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        })
+}
+
+/// The leading components of a glob pattern that contain no wildcards
+fn literal_prefix(pattern: &Path) -> PathBuf {
+    pattern
+        .components()
+        .take_while(|component| match component {
+            Component::Normal(part) => !part.to_string_lossy().contains(['*', '?', '[']),
+            _ => true,
+        })
+        .collect()
+}
+
+/// Make room for `job`'s output, unless it replaces the input
+fn create_parent(job: &Job) -> io::Result<()> {
+    if job.output == job.input {
+        return Ok(());
+    }
+    match job.output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,41 @@
+use std::{io, path::PathBuf, process::ExitCode};
+
+use clap::Args;
+use cursor_handler::project::CursorProject;
+
+#[derive(Args)]
+pub struct BuildArgs {
+    /// Project file describing the cursors (TOML)
+    project: PathBuf,
+
+    /// Directory to write the cursors to (defaults to the project's directory)
+    #[arg(short, long)]
+    out: Option<PathBuf>,
+
+    /// Keep running, rebuilding the cursors whose sources change
+    #[cfg(feature = "watch")]
+    #[arg(long)]
+    watch: bool,
+}
+
+pub fn run(args: BuildArgs) -> io::Result<ExitCode> {
+    let out = match args.out {
+        Some(out) => out,
+        None => args.project.parent().map(PathBuf::from).unwrap_or_default(),
+    };
+    #[cfg(feature = "watch")]
+    if args.watch {
+        return crate::watch::run(&args.project, &out);
+    }
+
+    let project = CursorProject::load(&args.project)?;
+
+    let report = project.build(out)?;
+    for warning in &report.warnings {
+        eprintln!("warning: {warning}");
+    }
+    for output in &report.outputs {
+        println!("wrote {}", output.display());
+    }
+    Ok(ExitCode::SUCCESS)
+}
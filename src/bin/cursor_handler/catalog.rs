@@ -0,0 +1,114 @@
+// The `formats` and `roles` listings, generated from the library's own
+// tables so they stay in step with what it accepts
+use std::{io, process::ExitCode};
+
+use clap::{Args, builder::PossibleValuesParser};
+use cursor_handler::{convert, project::TargetFormat};
+use serde::Serialize;
+
+use crate::output::OutputArgs;
+
+#[derive(Args)]
+pub struct FormatsArgs {
+    /// Show only this format
+    #[arg(
+        value_name = "FORMAT",
+        value_parser = PossibleValuesParser::new(TargetFormat::ALL.map(TargetFormat::name))
+    )]
+    name: Option<String>,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Args)]
+pub struct RolesArgs {
+    /// Show only this role
+    #[arg(
+        value_parser = PossibleValuesParser::new(convert::roles().iter().map(|role| role.name)),
+        ignore_case = true
+    )]
+    role: Option<String>,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// One row of `formats --format json`
+#[derive(Serialize)]
+struct FormatEntry {
+    name: &'static str,
+    description: &'static str,
+}
+
+/// One row of `roles --format json`
+#[derive(Serialize)]
+struct RoleEntry {
+    name: &'static str,
+    xcursor: Option<&'static str>,
+    aliases: &'static [&'static str],
+    file_stems: &'static [&'static str],
+}
+
+pub fn formats(args: FormatsArgs) -> io::Result<ExitCode> {
+    let formats: Vec<_> = TargetFormat::ALL
+        .into_iter()
+        .filter(|format| {
+            args.name
+                .as_deref()
+                .is_none_or(|name| name == format.name())
+        })
+        .collect();
+
+    if args.output.json() {
+        let entries: Vec<_> = formats
+            .iter()
+            .map(|format| FormatEntry {
+                name: format.name(),
+                description: format.description(),
+            })
+            .collect();
+        args.output.print(&entries)?;
+    } else {
+        println!("{:<9} DESCRIPTION", "FORMAT");
+        for format in formats {
+            println!("{:<9} {}", format.name(), format.description());
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+pub fn roles(args: RolesArgs) -> io::Result<ExitCode> {
+    let roles: Vec<_> = convert::roles()
+        .iter()
+        .filter(|role| {
+            args.role
+                .as_deref()
+                .is_none_or(|name| name.eq_ignore_ascii_case(role.name))
+        })
+        .collect();
+
+    if args.output.json() {
+        let entries: Vec<_> = roles
+            .iter()
+            .map(|role| RoleEntry {
+                name: role.name,
+                xcursor: role.xcursor,
+                aliases: role.aliases,
+                file_stems: role.file_stems,
+            })
+            .collect();
+        args.output.print(&entries)?;
+    } else {
+        println!("{:<12} {:<18} ALIASES", "ROLE", "XCURSOR");
+        for role in roles {
+            println!(
+                "{:<12} {:<18} {}",
+                role.name,
+                role.xcursor.unwrap_or("-"),
+                role.aliases.join(", ")
+            );
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
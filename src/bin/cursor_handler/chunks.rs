@@ -0,0 +1,22 @@
+use std::{fs::File, io, path::PathBuf, process::ExitCode};
+
+use clap::Args;
+use cursor_handler::riff::list_chunks;
+
+#[derive(Args)]
+pub struct ChunksArgs {
+    /// .ani files to list
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+}
+
+pub fn run(args: ChunksArgs) -> io::Result<ExitCode> {
+    for (i, path) in args.inputs.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}:", path.display());
+        print!("{}", list_chunks(File::open(path)?)?);
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,163 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::Args;
+use cursor_handler::{
+    AniFile, CursorFile,
+    diff::{self, AniDiff, CursorDiff},
+};
+use image::RgbaImage;
+
+use crate::{
+    input::CursorInput,
+    output::{Failure, Outcome, OutputArgs},
+};
+
+#[derive(Args)]
+pub struct CompareArgs {
+    /// The original .cur or .ani file
+    old: PathBuf,
+
+    /// The file to compare it with: of the same kind, or a .cur against a
+    /// single-frame .ani
+    new: PathBuf,
+
+    /// Write old, new and an overlay of changed pixels side by side, one
+    /// row per frame, to this PNG
+    #[arg(long, value_name = "PATH")]
+    diff_image: Option<PathBuf>,
+
+    /// Largest channel difference the diff image ignores
+    #[arg(long, default_value_t = 0)]
+    threshold: u8,
+
+    /// Exit 1 when the files differ, as well as 2 on errors
+    #[arg(long)]
+    exit_code: bool,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// `{"old": ..., "new": ..., "kind": ..., "identical": ..., "changes": [...]}`
+#[derive(serde::Serialize)]
+struct CompareDocument {
+    old: String,
+    new: String,
+    #[serde(flatten)]
+    outcome: Outcome<CompareReport>,
+}
+
+#[derive(serde::Serialize)]
+struct CompareReport {
+    /// `"cur"` or `"ani"`
+    kind: &'static str,
+    identical: bool,
+    #[serde(flatten)]
+    diff: Diff,
+}
+
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+enum Diff {
+    Cur(CursorDiff),
+    Ani(AniDiff),
+}
+
+/// Two files that can be compared, with a single-frame .ani standing in for
+/// a .cur
+enum Inputs {
+    Cur(CursorFile, CursorFile),
+    Ani(Box<(AniFile, AniFile)>),
+}
+
+/// Exits 2 when the files can't be compared, and with `--exit-code` 1 when
+/// they differ
+pub fn run(args: CompareArgs) -> io::Result<ExitCode> {
+    let json = args.output.json();
+    let inputs = decode(&args.old).and_then(|old| pair(old, decode(&args.new)?));
+    let report = inputs.as_ref().map(compare).map_err(Failure::clone);
+    let mut code = match &report {
+        Ok(report) if !report.identical && args.exit_code => ExitCode::FAILURE,
+        Ok(_) => ExitCode::SUCCESS,
+        Err(_) => ExitCode::from(2),
+    };
+
+    if json {
+        args.output.print(&CompareDocument {
+            old: args.old.display().to_string(),
+            new: args.new.display().to_string(),
+            outcome: report.into(),
+        })?;
+    } else {
+        match report.map(|report| report.diff) {
+            Ok(Diff::Cur(diff)) => print!("{diff}"),
+            Ok(Diff::Ani(diff)) => print!("{diff}"),
+            Err(failure) => eprintln!("error: {failure}"),
+        }
+    }
+
+    if let (Some(path), Ok(inputs)) = (&args.diff_image, &inputs) {
+        let written = diff_image(inputs, args.threshold)
+            .and_then(|image| image.save(path).map_err(io::Error::other));
+        if let Err(err) = written {
+            eprintln!("error: {}: {err}", path.display());
+            code = ExitCode::from(2);
+        }
+    }
+    Ok(code)
+}
+
+fn decode(path: &Path) -> Result<CursorInput, Failure> {
+    let named = |err| format!("{}: {err}", path.display());
+    let bytes = fs::read(path).map_err(|err| Failure::read(named(err)))?;
+    CursorInput::decode(&bytes).map_err(|err| Failure::decode(named(err)))
+}
+
+fn pair(old: CursorInput, new: CursorInput) -> Result<Inputs, Failure> {
+    let as_cur = |ani: AniFile| match ani.num_frames() {
+        1 => ani.extract_frame(0).map_err(Failure::decode),
+        frames => Err(Failure {
+            kind: "mismatch",
+            message: format!("can't compare a .cur file with an .ani file of {frames} frames"),
+        }),
+    };
+    Ok(match (old, new) {
+        (CursorInput::Cur(old), CursorInput::Cur(new)) => Inputs::Cur(old, new),
+        (CursorInput::Ani(old), CursorInput::Ani(new)) => Inputs::Ani(Box::new((old, new))),
+        (CursorInput::Cur(old), CursorInput::Ani(new)) => Inputs::Cur(old, as_cur(new)?),
+        (CursorInput::Ani(old), CursorInput::Cur(new)) => Inputs::Cur(as_cur(old)?, new),
+    })
+}
+
+fn compare(inputs: &Inputs) -> CompareReport {
+    match inputs {
+        Inputs::Cur(old, new) => {
+            let diff = diff::compare_cur(old, new);
+            CompareReport {
+                kind: "cur",
+                identical: diff.is_identical(),
+                diff: Diff::Cur(diff),
+            }
+        }
+        Inputs::Ani(pair) => {
+            let (old, new) = &**pair;
+            let diff = diff::compare_ani(old, new);
+            CompareReport {
+                kind: "ani",
+                identical: diff.is_identical(),
+                diff: Diff::Ani(diff),
+            }
+        }
+    }
+}
+
+fn diff_image(inputs: &Inputs, threshold: u8) -> io::Result<RgbaImage> {
+    match inputs {
+        Inputs::Cur(old, new) => diff::cur_diff_image(old, new, threshold),
+        Inputs::Ani(pair) => diff::ani_diff_image(&pair.0, &pair.1, threshold),
+    }
+}
@@ -0,0 +1,17 @@
+use std::{io, process::ExitCode};
+
+use clap::Args;
+use clap_complete::Shell;
+
+#[derive(Args)]
+pub struct CompletionsArgs {
+    /// Shell to generate the completion script for
+    shell: Shell,
+}
+
+/// Print a completion script for `command` to stdout
+pub fn run(args: CompletionsArgs, mut command: clap::Command) -> io::Result<ExitCode> {
+    let name = command.get_name().to_string();
+    clap_complete::generate(args.shell, &mut command, name, &mut io::stdout());
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,38 @@
+use std::{fs, io, path::PathBuf, process::ExitCode};
+
+use clap::Args;
+use cursor_handler::windows;
+
+use crate::input;
+
+#[derive(Args)]
+pub struct ExportSchemeArgs {
+    /// Directory to write one file per cursor role into
+    #[arg(long, value_name = "DIR")]
+    out_dir: PathBuf,
+}
+
+pub fn run(args: ExportSchemeArgs) -> io::Result<ExitCode> {
+    let scheme = windows::current_scheme()?;
+    fs::create_dir_all(&args.out_dir)?;
+
+    for (role, source) in &scheme {
+        let cursor = source.cursor();
+        let path = args
+            .out_dir
+            .join(role.registry_name())
+            .with_extension(cursor.extension());
+        input::write_atomic(&path, &cursor.encode()?)?;
+
+        let origin = source.path().map_or_else(
+            || "system default".into(),
+            |path| path.display().to_string(),
+        );
+        println!(
+            "{:<12} {} (from {origin})",
+            role.registry_name(),
+            path.display()
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,129 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::{Args, Subcommand, value_parser};
+use cursor_handler::{AniFile, GenerateOptions, HotspotSpec, generate};
+use image::ImageReader;
+
+use crate::args::{parse_hotspot, parse_rate};
+
+#[derive(Args)]
+pub struct GenerateArgs {
+    #[command(subcommand)]
+    kind: Generator,
+}
+
+#[derive(Subcommand)]
+enum Generator {
+    /// Rotate the hue a little more on every step
+    HueCycle {
+        #[command(flatten)]
+        common: CommonArgs,
+
+        /// Hue rotation added per step
+        #[arg(long, default_value_t = 15, allow_negative_numbers = true)]
+        degrees: i32,
+    },
+    /// Rotate the image clockwise
+    Spin {
+        #[command(flatten)]
+        common: CommonArgs,
+
+        /// Total rotation over all steps
+        #[arg(long, default_value_t = 360.0, allow_negative_numbers = true)]
+        degrees: f32,
+    },
+    /// Shrink and grow the image around its center
+    Pulse {
+        #[command(flatten)]
+        common: CommonArgs,
+
+        /// Smallest scale reached mid-cycle
+        #[arg(long, default_value_t = 0.6)]
+        min_scale: f32,
+    },
+    /// Alternate between the image and a blank frame
+    Blink {
+        #[command(flatten)]
+        common: CommonArgs,
+    },
+}
+
+#[derive(Args)]
+struct CommonArgs {
+    /// Source image (PNG or anything the image crate reads)
+    image: PathBuf,
+
+    /// Number of animation steps
+    #[arg(long, default_value_t = 14, value_parser = value_parser!(u32).range(2..))]
+    steps: u32,
+
+    /// Hotspot as X,Y, center or top-left
+    #[arg(long, default_value = "top-left", value_parser = parse_hotspot)]
+    hotspot: HotspotSpec,
+
+    /// Duration of each step: 100ms, 0.5s, 6j or jiffies
+    #[arg(long, default_value = "100ms", value_parser = parse_rate)]
+    rate: u32,
+
+    /// Output .ani path
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+impl CommonArgs {
+    fn options(&self) -> GenerateOptions {
+        GenerateOptions {
+            steps: self.steps,
+            hotspot: self.hotspot,
+            rate: self.rate,
+        }
+    }
+}
+
+pub fn run(args: GenerateArgs) -> io::Result<ExitCode> {
+    let (ani, common) = match &args.kind {
+        Generator::HueCycle { common, degrees } => (
+            generate::hue_cycle(&open(common)?, *degrees, &common.options())?,
+            common,
+        ),
+        Generator::Spin { common, degrees } => (
+            generate::spin(&open(common)?, *degrees, &common.options())?,
+            common,
+        ),
+        Generator::Pulse { common, min_scale } => (
+            generate::pulse(&open(common)?, *min_scale, &common.options())?,
+            common,
+        ),
+        Generator::Blink { common } => {
+            (generate::blink(&open(common)?, &common.options())?, common)
+        }
+    };
+
+    write(&ani, &common.output)?;
+    println!(
+        "{}: {} frame(s), {} step(s) of {} jiffies",
+        common.output.display(),
+        ani.num_frames(),
+        ani.sequence.len(),
+        ani.header.default_rate()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn open(common: &CommonArgs) -> io::Result<image::DynamicImage> {
+    ImageReader::open(&common.image)?
+        .decode()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn write(ani: &AniFile, path: &PathBuf) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    ani.encode(&mut writer)?;
+    writer.into_inner().map_err(|err| err.into_error())?;
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use std::{fs, io, path::PathBuf, process::ExitCode};
+
+use clap::Args;
+use cursor_handler::Manifest;
+
+use crate::{
+    input::CursorInput,
+    output::{Failure, FileDocument, OutputArgs},
+};
+
+#[derive(Args)]
+pub struct InfoArgs {
+    /// .cur or .ani files to describe
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// `{"path": ..., "manifest": {...}}`
+#[derive(serde::Serialize)]
+struct InfoReport {
+    manifest: Manifest,
+}
+
+pub fn run(args: InfoArgs) -> io::Result<ExitCode> {
+    let json = args.output.json();
+    let mut failed = false;
+    for (i, path) in args.inputs.iter().enumerate() {
+        let decoded = fs::read(path)
+            .map_err(Failure::read)
+            .and_then(|bytes| CursorInput::decode_with_stats(&bytes).map_err(Failure::decode));
+        failed |= decoded.is_err();
+
+        if json {
+            let report = decoded.map(|(input, _)| InfoReport {
+                manifest: input.manifest(),
+            });
+            args.output.print(&FileDocument::new(path, report))?;
+            continue;
+        }
+        if i > 0 {
+            println!();
+        }
+        println!("{}:", path.display());
+        match decoded {
+            Ok((input, stats)) => {
+                match input {
+                    CursorInput::Cur(cur) => print!("{cur}"),
+                    CursorInput::Ani(ani) => print!("{ani}"),
+                }
+                println!("Decode: {stats}");
+            }
+            Err(failure) => eprintln!("error: {failure}"),
+        }
+    }
+
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
@@ -0,0 +1,67 @@
+use std::{
+    fs,
+    io::{self, Cursor},
+    path::Path,
+};
+
+use cursor_handler::{AniFile, CursorFile, DecodeStats, Manifest};
+
+/// A decoded input file of either kind
+pub enum CursorInput {
+    Cur(CursorFile),
+    Ani(AniFile),
+}
+
+impl CursorInput {
+    /// Decode bytes, telling .ani from .cur by the RIFF magic
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.starts_with(b"RIFF") {
+            AniFile::decode(Cursor::new(bytes)).map(Self::Ani)
+        } else {
+            CursorFile::decode(Cursor::new(bytes)).map(Self::Cur)
+        }
+    }
+
+    /// `decode`, along with what the decoder saw
+    pub fn decode_with_stats(bytes: &[u8]) -> io::Result<(Self, DecodeStats)> {
+        if bytes.starts_with(b"RIFF") {
+            AniFile::decode_with_stats(Cursor::new(bytes))
+                .map(|(ani, stats)| (Self::Ani(ani), stats))
+        } else {
+            CursorFile::decode_with_stats(Cursor::new(bytes))
+                .map(|(cur, stats)| (Self::Cur(cur), stats))
+        }
+    }
+
+    pub fn manifest(&self) -> Manifest {
+        match self {
+            Self::Cur(cur) => cur.into(),
+            Self::Ani(ani) => ani.into(),
+        }
+    }
+
+    pub fn read(path: &Path) -> io::Result<(Self, Vec<u8>)> {
+        let bytes = fs::read(path)?;
+        Ok((Self::decode(&bytes)?, bytes))
+    }
+
+    pub fn encode(&self) -> io::Result<Vec<u8>> {
+        let mut buf = Cursor::new(Vec::new());
+        match self {
+            Self::Cur(cur) => cur.encode(&mut buf)?,
+            Self::Ani(ani) => ani.encode(&mut buf)?,
+        }
+        Ok(buf.into_inner())
+    }
+}
+
+/// Write through a temporary sibling file so a failed write never clobbers `path`
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
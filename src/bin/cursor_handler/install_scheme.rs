@@ -0,0 +1,97 @@
+use std::{io, path::PathBuf, process::ExitCode};
+
+use clap::Args;
+use cursor_handler::windows::{self, InstallOptions, SchemeChange, Scope, SystemCursor};
+
+#[derive(Args)]
+pub struct InstallSchemeArgs {
+    /// Scheme name shown in the Mouse control panel
+    name: String,
+
+    /// Role and file as ROLE=PATH (e.g. Arrow=arrow.cur); repeat per role
+    #[arg(long = "role", value_name = "ROLE=PATH", value_parser = parse_role, required = true)]
+    roles: Vec<(SystemCursor, PathBuf)>,
+
+    /// Directory the scheme folder is created in (defaults to %APPDATA%\Cursors)
+    #[arg(long, value_name = "DIR")]
+    install_dir: Option<PathBuf>,
+
+    /// Register for all users under HKLM (needs administrator rights)
+    #[arg(long)]
+    machine: bool,
+
+    /// Register the scheme without making it active
+    #[arg(long)]
+    no_activate: bool,
+
+    /// Show the changes without making them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct UninstallSchemeArgs {
+    /// Scheme name to remove
+    name: String,
+
+    /// Directory the scheme folder was created in (defaults to %APPDATA%\Cursors)
+    #[arg(long, value_name = "DIR")]
+    install_dir: Option<PathBuf>,
+
+    /// Remove the scheme from HKLM instead of HKCU
+    #[arg(long)]
+    machine: bool,
+
+    /// Show the changes without making them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub fn install(args: InstallSchemeArgs) -> io::Result<ExitCode> {
+    let options = InstallOptions {
+        install_dir: args.install_dir,
+        activate: !args.no_activate,
+        dry_run: args.dry_run,
+    };
+
+    let changes = windows::install_scheme(&args.name, &args.roles, scope(args.machine), &options)?;
+    print_changes(&changes, args.dry_run);
+    Ok(ExitCode::SUCCESS)
+}
+
+pub fn uninstall(args: UninstallSchemeArgs) -> io::Result<ExitCode> {
+    let options = InstallOptions {
+        install_dir: args.install_dir,
+        dry_run: args.dry_run,
+        ..InstallOptions::default()
+    };
+
+    let changes = windows::uninstall_scheme(&args.name, scope(args.machine), &options)?;
+    print_changes(&changes, args.dry_run);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn scope(machine: bool) -> Scope {
+    if machine { Scope::Machine } else { Scope::User }
+}
+
+fn print_changes(changes: &[SchemeChange], dry_run: bool) {
+    let prefix = if dry_run { "would " } else { "" };
+    for change in changes {
+        println!("{prefix}{change}");
+    }
+}
+
+fn parse_role(value: &str) -> Result<(SystemCursor, PathBuf), String> {
+    let (role, path) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected ROLE=PATH but got {value}"))?;
+    let role = SystemCursor::from_registry_name(role.trim()).ok_or_else(|| {
+        let names: Vec<_> = SystemCursor::ALL
+            .iter()
+            .map(|r| r.registry_name())
+            .collect();
+        format!("unknown role {role}; expected one of {}", names.join(", "))
+    })?;
+    Ok((role, PathBuf::from(path)))
+}
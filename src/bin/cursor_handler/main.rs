@@ -0,0 +1,132 @@
+mod args;
+mod batch;
+mod build;
+mod catalog;
+mod chunks;
+mod compare;
+mod completions;
+#[cfg(feature = "windows-apply")]
+mod export_scheme;
+mod generate;
+mod info;
+mod input;
+#[cfg(feature = "windows-apply")]
+mod install_scheme;
+mod meta;
+mod optimize;
+mod output;
+mod resize;
+mod strip;
+mod theme;
+mod validate;
+#[cfg(feature = "watch")]
+mod watch;
+
+use std::process::ExitCode;
+
+use clap::{ArgAction, CommandFactory, Parser, Subcommand};
+
+/// Inspect, convert and optimize Windows cursor files
+#[derive(Parser)]
+#[command(name = "cursor_handler", version, about)]
+struct Cli {
+    /// Log decoding and encoding to stderr, -v for summaries and -vv for
+    /// every chunk (needs the `tracing` feature)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Describe the frames, payloads and timing of cursor files
+    Info(info::InfoArgs),
+    /// View and edit the INFO tags (title, artist, ...) of .ani files
+    Meta(meta::MetaArgs),
+    /// Show the raw RIFF chunk tree of .ani files, even broken ones
+    Chunks(chunks::ChunksArgs),
+    /// Shrink .cur and .ani files without changing how they play back
+    Optimize(optimize::OptimizeArgs),
+    /// Build a procedural animation from a single image
+    Generate(generate::GenerateArgs),
+    /// Scale a cursor to new sizes, choosing where the hotspot goes
+    Resize(resize::ResizeArgs),
+    /// Export an .ani to a sprite sheet, or import one back
+    Strip(strip::StripArgs),
+    /// Report structural problems that decoding tolerates
+    Validate(validate::ValidateArgs),
+    /// Show what changed between two cursor files, optionally as an image
+    Compare(compare::CompareArgs),
+    /// Build every cursor described by a project file
+    Build(build::BuildArgs),
+    /// Build a whole Windows cursor scheme, with its install.inf, from a config
+    Theme(theme::ThemeArgs),
+    /// List the formats a project can build cursors as
+    Formats(catalog::FormatsArgs),
+    /// List the Windows cursor roles and their Xcursor names
+    Roles(catalog::RolesArgs),
+    /// Print a shell completion script
+    Completions(completions::CompletionsArgs),
+    /// Back up the current Windows cursor scheme
+    #[cfg(feature = "windows-apply")]
+    ExportScheme(export_scheme::ExportSchemeArgs),
+    /// Install a Windows cursor scheme into the registry
+    #[cfg(feature = "windows-apply")]
+    InstallScheme(install_scheme::InstallSchemeArgs),
+    /// Remove a Windows cursor scheme installed with install-scheme
+    #[cfg(feature = "windows-apply")]
+    UninstallScheme(install_scheme::UninstallSchemeArgs),
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    init_tracing(cli.verbose);
+
+    let result = match cli.command {
+        Command::Info(args) => info::run(args),
+        Command::Meta(args) => meta::run(args),
+        Command::Chunks(args) => chunks::run(args),
+        Command::Optimize(args) => optimize::run(args),
+        Command::Generate(args) => generate::run(args),
+        Command::Resize(args) => resize::run(args),
+        Command::Strip(args) => strip::run(args),
+        Command::Validate(args) => validate::run(args),
+        Command::Compare(args) => compare::run(args),
+        Command::Build(args) => build::run(args),
+        Command::Theme(args) => theme::run(args),
+        Command::Formats(args) => catalog::formats(args),
+        Command::Roles(args) => catalog::roles(args),
+        Command::Completions(args) => completions::run(args, Cli::command()),
+        #[cfg(feature = "windows-apply")]
+        Command::ExportScheme(args) => export_scheme::run(args),
+        #[cfg(feature = "windows-apply")]
+        Command::InstallScheme(args) => install_scheme::install(args),
+        #[cfg(feature = "windows-apply")]
+        Command::UninstallScheme(args) => install_scheme::uninstall(args),
+    };
+
+    result.unwrap_or_else(|err| {
+        eprintln!("error: {err}");
+        ExitCode::FAILURE
+    })
+}
+
+#[cfg(feature = "tracing")]
+fn init_tracing(verbose: u8) {
+    use tracing_subscriber::filter::LevelFilter;
+
+    let level = match verbose {
+        0 => return,
+        1 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+#[cfg(not(feature = "tracing"))]
+fn init_tracing(_verbose: u8) {}
@@ -0,0 +1,151 @@
+use std::{fs, io, path::PathBuf, process::ExitCode};
+
+use clap::{Args, Subcommand};
+use cursor_handler::{
+    InfoEntry,
+    metadata::{self, read_info, rewrite_info},
+};
+
+use crate::input;
+
+#[derive(Args)]
+pub struct MetaArgs {
+    #[command(subcommand)]
+    command: Meta,
+}
+
+#[derive(Subcommand)]
+enum Meta {
+    /// Print the INFO tags of an .ani
+    Show {
+        /// The .ani to read
+        input: PathBuf,
+    },
+    /// Set INFO tags, rewriting only the INFO list; an empty value removes
+    /// the tag
+    Set(SetArgs),
+    /// Remove every INFO tag
+    Strip {
+        /// The .ani to edit
+        input: PathBuf,
+
+        /// Where to write the result (defaults to replacing the input)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct SetArgs {
+    /// The .ani to edit
+    input: PathBuf,
+
+    /// Title (INAM)
+    #[arg(long)]
+    title: Option<String>,
+
+    /// Artist (IART)
+    #[arg(long)]
+    artist: Option<String>,
+
+    /// Copyright (ICOP)
+    #[arg(long)]
+    copyright: Option<String>,
+
+    /// Comment (ICMT)
+    #[arg(long)]
+    comment: Option<String>,
+
+    /// Any other tag, as ID=VALUE with a four-character ID
+    #[arg(long, value_name = "ID=VALUE", value_parser = parse_tag)]
+    tag: Vec<([u8; 4], String)>,
+
+    /// Where to write the result (defaults to replacing the input)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn run(args: MetaArgs) -> io::Result<ExitCode> {
+    match args.command {
+        Meta::Show { input } => show(&fs::read(&input)?),
+        Meta::Set(args) => {
+            let bytes = fs::read(&args.input)?;
+            let mut info = read_info(&bytes)?;
+            let named = [
+                (*b"INAM", args.title),
+                (*b"IART", args.artist),
+                (*b"ICOP", args.copyright),
+                (*b"ICMT", args.comment),
+            ];
+            let edits = named
+                .into_iter()
+                .filter_map(|(id, value)| Some((id, value?)))
+                .chain(args.tag);
+            for (id, value) in edits {
+                set(&mut info, id, &value);
+            }
+            write(
+                &rewrite_info(&bytes, &info)?,
+                args.output.unwrap_or(args.input),
+            )
+        }
+        Meta::Strip { input, output } => {
+            let bytes = fs::read(&input)?;
+            write(&rewrite_info(&bytes, &[])?, output.unwrap_or(input))
+        }
+    }
+}
+
+fn show(bytes: &[u8]) -> io::Result<ExitCode> {
+    let info = read_info(bytes)?;
+    if info.is_empty() {
+        println!("no INFO tags");
+    }
+    for entry in &info {
+        let id = String::from_utf8_lossy(&entry.id);
+        let name = metadata::tag_name(&entry.id).map_or(id, Into::into);
+        let lossy = if std::str::from_utf8(&entry.value).is_err() {
+            " (not UTF-8)"
+        } else {
+            ""
+        };
+        println!("{name}: {}{lossy}", entry.text());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Replace the first `id` tag in place, dropping any repeats, or append one;
+/// an empty `value` removes it
+fn set(info: &mut Vec<InfoEntry>, id: [u8; 4], value: &str) {
+    let first = info.iter().position(|entry| entry.id == id);
+    let mut index = 0;
+    info.retain(|entry| {
+        let keep = entry.id != id || Some(index) == first;
+        index += 1;
+        keep
+    });
+    match first {
+        _ if value.is_empty() => info.retain(|entry| entry.id != id),
+        Some(first) => info[first] = InfoEntry::from_text(id, value),
+        None => info.push(InfoEntry::from_text(id, value)),
+    }
+}
+
+fn write(bytes: &[u8], path: PathBuf) -> io::Result<ExitCode> {
+    input::write_atomic(&path, bytes)?;
+    println!("wrote {}", path.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn parse_tag(value: &str) -> Result<([u8; 4], String), String> {
+    let (id, text) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected ID=VALUE but got {value}"))?;
+    let id: [u8; 4] = id
+        .as_bytes()
+        .try_into()
+        .ok()
+        .filter(|id: &[u8; 4]| id.iter().all(u8::is_ascii_graphic))
+        .ok_or_else(|| format!("{id} is not a four-character tag ID"))?;
+    Ok((id, text.to_string()))
+}
@@ -0,0 +1,132 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::Args;
+use cursor_handler::{OptimizeOptions, OptimizeReport};
+
+use crate::{
+    batch::{BatchArgs, FileOutcome},
+    input::{self, CursorInput},
+};
+
+#[derive(Args)]
+pub struct OptimizeArgs {
+    /// Files, directories or glob patterns to optimize
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Where to write the result (single input only; defaults to replacing the input)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    #[command(flatten)]
+    batch: BatchArgs,
+
+    /// Re-compress PNG payloads at maximum compression
+    #[arg(long)]
+    recompress: bool,
+
+    /// Transcode DIB frames at least this many pixels wide or tall to PNG
+    #[arg(long, value_name = "PIXELS")]
+    png_threshold: Option<u32>,
+
+    /// Remove INFO metadata (title, artist, ...)
+    #[arg(long)]
+    strip: bool,
+
+    /// Report the savings without writing anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub fn run(args: OptimizeArgs) -> io::Result<ExitCode> {
+    let jobs = args.batch.jobs(&args.inputs, args.output.as_deref())?;
+    let options = OptimizeOptions {
+        recompress_png: args.recompress,
+        png_threshold: args.png_threshold,
+        strip_metadata: args.strip,
+        ..OptimizeOptions::default()
+    };
+
+    args.batch.run(jobs, |job| {
+        let (before, after, report) =
+            optimize_file(&job.input, &job.output, &options, args.dry_run)?;
+        let mut outcome = FileOutcome {
+            bytes_saved: before as i64 - after as i64,
+            report: vec![describe(&job.input, before, after, &report)],
+            ..FileOutcome::default()
+        };
+        if after >= before {
+            outcome
+                .warnings
+                .push("already optimal, left unchanged".into());
+            outcome.bytes_saved = 0;
+        }
+        Ok(outcome)
+    })
+}
+
+fn optimize_file(
+    path: &Path,
+    output: &Path,
+    options: &OptimizeOptions,
+    dry_run: bool,
+) -> io::Result<(usize, usize, OptimizeReport)> {
+    let (original, bytes) = CursorInput::read(path)?;
+
+    let mut optimized = CursorInput::decode(&bytes)?;
+    let report = match &mut optimized {
+        CursorInput::Cur(cur) => cur.optimize(options)?,
+        CursorInput::Ani(ani) => ani.optimize(options)?,
+    };
+    let encoded = optimized.encode()?;
+
+    // Check the bytes we are about to write, not the in-memory value
+    let same = match (&original, CursorInput::decode(&encoded)?) {
+        (CursorInput::Cur(a), CursorInput::Cur(b)) => a.renders_same_as(&b)?,
+        (CursorInput::Ani(a), CursorInput::Ani(b)) => a.plays_same_as(&b)?,
+        _ => false,
+    };
+    if !same {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "optimized output does not play back like the input; nothing written",
+        ));
+    }
+
+    // Keep the original bytes when optimizing didn't help
+    let smaller = encoded.len() < bytes.len();
+    let result = if smaller { &encoded } else { &bytes };
+    if !dry_run && (smaller || output != path) {
+        input::write_atomic(output, result)?;
+    }
+
+    Ok((bytes.len(), result.len(), report))
+}
+
+fn describe(path: &Path, before: usize, after: usize, report: &OptimizeReport) -> String {
+    let saved = before as i64 - after as i64;
+    let percent = if before == 0 {
+        0.0
+    } else {
+        saved as f64 * 100.0 / before as f64
+    };
+
+    format!(
+        "{}: {before} -> {after} bytes ({saved} saved, {percent:.1}%)\n  \
+         frames removed: {}, steps merged: {}, recompressed: {}, transcoded: {}{}",
+        path.display(),
+        report.frames_removed,
+        report.steps_merged,
+        report.payloads_recompressed,
+        report.frames_transcoded,
+        if report.metadata_stripped {
+            ", metadata stripped"
+        } else {
+            ""
+        }
+    )
+}
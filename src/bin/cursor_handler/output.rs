@@ -0,0 +1,122 @@
+// `--format` and `--jsonl`, shared by the read-only subcommands
+//
+// JSON output is one document per input, built from the library's
+// serializable types. An input that can't be read or decoded still gets a
+// document, with an `error` object in place of the result. Exit codes don't
+// depend on the format.
+use std::{
+    fmt::{self, Display},
+    io,
+    path::Path,
+};
+
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Args)]
+pub struct OutputArgs {
+    /// How to print results
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Print each JSON document on a single line (implies --format json)
+    #[arg(long)]
+    jsonl: bool,
+}
+
+impl OutputArgs {
+    /// Whether to print JSON
+    pub fn json(&self) -> bool {
+        self.jsonl || self.format == Format::Json
+    }
+
+    /// Print one JSON document
+    pub fn print(&self, document: &impl Serialize) -> io::Result<()> {
+        println!("{}", self.render(document)?);
+        Ok(())
+    }
+
+    /// One JSON document as `print` would write it
+    pub fn render(&self, document: &impl Serialize) -> io::Result<String> {
+        let json = if self.jsonl {
+            serde_json::to_string(document)
+        } else {
+            serde_json::to_string_pretty(document)
+        };
+        json.map_err(io::Error::other)
+    }
+}
+
+/// Why an input has no result
+#[derive(Clone, Serialize)]
+pub struct Failure {
+    /// `"read"`, `"decode"`, or `"mismatch"` when comparing a .cur with an .ani
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn read(err: impl Display) -> Self {
+        Self {
+            kind: "read",
+            message: err.to_string(),
+        }
+    }
+
+    pub fn decode(err: impl Display) -> Self {
+        Self {
+            kind: "decode",
+            message: err.to_string(),
+        }
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            "read" => write!(f, "can't read: {}", self.message),
+            "decode" => write!(f, "can't decode: {}", self.message),
+            _ => f.write_str(&self.message),
+        }
+    }
+}
+
+/// An input's result, or an `error` object in its place
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum Outcome<T> {
+    Ok(T),
+    Err { error: Failure },
+}
+
+impl<T> From<Result<T, Failure>> for Outcome<T> {
+    fn from(result: Result<T, Failure>) -> Self {
+        match result {
+            Ok(value) => Self::Ok(value),
+            Err(error) => Self::Err { error },
+        }
+    }
+}
+
+/// The document for one input file: its path, then its result's fields
+#[derive(Serialize)]
+pub struct FileDocument<T> {
+    pub path: String,
+    #[serde(flatten)]
+    pub outcome: Outcome<T>,
+}
+
+impl<T> FileDocument<T> {
+    pub fn new(path: &Path, result: Result<T, Failure>) -> Self {
+        Self {
+            path: path.display().to_string(),
+            outcome: result.into(),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::{io, path::PathBuf, process::ExitCode};
+
+use clap::{Args, ValueEnum, value_parser};
+use cursor_handler::transform::{ResizeHotspot, ResizeOptions, ScaleFilter};
+
+use crate::{
+    args::parse_resize_hotspot,
+    batch::{BatchArgs, FileOutcome, Job},
+    input::{self, CursorInput},
+};
+
+/// How far a side may grow before it's worth a warning
+const UPSCALE_WARNING: f32 = 2.0;
+
+#[derive(Args)]
+pub struct ResizeArgs {
+    /// .cur or .ani files, directories or glob patterns to resize
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Longer side of each output frame, comma separated; an .ani takes one
+    #[arg(
+        long,
+        visible_alias = "size",
+        value_name = "PIXELS",
+        value_delimiter = ',',
+        required = true,
+        value_parser = value_parser!(u32).range(1..)
+    )]
+    sizes: Vec<u32>,
+
+    /// How to sample the source
+    #[arg(long, value_enum, default_value_t = Filter::Auto)]
+    filter: Filter,
+
+    /// Where the hotspot goes: keep-fraction, keep-pixel or X,Y at every size
+    #[arg(long, default_value = "keep-fraction", value_parser = parse_resize_hotspot)]
+    hotspot: ResizeHotspot,
+
+    /// Centre non-square frames on a square canvas instead of keeping their
+    /// aspect ratio
+    #[arg(long)]
+    pad_square: bool,
+
+    /// Don't warn about scaling a frame up more than 2x
+    #[arg(long)]
+    allow_upscale: bool,
+
+    /// Output path (single input only; otherwise use --out-dir)
+    #[arg(short, long, required_unless_present = "out_dir")]
+    output: Option<PathBuf>,
+
+    #[command(flatten)]
+    batch: BatchArgs,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Filter {
+    /// Area averaging when shrinking, Lanczos when growing
+    Auto,
+    /// Lanczos3, sharp but prone to dark fringes on transparent edges
+    Lanczos,
+    /// Nearest neighbour, for pixel art
+    Nearest,
+}
+
+pub fn run(args: ResizeArgs) -> io::Result<ExitCode> {
+    let options = ResizeOptions {
+        filter: match args.filter {
+            Filter::Auto => None,
+            Filter::Lanczos => Some(ScaleFilter::Lanczos3),
+            Filter::Nearest => Some(ScaleFilter::Nearest),
+        },
+        hotspot: args.hotspot,
+        pad_square: args.pad_square,
+    };
+
+    let jobs = args.batch.jobs(&args.inputs, args.output.as_deref())?;
+    args.batch
+        .run(jobs, |job| resize_file(job, &args, &options))
+}
+
+fn resize_file(job: &Job, args: &ResizeArgs, options: &ResizeOptions) -> io::Result<FileOutcome> {
+    let (mut cursor, _) = CursorInput::read(&job.input)?;
+    let source_side = match &mut cursor {
+        CursorInput::Cur(cur) => {
+            let source_side = cur
+                .largest_frame()
+                .map(|frame| frame.size.max_side())
+                .unwrap_or_default();
+            *cur = cur.resized(&args.sizes, options)?;
+            source_side
+        }
+        CursorInput::Ani(ani) => {
+            let [side] = args.sizes[..] else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "an .ani is resized to one --size",
+                ));
+            };
+            let source_side = ani
+                .frames()
+                .map(|frame| frame.size.max_side())
+                .min()
+                .unwrap_or_default();
+            ani.resize(side, options)?;
+            source_side
+        }
+    };
+
+    let mut outcome = FileOutcome::default();
+    if !args.allow_upscale {
+        for &side in &args.sizes {
+            let factor = side as f32 / source_side.max(1) as f32;
+            if factor > UPSCALE_WARNING {
+                outcome.warnings.push(format!(
+                    "{side}px is {factor:.1}x the {source_side}px source and will look blurry; \
+                     pass --allow-upscale if that's intended"
+                ));
+            }
+        }
+    }
+
+    let name = job.input.display();
+    match &cursor {
+        CursorInput::Cur(cur) => {
+            for frame in cur.frames() {
+                let line = format!("{name}: {}: hotspot {}", frame.size, frame.hotspot);
+                outcome.report.push(line);
+            }
+        }
+        CursorInput::Ani(ani) => {
+            for (index, frame) in ani.frames().enumerate() {
+                let line = format!(
+                    "{name}: frame {index}: {}: hotspot {}",
+                    frame.size, frame.hotspot
+                );
+                outcome.report.push(line);
+            }
+        }
+    }
+    input::write_atomic(&job.output, &cursor.encode()?)?;
+    outcome
+        .report
+        .push(format!("wrote {}", job.output.display()));
+    Ok(outcome)
+}
@@ -0,0 +1,194 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Args, Subcommand, ValueEnum, value_parser};
+use cursor_handler::{
+    AniFile, HotspotSpec,
+    sheet::{SheetDirection, SheetManifest, SheetOptions},
+};
+use image::RgbaImage;
+
+use crate::{
+    args::{parse_hotspot, parse_rate},
+    input::{self, CursorInput},
+};
+
+#[derive(Args)]
+pub struct StripArgs {
+    #[command(subcommand)]
+    command: Strip,
+}
+
+#[derive(Subcommand)]
+enum Strip {
+    /// Lay the frames of an .ani out on a sprite sheet with a JSON manifest
+    Export(ExportArgs),
+    /// Build an .ani from a sprite sheet and its manifest, or a plain strip
+    Import(ImportArgs),
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    /// The .ani to export
+    input: PathBuf,
+
+    /// Sheet image; the format follows the extension
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Manifest path (defaults to the sheet with a .json extension)
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+
+    /// Columns of the grid (defaults to a single row or column)
+    #[arg(long, value_parser = value_parser!(u32).range(1..))]
+    columns: Option<u32>,
+
+    /// Render every frame at this square size (defaults to each frame's
+    /// largest image)
+    #[arg(long, value_name = "PIXELS", value_parser = value_parser!(u32).range(1..=256))]
+    cell_size: Option<u32>,
+
+    /// The order cells fill the sheet in
+    #[arg(long, value_enum, default_value_t = Direction::Horizontal)]
+    direction: Direction,
+
+    /// Read the written files back and check they play the same steps
+    #[arg(long)]
+    verify: bool,
+}
+
+#[derive(Args)]
+struct ImportArgs {
+    /// The sheet image
+    sheet: PathBuf,
+
+    /// Manifest written by `strip export`
+    #[arg(long, conflicts_with = "frames")]
+    manifest: Option<PathBuf>,
+
+    /// Without a manifest, the number of square frames in a single row or
+    /// column
+    #[arg(long, required_unless_present = "manifest", value_parser = value_parser!(u32).range(1..))]
+    frames: Option<u32>,
+
+    /// Without a manifest, the duration of each frame: 100ms, 0.5s, 6j or
+    /// jiffies
+    #[arg(long, default_value = "100ms", value_parser = parse_rate)]
+    rate: u32,
+
+    /// Without a manifest, the hotspot as X,Y, center or top-left
+    #[arg(long, default_value = "top-left", value_parser = parse_hotspot)]
+    hotspot: HotspotSpec,
+
+    /// Without a manifest, whether the frames run across or down
+    #[arg(long, value_enum, default_value_t = Direction::Horizontal)]
+    direction: Direction,
+
+    /// Output .ani path
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Direction {
+    /// Left to right, then down a row
+    Horizontal,
+    /// Top to bottom, then across a column
+    Vertical,
+}
+
+impl From<Direction> for SheetDirection {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Horizontal => Self::Horizontal,
+            Direction::Vertical => Self::Vertical,
+        }
+    }
+}
+
+pub fn run(args: StripArgs) -> io::Result<ExitCode> {
+    match args.command {
+        Strip::Export(args) => export(args),
+        Strip::Import(args) => import(args),
+    }
+}
+
+fn export(args: ExportArgs) -> io::Result<ExitCode> {
+    let (CursorInput::Ani(ani), _) = CursorInput::read(&args.input)? else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not an .ani", args.input.display()),
+        ));
+    };
+    let options = SheetOptions {
+        columns: args.columns,
+        cell_size: args.cell_size,
+        direction: args.direction.into(),
+    };
+    let manifest_path = args
+        .manifest
+        .unwrap_or_else(|| args.output.with_extension("json"));
+
+    let (sheet, manifest) = ani.to_sheet(&options)?;
+    sheet.save(&args.output).map_err(io::Error::other)?;
+    fs::write(&manifest_path, manifest.to_json())?;
+    println!(
+        "wrote {}: {} frame(s) in {}x{} cells, {} column(s)",
+        args.output.display(),
+        manifest.frames.len(),
+        manifest.cell.0,
+        manifest.cell.1,
+        manifest.columns
+    );
+    println!("wrote {}", manifest_path.display());
+
+    if args.verify {
+        let manifest = SheetManifest::parse(&fs::read_to_string(&manifest_path)?)?;
+        let rebuilt = AniFile::from_sheet(&read_sheet(&args.output)?, &manifest)?;
+        ani.check_sheet(&options, &rebuilt)
+            .map_err(|err| io::Error::new(err.kind(), format!("verify failed: {err}")))?;
+        println!("verified: {} step(s) play the same", rebuilt.num_steps());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn import(args: ImportArgs) -> io::Result<ExitCode> {
+    let sheet = read_sheet(&args.sheet)?;
+    let manifest = match (&args.manifest, args.frames) {
+        (Some(path), _) => SheetManifest::parse(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))?,
+        (None, Some(frames)) => SheetManifest::strip(
+            sheet.dimensions(),
+            frames,
+            args.direction.into(),
+            args.rate,
+            args.hotspot,
+        )?,
+        (None, None) => unreachable!("clap requires --frames without --manifest"),
+    };
+
+    let ani = AniFile::from_sheet(&sheet, &manifest)?;
+    input::write_atomic(&args.output, &CursorInput::Ani(ani).encode()?)?;
+    println!(
+        "wrote {}: {} frame(s) of {}x{}",
+        args.output.display(),
+        manifest.frames.len(),
+        manifest.cell.0,
+        manifest.cell.1
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn read_sheet(path: &Path) -> io::Result<RgbaImage> {
+    let sheet = image::open(path).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {err}", path.display()),
+        )
+    })?;
+    Ok(sheet.into_rgba8())
+}
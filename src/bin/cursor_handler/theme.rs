@@ -0,0 +1,60 @@
+use std::{io, path::PathBuf, process::ExitCode};
+
+use clap::{Args, ValueEnum};
+use cursor_handler::{
+    convert::{self, ConvertOptions},
+    scheme::SchemeConfig,
+};
+
+#[derive(Args)]
+pub struct ThemeArgs {
+    /// Scheme config mapping roles to sources (TOML)
+    config: PathBuf,
+
+    /// Directory for the cursors and install.inf (defaults to the config's
+    /// directory)
+    #[arg(short, long)]
+    out_dir: Option<PathBuf>,
+
+    /// Also convert the scheme for another platform, into a subdirectory of
+    /// the output
+    #[arg(long, value_enum)]
+    also: Vec<Also>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Also {
+    /// An Xcursor theme in `xcursor/<scheme name>`
+    Xcursor,
+}
+
+pub fn run(args: ThemeArgs) -> io::Result<ExitCode> {
+    let out_dir = match args.out_dir {
+        Some(out_dir) => out_dir,
+        None => args.config.parent().map(PathBuf::from).unwrap_or_default(),
+    };
+    let config = SchemeConfig::load(&args.config)?;
+
+    let report = config.build(&out_dir)?;
+    for warning in &report.warnings {
+        eprintln!("warning: {warning}");
+    }
+    for output in &report.outputs {
+        println!("wrote {}", output.display());
+    }
+    println!("wrote {}", report.inf.display());
+
+    if args.also.contains(&Also::Xcursor) {
+        let theme = convert::windows_theme_to_xcursor(
+            &out_dir,
+            Some(&report.inf),
+            out_dir.join("xcursor"),
+            &ConvertOptions::default(),
+        )?;
+        for skipped in &theme.unmapped {
+            eprintln!("warning: xcursor: {skipped}");
+        }
+        println!("wrote {}", theme.theme_dir.display());
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,126 @@
+use std::{fs, io, path::PathBuf, process::ExitCode};
+
+use clap::Args;
+use cursor_handler::{AniFile, CursorFile, Issue, Severity};
+
+use crate::{
+    batch::{BatchArgs, FileOutcome, Job, Status},
+    input::{self, CursorInput},
+    output::{Failure, FileDocument, OutputArgs},
+};
+
+#[derive(Args)]
+pub struct ValidateArgs {
+    /// .cur or .ani files, directories or glob patterns to check
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Options for many files; with --out-dir, the files that pass are
+    /// copied there
+    #[command(flatten)]
+    batch: BatchArgs,
+
+    /// Ignore an issue code (e.g. ANI011); may be repeated
+    #[arg(long, value_name = "CODE")]
+    allow: Vec<String>,
+
+    /// Fail on warnings as well as errors
+    #[arg(long)]
+    deny_warnings: bool,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// `{"path": ..., "issues": [...]}`, without allowed issues
+#[derive(serde::Serialize)]
+struct ValidateReport {
+    issues: Vec<Issue>,
+}
+
+pub fn run(args: ValidateArgs) -> io::Result<ExitCode> {
+    let json = args.output.json();
+    let threshold = if args.deny_warnings {
+        Severity::Warning
+    } else {
+        Severity::Error
+    };
+
+    let jobs = args.batch.jobs(&args.inputs, None)?;
+    args.batch
+        .run(jobs, |job| validate_job(job, &args, json, threshold))
+}
+
+fn validate_job(
+    job: &Job,
+    args: &ValidateArgs,
+    json: bool,
+    threshold: Severity,
+) -> io::Result<FileOutcome> {
+    let path = &job.input;
+    let bytes = fs::read(path);
+    let issues = match &bytes {
+        Ok(bytes) => validate_file(bytes).map_err(Failure::decode),
+        Err(err) => Err(Failure::read(err)),
+    }
+    .map(|issues| {
+        issues
+            .into_iter()
+            .filter(|issue| {
+                !args
+                    .allow
+                    .iter()
+                    .any(|code| code.eq_ignore_ascii_case(issue.code))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let status = match &issues {
+        Ok(issues) if issues.iter().any(|issue| issue.severity >= threshold) => Status::Fail,
+        Ok(issues) if issues.is_empty() => Status::Ok,
+        Ok(_) => Status::Warn,
+        Err(_) => Status::Fail,
+    };
+    if let Ok(bytes) = &bytes
+        && status != Status::Fail
+        && job.output != job.input
+    {
+        input::write_atomic(&job.output, bytes)?;
+    }
+    let mut outcome = FileOutcome {
+        status,
+        ..FileOutcome::default()
+    };
+
+    if json {
+        let report = issues.map(|issues| ValidateReport { issues });
+        outcome
+            .report
+            .push(args.output.render(&FileDocument::new(path, report))?);
+        return Ok(outcome);
+    }
+    match issues {
+        Ok(issues) => {
+            for issue in issues {
+                outcome.report.push(format!("{}: {issue}", path.display()));
+            }
+        }
+        Err(failure) => outcome
+            .report
+            .push(format!("{}: error: {failure}", path.display())),
+    }
+    if let Ok((_, stats)) = bytes.and_then(|bytes| CursorInput::decode_with_stats(&bytes)) {
+        outcome
+            .report
+            .push(format!("{}: decoded {stats}", path.display()));
+    }
+    Ok(outcome)
+}
+
+fn validate_file(bytes: &[u8]) -> io::Result<Vec<Issue>> {
+    if bytes.starts_with(b"RIFF") {
+        AniFile::validate_bytes(bytes)
+    } else {
+        CursorFile::validate_bytes(bytes)
+    }
+}
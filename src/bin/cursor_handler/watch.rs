@@ -0,0 +1,148 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+use cursor_handler::project::{CursorEntry, CursorProject};
+use notify::{EventKind, RecursiveMode, Watcher};
+
+/// How long events must stop arriving before a rebuild; editors often save
+/// in several writes
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+enum Message {
+    Changed(Vec<PathBuf>),
+    Stop,
+}
+
+/// Build the project, then rebuild the cursors whose sources change until
+/// Ctrl-C
+///
+/// Editing the project file reloads it and rebuilds everything. A failed
+/// build is reported and the watch carries on.
+pub fn run(project_path: &Path, out: &Path) -> io::Result<ExitCode> {
+    let (sender, receiver) = mpsc::channel();
+    let stop = sender.clone();
+    ctrlc::set_handler(move || {
+        let _ = stop.send(Message::Stop);
+    })
+    .map_err(io::Error::other)?;
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        // Reads, including our own, are not changes
+        if let Ok(event) = event
+            && !matches!(event.kind, EventKind::Access(_))
+        {
+            let _ = sender.send(Message::Changed(event.paths));
+        }
+    })
+    .map_err(io::Error::other)?;
+
+    let project_key = watch_key(project_path);
+    let mut project = CursorProject::default();
+    let mut watched = HashSet::new();
+    let mut reload = true;
+    loop {
+        if reload {
+            match CursorProject::load(project_path) {
+                Ok(loaded) => project = loaded,
+                Err(err) => println!("failed to load the project: {err}"),
+            }
+            let mut dirs: HashSet<PathBuf> = project
+                .cursors
+                .iter()
+                .map(|entry| watch_dir(&entry.source))
+                .collect();
+            dirs.insert(watch_dir(project_path));
+            for dir in watched.difference(&dirs) {
+                let _ = watcher.unwatch(dir);
+            }
+            for dir in dirs.difference(&watched) {
+                if let Err(err) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                    println!("can't watch {}: {err}", dir.display());
+                }
+            }
+            watched = dirs;
+            for entry in &project.cursors {
+                build(entry, out);
+            }
+            println!("watching for changes, Ctrl-C to stop");
+        }
+
+        let Some(changed) = next_changes(&receiver) else {
+            return Ok(ExitCode::SUCCESS);
+        };
+        reload = changed.contains(&project_key);
+        if !reload {
+            for entry in &project.cursors {
+                let source = watch_key(&entry.source);
+                if changed
+                    .iter()
+                    .any(|path| *path == source || path.parent() == Some(&source))
+                {
+                    build(entry, out);
+                }
+            }
+        }
+    }
+}
+
+/// The paths changed in the next burst of events, once they settle; `None`
+/// on Ctrl-C
+fn next_changes(receiver: &mpsc::Receiver<Message>) -> Option<BTreeSet<PathBuf>> {
+    let mut changed = BTreeSet::new();
+    let mut message = receiver.recv().ok()?;
+    loop {
+        match message {
+            Message::Changed(paths) => changed.extend(paths),
+            Message::Stop => return None,
+        }
+        message = match receiver.recv_timeout(DEBOUNCE) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => return Some(changed),
+            Err(RecvTimeoutError::Disconnected) => return None,
+        };
+    }
+}
+
+fn build(entry: &CursorEntry, out: &Path) {
+    let start = Instant::now();
+    match entry.build(out) {
+        Ok(report) => {
+            for warning in &report.warnings {
+                println!("warning: {warning}");
+            }
+            println!("built {} in {} ms", entry.name, start.elapsed().as_millis());
+        }
+        Err(err) => println!("failed {}: {err}", entry.name),
+    }
+}
+
+/// The directory to watch for changes to `source`: its parent for a file,
+/// since editors often replace files rather than write them in place
+fn watch_dir(source: &Path) -> PathBuf {
+    let key = watch_key(source);
+    if source.is_dir() {
+        key
+    } else {
+        key.parent().map(PathBuf::from).unwrap_or(key)
+    }
+}
+
+/// `path` as event paths name it: under its parent's canonical path
+fn watch_key(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        return fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match (fs::canonicalize(parent), path.file_name()) {
+        (Ok(parent), Some(name)) => parent.join(name),
+        _ => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,76 @@
+// Writes the synthetic test fixtures and their SHA-256 manifest, or checks
+// the committed ones against a fresh build
+//
+//   gen-fixtures [--check] [DIR]    DIR defaults to tests/fixtures
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::Parser;
+use cursor_handler::test_utils::{FIXTURE_DIR, FIXTURE_MANIFEST, fixtures, manifest};
+
+/// Regenerate the synthetic .cur/.ani fixtures from code
+#[derive(Parser)]
+#[command(name = "gen-fixtures", version, about)]
+struct Args {
+    /// Compare the files in DIR with a fresh build instead of writing them
+    #[arg(long)]
+    check: bool,
+
+    /// Where the fixtures live
+    #[arg(default_value = FIXTURE_DIR)]
+    dir: PathBuf,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("gen-fixtures: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Whether the fixtures in `args.dir` are (now) current
+fn run(args: &Args) -> std::io::Result<bool> {
+    let fixtures = fixtures()?;
+    let mut files: Vec<(&str, Vec<u8>)> = fixtures.clone();
+    files.push((FIXTURE_MANIFEST, manifest(&fixtures).into_bytes()));
+
+    if !args.check {
+        for (path, bytes) in &files {
+            let path = args.dir.join(path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, bytes)?;
+        }
+        println!(
+            "Wrote {} fixtures to {}",
+            fixtures.len(),
+            args.dir.display()
+        );
+        return Ok(true);
+    }
+
+    let mut current = true;
+    for (path, bytes) in &files {
+        if !matches(&args.dir.join(path), bytes) {
+            println!("{path}: differs from a fresh build");
+            current = false;
+        }
+    }
+    if current {
+        println!("{} fixtures are current", fixtures.len());
+    }
+    Ok(current)
+}
+
+fn matches(path: &Path, bytes: &[u8]) -> bool {
+    fs::read(path).is_ok_and(|existing| existing == bytes)
+}
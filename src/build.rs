@@ -0,0 +1,126 @@
+// Build-script helpers for embedding cursors
+//
+// In the dependent crate's build.rs:
+//
+//   cursor_handler::build::validate_and_copy("cursors", std::env::var("OUT_DIR").unwrap())?;
+//
+// and in its source:
+//
+//   include!(concat!(env!("OUT_DIR"), "/cursors.rs"));
+//   let arrow = cursors::ARROW.decode_static()?;
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+};
+
+use crate::{ani::AniFile, cur::CursorFile};
+
+/// Validate every .cur and .ani in `src_dir`, copy them to `out_dir` and
+/// generate `out_dir/cursors.rs` with one `CursorBytes` static per file
+///
+/// Statics are named after the uppercased file stem. Any file that fails to
+/// decode is reported by path, so the calling build script can fail the build.
+/// Returns the path of the generated module.
+pub fn validate_and_copy(
+    src_dir: impl AsRef<Path>,
+    out_dir: impl AsRef<Path>,
+) -> io::Result<PathBuf> {
+    let src_dir = src_dir.as_ref();
+    let out_dir = out_dir.as_ref();
+    println!("cargo:rerun-if-changed={}", src_dir.display());
+
+    // Sorted, so a clash is reported the same way on every file system
+    let mut paths = fs::read_dir(src_dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.sort();
+
+    let mut statics: BTreeMap<String, (PathBuf, PathBuf)> = BTreeMap::new();
+    for path in paths {
+        let Some(extension) = path.extension().and_then(|ext| ext.to_str()) else {
+            continue;
+        };
+        let extension = extension.to_ascii_lowercase();
+        if extension != "cur" && extension != "ani" {
+            continue;
+        }
+        println!("cargo:rerun-if-changed={}", path.display());
+
+        let bytes = fs::read(&path)?;
+        validate(&bytes, &extension).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("invalid cursor {}: {err}", path.display()),
+            )
+        })?;
+
+        let file_name = path.file_name().unwrap_or_default();
+        let name = static_name(&path);
+        if let Some((taken, _)) = statics.get(&name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} and {} would both be the static {name}",
+                    taken.display(),
+                    path.display()
+                ),
+            ));
+        }
+
+        let copy = out_dir.join(file_name);
+        fs::write(&copy, &bytes)?;
+        statics.insert(name, (path, copy));
+    }
+
+    let mut module = String::from("pub mod cursors {\n");
+    for (name, (_, path)) in &statics {
+        let _ = writeln!(
+            module,
+            "    pub static {name}: ::cursor_handler::embed::CursorBytes =\n        \
+             ::cursor_handler::embed::CursorBytes::new(include_bytes!({:?}));",
+            path.display().to_string()
+        );
+    }
+    module.push_str("}\n");
+
+    let module_path = out_dir.join("cursors.rs");
+    fs::write(&module_path, module)?;
+    Ok(module_path)
+}
+
+/// Decode the container and every frame's pixels
+fn validate(bytes: &[u8], extension: &str) -> io::Result<()> {
+    if extension == "ani" {
+        let ani = AniFile::decode(Cursor::new(bytes))?;
+        for frame in &ani.frames {
+            frame.decode_image()?;
+        }
+    } else {
+        let cursor = CursorFile::decode(Cursor::new(bytes))?;
+        for frame in &cursor.frames {
+            frame.decode_image()?;
+        }
+    }
+    Ok(())
+}
+
+fn static_name(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
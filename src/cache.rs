@@ -0,0 +1,352 @@
+// Shared cache of decoded cursors for programs that load the same files from
+// several threads
+//
+// Entries are keyed by content fingerprint, and a path index remembers which
+// fingerprint each file had at which modification time. A file whose mtime
+// changed is read again; one whose content matches a cached entry shares it.
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::SystemTime,
+};
+
+use image::RgbaImage;
+
+use crate::{
+    ani::AniFile, cur::CursorFile, file::CursorRef, fingerprint::Fingerprint, hotspot::Hotspot,
+};
+
+/// Entries `CursorCache::default` holds before evicting
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// A cursor converted to RGBA, ready to hand to a compositor
+#[derive(Debug, Clone)]
+pub struct DecodedCursor {
+    /// Every resolution of a .cur, or the largest resolution of each stored
+    /// frame of an .ani
+    pub frames: Vec<DecodedFrame>,
+    /// Playback steps of an .ani, empty for a .cur
+    pub steps: Vec<DecodedStep>,
+    pub fingerprint: Fingerprint,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodedFrame {
+    pub image: RgbaImage,
+    pub hotspot: Hotspot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodedStep {
+    /// Index into `DecodedCursor::frames`
+    pub frame: usize,
+    /// Duration in jiffies (1/60 s)
+    pub duration: u32,
+}
+
+impl DecodedCursor {
+    /// Decode a .cur or .ani file and convert every frame to RGBA
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let (mut cur, mut ani) = (None, None);
+        let file = parse(bytes, &mut cur, &mut ani)?;
+        Self::convert(file, fingerprint(file))
+    }
+
+    pub fn from_cur(cursor: &CursorFile) -> io::Result<Self> {
+        Self::convert(cursor.into(), cursor.fingerprint())
+    }
+
+    pub fn from_ani(ani: &AniFile) -> io::Result<Self> {
+        Self::convert(ani.into(), ani.fingerprint())
+    }
+
+    fn convert(file: CursorRef<'_>, fingerprint: Fingerprint) -> io::Result<Self> {
+        let (frames, steps) = match file {
+            CursorRef::Cur(cursor) => {
+                let frames = cursor
+                    .frames()
+                    .map(|frame| {
+                        Ok(DecodedFrame {
+                            image: frame.decode_image()?,
+                            hotspot: frame.hotspot,
+                        })
+                    })
+                    .collect::<io::Result<_>>()?;
+                (frames, Vec::new())
+            }
+            CursorRef::Ani(ani) => {
+                let mut frames = Vec::with_capacity(ani.num_frames() as usize);
+                for index in 0..ani.num_frames() as usize {
+                    let cursor = ani.extract_frame(index)?;
+                    let frame = cursor
+                        .frames()
+                        .max_by_key(|frame| frame.size.max_side())
+                        .ok_or_else(|| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("Frame {index} is empty"),
+                            )
+                        })?;
+                    frames.push(DecodedFrame {
+                        image: frame.decode_image()?,
+                        hotspot: frame.hotspot,
+                    });
+                }
+                let steps = ani
+                    .steps()
+                    .map(|step| DecodedStep {
+                        frame: step.frame_index,
+                        duration: step.duration,
+                    })
+                    .collect();
+                (frames, steps)
+            }
+        };
+        Ok(Self {
+            frames,
+            steps,
+            fingerprint,
+        })
+    }
+}
+
+/// Decode `bytes` into whichever of `cur` and `ani` matches the file kind
+fn parse<'a>(
+    bytes: &[u8],
+    cur: &'a mut Option<CursorFile>,
+    ani: &'a mut Option<AniFile>,
+) -> io::Result<CursorRef<'a>> {
+    Ok(if bytes.starts_with(b"RIFF") {
+        CursorRef::Ani(ani.insert(AniFile::decode(Cursor::new(bytes))?))
+    } else {
+        CursorRef::Cur(cur.insert(CursorFile::decode(Cursor::new(bytes))?))
+    })
+}
+
+fn fingerprint(file: CursorRef<'_>) -> Fingerprint {
+    match file {
+        CursorRef::Cur(cursor) => cursor.fingerprint(),
+        CursorRef::Ani(ani) => ani.fingerprint(),
+    }
+}
+
+/// A thread-safe, size-bounded cache of `DecodedCursor`s
+///
+/// Lookups take a shared lock, so hits from many threads don't wait on each
+/// other. Once more than `capacity` distinct cursors are cached, the least
+/// recently used one is evicted.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use cursor_handler::cache::CursorCache;
+///
+/// let cache = CursorCache::new(1);
+/// let busy = cache.get_or_load("assets/aero_busy.ani")?;
+/// assert_eq!((busy.frames.len(), busy.steps.len()), (18, 18));
+/// assert!(Arc::ptr_eq(&busy, &cache.get_or_load("assets/aero_busy.ani")?));
+///
+/// // Loading a second cursor evicts the first
+/// let link = cache.get_or_load("assets/windows_theme/Link.cur")?;
+/// assert!(link.steps.is_empty());
+/// assert_eq!(cache.len(), 1);
+/// assert!(!Arc::ptr_eq(&busy, &cache.get_or_load("assets/aero_busy.ani")?));
+///
+/// // Threads racing for the same file all get one shared entry
+/// let cache = CursorCache::default();
+/// let loaded: Vec<_> = std::thread::scope(|scope| {
+///     let threads: Vec<_> = (0..4)
+///         .map(|_| scope.spawn(|| cache.get_or_load("assets/aero_busy.ani")))
+///         .collect();
+///     threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+/// });
+/// let first = loaded[0].as_ref().unwrap();
+/// assert!(loaded.iter().all(|cursor| Arc::ptr_eq(first, cursor.as_ref().unwrap())));
+/// assert_eq!(cache.len(), 1);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct CursorCache {
+    capacity: usize,
+    /// Source of the recency stamps on entries
+    clock: AtomicU64,
+    state: RwLock<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<Fingerprint, Entry>,
+    /// What each loaded file held when last read
+    paths: HashMap<PathBuf, (Option<SystemTime>, Fingerprint)>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    cursor: Arc<DecodedCursor>,
+    last_used: AtomicU64,
+}
+
+impl Default for CursorCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl CursorCache {
+    /// A cache holding up to `capacity` cursors (at least one)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            clock: AtomicU64::new(0),
+            state: RwLock::new(State::default()),
+        }
+    }
+
+    /// The cursor at `path`, decoded once and shared until evicted or the
+    /// file's modification time changes
+    ///
+    /// ```
+    /// use std::{fs::File, sync::Arc, time::{Duration, SystemTime}};
+    ///
+    /// use cursor_handler::cache::CursorCache;
+    ///
+    /// let path = std::env::temp_dir().join("cursor_handler_cache_doc.cur");
+    /// std::fs::copy("assets/windows_theme/Link.cur", &path)?;
+    /// let cache = CursorCache::default();
+    /// let before = cache.get_or_load(&path)?;
+    ///
+    /// // Rewriting the file with a new mtime is noticed on the next lookup
+    /// let ani = std::fs::read("assets/aero_busy.ani")?;
+    /// std::fs::write(&path, ani)?;
+    /// File::options()
+    ///     .write(true)
+    ///     .open(&path)?
+    ///     .set_modified(SystemTime::now() + Duration::from_secs(10))?;
+    /// let after = cache.get_or_load(&path)?;
+    /// assert!(!Arc::ptr_eq(&before, &after));
+    /// assert_ne!(before.fingerprint, after.fingerprint);
+    /// assert_eq!(after.steps.len(), 18);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn get_or_load(&self, path: impl AsRef<Path>) -> io::Result<Arc<DecodedCursor>> {
+        let path = path.as_ref();
+        let modified = fs::metadata(path)?.modified().ok();
+
+        {
+            let state = self.read();
+            if let Some((seen, fingerprint)) = state.paths.get(path)
+                && *seen == modified
+                && modified.is_some()
+                && let Some(entry) = state.entries.get(fingerprint)
+            {
+                return Ok(self.touch(entry));
+            }
+        }
+
+        // Decode without holding the lock. Content already cached under
+        // another path or mtime skips the RGBA conversion, and a racing
+        // thread's result wins if it got there first.
+        let bytes = fs::read(path)?;
+        let (mut cur, mut ani) = (None, None);
+        let file = parse(&bytes, &mut cur, &mut ani)?;
+        let fingerprint = fingerprint(file);
+        let cached = self
+            .read()
+            .entries
+            .get(&fingerprint)
+            .map(|entry| self.touch(entry));
+        let decoded = match cached {
+            Some(cursor) => cursor,
+            None => Arc::new(DecodedCursor::convert(file, fingerprint)?),
+        };
+
+        let mut state = self.write();
+        state
+            .paths
+            .insert(path.to_path_buf(), (modified, fingerprint));
+        if let Some(entry) = state.entries.get(&fingerprint) {
+            return Ok(self.touch(entry));
+        }
+        let cursor = decoded;
+        state.entries.insert(
+            fingerprint,
+            Entry {
+                cursor: cursor.clone(),
+                last_used: AtomicU64::new(self.tick()),
+            },
+        );
+        while state.entries.len() > self.capacity {
+            state.evict_oldest();
+        }
+        Ok(cursor)
+    }
+
+    /// Forget what was loaded from `path`, so the next lookup reads it again
+    pub fn invalidate(&self, path: impl AsRef<Path>) {
+        let mut state = self.write();
+        if let Some((_, fingerprint)) = state.paths.remove(path.as_ref()) {
+            state.entries.remove(&fingerprint);
+            state.paths.retain(|_, (_, other)| *other != fingerprint);
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.write();
+        state.entries.clear();
+        state.paths.clear();
+    }
+
+    /// Number of distinct cursors cached
+    pub fn len(&self) -> usize {
+        self.read().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn touch(&self, entry: &Entry) -> Arc<DecodedCursor> {
+        entry.last_used.store(self.tick(), Ordering::Relaxed);
+        entry.cursor.clone()
+    }
+
+    // A panic while holding the lock leaves the maps consistent, so a
+    // poisoned lock is still usable
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, State> {
+        self.state.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, State> {
+        self.state.write().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl State {
+    fn evict_oldest(&mut self) {
+        let Some(oldest) = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+            .map(|(fingerprint, _)| *fingerprint)
+        else {
+            return;
+        };
+        self.entries.remove(&oldest);
+        self.paths
+            .retain(|_, (_, fingerprint)| *fingerprint != oldest);
+    }
+}
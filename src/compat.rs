@@ -0,0 +1,717 @@
+// Compatibility profiles for older Windows versions and remote clients
+//
+// `validate_for` reports what a profile would reject; `downgrade` rewrites the
+// cursor until it has nothing to report, listing each change it made.
+use std::{
+    cmp::Reverse,
+    io::{self, Cursor},
+};
+
+use image::{ImageFormat, RgbaImage};
+
+use crate::{
+    ani::{AniFile, AniFrame},
+    cur::{CursorFile, CursorFrame, payload_bit_count},
+    dib::encode_dib32,
+    manifest::PayloadKind,
+    merge::FrameOrder,
+    optimize::encode_png,
+    sanitize::Fix,
+    size::{FrameSize, MAX_DIR_SIZE},
+    transform::{fit_hotspot, fit_square},
+    validate::{Issue, Severity, chunk_padding},
+};
+
+/// What a target platform accepts, and how to encode for it
+///
+/// Pass a profile in `EncodeOptions::profile` and both encoders pick payloads,
+/// frame order and metadata from it; `validate_for` checks the same rules.
+/// Start from a preset and adjust it with the `with_` methods:
+///
+/// ```
+/// use cursor_handler::{FrameOrder, compat::CompatibilityProfile};
+///
+/// let kiosk = CompatibilityProfile::WINDOWS_7
+///     .with_max_side(Some(64))
+///     .with_frame_order(FrameOrder::Descending);
+/// assert_eq!(kiosk.png_threshold, Some(256));
+/// ```
+///
+/// One cursor encoded under each preset:
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::{AniFile, CursorFile, EncodeOptions, compat::CompatibilityProfile};
+///
+/// let ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+/// let mut cursor = ani.extract_frame(0)?;
+/// let encode = |cursor: &CursorFile, profile| -> std::io::Result<Vec<String>> {
+///     let options = EncodeOptions { profile: Some(profile), ..Default::default() };
+///     let mut bytes = Vec::new();
+///     cursor.encode_with(&mut bytes, &options)?;
+///     let encoded = CursorFile::decode(Cursor::new(bytes))?;
+///     assert!(encoded.validate_for(profile).is_empty());
+///     let frames = encoded.frames();
+///     Ok(frames.map(|frame| format!("{} {}", frame.size, frame.payload_format())).collect())
+/// };
+///
+/// // Stored as 64, 48 and 32px 32-bit DIBs
+/// assert_eq!(
+///     encode(&cursor, CompatibilityProfile::MODERN)?,
+///     ["64x64 PNG", "48x48 DIB-32", "32x32 DIB-32"]
+/// );
+/// assert_eq!(
+///     encode(&cursor, CompatibilityProfile::WINDOWS_7)?,
+///     ["32x32 DIB-32", "48x48 DIB-32", "64x64 DIB-32"]
+/// );
+///
+/// // Encoding never drops frames; `downgrade` does, and says so
+/// assert!(encode(&cursor, CompatibilityProfile::WINDOWS_XP).is_err());
+/// cursor.downgrade(CompatibilityProfile::WINDOWS_XP)?;
+/// assert_eq!(
+///     encode(&cursor, CompatibilityProfile::WINDOWS_XP)?,
+///     ["32x32 DIB-32", "48x48 DIB-32"]
+/// );
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompatibilityProfile {
+    /// Largest frame side, `None` for no limit
+    pub max_side: Option<u32>,
+    /// Frames at least this many pixels wide or tall are stored as PNG and
+    /// smaller ones as DIBs; `None` when PNG payloads can't be read at all.
+    /// Frames over 256px are PNG regardless.
+    pub png_threshold: Option<u32>,
+    /// Deepest payload accepted, in bits per pixel
+    pub max_bit_count: u32,
+    /// Most stored frames an animation may have, `None` for no limit
+    pub max_frames: Option<usize>,
+    /// Order of the resolutions in each .cur directory; some loaders take
+    /// the first entry whatever the display size
+    pub frame_order: FrameOrder,
+    /// Write the `LIST INFO` chunk of animations
+    pub write_info: bool,
+    /// Treat an odd-sized chunk without its pad byte as an error in
+    /// `validate_bytes_for`; encoding always pads
+    pub strict_padding: bool,
+}
+
+impl CompatibilityProfile {
+    /// Windows XP, wine and older RDP clients: DIB frames up to 48px, at
+    /// most 32 frames, smallest resolution first and no INFO chunk
+    pub const WINDOWS_XP: Self = Self {
+        max_side: Some(48),
+        png_threshold: None,
+        max_bit_count: 32,
+        max_frames: Some(32),
+        frame_order: FrameOrder::Ascending,
+        write_info: false,
+        strict_padding: true,
+    };
+
+    /// Windows Vista and 7: frames up to 256px, PNG at 256px as Windows'
+    /// own cursors do
+    pub const WINDOWS_7: Self = Self {
+        max_side: Some(256),
+        png_threshold: Some(256),
+        max_bit_count: 32,
+        max_frames: None,
+        frame_order: FrameOrder::Ascending,
+        write_info: true,
+        strict_padding: true,
+    };
+
+    /// Windows 10 and later: any size, PNG from 64px, frames kept in the
+    /// order given
+    pub const MODERN: Self = Self {
+        max_side: None,
+        png_threshold: Some(64),
+        max_bit_count: 32,
+        max_frames: None,
+        frame_order: FrameOrder::AsGiven,
+        write_info: true,
+        strict_padding: false,
+    };
+
+    pub const fn with_max_side(mut self, max_side: Option<u32>) -> Self {
+        self.max_side = max_side;
+        self
+    }
+
+    pub const fn with_png_threshold(mut self, png_threshold: Option<u32>) -> Self {
+        self.png_threshold = png_threshold;
+        self
+    }
+
+    pub const fn with_max_bit_count(mut self, max_bit_count: u32) -> Self {
+        self.max_bit_count = max_bit_count;
+        self
+    }
+
+    pub const fn with_max_frames(mut self, max_frames: Option<usize>) -> Self {
+        self.max_frames = max_frames;
+        self
+    }
+
+    pub const fn with_frame_order(mut self, frame_order: FrameOrder) -> Self {
+        self.frame_order = frame_order;
+        self
+    }
+
+    pub const fn with_write_info(mut self, write_info: bool) -> Self {
+        self.write_info = write_info;
+        self
+    }
+
+    pub const fn with_strict_padding(mut self, strict_padding: bool) -> Self {
+        self.strict_padding = strict_padding;
+        self
+    }
+
+    /// The payload encoding a frame of `size` gets
+    fn payload_for(&self, size: FrameSize) -> PayloadKind {
+        let side = size.max_side();
+        if side > MAX_DIR_SIZE
+            || self
+                .png_threshold
+                .is_some_and(|threshold| side >= threshold)
+        {
+            PayloadKind::Png
+        } else {
+            PayloadKind::Dib
+        }
+    }
+
+    /// Whether `frames` are in the profile's order
+    fn in_order(&self, frames: &[CursorFrame]) -> bool {
+        let sides = frames
+            .windows(2)
+            .map(|pair| (pair[0].size.max_side(), pair[1].size.max_side()));
+        match self.frame_order {
+            FrameOrder::Ascending => sides.clone().all(|(a, b)| a <= b),
+            FrameOrder::Descending => sides.clone().all(|(a, b)| a >= b),
+            FrameOrder::AsGiven => true,
+        }
+    }
+}
+
+/// What a `downgrade` pass changed, under the `validate_for` codes
+#[derive(Debug, Clone, Default)]
+pub struct DowngradeReport {
+    pub actions: Vec<Fix>,
+}
+
+impl DowngradeReport {
+    /// Whether the cursor already fit the profile
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    fn push(&mut self, code: &'static str, frame: Option<usize>, message: impl Into<String>) {
+        self.actions.push(Fix {
+            code,
+            frame,
+            message: message.into(),
+        });
+    }
+}
+
+impl CursorFile {
+    /// Check the cursor against `profile`
+    ///
+    /// Errors are what the target would reject: CMP001 a frame larger than
+    /// it allows, CMP002 a PNG payload it can't read, CMP003 a payload deeper
+    /// than it allows. Infos are what encoding under the profile would
+    /// change: CMP005 a payload of the other kind, CMP006 frames out of
+    /// order. A file written by `encode_with` under the profile has none.
+    pub fn validate_for(&self, profile: CompatibilityProfile) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        cursor_issues(&self.frames, None, &profile, &mut issues);
+        issues
+    }
+
+    /// The cursor `encode_with` writes under `profile`: payloads of the kind
+    /// the profile picks for their size, no deeper than it allows, in its
+    /// frame order
+    ///
+    /// Fails rather than drop or resize frames; see `downgrade` for that.
+    pub(crate) fn conform(&self, profile: &CompatibilityProfile) -> io::Result<CursorFile> {
+        if let Some(max) = profile.max_side
+            && let Some(frame) = self.frames.iter().find(|frame| frame.size.max_side() > max)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} frame is over the profile's {max}px limit; downgrade it first",
+                    frame.size
+                ),
+            ));
+        }
+
+        let mut frames = self.frames.clone();
+        for frame in &mut frames {
+            let deep = payload_bit_count(&frame.image_data) > profile.max_bit_count;
+            match profile.payload_for(frame.size) {
+                PayloadKind::Png if deep || !frame.is_png() => {
+                    frame.set_payload(encode_png(&decode_any(frame)?)?);
+                }
+                PayloadKind::Dib if deep || frame.is_png() => {
+                    frame.set_payload(encode_dib32(&decode_any(frame)?));
+                }
+                _ => {}
+            }
+        }
+        match profile.frame_order {
+            FrameOrder::Ascending => frames.sort_by_key(|frame| frame.size.max_side()),
+            FrameOrder::Descending => frames.sort_by_key(|frame| Reverse(frame.size.max_side())),
+            FrameOrder::AsGiven => {}
+        }
+        Ok(CursorFile::new(frames))
+    }
+
+    /// Rewrite the cursor to fit `profile`
+    ///
+    /// Of the frames larger than the profile allows, the largest is resized
+    /// to the largest allowed size unless a frame of that size exists, and
+    /// the rest are dropped. PNG payloads the profile can't read become
+    /// 32-bit DIBs, and payloads deeper than it allows are re-encoded at 8
+    /// bits per channel.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::test_utils::png_frame;
+    /// use cursor_handler::{
+    ///     CursorFile, CursorFrame, FrameSize, Hotspot, Severity, compat::CompatibilityProfile,
+    /// };
+    /// use image::{DynamicImage, ImageBuffer, ImageFormat, Rgba, RgbaImage};
+    ///
+    /// let hotspot = |side: u32| Hotspot::new(side as u16 / 4, side as u16 / 2);
+    /// let opaque = |side| RgbaImage::from_pixel(side, side, Rgba([9, 9, 9, 255]));
+    /// // 16 bits per channel, which only a PNG written by hand holds
+    /// let deep = ImageBuffer::from_pixel(32, 32, Rgba([900u16, 900, 900, 65535]));
+    /// let mut png = Vec::new();
+    /// DynamicImage::ImageRgba16(deep)
+    ///     .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+    ///     .map_err(std::io::Error::other)?;
+    /// let mut cursor = CursorFile::new(vec![
+    ///     png_frame(&opaque(256), hotspot(256))?,
+    ///     png_frame(&opaque(128), hotspot(128))?,
+    ///     CursorFrame::new(FrameSize::square(32)?, hotspot(32), png),
+    /// ]);
+    ///
+    /// let errors = |cursor: &CursorFile, profile| -> Vec<_> {
+    ///     let issues = cursor.validate_for(profile);
+    ///     let errors = issues.iter().filter(|issue| issue.severity == Severity::Error);
+    ///     errors.map(|issue| issue.code).collect()
+    /// };
+    /// assert_eq!(errors(&cursor, CompatibilityProfile::WINDOWS_7), ["CMP003"]);
+    /// assert_eq!(errors(&cursor, CompatibilityProfile::WINDOWS_XP).len(), 6);
+    ///
+    /// // 64 bits per pixel is re-encoded as 32-bit PNG
+    /// let report = cursor.clone().downgrade(CompatibilityProfile::WINDOWS_7)?;
+    /// assert_eq!(report.actions.len(), 1);
+    ///
+    /// // The 256px frame is resized to 48px, the 128px one dropped and
+    /// // every PNG becomes a DIB
+    /// let report = cursor.downgrade(CompatibilityProfile::WINDOWS_XP)?;
+    /// let actions: Vec<_> = report.actions.iter().map(|fix| fix.code).collect();
+    /// assert_eq!(actions, ["CMP001", "CMP001", "CMP002"]);
+    /// let sizes: Vec<_> = cursor.frames().map(|frame| frame.size.width()).collect();
+    /// assert_eq!(sizes, [48, 32]);
+    /// assert_eq!(cursor[0].hotspot, Hotspot::new(12, 24));
+    /// assert_eq!(cursor[0].payload_format(), "DIB-32");
+    /// assert!(errors(&cursor, CompatibilityProfile::WINDOWS_XP).is_empty());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn downgrade(&mut self, profile: CompatibilityProfile) -> io::Result<DowngradeReport> {
+        let mut report = DowngradeReport::default();
+        self.downgrade_into(profile, None, &mut report)?;
+        Ok(report)
+    }
+
+    /// `downgrade`, reporting actions against `ani_frame` when set
+    fn downgrade_into(
+        &mut self,
+        profile: CompatibilityProfile,
+        ani_frame: Option<usize>,
+        report: &mut DowngradeReport,
+    ) -> io::Result<()> {
+        let at = |index: usize| ani_frame.or(Some(index));
+
+        // Work on a copy so an error leaves the cursor as it was
+        let mut frames = self.frames.clone();
+        if let Some(max) = profile.max_side {
+            let has_max = frames.iter().any(|frame| frame.size.max_side() == max);
+            let largest = frames
+                .iter()
+                .enumerate()
+                .filter(|(_, frame)| frame.size.max_side() > max)
+                .max_by_key(|(_, frame)| frame.size.max_side())
+                .map(|(index, _)| index);
+
+            let mut kept = Vec::with_capacity(frames.len());
+            for (index, frame) in frames.into_iter().enumerate() {
+                if frame.size.max_side() <= max {
+                    kept.push(frame);
+                } else if Some(index) == largest && !has_max {
+                    let image = decode_any(&frame)?;
+                    let hotspot = fit_hotspot(frame.hotspot, image.dimensions(), max);
+                    let resized = CursorFrame::from_image(&fit_square(&image, max), hotspot)?;
+                    report.push(
+                        "CMP001",
+                        at(index),
+                        format!("resized {} to {}", frame.size, resized.size),
+                    );
+                    kept.push(resized);
+                } else {
+                    report.push(
+                        "CMP001",
+                        at(index),
+                        format!("dropped {}, over {max}px", frame.size),
+                    );
+                }
+            }
+            frames = kept;
+        }
+
+        for (index, frame) in frames.iter_mut().enumerate() {
+            let bit_count = payload_bit_count(&frame.image_data);
+            if frame.is_png() && profile.png_threshold.is_none() {
+                frame.set_payload(encode_dib32(&decode_any(frame)?));
+                report.push(
+                    "CMP002",
+                    at(index),
+                    "PNG payload transcoded to a 32-bit DIB",
+                );
+            } else if bit_count > profile.max_bit_count {
+                let image = decode_any(frame)?;
+                let image_data = if frame.is_png() {
+                    encode_png(&image)?
+                } else {
+                    encode_dib32(&image)
+                };
+                frame.set_payload(image_data);
+                report.push(
+                    "CMP003",
+                    at(index),
+                    format!("{bit_count}-bit payload re-encoded at 32 bits"),
+                );
+            }
+        }
+        self.frames = frames;
+        Ok(())
+    }
+}
+
+/// The frame's pixels, also for PNGs deeper than an .ico reader accepts
+fn decode_any(frame: &CursorFrame) -> io::Result<RgbaImage> {
+    match frame.png_data() {
+        Some(png) => image::load_from_memory_with_format(png, ImageFormat::Png)
+            .map(|image| image.to_rgba8())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+        None => frame.decode_image(),
+    }
+}
+
+/// `validate_for` for the frames of one cursor, or of one animation frame
+/// when `ani_frame` is set
+fn cursor_issues(
+    frames: &[CursorFrame],
+    ani_frame: Option<usize>,
+    profile: &CompatibilityProfile,
+    issues: &mut Vec<Issue>,
+) {
+    for (index, frame) in frames.iter().enumerate() {
+        let at = ani_frame.or(Some(index));
+        if let Some(max) = profile.max_side
+            && frame.size.max_side() > max
+        {
+            issues.push(Issue::new(
+                Severity::Error,
+                "CMP001",
+                at,
+                format!("{} is over the {max}px limit", frame.size),
+            ));
+        }
+        if frame.is_png() && profile.png_threshold.is_none() {
+            issues.push(Issue::new(
+                Severity::Error,
+                "CMP002",
+                at,
+                "PNG payloads are not supported",
+            ));
+        } else if frame.is_png() != (profile.payload_for(frame.size) == PayloadKind::Png) {
+            issues.push(Issue::new(
+                Severity::Info,
+                "CMP005",
+                at,
+                format!(
+                    "{} payload, the profile stores {} frames as {}",
+                    frame.payload_format(),
+                    frame.size,
+                    if frame.is_png() { "DIBs" } else { "PNG" }
+                ),
+            ));
+        }
+        let bit_count = payload_bit_count(&frame.image_data);
+        if bit_count > profile.max_bit_count {
+            issues.push(Issue::new(
+                Severity::Error,
+                "CMP003",
+                at,
+                format!(
+                    "{bit_count}-bit payload is over the {}-bit limit",
+                    profile.max_bit_count
+                ),
+            ));
+        }
+    }
+    if !profile.in_order(frames) {
+        issues.push(Issue::new(
+            Severity::Info,
+            "CMP006",
+            ani_frame,
+            format!("resolutions are not in {:?} order", profile.frame_order),
+        ));
+    }
+}
+
+impl AniFile {
+    /// Check the animation against `profile`: the `CursorFile::validate_for`
+    /// codes for every frame, CMP004 (error) more stored frames than it
+    /// allows and CMP007 (info) INFO metadata it leaves out
+    pub fn validate_for(&self, profile: CompatibilityProfile) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        if let Some(max) = profile.max_frames
+            && self.frames.len() > max
+        {
+            issues.push(Issue::new(
+                Severity::Error,
+                "CMP004",
+                None,
+                format!("{} frames is over the limit of {max}", self.frames.len()),
+            ));
+        }
+        if !profile.write_info && !self.info.is_empty() {
+            issues.push(Issue::new(
+                Severity::Info,
+                "CMP007",
+                None,
+                "INFO metadata is not written for this profile",
+            ));
+        }
+        for (index, frame) in self.frames.iter().enumerate() {
+            if let Ok(cursor) = CursorFile::decode(Cursor::new(&frame.image_data)) {
+                cursor_issues(&cursor.frames, Some(index), &profile, &mut issues);
+            }
+        }
+        issues
+    }
+
+    /// Decode `bytes` and `validate_for`, adding ANI011 for chunks missing
+    /// their pad byte, as an error when the profile's padding is strict
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, EncodeOptions, InfoEntry, Severity};
+    /// use cursor_handler::compat::CompatibilityProfile;
+    ///
+    /// let mut ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// ani.info.push(InfoEntry::new(*b"INAM", "Busy"));
+    /// let encode = |ani: &AniFile, profile| -> std::io::Result<Vec<u8>> {
+    ///     let options = EncodeOptions { profile: Some(profile), ..Default::default() };
+    ///     let mut bytes = Cursor::new(Vec::new());
+    ///     ani.encode_with(&mut bytes, &options)?;
+    ///     Ok(bytes.into_inner())
+    /// };
+    ///
+    /// // Windows 7 keeps the title, a profile without INFO drops it
+    /// let kept = encode(&ani, CompatibilityProfile::WINDOWS_7)?;
+    /// assert_eq!(AniFile::decode(Cursor::new(&kept))?.title().as_deref(), Some("Busy"));
+    /// let bare = CompatibilityProfile::WINDOWS_7.with_write_info(false);
+    /// let dropped = encode(&ani, bare)?;
+    /// assert_eq!(AniFile::decode(Cursor::new(&dropped))?.title(), None);
+    /// assert!(ani.validate_for(bare).iter().any(|issue| issue.code == "CMP007"));
+    ///
+    /// // An odd-sized chunk at the end without its pad byte
+    /// let mut unpadded = dropped.clone();
+    /// unpadded.extend_from_slice(b"junk\x01\0\0\0!");
+    /// let size = unpadded.len() as u32 - 8;
+    /// unpadded[4..8].copy_from_slice(&size.to_le_bytes());
+    /// let severity = |profile| -> std::io::Result<Vec<Severity>> {
+    ///     let issues = AniFile::validate_bytes_for(&unpadded, profile)?;
+    ///     let padding = issues.iter().filter(|issue| issue.code == "ANI011");
+    ///     Ok(padding.map(|issue| issue.severity).collect())
+    /// };
+    /// assert_eq!(severity(CompatibilityProfile::WINDOWS_7)?, [Severity::Error]);
+    /// assert_eq!(severity(CompatibilityProfile::MODERN)?, [Severity::Warning]);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn validate_bytes_for(
+        bytes: &[u8],
+        profile: CompatibilityProfile,
+    ) -> io::Result<Vec<Issue>> {
+        let mut issues = Self::decode(Cursor::new(bytes))?.validate_for(profile);
+        if let Some(body) = bytes.get(12..) {
+            let mut padding = Vec::new();
+            chunk_padding(body, 12, &mut padding);
+            if profile.strict_padding {
+                padding
+                    .iter_mut()
+                    .for_each(|issue| issue.severity = Severity::Error);
+            }
+            issues.extend(padding);
+        }
+        Ok(issues)
+    }
+
+    /// The animation `encode_with` writes under `profile`, every frame
+    /// conformed and the INFO chunk dropped if the profile leaves it out
+    pub(crate) fn conform(&self, profile: &CompatibilityProfile) -> io::Result<AniFile> {
+        if let Some(max) = profile.max_frames
+            && self.frames.len() > max
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} frames is over the profile's limit of {max}; downgrade it first",
+                    self.frames.len()
+                ),
+            ));
+        }
+
+        let mut ani = self.clone();
+        for frame in &mut ani.frames {
+            // Frames that aren't a well-formed .cur (e.g. ICO data) are left alone
+            if let Ok(cursor) = CursorFile::decode(Cursor::new(&frame.image_data)) {
+                replace_cursor(frame, &cursor.conform(profile)?)?;
+            }
+        }
+        if !profile.write_info {
+            ani.info.clear();
+        }
+        ani.id_variants.clear(); // Every profile gets the standard ids
+        ani.refresh_header();
+        Ok(ani)
+    }
+
+    /// Point the header at the first frame again if it described another
+    pub(crate) fn refresh_header(&mut self) {
+        if let Some((width, height, bit_count)) = self.header_disagreement() {
+            self.header.width = width;
+            self.header.height = height;
+            self.header.bit_count = bit_count;
+        }
+    }
+
+    /// Rewrite the animation to fit `profile`
+    ///
+    /// An animation with too many frames keeps an evenly spaced selection,
+    /// each shown for as long as the frames it replaces, so the cycle takes
+    /// as long as before. Every frame then gets `CursorFile::downgrade`. On
+    /// error the animation is left unchanged.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, compat::CompatibilityProfile, generate};
+    /// use image::{DynamicImage, Rgba, RgbaImage};
+    ///
+    /// let image = DynamicImage::from(RgbaImage::from_pixel(64, 64, Rgba([255, 0, 0, 255])));
+    /// let options = generate::GenerateOptions { steps: 60, hotspot: (0, 0).into(), rate: 2 };
+    /// let mut ani = generate::hue_cycle(&image, 6, &options)?;
+    /// let duration = |ani: &AniFile| ani.steps().map(|step| step.duration).sum::<u32>();
+    /// assert_eq!(duration(&ani), 120);
+    ///
+    /// let codes = |ani: &AniFile| -> Vec<_> {
+    ///     let issues = ani.validate_for(CompatibilityProfile::WINDOWS_XP);
+    ///     issues.iter().map(|issue| issue.code).collect()
+    /// };
+    /// assert_eq!(codes(&ani)[0], "CMP004");
+    /// assert!(ani.validate_for(CompatibilityProfile::MODERN).is_empty());
+    ///
+    /// let report = ani.downgrade(CompatibilityProfile::WINDOWS_XP)?;
+    /// assert_eq!(report.actions[0].code, "CMP004");
+    /// assert_eq!(ani.num_frames(), 32);
+    /// assert_eq!(duration(&ani), 120);
+    /// assert_eq!(ani.header.width(), 48);
+    /// assert!(codes(&ani).is_empty());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn downgrade(&mut self, profile: CompatibilityProfile) -> io::Result<DowngradeReport> {
+        let mut report = DowngradeReport::default();
+        let mut ani = self.clone();
+
+        if let Some(max) = profile.max_frames
+            && ani.frames.len() > max
+        {
+            let count = ani.frames.len();
+            ani.keep_spaced_frames(max);
+            report.push(
+                "CMP004",
+                None,
+                format!("kept {max} of {count} frames, merging their durations"),
+            );
+        }
+
+        for (index, frame) in ani.frames.iter_mut().enumerate() {
+            // Frames that aren't a well-formed .cur (e.g. ICO data) are left alone
+            let Ok(mut cursor) = CursorFile::decode(Cursor::new(&frame.image_data)) else {
+                continue;
+            };
+            let before = report.actions.len();
+            cursor.downgrade_into(profile, Some(index), &mut report)?;
+            if report.actions.len() == before {
+                continue;
+            }
+            replace_cursor(frame, &cursor)?;
+        }
+
+        ani.refresh_header();
+        *self = ani;
+        Ok(report)
+    }
+
+    /// Keep `count` evenly spaced frames, each step showing the nearest kept
+    /// frame at or before the one it showed, then merge repeated steps
+    fn keep_spaced_frames(&mut self, count: usize) {
+        let total = self.frames.len();
+        let kept: Vec<usize> = (0..count).map(|index| index * total / count).collect();
+        let remap = |frame: usize| (kept.partition_point(|&index| index <= frame) - 1) as u32;
+
+        let (sequence, rates) = self
+            .steps()
+            .map(|step| (remap(step.frame_index), step.duration))
+            .unzip();
+        self.sequence = sequence;
+        self.rates = rates;
+        for chunk in &mut self.unknown_chunks {
+            chunk.before_frame = kept.partition_point(|&index| index < chunk.before_frame);
+        }
+
+        let mut index = 0;
+        self.frames.retain(|_| {
+            index += 1;
+            kept.contains(&(index - 1))
+        });
+        self.header.num_frames = None;
+        self.header.num_steps = None;
+        self.coalesce_steps();
+    }
+}
+
+/// Store `cursor` as the frame's icon, taking the size and hotspot from its
+/// first entry as decoding does
+fn replace_cursor(frame: &mut AniFrame, cursor: &CursorFile) -> io::Result<()> {
+    let mut image_data = Vec::new();
+    cursor.encode(&mut image_data)?;
+    if let Some(first) = cursor.frames.first() {
+        frame.size = first.size;
+        frame.hotspot = first.hotspot;
+    }
+    frame.image_data = image_data;
+    Ok(())
+}
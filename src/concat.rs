@@ -0,0 +1,181 @@
+// Splicing one animation onto the end of another
+use std::io;
+
+use crate::{
+    ani::{AniFile, AniFrame},
+    generate::cursor_frame,
+    hotspot::Hotspot,
+    optimize::same_frame,
+    size::FrameSize,
+    transform::resize,
+};
+
+/// Options for `AniFile::concat_with`
+#[derive(Debug, Clone, Default)]
+pub struct ConcatOptions {
+    /// Scale frames of the other size to the first animation's frame size
+    /// instead of failing
+    pub resize: bool,
+}
+
+/// A concatenated animation with the metadata conflicts that were resolved
+#[derive(Debug, Clone)]
+pub struct Concatenated {
+    pub value: AniFile,
+    pub warnings: Vec<String>,
+}
+
+impl AniFile {
+    /// Play `other` after this animation, see `concat_with`
+    pub fn concat(&self, other: &AniFile) -> io::Result<AniFile> {
+        self.concat_with(other, &ConcatOptions::default())
+            .map(|concatenated| concatenated.value)
+    }
+
+    /// Play `other` after this animation
+    ///
+    /// Frames of `other` identical to one already present are shared. Every
+    /// step keeps its duration, writing a rate table if the two default rates
+    /// differ. All frames must be the size of this animation's first frame
+    /// unless `options.resize` is set. Header, loop behaviour and unknown
+    /// chunks come from `self`; INFO tags only `other` has are added, and
+    /// tags both have with different values keep this animation's, with a
+    /// warning.
+    ///
+    /// ```
+    /// use cursor_handler::{AniFile, AniFrame, Hotspot};
+    /// use image::{Rgba, RgbaImage};
+    ///
+    /// let frame = |shade: u8| {
+    ///     let image = RgbaImage::from_pixel(4, 4, Rgba([shade, 0, 0, 255]));
+    ///     AniFrame::from_image(&image, Hotspot::default())
+    /// };
+    ///
+    /// // A 3-step intro at the default rate and a 4-step loop with its own rates
+    /// let intro = AniFile::new(vec![frame(0)?, frame(1)?, frame(2)?]).with_default_rate(5)?;
+    /// let spin = AniFile::new(vec![frame(2)?, frame(3)?])
+    ///     .with_sequence(vec![0, 1, 0, 1])
+    ///     .with_rates(vec![1, 2, 3, 4]);
+    ///
+    /// let both = intro.concat(&spin)?;
+    /// let timings: Vec<_> = both.steps().map(|step| step.duration).collect();
+    /// assert_eq!(timings, [5, 5, 5, 1, 2, 3, 4]);
+    /// assert_eq!(both.num_frames(), 4); // The spin's first frame is the intro's last
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn concat_with(
+        &self,
+        other: &AniFile,
+        options: &ConcatOptions,
+    ) -> io::Result<Concatenated> {
+        let mut frames = self.frames.clone();
+        let target = frames
+            .first()
+            .or(other.frames.first())
+            .map(|frame| frame.size);
+        if let Some(target) = target
+            && !options.resize
+            && let Some(frame) = frames
+                .iter()
+                .chain(&other.frames)
+                .find(|f| f.size != target)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} frame does not match the {target} animation", frame.size),
+            ));
+        }
+
+        if let Some(target) = target {
+            for frame in &mut frames {
+                if frame.size != target {
+                    *frame = resized(frame, target)?;
+                }
+            }
+        }
+
+        let mut remap = Vec::with_capacity(other.frames.len());
+        for frame in &other.frames {
+            let frame = match target {
+                Some(target) if frame.size != target => resized(frame, target)?,
+                _ => frame.clone(),
+            };
+            match frames.iter().position(|kept| same_frame(kept, &frame)) {
+                Some(index) => remap.push(index as u32),
+                None => {
+                    remap.push(frames.len() as u32);
+                    frames.push(frame);
+                }
+            }
+        }
+        let mut sequence = Vec::new();
+        let mut rates = Vec::new();
+        for step in self.steps() {
+            sequence.push(step.frame_index as u32);
+            rates.push(step.duration);
+        }
+        for step in other.steps() {
+            sequence.push(remap[step.frame_index]);
+            rates.push(step.duration);
+        }
+        // The default rate covers every step, so no table is needed
+        if rates.iter().all(|&rate| rate == self.header.default_rate()) {
+            rates.clear();
+        }
+
+        let mut info = self.info.clone();
+        let mut warnings = Vec::new();
+        for entry in &other.info {
+            match info.iter().find(|kept| kept.id == entry.id) {
+                None => info.push(entry.clone()),
+                Some(kept) if kept.value != entry.value => warnings.push(format!(
+                    "keeping '{}' {:?} over {:?}",
+                    String::from_utf8_lossy(&entry.id),
+                    kept.text(),
+                    entry.text()
+                )),
+                Some(_) => {}
+            }
+        }
+
+        let mut header = self.header.clone();
+        header.num_frames = None;
+        header.num_steps = None;
+        if let Some(target) = target {
+            header.width = target.width();
+            header.height = target.height();
+        }
+
+        Ok(Concatenated {
+            value: AniFile {
+                header,
+                frames,
+                sequence,
+                rates,
+                info,
+                unknown_chunks: self.unknown_chunks.clone(),
+                loop_behavior: self.loop_behavior,
+                id_variants: Vec::new(),
+            },
+            warnings,
+        })
+    }
+}
+
+/// Scale a frame to `target`, moving its hotspot with the pixels
+pub(crate) fn resized(frame: &AniFrame, target: FrameSize) -> io::Result<AniFrame> {
+    let image = frame.decode_image()?;
+    let scaled = resize(&image, target.width(), target.height());
+
+    let factor_x = target.width() as f32 / frame.size.width() as f32;
+    let factor_y = target.height() as f32 / frame.size.height() as f32;
+    let hotspot = Hotspot::new(
+        frame.hotspot.scaled(factor_x).x,
+        frame.hotspot.scaled(factor_y).y,
+    )
+    .clamp_to(target.width(), target.height());
+
+    let mut resized = cursor_frame(&scaled, hotspot)?;
+    resized.duration = frame.duration;
+    Ok(resized)
+}
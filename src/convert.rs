@@ -0,0 +1,1094 @@
+// Converting whole cursor themes between Windows schemes and Xcursor themes
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    ani::AniFile,
+    compat::CompatibilityProfile,
+    cur::CursorFile,
+    encode::EncodeOptions,
+    progress::{NoProgress, Phase, Progress, Tracker},
+    xcursor::{self, WindowsCursor},
+};
+
+/// A Windows scheme role and where it goes in an Xcursor theme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Role {
+    /// Value name under `HKCU\Control Panel\Cursors`
+    pub name: &'static str,
+    /// Conventional file stems, lowercased with only letters and digits kept
+    pub file_stems: &'static [&'static str],
+    /// The X cursor name, `None` for roles X has no equivalent of
+    pub xcursor: Option<&'static str>,
+    /// Other names toolkits look the same cursor up by
+    pub aliases: &'static [&'static str],
+}
+
+/// Every role the conversions know, in scheme slot order
+///
+/// ```
+/// use cursor_handler::convert;
+///
+/// let arrow = convert::roles()[0];
+/// assert_eq!((arrow.name, arrow.xcursor), ("Arrow", Some("left_ptr")));
+/// assert_eq!(convert::roles().len(), 17);
+/// ```
+pub fn roles() -> &'static [Role] {
+    &ROLES
+}
+
+/// Every role, in the slot order of a scheme's `Schemes` registry value
+const ROLES: [Role; 17] = [
+    Role {
+        name: "Arrow",
+        file_stems: &["arrow", "normal", "normalselect", "pointer", "aeroarrow"],
+        xcursor: Some("left_ptr"),
+        aliases: &["default", "arrow", "top_left_arrow", "left_arrow"],
+    },
+    Role {
+        name: "Help",
+        file_stems: &["help", "helpselect", "aerohelpsel"],
+        xcursor: Some("question_arrow"),
+        aliases: &[
+            "help",
+            "whats_this",
+            "left_ptr_help",
+            "5c6cd98b3f3ebcb1f9c7f1c204630408",
+            "d9ce0ab605698f320427677b458ad60b",
+        ],
+    },
+    Role {
+        name: "AppStarting",
+        file_stems: &[
+            "appstarting",
+            "working",
+            "workinginbackground",
+            "aeroworking",
+        ],
+        xcursor: Some("left_ptr_watch"),
+        aliases: &[
+            "progress",
+            "half-busy",
+            "08e8e1c95fe2fc01f976f1e063a24ccd",
+            "3ecb610c1bf2410f44200f48c40d3599",
+        ],
+    },
+    Role {
+        name: "Wait",
+        file_stems: &["wait", "busy", "aerobusy"],
+        xcursor: Some("watch"),
+        aliases: &["wait"],
+    },
+    Role {
+        name: "Crosshair",
+        file_stems: &["crosshair", "cross", "precision", "precisionselect"],
+        xcursor: Some("crosshair"),
+        aliases: &["cross", "tcross", "cross_reverse"],
+    },
+    Role {
+        name: "IBeam",
+        file_stems: &["ibeam", "text", "textselect", "beam"],
+        xcursor: Some("xterm"),
+        aliases: &["text", "ibeam"],
+    },
+    Role {
+        name: "NWPen",
+        file_stems: &["nwpen", "handwriting", "pen", "aeropen"],
+        xcursor: Some("pencil"),
+        aliases: &[],
+    },
+    Role {
+        name: "No",
+        file_stems: &["no", "unavailable", "notallowed", "aerounavail"],
+        xcursor: Some("circle"),
+        aliases: &[
+            "not-allowed",
+            "no-drop",
+            "crossed_circle",
+            "forbidden",
+            "03b6e0fcb3499374a867c041f52298f0",
+        ],
+    },
+    Role {
+        name: "SizeNS",
+        file_stems: &["sizens", "vertical", "verticalresize", "vert", "aerons"],
+        xcursor: Some("sb_v_double_arrow"),
+        aliases: &[
+            "ns-resize",
+            "row-resize",
+            "size_ver",
+            "v_double_arrow",
+            "n-resize",
+            "s-resize",
+            "top_side",
+            "bottom_side",
+            "00008160000006810000408080010102",
+        ],
+    },
+    Role {
+        name: "SizeWE",
+        file_stems: &["sizewe", "horizontal", "horizontalresize", "horz", "aeroew"],
+        xcursor: Some("sb_h_double_arrow"),
+        aliases: &[
+            "ew-resize",
+            "col-resize",
+            "size_hor",
+            "h_double_arrow",
+            "e-resize",
+            "w-resize",
+            "left_side",
+            "right_side",
+            "028006030e0e7ebffc7f7070c0600140",
+        ],
+    },
+    Role {
+        name: "SizeNWSE",
+        file_stems: &[
+            "sizenwse",
+            "diagonal1",
+            "diagonalresize1",
+            "dgn1",
+            "aeronwse",
+        ],
+        xcursor: Some("bd_double_arrow"),
+        aliases: &[
+            "nwse-resize",
+            "size_fdiag",
+            "nw-resize",
+            "se-resize",
+            "top_left_corner",
+            "bottom_right_corner",
+            "c7088f0f3e6c8088236ef8e1e3e70000",
+        ],
+    },
+    Role {
+        name: "SizeNESW",
+        file_stems: &[
+            "sizenesw",
+            "diagonal2",
+            "diagonalresize2",
+            "dgn2",
+            "aeronesw",
+        ],
+        xcursor: Some("fd_double_arrow"),
+        aliases: &[
+            "nesw-resize",
+            "size_bdiag",
+            "ne-resize",
+            "sw-resize",
+            "top_right_corner",
+            "bottom_left_corner",
+            "fcf1c3c7cd4491d801f1e1c78f100000",
+        ],
+    },
+    Role {
+        name: "SizeAll",
+        file_stems: &["sizeall", "move", "aeromove"],
+        xcursor: Some("fleur"),
+        aliases: &["move", "all-scroll", "size_all"],
+    },
+    Role {
+        name: "UpArrow",
+        file_stems: &["uparrow", "alternate", "alternateselect", "aeroup"],
+        xcursor: Some("center_ptr"),
+        aliases: &[],
+    },
+    Role {
+        name: "Hand",
+        file_stems: &["hand", "link", "linkselect", "aerolink"],
+        xcursor: Some("hand2"),
+        aliases: &[
+            "pointer",
+            "hand",
+            "hand1",
+            "pointing_hand",
+            "e29285e634086352946a0e7090d73106",
+            "9d800788f1b08800ae810202380a0822",
+        ],
+    },
+    Role {
+        name: "Pin",
+        file_stems: &["pin", "location", "locationselect", "aeropin"],
+        xcursor: None,
+        aliases: &[],
+    },
+    Role {
+        name: "Person",
+        file_stems: &["person", "personselect", "aeroperson"],
+        xcursor: None,
+        aliases: &[],
+    },
+];
+
+/// Options for `windows_theme_to_xcursor`
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    /// Theme directory name; defaults to the scheme name from the .inf, then
+    /// the input directory's name
+    pub theme_name: Option<String>,
+    /// Link the alias names toolkits also look up (e.g. `pointer` for
+    /// `hand2`); copies are made where symlinks aren't available
+    pub aliases: bool,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            theme_name: None,
+            aliases: true,
+        }
+    }
+}
+
+/// A role that made it into the theme
+#[derive(Debug, Clone)]
+pub struct ConvertedRole {
+    pub role: &'static str,
+    pub source: PathBuf,
+    pub xcursor: &'static str,
+}
+
+/// What `windows_theme_to_xcursor` did
+#[derive(Debug, Clone, Default)]
+pub struct ThemeReport {
+    /// The theme directory, holding `index.theme` and `cursors/`
+    pub theme_dir: PathBuf,
+    pub converted: Vec<ConvertedRole>,
+    /// Roles or files left out, each with the reason
+    pub unmapped: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Convert a directory of .cur/.ani files into an installable Xcursor theme
+///
+/// Roles come from the scheme's .inf when one is given, otherwise from
+/// conventional file names ("Busy.ani", "Link.cur", "Normal Select.cur",
+/// registry names like "IBeam.cur"). Each file is converted with its sizes
+/// and animation timing kept, and written as `out_dir/<theme>/cursors/<name>`
+/// next to an `index.theme`. Roles X has no cursor for, files that are
+/// missing or fail to decode, and unrecognized files are listed in the
+/// report rather than failing the conversion.
+///
+/// ```
+/// use std::path::Path;
+///
+/// use cursor_handler::convert::{self, ConvertOptions};
+///
+/// let theme = Path::new("assets/windows_theme");
+/// let out = std::env::temp_dir().join("cursor_handler_convert_doc");
+/// let inf = theme.join("Install.inf");
+/// let report =
+///     convert::windows_theme_to_xcursor(theme, Some(&inf), &out, &ConvertOptions::default())?;
+///
+/// let mapped: Vec<_> = report.converted.iter().map(|role| (role.role, role.xcursor)).collect();
+/// assert_eq!(mapped, [("AppStarting", "left_ptr_watch"), ("Hand", "hand2")]);
+/// assert_eq!(report.unmapped.len(), 2); // Pin has no X cursor, Missing.cur is missing
+///
+/// assert_eq!(report.theme_dir, out.join("Tiny"));
+/// let index = std::fs::read_to_string(report.theme_dir.join("index.theme"))?;
+/// assert!(index.starts_with("[Icon Theme]\nName=Tiny\n"));
+/// let pointer = std::fs::read(report.theme_dir.join("cursors/pointer"))?;
+/// assert_eq!(&pointer[..4], b"Xcur");
+///
+/// // Without the .inf, roles come from the file names
+/// let report = convert::windows_theme_to_xcursor(theme, None, &out, &ConvertOptions::default())?;
+/// assert_eq!(report.theme_dir, out.join("windows_theme"));
+/// assert_eq!(report.converted.len(), 2);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn windows_theme_to_xcursor(
+    input_dir: impl AsRef<Path>,
+    inf: Option<&Path>,
+    out_dir: impl AsRef<Path>,
+    options: &ConvertOptions,
+) -> io::Result<ThemeReport> {
+    windows_theme_to_xcursor_with_progress(input_dir, inf, out_dir, options, &NoProgress)
+}
+
+/// `windows_theme_to_xcursor`, reporting each role as it is converted or
+/// given up on
+///
+/// Cursors are written through a temporary file and renamed into place. If
+/// `progress` cancels, the cursors already written are removed again.
+///
+/// ```
+/// use std::path::Path;
+///
+/// use cursor_handler::{
+///     convert::{self, ConvertOptions},
+///     progress::{CancelToken, Cancellable, Cancelled, ProgressEvent},
+/// };
+///
+/// let theme = Path::new("assets/windows_theme");
+/// let out = std::env::temp_dir().join("cursor_handler_cancel_doc");
+/// let _ = std::fs::remove_dir_all(&out);
+/// let inf = theme.join("Install.inf");
+///
+/// // Cancel once the first role is converted
+/// let token = CancelToken::new();
+/// let progress = Cancellable {
+///     progress: |event: ProgressEvent| {
+///         if event.completed == 1 {
+///             token.cancel();
+///         }
+///     },
+///     token: token.clone(),
+/// };
+/// let options = ConvertOptions::default();
+/// let err = convert::windows_theme_to_xcursor_with_progress(
+///     theme,
+///     Some(&inf),
+///     &out,
+///     &options,
+///     &progress,
+/// )
+/// .unwrap_err();
+/// assert!(Cancelled::is(&err));
+/// let cursors = out.join("Tiny/cursors");
+/// assert_eq!(std::fs::read_dir(&cursors)?.count(), 0);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn windows_theme_to_xcursor_with_progress(
+    input_dir: impl AsRef<Path>,
+    inf: Option<&Path>,
+    out_dir: impl AsRef<Path>,
+    options: &ConvertOptions,
+    progress: &dyn Progress,
+) -> io::Result<ThemeReport> {
+    let input_dir = input_dir.as_ref();
+    let mut report = ThemeReport::default();
+
+    // Cursor files by lowercased file name, since .inf names ignore case
+    let mut files = HashMap::new();
+    for entry in fs::read_dir(input_dir)? {
+        let path = entry?.path();
+        let is_cursor = path.extension().is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("cur") || extension.eq_ignore_ascii_case("ani")
+        });
+        if let (true, Some(name)) = (is_cursor, path.file_name().and_then(|name| name.to_str())) {
+            files.insert(name.to_lowercase(), path.clone());
+        }
+    }
+
+    let (scheme_name, assignments) = match inf {
+        Some(inf) => {
+            let scheme = parse_inf(&fs::read_to_string(inf)?);
+            let mut assignments = Vec::new();
+            for (role, file_name) in scheme.roles {
+                match files.get(&file_name.to_lowercase()) {
+                    Some(path) => assignments.push((role, path.clone())),
+                    None => report
+                        .unmapped
+                        .push(format!("{}: {file_name} not found", ROLES[role].name)),
+                }
+            }
+            (scheme.name, assignments)
+        }
+        None => (None, infer_roles(&files, &mut report)),
+    };
+
+    let theme_name = options
+        .theme_name
+        .clone()
+        .or(scheme_name)
+        .or_else(|| {
+            let name = input_dir
+                .canonicalize()
+                .ok()?
+                .file_name()?
+                .to_str()?
+                .to_string();
+            Some(name)
+        })
+        .unwrap_or_else(|| "converted".to_string());
+    report.theme_dir = out_dir.as_ref().join(&theme_name);
+    let cursors_dir = report.theme_dir.join("cursors");
+    fs::create_dir_all(&cursors_dir)?;
+
+    // A cancelled conversion takes back the cursors it wrote
+    let tracker = Tracker::new(progress, Phase::Converting, assignments.len());
+    let advance = |tracker: &Tracker, report: &ThemeReport, bytes| {
+        tracker.advance(bytes).map_err(|err| {
+            let written = report.converted.iter().map(|converted| converted.xcursor);
+            discard(&cursors_dir, written, err)
+        })
+    };
+    for (role, source) in assignments {
+        let role = &ROLES[role];
+        let Some(xcursor) = role.xcursor else {
+            report.unmapped.push(format!(
+                "{} ({}): no X cursor equivalent",
+                role.name,
+                source.display()
+            ));
+            advance(&tracker, &report, 0)?;
+            continue;
+        };
+        let bytes = match to_xcursor(&source) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                report
+                    .unmapped
+                    .push(format!("{} ({}): {err}", role.name, source.display()));
+                advance(&tracker, &report, 0)?;
+                continue;
+            }
+        };
+        write_atomic(&cursors_dir.join(xcursor), &bytes)?;
+        report.converted.push(ConvertedRole {
+            role: role.name,
+            source,
+            xcursor,
+        });
+        advance(&tracker, &report, bytes.len() as u64)?;
+    }
+
+    if options.aliases {
+        for converted in &report.converted {
+            let role = ROLES
+                .iter()
+                .find(|role| role.name == converted.role)
+                .unwrap();
+            for alias in role.aliases {
+                // A role's own cursor wins over another role's alias
+                let taken = report.converted.iter().any(|other| other.xcursor == *alias);
+                if !taken {
+                    link(&cursors_dir, converted.xcursor, alias)?;
+                }
+            }
+        }
+    }
+
+    fs::write(
+        report.theme_dir.join("index.theme"),
+        format!(
+            "[Icon Theme]\nName={theme_name}\nComment=Converted from a Windows cursor scheme\n"
+        ),
+    )?;
+    Ok(report)
+}
+
+fn to_xcursor(path: &Path) -> io::Result<Vec<u8>> {
+    let bytes = fs::read(path)?;
+    if bytes.starts_with(b"RIFF") {
+        AniFile::decode(io::Cursor::new(bytes))?.to_xcursor()
+    } else {
+        CursorFile::decode(io::Cursor::new(bytes))?.to_xcursor()
+    }
+}
+
+/// Write through a temporary sibling file so an interrupted write never
+/// leaves half a cursor at `path`
+fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// Remove the `files` in `dir` a cancelled conversion wrote, passing `err` on
+fn discard<'a>(dir: &Path, files: impl Iterator<Item = &'a str>, err: io::Error) -> io::Error {
+    for file in files {
+        let _ = fs::remove_file(dir.join(file));
+    }
+    err
+}
+
+/// Point `cursors_dir/alias` at `target`, replacing what was there
+fn link(cursors_dir: &Path, target: &str, alias: &str) -> io::Result<()> {
+    let path = cursors_dir.join(alias);
+    if path.symlink_metadata().is_ok() {
+        fs::remove_file(&path)?;
+    }
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(target, &path);
+    #[cfg(not(unix))]
+    let result = fs::copy(cursors_dir.join(target), &path).map(drop);
+    result
+}
+
+/// Roles picked from file stems, as `(index into ROLES, path)`
+fn infer_roles(
+    files: &HashMap<String, PathBuf>,
+    report: &mut ThemeReport,
+) -> Vec<(usize, PathBuf)> {
+    let mut paths: Vec<&PathBuf> = files.values().collect();
+    paths.sort();
+
+    let mut assignments: Vec<(usize, PathBuf)> = Vec::new();
+    for path in paths {
+        let stem: String = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        let Some(role) = ROLES
+            .iter()
+            .position(|role| role.file_stems.contains(&stem.as_str()))
+        else {
+            report
+                .unmapped
+                .push(format!("{}: no role matches the file name", path.display()));
+            continue;
+        };
+        if let Some((_, first)) = assignments.iter().find(|(other, _)| *other == role) {
+            report.warnings.push(format!(
+                "{}: {} is already used for {}",
+                path.display(),
+                first.display(),
+                ROLES[role].name
+            ));
+            continue;
+        }
+        assignments.push((role, path.clone()));
+    }
+    assignments.sort_by_key(|(role, _)| *role);
+    assignments
+}
+
+/// The parts of a scheme .inf the conversion uses
+#[derive(Debug, Default)]
+struct InfScheme {
+    name: Option<String>,
+    /// `(index into ROLES, file name)`, in role order
+    roles: Vec<(usize, String)>,
+}
+
+/// Read the per-role `HKCU,"Control Panel\Cursors",<Role>,...,"<path>"`
+/// lines, falling back to the slots of the `...\Cursors\Schemes` value for
+/// roles they don't set
+///
+/// `%name%` references are expanded from `[Strings]`; only the file name of
+/// each path is kept, since the files are read from the input directory.
+fn parse_inf(text: &str) -> InfScheme {
+    let mut strings = HashMap::new();
+    let mut registry_lines = Vec::new();
+    let mut section = String::new();
+    for line in text.lines() {
+        let line = line.split(';').next().unwrap_or_default().trim();
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            section = name.trim().to_lowercase();
+        } else if section == "strings" {
+            if let Some((key, value)) = line.split_once('=') {
+                strings.insert(key.trim().to_lowercase(), unquote(value.trim()).to_string());
+            }
+        } else if !line.is_empty() {
+            registry_lines.push(line);
+        }
+    }
+
+    let expand = |value: &str| {
+        let mut expanded = String::new();
+        let mut parts = value.split('%');
+        expanded.push_str(parts.next().unwrap_or_default());
+        // Odd parts are between percent signs
+        for (index, part) in parts.enumerate() {
+            match strings.get(&part.to_lowercase()) {
+                Some(value) if index % 2 == 0 => expanded.push_str(value),
+                _ => expanded.push_str(part),
+            }
+        }
+        expanded
+    };
+    let file_name = |path: &str| {
+        let path = expand(path);
+        let name = path.rsplit(['\\', '/']).next().unwrap_or_default().trim();
+        (!name.is_empty()).then(|| name.to_string())
+    };
+
+    let mut scheme = InfScheme::default();
+    let mut slots = Vec::new();
+    for line in registry_lines {
+        let fields = split_fields(line);
+        let [root, key, value_name, _, data, ..] = fields.as_slice() else {
+            continue;
+        };
+        if !root.eq_ignore_ascii_case("HKCU") {
+            continue;
+        }
+        let key = key.to_lowercase();
+        if key == r"control panel\cursors\schemes" {
+            scheme.name.get_or_insert_with(|| expand(value_name));
+            slots = data.split(',').map(file_name).collect();
+        } else if key == r"control panel\cursors" {
+            let role = ROLES
+                .iter()
+                .position(|role| role.name.eq_ignore_ascii_case(value_name));
+            if let (Some(role), Some(file)) = (role, file_name(data)) {
+                scheme.roles.retain(|(other, _)| *other != role);
+                scheme.roles.push((role, file));
+            } else if value_name.is_empty() && !data.is_empty() {
+                // The default value names the active scheme
+                scheme.name.get_or_insert_with(|| expand(data));
+            }
+        }
+    }
+
+    for (role, file) in slots.into_iter().enumerate().take(ROLES.len()) {
+        if let Some(file) = file
+            && !scheme.roles.iter().any(|(other, _)| *other == role)
+        {
+            scheme.roles.push((role, file));
+        }
+    }
+    scheme.roles.sort_by_key(|(role, _)| *role);
+    scheme
+}
+
+/// Split a comma-separated .inf line, keeping commas inside quotes
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+        .iter()
+        .map(|field| field.trim().to_string())
+        .collect()
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// What `xcursor_theme_to_windows` does for a role the theme has no cursor
+/// for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingRole {
+    /// Leave the role out of the scheme, so Windows keeps its own cursor
+    Omit,
+    /// Use the theme's Arrow cursor
+    Arrow,
+    /// Use the closest role the theme has (Wait for AppStarting, SizeAll for
+    /// the resize roles, Hand for Pin and Person), then Arrow
+    #[default]
+    Related,
+}
+
+/// Options for `xcursor_theme_to_windows`
+#[derive(Debug, Clone)]
+pub struct SchemeOptions {
+    /// Scheme name; defaults to the `Name` in index.theme, then the theme
+    /// directory's name
+    pub scheme_name: Option<String>,
+    /// Nominal size that sets the frames of animations; other sizes become
+    /// extra resolutions
+    pub preferred_size: u32,
+    /// `(role, X cursor name)` pairs tried before the built-in names, e.g.
+    /// `("Hand", "pointer")`
+    pub mapping: Vec<(String, String)>,
+    pub missing: MissingRole,
+    /// Platform the cursors are encoded for; a cursor the profile can't
+    /// show without resizing or dropping frames is skipped
+    pub profile: Option<CompatibilityProfile>,
+}
+
+impl Default for SchemeOptions {
+    fn default() -> Self {
+        Self {
+            scheme_name: None,
+            preferred_size: 32,
+            mapping: Vec::new(),
+            missing: MissingRole::default(),
+            profile: None,
+        }
+    }
+}
+
+/// A role of the converted scheme
+#[derive(Debug, Clone)]
+pub struct SchemeRole {
+    pub role: &'static str,
+    /// The X cursor name the file was converted from, or for a scheme
+    /// config the source path
+    pub source: String,
+    /// File name in the output directory
+    pub file: String,
+    /// Whether the cursor was borrowed from another role by the
+    /// `MissingRole` policy
+    pub fallback: bool,
+}
+
+/// What `xcursor_theme_to_windows` did
+#[derive(Debug, Clone, Default)]
+pub struct SchemeReport {
+    pub inf: PathBuf,
+    pub roles: Vec<SchemeRole>,
+    /// Roles left out of the scheme
+    pub missing: Vec<&'static str>,
+    /// Theme cursors that weren't converted, each with the reason
+    pub skipped: Vec<String>,
+}
+
+/// Convert an Xcursor theme into a Windows cursor scheme with an install.inf
+///
+/// Every file of `theme_dir/cursors` is resolved through its symlinks, so
+/// each distinct cursor is converted once: to an .ani when it animates and a
+/// .cur otherwise, with all of its sizes. Roles are matched by X cursor name
+/// (`left_ptr` for Arrow, `watch` for Wait, `xterm` for IBeam, ...), and roles
+/// without a cursor follow `options.missing`. The files and `install.inf` are
+/// written to `out_dir`.
+///
+/// ```
+/// use cursor_handler::convert::{self, ConvertOptions, SchemeOptions};
+///
+/// let temp = std::env::temp_dir().join("cursor_handler_scheme_doc");
+/// let theme = convert::windows_theme_to_xcursor(
+///     "assets/windows_theme",
+///     Some("assets/windows_theme/Install.inf".as_ref()),
+///     temp.join("xcursor"),
+///     &ConvertOptions::default(),
+/// )?;
+///
+/// let out = temp.join("windows");
+/// let report =
+///     convert::xcursor_theme_to_windows(&theme.theme_dir, &out, &SchemeOptions::default())?;
+/// let roles: Vec<_> = report.roles.iter().map(|role| (role.role, role.fallback)).collect();
+/// assert_eq!(
+///     roles,
+///     [("AppStarting", false), ("Wait", true), ("Hand", false), ("Pin", true), ("Person", true)]
+/// );
+/// assert_eq!(report.roles[0].file, "left_ptr_watch.ani");
+/// assert!(report.missing.contains(&"Arrow"));
+///
+/// let inf = std::fs::read_to_string(&report.inf)?;
+/// assert!(inf.contains("SCHEME_NAME = \"Tiny\""));
+/// let hand = r#"HKCU,"Control Panel\Cursors",Hand,0x00020000,"%10%\%CUR_DIR%\%hand%""#;
+/// assert!(inf.contains(hand));
+///
+/// // The mapping table picks cursors for roles the built-in names miss
+/// let options = SchemeOptions {
+///     mapping: vec![("Arrow".into(), "hand2".into())],
+///     ..SchemeOptions::default()
+/// };
+/// let report = convert::xcursor_theme_to_windows(&theme.theme_dir, &out, &options)?;
+/// assert_eq!(report.roles[0].role, "Arrow");
+/// assert_eq!(report.roles.len(), 17);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn xcursor_theme_to_windows(
+    theme_dir: impl AsRef<Path>,
+    out_dir: impl AsRef<Path>,
+    options: &SchemeOptions,
+) -> io::Result<SchemeReport> {
+    xcursor_theme_to_windows_with_progress(theme_dir, out_dir, options, &NoProgress)
+}
+
+/// `xcursor_theme_to_windows`, reporting each of the 17 roles as it is
+/// looked up and, the first time its file is seen, converted
+pub fn xcursor_theme_to_windows_with_progress(
+    theme_dir: impl AsRef<Path>,
+    out_dir: impl AsRef<Path>,
+    options: &SchemeOptions,
+    progress: &dyn Progress,
+) -> io::Result<SchemeReport> {
+    let theme_dir = theme_dir.as_ref();
+    let out_dir = out_dir.as_ref();
+    for (role, _) in &options.mapping {
+        if !ROLES
+            .iter()
+            .any(|known| known.name.eq_ignore_ascii_case(role))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown role '{role}' in the mapping"),
+            ));
+        }
+    }
+
+    let mut report = SchemeReport::default();
+
+    // Every name of each distinct cursor file
+    let mut names: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    for entry in fs::read_dir(theme_dir.join("cursors"))? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        match fs::canonicalize(&path) {
+            Ok(target) if target.is_file() => names.entry(target).or_default().push(name.into()),
+            Ok(_) => {}
+            Err(err) => report.skipped.push(format!("{name}: {err}")),
+        }
+    }
+    let target_of: HashMap<&str, &PathBuf> = names
+        .iter()
+        .flat_map(|(target, names)| names.iter().map(move |name| (name.as_str(), target)))
+        .collect();
+
+    // Roles with a cursor of their own, converting each file once
+    let mut files: HashMap<&PathBuf, Option<String>> = HashMap::new();
+    let mut assigned: Vec<Option<SchemeRole>> = Vec::with_capacity(ROLES.len());
+    // A cancelled conversion takes back the cursors it wrote
+    let tracker = Tracker::new(progress, Phase::Converting, ROLES.len());
+    let advance = |tracker: &Tracker, files: &HashMap<&PathBuf, Option<String>>, bytes| {
+        tracker.advance(bytes).map_err(|err| {
+            let written = files.values().flatten().map(String::as_str);
+            discard(out_dir, written, err)
+        })
+    };
+    for role in &ROLES {
+        let overrides = options
+            .mapping
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(role.name))
+            .map(|(_, cursor)| cursor.as_str());
+        let mut candidates = overrides
+            .chain(role.xcursor)
+            .chain(role.aliases.iter().copied());
+        let Some((source, target)) =
+            candidates.find_map(|name| Some((name, *target_of.get(name)?)))
+        else {
+            assigned.push(None);
+            advance(&tracker, &files, 0)?;
+            continue;
+        };
+
+        let mut written = 0;
+        let file = files.entry(target).or_insert_with(|| {
+            let converted = fs::read(target)
+                .and_then(|bytes| xcursor::decode(&bytes))
+                .and_then(|images| WindowsCursor::from_images(&images, options.preferred_size))
+                .and_then(|cursor| {
+                    let cursor = cursor.cursor();
+                    let file = format!("{source}.{}", cursor.extension());
+                    fs::create_dir_all(out_dir)?;
+                    let bytes = cursor.encode_with(&EncodeOptions {
+                        profile: options.profile,
+                        ..Default::default()
+                    })?;
+                    write_atomic(&out_dir.join(&file), &bytes)?;
+                    written = bytes.len() as u64;
+                    Ok(file)
+                });
+            converted
+                .map_err(|err| report.skipped.push(format!("{source}: {err}")))
+                .ok()
+        });
+        assigned.push(file.clone().map(|file| SchemeRole {
+            role: role.name,
+            source: source.to_string(),
+            file,
+            fallback: false,
+        }));
+        advance(&tracker, &files, written)?;
+    }
+
+    for (target, names) in &names {
+        if !files.contains_key(target) {
+            report
+                .skipped
+                .push(format!("{}: no Windows role", names.join(", ")));
+        }
+    }
+
+    fill_missing(&mut assigned, options.missing);
+    for (role, slot) in ROLES.iter().zip(&assigned) {
+        if slot.is_none() {
+            report.missing.push(role.name);
+        }
+    }
+
+    let name = options
+        .scheme_name
+        .clone()
+        .or_else(|| theme_name(theme_dir))
+        .unwrap_or_else(|| "Converted".to_string());
+    let package = SchemePackage {
+        comment: format!("{name}, converted from an Xcursor theme"),
+        name,
+        roles: assigned.into_iter().flatten().collect(),
+    };
+    report.inf = package.write_inf(out_dir)?;
+    report.roles = package.roles;
+    Ok(report)
+}
+
+/// Give each empty slot, one per role in `roles()` order, the cursor of
+/// another role as `policy` says, marked as a fallback
+pub(crate) fn fill_missing(assigned: &mut [Option<SchemeRole>], policy: MissingRole) {
+    let direct = assigned.to_vec();
+    for (index, slot) in assigned.iter_mut().enumerate() {
+        if slot.is_some() {
+            continue;
+        }
+        let donors: &[&str] = match policy {
+            MissingRole::Omit => &[],
+            MissingRole::Arrow => &["Arrow"],
+            MissingRole::Related => related_roles(ROLES[index].name),
+        };
+        *slot = donors.iter().find_map(|donor| {
+            let donor = ROLES.iter().position(|role| role.name == *donor)?;
+            let donor = direct[donor].as_ref()?;
+            Some(SchemeRole {
+                role: ROLES[index].name,
+                fallback: true,
+                ..donor.clone()
+            })
+        });
+    }
+}
+
+/// Roles whose cursor stands in for `role`, best first
+fn related_roles(role: &str) -> &'static [&'static str] {
+    match role {
+        "AppStarting" => &["Wait", "Arrow"],
+        "Wait" => &["AppStarting", "Arrow"],
+        "NWPen" => &["IBeam", "Arrow"],
+        "SizeNS" | "SizeWE" | "SizeNWSE" | "SizeNESW" => &["SizeAll", "Arrow"],
+        "Pin" | "Person" => &["Hand", "Arrow"],
+        "Arrow" => &[],
+        _ => &["Arrow"],
+    }
+}
+
+/// The `Name` of an index.theme, or the theme directory's name
+fn theme_name(theme_dir: &Path) -> Option<String> {
+    let index = fs::read_to_string(theme_dir.join("index.theme")).unwrap_or_default();
+    let name = index
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Name="))
+        .map(|name| name.trim().to_string())
+        .next();
+    name.or_else(|| {
+        let name = theme_dir
+            .canonicalize()
+            .ok()?
+            .file_name()?
+            .to_str()?
+            .to_string();
+        Some(name)
+    })
+}
+
+/// A Windows scheme: its name, and the file each role uses
+///
+/// The files sit next to the install.inf this writes, which copies them to
+/// `%windir%\Cursors\<name>` and registers and applies the scheme.
+///
+/// ```
+/// use cursor_handler::convert::{SchemePackage, SchemeRole};
+///
+/// let role = |role, file: &str| SchemeRole {
+///     role,
+///     source: file.to_string(),
+///     file: file.to_string(),
+///     fallback: false,
+/// };
+/// let package = SchemePackage {
+///     name: "Ocean".to_string(),
+///     comment: "Ocean cursors".to_string(),
+///     roles: vec![role("Arrow", "Arrow.cur"), role("Wait", "Wait.ani")],
+/// };
+/// let inf = package.inf();
+/// assert!(inf.starts_with("; Ocean cursors\r\n"));
+/// assert!(inf.contains("\r\n\"Arrow.cur\"\r\n\"Wait.ani\"\r\n"));
+/// assert!(inf.ends_with("wait = \"Wait.ani\"\r\n"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SchemePackage {
+    /// Scheme name; double quotes are dropped when written
+    pub name: String,
+    /// The .inf's first line, as a comment
+    pub comment: String,
+    pub roles: Vec<SchemeRole>,
+}
+
+impl SchemePackage {
+    /// The text of the install.inf, with CRLF line endings
+    pub fn inf(&self) -> String {
+        scheme_inf(self)
+    }
+
+    /// Write `out_dir/install.inf`, returning its path
+    pub fn write_inf(&self, out_dir: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let out_dir = out_dir.as_ref();
+        fs::create_dir_all(out_dir)?;
+        let path = out_dir.join("install.inf");
+        fs::write(&path, self.inf())?;
+        Ok(path)
+    }
+}
+
+fn scheme_inf(package: &SchemePackage) -> String {
+    let name = package.name.replace('"', "");
+    let roles = &package.roles;
+    let key = |role: &str| role.to_lowercase();
+    let slots: Vec<String> = ROLES
+        .iter()
+        .map(
+            |role| match roles.iter().find(|assigned| assigned.role == role.name) {
+                Some(_) => format!("%10%\\%CUR_DIR%\\%{}%", key(role.name)),
+                None => String::new(),
+            },
+        )
+        .collect();
+    let mut files: Vec<&str> = roles.iter().map(|role| role.file.as_str()).collect();
+    files.sort_unstable();
+    files.dedup();
+
+    let mut lines = vec![
+        format!("; {}", package.comment),
+        String::new(),
+        "[Version]".into(),
+        "signature=\"$CHICAGO$\"".into(),
+        String::new(),
+        "[DefaultInstall]".into(),
+        "CopyFiles = Scheme.Cur".into(),
+        "AddReg    = Scheme.Reg,Wreg".into(),
+        String::new(),
+        "[DestinationDirs]".into(),
+        "Scheme.Cur = 10,\"%CUR_DIR%\"".into(),
+        String::new(),
+        "[Scheme.Reg]".into(),
+        format!(
+            "HKCU,\"Control Panel\\Cursors\\Schemes\",\"%SCHEME_NAME%\",,\"{}\"",
+            slots.join(",")
+        ),
+        String::new(),
+        "[Wreg]".into(),
+        "HKCU,\"Control Panel\\Cursors\",,0x00020000,\"%SCHEME_NAME%\"".into(),
+    ];
+    for role in roles {
+        lines.push(format!(
+            "HKCU,\"Control Panel\\Cursors\",{},0x00020000,\"%10%\\%CUR_DIR%\\%{}%\"",
+            role.role,
+            key(role.role)
+        ));
+    }
+    lines.extend([String::new(), "[Scheme.Cur]".into()]);
+    lines.extend(files.iter().map(|file| format!("\"{file}\"")));
+    lines.extend([
+        String::new(),
+        "[Strings]".into(),
+        format!("CUR_DIR = \"Cursors\\{name}\""),
+        format!("SCHEME_NAME = \"{name}\""),
+    ]);
+    for role in roles {
+        lines.push(format!("{} = \"{}\"", key(role.role), role.file));
+    }
+
+    let mut inf = lines.join("\r\n");
+    inf.push_str("\r\n");
+    inf
+}
@@ -1,46 +1,208 @@
 // lib.rs
 #![allow(dead_code)]
 use std::{
-    fmt::Display,
+    fmt::{Debug, Display},
     io::{self, Read, Seek, SeekFrom, Write},
+    ops::{Index, IndexMut},
+    slice,
 };
 
+use image::{ImageFormat, RgbaImage};
+
+use crate::debug::{BytesSummary, FramesSummary};
+use crate::decode::{
+    DecodeOptions, DecodeStats, Decoded, Diagnostics, FrameErrorPolicy, invalid_chunk, read_chunk,
+};
+use crate::dib::BmpInfo;
+use crate::encode::{EncodeOptions, FileLayout, Section, record};
+use crate::hotspot::{Hotspot, HotspotReference};
+use crate::size::{FrameSize, MAX_DIR_SIZE, MAX_FRAME_SIZE};
+use crate::trace;
+
+/// Signature at the start of PNG-compressed frame data
+pub const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
 /// A cursor frame with image data and hotspot
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct CursorFrame {
-    pub width: u32,
-    pub height: u32,
-    pub hotspot_x: u16,
-    pub hotspot_y: u16,
+    pub size: FrameSize,
+    pub hotspot: Hotspot,
+    /// Bits per pixel, 0 if unknown: an icon's directory entry holds it,
+    /// otherwise it is read from the payload
+    pub bit_count: u16,
     pub image_data: Vec<u8>,
 }
 
 impl CursorFrame {
-    pub fn new(
-        width: u32,
-        height: u32,
-        hotspot_x: u16,
-        hotspot_y: u16,
-        image_data: Vec<u8>,
-    ) -> Self {
+    pub fn new(size: FrameSize, hotspot: impl Into<Hotspot>, image_data: Vec<u8>) -> Self {
         Self {
-            width,
-            height,
-            hotspot_x,
-            hotspot_y,
+            size,
+            hotspot: hotspot.into(),
+            bit_count: payload_bit_count(&image_data) as u16,
             image_data,
         }
     }
+
+    /// Replace the payload, reading the bit depth from the new one
+    pub(crate) fn set_payload(&mut self, image_data: Vec<u8>) {
+        self.bit_count = payload_bit_count(&image_data) as u16;
+        self.image_data = image_data;
+    }
+
+    /// A frame holding `image`: a 32-bit DIB below 256 pixels, which every
+    /// Windows version reads, and PNG from there up
+    ///
+    /// ```
+    /// use cursor_handler::{CursorFrame, FrameSize, Hotspot};
+    /// use image::{Rgba, RgbaImage};
+    ///
+    /// let image = RgbaImage::from_pixel(32, 32, Rgba([0, 0, 0, 255]));
+    /// let frame = CursorFrame::from_image(&image, Hotspot::new(4, 4))?;
+    /// assert_eq!(frame.size, FrameSize::square(32)?);
+    /// assert!(!frame.is_png() && frame.bit_count == 32);
+    /// assert_eq!(frame.decode_image()?, image);
+    ///
+    /// let large = RgbaImage::from_pixel(256, 256, Rgba([0, 0, 0, 255]));
+    /// assert!(CursorFrame::from_image(&large, Hotspot::new(4, 4))?.is_png());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_image(image: &RgbaImage, hotspot: Hotspot) -> io::Result<Self> {
+        let size = FrameSize::new(image.width(), image.height())?;
+        let image_data = if size.max_side() < MAX_DIR_SIZE {
+            crate::dib::encode_dib32(image)
+        } else {
+            crate::optimize::encode_png(image)?
+        };
+        Ok(Self::new(size, hotspot, image_data))
+    }
+
+    /// Move the hotspot, refusing one outside the frame
+    pub fn set_hotspot(&mut self, hotspot: Hotspot) -> io::Result<()> {
+        if !hotspot.is_within(self.size.width(), self.size.height()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("hotspot {hotspot} is outside the {} frame", self.size),
+            ));
+        }
+        self.hotspot = hotspot;
+        Ok(())
+    }
+
+    /// Whether the image data is PNG rather than a DIB
+    pub fn is_png(&self) -> bool {
+        self.image_data.starts_with(&PNG_SIGNATURE)
+    }
+
+    /// The payload itself when it is a PNG, cut at the end of its IEND chunk
+    ///
+    /// Nothing is copied or re-encoded, so the bytes can be written out or
+    /// served as `image/png` as they are. A PNG missing its IEND gives `None`.
+    pub fn png_data(&self) -> Option<&[u8]> {
+        png_data(&self.image_data)
+    }
+
+    /// Short description of the payload encoding, e.g. `PNG` or `DIB-32`
+    pub fn payload_format(&self) -> String {
+        if self.is_png() {
+            return "PNG".to_string();
+        }
+        match self.image_data.get(14..16) {
+            Some(bits) if self.image_data.len() >= 40 => {
+                format!("DIB-{}", u16::from_le_bytes([bits[0], bits[1]]))
+            }
+            _ => "unknown".to_string(),
+        }
+    }
+
+    /// Length the payload's own headers imply, if they can be parsed
+    ///
+    /// Differs from `image_data.len()` when the directory declared a size
+    /// with trailing junk or one that cuts the image short.
+    pub fn expected_len(&self) -> Option<usize> {
+        payload_len(&self.image_data)
+    }
+
+    /// Whether the payload is as long as its own headers say, or can't tell
+    ///
+    /// ```
+    /// use cursor_handler::{CursorFrame, FrameSize};
+    ///
+    /// let dib = [40u32, 1, 2, 1 | (32 << 16), 0, 0, 0, 0, 0, 0]
+    ///     .map(u32::to_le_bytes)
+    ///     .concat();
+    /// let mut frame = CursorFrame::new(FrameSize::square(1)?, (0, 0), dib);
+    /// assert!(!frame.payload_len_consistent()); // Header only, no pixels
+    /// frame.image_data.extend([0; 8]); // One pixel and one mask row
+    /// assert!(frame.payload_len_consistent());
+    /// frame.image_data.push(0);
+    /// assert!(!frame.payload_len_consistent());
+    /// frame.image_data = b"not an image".to_vec();
+    /// assert!(frame.payload_len_consistent());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn payload_len_consistent(&self) -> bool {
+        self.expected_len()
+            .is_none_or(|expected| expected == self.image_data.len())
+    }
+
+    /// Decode the image data (PNG or DIB) into an RGBA image
+    pub fn decode_image(&self) -> io::Result<RgbaImage> {
+        if let Some(image) = crate::dib::decode_bitfields(self) {
+            return Ok(image);
+        }
+
+        let mut frame = self.clone();
+        if let Some(plain) = crate::dib::decompress_rle(&self.image_data)? {
+            frame.image_data = plain;
+        }
+        if let Some(flipped) = crate::dib::to_bottom_up(&self.image_data) {
+            frame.image_data = flipped;
+        }
+        let mut container = Vec::new();
+        CursorFile::single(frame).encode(&mut container)?;
+
+        image::load_from_memory_with_format(&container, ImageFormat::Ico)
+            .map(|image| image.to_rgba8())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+impl Debug for CursorFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CursorFrame")
+            .field("size", &self.size)
+            .field("hotspot", &self.hotspot)
+            .field("bit_count", &self.bit_count)
+            .field("image_data", &BytesSummary(&self.image_data))
+            .finish()
+    }
+}
+
+impl Debug for CursorFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CursorFile")
+            .field("is_icon", &self.is_icon())
+            .field("frames", &FramesSummary(&self.frames))
+            .finish()
+    }
 }
 
 impl Display for CursorFile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "Cursor with {} frame(s):", self.frames.len())?;
+        let kind = if self.is_icon() { "Icon" } else { "Cursor" };
+        writeln!(f, "{kind} with {} frame(s):", self.frames.len())?;
         for (i, frame) in self.frames.iter().enumerate() {
+            writeln!(f, "  Frame {i}:\n    Size:    {}", frame.size)?;
+            // An icon's hotspot is only the configured default
+            if !self.is_icon() {
+                writeln!(f, "    Hotspot: {}", frame.hotspot)?;
+            }
+            writeln!(f, "    Bits:    {}", frame.bit_count)?;
             writeln!(
                 f,
-                "  Frame {i}:\n    Size:    {}x{}\n    Hotspot: ({}, {})",
-                frame.width, frame.height, frame.hotspot_x, frame.hotspot_y
+                "    Payload: {}, {} bytes",
+                frame.payload_format(),
+                frame.image_data.len()
             )?;
         }
         Ok(())
@@ -48,116 +210,792 @@ impl Display for CursorFile {
 }
 
 /// A cursor file containing one or more frames
-#[derive(Debug, Clone)]
+#[derive(Clone, Default)]
 pub struct CursorFile {
-    pub frames: Vec<CursorFrame>,
+    pub(crate) frames: Vec<CursorFrame>,
+    kind: ContainerKind,
 }
 
 impl CursorFile {
     pub fn new(frames: Vec<CursorFrame>) -> Self {
-        Self { frames }
+        Self {
+            frames,
+            ..Self::default()
+        }
     }
 
     pub fn single(frame: CursorFrame) -> Self {
-        Self {
-            frames: vec![frame],
+        Self::new(vec![frame])
+    }
+
+    /// A cursor with no frames yet; `encode` fails until one is pushed
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Whether this was decoded from an icon (type 1) container
+    ///
+    /// Icon entries hold planes and bit depth where cursor entries hold the
+    /// hotspot, so an icon's frames get a hotspot from
+    /// `DecodeOptions::icon_hotspot` and the directory's `bit_count`.
+    ///
+    /// ```
+    /// use std::{fs::File, io::Cursor};
+    ///
+    /// use cursor_handler::{CursorFile, DecodeOptions, Hotspot, HotspotPolicy};
+    ///
+    /// let cursor = CursorFile::decode(File::open("assets/windows_theme/Link.cur")?)?;
+    /// let mut ico = Vec::new();
+    /// cursor.encode_as_ico(&mut ico)?;
+    ///
+    /// let options = DecodeOptions { allow_ico: true, ..Default::default() };
+    /// let icon = CursorFile::decode_with(Cursor::new(&ico), &options)?.value;
+    /// assert!(icon.is_icon() && !cursor.is_icon());
+    /// for (icon, cursor) in icon.frames().zip(cursor.frames()) {
+    ///     assert_eq!(icon.bit_count, cursor.bit_count);
+    ///     assert_eq!(icon.hotspot, Hotspot::default());
+    ///     assert_eq!(icon.image_data, cursor.image_data);
+    /// }
+    /// assert_ne!(cursor[0].hotspot, Hotspot::default());
+    /// assert!(icon.to_string().starts_with("Icon with"));
+    /// assert!(!icon.to_string().contains("Hotspot"));
+    ///
+    /// let options = DecodeOptions { icon_hotspot: HotspotPolicy::Center, ..options };
+    /// let icon = CursorFile::decode_with(Cursor::new(&ico), &options)?.value;
+    /// let side = icon[0].size.width() as u16;
+    /// assert_eq!(icon[0].hotspot, Hotspot::new(side / 2, side / 2));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn is_icon(&self) -> bool {
+        self.kind == ContainerKind::Icon
+    }
+
+    pub fn push_frame(&mut self, frame: CursorFrame) {
+        self.frames.push(frame);
+    }
+
+    /// Give every frame the same hotspot, changing none if it lies outside
+    /// any of them
+    pub fn set_hotspot_all(&mut self, hotspot: Hotspot) -> io::Result<()> {
+        if let Some(frame) = self
+            .frames
+            .iter()
+            .find(|frame| !hotspot.is_within(frame.size.width(), frame.size.height()))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("hotspot {hotspot} is outside the {} frame", frame.size),
+            ));
+        }
+        for frame in &mut self.frames {
+            frame.hotspot = hotspot;
+        }
+        Ok(())
+    }
+
+    /// Put every frame's hotspot on the same point of the image, so the
+    /// pointer stays put when Windows switches sizes (CUR014)
+    ///
+    /// ```
+    /// use std::{fs::File, io::Cursor};
+    ///
+    /// use cursor_handler::{CursorFile, Hotspot, HotspotReference};
+    ///
+    /// // 64, 48 and 32px frames with the 32px hotspot off by a dozen pixels
+    /// let mut cursor = CursorFile::decode(File::open("assets/inconsistent_hotspots.cur")?)?;
+    /// let issues = cursor.validate();
+    /// let drifted: Vec<_> = issues.iter().filter(|issue| issue.code == "CUR014").collect();
+    /// assert_eq!((drifted.len(), drifted[0].frame), (1, Some(2)));
+    ///
+    /// let hotspots = |cursor: &CursorFile| -> Vec<_> {
+    ///     cursor.frames().map(|frame| (frame.hotspot.x, frame.hotspot.y)).collect()
+    /// };
+    /// cursor.normalize_hotspots(HotspotReference::Frame(0))?;
+    /// assert_eq!(hotspots(&cursor), [(32, 32), (24, 24), (16, 16)]);
+    /// cursor.normalize_hotspots(HotspotReference::Fraction(0.25, 0.0))?;
+    /// assert_eq!(hotspots(&cursor), [(16, 0), (12, 0), (8, 0)]);
+    /// assert!(cursor.normalize_hotspots(HotspotReference::Frame(3)).is_err());
+    ///
+    /// let mut bytes = Vec::new();
+    /// cursor.encode(&mut bytes)?;
+    /// let decoded = CursorFile::decode(Cursor::new(bytes))?;
+    /// assert_eq!(hotspots(&decoded), [(16, 0), (12, 0), (8, 0)]);
+    /// assert!(decoded.validate().iter().all(|issue| issue.code != "CUR014"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn normalize_hotspots(&mut self, reference: HotspotReference) -> io::Result<()> {
+        let fraction = match reference {
+            HotspotReference::Frame(index) => {
+                let Some(frame) = self.frames.get(index) else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("no frame {index}"),
+                    ));
+                };
+                frame
+                    .hotspot
+                    .fraction(frame.size.width(), frame.size.height())
+            }
+            HotspotReference::Fraction(fx, fy) => {
+                if !(0.0..=1.0).contains(&fx) || !(0.0..=1.0).contains(&fy) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("hotspot fraction ({fx}, {fy}) is outside 0.0 to 1.0"),
+                    ));
+                }
+                (fx, fy)
+            }
+        };
+        for frame in &mut self.frames {
+            let (width, height) = (frame.size.width(), frame.size.height());
+            frame.hotspot = Hotspot::from_fraction(fraction, width, height);
+        }
+        Ok(())
+    }
+
+    pub fn frames(&self) -> slice::Iter<'_, CursorFrame> {
+        self.frames.iter()
+    }
+
+    pub fn frames_mut(&mut self) -> slice::IterMut<'_, CursorFrame> {
+        self.frames.iter_mut()
+    }
+
+    /// The frame closest in size to `size`, preferring the larger one on a tie
+    pub fn best_frame(&self, size: u32) -> Option<&CursorFrame> {
+        self.frames.iter().min_by_key(|frame| {
+            let frame_size = frame.size.max_side();
+            (frame_size.abs_diff(size), std::cmp::Reverse(frame_size))
+        })
+    }
+
+    /// Remove the frame of exactly `size`, returning it
+    pub fn remove_frame_by_size(&mut self, size: FrameSize) -> Option<CursorFrame> {
+        let index = self.frames.iter().position(|frame| frame.size == size)?;
+        Some(self.frames.remove(index))
+    }
+
+    /// Put `frame` in place of the frame of `size`, keeping its position
+    ///
+    /// The replacement must be `size` itself, as its directory entry says and
+    /// as its PNG or DIB header says.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{CursorFile, CursorFrame, FrameSize, Hotspot};
+    /// use image::{Rgba, RgbaImage};
+    ///
+    /// let frame = |side: u32, shade: u8| {
+    ///     let image = RgbaImage::from_pixel(side, side, Rgba([shade, 0, 0, 255]));
+    ///     CursorFrame::from_image(&image, Hotspot::default())
+    /// };
+    /// let mut cursor = CursorFile::empty();
+    /// for side in [24, 32, 48, 64] {
+    ///     cursor.push_frame(frame(side, 0)?);
+    /// }
+    ///
+    /// let blurry = cursor.remove_frame_by_size(FrameSize::square(24)?).unwrap();
+    /// assert_eq!(blurry.size.width(), 24);
+    /// cursor.replace_frame(FrameSize::square(48)?, frame(48, 255)?)?;
+    /// let mut lying = frame(32, 0)?;
+    /// lying.size = FrameSize::square(48)?;
+    /// assert!(cursor.replace_frame(FrameSize::square(48)?, lying).is_err());
+    /// cursor.retain_sizes(&[48, 64]);
+    ///
+    /// let mut bytes = Vec::new();
+    /// cursor.encode(&mut bytes)?;
+    /// let decoded = CursorFile::decode(Cursor::new(bytes))?;
+    /// let sides: Vec<_> = decoded.frames().map(|frame| frame.size.width()).collect();
+    /// assert_eq!(sides, [48, 64]);
+    /// assert_eq!(decoded[0].decode_image()?.get_pixel(0, 0)[0], 255);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn replace_frame(&mut self, size: FrameSize, frame: CursorFrame) -> io::Result<()> {
+        let payload = payload_size(&frame.image_data);
+        if frame.size != size || payload != Some((size.width(), size.height())) {
+            let payload = match payload {
+                Some((width, height)) => format!("{width}x{height}"),
+                None => "unreadable".to_string(),
+            };
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "replacement for {size} is {} with a {payload} payload",
+                    frame.size
+                ),
+            ));
         }
+
+        let Some(slot) = self.frames.iter_mut().find(|kept| kept.size == size) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no {size} frame to replace"),
+            ));
+        };
+        *slot = frame;
+        Ok(())
+    }
+
+    /// Keep only frames whose longer side is in `sides`
+    pub fn retain_sizes(&mut self, sides: &[u32]) {
+        self.frames
+            .retain(|frame| sides.contains(&frame.size.max_side()));
     }
 
     /// Encode cursor to writer
-    pub fn encode<W: Write>(&self, mut writer: W) -> io::Result<()> {
+    ///
+    /// Frames larger than 256px must have a PNG payload; their directory
+    /// entry records 0 (256) and readers take the real size from the PNG.
+    pub fn encode<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.encode_with(writer, &EncodeOptions::default())
+            .map(|_| ())
+    }
+
+    /// Encode cursor to writer, applying `options`
+    ///
+    /// Returns the number of bytes saved by `share_payloads`. Directory
+    /// entries may point anywhere in the file, so decoding needs nothing
+    /// special for shared or reordered payloads.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{CursorFile, CursorFrame, EncodeOptions, FrameSize};
+    ///
+    /// let frame = |shade: u8| -> std::io::Result<CursorFrame> {
+    ///     // A 1x1 32-bit DIB: header, one BGRA pixel, a padded mask row
+    ///     let mut dib = [40u32, 1, 2, 0x0020_0001, 0, 8, 0, 0, 0, 0]
+    ///         .map(u32::to_le_bytes)
+    ///         .concat();
+    ///     dib.extend_from_slice(&[shade, shade, shade, 255, 0, 0, 0, 0]);
+    ///     Ok(CursorFrame::new(FrameSize::square(1)?, (0, 0), dib))
+    /// };
+    /// let cursor = CursorFile::new(vec![frame(0)?, frame(0)?, frame(255)?]);
+    ///
+    /// let options = EncodeOptions { share_payloads: true, ..Default::default() };
+    /// let mut shared = Vec::new();
+    /// assert_eq!(cursor.encode_with(&mut shared, &options)?, 48);
+    /// let decoded = CursorFile::decode(Cursor::new(&shared))?;
+    /// assert_eq!(decoded[0].image_data, decoded[1].image_data);
+    ///
+    /// // Entries pointing backwards are followed as well
+    /// let mut swapped = shared.clone();
+    /// swapped[6..22].copy_from_slice(&shared[38..54]);
+    /// swapped[38..54].copy_from_slice(&shared[6..22]);
+    /// let decoded = CursorFile::decode(Cursor::new(swapped))?;
+    /// assert_eq!(decoded[0].image_data, cursor[2].image_data);
+    /// assert_eq!(decoded[2].image_data, cursor[0].image_data);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn encode_with<W: Write>(&self, writer: W, options: &EncodeOptions) -> io::Result<usize> {
+        self.encode_into(writer, options, None)
+    }
+
+    /// Where `encode_with` would put each part of the file, without
+    /// writing it
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, EncodeOptions, Section};
+    ///
+    /// let bytes = std::fs::read("assets/aero_busy.ani")?;
+    /// let cursor = AniFile::decode(Cursor::new(bytes))?.extract_frame(0)?;
+    /// let layout = cursor.layout(&EncodeOptions::default())?;
+    /// let mut encoded = Vec::new();
+    /// cursor.encode(&mut encoded)?;
+    /// assert_eq!(layout.total, encoded.len() as u64);
+    ///
+    /// let (offset, len) = layout.find(Section::Frame(1)).unwrap();
+    /// assert_eq!(offset, 6 + 3 * 16 + cursor[0].image_data.len() as u64);
+    /// assert_eq!(len, cursor[1].image_data.len() as u64);
+    /// assert_eq!(encoded[6 + 16 + 12..6 + 16 + 16], (offset as u32).to_le_bytes());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn layout(&self, options: &EncodeOptions) -> io::Result<FileLayout> {
+        let mut layout = FileLayout::default();
+        self.encode_into(io::sink(), options, Some(&mut layout))?;
+        Ok(layout)
+    }
+
+    fn encode_into<W: Write>(
+        &self,
+        writer: W,
+        options: &EncodeOptions,
+        layout: Option<&mut FileLayout>,
+    ) -> io::Result<usize> {
+        if let Some(profile) = &options.profile {
+            let options = EncodeOptions {
+                profile: None,
+                ..options.clone()
+            };
+            return self.conform(profile)?.encode_into(writer, &options, layout);
+        }
+        self.write_container(writer, ContainerKind::Cursor, options, layout)
+    }
+
+    /// Encode the frames as an icon (.ico)
+    ///
+    /// The container matches .cur except for the type word and the two
+    /// directory words that hold the hotspot; an icon stores planes (1)
+    /// and each payload's bit count there instead. Hotspots are dropped.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{CursorFile, CursorFrame, Hotspot};
+    /// use image::{ImageFormat, Rgba, RgbaImage};
+    ///
+    /// let image = RgbaImage::from_pixel(32, 32, Rgba([200, 0, 0, 255]));
+    /// let cursor = CursorFile::single(CursorFrame::from_image(&image, Hotspot::new(5, 7))?);
+    ///
+    /// let mut ico = Vec::new();
+    /// cursor.encode_as_ico(&mut ico)?;
+    /// assert_eq!(ico[2..4], [1, 0]); // type 1 (icon)
+    /// assert_eq!(ico[10..14], [1, 0, 32, 0]); // planes 1, 32 bits per pixel
+    ///
+    /// let image = image::load_from_memory_with_format(&ico, ImageFormat::Ico)
+    ///     .map_err(std::io::Error::other)?;
+    /// assert_eq!(image.to_rgba8(), cursor[0].decode_image()?);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn encode_as_ico<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_container(writer, ContainerKind::Icon, &EncodeOptions::default(), None)
+            .map(|_| ())
+    }
+
+    fn write_container<W: Write>(
+        &self,
+        mut writer: W,
+        kind: ContainerKind,
+        options: &EncodeOptions,
+        mut layout: Option<&mut FileLayout>,
+    ) -> io::Result<usize> {
+        let _span = trace::span!(DEBUG, "encode_cur");
         if self.frames.is_empty() {
             return Err(io::Error::new(io::ErrorKind::InvalidInput, "No frames"));
         }
 
         // Write header
-        writer.write_all(&[0, 0, 2, 0])?; // reserved=0, type=2 (cursor)
+        writer.write_all(&[0, 0, kind as u8, 0])?; // reserved=0, type=1 (icon) or 2 (cursor)
         writer.write_all(&(self.frames.len() as u16).to_le_bytes())?;
+        record(&mut layout, |layout| layout.push(Section::Header, 6));
 
         // Calculate directory size
         let dir_size = 6 + (self.frames.len() * 16);
         let mut offset = dir_size as u32;
 
+        // Each frame's payload, written at the first frame holding the same
+        // bytes, with the offset and index of that frame
+        let mut payloads: Vec<(&[u8], u32, usize)> = Vec::with_capacity(self.frames.len());
+        let mut saved = 0;
+
         // Write directory entries
-        for frame in &self.frames {
-            let width_byte = if frame.width == 256 {
-                0
-            } else {
-                frame.width as u8
+        for (index, frame) in self.frames.iter().enumerate() {
+            let (width, height) = (frame.size.width(), frame.size.height());
+            let [width_byte, height_byte] = match frame.size.to_dir_bytes() {
+                Some(bytes) => bytes,
+                None if frame.size.max_side() > MAX_FRAME_SIZE => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{} frame is over the {MAX_FRAME_SIZE}px limit", frame.size),
+                    ));
+                }
+                // Sides past 256 are stored as 0, leaving the size to the payload
+                None if payload_size(&frame.image_data) == Some((width, height)) => {
+                    [width, height].map(|side| u8::try_from(side).unwrap_or(0))
+                }
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "{} frame's payload doesn't declare that size, \
+                             which frames over {MAX_DIR_SIZE}px need",
+                            frame.size
+                        ),
+                    ));
+                }
             };
-            let height_byte = if frame.height == 256 {
-                0
-            } else {
-                frame.height as u8
+
+            let shared = payloads
+                .iter()
+                .find(|(data, ..)| options.share_payloads && *data == frame.image_data.as_slice());
+            let data_offset = match shared {
+                Some(&(_, shared_offset, _)) => {
+                    saved += frame.image_data.len();
+                    shared_offset
+                }
+                None => {
+                    let data_offset = offset;
+                    payloads.push((&frame.image_data, data_offset, index));
+                    offset += frame.image_data.len() as u32;
+                    data_offset
+                }
             };
 
-            writer.write_all(&[width_byte, height_byte, 0, 0])?; // width, height, colors, reserved
-            writer.write_all(&frame.hotspot_x.to_le_bytes())?;
-            writer.write_all(&frame.hotspot_y.to_le_bytes())?;
+            let colors = colors_byte(payload_bit_count(&frame.image_data));
+            writer.write_all(&[width_byte, height_byte, colors, 0])?; // The last byte is reserved
+            let (first, second) = match kind {
+                ContainerKind::Icon => (1, payload_bit_count(&frame.image_data) as u16),
+                ContainerKind::Cursor => (frame.hotspot.x, frame.hotspot.y),
+            };
+            writer.write_all(&first.to_le_bytes())?;
+            writer.write_all(&second.to_le_bytes())?;
             writer.write_all(&(frame.image_data.len() as u32).to_le_bytes())?;
-            writer.write_all(&offset.to_le_bytes())?;
-
-            offset += frame.image_data.len() as u32;
+            writer.write_all(&data_offset.to_le_bytes())?;
+            record(&mut layout, |layout| {
+                layout.push(Section::DirectoryEntry(index), 16)
+            });
+            trace::trace!(
+                %frame.size,
+                size = frame.image_data.len(),
+                offset = data_offset,
+                "directory entry"
+            );
         }
+        trace::debug!(
+            frames = self.frames.len(),
+            shared_bytes = saved,
+            "encoded cursor"
+        );
 
         // Write image data
-        for frame in &self.frames {
-            writer.write_all(&frame.image_data)?;
+        for &(data, _, index) in &payloads {
+            writer.write_all(data)?;
+            record(&mut layout, |layout| {
+                layout.push(Section::Frame(index), data.len() as u64)
+            });
         }
 
-        Ok(())
+        Ok(saved)
     }
 
     /// Decode cursor from reader
-    pub fn decode<R: Read + Seek>(mut reader: R) -> io::Result<Self> {
+    pub fn decode<R: Read + Seek>(reader: R) -> io::Result<Self> {
+        Self::decode_with(reader, &DecodeOptions::default()).map(|decoded| decoded.value)
+    }
+
+    /// Decode cursor from reader with default options, collecting
+    /// `DecodeStats` on the way
+    pub fn decode_with_stats<R: Read + Seek>(reader: R) -> io::Result<(Self, DecodeStats)> {
+        let options = DecodeOptions {
+            collect_stats: true,
+            ..DecodeOptions::default()
+        };
+        let decoded = Self::decode_with(reader, &options)?;
+        Ok((decoded.value, decoded.stats.unwrap_or_default()))
+    }
+
+    /// Decode cursor from reader, applying `options`
+    pub fn decode_with<R: Read + Seek>(
+        mut reader: R,
+        options: &DecodeOptions,
+    ) -> io::Result<Decoded<Self>> {
+        let _span = trace::span!(DEBUG, "decode_cur");
+        let mut diagnostics = Diagnostics::new(options);
+        diagnostics.check_decoded_size("header", &mut reader)?;
+
         // Read header
-        let mut header = [0u8; 6];
-        reader.read_exact(&mut header)?;
+        let header = read_chunk(&mut reader, "header", None, 0, 6)?;
 
-        if u16::from_le_bytes([header[2], header[3]]) != 2 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Not a cursor file",
-            ));
+        if header[0..2] != [0, 0] {
+            diagnostics.tolerate("header", 0, "Reserved header field is not zero")?;
         }
 
+        let is_icon = match u16::from_le_bytes([header[2], header[3]]) {
+            2 => false,
+            1 if options.allow_ico => true,
+            _ => return Err(invalid_chunk("header", None, 2, "Not a cursor file")),
+        };
+
         let count = u16::from_le_bytes([header[4], header[5]]) as usize;
         if count == 0 {
-            return Err(io::Error::new(io::ErrorKind::InvalidData, "No frames"));
+            return Err(invalid_chunk("header", None, 4, "No frames"));
         }
+        diagnostics.check_frames("header", 4, count)?;
 
         // Read directory entries
+        let dir_size = (6 + count * 16) as u64;
         let mut entries = Vec::with_capacity(count);
-        for _ in 0..count {
-            let mut entry = [0u8; 16];
-            reader.read_exact(&mut entry)?;
-
-            let width = if entry[0] == 0 { 256 } else { entry[0] as u32 };
-            let height = if entry[1] == 0 { 256 } else { entry[1] as u32 };
-            let hotspot_x = u16::from_le_bytes([entry[4], entry[5]]);
-            let hotspot_y = u16::from_le_bytes([entry[6], entry[7]]);
+        for index in 0..count {
+            let entry_offset = 6 + index as u64 * 16;
+            let entry = read_chunk(&mut reader, "directory", Some(index), entry_offset, 16)?;
+
+            let frame_size = FrameSize::from_dir_bytes([entry[0], entry[1]]);
+            // Icons keep planes and bit count where cursors keep the hotspot
+            let first = u16::from_le_bytes([entry[4], entry[5]]);
+            let second = u16::from_le_bytes([entry[6], entry[7]]);
+            let (hotspot, bit_count) = if is_icon {
+                // Placed once the payload's real size is known
+                (Hotspot::default(), second)
+            } else {
+                (Hotspot::new(first, second), 0)
+            };
             let size = u32::from_le_bytes([entry[8], entry[9], entry[10], entry[11]]);
             let offset = u32::from_le_bytes([entry[12], entry[13], entry[14], entry[15]]);
 
-            entries.push((width, height, hotspot_x, hotspot_y, size, offset));
+            if entry[3] != 0 {
+                diagnostics.tolerate(
+                    "directory",
+                    entry_offset + 3,
+                    "Reserved directory field is not zero",
+                )?;
+            }
+            if (offset as u64) < dir_size {
+                diagnostics.tolerate(
+                    "directory",
+                    entry_offset + 12,
+                    "Image data overlaps the directory",
+                )?;
+            }
+            diagnostics.check_size("directory", entry_offset + 8, size as usize)?;
+            trace::trace!(index, %frame_size, %hotspot, size, offset, "directory entry");
+
+            entries.push((frame_size, hotspot, bit_count, entry[2], size, offset));
+        }
+
+        // Bytes the header, directory and payloads don't cover count as skipped
+        if diagnostics.collecting_stats() {
+            let end = reader.seek(SeekFrom::End(0))?;
+            let mut ranges: Vec<(u64, u64)> = entries
+                .iter()
+                .map(|entry| (entry.5 as u64, entry.5 as u64 + entry.4 as u64))
+                .collect();
+            ranges.push((0, dir_size));
+            ranges.sort_unstable();
+            let (mut covered, mut reached) = (0, 0);
+            for (start, stop) in ranges {
+                let (start, stop) = (start.max(reached).min(end), stop.min(end));
+                covered += stop.saturating_sub(start);
+                reached = reached.max(stop);
+            }
+            diagnostics.skipped(end - covered);
         }
 
         // Read image data
         let mut frames = Vec::with_capacity(count);
-        for (width, height, hotspot_x, hotspot_y, size, offset) in entries {
-            reader.seek(SeekFrom::Start(offset as u64))?;
-            let mut image_data = vec![0u8; size as usize];
-            reader.read_exact(&mut image_data)?;
+        for (index, entry) in entries.into_iter().enumerate() {
+            let (frame_size, hotspot, bit_count, colors, size, offset) = entry;
+            let _frame = trace::span!(TRACE, "frame", index);
+            let read = reader
+                .seek(SeekFrom::Start(offset as u64))
+                .and_then(|_| {
+                    read_chunk(
+                        &mut reader,
+                        "image",
+                        Some(index),
+                        offset as u64,
+                        size as u64,
+                    )
+                })
+                .and_then(|mut image_data| {
+                    let expected = payload_len(&image_data);
+                    diagnostics.check_payload(
+                        "image",
+                        Some(index),
+                        offset as u64,
+                        &mut image_data,
+                        expected,
+                    )?;
+                    Ok(image_data)
+                });
+            let image_data = match (read, options.frame_error_policy) {
+                (Ok(image_data), _) => image_data,
+                (Err(err), FrameErrorPolicy::Fail) => return Err(err),
+                (Err(err), FrameErrorPolicy::Skip) => {
+                    diagnostics.note(offset as u64, format!("Skipped frame {index}: {err}"));
+                    continue;
+                }
+                (Err(err), FrameErrorPolicy::Placeholder) => {
+                    diagnostics.note(
+                        offset as u64,
+                        format!("Replaced frame {index} with a blank {frame_size} frame: {err}"),
+                    );
+                    let (width, height) = (frame_size.width(), frame_size.height());
+                    let hotspot = match is_icon {
+                        true => options.icon_hotspot.resolve(width, height),
+                        false => hotspot,
+                    };
+                    let blank = RgbaImage::new(width, height);
+                    frames.push(CursorFrame::from_image(&blank, hotspot)?);
+                    continue;
+                }
+            };
 
+            diagnostics.payload(&image_data);
+            let depth = payload_bit_count(&image_data);
+            if colors != 0 && colors != colors_byte(depth) {
+                diagnostics.tolerate_frame(
+                    "directory",
+                    Some(index),
+                    6 + index as u64 * 16 + 2,
+                    format!("Colors byte {colors} disagrees with the {depth}-bit payload"),
+                )?;
+            }
+            let bit_count = match bit_count {
+                0 => depth as u16,
+                declared => declared,
+            };
+            let size = stored_size(frame_size, &image_data);
+            let hotspot = match is_icon {
+                true => options.icon_hotspot.resolve(size.width(), size.height()),
+                false => hotspot,
+            };
             frames.push(CursorFrame {
-                width,
-                height,
-                hotspot_x,
-                hotspot_y,
+                size,
+                hotspot,
+                bit_count,
                 image_data,
             });
         }
 
-        Ok(Self { frames })
+        if frames.is_empty() {
+            return Err(invalid_chunk("image", None, 6, "No readable frames"));
+        }
+        trace::debug!(frames = frames.len(), is_icon, "decoded cursor");
+        let kind = if is_icon {
+            ContainerKind::Icon
+        } else {
+            ContainerKind::Cursor
+        };
+        Ok(diagnostics.finish(Self { frames, kind }))
+    }
+}
+
+/// The type word of a .cur/.ico header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum ContainerKind {
+    Icon = 1,
+    #[default]
+    Cursor = 2,
+}
+
+impl Index<usize> for CursorFile {
+    type Output = CursorFrame;
+
+    fn index(&self, index: usize) -> &CursorFrame {
+        &self.frames[index]
+    }
+}
+
+impl IndexMut<usize> for CursorFile {
+    fn index_mut(&mut self, index: usize) -> &mut CursorFrame {
+        &mut self.frames[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a CursorFile {
+    type Item = &'a CursorFrame;
+    type IntoIter = slice::Iter<'a, CursorFrame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut CursorFile {
+    type Item = &'a mut CursorFrame;
+    type IntoIter = slice::IterMut<'a, CursorFrame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.iter_mut()
+    }
+}
+
+/// The directory size, or the payload header's own size where the
+/// directory's 256 stands for a larger image, up to `MAX_FRAME_SIZE`
+pub(crate) fn stored_size(dir_size: FrameSize, data: &[u8]) -> FrameSize {
+    match payload_size(data) {
+        Some((width, height))
+            if dir_size.max_side() == MAX_DIR_SIZE
+                && (MAX_DIR_SIZE + 1..=MAX_FRAME_SIZE).contains(&width.max(height)) =>
+        {
+            FrameSize::new(width, height).unwrap_or(dir_size)
+        }
+        _ => dir_size,
+    }
+}
+
+/// Pixel size in a PNG's IHDR chunk
+pub(crate) fn png_size(data: &[u8]) -> Option<(u32, u32)> {
+    if !data.starts_with(&PNG_SIGNATURE) {
+        return None;
+    }
+    let width = u32::from_be_bytes(data.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(data.get(20..24)?.try_into().ok()?);
+    Some((width, height))
+}
+
+/// A PNG payload up to the end of its IEND chunk
+pub(crate) fn png_data(data: &[u8]) -> Option<&[u8]> {
+    if !data.starts_with(&PNG_SIGNATURE) {
+        return None;
+    }
+    data.get(..payload_len(data)?)
+}
+
+/// Pixel size stored in a PNG IHDR or a BITMAPINFOHEADER (whose height covers
+/// both the color and mask planes)
+pub(crate) fn payload_size(data: &[u8]) -> Option<(u32, u32)> {
+    png_size(data).or_else(|| {
+        let info = BmpInfo::parse(data)?;
+        Some((info.image_width(), info.image_height()))
+    })
+}
+
+/// Bits per pixel of a PNG or DIB payload, 0 if it can't be read
+pub(crate) fn payload_bit_count(data: &[u8]) -> u32 {
+    if data.starts_with(&PNG_SIGNATURE) {
+        // IHDR bit depth and color type
+        let channels = match data.get(25) {
+            Some(0 | 3) => 1,
+            Some(4) => 2,
+            Some(2) => 3,
+            Some(6) => 4,
+            _ => 0,
+        };
+        return data.get(24).map_or(0, |&depth| depth as u32 * channels);
     }
+    data.get(14..16)
+        .map_or(0, |bits| u16::from_le_bytes([bits[0], bits[1]]) as u32)
+}
+
+/// The directory's colors byte for a payload of `bit_count` bits: the palette
+/// size below 8 bits, otherwise 0
+pub(crate) fn colors_byte(bit_count: u32) -> u8 {
+    match bit_count {
+        1..8 => 1 << bit_count,
+        _ => 0,
+    }
+}
+
+/// Byte length of a PNG (through IEND) or an uncompressed DIB with its mask
+pub(crate) fn payload_len(data: &[u8]) -> Option<usize> {
+    if data.starts_with(&PNG_SIGNATURE) {
+        let mut pos = PNG_SIGNATURE.len();
+        loop {
+            let length = u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+            let kind = data.get(pos + 4..pos + 8)?;
+            pos = pos.checked_add(12 + length)?;
+            if kind == b"IEND" {
+                return Some(pos);
+            }
+        }
+    }
+
+    BmpInfo::parse(data)?.expected_len()
+}
+
+/// Byte length of a .cur/.ico container, up to the end of its last image
+pub(crate) fn container_len(data: &[u8]) -> Option<usize> {
+    let count = u16::from_le_bytes(data.get(4..6)?.try_into().ok()?) as usize;
+    (0..count)
+        .map(|index| {
+            let entry = data.get(6 + index * 16..6 + (index + 1) * 16)?;
+            let size = u32::from_le_bytes(entry[8..12].try_into().ok()?) as usize;
+            let offset = u32::from_le_bytes(entry[12..16].try_into().ok()?) as usize;
+            offset.checked_add(size)
+        })
+        .try_fold(6 + count * 16, |end, image_end| Some(end.max(image_end?)))
 }
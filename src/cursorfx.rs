@@ -0,0 +1,290 @@
+// Importer for Stardock CursorFX themes (.cursorfx / .curxptheme)
+//
+// Container layout, all integers little-endian:
+//
+//   u32 version, u32 header size, u32 unpacked size, reserved up to header size
+//   zlib stream holding a list of records: u32 kind, u32 id, u32 length, payload
+//
+// Kind 1 records carry the script, kind 2 records carry strip bitmaps (PNG or
+// BMP). The script is INI-like, with a `[General]` section and one section per
+// cursor role:
+//
+//   [Arrow]
+//   Image=2
+//   Frames=8
+//   Interval=50
+//   HotspotX=0
+//   HotspotY=0
+//   Direction=Horizontal
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Debug},
+    io::{self, Read},
+};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use flate2::read::ZlibDecoder;
+use image::RgbaImage;
+
+use crate::{
+    ani::{AniFile, ms_to_jiffies},
+    cur::{CursorFile, CursorFrame},
+    debug::FramesSummary,
+    generate::cursor_frame,
+    hotspot::Hotspot,
+    optimize::encode_png,
+    size::FrameSize,
+};
+
+const RECORD_SCRIPT: u32 = 1;
+const RECORD_IMAGE: u32 = 2;
+
+/// A decoded CursorFX theme
+#[derive(Debug, Clone)]
+pub struct CursorFxTheme {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub cursors: Vec<FxCursor>,
+    pub extra: BTreeMap<String, String>, // Unrecognised [General] keys
+}
+
+/// One cursor role of a theme, with its strip already sliced into frames
+#[derive(Clone)]
+pub struct FxCursor {
+    pub role: String, // Script section name, e.g. "Arrow"
+    pub frames: Vec<RgbaImage>,
+    pub hotspot: Hotspot,
+    pub interval_ms: u32,
+    pub extra: BTreeMap<String, String>, // Unrecognised keys of the section
+}
+
+impl CursorFxTheme {
+    pub fn decode<R: Read>(mut reader: R) -> io::Result<Self> {
+        let _version = reader.read_u32::<LittleEndian>()?;
+        let header_size = reader.read_u32::<LittleEndian>()?;
+        let unpacked_size = reader.read_u32::<LittleEndian>()?;
+        if header_size < 12 {
+            return Err(invalid_data("CursorFX header is too short"));
+        }
+        io::copy(
+            &mut (&mut reader).take(u64::from(header_size) - 12),
+            &mut io::sink(),
+        )?;
+
+        // Stop one byte past the declared size so a zip bomb cannot exhaust memory
+        let mut data = Vec::new();
+        ZlibDecoder::new(reader)
+            .take(u64::from(unpacked_size) + 1)
+            .read_to_end(&mut data)?;
+        if data.len() != unpacked_size as usize {
+            return Err(invalid_data("CursorFX payload size does not match header"));
+        }
+
+        let mut script = None;
+        let mut images = HashMap::new();
+        let mut records = data.as_slice();
+        while !records.is_empty() {
+            let kind = records.read_u32::<LittleEndian>()?;
+            let id = records.read_u32::<LittleEndian>()?;
+            let length = records.read_u32::<LittleEndian>()? as usize;
+            if length > records.len() {
+                return Err(invalid_data("CursorFX record extends past the payload"));
+            }
+            let (payload, rest) = records.split_at(length);
+            records = rest;
+
+            match kind {
+                RECORD_SCRIPT => script = Some(decode_text(payload)),
+                RECORD_IMAGE => {
+                    images.insert(id, payload);
+                }
+                _ => {} // Sounds and effects are not supported
+            }
+        }
+
+        let script = script.ok_or_else(|| invalid_data("CursorFX theme has no script"))?;
+        let mut theme = CursorFxTheme {
+            name: None,
+            author: None,
+            cursors: Vec::new(),
+            extra: BTreeMap::new(),
+        };
+
+        for (section, mut keys) in parse_script(&script) {
+            if section.eq_ignore_ascii_case("General") {
+                theme.name = keys.remove("name");
+                theme.author = keys.remove("author");
+                theme.extra = keys;
+                continue;
+            }
+
+            let image_id = take_number(&mut keys, "image", &section)?
+                .ok_or_else(|| invalid_data(format!("[{section}] has no Image key")))?;
+            let strip = images.get(&image_id).ok_or_else(|| {
+                invalid_data(format!("[{section}] refers to missing image {image_id}"))
+            })?;
+            let strip = image::load_from_memory(strip)
+                .map_err(io::Error::other)?
+                .to_rgba8();
+
+            let count = take_number(&mut keys, "frames", &section)?.unwrap_or(1);
+            let vertical = keys
+                .remove("direction")
+                .is_some_and(|direction| direction.eq_ignore_ascii_case("vertical"));
+            let frames = slice_strip(&strip, count, vertical).ok_or_else(|| {
+                invalid_data(format!("[{section}] strip cannot hold {count} frames"))
+            })?;
+
+            let hotspot = |value: Option<u32>| {
+                u16::try_from(value.unwrap_or(0))
+                    .map_err(|_| invalid_data(format!("[{section}] hotspot is out of range")))
+            };
+            let hotspot = Hotspot::new(
+                hotspot(take_number(&mut keys, "hotspotx", &section)?)?,
+                hotspot(take_number(&mut keys, "hotspoty", &section)?)?,
+            );
+            let interval_ms = take_number(&mut keys, "interval", &section)?.unwrap_or(0);
+
+            theme.cursors.push(FxCursor {
+                role: section,
+                frames,
+                hotspot,
+                interval_ms,
+                extra: keys,
+            });
+        }
+
+        Ok(theme)
+    }
+
+    /// Look up a cursor by its script section name, ignoring case
+    pub fn cursor(&self, role: &str) -> Option<&FxCursor> {
+        self.cursors
+            .iter()
+            .find(|cursor| cursor.role.eq_ignore_ascii_case(role))
+    }
+}
+
+impl Debug for FxCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Frame dimensions only, the pixels would flood the output
+        let sizes: Vec<_> = self.frames.iter().map(RgbaImage::dimensions).collect();
+        f.debug_struct("FxCursor")
+            .field("role", &self.role)
+            .field("frames", &FramesSummary(&sizes))
+            .field("hotspot", &self.hotspot)
+            .field("interval_ms", &self.interval_ms)
+            .field("extra", &self.extra)
+            .finish()
+    }
+}
+
+impl FxCursor {
+    pub fn is_animated(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    /// The first frame as a single-image .cur
+    pub fn to_cursor_file(&self) -> io::Result<CursorFile> {
+        let image = self
+            .frames
+            .first()
+            .ok_or_else(|| invalid_data("cursor has no frames"))?;
+        Ok(CursorFile::single(CursorFrame::new(
+            FrameSize::new(image.width(), image.height())?,
+            self.hotspot,
+            encode_png(image)?,
+        )))
+    }
+
+    /// All frames as an ANI, with the interval rounded to whole jiffies
+    pub fn to_ani(&self) -> io::Result<AniFile> {
+        let frames = self
+            .frames
+            .iter()
+            .map(|image| cursor_frame(image, self.hotspot))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        AniFile::new(frames).with_default_rate(ms_to_jiffies(self.interval_ms))
+    }
+}
+
+/// Split the script into sections of lowercase keys, keeping section order
+fn parse_script(script: &str) -> Vec<(String, BTreeMap<String, String>)> {
+    let mut sections: Vec<(String, BTreeMap<String, String>)> = Vec::new();
+    for line in script.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            sections.push((name.trim().to_string(), BTreeMap::new()));
+        } else if let Some((key, value)) = line.split_once('=')
+            && let Some((_, keys)) = sections.last_mut()
+        {
+            keys.insert(key.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+    sections
+}
+
+fn take_number(
+    keys: &mut BTreeMap<String, String>,
+    key: &str,
+    section: &str,
+) -> io::Result<Option<u32>> {
+    keys.remove(key)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| invalid_data(format!("[{section}] {key}={value} is not a number")))
+        })
+        .transpose()
+}
+
+fn slice_strip(strip: &RgbaImage, count: u32, vertical: bool) -> Option<Vec<RgbaImage>> {
+    let (width, height) = if vertical {
+        (strip.width(), strip.height().checked_div(count)?)
+    } else {
+        (strip.width().checked_div(count)?, strip.height())
+    };
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let frames = (0..count)
+        .map(|index| {
+            let (x, y) = if vertical {
+                (0, index * height)
+            } else {
+                (index * width, 0)
+            };
+            image::imageops::crop_imm(strip, x, y, width, height).to_image()
+        })
+        .collect();
+    Some(frames)
+}
+
+/// Scripts are UTF-16LE when they start with a BOM, UTF-8 or Latin-1 otherwise
+fn decode_text(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(&[0xFF, 0xFE]) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => match std::str::from_utf8(bytes) {
+            Ok(text) => text.to_string(),
+            Err(_) => bytes.iter().map(|&byte| byte as char).collect(),
+        },
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
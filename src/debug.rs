@@ -0,0 +1,42 @@
+// Compact Debug helpers so frames don't print their whole payload
+use std::fmt::{self, Debug};
+
+use crate::manifest::PayloadKind;
+
+/// Frame lists longer than this show only their first and last entries
+const MAX_LISTED_FRAMES: usize = 8;
+
+/// Formats a payload as `[4096 bytes, png, fnv:1a2b3c4d]`
+pub(crate) struct BytesSummary<'a>(pub &'a [u8]);
+
+impl Debug for BytesSummary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match PayloadKind::sniff(self.0) {
+            PayloadKind::Png => "png",
+            PayloadKind::Dib => "dib",
+            PayloadKind::Cursor => "cur",
+        };
+        // FNV-1a, enough to tell payloads apart at a glance
+        let hash = self.0.iter().fold(0x811c_9dc5_u32, |hash, &byte| {
+            (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+        });
+        write!(f, "[{} bytes, {kind}, fnv:{hash:08x}]", self.0.len())
+    }
+}
+
+/// Formats a frame list in full, or as first, count and last when long
+pub(crate) struct FramesSummary<'a, T>(pub &'a [T]);
+
+impl<T: Debug> Debug for FramesSummary<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            [first, .., last] if self.0.len() > MAX_LISTED_FRAMES => f
+                .debug_list()
+                .entry(first)
+                .entry(&format_args!("... {} more ...", self.0.len() - 2))
+                .entry(last)
+                .finish(),
+            frames => f.debug_list().entries(frames).finish(),
+        }
+    }
+}
@@ -0,0 +1,595 @@
+// Options shared by `CursorFile::decode_with` and `AniFile::decode_with`
+use std::{
+    fmt::Display,
+    io::{self, Read, Seek, SeekFrom},
+    time::Duration,
+};
+
+use crate::{
+    estimate::estimate_from_directory, hotspot::HotspotPolicy, manifest::PayloadKind, riff::FourCC,
+    trace,
+};
+
+/// How to treat input that is malformed but still usable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecodeMode {
+    /// Fail with the byte offset of the first irregularity
+    Strict,
+    /// Carry on, recording each irregularity as a warning
+    #[default]
+    Lenient,
+}
+
+/// What to do with a frame whose data can't be read
+///
+/// Covers `.ani` icon chunks and `.cur` directory entries. Skipped and
+/// replaced frames are always recorded as warnings, in either `DecodeMode`.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::{AniFile, CursorFile, DecodeOptions, FrameErrorPolicy};
+///
+/// let aero = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+/// let mut ani = AniFile::new(aero.frames().take(3).cloned().collect());
+/// ani = ani.with_sequence(vec![0, 1, 2, 1]);
+/// ani.rates = vec![1, 2, 3, 4];
+/// let mut bytes = Vec::new();
+/// ani.encode(Cursor::new(&mut bytes))?;
+/// // Break the type word of the middle frame's container
+/// let second = bytes.windows(4).enumerate().filter(|(_, id)| *id == b"icon").nth(1);
+/// let at = second.unwrap().0 + 8 + 2;
+/// bytes[at] = 9;
+///
+/// let decode = |frame_error_policy| {
+///     let options = DecodeOptions { frame_error_policy, ..DecodeOptions::lenient() };
+///     AniFile::decode_with(Cursor::new(&bytes), &options)
+/// };
+/// assert!(decode(FrameErrorPolicy::Fail).is_err());
+///
+/// let skipped = decode(FrameErrorPolicy::Skip)?;
+/// assert_eq!((skipped.value.num_frames(), &skipped.value.sequence[..]), (2, &[0, 1][..]));
+/// assert_eq!(skipped.value.rates, [1, 3]);
+/// assert_eq!(skipped.warnings.len(), 3); // The frame and the two steps playing it
+///
+/// let filled = decode(FrameErrorPolicy::Placeholder)?.value;
+/// assert_eq!((filled.num_frames(), filled.num_steps()), (3, 4));
+/// let blank = filled[1].decode_image()?;
+/// assert_eq!((blank.width(), blank.height()), (ani.header.width(), ani.header.height()));
+/// assert!(blank.pixels().all(|pixel| pixel[3] == 0));
+///
+/// // A .cur entry pointing past the end of the file
+/// let mut cur = Vec::new();
+/// aero.extract_frame(0)?.encode(&mut cur)?;
+/// cur[6 + 16 + 12..6 + 16 + 16].copy_from_slice(&u32::MAX.to_le_bytes());
+/// let decode = |frame_error_policy| {
+///     let options = DecodeOptions { frame_error_policy, ..Default::default() };
+///     CursorFile::decode_with(Cursor::new(&cur), &options).map(|decoded| decoded.value)
+/// };
+/// assert!(decode(FrameErrorPolicy::Fail).is_err());
+/// assert_eq!(decode(FrameErrorPolicy::Skip)?.frames().count(), 2);
+/// let filled = decode(FrameErrorPolicy::Placeholder)?;
+/// assert_eq!(filled.frames().count(), 3);
+/// assert!(filled[1].decode_image()?.pixels().all(|pixel| pixel[3] == 0));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameErrorPolicy {
+    /// Fail the whole decode
+    #[default]
+    Fail,
+    /// Drop the frame, along with the `.ani` steps that play it
+    Skip,
+    /// Put a transparent frame in its place, keeping every step
+    Placeholder,
+}
+
+/// Caps on what a single file may make the decoder allocate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    pub max_frames: usize,
+    pub max_chunk_size: usize, // Largest chunk or image payload, in bytes
+    /// Largest `estimate::estimate_from_directory` total, checked before
+    /// any payload is read
+    pub max_decoded_size: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_frames: usize::MAX,
+            max_chunk_size: usize::MAX,
+            max_decoded_size: usize::MAX,
+        }
+    }
+}
+
+/// The defaults match plain `decode`
+#[derive(Debug, Clone, Default)]
+pub struct DecodeOptions {
+    pub mode: DecodeMode,
+    pub allow_ico: bool, // Accept type 1 (icon) containers
+    /// Where frames of an icon, which hold no hotspot, get theirs; this
+    /// covers `.ani` frames that embed icons too
+    pub icon_hotspot: HotspotPolicy,
+    pub frame_error_policy: FrameErrorPolicy,
+    pub limits: DecodeLimits,
+    pub collect_warnings: bool,
+    pub trim_payloads: bool, // Drop trailing junk after a frame's image data
+    /// Fill in `Decoded::stats`; the decoded value is the same either way
+    pub collect_stats: bool,
+}
+
+impl DecodeOptions {
+    pub fn strict() -> Self {
+        Self {
+            mode: DecodeMode::Strict,
+            ..Self::default()
+        }
+    }
+
+    pub fn lenient() -> Self {
+        Self {
+            mode: DecodeMode::Lenient,
+            collect_warnings: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Something lenient decoding tolerated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeWarning {
+    pub offset: u64,
+    pub message: String,
+}
+
+impl Display for DecodeWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+/// A decoded value with the warnings collected along the way
+#[derive(Debug, Clone)]
+pub struct Decoded<T> {
+    pub value: T,
+    pub warnings: Vec<DecodeWarning>,
+    pub stats: Option<DecodeStats>, // With `DecodeOptions::collect_stats`
+}
+
+/// What a decoder saw in one file, for triaging a corpus
+///
+/// Warnings are kept here whatever `collect_warnings` says. `elapsed` stays
+/// zero on `wasm32-unknown-unknown`, which has no clock.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::{AniFile, CursorFile, PayloadKind};
+///
+/// let bytes = std::fs::read("assets/aero_busy.ani")?;
+/// let (ani, stats) = AniFile::decode_with_stats(Cursor::new(&bytes))?;
+/// assert_eq!(ani.num_frames(), 18);
+/// assert_eq!(stats.chunks_seen, [(*b"anih", 1), (*b"LIST", 1), (*b"icon", 18)]);
+/// assert_eq!((stats.unknown_chunks, stats.bytes_skipped), (0, 0));
+/// assert!(stats.frame_payload_kinds.iter().all(|kind| *kind == PayloadKind::Cursor));
+/// let summary = "20 chunks, 0 unknown, 0 bytes skipped, 18 frames (18 cursor), 0 warnings";
+/// assert!(stats.to_string().starts_with(summary));
+///
+/// // Trailing bytes after the last image of a .cur are skipped
+/// let mut cur = Vec::new();
+/// ani.extract_frame(0)?.encode(&mut cur)?;
+/// cur.extend_from_slice(&[0; 10]);
+/// let (_, stats) = CursorFile::decode_with_stats(Cursor::new(&cur))?;
+/// assert!(stats.chunks_seen.is_empty());
+/// assert_eq!(stats.bytes_skipped, 10);
+/// assert_eq!(stats.frame_payload_kinds, [PayloadKind::Dib; 3]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodeStats {
+    /// Each RIFF chunk id as stored, nested ones included, with how often
+    /// it occurred, in order of first appearance; empty for .cur files
+    pub chunks_seen: Vec<(FourCC, u32)>,
+    /// Bytes read past without becoming part of the decoded file
+    pub bytes_skipped: u64,
+    pub unknown_chunks: u32,
+    pub warnings: Vec<DecodeWarning>,
+    /// How each frame read was stored, in file order
+    pub frame_payload_kinds: Vec<PayloadKind>,
+    pub elapsed: Duration,
+}
+
+impl Display for DecodeStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let chunks: u32 = self.chunks_seen.iter().map(|&(_, count)| count).sum();
+        write!(
+            f,
+            "{chunks} chunks, {} unknown, {} bytes skipped, {} frames",
+            self.unknown_chunks,
+            self.bytes_skipped,
+            self.frame_payload_kinds.len()
+        )?;
+        let mut separator = " (";
+        for kind in [PayloadKind::Png, PayloadKind::Dib, PayloadKind::Cursor] {
+            let count = self
+                .frame_payload_kinds
+                .iter()
+                .filter(|k| **k == kind)
+                .count();
+            if count > 0 {
+                write!(
+                    f,
+                    "{separator}{count} {}",
+                    format!("{kind:?}").to_lowercase()
+                )?;
+                separator = ", ";
+            }
+        }
+        if separator == ", " {
+            f.write_str(")")?;
+        }
+        write!(
+            f,
+            ", {} warnings in {:.2?}",
+            self.warnings.len(),
+            self.elapsed
+        )
+    }
+}
+
+/// Where decoding failed, carried as the payload of the returned `io::Error`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A chunk or structure ended before its declared size
+    TruncatedChunk {
+        id: String,
+        index: Option<usize>, // Frame index, for per-frame structures
+        at: u64,
+        expected: u64,
+        got: u64,
+    },
+    /// A chunk or structure is present but unusable
+    InvalidChunk {
+        id: String,
+        index: Option<usize>,
+        at: u64,
+        message: String,
+    },
+}
+
+impl DecodeError {
+    /// The `DecodeError` inside an error returned by a decoder, if any
+    pub fn find(err: &io::Error) -> Option<&Self> {
+        err.get_ref()?.downcast_ref()
+    }
+
+    pub fn offset(&self) -> u64 {
+        match self {
+            Self::TruncatedChunk { at, .. } | Self::InvalidChunk { at, .. } => *at,
+        }
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (id, index, at) = match self {
+            Self::TruncatedChunk { id, index, at, .. }
+            | Self::InvalidChunk { id, index, at, .. } => (id, index, at),
+        };
+        write!(f, "'{id}'")?;
+        if let Some(index) = index {
+            write!(f, " #{index}")?;
+        }
+        write!(f, " at {at:#X}: ")?;
+
+        match self {
+            Self::TruncatedChunk { expected, got, .. } => {
+                write!(f, "truncated, expected {expected} bytes, got {got}")
+            }
+            Self::InvalidChunk { message, .. } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<DecodeError> for io::Error {
+    fn from(err: DecodeError) -> Self {
+        let kind = match err {
+            DecodeError::TruncatedChunk { .. } => io::ErrorKind::UnexpectedEof,
+            DecodeError::InvalidChunk { .. } => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+pub(crate) fn invalid_chunk(
+    id: &str,
+    index: Option<usize>,
+    at: u64,
+    message: impl Into<String>,
+) -> io::Error {
+    DecodeError::InvalidChunk {
+        id: id.to_string(),
+        index,
+        at,
+        message: message.into(),
+    }
+    .into()
+}
+
+pub(crate) fn truncated_chunk(
+    id: &str,
+    index: Option<usize>,
+    at: u64,
+    expected: u64,
+    got: u64,
+) -> io::Error {
+    DecodeError::TruncatedChunk {
+        id: id.to_string(),
+        index,
+        at,
+        expected,
+        got,
+    }
+    .into()
+}
+
+/// Read exactly `len` bytes, reporting a short read as `TruncatedChunk`
+pub(crate) fn read_chunk<R: Read>(
+    reader: &mut R,
+    id: &str,
+    index: Option<usize>,
+    at: u64,
+    len: u64,
+) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(len).read_to_end(&mut data)?;
+    if (data.len() as u64) < len {
+        return Err(truncated_chunk(id, index, at, len, data.len() as u64));
+    }
+    Ok(data)
+}
+
+/// A fourcc as text, for messages
+pub(crate) fn fourcc(id: &[u8]) -> String {
+    String::from_utf8_lossy(id).into_owned()
+}
+
+/// Applies `DecodeOptions` while a decoder runs
+pub(crate) struct Diagnostics<'a> {
+    options: &'a DecodeOptions,
+    warnings: Vec<DecodeWarning>,
+    stats: Option<(DecodeStats, Clock)>,
+}
+
+impl<'a> Diagnostics<'a> {
+    pub(crate) fn new(options: &'a DecodeOptions) -> Self {
+        Self {
+            options,
+            warnings: Vec::new(),
+            stats: options
+                .collect_stats
+                .then(|| (DecodeStats::default(), Clock::start())),
+        }
+    }
+
+    pub(crate) fn collecting_stats(&self) -> bool {
+        self.stats.is_some()
+    }
+
+    /// Count a chunk the walk reached
+    pub(crate) fn chunk(&mut self, id: FourCC) {
+        if let Some((stats, _)) = &mut self.stats {
+            match stats.chunks_seen.iter_mut().find(|(seen, _)| *seen == id) {
+                Some((_, count)) => *count += 1,
+                None => stats.chunks_seen.push((id, 1)),
+            }
+        }
+    }
+
+    /// Count a chunk the decoder doesn't understand
+    pub(crate) fn unknown_chunk(&mut self) {
+        if let Some((stats, _)) = &mut self.stats {
+            stats.unknown_chunks += 1;
+        }
+    }
+
+    pub(crate) fn skipped(&mut self, bytes: u64) {
+        if let Some((stats, _)) = &mut self.stats {
+            stats.bytes_skipped += bytes;
+        }
+    }
+
+    /// Record the storage of a frame that was read
+    pub(crate) fn payload(&mut self, data: &[u8]) {
+        if let Some((stats, _)) = &mut self.stats {
+            stats.frame_payload_kinds.push(PayloadKind::sniff(data));
+        }
+    }
+
+    fn warn(&mut self, offset: u64, message: String) {
+        if let Some((stats, _)) = &mut self.stats {
+            stats.warnings.push(DecodeWarning {
+                offset,
+                message: message.clone(),
+            });
+        }
+        if self.options.collect_warnings {
+            self.warnings.push(DecodeWarning { offset, message });
+        }
+    }
+
+    pub(crate) fn options(&self) -> &'a DecodeOptions {
+        self.options
+    }
+
+    /// Record something worth knowing that isn't an irregularity, in either
+    /// mode
+    pub(crate) fn note(&mut self, offset: u64, message: impl Into<String>) {
+        let message = message.into();
+        trace::warning!(offset, "{message}");
+        self.warn(offset, message);
+    }
+
+    /// Fail in strict mode, otherwise note the problem and continue
+    pub(crate) fn tolerate(
+        &mut self,
+        id: &str,
+        offset: u64,
+        message: impl Into<String>,
+    ) -> io::Result<()> {
+        self.tolerate_frame(id, None, offset, message)
+    }
+
+    pub(crate) fn tolerate_frame(
+        &mut self,
+        id: &str,
+        index: Option<usize>,
+        offset: u64,
+        message: impl Into<String>,
+    ) -> io::Result<()> {
+        let message = message.into();
+        match self.options.mode {
+            DecodeMode::Strict => Err(invalid_chunk(id, index, offset, message)),
+            DecodeMode::Lenient => {
+                trace::warning!(id, ?index, offset, "{message}");
+                self.warn(offset, message);
+                Ok(())
+            }
+        }
+    }
+
+    /// Compare a payload with the length its own headers imply, trimming
+    /// trailing junk if the options ask for it
+    pub(crate) fn check_payload(
+        &mut self,
+        id: &str,
+        index: Option<usize>,
+        offset: u64,
+        data: &mut Vec<u8>,
+        expected: Option<usize>,
+    ) -> io::Result<()> {
+        let Some(expected) = expected else {
+            return Ok(());
+        };
+        if expected < data.len() {
+            let junk = data.len() - expected;
+            self.tolerate_frame(id, index, offset, format!("{junk} bytes of trailing data"))?;
+            if self.options.trim_payloads {
+                data.truncate(expected);
+                self.skipped(junk as u64);
+            }
+        } else if expected > data.len() {
+            self.tolerate_frame(
+                id,
+                index,
+                offset,
+                format!("{expected} byte image declared as {} bytes", data.len()),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_frames(&self, id: &str, offset: u64, count: usize) -> io::Result<()> {
+        if count > self.options.limits.max_frames {
+            return Err(invalid_chunk(
+                id,
+                None,
+                offset,
+                format!(
+                    "{count} frames exceed the limit of {}",
+                    self.options.limits.max_frames
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_size(&self, id: &str, offset: u64, size: usize) -> io::Result<()> {
+        if size > self.options.limits.max_chunk_size {
+            return Err(invalid_chunk(
+                id,
+                None,
+                offset,
+                format!(
+                    "{size} bytes exceed the limit of {}",
+                    self.options.limits.max_chunk_size
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Estimate the file at the reader's position from its headers and
+    /// refuse it if it would decode to more than `max_decoded_size`
+    ///
+    /// Files the estimate can't read are left for the decoder to report.
+    pub(crate) fn check_decoded_size<R: Read + Seek>(
+        &self,
+        id: &str,
+        reader: &mut R,
+    ) -> io::Result<()> {
+        let limit = self.options.limits.max_decoded_size;
+        if limit == usize::MAX {
+            return Ok(());
+        }
+        let start = reader.stream_position()?;
+        let estimate = estimate_from_directory(&mut *reader);
+        reader.seek(SeekFrom::Start(start))?;
+        match estimate {
+            Ok(estimate) if estimate.total() > limit => Err(invalid_chunk(
+                id,
+                None,
+                0,
+                format!(
+                    "decoding needs about {} bytes, over the limit of {limit}",
+                    estimate.total()
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn finish<T>(self, value: T) -> Decoded<T> {
+        Decoded {
+            value,
+            warnings: self.warnings,
+            stats: self.stats.map(|(stats, clock)| DecodeStats {
+                elapsed: clock.elapsed(),
+                ..stats
+            }),
+        }
+    }
+}
+
+/// `Instant`, where the target has a clock
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+struct Clock(std::time::Instant);
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl Clock {
+    fn start() -> Self {
+        Self(std::time::Instant::now())
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+struct Clock;
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+impl Clock {
+    fn start() -> Self {
+        Self
+    }
+
+    fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
@@ -0,0 +1,804 @@
+// Read-only view of a DIB payload's BITMAPINFOHEADER
+use std::{
+    fmt::{self, Debug, Display},
+    io,
+};
+
+use image::{Rgba, RgbaImage};
+
+use crate::cur::{CursorFrame, PNG_SIGNATURE};
+
+/// `biCompression` values
+pub const BI_RGB: u32 = 0;
+pub const BI_RLE8: u32 = 1;
+pub const BI_RLE4: u32 = 2;
+pub const BI_BITFIELDS: u32 = 3;
+pub const BI_ALPHABITFIELDS: u32 = 6;
+
+/// Order of the rows in a DIB plane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowOrder {
+    /// The last row is stored first, the usual case (positive `biHeight`)
+    BottomUp,
+    /// Negative `biHeight`
+    TopDown,
+}
+
+/// Where each channel sits in a 16- or 32-bit pixel; a zero mask means the
+/// channel isn't stored
+///
+/// ```
+/// use cursor_handler::{ChannelMasks, CursorFrame, FrameSize};
+///
+/// // 2x2 BI_BITFIELDS (3) or BI_ALPHABITFIELDS (6) payloads, masks after
+/// // the header, then the bottom row, top row and an AND mask on the top row
+/// let dib = |bits: u32, compression: u32, masks: &[u32], rows: [&[u8]; 2], and: u8| {
+///     let mut dib = [40, 2, 4, 1 | (bits << 16), compression, 0, 0, 0, 0, 0]
+///         .map(u32::to_le_bytes)
+///         .concat();
+///     dib.extend(masks.iter().flat_map(|mask| mask.to_le_bytes()));
+///     rows.iter().for_each(|row| dib.extend_from_slice(row));
+///     dib.extend_from_slice(&[0, 0, 0, 0, and, 0, 0, 0]);
+///     CursorFrame::new(FrameSize::square(2).unwrap(), (0, 0), dib)
+/// };
+/// let pixels = |frame: &CursorFrame| -> std::io::Result<Vec<[u8; 4]>> {
+///     Ok(frame.decode_image()?.pixels().map(|pixel| pixel.0).collect())
+/// };
+///
+/// // 5-6-5: blue and white below red and green
+/// let rgb565 = dib(16, 3, &[0xf800, 0x07e0, 0x001f], [
+///     &[0x1f, 0x00, 0xff, 0xff],
+///     &[0x00, 0xf8, 0xe0, 0x07],
+/// ], 0);
+/// assert_eq!(rgb565.dib_info().unwrap().channel_masks(), Some(ChannelMasks::RGB565));
+/// assert_eq!(pixels(&rgb565)?, [
+///     [255, 0, 0, 255], [0, 255, 0, 255],
+///     [0, 0, 255, 255], [255, 255, 255, 255],
+/// ]);
+///
+/// // Bytes in R, G, B, A order, with half-transparent white
+/// let abgr = [0x0000_00ff, 0x0000_ff00, 0x00ff_0000, 0xff00_0000];
+/// let rgba = dib(32, 6, &abgr, [
+///     &[0, 0, 255, 255, 255, 255, 255, 128],
+///     &[255, 0, 0, 255, 0, 255, 0, 255],
+/// ], 0);
+/// assert_eq!(rgba.dib_info().unwrap().masks, Some(ChannelMasks::new(
+///     0xff, 0xff00, 0xff_0000, 0xff00_0000,
+/// )));
+/// assert_eq!(pixels(&rgba)?, [
+///     [255, 0, 0, 255], [0, 255, 0, 255],
+///     [0, 0, 255, 255], [255, 255, 255, 128],
+/// ]);
+///
+/// // Without an alpha mask the AND mask decides, here the top left pixel
+/// let masked = dib(32, 3, &abgr[..3], [&[0; 8], &[255, 0, 0, 0, 0, 255, 0, 0]], 0x80);
+/// assert_eq!(pixels(&masked)?[..2], [[255, 0, 0, 0], [0, 255, 0, 255]]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChannelMasks {
+    pub red: u32,
+    pub green: u32,
+    pub blue: u32,
+    pub alpha: u32,
+}
+
+impl ChannelMasks {
+    /// The 5-5-5 layout BI_RGB implies for 16-bit pixels
+    pub const RGB555: Self = Self::new(0x7c00, 0x03e0, 0x001f, 0);
+    /// The 5-6-5 layout most BI_BITFIELDS 16-bit pixels use
+    pub const RGB565: Self = Self::new(0xf800, 0x07e0, 0x001f, 0);
+    /// The BGRA byte order BI_RGB implies for 32-bit pixels
+    pub const BGRA: Self = Self::new(0x00ff_0000, 0x0000_ff00, 0x0000_00ff, 0xff00_0000);
+
+    pub const fn new(red: u32, green: u32, blue: u32, alpha: u32) -> Self {
+        Self {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// The 8-bit RGBA value of `pixel`; channels without a mask read as
+    /// black, or opaque for alpha
+    pub fn unpack(&self, pixel: u32) -> Rgba<u8> {
+        let channel = |mask: u32, missing: u8| {
+            if mask == 0 {
+                return missing;
+            }
+            let shift = mask.trailing_zeros();
+            let width = (mask >> shift).count_ones();
+            let value = (pixel & mask) >> shift;
+            if width >= 8 {
+                (value >> (width - 8)) as u8
+            } else {
+                let max = (1u32 << width) - 1;
+                ((value * 255 + max / 2) / max) as u8
+            }
+        };
+        Rgba([
+            channel(self.red, 0),
+            channel(self.green, 0),
+            channel(self.blue, 0),
+            channel(self.alpha, 255),
+        ])
+    }
+}
+
+impl Display for ChannelMasks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "R {:#x} G {:#x} B {:#x} A {:#x}",
+            self.red, self.green, self.blue, self.alpha
+        )
+    }
+}
+
+/// The color (XOR) plane of a DIB payload, borrowed from it
+#[derive(Debug, Clone, Copy)]
+pub struct DibPlane<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub stride: usize,
+    pub bit_count: u16,
+    pub row_order: RowOrder,
+    pub data: &'a [u8],
+}
+
+/// The 1-bit AND mask of a DIB payload, borrowed from it
+///
+/// A set bit lets the screen show through (or be inverted, under a
+/// non-black color pixel).
+#[derive(Debug, Clone, Copy)]
+pub struct MaskPlane<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub stride: usize,
+    pub row_order: RowOrder,
+    pub data: &'a [u8],
+}
+
+/// The header fields of a DIB payload, read without touching its pixels
+///
+/// Cursor DIBs store the color (XOR) and mask (AND) planes one after the
+/// other, so `height` normally covers twice the image height.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct BmpInfo {
+    pub header_size: u32,
+    pub width: i32,
+    pub height: i32,
+    pub planes: u16,
+    pub bit_count: u16,
+    pub compression: u32,
+    /// `biSizeImage`, which RLE payloads need to say where the mask starts
+    pub image_size: u32,
+    pub colors_used: u32,
+    /// The channel masks of a BI_BITFIELDS or BI_ALPHABITFIELDS payload,
+    /// from the header or the DWORDs after it
+    pub masks: Option<ChannelMasks>,
+}
+
+impl BmpInfo {
+    /// Parse the header at the start of `data`, `None` for PNG or short input
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&PNG_SIGNATURE) {
+            return None;
+        }
+        let u16_at = |offset: usize| {
+            Some(u16::from_le_bytes(
+                data.get(offset..offset + 2)?.try_into().ok()?,
+            ))
+        };
+        let u32_at = |offset: usize| {
+            Some(u32::from_le_bytes(
+                data.get(offset..offset + 4)?.try_into().ok()?,
+            ))
+        };
+
+        let header_size = u32_at(0)?;
+        if header_size < 40 {
+            return None;
+        }
+        let compression = u32_at(16)?;
+        let masks = match compression {
+            BI_BITFIELDS | BI_ALPHABITFIELDS => {
+                // Later header versions hold the masks, a plain
+                // BITMAPINFOHEADER is followed by them
+                let alpha = match compression {
+                    BI_ALPHABITFIELDS => u32_at(52)?,
+                    _ if header_size >= 56 => u32_at(52)?,
+                    _ => 0,
+                };
+                Some(ChannelMasks::new(
+                    u32_at(40)?,
+                    u32_at(44)?,
+                    u32_at(48)?,
+                    alpha,
+                ))
+            }
+            _ => None,
+        };
+        Some(Self {
+            header_size,
+            width: u32_at(4)? as i32,
+            height: u32_at(8)? as i32,
+            planes: u16_at(12)?,
+            bit_count: u16_at(14)?,
+            compression,
+            image_size: u32_at(20)?,
+            colors_used: u32_at(32)?,
+            masks,
+        })
+    }
+
+    /// Where each channel sits in a pixel: the payload's masks, or those
+    /// BI_RGB implies at 16 and 32 bits
+    pub fn channel_masks(&self) -> Option<ChannelMasks> {
+        match (self.masks, self.bit_count) {
+            (Some(masks), 16 | 32) => Some(masks),
+            (None, 16) if self.compression == BI_RGB => Some(ChannelMasks::RGB555),
+            (None, 32) if self.compression == BI_RGB => Some(ChannelMasks::BGRA),
+            _ => None,
+        }
+    }
+
+    /// Image width in pixels
+    pub fn image_width(&self) -> u32 {
+        self.width.unsigned_abs()
+    }
+
+    /// Image height in pixels, half the stored height
+    pub fn image_height(&self) -> u32 {
+        self.height.unsigned_abs() / 2
+    }
+
+    /// Number of palette entries following the header
+    pub fn palette_len(&self) -> usize {
+        match self.colors_used as usize {
+            0 if self.bit_count <= 8 => 1 << self.bit_count,
+            used => used,
+        }
+    }
+
+    /// Bytes per color row, padded to 4 bytes; `usize::MAX` where that
+    /// doesn't fit, as on 32-bit targets, so any later size check fails
+    pub fn stride(&self) -> usize {
+        let bits = self.image_width() as u64 * self.bit_count as u64;
+        usize::try_from(bits.div_ceil(32) * 4).unwrap_or(usize::MAX)
+    }
+
+    /// Bytes per 1-bit mask row, padded to 4 bytes
+    pub fn mask_stride(&self) -> usize {
+        (self.image_width() as usize).div_ceil(32) * 4
+    }
+
+    /// Whether the color plane is run-length encoded
+    ///
+    /// ```
+    /// use cursor_handler::{CursorFile, CursorFrame, FrameSize};
+    ///
+    /// // A 4x4 cursor of black, red, green and blue at `bits` per pixel
+    /// let rle = |bits: u32, runs: &[u8]| {
+    ///     let compression = if bits == 8 { 1 } else { 2 };
+    ///     let size = runs.len() as u32;
+    ///     let mut dib = [40, 4, 8, 1 | (bits << 16), compression, size, 0, 0, 4, 0]
+    ///         .map(u32::to_le_bytes)
+    ///         .concat();
+    ///     dib.extend_from_slice(&[0, 0, 0, 0, 0, 0, 255, 0, 0, 255, 0, 0, 255, 0, 0, 0]);
+    ///     dib.extend_from_slice(runs);
+    ///     dib.extend_from_slice(&[0; 16]); // Nothing masked
+    ///     CursorFrame::new(FrameSize::square(4).unwrap(), (0, 0), dib)
+    /// };
+    /// let indices = |frame: &CursorFrame| -> std::io::Result<Vec<usize>> {
+    ///     let colors = [[0, 0, 0, 255], [255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+    ///     let image = frame.decode_image()?;
+    ///     let index = |pixel: &image::Rgba<u8>| colors.iter().position(|c| *c == pixel.0);
+    ///     Ok(image.pixels().map(|pixel| index(pixel).unwrap()).collect())
+    /// };
+    ///
+    /// // A bottom row of red; green, blue and green as literals; a delta to
+    /// // the end of the next row for one blue pixel; then an early end
+    /// let rle8 = rle(8, &[4, 1, 0, 0, 0, 3, 2, 3, 2, 0, 0, 2, 0, 1, 1, 3, 0, 1]);
+    /// assert!(rle8.dib_info().unwrap().is_rle());
+    /// assert_eq!(indices(&rle8)?, [0, 0, 0, 0, 0, 0, 0, 3, 2, 3, 2, 0, 1, 1, 1, 1]);
+    ///
+    /// // Alternating nibbles, then two bytes of literals
+    /// let rle4 = rle(4, &[4, 0x12, 0, 0, 0, 4, 0x30, 0x21, 0, 1]);
+    /// assert_eq!(indices(&rle4)?, [0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 2, 1, 1, 2, 1, 2]);
+    ///
+    /// // Runs past the row or the data fail rather than wrap or overread
+    /// assert!(rle(8, &[5, 1, 0, 1]).decode_image().is_err());
+    /// assert!(rle(8, &[4, 1, 0, 0, 4]).decode_image().is_err());
+    ///
+    /// let issues = CursorFile::single(rle8).validate();
+    /// assert!(issues.iter().any(|issue| issue.code == "CUR010"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn is_rle(&self) -> bool {
+        matches!(self.compression, BI_RLE8 | BI_RLE4)
+    }
+
+    /// Offset of the color plane, after the header, masks and palette
+    ///
+    /// `None` for other compressed payloads, whose layout can't be derived.
+    pub fn xor_offset(&self) -> Option<usize> {
+        let masks = match self.compression {
+            BI_RGB | BI_RLE8 | BI_RLE4 => 0,
+            BI_BITFIELDS if self.header_size == 40 => 12, // Masks follow the header
+            BI_ALPHABITFIELDS if self.header_size == 40 => 16,
+            BI_BITFIELDS | BI_ALPHABITFIELDS => 0,
+            _ => return None,
+        };
+        let palette = self.palette_len().checked_mul(4)?;
+        (self.header_size as usize + masks).checked_add(palette)
+    }
+
+    /// Offset of the mask plane, which follows `image_size` bytes of RLE data
+    ///
+    /// `None` as well when the header's sizes overflow, which no payload can
+    /// hold anyway.
+    pub fn and_offset(&self) -> Option<usize> {
+        let color_len = match self.is_rle() {
+            true if self.image_size == 0 => return None,
+            true => self.image_size as usize,
+            false => self.stride().checked_mul(self.image_height() as usize)?,
+        };
+        self.xor_offset()?.checked_add(color_len)
+    }
+
+    /// Length of the whole payload, through the last mask row
+    pub fn expected_len(&self) -> Option<usize> {
+        let mask_len = self
+            .mask_stride()
+            .checked_mul(self.image_height() as usize)?;
+        self.and_offset()?.checked_add(mask_len)
+    }
+
+    /// Whether rows are stored from the top down (negative `biHeight`)
+    ///
+    /// The sign doesn't change the layout otherwise: the mask still follows
+    /// the color plane and the stored height still covers both.
+    ///
+    /// ```
+    /// use cursor_handler::{CursorFile, CursorFrame, FrameSize, Severity};
+    ///
+    /// // 2x2 24-bit twins: red and green above blue and white, the top left
+    /// // pixel masked
+    /// let twin = |height: i32, rows: [[u8; 8]; 2], masks: [u8; 2]| {
+    ///     let mut dib = [40, 2, height as u32, 1 | (24 << 16), 0, 0, 0, 0, 0, 0]
+    ///         .map(u32::to_le_bytes)
+    ///         .concat();
+    ///     rows.iter().for_each(|row| dib.extend_from_slice(row));
+    ///     masks.iter().for_each(|&mask| dib.extend_from_slice(&[mask, 0, 0, 0]));
+    ///     CursorFrame::new(FrameSize::square(2).unwrap(), (0, 0), dib)
+    /// };
+    /// let top = [0, 0, 255, 0, 255, 0, 0, 0];
+    /// let bottom = [255, 0, 0, 255, 255, 255, 0, 0];
+    /// let bottom_up = twin(4, [bottom, top], [0, 0x80]);
+    /// let top_down = twin(-4, [top, bottom], [0x80, 0]);
+    ///
+    /// let info = top_down.dib_info().unwrap();
+    /// assert!(info.top_down() && !bottom_up.dib_info().unwrap().top_down());
+    /// assert_eq!((info.image_width(), info.image_height()), (2, 2));
+    /// let image = top_down.decode_image()?;
+    /// assert_eq!(image, bottom_up.decode_image()?);
+    /// assert_eq!((image[(0, 0)].0[3], image[(1, 0)].0), (0, [0, 255, 0, 255]));
+    ///
+    /// let issues = CursorFile::single(top_down).validate();
+    /// let issue = issues.iter().find(|issue| issue.severity > Severity::Info).unwrap();
+    /// assert_eq!(issue.code, "CUR009");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn top_down(&self) -> bool {
+        self.height < 0
+    }
+
+    pub fn row_order(&self) -> RowOrder {
+        if self.height < 0 {
+            RowOrder::TopDown
+        } else {
+            RowOrder::BottomUp
+        }
+    }
+
+    /// Whether the header describes a `width` x `height` cursor image, with
+    /// the stored height doubled for the mask
+    pub fn consistent_with(&self, width: u32, height: u32) -> bool {
+        self.image_width() == width && self.height.unsigned_abs() == height * 2
+    }
+}
+
+impl<'a> DibPlane<'a> {
+    /// Rows from the top of the image down, `stride` bytes each
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &'a [u8]> {
+        plane_rows(self.data, self.stride, self.row_order)
+    }
+}
+
+impl<'a> MaskPlane<'a> {
+    /// Whether the mask bit is set at `x`, `y` (from the top left)
+    ///
+    /// Panics if the pixel is outside the plane.
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        assert!(
+            x < self.width && y < self.height,
+            "({x}, {y}) is outside the mask"
+        );
+        let row = match self.row_order {
+            RowOrder::BottomUp => self.height - 1 - y,
+            RowOrder::TopDown => y,
+        };
+        let byte = self.data[row as usize * self.stride + x as usize / 8];
+        byte & (0x80 >> (x % 8)) != 0
+    }
+
+    /// Rows from the top of the image down, `stride` bytes each
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &'a [u8]> {
+        plane_rows(self.data, self.stride, self.row_order)
+    }
+}
+
+fn plane_rows(
+    data: &[u8],
+    stride: usize,
+    order: RowOrder,
+) -> impl DoubleEndedIterator<Item = &[u8]> {
+    let count = data.len() / stride.max(1);
+    (0..count).map(move |y| {
+        let row = match order {
+            RowOrder::BottomUp => count - 1 - y,
+            RowOrder::TopDown => y,
+        };
+        &data[row * stride..(row + 1) * stride]
+    })
+}
+
+impl Display for BmpInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{} {}bpp ", self.width, self.height, self.bit_count)?;
+        match self.compression {
+            BI_RGB => f.write_str("BI_RGB")?,
+            BI_RLE8 => f.write_str("BI_RLE8")?,
+            BI_RLE4 => f.write_str("BI_RLE4")?,
+            BI_BITFIELDS => f.write_str("BI_BITFIELDS")?,
+            BI_ALPHABITFIELDS => f.write_str("BI_ALPHABITFIELDS")?,
+            other => write!(f, "compression {other}")?,
+        }
+        if let Some(masks) = self.masks {
+            write!(f, " ({masks})")?;
+        }
+        if self.palette_len() > 0 {
+            write!(f, ", {} colors", self.palette_len())?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for BmpInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BmpInfo({self}, header {}", self.header_size)?;
+        if let Some(len) = self.expected_len() {
+            write!(f, ", stride {}, {len} bytes", self.stride())?;
+        }
+        f.write_str(")")
+    }
+}
+
+impl CursorFrame {
+    /// The DIB header of the payload, `None` for PNG or unreadable data
+    ///
+    /// ```
+    /// use cursor_handler::{BmpInfo, CursorFrame, FrameSize};
+    ///
+    /// // Header-only fixtures at each bit depth, 17x17 so rows need padding
+    /// let fixtures = [(1, 2, 4), (4, 16, 12), (8, 256, 20), (24, 0, 52), (32, 0, 68)];
+    /// for (bits, palette, stride) in fixtures {
+    ///     let mut dib = [40u32, 17, 34, 1 | (bits << 16), 0, 0, 0, 0, 0, 0]
+    ///         .map(u32::to_le_bytes)
+    ///         .concat();
+    ///     let info = BmpInfo::parse(&dib).unwrap();
+    ///     assert_eq!((info.palette_len(), info.stride()), (palette, stride));
+    ///     assert!(info.consistent_with(17, 17));
+    ///     assert!(!info.consistent_with(17, 34));
+    ///
+    ///     let xor = 40 + palette * 4;
+    ///     assert_eq!(info.xor_offset(), Some(xor));
+    ///     assert_eq!(info.and_offset(), Some(xor + stride * 17));
+    ///     assert_eq!(info.expected_len(), Some(xor + (stride + 4) * 17));
+    ///
+    ///     // A full-length payload of that layout decodes
+    ///     dib.resize(xor + (stride + 4) * 17, 0);
+    ///     let frame = CursorFrame::new(FrameSize::square(17)?, (0, 0), dib);
+    ///     assert_eq!(frame.dib_info(), Some(info));
+    ///     assert_eq!(frame.decode_image()?.dimensions(), (17, 17));
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn dib_info(&self) -> Option<BmpInfo> {
+        BmpInfo::parse(&self.image_data)
+    }
+
+    /// The color plane of a DIB payload, `None` for PNG, compressed or
+    /// truncated data
+    pub fn xor_data(&self) -> Option<DibPlane<'_>> {
+        let info = self.dib_info().filter(|info| !info.is_rle())?;
+        Some(DibPlane {
+            width: info.image_width(),
+            height: info.image_height(),
+            stride: info.stride(),
+            bit_count: info.bit_count,
+            row_order: info.row_order(),
+            data: self
+                .image_data
+                .get(info.xor_offset()?..info.and_offset()?)?,
+        })
+    }
+
+    /// The AND mask of a DIB payload, `None` for PNG, compressed or
+    /// truncated data
+    ///
+    /// ```
+    /// use cursor_handler::{CursorFrame, FrameSize};
+    ///
+    /// // 2x2 32-bit DIB: opaque top row, transparent bottom row, mask agreeing
+    /// let mut dib = [40u32, 2, 4, 0x0020_0001, 0, 0, 0, 0, 0, 0]
+    ///     .map(u32::to_le_bytes)
+    ///     .concat();
+    /// dib.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0]); // Bottom row first
+    /// dib.extend_from_slice(&[9, 9, 9, 255, 9, 9, 9, 255]);
+    /// dib.extend_from_slice(&[0xC0, 0, 0, 0, 0x00, 0, 0, 0]);
+    /// let mut frame = CursorFrame::new(FrameSize::square(2)?, (0, 0), dib);
+    ///
+    /// let color = frame.xor_data().unwrap();
+    /// assert_eq!((color.bit_count, color.stride), (32, 8));
+    /// assert_eq!(color.rows().next().unwrap()[3], 255);
+    ///
+    /// let mask = frame.and_mask().unwrap();
+    /// assert!(!mask.get(0, 0) && mask.get(1, 1));
+    /// assert_eq!(mask.rows().map(|row| row[0]).collect::<Vec<_>>(), [0x00, 0xC0]);
+    ///
+    /// // Nothing to fix, until the top row's alpha is cleared
+    /// assert!(!frame.recompute_mask_from_alpha(128));
+    /// frame.image_data[51] = 0;
+    /// assert!(frame.recompute_mask_from_alpha(128));
+    /// let mask = frame.and_mask().unwrap();
+    /// assert!(mask.get(0, 0) && !mask.get(1, 0));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn and_mask(&self) -> Option<MaskPlane<'_>> {
+        let info = self.dib_info()?;
+        Some(MaskPlane {
+            width: info.image_width(),
+            height: info.image_height(),
+            stride: info.mask_stride(),
+            row_order: info.row_order(),
+            data: self
+                .image_data
+                .get(info.and_offset()?..info.expected_len()?)?,
+        })
+    }
+
+    /// Set the AND mask bit of every pixel whose alpha is below `threshold`
+    /// and clear the rest
+    ///
+    /// Only 32-bit DIBs carry alpha; other payloads are left alone. Returns
+    /// whether any bit changed.
+    pub fn recompute_mask_from_alpha(&mut self, threshold: u8) -> bool {
+        let Some(info) = self.dib_info().filter(|info| info.bit_count == 32) else {
+            return false;
+        };
+        // The byte holding alpha, when the masks give it one
+        let Some(alpha_byte) = info
+            .channel_masks()
+            .map(|masks| masks.alpha)
+            .filter(|&alpha| alpha.count_ones() == 8 && alpha.trailing_zeros() % 8 == 0)
+            .map(|alpha| alpha.trailing_zeros() as usize / 8)
+        else {
+            return false;
+        };
+        let (Some(xor), Some(and), Some(end)) =
+            (info.xor_offset(), info.and_offset(), info.expected_len())
+        else {
+            return false;
+        };
+        if self.image_data.len() < end {
+            return false;
+        }
+
+        let mut changed = false;
+        // Both planes share the row order, so rows pair up as stored
+        for row in 0..info.image_height() as usize {
+            for x in 0..info.image_width() as usize {
+                let alpha = self.image_data[xor + row * info.stride() + x * 4 + alpha_byte];
+                let byte = &mut self.image_data[and + row * info.mask_stride() + x / 8];
+                let bit = 0x80 >> (x % 8);
+                let masked = alpha < threshold;
+                if (*byte & bit != 0) != masked {
+                    *byte ^= bit;
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+}
+
+/// Decode a 16- or 32-bit BI_BITFIELDS or BI_ALPHABITFIELDS payload, which
+/// the ICO decoder would read with the wrong channels
+///
+/// Without an alpha mask, pixels under a set AND mask bit are transparent.
+pub(crate) fn decode_bitfields(frame: &CursorFrame) -> Option<RgbaImage> {
+    let masks = frame.dib_info()?.masks?;
+    let (color, and_mask) = (frame.xor_data()?, frame.and_mask()?);
+    let bytes = match color.bit_count {
+        16 => 2,
+        32 => 4,
+        _ => return None,
+    };
+
+    let rows: Vec<_> = color.rows().collect();
+    Some(RgbaImage::from_fn(color.width, color.height, |x, y| {
+        let at = x as usize * bytes;
+        let mut raw = [0; 4];
+        raw[..bytes].copy_from_slice(&rows[y as usize][at..at + bytes]);
+        let mut pixel = masks.unpack(u32::from_le_bytes(raw));
+        if masks.alpha == 0 && and_mask.get(x, y) {
+            pixel[3] = 0;
+        }
+        pixel
+    }))
+}
+
+/// A BI_RGB copy of a BI_RLE8 or BI_RLE4 payload, its runs expanded into
+/// indexed rows; `Ok(None)` for anything else
+///
+/// Pixels the runs skip, by a delta or an early end of bitmap, take palette
+/// index 0. A stream that runs past its data or the image fails.
+pub(crate) fn decompress_rle(data: &[u8]) -> io::Result<Option<Vec<u8>>> {
+    let Some(info) = BmpInfo::parse(data).filter(BmpInfo::is_rle) else {
+        return Ok(None);
+    };
+    let error =
+        |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("RLE data {message}"));
+    if info.top_down() {
+        return Err(error("can't be stored top-down"));
+    }
+    let (Some(xor), Some(and), Some(end)) =
+        (info.xor_offset(), info.and_offset(), info.expected_len())
+    else {
+        return Err(error("has no biSizeImage to find the mask by"));
+    };
+    let (Some(runs), Some(mask)) = (data.get(xor..and), data.get(and..end)) else {
+        return Err(error("is cut short"));
+    };
+
+    let (width, height) = (info.image_width() as usize, info.image_height() as usize);
+    let nibbles = info.compression == BI_RLE4;
+    // Palette indices, bottom row first like the runs
+    let mut indices = vec![0u8; width * height];
+    let mut put = |x: &mut usize, y: usize, index: u8| {
+        if *x >= width || y >= height {
+            return Err(error("runs past the edge of the image"));
+        }
+        indices[y * width + *x] = index;
+        *x += 1;
+        Ok(())
+    };
+    let mut stream = runs.iter().copied();
+    let mut next = || {
+        stream
+            .next()
+            .ok_or_else(|| error("ends before its end-of-bitmap marker"))
+    };
+
+    let (mut x, mut y) = (0, 0);
+    loop {
+        match (next()?, next()?) {
+            (0, 0) => (x, y) = (0, y + 1),
+            (0, 1) => break,
+            (0, 2) => {
+                x += next()? as usize;
+                y += next()? as usize;
+            }
+            // Absolute mode: `count` literal pixels, padded to a 16-bit word
+            (0, count) => {
+                let mut byte = 0;
+                for i in 0..count {
+                    if !nibbles || i % 2 == 0 {
+                        byte = next()?;
+                    }
+                    let index = match (nibbles, i % 2) {
+                        (false, _) => byte,
+                        (true, 0) => byte >> 4,
+                        (true, _) => byte & 0x0f,
+                    };
+                    put(&mut x, y, index)?;
+                }
+                let len = if nibbles { count.div_ceil(2) } else { count };
+                if len % 2 == 1 {
+                    next()?;
+                }
+            }
+            // Encoded mode: `count` pixels of one index, or two alternating
+            (count, value) => {
+                for i in 0..count {
+                    let index = match (nibbles, i % 2) {
+                        (false, _) => value,
+                        (true, 0) => value >> 4,
+                        (true, _) => value & 0x0f,
+                    };
+                    put(&mut x, y, index)?;
+                }
+            }
+        }
+    }
+
+    let mut plain = data[..xor].to_vec();
+    plain[16..24].copy_from_slice(&[BI_RGB, 0].map(u32::to_le_bytes).concat());
+    for row in indices.chunks_exact(width.max(1)) {
+        let start = plain.len();
+        if nibbles {
+            plain.extend(
+                row.chunks(2)
+                    .map(|pair| pair[0] << 4 | pair.get(1).unwrap_or(&0)),
+            );
+        } else {
+            plain.extend_from_slice(row);
+        }
+        plain.resize(start + info.stride(), 0);
+    }
+    plain.extend_from_slice(mask);
+    Ok(Some(plain))
+}
+
+/// A copy of a top-down DIB payload with both planes flipped bottom-up, the
+/// only order the ICO decoder reads masks in; `None` for anything else
+pub(crate) fn to_bottom_up(data: &[u8]) -> Option<Vec<u8>> {
+    let info = BmpInfo::parse(data).filter(|info| info.top_down() && !info.is_rle())?;
+    let (xor, and, end) = (info.xor_offset()?, info.and_offset()?, info.expected_len()?);
+    if data.len() < end {
+        return None;
+    }
+
+    let mut flipped = data[..xor].to_vec();
+    flipped[8..12].copy_from_slice(&info.height.unsigned_abs().to_le_bytes());
+    for (plane, stride) in [
+        (&data[xor..and], info.stride()),
+        (&data[and..end], info.mask_stride()),
+    ] {
+        plane_rows(plane, stride, RowOrder::BottomUp)
+            .for_each(|row| flipped.extend_from_slice(row));
+    }
+    flipped.extend_from_slice(&data[end..]);
+    Some(flipped)
+}
+
+/// A bottom-up 32-bit BI_RGB payload for `image`, masking fully transparent
+/// pixels
+pub(crate) fn encode_dib32(image: &RgbaImage) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let mask_stride = (width as usize).div_ceil(32) * 4;
+    let mut dib = [40, width, height * 2, 1 | (32 << 16), BI_RGB, 0, 0, 0, 0, 0]
+        .map(u32::to_le_bytes)
+        .concat();
+    dib.reserve((width as usize * 4 + mask_stride) * height as usize);
+
+    for row in image.rows().rev() {
+        for pixel in row {
+            let [r, g, b, a] = pixel.0;
+            dib.extend_from_slice(&[b, g, r, a]);
+        }
+    }
+    for row in image.rows().rev() {
+        let mut mask = vec![0u8; mask_stride];
+        for (x, pixel) in row.enumerate() {
+            if pixel.0[3] == 0 {
+                mask[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        dib.extend_from_slice(&mask);
+    }
+    dib
+}
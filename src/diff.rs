@@ -0,0 +1,564 @@
+// Structural comparison of two cursor files
+//
+// Frames are compared by their decoded pixels, so re-encoding a payload
+// (e.g. PNG recompression) doesn't count as a change; a different payload
+// format is reported separately.
+use std::{
+    fmt::{self, Display},
+    io,
+};
+
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    ani::{AniFile, LoopBehavior},
+    cur::{CursorFile, CursorFrame},
+    hotspot::Hotspot,
+    size::FrameSize,
+};
+
+/// How far apart two decoded images are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PixelDiff {
+    pub differing: usize,
+    pub max_delta: u8, // Largest difference in any one RGBA channel
+}
+
+/// One difference between the frames of two cursors
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FrameChange {
+    Added(FrameSize),
+    Removed(FrameSize),
+    /// A size that only one side has, paired with a size only the other has
+    Resized {
+        from: FrameSize,
+        to: FrameSize,
+    },
+    Hotspot {
+        size: FrameSize,
+        from: Hotspot,
+        to: Hotspot,
+    },
+    Format {
+        size: FrameSize,
+        from: String,
+        to: String,
+    },
+    Pixels {
+        size: FrameSize,
+        diff: PixelDiff,
+    },
+    /// The payloads differ and at least one can't be decoded to compare
+    Unreadable {
+        size: FrameSize,
+    },
+}
+
+/// What changed between two .cur files, frames matched by size
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CursorDiff {
+    pub changes: Vec<FrameChange>,
+}
+
+/// The frame and duration of a playback step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StepTiming {
+    pub frame: usize,
+    pub duration: u32,
+}
+
+/// One difference between two animations
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AniChange {
+    FrameAdded(usize),
+    FrameRemoved(usize),
+    Frame {
+        index: usize,
+        diff: CursorDiff,
+    },
+    /// `from` is `None` for an added step, `to` for a removed one
+    Step {
+        index: usize,
+        from: Option<StepTiming>,
+        to: Option<StepTiming>,
+    },
+    /// Serialized as text, like `Display` shows it
+    Info {
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_id"))]
+        id: [u8; 4],
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_info_value"))]
+        from: Option<Vec<u8>>,
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_info_value"))]
+        to: Option<Vec<u8>>,
+    },
+    Loop {
+        from: LoopBehavior,
+        to: LoopBehavior,
+    },
+}
+
+/// What changed between two .ani files, frames matched by index
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AniDiff {
+    pub changes: Vec<AniChange>,
+}
+
+impl CursorDiff {
+    pub fn is_identical(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl AniDiff {
+    pub fn is_identical(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Compare two cursors
+///
+/// Frames of the same size are paired first; sizes left over on both sides
+/// are paired in order as resizes.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::{AniFile, FrameSize, diff::{self, FrameChange, PixelDiff}};
+///
+/// let ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+/// let old = ani.extract_frame(0)?;
+///
+/// // Moving to PNG changes the bytes and the format, not the pixels
+/// let mut new = old.clone();
+/// new[0].transcode_to_png()?;
+/// let changes = diff::compare_cur(&old, &new).changes;
+/// assert!(matches!(changes[..], [FrameChange::Format { .. }]));
+///
+/// let mut new = old.clone();
+/// new[0].hotspot.x += 1;
+/// new.retain_sizes(&[64, 32]);
+/// let diff = diff::compare_cur(&old, &new);
+/// assert_eq!(diff.to_string(), "64x64 hotspot (32, 32) -> (33, 32)\n48x48 frame removed\n");
+/// assert!(diff::compare_cur(&old, &old).is_identical());
+///
+/// // One byte of one pixel's color
+/// new[1].image_data[40] ^= 0x10;
+/// let changes = diff::compare_cur(&old, &new).changes;
+/// let pixels = PixelDiff { differing: 1, max_delta: 0x10 };
+/// assert!(changes.contains(&FrameChange::Pixels { size: FrameSize::square(32)?, diff: pixels }));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn compare_cur(a: &CursorFile, b: &CursorFile) -> CursorDiff {
+    let mut changes = Vec::new();
+    for pair in pair_frames(a, b) {
+        match pair {
+            (Some(frame), Some(other)) if frame.size == other.size => {
+                compare_frame(frame, other, &mut changes)
+            }
+            (Some(frame), Some(other)) => changes.push(FrameChange::Resized {
+                from: frame.size,
+                to: other.size,
+            }),
+            (Some(frame), None) => changes.push(FrameChange::Removed(frame.size)),
+            (None, Some(other)) => changes.push(FrameChange::Added(other.size)),
+            (None, None) => {}
+        }
+    }
+    CursorDiff { changes }
+}
+
+type FramePair<'a> = (Option<&'a CursorFrame>, Option<&'a CursorFrame>);
+
+/// The frames of `a` and `b` as `compare_cur` pairs them: same sizes, then
+/// leftovers in order, then whatever one side has left
+fn pair_frames<'a>(a: &'a CursorFile, b: &'a CursorFile) -> Vec<FramePair<'a>> {
+    let mut pairs = Vec::new();
+    let mut only_b: Vec<&CursorFrame> = b.frames.iter().collect();
+    let mut only_a = Vec::new();
+
+    for frame in &a.frames {
+        match only_b.iter().position(|other| other.size == frame.size) {
+            Some(position) => pairs.push((Some(frame), Some(only_b.remove(position)))),
+            None => only_a.push(frame),
+        }
+    }
+
+    let mut only_b = only_b.into_iter();
+    for frame in only_a {
+        pairs.push((Some(frame), only_b.next()));
+    }
+    pairs.extend(only_b.map(|frame| (None, Some(frame))));
+    pairs
+}
+
+/// The color `cur_diff_image` and `ani_diff_image` paint differing pixels
+pub const DIFF_HIGHLIGHT: Rgba<u8> = Rgba([255, 0, 255, 255]);
+
+/// Space between the cells of a diff image
+const DIFF_GAP: u32 = 2;
+
+/// Render two cursors for review, one row per pair of frames
+///
+/// Each row shows the old frame, the new frame, and an overlay of the new
+/// frame faded out with `DIFF_HIGHLIGHT` wherever a channel differs by more
+/// than `threshold`. Frames are paired as `compare_cur` pairs them; an added
+/// or removed frame is compared against nothing, so all of it is
+/// highlighted.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::{CursorFile, diff::{self, DIFF_HIGHLIGHT}};
+///
+/// let old = CursorFile::decode(Cursor::new(std::fs::read("assets/windows_theme/Link.cur")?))?;
+/// let image = diff::cur_diff_image(&old, &old, 0)?;
+/// assert!(!image.pixels().any(|pixel| *pixel == DIFF_HIGHLIGHT));
+///
+/// // A hotspot is not a pixel
+/// let mut new = old.clone();
+/// new[0].hotspot.x += 1;
+/// assert_eq!(diff::cur_diff_image(&old, &new, 0)?, image);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn cur_diff_image(a: &CursorFile, b: &CursorFile, threshold: u8) -> io::Result<RgbaImage> {
+    let mut rows = Vec::new();
+    diff_rows(a, b, threshold, &mut rows)?;
+    Ok(stack_rows(&rows))
+}
+
+/// `cur_diff_image` for animations, with rows for each frame in turn
+pub fn ani_diff_image(a: &AniFile, b: &AniFile, threshold: u8) -> io::Result<RgbaImage> {
+    let empty = CursorFile::new(Vec::new());
+    let mut rows = Vec::new();
+    for index in 0..a.frames.len().max(b.frames.len()) {
+        let extract = |ani: &AniFile| {
+            if index < ani.frames.len() {
+                ani.extract_frame(index).map(Some)
+            } else {
+                Ok(None)
+            }
+        };
+        let (cur_a, cur_b) = (extract(a)?, extract(b)?);
+        diff_rows(
+            cur_a.as_ref().unwrap_or(&empty),
+            cur_b.as_ref().unwrap_or(&empty),
+            threshold,
+            &mut rows,
+        )?;
+    }
+    Ok(stack_rows(&rows))
+}
+
+fn diff_rows(
+    a: &CursorFile,
+    b: &CursorFile,
+    threshold: u8,
+    rows: &mut Vec<RgbaImage>,
+) -> io::Result<()> {
+    let decode = |frame: Option<&CursorFrame>| match frame {
+        Some(frame) => frame.decode_image(),
+        None => Ok(RgbaImage::new(0, 0)),
+    };
+    for (frame_a, frame_b) in pair_frames(a, b) {
+        rows.push(diff_row(&decode(frame_a)?, &decode(frame_b)?, threshold));
+    }
+    Ok(())
+}
+
+/// Old, new and overlay side by side
+fn diff_row(a: &RgbaImage, b: &RgbaImage, threshold: u8) -> RgbaImage {
+    let (width, height) = (a.width().max(b.width()), a.height().max(b.height()));
+    let mut row = RgbaImage::new(width * 3 + DIFF_GAP * 2, height);
+    let step = width + DIFF_GAP;
+    image::imageops::replace(&mut row, a, 0, 0);
+    image::imageops::replace(&mut row, b, step as i64, 0);
+
+    // Outside an image counts as transparent
+    let pixel =
+        |image: &RgbaImage, x, y| *image.get_pixel_checked(x, y).unwrap_or(&Rgba([0, 0, 0, 0]));
+    for y in 0..height {
+        for x in 0..width {
+            let (pixel_a, pixel_b) = (pixel(a, x, y), pixel(b, x, y));
+            let delta = (pixel_a.0.iter().zip(pixel_b.0))
+                .map(|(&channel_a, channel_b)| channel_a.abs_diff(channel_b))
+                .max()
+                .unwrap_or(0);
+            let overlay = if delta > threshold {
+                DIFF_HIGHLIGHT
+            } else {
+                let [r, g, b, alpha] = pixel_b.0;
+                Rgba([r, g, b, alpha / 4])
+            };
+            row.put_pixel(step * 2 + x, y, overlay);
+        }
+    }
+    row
+}
+
+/// Rows one under another, left-aligned
+fn stack_rows(rows: &[RgbaImage]) -> RgbaImage {
+    let width = rows.iter().map(RgbaImage::width).max().unwrap_or(0);
+    let height = rows.iter().map(|row| row.height() + DIFF_GAP).sum::<u32>();
+    let mut image = RgbaImage::new(width, height.saturating_sub(DIFF_GAP));
+    let mut y = 0;
+    for row in rows {
+        image::imageops::replace(&mut image, row, 0, y as i64);
+        y += row.height() + DIFF_GAP;
+    }
+    image
+}
+
+/// Compare two animations
+///
+/// Frames are compared as cursors with `compare_cur`, steps by the frame
+/// they show and their duration.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::{AniFile, InfoEntry, LoopBehavior, diff::{self, AniChange}};
+///
+/// let old = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+/// assert!(diff::compare_ani(&old, &old.clone()).is_identical());
+///
+/// let mut new = old.clone().with_rates(vec![6; old.num_steps() as usize]);
+/// new.info.push(InfoEntry::new(*b"INAM", "Busy"));
+/// new.loop_behavior = LoopBehavior::Count(2);
+/// let diff = diff::compare_ani(&old, &new);
+///
+/// let steps = diff.changes.iter().filter(|change| matches!(change, AniChange::Step { .. }));
+/// assert_eq!(steps.count(), old.num_steps() as usize);
+/// assert!(diff.to_string().contains("INAM (none) -> \"Busy\""));
+/// assert!(diff.to_string().ends_with("loop Infinite -> Count(2)\n"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn compare_ani(a: &AniFile, b: &AniFile) -> AniDiff {
+    let mut changes = Vec::new();
+
+    let (frames_a, frames_b) = (a.frames.len(), b.frames.len());
+    for index in 0..frames_a.min(frames_b) {
+        // Both indices are in range, so extraction can't fail
+        let (Ok(cur_a), Ok(cur_b)) = (a.extract_frame(index), b.extract_frame(index)) else {
+            continue;
+        };
+        let diff = compare_cur(&cur_a, &cur_b);
+        if !diff.is_identical() {
+            changes.push(AniChange::Frame { index, diff });
+        }
+    }
+    changes.extend((frames_b..frames_a).map(AniChange::FrameRemoved));
+    changes.extend((frames_a..frames_b).map(AniChange::FrameAdded));
+
+    let timing = |ani: &AniFile| -> Vec<StepTiming> {
+        ani.steps()
+            .map(|step| StepTiming {
+                frame: step.frame_index,
+                duration: step.duration,
+            })
+            .collect()
+    };
+    let (steps_a, steps_b) = (timing(a), timing(b));
+    for index in 0..steps_a.len().max(steps_b.len()) {
+        let (from, to) = (steps_a.get(index).copied(), steps_b.get(index).copied());
+        if from != to {
+            changes.push(AniChange::Step { index, from, to });
+        }
+    }
+
+    let mut ids: Vec<[u8; 4]> = a.info.iter().map(|entry| entry.id).collect();
+    for entry in &b.info {
+        if !ids.contains(&entry.id) {
+            ids.push(entry.id);
+        }
+    }
+    for id in ids {
+        let from = a.info_entry(&id).map(|entry| entry.value.clone());
+        let to = b.info_entry(&id).map(|entry| entry.value.clone());
+        if from != to {
+            changes.push(AniChange::Info { id, from, to });
+        }
+    }
+
+    // Count(1) and PlayOnceHoldLast play the same way
+    if a.loop_behavior.plays() != b.loop_behavior.plays() {
+        changes.push(AniChange::Loop {
+            from: a.loop_behavior,
+            to: b.loop_behavior,
+        });
+    }
+
+    AniDiff { changes }
+}
+
+fn compare_frame(a: &CursorFrame, b: &CursorFrame, changes: &mut Vec<FrameChange>) {
+    let size = a.size;
+    if a.hotspot != b.hotspot {
+        changes.push(FrameChange::Hotspot {
+            size,
+            from: a.hotspot,
+            to: b.hotspot,
+        });
+    }
+
+    let (format_a, format_b) = (a.payload_format(), b.payload_format());
+    if format_a != format_b {
+        changes.push(FrameChange::Format {
+            size,
+            from: format_a,
+            to: format_b,
+        });
+    }
+
+    if a.image_data == b.image_data {
+        return;
+    }
+    let (Ok(image_a), Ok(image_b)) = (a.decode_image(), b.decode_image()) else {
+        changes.push(FrameChange::Unreadable { size });
+        return;
+    };
+
+    let diff = if image_a.dimensions() != image_b.dimensions() {
+        let area = |image: &image::RgbaImage| (image.width() * image.height()) as usize;
+        PixelDiff {
+            differing: area(&image_a).max(area(&image_b)),
+            max_delta: u8::MAX,
+        }
+    } else {
+        let mut diff = PixelDiff {
+            differing: 0,
+            max_delta: 0,
+        };
+        for (pixel_a, pixel_b) in image_a.pixels().zip(image_b.pixels()) {
+            let delta = (pixel_a.0.iter().zip(pixel_b.0))
+                .map(|(&channel_a, channel_b)| channel_a.abs_diff(channel_b))
+                .max()
+                .unwrap_or(0);
+            if delta > 0 {
+                diff.differing += 1;
+                diff.max_delta = diff.max_delta.max(delta);
+            }
+        }
+        diff
+    };
+    if diff.differing > 0 {
+        changes.push(FrameChange::Pixels { size, diff });
+    }
+}
+
+impl Display for FrameChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added(size) => write!(f, "{size} frame added"),
+            Self::Removed(size) => write!(f, "{size} frame removed"),
+            Self::Resized { from, to } => write!(f, "{from} frame resized to {to}"),
+            Self::Hotspot { size, from, to } => write!(f, "{size} hotspot {from} -> {to}"),
+            Self::Format { size, from, to } => write!(f, "{size} payload {from} -> {to}"),
+            Self::Pixels { size, diff } => write!(
+                f,
+                "{size} {} pixels differ, max channel delta {}",
+                diff.differing, diff.max_delta
+            ),
+            Self::Unreadable { size } => {
+                write!(f, "{size} payload differs and can't be decoded")
+            }
+        }
+    }
+}
+
+impl Display for CursorDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_identical() {
+            return writeln!(f, "identical");
+        }
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for StepTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "frame {} for {} jiffies", self.frame, self.duration)
+    }
+}
+
+/// An INFO value as text, without its terminating NULs
+fn info_text(value: &[u8]) -> String {
+    String::from_utf8_lossy(value)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+#[cfg(feature = "serde")]
+fn serialize_id<S: serde::Serializer>(id: &[u8; 4], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&String::from_utf8_lossy(id))
+}
+
+#[cfg(feature = "serde")]
+fn serialize_info_value<S: serde::Serializer>(
+    value: &Option<Vec<u8>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serializer.serialize_some(&info_text(value)),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl Display for AniChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let info_value = |value: &Option<Vec<u8>>| match value {
+            Some(value) => format!("{:?}", info_text(value)),
+            None => "(none)".to_string(),
+        };
+
+        match self {
+            Self::FrameAdded(index) => write!(f, "frame {index} added"),
+            Self::FrameRemoved(index) => write!(f, "frame {index} removed"),
+            Self::Frame { index, diff } => {
+                for (line, change) in diff.changes.iter().enumerate() {
+                    if line > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "frame {index}: {change}")?;
+                }
+                Ok(())
+            }
+            Self::Step { index, from, to } => match (from, to) {
+                (Some(from), Some(to)) => write!(f, "step {index}: {from} -> {to}"),
+                (None, Some(to)) => write!(f, "step {index} added: {to}"),
+                (Some(from), None) => write!(f, "step {index} removed: {from}"),
+                (None, None) => write!(f, "step {index}"),
+            },
+            Self::Info { id, from, to } => write!(
+                f,
+                "{} {} -> {}",
+                String::from_utf8_lossy(id),
+                info_value(from),
+                info_value(to)
+            ),
+            Self::Loop { from, to } => write!(f, "loop {from:?} -> {to:?}"),
+        }
+    }
+}
+
+impl Display for AniDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_identical() {
+            return writeln!(f, "identical");
+        }
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
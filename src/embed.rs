@@ -0,0 +1,159 @@
+// Cursors compiled into the binary, see `build::validate_and_copy`
+use std::{
+    fmt::{self, Debug},
+    io::{self, Cursor},
+};
+
+use crate::{
+    ani::AniFile,
+    cur::{CursorFile, CursorFrame, png_data, stored_size},
+    debug::{BytesSummary, FramesSummary},
+    hotspot::Hotspot,
+    size::FrameSize,
+};
+
+/// Embed a cursor file as `CursorBytes`, like `include_bytes!`
+#[macro_export]
+macro_rules! include_cursor {
+    ($path:expr) => {
+        $crate::embed::CursorBytes::new(include_bytes!($path))
+    };
+}
+
+/// The raw bytes of an embedded .cur or .ani file
+#[derive(Clone, Copy)]
+pub struct CursorBytes {
+    bytes: &'static [u8],
+}
+
+impl CursorBytes {
+    pub const fn new(bytes: &'static [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub const fn as_bytes(&self) -> &'static [u8] {
+        self.bytes
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.bytes.starts_with(b"RIFF")
+    }
+
+    /// Parse a .cur without copying any image data
+    pub fn decode_static(&self) -> io::Result<CursorView<'static>> {
+        CursorFile::decode_static(self.bytes)
+    }
+
+    pub fn to_cursor_file(&self) -> io::Result<CursorFile> {
+        CursorFile::decode(Cursor::new(self.bytes))
+    }
+
+    pub fn to_ani_file(&self) -> io::Result<AniFile> {
+        AniFile::decode(Cursor::new(self.bytes))
+    }
+}
+
+/// A .cur file whose frames borrow their image data from the input
+#[derive(Clone)]
+pub struct CursorView<'a> {
+    pub frames: Vec<FrameView<'a>>,
+}
+
+/// A borrowed `CursorFrame`
+#[derive(Clone, Copy)]
+pub struct FrameView<'a> {
+    pub size: FrameSize,
+    pub hotspot: Hotspot,
+    pub image_data: &'a [u8],
+}
+
+impl Debug for CursorBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CursorBytes")
+            .field("bytes", &BytesSummary(self.bytes))
+            .finish()
+    }
+}
+
+impl Debug for CursorView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CursorView")
+            .field("frames", &FramesSummary(&self.frames))
+            .finish()
+    }
+}
+
+impl Debug for FrameView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameView")
+            .field("size", &self.size)
+            .field("hotspot", &self.hotspot)
+            .field("image_data", &BytesSummary(self.image_data))
+            .finish()
+    }
+}
+
+impl<'a> CursorView<'a> {
+    pub fn parse(bytes: &'a [u8]) -> io::Result<Self> {
+        let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+        let header = bytes.get(..6).ok_or_else(|| invalid("Truncated header"))?;
+        if u16::from_le_bytes([header[2], header[3]]) != 2 {
+            return Err(invalid("Not a cursor file"));
+        }
+
+        let count = u16::from_le_bytes([header[4], header[5]]) as usize;
+        if count == 0 {
+            return Err(invalid("No frames"));
+        }
+
+        let directory = bytes
+            .get(6..6 + count * 16)
+            .ok_or_else(|| invalid("Truncated directory"))?;
+        let frames = directory
+            .chunks_exact(16)
+            .map(|entry| {
+                let size = u32::from_le_bytes([entry[8], entry[9], entry[10], entry[11]]) as usize;
+                let offset =
+                    u32::from_le_bytes([entry[12], entry[13], entry[14], entry[15]]) as usize;
+                let image_data = offset
+                    .checked_add(size)
+                    .and_then(|end| bytes.get(offset..end))
+                    .ok_or_else(|| invalid("Image data out of bounds"))?;
+
+                Ok(FrameView {
+                    size: stored_size(FrameSize::from_dir_bytes([entry[0], entry[1]]), image_data),
+                    hotspot: Hotspot::new(
+                        u16::from_le_bytes([entry[4], entry[5]]),
+                        u16::from_le_bytes([entry[6], entry[7]]),
+                    ),
+                    image_data,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(Self { frames })
+    }
+
+    /// Copy the image data into an owned `CursorFile`
+    pub fn to_cursor_file(&self) -> CursorFile {
+        CursorFile::new(self.frames.iter().map(FrameView::to_frame).collect())
+    }
+}
+
+impl<'a> FrameView<'a> {
+    pub fn to_frame(&self) -> CursorFrame {
+        CursorFrame::new(self.size, self.hotspot, self.image_data.to_vec())
+    }
+
+    /// The PNG payload, see `CursorFrame::png_data`
+    pub fn png_data(&self) -> Option<&'a [u8]> {
+        png_data(self.image_data)
+    }
+}
+
+impl CursorFile {
+    /// Parse embedded bytes without allocating for the image data
+    pub fn decode_static(bytes: &'static [u8]) -> io::Result<CursorView<'static>> {
+        CursorView::parse(bytes)
+    }
+}
@@ -0,0 +1,129 @@
+// Options shared by `CursorFile::encode_with` and `AniFile::encode_with`,
+// and the layout those encoders produce
+use std::io::{self, Seek, SeekFrom, Write};
+
+use crate::{
+    compat::CompatibilityProfile,
+    riff::{ChunkWriter, FourCC},
+};
+
+/// The defaults match plain `encode`
+#[derive(Debug, Clone, Default)]
+pub struct EncodeOptions {
+    /// Store `AniFile::loop_behavior` in a `LOOP` chunk unless it is `Infinite`
+    pub write_loop_chunk: bool,
+    /// Write byte-identical .cur payloads once, with every directory entry
+    /// for them pointing at the same offset
+    pub share_payloads: bool,
+    /// Pick payloads, frame order and metadata for a target platform,
+    /// failing if the cursor has frames it can't show
+    pub profile: Option<CompatibilityProfile>,
+}
+
+/// One part of an encoded file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    /// The .cur header, or the `RIFF` header with its form type
+    Header,
+    /// A .cur directory entry, by frame
+    DirectoryEntry(usize),
+    /// A whole chunk with its 8-byte header: `anih`, `seq `, `rate`,
+    /// `LOOP`, INFO tags and unknown chunks
+    Chunk(FourCC),
+    /// The 12-byte header of a `LIST`, by list type
+    List(FourCC),
+    /// A frame's image data; in a .ani, its whole `icon` chunk. Frames
+    /// sharing a .cur payload appear once, at the first of them
+    Frame(usize),
+    /// The pad byte after an odd-sized chunk
+    Padding,
+}
+
+/// Where everything lands in an encoded file, from `CursorFile::layout` or
+/// `AniFile::layout`
+///
+/// Entries are in file order and cover it without gaps or overlaps.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileLayout {
+    pub total: u64,
+    pub entries: Vec<(Section, u64, u64)>, // Section, offset, length
+}
+
+impl FileLayout {
+    /// Offset and length of the first entry for `section`
+    pub fn find(&self, section: Section) -> Option<(u64, u64)> {
+        self.entries
+            .iter()
+            .find(|entry| entry.0 == section)
+            .map(|&(_, offset, len)| (offset, len))
+    }
+
+    /// Append `len` bytes of `section` at the end
+    pub(crate) fn push(&mut self, section: Section, len: u64) {
+        self.entries.push((section, self.total, len));
+        self.total += len;
+    }
+
+    /// Append a chunk of `data_len` bytes, as `ChunkWriter::write_chunk`
+    /// writes it
+    pub(crate) fn push_chunk(&mut self, section: Section, data_len: usize) {
+        self.push(section, 8 + data_len as u64);
+        if !data_len.is_multiple_of(2) {
+            self.push(Section::Padding, 1);
+        }
+    }
+}
+
+/// Record into `layout`, if there is one
+pub(crate) fn record(layout: &mut Option<&mut FileLayout>, update: impl FnOnce(&mut FileLayout)) {
+    if let Some(layout) = layout {
+        update(layout);
+    }
+}
+
+/// Write a chunk, recording it as `Section::Chunk`
+pub(crate) fn write_chunk<W: Write + Seek>(
+    riff: &mut ChunkWriter<W>,
+    layout: &mut Option<&mut FileLayout>,
+    id: FourCC,
+    data: &[u8],
+) -> io::Result<()> {
+    riff.write_chunk(id, data)?;
+    record(layout, |layout| {
+        layout.push_chunk(Section::Chunk(id), data.len())
+    });
+    Ok(())
+}
+
+/// A writer that only keeps track of its position, for running an encoder
+/// without output
+#[derive(Debug, Default)]
+pub(crate) struct Discard {
+    position: u64,
+    len: u64,
+}
+
+impl Write for Discard {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.position += buf.len() as u64;
+        self.len = self.len.max(self.position);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for Discard {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+        self.position = position
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Seek before the start"))?;
+        Ok(self.position)
+    }
+}
@@ -0,0 +1,209 @@
+// Memory estimates for decoding, so a service can refuse a file up front
+//
+// An estimate counts the payload bytes a decoded file holds plus an RGBA
+// buffer for every image in it. Image sizes come from each payload's own
+// header (PNG IHDR or BITMAPINFOHEADER) where present, since directory
+// bytes can't express sizes above 256 and are sometimes wrong.
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+use crate::{ani::AniFile, cur::CursorFile, decode::invalid_chunk, riff::ChunkReader};
+
+/// What decoding a file and converting it to RGBA is expected to cost
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SizeEstimate {
+    /// Images across all frames, counting every resolution
+    pub images: usize,
+    /// Payload bytes the decoded file holds
+    pub payload_bytes: usize,
+    /// `width * height * 4` for every image
+    pub rgba_bytes: usize,
+}
+
+impl SizeEstimate {
+    pub fn total(&self) -> usize {
+        self.payload_bytes.saturating_add(self.rgba_bytes)
+    }
+
+    fn add(&mut self, other: SizeEstimate) {
+        self.images += other.images;
+        self.payload_bytes = self.payload_bytes.saturating_add(other.payload_bytes);
+        self.rgba_bytes = self.rgba_bytes.saturating_add(other.rgba_bytes);
+    }
+}
+
+impl CursorFile {
+    /// Bytes this cursor's payloads take plus an RGBA buffer per frame
+    pub fn estimated_decoded_size(&self) -> usize {
+        self.frames()
+            .map(|frame| {
+                let rgba = frame.size.width() as usize * frame.size.height() as usize * 4;
+                frame.image_data.len() + rgba
+            })
+            .sum()
+    }
+}
+
+impl AniFile {
+    /// Bytes the stored frames take plus an RGBA buffer for every
+    /// resolution of every frame
+    ///
+    /// ```
+    /// use std::{fs::File, io::Cursor};
+    ///
+    /// use cursor_handler::{AniFile, estimate};
+    ///
+    /// let bytes = std::fs::read("assets/aero_busy.ani")?;
+    /// let before = estimate::estimate_from_directory(File::open("assets/aero_busy.ani")?)?;
+    /// assert_eq!(before.images, 18 * 3);
+    /// assert_eq!(before.rgba_bytes, 18 * (64 * 64 + 48 * 48 + 32 * 32) * 4);
+    ///
+    /// // The estimate made from headers alone is within 10% of the decoded one
+    /// let after = AniFile::decode(Cursor::new(&bytes))?.estimated_decoded_size();
+    /// assert!(before.total().abs_diff(after) * 10 <= after);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn estimated_decoded_size(&self) -> usize {
+        self.frames()
+            .map(|frame| {
+                let len = frame.image_data.len() as u64;
+                let rgba = match directory(&mut Cursor::new(&frame.image_data), 0, len) {
+                    Ok(estimate) => estimate.rgba_bytes,
+                    Err(_) => frame.size.width() as usize * frame.size.height() as usize * 4,
+                };
+                frame.image_data.len() + rgba
+            })
+            .sum()
+    }
+}
+
+/// Estimate the cost of decoding the .cur or .ani at the reader's position
+/// from its headers alone
+///
+/// Only the RIFF chunk headers, the .cur directories and the first bytes
+/// of each payload are read, so nothing the size of a payload is allocated.
+/// The reader is left wherever the last header read ended.
+///
+/// `DecodeLimits::max_decoded_size` applies this estimate before decoding.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::{CursorFile, DecodeLimits, DecodeOptions, DecodeError, estimate};
+///
+/// let bytes = std::fs::read("assets/windows_theme/Link.cur")?;
+/// let before = estimate::estimate_from_directory(Cursor::new(&bytes))?;
+/// let after = CursorFile::decode(Cursor::new(&bytes))?.estimated_decoded_size();
+/// assert!(before.total().abs_diff(after) * 10 <= after);
+///
+/// let limits = DecodeLimits { max_decoded_size: before.total() - 1, ..Default::default() };
+/// let options = DecodeOptions { limits, ..Default::default() };
+/// let err = CursorFile::decode_with(Cursor::new(&bytes), &options).unwrap_err();
+/// assert!(DecodeError::find(&err).is_some());
+/// assert!(err.to_string().contains("over the limit"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn estimate_from_directory<R: Read + Seek>(mut reader: R) -> io::Result<SizeEstimate> {
+    let start = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(start))?;
+
+    let mut riff = [0; 12];
+    let read = read_up_to(&mut reader, &mut riff)?;
+    if read < 12 || &riff[..4] != b"RIFF" {
+        return directory(&mut reader, start, end - start);
+    }
+    if &riff[8..] != b"ACON" {
+        return Err(invalid_chunk("RIFF", None, 8, "Not an ANI file"));
+    }
+
+    // Where each icon's data starts and how much of it is present
+    let mut icons = Vec::new();
+    let mut chunks = ChunkReader::new(&mut reader)?;
+    while let Some(chunk) = chunks.next_chunk()? {
+        if &chunk.id() != b"LIST" || chunk.available() < 4 {
+            continue;
+        }
+        let (list_type, mut items) = chunk.list()?;
+        if &list_type != b"fram" {
+            continue;
+        }
+        while let Some(item) = items.next_chunk()? {
+            if &item.id() == b"icon" {
+                icons.push((item.offset() + 8, item.available()));
+            }
+        }
+    }
+
+    let mut estimate = SizeEstimate::default();
+    for (index, (offset, len)) in icons.into_iter().enumerate() {
+        let icon = directory(&mut reader, offset, len)
+            .map_err(|err| invalid_chunk("icon", Some(index), offset, err.to_string()))?;
+        estimate.add(SizeEstimate {
+            payload_bytes: len as usize,
+            ..icon
+        });
+    }
+    Ok(estimate)
+}
+
+/// Estimate one .cur/.ico container of `len` bytes at `base`
+fn directory<R: Read + Seek>(reader: &mut R, base: u64, len: u64) -> io::Result<SizeEstimate> {
+    reader.seek(SeekFrom::Start(base))?;
+    let mut header = [0; 6];
+    if read_up_to(reader, &mut header)? < 6 || !matches!(header[2..4], [1, 0] | [2, 0]) {
+        return Err(invalid_chunk("header", None, 0, "Not a cursor file"));
+    }
+    let count = u16::from_le_bytes([header[4], header[5]]) as usize;
+    let mut entries = vec![0; count * 16];
+    if read_up_to(reader, &mut entries)? < entries.len() {
+        return Err(invalid_chunk(
+            "directory",
+            None,
+            6,
+            "Directory is truncated",
+        ));
+    }
+
+    let mut estimate = SizeEstimate::default();
+    for entry in entries.chunks_exact(16) {
+        let size = u32::from_le_bytes([entry[8], entry[9], entry[10], entry[11]]) as u64;
+        let offset = u32::from_le_bytes([entry[12], entry[13], entry[14], entry[15]]) as u64;
+        let side = |byte: u8| if byte == 0 { 256 } else { byte as u64 };
+        let (mut width, mut height) = (side(entry[0]), side(entry[1]));
+
+        // The payload's own header, when it is there to read
+        let mut head = [0; 24];
+        reader.seek(SeekFrom::Start(base + offset))?;
+        let read = read_up_to(reader, &mut head)?;
+        let word =
+            |at: usize| u32::from_le_bytes([head[at], head[at + 1], head[at + 2], head[at + 3]]);
+        if read == 24 && head.starts_with(b"\x89PNG") {
+            width = u32::from_be_bytes([head[16], head[17], head[18], head[19]]) as u64;
+            height = u32::from_be_bytes([head[20], head[21], head[22], head[23]]) as u64;
+        } else if read >= 12 && word(0) >= 40 {
+            // The DIB height covers the color and mask planes
+            width = (word(4) as i32).unsigned_abs() as u64;
+            height = (word(8) as i32).unsigned_abs() as u64 / 2;
+        }
+
+        estimate.add(SizeEstimate {
+            images: 1,
+            payload_bytes: size.min(len.saturating_sub(offset)) as usize,
+            rgba_bytes: usize::try_from(width.saturating_mul(height).saturating_mul(4))
+                .unwrap_or(usize::MAX),
+        });
+    }
+    Ok(estimate)
+}
+
+/// Fill as much of `buf` as the reader has, returning how much that was
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
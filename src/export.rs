@@ -0,0 +1,597 @@
+// Conversions between cursors and common animation formats
+use std::{
+    cell::Cell,
+    io::{self, BufReader, Cursor, Read, Seek},
+};
+
+use image::{
+    Delay, DynamicImage, Frame, ImageFormat, ImageReader, RgbaImage,
+    codecs::gif::{GifEncoder, Repeat},
+    imageops,
+};
+use png::{BitDepth, BlendOp, ColorType, Decoder, DisposeOp, Encoder, Transformations};
+
+use crate::{
+    ani::{AniFile, LoopBehavior},
+    cur::{CursorFile, CursorFrame},
+    dib::encode_dib32,
+    generate::cursor_frame,
+    hotspot::{Hotspot, HotspotSpec},
+    optimize::encode_png,
+    progress::{CountingWriter, NoProgress, Phase, Progress, Tracker},
+    size::FrameSize,
+    transform::fit_square,
+};
+
+impl AniFile {
+    /// Render every playback step into an APNG, looping as `loop_behavior` says
+    ///
+    /// Frames smaller than the largest one are placed at the top-left corner
+    /// of the shared canvas.
+    pub fn to_apng(&self) -> io::Result<Vec<u8>> {
+        self.to_apng_with_progress(&NoProgress)
+    }
+
+    /// `to_apng`, reporting each step as it is written
+    ///
+    /// ```
+    /// use std::{io::Cursor, sync::Mutex};
+    ///
+    /// use cursor_handler::{AniFile, progress::{Phase, ProgressEvent}};
+    ///
+    /// let mut ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// ani.sequence.truncate(10);
+    /// ani.rates.truncate(10);
+    ///
+    /// let events = Mutex::new(Vec::new());
+    /// let apng = ani.to_apng_with_progress(&|event: ProgressEvent| {
+    ///     events.lock().unwrap().push(event)
+    /// })?;
+    ///
+    /// let events = events.into_inner().unwrap();
+    /// assert_eq!(events.len(), 10);
+    /// assert!(events.iter().enumerate().all(|(i, event)| event.completed == i + 1));
+    /// assert!(events.iter().all(|event| event.phase == Phase::Encoding && event.total == 10));
+    /// assert!(events[9].bytes_written <= apng.len() as u64);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn to_apng_with_progress(&self, progress: &dyn Progress) -> io::Result<Vec<u8>> {
+        self.write_apng(self.render_steps()?, progress)
+    }
+
+    /// `to_apng` with every step at `size`, see `steps_at_size`
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::AniFile;
+    ///
+    /// let ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// for (size, apng) in [(48, ani.to_apng_at_size(48)?), (40, ani.to_apng_at_size(40)?)] {
+    ///     let apng = image::load_from_memory(&apng).map_err(std::io::Error::other)?;
+    ///     assert_eq!((apng.width(), apng.height()), (size, size));
+    /// }
+    /// let gif = ani.to_gif_at_size(32)?;
+    /// let gif = image::load_from_memory(&gif).map_err(std::io::Error::other)?;
+    /// assert_eq!((gif.width(), gif.height()), (32, 32));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn to_apng_at_size(&self, size: u32) -> io::Result<Vec<u8>> {
+        self.write_apng(self.render_steps_at(size)?, &NoProgress)
+    }
+
+    fn write_apng(
+        &self,
+        canvases: Vec<(RgbaImage, u32)>,
+        progress: &dyn Progress,
+    ) -> io::Result<Vec<u8>> {
+        let (width, height) = canvases[0].0.dimensions();
+        let tracker = Tracker::new(progress, Phase::Encoding, canvases.len());
+
+        let mut apng = Vec::new();
+        let written = Cell::new(0);
+        let counting = CountingWriter {
+            inner: &mut apng,
+            count: &written,
+        };
+        let mut encoder = Encoder::new(counting, width, height);
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+        let plays = self.loop_behavior.plays().unwrap_or(0) as u32; // 0 loops forever
+        encoder
+            .set_animated(canvases.len() as u32, plays)
+            .map_err(io::Error::other)?;
+
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        let mut reported = 0;
+        for (canvas, duration) in &canvases {
+            // Jiffies are 1/60 s, so the delay fraction needs no rounding
+            let delay = u16::try_from(*duration).unwrap_or(u16::MAX);
+            writer
+                .set_frame_delay(delay, 60)
+                .map_err(io::Error::other)?;
+            writer
+                .write_image_data(canvas.as_raw())
+                .map_err(io::Error::other)?;
+            tracker.advance(written.get() - reported)?;
+            reported = written.get();
+        }
+        writer.finish().map_err(io::Error::other)?;
+
+        Ok(apng)
+    }
+
+    /// Render every playback step into a GIF, looping as `loop_behavior` says
+    ///
+    /// GIF stores delays in hundredths of a second and has 1-bit transparency,
+    /// so timing and soft edges are approximated.
+    pub fn to_gif(&self) -> io::Result<Vec<u8>> {
+        self.to_gif_with_progress(&NoProgress)
+    }
+
+    /// `to_gif`, reporting each step as it is written
+    pub fn to_gif_with_progress(&self, progress: &dyn Progress) -> io::Result<Vec<u8>> {
+        self.write_gif(self.render_steps()?, progress)
+    }
+
+    /// `to_gif` with every step at `size`, see `steps_at_size`
+    pub fn to_gif_at_size(&self, size: u32) -> io::Result<Vec<u8>> {
+        self.write_gif(self.render_steps_at(size)?, &NoProgress)
+    }
+
+    fn write_gif(
+        &self,
+        canvases: Vec<(RgbaImage, u32)>,
+        progress: &dyn Progress,
+    ) -> io::Result<Vec<u8>> {
+        let tracker = Tracker::new(progress, Phase::Encoding, canvases.len());
+
+        let mut gif = Vec::new();
+        {
+            let written = Cell::new(0);
+            let mut encoder = GifEncoder::new(CountingWriter {
+                inner: &mut gif,
+                count: &written,
+            });
+            // The NETSCAPE2.0 extension counts repeats after the first play,
+            // and a GIF without it plays once
+            match self.loop_behavior.plays() {
+                None => encoder.set_repeat(Repeat::Infinite),
+                Some(1) => Ok(()),
+                Some(plays) => encoder.set_repeat(Repeat::Finite(plays - 1)),
+            }
+            .map_err(io::Error::other)?;
+
+            let mut reported = 0;
+            for (canvas, duration) in canvases {
+                let delay = Delay::from_numer_denom_ms(duration.saturating_mul(1000), 60);
+                let frame = Frame::from_parts(canvas, 0, 0, delay);
+                encoder.encode_frame(frame).map_err(io::Error::other)?;
+                tracker.advance(written.get() - reported)?;
+                reported = written.get();
+            }
+        }
+
+        Ok(gif)
+    }
+
+    /// Build an animation from an APNG, one frame and step per APNG frame
+    ///
+    /// Frames are composited onto the full canvas and stored as PNG-payload
+    /// cursors with a (0, 0) hotspot. `num_plays` becomes `loop_behavior`, and
+    /// delays are rounded to whole jiffies. A still PNG gives a single frame.
+    ///
+    /// ```
+    /// use cursor_handler::test_utils::synthetic_ani;
+    /// use cursor_handler::{AniFile, LoopBehavior};
+    ///
+    /// let mut ani = synthetic_ani(2, 2, 4)?;
+    /// ani.loop_behavior = LoopBehavior::Count(3);
+    ///
+    /// let back = AniFile::from_apng(&ani.to_apng()?)?;
+    /// assert_eq!(back.loop_behavior, LoopBehavior::Count(3));
+    /// assert_eq!(back.num_frames(), 2);
+    /// assert_eq!(back.header.default_rate(), 4);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_apng(bytes: &[u8]) -> io::Result<Self> {
+        let mut decoder = Decoder::new(Cursor::new(bytes));
+        decoder.set_transformations(Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(io::Error::other)?;
+
+        let info = reader.info();
+        let (width, height) = (info.width, info.height);
+        let animation = info.animation_control;
+        // Without an fcTL before it, the default image is only a fallback
+        let skip_default = animation.is_some() && info.frame_control.is_none();
+        let count = animation.map_or(1, |control| control.num_frames as usize);
+
+        let mut canvas = RgbaImage::new(width, height);
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let mut images = Vec::with_capacity(count);
+        let mut durations = Vec::with_capacity(count);
+        for index in 0..count + skip_default as usize {
+            let output = reader.next_frame(&mut buffer).map_err(io::Error::other)?;
+            if skip_default && index == 0 {
+                continue;
+            }
+            let image = rgba_rows(&buffer, &output)?;
+
+            let Some(control) = reader.info().frame_control else {
+                images.push(image);
+                continue;
+            };
+            let den = if control.delay_den == 0 {
+                100
+            } else {
+                control.delay_den as u32
+            };
+            durations.push(((control.delay_num as u32 * 60 + den / 2) / den).max(1));
+
+            let (x, y) = (control.x_offset as i64, control.y_offset as i64);
+            let previous = (control.dispose_op == DisposeOp::Previous).then(|| canvas.clone());
+            match control.blend_op {
+                BlendOp::Source => imageops::replace(&mut canvas, &image, x, y),
+                BlendOp::Over => imageops::overlay(&mut canvas, &image, x, y),
+            }
+            images.push(canvas.clone());
+
+            match control.dispose_op {
+                DisposeOp::None => {}
+                DisposeOp::Background => {
+                    let clear = RgbaImage::new(control.width, control.height);
+                    imageops::replace(&mut canvas, &clear, x, y);
+                }
+                DisposeOp::Previous => canvas = previous.unwrap_or(canvas),
+            }
+        }
+
+        let mut ani = AniFile::empty();
+        for image in &images {
+            ani.push_frame(cursor_frame(image, Hotspot::default())?);
+        }
+        // A still PNG has no delays and keeps the default rate
+        match durations.as_slice() {
+            [] => {}
+            [first, rest @ ..] if rest.iter().all(|rate| rate == first) => {
+                ani.header.set_default_rate(*first)?;
+            }
+            _ => ani.rates = durations,
+        }
+        ani.loop_behavior = match animation.map_or(0, |control| control.num_plays) {
+            0 => LoopBehavior::Infinite,
+            1 => LoopBehavior::PlayOnceHoldLast,
+            plays => LoopBehavior::Count(u16::try_from(plays).unwrap_or(u16::MAX)),
+        };
+        Ok(ani)
+    }
+
+    /// Build an animation from a film-strip BMP, frames stacked vertically
+    ///
+    /// The strip is cut into `frame_count` cells of equal height, read in
+    /// `order`. Every frame gets `hotspot` and plays for `rate` jiffies.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, StripOrder};
+    /// use image::{ImageFormat, Rgba, RgbaImage};
+    ///
+    /// // Four 8x8 cells, shaded 0, 60, 120, 180 from the top
+    /// let strip = RgbaImage::from_fn(8, 32, |_, y| Rgba([(y / 8 * 60) as u8, 0, 0, 255]));
+    /// let mut bmp = Vec::new();
+    /// strip.write_to(&mut Cursor::new(&mut bmp), ImageFormat::Bmp)
+    ///     .map_err(std::io::Error::other)?;
+    ///
+    /// let shade = |ani: &AniFile, index| -> std::io::Result<u8> {
+    ///     Ok(ani.extract_frame(index)?[0].decode_image()?.get_pixel(0, 0)[0])
+    /// };
+    /// let down = AniFile::from_strip_bmp(Cursor::new(&bmp), 4, (2, 3), 5, StripOrder::TopDown)?;
+    /// let up = AniFile::from_strip_bmp(Cursor::new(&bmp), 4, (2, 3), 5, StripOrder::BottomUp)?;
+    /// assert_eq!((down.num_frames(), down.header.default_rate()), (4, 5));
+    /// assert_eq!((shade(&down, 0)?, shade(&down, 3)?), (0, 180));
+    /// assert_eq!((shade(&up, 0)?, shade(&up, 3)?), (180, 0));
+    ///
+    /// // Writing the strip back gives the same pixels
+    /// let back = image::load_from_memory(&down.to_strip_bmp()?).map_err(std::io::Error::other)?;
+    /// assert_eq!(back.to_rgba8(), strip);
+    ///
+    /// let err = AniFile::from_strip_bmp(Cursor::new(&bmp), 3, (0, 0), 5, StripOrder::TopDown);
+    /// assert!(err.unwrap_err().to_string().contains("not a multiple of 3"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_strip_bmp<R: Read + Seek>(
+        reader: R,
+        frame_count: u32,
+        hotspot: impl Into<HotspotSpec>,
+        rate: u32,
+        order: StripOrder,
+    ) -> io::Result<Self> {
+        if frame_count == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "A strip needs frames",
+            ));
+        }
+        let strip = ImageReader::with_format(BufReader::new(reader), ImageFormat::Bmp)
+            .decode()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?
+            .into_rgba8();
+
+        let (width, height) = strip.dimensions();
+        if height % frame_count != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Strip height {height} is not a multiple of {frame_count} frames"),
+            ));
+        }
+        let cell = height / frame_count;
+        let hotspot = hotspot.into().resolve(width, cell);
+        if !hotspot.is_within(width, cell) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Hotspot {hotspot} is outside the {width}x{cell} frames"),
+            ));
+        }
+
+        let mut ani = AniFile::empty().with_default_rate(rate)?;
+        for index in 0..frame_count {
+            let slot = match order {
+                StripOrder::TopDown => index,
+                StripOrder::BottomUp => frame_count - 1 - index,
+            };
+            let image = imageops::crop_imm(&strip, 0, slot * cell, width, cell).to_image();
+            ani.push_frame(cursor_frame(&image, hotspot)?);
+        }
+        Ok(ani)
+    }
+
+    /// Stack the stored frames top to bottom into a 32-bit film-strip BMP
+    ///
+    /// Frames holding several resolutions give their largest. All frames
+    /// must be the same size; pad or resize mixed ones first.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, StripOrder};
+    /// use image::{ImageFormat, RgbaImage};
+    ///
+    /// let strip = |width, height| -> std::io::Result<AniFile> {
+    ///     let mut bmp = Vec::new();
+    ///     RgbaImage::new(width, height)
+    ///         .write_to(&mut Cursor::new(&mut bmp), ImageFormat::Bmp)
+    ///         .map_err(std::io::Error::other)?;
+    ///     AniFile::from_strip_bmp(Cursor::new(bmp), 1, (0, 0), 5, StripOrder::TopDown)
+    /// };
+    /// let mut ani = strip(8, 8)?;
+    /// ani.push_frame(strip(4, 4)?.frames().next().unwrap().clone());
+    ///
+    /// let err = ani.to_strip_bmp().unwrap_err();
+    /// assert!(err.to_string().starts_with("Frame 1 is 4x4 but frame 0 is 8x8; pad or resize"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn to_strip_bmp(&self) -> io::Result<Vec<u8>> {
+        let mut images = Vec::with_capacity(self.frames.len());
+        for index in 0..self.frames.len() {
+            let cursor = self.extract_frame(index)?;
+            let frame = cursor
+                .frames()
+                .max_by_key(|frame| frame.size.max_side())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Frame {index} is empty"),
+                    )
+                })?;
+            images.push(frame.decode_image()?);
+        }
+        let Some(first) = images.first() else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No frames"));
+        };
+
+        let (width, cell) = first.dimensions();
+        if let Some((index, odd)) = images
+            .iter()
+            .enumerate()
+            .find(|(_, image)| image.dimensions() != (width, cell))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Frame {index} is {}x{} but frame 0 is {width}x{cell}; \
+                     pad or resize the frames to one size first",
+                    odd.width(),
+                    odd.height()
+                ),
+            ));
+        }
+
+        let mut strip = RgbaImage::new(width, cell * images.len() as u32);
+        for (index, image) in images.iter().enumerate() {
+            imageops::replace(&mut strip, image, 0, i64::from(cell) * index as i64);
+        }
+        let mut bmp = Vec::new();
+        DynamicImage::ImageRgba8(strip)
+            .write_to(&mut Cursor::new(&mut bmp), ImageFormat::Bmp)
+            .map_err(io::Error::other)?;
+        Ok(bmp)
+    }
+
+    /// Every playback step drawn on a canvas fitting the largest frame, with
+    /// its duration in jiffies
+    fn render_steps(&self) -> io::Result<Vec<(RgbaImage, u32)>> {
+        let images = self
+            .frames
+            .iter()
+            .map(|frame| frame.decode_image())
+            .collect::<io::Result<Vec<_>>>()?;
+        let steps: Vec<_> = self.steps().collect();
+        if steps.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No steps"));
+        }
+
+        let width = images.iter().map(RgbaImage::width).max().unwrap_or(1);
+        let height = images.iter().map(RgbaImage::height).max().unwrap_or(1);
+
+        Ok(steps
+            .iter()
+            .map(|step| {
+                let mut canvas = RgbaImage::new(width, height);
+                imageops::replace(&mut canvas, &images[step.frame_index], 0, 0);
+                (canvas, step.duration)
+            })
+            .collect())
+    }
+
+    /// Each step at `size` with its duration
+    fn render_steps_at(&self, size: u32) -> io::Result<Vec<(RgbaImage, u32)>> {
+        let steps = self.steps_at_size(size)?;
+        if steps.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No steps"));
+        }
+        Ok(steps
+            .into_iter()
+            .map(|step| (step.image, step.duration))
+            .collect())
+    }
+}
+
+/// Which end of a film-strip BMP holds the first frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StripOrder {
+    /// First frame at the top
+    #[default]
+    TopDown,
+    /// First frame at the bottom, as some older editors stack them
+    BottomUp,
+}
+
+/// Sizes `to_favicon` writes when given none
+pub const FAVICON_SIZES: [u32; 3] = [16, 32, 48];
+
+/// Favicon sizes from this one up are stored as PNG, smaller ones as DIB
+const FAVICON_PNG_THRESHOLD: u32 = 64;
+
+impl CursorFile {
+    /// Build a favicon-style .ico holding `sizes` (`FAVICON_SIZES` if empty)
+    ///
+    /// Each size is resampled from the frame of exactly that size, else the
+    /// smallest larger one, else the largest. Non-square art is scaled to
+    /// fit and centred. Sizes below 64 are stored as 32-bit DIBs, which
+    /// every browser reads; larger ones as PNG.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, CursorFile, DecodeOptions};
+    /// use image::ImageFormat;
+    ///
+    /// let ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// let favicon = ani.extract_frame(0)?.to_favicon(&[])?;
+    ///
+    /// let options = DecodeOptions { allow_ico: true, ..Default::default() };
+    /// let ico = CursorFile::decode_with(Cursor::new(&favicon), &options)?.value;
+    /// let sizes: Vec<_> = ico.frames().map(|frame| frame.size.to_string()).collect();
+    /// assert_eq!(sizes, ["16x16", "32x32", "48x48"]);
+    /// assert!(ico.frames().all(|frame| frame.payload_format() == "DIB-32"));
+    ///
+    /// let image = image::load_from_memory_with_format(&favicon, ImageFormat::Ico)
+    ///     .map_err(std::io::Error::other)?;
+    /// assert_eq!(image.width(), 48);
+    ///
+    /// let large = ani.extract_frame(0)?.to_favicon(&[16, 128])?;
+    /// let ico = CursorFile::decode_with(Cursor::new(&large), &options)?.value;
+    /// assert_eq!(ico[1].payload_format(), "PNG");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn to_favicon(&self, sizes: &[u32]) -> io::Result<Vec<u8>> {
+        let sizes = if sizes.is_empty() {
+            &FAVICON_SIZES[..]
+        } else {
+            sizes
+        };
+
+        let mut frames = Vec::with_capacity(sizes.len());
+        for &size in sizes {
+            let frame_size = FrameSize::square(size)?;
+            let source = self
+                .frames
+                .iter()
+                .filter(|frame| frame.size.max_side() >= size)
+                .min_by_key(|frame| frame.size.max_side())
+                .or_else(|| self.frames.iter().max_by_key(|frame| frame.size.max_side()))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No frames"))?;
+
+            let image = fit_square(&source.decode_image()?, size);
+
+            let payload = if size >= FAVICON_PNG_THRESHOLD {
+                encode_png(&image)?
+            } else {
+                encode_dib32(&image)
+            };
+            frames.push(CursorFrame::new(frame_size, Hotspot::default(), payload));
+        }
+
+        let mut ico = Vec::new();
+        CursorFile::new(frames).encode_as_ico(&mut ico)?;
+        Ok(ico)
+    }
+}
+
+/// Expand one decoded 8-bit PNG frame to RGBA
+fn rgba_rows(buffer: &[u8], output: &png::OutputInfo) -> io::Result<RgbaImage> {
+    RgbaImage::from_raw(output.width, output.height, rgba_pixels(buffer, output)?)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Short PNG frame"))
+}
+
+/// A decoded 8-bit PNG frame as tightly packed RGBA
+pub(crate) fn rgba_pixels(buffer: &[u8], output: &png::OutputInfo) -> io::Result<Vec<u8>> {
+    let channels = match output.color_type {
+        ColorType::Grayscale => 1,
+        ColorType::GrayscaleAlpha => 2,
+        ColorType::Rgb => 3,
+        ColorType::Rgba => 4,
+        ColorType::Indexed => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpanded palette",
+            ));
+        }
+    };
+
+    let mut pixels = Vec::with_capacity(output.width as usize * output.height as usize * 4);
+    for row in buffer.chunks(output.line_size).take(output.height as usize) {
+        for pixel in row[..output.width as usize * channels].chunks_exact(channels) {
+            pixels.extend_from_slice(&match *pixel {
+                [v] => [v, v, v, 255],
+                [v, a] => [v, v, v, a],
+                [r, g, b] => [r, g, b, 255],
+                [r, g, b, a] => [r, g, b, a],
+                _ => unreachable!(),
+            });
+        }
+    }
+    Ok(pixels)
+}
+
+/// Decode `bytes` as .cur or .ani and return frame `index` as RGBA
+///
+/// For ANI files `index` counts stored frames, not playback steps.
+pub fn frame_rgba(bytes: &[u8], index: usize) -> io::Result<RgbaImage> {
+    let out_of_range = || io::Error::new(io::ErrorKind::InvalidInput, "frame index out of range");
+
+    if bytes.starts_with(b"RIFF") {
+        let ani = AniFile::decode(Cursor::new(bytes))?;
+        ani.frames
+            .get(index)
+            .ok_or_else(out_of_range)?
+            .decode_image()
+    } else {
+        let cur = CursorFile::decode(Cursor::new(bytes))?;
+        cur.frames
+            .get(index)
+            .ok_or_else(out_of_range)?
+            .decode_image()
+    }
+}
@@ -0,0 +1,266 @@
+// C API, see include/cursor_handler.h
+//
+// Every function returns a CH_* status code and never unwinds into C: panics
+// are caught and reported as CH_ERR_PANIC. Objects handed out are owned by
+// the caller and must be released with the matching _free function.
+#![allow(non_camel_case_types)]
+
+use std::{
+    io::Cursor,
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use image::RgbaImage;
+
+use crate::{ani::AniFile, cur::CursorFile};
+
+pub const CH_OK: i32 = 0;
+pub const CH_ERR_NULL: i32 = 1;
+pub const CH_ERR_INVALID_DATA: i32 = 2;
+pub const CH_ERR_OUT_OF_RANGE: i32 = 3;
+pub const CH_ERR_PANIC: i32 = 4;
+
+/// Opaque handle to a decoded .cur
+pub struct ch_cursor(CursorFile);
+
+/// Opaque handle to a decoded .ani
+pub struct ch_ani(AniFile);
+
+/// Decode a .cur from `len` bytes at `data`
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `out` must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ch_cursor_decode(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut ch_cursor,
+) -> i32 {
+    guard(|| {
+        let bytes = unsafe { input(data, len)? };
+        let cursor = CursorFile::decode(Cursor::new(bytes)).map_err(|_| CH_ERR_INVALID_DATA)?;
+        unsafe { write(out, Box::into_raw(Box::new(ch_cursor(cursor)))) }
+    })
+}
+
+/// Number of frames in `cursor`, or 0 if it is NULL
+///
+/// # Safety
+/// `cursor` must be NULL or a live handle from `ch_cursor_decode`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ch_cursor_frame_count(cursor: *const ch_cursor) -> usize {
+    unsafe { cursor.as_ref() }.map_or(0, |cursor| cursor.0.frames.len())
+}
+
+/// Size of frame `index` in pixels
+///
+/// # Safety
+/// `cursor` must be NULL or a live handle; the outputs must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ch_cursor_frame_size(
+    cursor: *const ch_cursor,
+    index: usize,
+    out_width: *mut u32,
+    out_height: *mut u32,
+) -> i32 {
+    guard(|| {
+        let cursor = unsafe { cursor.as_ref() }.ok_or(CH_ERR_NULL)?;
+        let frame = cursor.0.frames.get(index).ok_or(CH_ERR_OUT_OF_RANGE)?;
+        unsafe {
+            write(out_width, frame.size.width())?;
+            write(out_height, frame.size.height())
+        }
+    })
+}
+
+/// Hotspot of frame `index`
+///
+/// # Safety
+/// `cursor` must be NULL or a live handle; the outputs must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ch_cursor_frame_hotspot(
+    cursor: *const ch_cursor,
+    index: usize,
+    out_x: *mut u16,
+    out_y: *mut u16,
+) -> i32 {
+    guard(|| {
+        let cursor = unsafe { cursor.as_ref() }.ok_or(CH_ERR_NULL)?;
+        let frame = cursor.0.frames.get(index).ok_or(CH_ERR_OUT_OF_RANGE)?;
+        unsafe {
+            write(out_x, frame.hotspot.x)?;
+            write(out_y, frame.hotspot.y)
+        }
+    })
+}
+
+/// Decode frame `index` of `cursor` into a width * height * 4 byte RGBA buffer
+///
+/// The buffer must be released with `ch_rgba_free`.
+///
+/// # Safety
+/// `cursor` must be NULL or a live handle; the outputs must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ch_frame_rgba(
+    cursor: *const ch_cursor,
+    index: usize,
+    out_data: *mut *mut u8,
+    out_len: *mut usize,
+    out_width: *mut u32,
+    out_height: *mut u32,
+) -> i32 {
+    guard(|| {
+        let cursor = unsafe { cursor.as_ref() }.ok_or(CH_ERR_NULL)?;
+        let frame = cursor.0.frames.get(index).ok_or(CH_ERR_OUT_OF_RANGE)?;
+        let image = frame.decode_image().map_err(|_| CH_ERR_INVALID_DATA)?;
+        unsafe { write_rgba(image, out_data, out_len, out_width, out_height) }
+    })
+}
+
+/// # Safety
+/// `cursor` must be NULL or a handle that has not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ch_cursor_free(cursor: *mut ch_cursor) {
+    if !cursor.is_null() {
+        drop(unsafe { Box::from_raw(cursor) });
+    }
+}
+
+/// Decode an .ani from `len` bytes at `data`
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `out` must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ch_ani_decode(data: *const u8, len: usize, out: *mut *mut ch_ani) -> i32 {
+    guard(|| {
+        let bytes = unsafe { input(data, len)? };
+        let ani = AniFile::decode(Cursor::new(bytes)).map_err(|_| CH_ERR_INVALID_DATA)?;
+        unsafe { write(out, Box::into_raw(Box::new(ch_ani(ani)))) }
+    })
+}
+
+/// Number of stored frames in `ani`, or 0 if it is NULL
+///
+/// # Safety
+/// `ani` must be NULL or a live handle from `ch_ani_decode`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ch_ani_frame_count(ani: *const ch_ani) -> usize {
+    unsafe { ani.as_ref() }.map_or(0, |ani| ani.0.frames.len())
+}
+
+/// Number of playback steps in `ani`, or 0 if it is NULL
+///
+/// # Safety
+/// `ani` must be NULL or a live handle from `ch_ani_decode`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ch_ani_step_count(ani: *const ch_ani) -> usize {
+    unsafe { ani.as_ref() }.map_or(0, |ani| ani.0.steps().count())
+}
+
+/// Stored frame index and duration of playback step `index`
+///
+/// # Safety
+/// `ani` must be NULL or a live handle; the outputs must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ch_ani_step(
+    ani: *const ch_ani,
+    index: usize,
+    out_frame: *mut u32,
+    out_duration_ms: *mut u32,
+) -> i32 {
+    guard(|| {
+        let ani = unsafe { ani.as_ref() }.ok_or(CH_ERR_NULL)?;
+        let step = ani.0.steps().nth(index).ok_or(CH_ERR_OUT_OF_RANGE)?;
+        let duration_ms = (u64::from(step.duration) * 1000 / 60).min(u32::MAX as u64) as u32;
+        unsafe {
+            write(out_frame, step.frame_index as u32)?;
+            write(out_duration_ms, duration_ms)
+        }
+    })
+}
+
+/// Decode stored frame `index` of `ani`, see `ch_frame_rgba`
+///
+/// # Safety
+/// `ani` must be NULL or a live handle; the outputs must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ch_ani_frame_rgba(
+    ani: *const ch_ani,
+    index: usize,
+    out_data: *mut *mut u8,
+    out_len: *mut usize,
+    out_width: *mut u32,
+    out_height: *mut u32,
+) -> i32 {
+    guard(|| {
+        let ani = unsafe { ani.as_ref() }.ok_or(CH_ERR_NULL)?;
+        let frame = ani.0.frames.get(index).ok_or(CH_ERR_OUT_OF_RANGE)?;
+        let image = frame.decode_image().map_err(|_| CH_ERR_INVALID_DATA)?;
+        unsafe { write_rgba(image, out_data, out_len, out_width, out_height) }
+    })
+}
+
+/// # Safety
+/// `ani` must be NULL or a handle that has not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ch_ani_free(ani: *mut ch_ani) {
+    if !ani.is_null() {
+        drop(unsafe { Box::from_raw(ani) });
+    }
+}
+
+/// Release a buffer returned by `ch_frame_rgba` or `ch_ani_frame_rgba`
+///
+/// # Safety
+/// `data` and `len` must be exactly as returned, and not freed before.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ch_rgba_free(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)) });
+    }
+}
+
+fn guard(body: impl FnOnce() -> Result<(), i32>) -> i32 {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => CH_OK,
+        Ok(Err(status)) => status,
+        Err(_) => CH_ERR_PANIC,
+    }
+}
+
+unsafe fn input<'a>(data: *const u8, len: usize) -> Result<&'a [u8], i32> {
+    if data.is_null() {
+        return Err(CH_ERR_NULL);
+    }
+    Ok(unsafe { slice::from_raw_parts(data, len) })
+}
+
+unsafe fn write<T>(out: *mut T, value: T) -> Result<(), i32> {
+    if out.is_null() {
+        return Err(CH_ERR_NULL);
+    }
+    unsafe { out.write(value) };
+    Ok(())
+}
+
+unsafe fn write_rgba(
+    image: RgbaImage,
+    out_data: *mut *mut u8,
+    out_len: *mut usize,
+    out_width: *mut u32,
+    out_height: *mut u32,
+) -> Result<(), i32> {
+    if out_data.is_null() || out_len.is_null() {
+        return Err(CH_ERR_NULL);
+    }
+    let (width, height) = image.dimensions();
+    let pixels = image.into_raw().into_boxed_slice();
+    let len = pixels.len();
+    unsafe {
+        write(out_width, width)?;
+        write(out_height, height)?;
+        write(out_len, len)?;
+        write(out_data, Box::into_raw(pixels).cast::<u8>())
+    }
+}
@@ -0,0 +1,267 @@
+// Borrowed access to either kind of cursor file
+use std::io;
+#[cfg(feature = "fs")]
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::{ani::AniFile, cur::CursorFile, encode::EncodeOptions};
+
+/// Either kind of cursor file
+#[derive(Debug, Clone, Copy)]
+pub enum CursorRef<'a> {
+    Cur(&'a CursorFile),
+    Ani(&'a AniFile),
+}
+
+impl<'a> From<&'a CursorFile> for CursorRef<'a> {
+    fn from(file: &'a CursorFile) -> Self {
+        Self::Cur(file)
+    }
+}
+
+impl<'a> From<&'a AniFile> for CursorRef<'a> {
+    fn from(file: &'a AniFile) -> Self {
+        Self::Ani(file)
+    }
+}
+
+impl CursorRef<'_> {
+    /// File extension matching the cursor kind
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Cur(_) => "cur",
+            Self::Ani(_) => "ani",
+        }
+    }
+
+    pub fn encode(self) -> io::Result<Vec<u8>> {
+        self.encode_with(&EncodeOptions::default())
+    }
+
+    pub fn encode_with(self, options: &EncodeOptions) -> io::Result<Vec<u8>> {
+        let mut buf = io::Cursor::new(Vec::new());
+        match self {
+            Self::Cur(cur) => {
+                cur.encode_with(&mut buf, options)?;
+            }
+            Self::Ani(ani) => ani.encode_with(&mut buf, options)?,
+        }
+        Ok(buf.into_inner())
+    }
+
+    /// Whether encoding gives exactly `existing`
+    ///
+    /// Encoding is deterministic, so this holds for bytes previously written
+    /// from an equal cursor.
+    pub fn encodes_identically_to(self, existing: &[u8]) -> io::Result<bool> {
+        self.encodes_identically_to_with(existing, &EncodeOptions::default())
+    }
+
+    /// Whether `encode_with(options)` gives exactly `existing`
+    pub fn encodes_identically_to_with(
+        self,
+        existing: &[u8],
+        options: &EncodeOptions,
+    ) -> io::Result<bool> {
+        Ok(self.encode_with(options)? == existing)
+    }
+
+    /// Write the cursor to `path` unless the file there already holds the
+    /// same bytes, returning whether it was written
+    ///
+    /// An unchanged file is not touched, so its modification time survives.
+    #[cfg(feature = "fs")]
+    pub fn save_if_changed(self, path: impl AsRef<Path>) -> io::Result<bool> {
+        self.save_if_changed_with(path, &EncodeOptions::default())
+    }
+
+    /// `save_if_changed`, encoding with `options`
+    #[cfg(feature = "fs")]
+    pub fn save_if_changed_with(
+        self,
+        path: impl AsRef<Path>,
+        options: &EncodeOptions,
+    ) -> io::Result<bool> {
+        let path = path.as_ref();
+        let bytes = self.encode_with(options)?;
+        match fs::read(path) {
+            Ok(existing) if existing == bytes => return Ok(false),
+            Ok(_) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        fs::write(path, bytes)?;
+        Ok(true)
+    }
+}
+
+impl CursorFile {
+    /// Whether `encode` would produce exactly `existing`
+    pub fn encodes_identically_to(&self, existing: &[u8]) -> io::Result<bool> {
+        CursorRef::from(self).encodes_identically_to(existing)
+    }
+
+    /// Whether `encode_with(options)` would produce exactly `existing`
+    pub fn encodes_identically_to_with(
+        &self,
+        existing: &[u8],
+        options: &EncodeOptions,
+    ) -> io::Result<bool> {
+        CursorRef::from(self).encodes_identically_to_with(existing, options)
+    }
+
+    /// Encode to `path` only when the output differs from what is there,
+    /// returning whether the file was written
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use cursor_handler::CursorFile;
+    ///
+    /// let mut cursor = CursorFile::decode(fs::File::open("assets/windows_theme/Link.cur")?)?;
+    /// let path = std::env::temp_dir().join("cursor_handler_save_if_changed.cur");
+    /// let _ = fs::remove_file(&path);
+    ///
+    /// assert!(cursor.save_if_changed(&path)?);
+    /// let modified = fs::metadata(&path)?.modified()?;
+    /// assert!(!cursor.save_if_changed(&path)?);
+    /// assert_eq!(fs::metadata(&path)?.modified()?, modified);
+    /// assert!(cursor.encodes_identically_to(&fs::read(&path)?)?);
+    ///
+    /// cursor[0].hotspot.x += 1;
+    /// assert!(!cursor.encodes_identically_to(&fs::read(&path)?)?);
+    /// assert!(cursor.save_if_changed(&path)?);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn save_if_changed(&self, path: impl AsRef<Path>) -> io::Result<bool> {
+        CursorRef::from(self).save_if_changed(path)
+    }
+
+    /// `save_if_changed`, encoding with `options`
+    #[cfg(feature = "fs")]
+    pub fn save_if_changed_with(
+        &self,
+        path: impl AsRef<Path>,
+        options: &EncodeOptions,
+    ) -> io::Result<bool> {
+        CursorRef::from(self).save_if_changed_with(path, options)
+    }
+}
+
+impl AniFile {
+    /// Whether `encode` would produce exactly `existing`
+    pub fn encodes_identically_to(&self, existing: &[u8]) -> io::Result<bool> {
+        CursorRef::from(self).encodes_identically_to(existing)
+    }
+
+    /// Whether `encode_with(options)` would produce exactly `existing`
+    pub fn encodes_identically_to_with(
+        &self,
+        existing: &[u8],
+        options: &EncodeOptions,
+    ) -> io::Result<bool> {
+        CursorRef::from(self).encodes_identically_to_with(existing, options)
+    }
+
+    /// Encode to `path` only when the output differs from what is there,
+    /// returning whether the file was written
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use cursor_handler::{AniFile, EncodeOptions, LoopBehavior};
+    ///
+    /// let mut ani = AniFile::decode(fs::File::open("assets/aero_busy.ani")?)?;
+    /// let path = std::env::temp_dir().join("cursor_handler_save_if_changed.ani");
+    /// let _ = fs::remove_file(&path);
+    ///
+    /// assert!(ani.save_if_changed(&path)?);
+    /// assert!(!ani.save_if_changed(&path)?);
+    ///
+    /// let hotspot = ani.uniform_hotspot().unwrap();
+    /// ani.normalize_hotspots(cursor_handler::Hotspot { x: hotspot.x + 1, ..hotspot })?;
+    /// assert!(ani.save_if_changed(&path)?);
+    ///
+    /// // Only a `LOOP`-writing encode sees the new loop count
+    /// ani.loop_behavior = LoopBehavior::Count(2);
+    /// let with_loop = EncodeOptions { write_loop_chunk: true, ..Default::default() };
+    /// assert!(ani.encodes_identically_to(&fs::read(&path)?)?);
+    /// assert!(!ani.encodes_identically_to_with(&fs::read(&path)?, &with_loop)?);
+    /// assert!(ani.save_if_changed_with(&path, &with_loop)?);
+    /// assert!(!ani.save_if_changed_with(&path, &with_loop)?);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn save_if_changed(&self, path: impl AsRef<Path>) -> io::Result<bool> {
+        CursorRef::from(self).save_if_changed(path)
+    }
+
+    /// `save_if_changed`, encoding with `options`
+    #[cfg(feature = "fs")]
+    pub fn save_if_changed_with(
+        &self,
+        path: impl AsRef<Path>,
+        options: &EncodeOptions,
+    ) -> io::Result<bool> {
+        CursorRef::from(self).save_if_changed_with(path, options)
+    }
+}
+
+/// A cursor written to a uniquely named file in the temp directory, deleted
+/// on drop
+///
+/// For APIs such as LoadCursorFromFileW that only take a path.
+///
+/// ```
+/// use std::{fs, io::Cursor};
+///
+/// use cursor_handler::{AniFile, CursorRef, file::TempCursorFile};
+///
+/// let ani = AniFile::decode(Cursor::new(fs::read("assets/aero_busy.ani")?))?;
+/// let first = TempCursorFile::write(CursorRef::from(&ani))?;
+/// let second = TempCursorFile::write(CursorRef::from(&ani))?;
+/// assert_ne!(first.path(), second.path());
+/// assert_eq!(first.path().extension().unwrap(), "ani");
+/// assert_eq!(fs::read(first.path())?, CursorRef::from(&ani).encode()?);
+///
+/// let path = first.path().to_path_buf();
+/// drop(first);
+/// assert!(!path.exists() && second.path().exists());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "fs")]
+#[derive(Debug)]
+pub struct TempCursorFile(PathBuf);
+
+#[cfg(feature = "fs")]
+impl TempCursorFile {
+    pub fn write(cursor: CursorRef<'_>) -> io::Result<Self> {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+        let name = format!(
+            "cursor_handler-{}-{}.{}",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            cursor.extension()
+        );
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, cursor.encode()?)?;
+        Ok(Self(path))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Drop for TempCursorFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
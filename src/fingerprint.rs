@@ -0,0 +1,156 @@
+// Content hashes that ignore how payloads happen to be encoded
+//
+// The hash is 128-bit FNV-1a over a fixed byte stream: decoded RGBA pixels,
+// sizes and hotspots, and for animations the steps and loop behavior. Raw
+// payload bytes and INFO metadata are left out, so re-encoding a frame or
+// retitling a file keeps the fingerprint. The stream and algorithm only
+// change with a minor version.
+use std::fmt::{self, Display};
+
+use crate::{
+    ani::AniFile,
+    cur::{CursorFile, CursorFrame},
+};
+
+const FNV_OFFSET: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+const FNV_PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+/// A stable content hash, see `CursorFile::fingerprint`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint(pub u128);
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+struct Fnv(u128);
+
+impl Fnv {
+    fn new(tag: &[u8]) -> Self {
+        let mut hasher = Self(FNV_OFFSET);
+        hasher.write(tag);
+        hasher
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u128).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+}
+
+impl CursorFile {
+    /// Hash of what the cursor looks like: each frame's size, hotspot and
+    /// decoded pixels, in size order
+    ///
+    /// A frame that can't be decoded is hashed by its payload bytes instead.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{CursorFile, CursorFrame, FrameSize};
+    /// use image::{
+    ///     ImageEncoder, Rgba, RgbaImage,
+    ///     codecs::png::{CompressionType, FilterType, PngEncoder},
+    /// };
+    ///
+    /// let image = RgbaImage::from_fn(32, 32, |x, y| Rgba([x as u8 * 8, y as u8 * 8, 0, 255]));
+    /// let mut png = Vec::new();
+    /// PngEncoder::new_with_quality(&mut png, CompressionType::Fast, FilterType::NoFilter)
+    ///     .write_image(image.as_raw(), 32, 32, image::ExtendedColorType::Rgba8)
+    ///     .map_err(std::io::Error::other)?;
+    /// let original = CursorFile::single(CursorFrame::new(FrameSize::square(32)?, (3, 4), png));
+    ///
+    /// let mut recompressed = original.clone();
+    /// assert!(recompressed[0].recompress_png()?);
+    /// assert_ne!(recompressed[0].image_data, original[0].image_data);
+    /// assert_eq!(recompressed.fingerprint(), original.fingerprint());
+    ///
+    /// let mut nudged = original.clone();
+    /// nudged[0].hotspot.x += 1;
+    /// assert_ne!(nudged.fingerprint(), original.fingerprint());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut hasher = Fnv::new(b"cur");
+        hash_frames(&mut hasher, self);
+        Fingerprint(hasher.0)
+    }
+}
+
+impl AniFile {
+    /// Hash of how the animation plays: every frame as in
+    /// `CursorFile::fingerprint`, then each step's frame and duration and
+    /// the loop behavior
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, InfoEntry};
+    ///
+    /// let ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// let mut retitled = ani.clone();
+    /// retitled.info.push(InfoEntry::new(*b"INAM", "Busy"));
+    /// assert_eq!(retitled.fingerprint(), ani.fingerprint());
+    ///
+    /// let slower = ani.clone().with_default_rate(4)?;
+    /// assert_ne!(slower.fingerprint(), ani.fingerprint());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut hasher = Fnv::new(b"ani");
+
+        hasher.write_u32(self.frames.len() as u32);
+        for index in 0..self.frames.len() {
+            // In range, so extraction can't fail
+            if let Ok(cursor) = self.extract_frame(index) {
+                hash_frames(&mut hasher, &cursor);
+            }
+        }
+
+        hasher.write_u32(self.num_steps());
+        for step in self.steps() {
+            hasher.write_u32(step.frame_index as u32);
+            hasher.write_u32(step.duration);
+        }
+
+        let (kind, count) = match self.loop_behavior.plays() {
+            None => (0, 0),
+            Some(plays) => (1, plays as u32),
+        };
+        hasher.write_u32(kind);
+        hasher.write_u32(count);
+
+        Fingerprint(hasher.0)
+    }
+}
+
+fn hash_frames(hasher: &mut Fnv, cursor: &CursorFile) {
+    let mut frames: Vec<&CursorFrame> = cursor.frames.iter().collect();
+    frames.sort_by_key(|frame| (frame.size.width(), frame.size.height()));
+
+    hasher.write_u32(frames.len() as u32);
+    for frame in frames {
+        hasher.write_u32(frame.size.width());
+        hasher.write_u32(frame.size.height());
+        hasher.write_u32(frame.hotspot.x as u32);
+        hasher.write_u32(frame.hotspot.y as u32);
+        match frame.decode_image() {
+            Ok(image) => {
+                hasher.write(b"rgba");
+                hasher.write(image.as_raw());
+            }
+            Err(_) => {
+                hasher.write(b"raw ");
+                hasher.write_u32(frame.image_data.len() as u32);
+                hasher.write(&frame.image_data);
+            }
+        }
+    }
+}
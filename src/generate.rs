@@ -0,0 +1,132 @@
+// Procedural animations built from a single source image
+use std::io;
+
+use image::{DynamicImage, RgbaImage};
+
+use crate::{
+    ani::{AniFile, AniFrame},
+    cur::{CursorFile, CursorFrame},
+    hotspot::{Hotspot, HotspotSpec},
+    optimize::encode_png,
+    size::FrameSize,
+    transform,
+};
+
+/// Parameters shared by every generator
+#[derive(Debug, Clone)]
+pub struct GenerateOptions {
+    pub steps: u32,
+    /// Resolved at the source image's size
+    pub hotspot: HotspotSpec,
+    pub rate: u32, // Duration of each step in jiffies
+}
+
+impl GenerateOptions {
+    /// Check the options against `image`, returning its hotspot
+    fn validate(&self, image: &RgbaImage) -> io::Result<Hotspot> {
+        if self.steps < 2 {
+            return Err(invalid_input("an animation needs at least 2 steps"));
+        }
+        if self.rate == 0 {
+            return Err(invalid_input("rate must be at least 1 jiffy"));
+        }
+        let hotspot = self.hotspot.resolve(image.width(), image.height());
+        if !hotspot.is_within(image.width(), image.height()) {
+            return Err(invalid_input(format!(
+                "hotspot {hotspot} is outside the {}x{} image",
+                image.width(),
+                image.height()
+            )));
+        }
+        Ok(hotspot)
+    }
+}
+
+/// Rotate the hue by `degrees` more on every step
+pub fn hue_cycle(
+    image: &DynamicImage,
+    degrees: i32,
+    options: &GenerateOptions,
+) -> io::Result<AniFile> {
+    let frames = (0..options.steps)
+        .map(|step| image.huerotate(step as i32 * degrees).to_rgba8())
+        .collect();
+    animate(frames, options)
+}
+
+/// Rotate clockwise through `degrees` in total over all steps
+pub fn spin(image: &DynamicImage, degrees: f32, options: &GenerateOptions) -> io::Result<AniFile> {
+    let image = image.to_rgba8();
+    let frames = (0..options.steps)
+        .map(|step| transform::rotate(&image, degrees * step as f32 / options.steps as f32))
+        .collect();
+    animate(frames, options)
+}
+
+/// Shrink towards `min_scale` and grow back over one cycle
+pub fn pulse(
+    image: &DynamicImage,
+    min_scale: f32,
+    options: &GenerateOptions,
+) -> io::Result<AniFile> {
+    if !(min_scale > 0.0 && min_scale <= 1.0) {
+        return Err(invalid_input("min scale must be in (0, 1]"));
+    }
+
+    let image = image.to_rgba8();
+    let frames = (0..options.steps)
+        .map(|step| {
+            // Triangle wave: 1 -> min_scale -> 1
+            let phase = step as f32 / options.steps as f32;
+            let depth = 1.0 - (2.0 * phase - 1.0).abs();
+            transform::scale(&image, 1.0 - (1.0 - min_scale) * depth)
+        })
+        .collect();
+    animate(frames, options)
+}
+
+/// Alternate between the image and a blank frame
+///
+/// Only two frames are stored; the sequence table repeats them.
+pub fn blink(image: &DynamicImage, options: &GenerateOptions) -> io::Result<AniFile> {
+    let image = image.to_rgba8();
+    let hotspot = options.validate(&image)?;
+
+    let blank = RgbaImage::new(image.width(), image.height());
+    let frames = vec![
+        cursor_frame(&image, hotspot)?,
+        cursor_frame(&blank, hotspot)?,
+    ];
+    let sequence = (0..options.steps).map(|step| step % 2).collect();
+
+    AniFile::new(frames)
+        .with_sequence(sequence)
+        .with_default_rate(options.rate)
+}
+
+fn animate(frames: Vec<RgbaImage>, options: &GenerateOptions) -> io::Result<AniFile> {
+    let Some(first) = frames.first() else {
+        return AniFile::empty().with_default_rate(options.rate);
+    };
+    let hotspot = options.validate(first)?;
+
+    let mut ani = AniFile::empty().with_default_rate(options.rate)?;
+    for image in &frames {
+        ani.push_frame(cursor_frame(image, hotspot)?);
+    }
+    Ok(ani)
+}
+
+/// Wrap an image as a PNG-payload .cur, the form Windows expects inside an ANI
+pub(crate) fn cursor_frame(image: &RgbaImage, hotspot: Hotspot) -> io::Result<AniFrame> {
+    let size = FrameSize::new(image.width(), image.height())?;
+    let cursor = CursorFile::single(CursorFrame::new(size, hotspot, encode_png(image)?));
+
+    let mut image_data = Vec::new();
+    cursor.encode(&mut image_data)?;
+    Ok(AniFrame::new(size, hotspot, image_data, None))
+}
+
+fn invalid_input(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
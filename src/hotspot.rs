@@ -0,0 +1,216 @@
+// The click point of a cursor frame, in pixels from the top-left corner
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Hotspot {
+    pub x: u16,
+    pub y: u16,
+}
+
+impl Hotspot {
+    pub const fn new(x: u16, y: u16) -> Self {
+        Self { x, y }
+    }
+
+    /// The same point on an image scaled by `factor`
+    ///
+    /// Pixel centres are mapped, so each coordinate becomes
+    /// `floor((c + 0.5) * factor)`: the hotspot lands on the pixel that covers
+    /// the centre of the original one. Results saturate at `u16::MAX`.
+    ///
+    /// ```
+    /// use cursor_handler::Hotspot;
+    ///
+    /// assert_eq!(Hotspot::new(3, 0).scaled(2.0), Hotspot::new(7, 1));
+    /// assert_eq!(Hotspot::new(7, 1).scaled(0.5), Hotspot::new(3, 0));
+    /// ```
+    pub fn scaled(self, factor: f32) -> Self {
+        let scale = |c: u16| ((c as f32 + 0.5) * factor).floor() as u16;
+        Self::new(scale(self.x), scale(self.y))
+    }
+
+    /// The same pixel's top-left corner on an image enlarged by a whole
+    /// `factor`, or `None` if a coordinate no longer fits in `u16`
+    ///
+    /// ```
+    /// use cursor_handler::Hotspot;
+    ///
+    /// assert_eq!(Hotspot::new(3, 0).scaled_by(4), Some(Hotspot::new(12, 0)));
+    /// assert_eq!(Hotspot::new(40_000, 0).scaled_by(2), None);
+    /// ```
+    pub fn scaled_by(self, factor: u32) -> Option<Self> {
+        let scale = |c: u16| u16::try_from(u32::from(c).checked_mul(factor)?).ok();
+        Some(Self::new(scale(self.x)?, scale(self.y)?))
+    }
+
+    /// Clamp into a `width` x `height` image, so it names an existing pixel
+    pub fn clamp_to(self, width: u32, height: u32) -> Self {
+        let clamp = |c: u16, size: u32| c.min(size.saturating_sub(1).min(u16::MAX as u32) as u16);
+        Self::new(clamp(self.x, width), clamp(self.y, height))
+    }
+
+    /// Whether the hotspot names a pixel of a `width` x `height` image
+    pub fn is_within(self, width: u32, height: u32) -> bool {
+        u32::from(self.x) < width && u32::from(self.y) < height
+    }
+
+    /// The centre of the hotspot's pixel as a fraction of a `width` x
+    /// `height` image, the inverse of `from_fraction`
+    ///
+    /// ```
+    /// use cursor_handler::Hotspot;
+    ///
+    /// let fraction = Hotspot::new(3, 0).fraction(32, 32);
+    /// assert_eq!(Hotspot::from_fraction(fraction, 32, 32), Hotspot::new(3, 0));
+    /// assert_eq!(Hotspot::from_fraction(fraction, 64, 64), Hotspot::new(7, 1));
+    /// assert_eq!(Hotspot::from_fraction((1.0, 0.5), 16, 16), Hotspot::new(15, 8));
+    /// ```
+    pub fn fraction(self, width: u32, height: u32) -> (f32, f32) {
+        let fraction = |c: u16, size: u32| (c as f32 + 0.5) / size.max(1) as f32;
+        (fraction(self.x, width), fraction(self.y, height))
+    }
+
+    /// The pixel covering `(fx, fy)` of a `width` x `height` image, rounding
+    /// the same way as `scaled`
+    pub fn from_fraction((fx, fy): (f32, f32), width: u32, height: u32) -> Self {
+        let place = |f: f32, size: u32| (f.clamp(0.0, 1.0) * size as f32).floor() as u16;
+        Self::new(place(fx, width), place(fy, height)).clamp_to(width, height)
+    }
+}
+
+/// Where a built cursor's hotspot goes, resolved for each frame size
+///
+/// `Center` is the pixel at half of each side, rounded down: 16 of a 32px
+/// side, the right of the two middle pixels, and 15 of a 31px side, its
+/// middle one. `Normalized` rounds the same way (see
+/// `Hotspot::from_fraction`), so `Normalized(0.5, 0.5)` agrees with
+/// `Center`.
+///
+/// ```
+/// use cursor_handler::{Hotspot, HotspotSpec};
+///
+/// let at = |spec: HotspotSpec, side| spec.resolve(side, side);
+/// assert_eq!(at(HotspotSpec::Center, 32), Hotspot::new(16, 16));
+/// assert_eq!(at(HotspotSpec::Center, 31), Hotspot::new(15, 15));
+/// assert_eq!(at(HotspotSpec::Center, 1), Hotspot::new(0, 0));
+/// assert_eq!(HotspotSpec::Center.resolve(48, 17), Hotspot::new(24, 8));
+/// for side in [1, 16, 31, 32, 48, 255] {
+///     assert_eq!(at(HotspotSpec::TopLeft, side), Hotspot::new(0, 0));
+///     assert_eq!(at(HotspotSpec::Normalized(0.5, 0.5), side), at(HotspotSpec::Center, side));
+///     assert_eq!(at(HotspotSpec::Normalized(1.0, 0.0), side), Hotspot::new(side as u16 - 1, 0));
+/// }
+/// assert_eq!(at(HotspotSpec::Normalized(0.25, 0.75), 32), Hotspot::new(8, 24));
+/// assert_eq!(at(HotspotSpec::Normalized(0.25, 0.75), 33), Hotspot::new(8, 24));
+/// // Absolute is kept as given, so builders can refuse it when it's outside
+/// assert_eq!(at((40, 2).into(), 32), Hotspot::new(40, 2));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HotspotSpec {
+    /// The top-left pixel
+    #[default]
+    TopLeft,
+    /// The pixel at the centre
+    Center,
+    /// In source pixels
+    Absolute(Hotspot),
+    /// As a fraction of the source width and height, each in 0..=1
+    Normalized(f32, f32),
+}
+
+impl HotspotSpec {
+    /// The hotspot on a `width` x `height` frame
+    pub fn resolve(self, width: u32, height: u32) -> Hotspot {
+        match self {
+            Self::TopLeft => Hotspot::default(),
+            Self::Center => HotspotPolicy::Center.resolve(width, height),
+            Self::Absolute(hotspot) => hotspot,
+            Self::Normalized(fx, fy) => Hotspot::from_fraction((fx, fy), width, height),
+        }
+    }
+
+    /// The hotspot as a fraction of a `width` x `height` source, for placing
+    /// it on resized copies
+    pub fn fraction(self, width: u32, height: u32) -> (f32, f32) {
+        match self {
+            Self::Normalized(fx, fy) => (fx, fy),
+            spec => spec.resolve(width, height).fraction(width, height),
+        }
+    }
+}
+
+impl From<Hotspot> for HotspotSpec {
+    fn from(hotspot: Hotspot) -> Self {
+        Self::Absolute(hotspot)
+    }
+}
+
+impl From<(u16, u16)> for HotspotSpec {
+    fn from(hotspot: (u16, u16)) -> Self {
+        Self::Absolute(hotspot.into())
+    }
+}
+
+/// Where `CursorFile::normalize_hotspots` takes every frame's hotspot from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HotspotReference {
+    /// The point frame `index` marks, moved to each frame's scale
+    Frame(usize),
+    /// A point as a fraction of each frame's width and height, from 0.0 to
+    /// 1.0, see `Hotspot::from_fraction`
+    Fraction(f32, f32),
+}
+
+/// Where to put the hotspot of a frame whose data holds none, such as an
+/// icon container
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HotspotPolicy {
+    /// The top-left pixel
+    #[default]
+    Origin,
+    /// The pixel at the centre of the frame
+    Center,
+    /// The same point at every size, clamped into the frame
+    Fixed(Hotspot),
+}
+
+impl HotspotPolicy {
+    /// The hotspot for a `width` x `height` frame
+    ///
+    /// ```
+    /// use cursor_handler::{Hotspot, HotspotPolicy};
+    ///
+    /// assert_eq!(HotspotPolicy::Origin.resolve(32, 32), Hotspot::new(0, 0));
+    /// assert_eq!(HotspotPolicy::Center.resolve(32, 48), Hotspot::new(16, 24));
+    /// let fixed = HotspotPolicy::Fixed(Hotspot::new(20, 4));
+    /// assert_eq!(fixed.resolve(16, 16), Hotspot::new(15, 4));
+    /// ```
+    pub fn resolve(self, width: u32, height: u32) -> Hotspot {
+        match self {
+            Self::Origin => Hotspot::default(),
+            Self::Center => {
+                let half = |side: u32| (side / 2).min(u16::MAX as u32) as u16;
+                Hotspot::new(half(width), half(height))
+            }
+            Self::Fixed(hotspot) => hotspot.clamp_to(width, height),
+        }
+    }
+}
+
+impl Display for Hotspot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl From<(u16, u16)> for Hotspot {
+    fn from((x, y): (u16, u16)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<Hotspot> for (u16, u16) {
+    fn from(hotspot: Hotspot) -> Self {
+        (hotspot.x, hotspot.y)
+    }
+}
@@ -0,0 +1,89 @@
+pub mod analysis;
+pub mod ani;
+#[cfg(feature = "json")]
+pub mod aseprite;
+#[cfg(feature = "fs")]
+pub mod build;
+#[cfg(feature = "fs")]
+pub mod cache;
+pub mod compat;
+pub mod concat;
+#[cfg(feature = "fs")]
+pub mod convert;
+pub mod cur;
+#[cfg(feature = "cursorfx")]
+pub mod cursorfx;
+mod debug;
+pub mod decode;
+pub mod dib;
+pub mod diff;
+pub mod embed;
+pub mod encode;
+pub mod estimate;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod file;
+pub mod fingerprint;
+pub mod generate;
+pub mod hotspot;
+pub mod manifest;
+pub mod merge;
+pub mod metadata;
+pub mod optimize;
+pub mod progress;
+#[cfg(feature = "fs")]
+pub mod project;
+#[cfg(feature = "svg")]
+pub mod rasterize;
+pub mod raw;
+pub mod riff;
+pub mod sanitize;
+#[cfg(feature = "fs")]
+pub mod scheme;
+pub mod sheet;
+pub mod size;
+pub mod split;
+pub mod svg;
+pub mod system_cursor;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+mod trace;
+pub mod transform;
+pub mod upgrade;
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "windows-apply")]
+pub mod windows;
+#[cfg(feature = "x11")]
+pub mod x11;
+pub mod xcursor;
+
+pub use ani::{
+    AlignReport, AniFile, AniFlags, AniFrame, AniHeader, AniStep, IdVariant, InfoEntry,
+    LoopBehavior, MixedSizePolicy, SizedStep, UnknownChunk,
+};
+pub use concat::{ConcatOptions, Concatenated};
+pub use cur::{CursorFile, CursorFrame};
+pub use decode::{
+    DecodeError, DecodeLimits, DecodeMode, DecodeOptions, DecodeStats, DecodeWarning, Decoded,
+    FrameErrorPolicy,
+};
+pub use dib::{BmpInfo, ChannelMasks, DibPlane, MaskPlane, RowOrder};
+pub use diff::{AniDiff, CursorDiff};
+pub use embed::{CursorBytes, CursorView, FrameView};
+pub use encode::{EncodeOptions, FileLayout, Section};
+pub use export::StripOrder;
+pub use file::CursorRef;
+pub use fingerprint::Fingerprint;
+pub use generate::GenerateOptions;
+pub use hotspot::{Hotspot, HotspotPolicy, HotspotReference, HotspotSpec};
+pub use manifest::{Manifest, PayloadKind};
+pub use merge::{FrameOrder, MergeOptions, Merged};
+pub use optimize::{OptimizeOptions, OptimizeReport};
+pub use sanitize::{SanitizeOptions, SanitizeReport};
+pub use size::FrameSize;
+pub use split::SplitManifest;
+pub use upgrade::UpgradeOptions;
+pub use validate::{Issue, Severity};
@@ -0,0 +1,145 @@
+// Plain-data summary of a cursor file, for tools and serialization
+use std::io::Cursor;
+
+use crate::{
+    ani::AniFile,
+    cur::{CursorFile, CursorFrame, PNG_SIGNATURE},
+};
+
+/// How a frame's image data is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum PayloadKind {
+    Png,
+    Dib,
+    /// An embedded .cur/.ico container (ANI frames)
+    Cursor,
+}
+
+impl PayloadKind {
+    pub fn sniff(data: &[u8]) -> Self {
+        if data.starts_with(&PNG_SIGNATURE) {
+            Self::Png
+        } else if data.starts_with(&[0, 0, 2, 0]) || data.starts_with(&[0, 0, 1, 0]) {
+            Self::Cursor
+        } else {
+            Self::Dib
+        }
+    }
+}
+
+/// One stored frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FrameManifest {
+    pub width: u32,
+    pub height: u32,
+    pub hotspot_x: u16,
+    pub hotspot_y: u16,
+    pub payload: PayloadKind,
+    pub bytes: usize,
+    /// Resolutions inside an embedded cursor container
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub sizes: Vec<(u32, u32)>,
+}
+
+impl FrameManifest {
+    fn from_cursor_frame(frame: &CursorFrame) -> Self {
+        Self {
+            width: frame.size.width(),
+            height: frame.size.height(),
+            hotspot_x: frame.hotspot.x,
+            hotspot_y: frame.hotspot.y,
+            payload: PayloadKind::sniff(&frame.image_data),
+            bytes: frame.image_data.len(),
+            sizes: Vec::new(),
+        }
+    }
+}
+
+/// One playback step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StepManifest {
+    pub frame: usize,
+    pub duration: u32, // Duration in jiffies
+}
+
+/// Everything about a file except the pixel data
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Manifest {
+    /// `"cur"` or `"ani"`
+    pub kind: &'static str,
+    pub frames: Vec<FrameManifest>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub steps: Vec<StepManifest>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub default_rate: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub title: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub artist: Option<String>,
+}
+
+impl From<&CursorFile> for Manifest {
+    fn from(cur: &CursorFile) -> Self {
+        Self {
+            kind: "cur",
+            frames: cur
+                .frames
+                .iter()
+                .map(FrameManifest::from_cursor_frame)
+                .collect(),
+            steps: Vec::new(),
+            default_rate: None,
+            title: None,
+            artist: None,
+        }
+    }
+}
+
+impl From<&AniFile> for Manifest {
+    fn from(ani: &AniFile) -> Self {
+        let frames = ani
+            .frames
+            .iter()
+            .map(|frame| {
+                let sizes = CursorFile::decode(Cursor::new(&frame.image_data))
+                    .map(|cur| {
+                        cur.frames
+                            .iter()
+                            .map(|f| (f.size.width(), f.size.height()))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                FrameManifest {
+                    width: frame.size.width(),
+                    height: frame.size.height(),
+                    hotspot_x: frame.hotspot.x,
+                    hotspot_y: frame.hotspot.y,
+                    payload: PayloadKind::sniff(&frame.image_data),
+                    bytes: frame.image_data.len(),
+                    sizes,
+                }
+            })
+            .collect();
+
+        Self {
+            kind: "ani",
+            frames,
+            steps: ani
+                .steps()
+                .map(|step| StepManifest {
+                    frame: step.frame_index,
+                    duration: step.duration,
+                })
+                .collect(),
+            default_rate: Some(ani.header.default_rate()),
+            title: ani.title(),
+            artist: ani.artist(),
+        }
+    }
+}
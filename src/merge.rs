@@ -0,0 +1,133 @@
+// Combining single-size cursors into one multi-resolution cursor
+use std::io;
+
+use crate::cur::{CursorFile, CursorFrame, payload_bit_count};
+
+/// Order of the frames in a merged cursor's directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameOrder {
+    /// Smallest first
+    #[default]
+    Ascending,
+    /// Largest first
+    Descending,
+    /// Files in the order given, each file's frames in its own order
+    AsGiven,
+}
+
+/// Options for `CursorFile::merge_with`
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    pub order: FrameOrder,
+    /// When two frames share a size, keep the one with more bits per pixel
+    /// instead of failing
+    pub keep_higher_bit_depth: bool,
+}
+
+/// A merged cursor with the hotspot mismatches found between its sizes
+#[derive(Debug, Clone)]
+pub struct Merged {
+    pub value: CursorFile,
+    pub warnings: Vec<String>,
+}
+
+impl CursorFile {
+    /// Combine the frames of `files` into one cursor, see `merge_with`
+    pub fn merge(files: &[&CursorFile]) -> io::Result<CursorFile> {
+        Self::merge_with(files, &MergeOptions::default()).map(|merged| merged.value)
+    }
+
+    /// Combine the frames of `files` into one cursor
+    ///
+    /// Two frames of the same size are an error unless
+    /// `options.keep_higher_bit_depth` is set (the first one wins a tie).
+    /// Each hotspot is scaled down to the smallest frame and compared with
+    /// that frame's; being more than a pixel apart there is a warning.
+    ///
+    /// ```
+    /// use cursor_handler::{CursorFile, CursorFrame, Hotspot};
+    /// use image::{Rgba, RgbaImage};
+    ///
+    /// let render = |side: u32| -> std::io::Result<CursorFile> {
+    ///     let image = RgbaImage::from_pixel(side, side, Rgba([0, 0, 0, 255]));
+    ///     let hotspot = Hotspot::new(side as u16 / 2, side as u16 / 2);
+    ///     Ok(CursorFile::single(CursorFrame::from_image(&image, hotspot)?))
+    /// };
+    /// let (large, small, medium) = (render(64)?, render(32)?, render(48)?);
+    ///
+    /// let merged = CursorFile::merge(&[&large, &small, &medium])?;
+    /// let sides: Vec<_> = merged.frames().map(|frame| frame.size.width()).collect();
+    /// assert_eq!(sides, [32, 48, 64]);
+    ///
+    /// let best = |size| merged.best_frame(size).unwrap().size.width();
+    /// assert_eq!((best(16), best(40), best(56), best(256)), (32, 48, 64, 64));
+    ///
+    /// assert!(CursorFile::merge(&[&small, &small]).is_err());
+    ///
+    /// // Centred hotspots line up at every size
+    /// let merged = CursorFile::merge_with(&[&large, &small], &Default::default())?;
+    /// assert!(merged.warnings.is_empty());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn merge_with(files: &[&CursorFile], options: &MergeOptions) -> io::Result<Merged> {
+        let mut frames: Vec<CursorFrame> = Vec::new();
+        for frame in files.iter().flat_map(|file| &file.frames) {
+            let Some(kept) = frames.iter_mut().find(|kept| kept.size == frame.size) else {
+                frames.push(frame.clone());
+                continue;
+            };
+            if !options.keep_higher_bit_depth {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("two frames are {}", frame.size),
+                ));
+            }
+            if payload_bit_count(&frame.image_data) > payload_bit_count(&kept.image_data) {
+                *kept = frame.clone();
+            }
+        }
+
+        if frames.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No frames"));
+        }
+        if frames.len() > u16::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} frames do not fit in a cursor directory", frames.len()),
+            ));
+        }
+
+        match options.order {
+            FrameOrder::Ascending => frames.sort_by_key(|frame| frame.size.max_side()),
+            FrameOrder::Descending => {
+                frames.sort_by_key(|frame| std::cmp::Reverse(frame.size.max_side()))
+            }
+            FrameOrder::AsGiven => {}
+        }
+
+        let mut warnings = Vec::new();
+        let smallest = frames.iter().min_by_key(|frame| frame.size.max_side());
+        if let Some(smallest) = smallest {
+            for frame in &frames {
+                // Map the pixel centre back onto the smallest frame
+                let back =
+                    |c: u16, from: u32, to: u32| (c as f32 + 0.5) * to as f32 / from as f32 - 0.5;
+                let x = back(frame.hotspot.x, frame.size.width(), smallest.size.width());
+                let y = back(frame.hotspot.y, frame.size.height(), smallest.size.height());
+                if (x - smallest.hotspot.x as f32).abs() > 1.0
+                    || (y - smallest.hotspot.y as f32).abs() > 1.0
+                {
+                    warnings.push(format!(
+                        "{} hotspot {} is ({x:.1}, {y:.1}) at {}, not {}",
+                        frame.size, frame.hotspot, smallest.size, smallest.hotspot
+                    ));
+                }
+            }
+        }
+
+        Ok(Merged {
+            value: CursorFile::new(frames),
+            warnings,
+        })
+    }
+}
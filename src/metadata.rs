@@ -0,0 +1,168 @@
+// Reading and editing the INFO tags of an .ani file without re-encoding it
+//
+// Decoding and encoding again normalizes the whole file: chunk order, stray
+// top-level chunks and padding all change. `rewrite_info` instead splices a
+// new `LIST INFO` over the old one, so the only other bytes that change are
+// those of the RIFF size field.
+use std::{
+    io::{self, Cursor},
+    ops::Range,
+};
+
+use crate::{
+    ani::InfoEntry,
+    riff::{ChunkReader, ChunkWriter, FourCC},
+};
+
+/// INFO tags with a common name, in the order `meta show` lists them
+pub const NAMED_TAGS: [(FourCC, &str); 5] = [
+    (*b"INAM", "title"),
+    (*b"IART", "artist"),
+    (*b"ICOP", "copyright"),
+    (*b"ICMT", "comment"),
+    (*b"ISFT", "software"),
+];
+
+/// The common name of an INFO tag, if it has one
+pub fn tag_name(id: &FourCC) -> Option<&'static str> {
+    NAMED_TAGS
+        .iter()
+        .find(|(tag, _)| tag == id)
+        .map(|(_, name)| *name)
+}
+
+/// Where the INFO lists of an .ani sit
+struct InfoLayout {
+    /// Each `LIST INFO` chunk, pad byte included
+    lists: Vec<Range<usize>>,
+    /// Where a new list goes when there is none: before the frame list, as
+    /// `AniFile::encode` writes it
+    insert_at: usize,
+    entries: Vec<InfoEntry>,
+}
+
+fn scan(bytes: &[u8]) -> io::Result<InfoLayout> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"ACON" {
+        return Err(invalid_data("not an .ani file"));
+    }
+    let declared = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+    let riff_end = (declared.saturating_add(8)).min(bytes.len());
+
+    let mut reader = Cursor::new(&bytes[..riff_end]);
+    reader.set_position(12);
+    let mut chunks = ChunkReader::new(reader)?;
+    let mut layout = InfoLayout {
+        lists: Vec::new(),
+        insert_at: riff_end,
+        entries: Vec::new(),
+    };
+    let mut before_frames = None;
+    while let Some(chunk) = chunks.next_chunk()? {
+        if &chunk.id() != b"LIST" {
+            continue;
+        }
+        let offset = chunk.offset() as usize;
+        let size = chunk.size() as usize;
+        if chunk.is_truncated() {
+            return Err(invalid_data(format!("the LIST at {offset} is truncated")));
+        }
+        let end = (offset + 8 + size + size % 2).min(riff_end);
+        let (list_type, mut items) = chunk.list()?;
+        match &list_type {
+            b"INFO" => {
+                while let Some(item) = items.next_chunk()? {
+                    let id = item.id();
+                    layout.entries.push(InfoEntry::new(id, item.read_data()?));
+                }
+                layout.lists.push(offset..end);
+            }
+            b"fram" => {
+                before_frames.get_or_insert(offset);
+            }
+            _ => {}
+        }
+    }
+    if let Some(offset) = before_frames {
+        layout.insert_at = offset;
+    }
+    Ok(layout)
+}
+
+/// The INFO tags of an .ani, in file order, read without decoding its frames
+///
+/// ```
+/// use cursor_handler::metadata::read_info;
+///
+/// let bytes = std::fs::read("assets/aero_busy.ani")?;
+/// assert!(read_info(&bytes)?.is_empty());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn read_info(bytes: &[u8]) -> io::Result<Vec<InfoEntry>> {
+    Ok(scan(bytes)?.entries)
+}
+
+/// The .ani in `bytes` with its INFO tags replaced by `info`
+///
+/// The first `LIST INFO` is overwritten and any later ones removed; without
+/// one, the list goes in front of the frames. An empty `info` removes the
+/// tags altogether. Apart from the RIFF size field, every byte outside the
+/// lists is kept, including data past the end of the RIFF chunk.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::metadata::{read_info, rewrite_info};
+/// use cursor_handler::{AniFile, InfoEntry};
+///
+/// let bytes = std::fs::read("assets/aero_busy.ani")?;
+/// let tagged = rewrite_info(&bytes, &[InfoEntry::from_text(*b"INAM", "Busy")])?;
+/// assert_eq!(tagged.len(), bytes.len() + 26);
+/// assert_eq!(AniFile::decode(Cursor::new(&tagged))?.title().as_deref(), Some("Busy"));
+///
+/// // Taking the tags out gives back the original file
+/// assert_eq!(rewrite_info(&tagged, &[])?, bytes);
+/// assert!(read_info(&bytes)?.is_empty());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn rewrite_info(bytes: &[u8], info: &[InfoEntry]) -> io::Result<Vec<u8>> {
+    let layout = scan(bytes)?;
+
+    let mut list = Vec::new();
+    if !info.is_empty() {
+        let mut riff = ChunkWriter::new(Cursor::new(&mut list));
+        riff.begin_list(*b"LIST", *b"INFO")?;
+        for entry in info {
+            riff.write_chunk(entry.id, &entry.value)?;
+        }
+        riff.finish()?;
+    }
+
+    let mut out = Vec::with_capacity(bytes.len() + list.len());
+    let (mut at, mut inserted) = (0, false);
+    let mut removed = 0;
+    for range in &layout.lists {
+        out.extend_from_slice(&bytes[at..range.start]);
+        if !inserted {
+            out.extend_from_slice(&list);
+            inserted = true;
+        }
+        removed += range.len();
+        at = range.end;
+    }
+    if !inserted {
+        out.extend_from_slice(&bytes[at..layout.insert_at]);
+        out.extend_from_slice(&list);
+        at = layout.insert_at;
+    }
+    out.extend_from_slice(&bytes[at..]);
+
+    let declared = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as i64;
+    let size = u32::try_from(declared + list.len() as i64 - removed as i64)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "the file is too large"))?;
+    out[4..8].copy_from_slice(&size.to_le_bytes());
+    Ok(out)
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
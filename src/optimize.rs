@@ -0,0 +1,342 @@
+// Lossless size optimizations for .cur and .ani files
+use std::io::{self, Cursor};
+
+use image::{
+    ImageEncoder, RgbaImage,
+    codecs::png::{CompressionType, FilterType, PngEncoder},
+};
+
+use crate::{
+    ani::{AniFile, AniFrame},
+    cur::{CursorFile, CursorFrame},
+    hotspot::Hotspot,
+    progress::{NoProgress, Phase, Progress, Tracker},
+};
+
+/// Which optimizations to apply
+#[derive(Debug, Clone)]
+pub struct OptimizeOptions {
+    /// Share one copy of identical frames through the sequence table
+    pub dedup_frames: bool,
+    /// Merge consecutive steps showing the same frame into one longer step
+    pub coalesce_steps: bool,
+    /// Re-compress PNG payloads at maximum compression
+    pub recompress_png: bool,
+    /// Transcode DIB frames at least this many pixels wide or tall to PNG
+    pub png_threshold: Option<u32>,
+    /// Drop the INFO metadata and unknown chunks
+    pub strip_metadata: bool,
+}
+
+impl Default for OptimizeOptions {
+    fn default() -> Self {
+        Self {
+            dedup_frames: true,
+            coalesce_steps: true,
+            recompress_png: false,
+            png_threshold: None,
+            strip_metadata: false,
+        }
+    }
+}
+
+/// What an optimization pass changed
+#[derive(Debug, Clone, Default)]
+pub struct OptimizeReport {
+    pub frames_removed: usize,
+    pub steps_merged: usize,
+    pub payloads_recompressed: usize,
+    pub frames_transcoded: usize,
+    pub metadata_stripped: bool,
+}
+
+impl CursorFrame {
+    /// Re-encode a PNG payload at maximum compression, keeping it only if smaller
+    pub fn recompress_png(&mut self) -> io::Result<bool> {
+        if !self.is_png() {
+            return Ok(false);
+        }
+
+        let png = encode_png(&self.decode_image()?)?;
+        if png.len() < self.image_data.len() {
+            self.set_payload(png);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Replace a DIB payload with PNG, keeping it only if smaller
+    ///
+    /// Pixels the AND mask would invert on screen become transparent.
+    pub fn transcode_to_png(&mut self) -> io::Result<bool> {
+        if self.is_png() {
+            return Ok(false);
+        }
+
+        let png = encode_png(&self.decode_image()?)?;
+        if png.len() < self.image_data.len() {
+            self.set_payload(png);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+impl CursorFile {
+    /// Apply the payload optimizations to every frame
+    pub fn optimize(&mut self, options: &OptimizeOptions) -> io::Result<OptimizeReport> {
+        let mut report = OptimizeReport::default();
+
+        for frame in &mut self.frames {
+            if options.recompress_png && frame.recompress_png()? {
+                report.payloads_recompressed += 1;
+            }
+
+            if let Some(threshold) = options.png_threshold
+                && frame.size.max_side() >= threshold
+                && frame.transcode_to_png()?
+            {
+                report.frames_transcoded += 1;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Whether both files hold the same images at the same hotspots
+    pub fn renders_same_as(&self, other: &CursorFile) -> io::Result<bool> {
+        if self.frames.len() != other.frames.len() {
+            return Ok(false);
+        }
+
+        for (a, b) in self.frames.iter().zip(&other.frames) {
+            if a.hotspot != b.hotspot || a.decode_image()? != b.decode_image()? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl AniFile {
+    /// Apply the selected optimizations
+    pub fn optimize(&mut self, options: &OptimizeOptions) -> io::Result<OptimizeReport> {
+        self.optimize_with_progress(options, &NoProgress)
+    }
+
+    /// `optimize`, reporting each frame whose payloads it re-encodes
+    ///
+    /// Only `recompress_png` and `png_threshold` work frame by frame, so
+    /// without them nothing is reported. `bytes_written` counts the frames'
+    /// new sizes. Re-encoded frames are only stored once every frame is
+    /// done, so a cancelled or failed pass leaves the animation unchanged.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{
+    ///     AniFile, OptimizeOptions,
+    ///     progress::{CancelToken, Cancellable, Cancelled, ProgressEvent},
+    /// };
+    ///
+    /// let encode = |ani: &AniFile| -> std::io::Result<Vec<u8>> {
+    ///     let mut bytes = Cursor::new(Vec::new());
+    ///     ani.encode(&mut bytes)?;
+    ///     Ok(bytes.into_inner())
+    /// };
+    /// let mut ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// let before = encode(&ani)?;
+    /// let options = OptimizeOptions { png_threshold: Some(32), ..Default::default() };
+    ///
+    /// // Cancel from the callback once three frames are done
+    /// let token = CancelToken::new();
+    /// let progress = Cancellable {
+    ///     progress: |event: ProgressEvent| {
+    ///         if event.completed == 3 {
+    ///             token.cancel();
+    ///         }
+    ///     },
+    ///     token: token.clone(),
+    /// };
+    /// let err = ani.optimize_with_progress(&options, &progress).unwrap_err();
+    /// assert!(Cancelled::is(&err));
+    /// assert_eq!(encode(&ani)?, before);
+    ///
+    /// // Uncancelled, the same pass goes through
+    /// assert!(ani.optimize_with_progress(&options, &CancelToken::new())?.frames_transcoded > 0);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn optimize_with_progress(
+        &mut self,
+        options: &OptimizeOptions,
+        progress: &dyn Progress,
+    ) -> io::Result<OptimizeReport> {
+        let mut report = OptimizeReport::default();
+
+        if options.recompress_png || options.png_threshold.is_some() {
+            let tracker = Tracker::new(progress, Phase::Optimizing, self.frames.len());
+            let mut encoded = Vec::new();
+            for (index, frame) in self.frames.iter().enumerate() {
+                // Frames that aren't a well-formed .cur (e.g. ICO data) are left alone
+                let Ok(mut cursor) = CursorFile::decode(Cursor::new(&frame.image_data)) else {
+                    tracker.advance(frame.image_data.len() as u64)?;
+                    continue;
+                };
+
+                let cursor_report = cursor.optimize(options)?;
+                let mut len = frame.image_data.len();
+                if cursor_report.payloads_recompressed + cursor_report.frames_transcoded > 0 {
+                    let mut image_data = Vec::new();
+                    cursor.encode(&mut image_data)?;
+                    len = image_data.len();
+                    encoded.push((index, image_data));
+                }
+
+                report.payloads_recompressed += cursor_report.payloads_recompressed;
+                report.frames_transcoded += cursor_report.frames_transcoded;
+                tracker.advance(len as u64)?;
+            }
+            for (index, image_data) in encoded {
+                self.frames[index].image_data = image_data;
+            }
+        }
+
+        // Dedup after re-encoding so identical frames compress identically
+        if options.dedup_frames {
+            report.frames_removed = self.dedup_frames();
+        }
+
+        if options.coalesce_steps {
+            report.steps_merged = self.coalesce_steps();
+        }
+
+        if options.strip_metadata && !(self.info.is_empty() && self.unknown_chunks.is_empty()) {
+            self.info.clear();
+            self.unknown_chunks.clear();
+            report.metadata_stripped = true;
+        }
+
+        Ok(report)
+    }
+
+    /// Store identical frames once and point the sequence at the shared copy
+    ///
+    /// Returns the number of frames removed.
+    pub fn dedup_frames(&mut self) -> usize {
+        let mut frames: Vec<AniFrame> = Vec::with_capacity(self.frames.len());
+        let mut remap = Vec::with_capacity(self.frames.len());
+
+        for frame in self.frames.drain(..) {
+            match frames.iter().position(|kept| same_frame(kept, &frame)) {
+                Some(index) => remap.push(index as u32),
+                None => {
+                    remap.push(frames.len() as u32);
+                    frames.push(frame);
+                }
+            }
+        }
+
+        let removed = remap.len() - frames.len();
+        for index in &mut self.sequence {
+            if let Some(&new_index) = remap.get(*index as usize) {
+                *index = new_index;
+            }
+        }
+
+        // Unknown chunks move to the first kept frame after their old position
+        for chunk in &mut self.unknown_chunks {
+            let kept_before = remap[..chunk.before_frame.min(remap.len())].iter().max();
+            chunk.before_frame = kept_before.map_or(0, |&index| index as usize + 1);
+        }
+
+        self.frames = frames;
+        self.header.num_frames = None; // The decoded count no longer applies
+        removed
+    }
+
+    /// Merge consecutive steps that show the same frame, summing their durations
+    ///
+    /// Returns the number of steps removed.
+    pub fn coalesce_steps(&mut self) -> usize {
+        let mut sequence: Vec<u32> = Vec::with_capacity(self.sequence.len());
+        let mut rates: Vec<u32> = Vec::with_capacity(self.sequence.len());
+
+        for step in self.steps() {
+            if sequence.last() == Some(&(step.frame_index as u32)) {
+                *rates.last_mut().unwrap() += step.duration;
+            } else {
+                sequence.push(step.frame_index as u32);
+                rates.push(step.duration);
+            }
+        }
+
+        let merged = self.sequence.len() - sequence.len();
+        if merged > 0 {
+            self.header.num_steps = None;
+            self.sequence = sequence;
+            self.rates = rates;
+        }
+        merged
+    }
+
+    /// Whether both animations play back the same images, hotspots and timing
+    ///
+    /// Consecutive steps showing the same image are merged before comparing,
+    /// so a coalesced animation still matches its source.
+    pub fn plays_same_as(&self, other: &AniFile) -> io::Result<bool> {
+        let (ours, theirs) = (render_frames(self)?, render_frames(other)?);
+        let (ours, theirs) = (timeline(self, &ours), timeline(other, &theirs));
+        Ok(ours == theirs)
+    }
+}
+
+pub(crate) fn same_frame(a: &AniFrame, b: &AniFrame) -> bool {
+    a.size == b.size
+        && a.hotspot == b.hotspot
+        && a.duration == b.duration
+        && a.image_data == b.image_data
+}
+
+type RenderedFrame = Vec<(RgbaImage, Hotspot)>;
+
+/// Decode every resolution of every frame
+fn render_frames(ani: &AniFile) -> io::Result<Vec<RenderedFrame>> {
+    ani.frames
+        .iter()
+        .map(
+            |frame| match CursorFile::decode(Cursor::new(&frame.image_data)) {
+                Ok(cursor) => cursor
+                    .frames
+                    .iter()
+                    .map(|entry| Ok((entry.decode_image()?, entry.hotspot)))
+                    .collect(),
+                Err(_) => Ok(vec![(frame.decode_image()?, frame.hotspot)]),
+            },
+        )
+        .collect()
+}
+
+fn timeline<'a>(ani: &AniFile, rendered: &'a [RenderedFrame]) -> Vec<(&'a RenderedFrame, u32)> {
+    let mut timeline: Vec<(&RenderedFrame, u32)> = Vec::new();
+    for step in ani.steps() {
+        let frame = &rendered[step.frame_index];
+        match timeline.last_mut() {
+            Some((last, duration)) if *last == frame => *duration += step.duration,
+            _ => timeline.push((frame, step.duration)),
+        }
+    }
+    timeline
+}
+
+pub(crate) fn encode_png(image: &RgbaImage) -> io::Result<Vec<u8>> {
+    let mut png = Vec::new();
+    PngEncoder::new_with_quality(&mut png, CompressionType::Best, FilterType::Adaptive)
+        .write_image(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            image::ExtendedColorType::Rgba8,
+        )
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    Ok(png)
+}
@@ -0,0 +1,195 @@
+// Progress reporting and cancellation for conversions that take long
+// enough to need a bar
+use std::{
+    cell::Cell,
+    fmt::{self, Display},
+    io::{self, Write},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// The stage a long-running operation is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Writing animation frames to an output format
+    Encoding,
+    /// Re-encoding frame payloads
+    Optimizing,
+    /// Converting the cursors of a theme
+    Converting,
+}
+
+/// One report from a long-running operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressEvent {
+    pub phase: Phase,
+    /// Items finished in this phase, counting up to `total`
+    pub completed: usize,
+    pub total: usize,
+    /// Output bytes produced so far in this phase
+    pub bytes_written: u64,
+}
+
+/// Receives a `ProgressEvent` each time an item is finished
+///
+/// Calls come from the operation's own loop, so keep them cheap; push the
+/// event to a channel or an atomic rather than redrawing a UI inline. Any
+/// `Fn(ProgressEvent) + Sync` closure is a `Progress`.
+///
+/// After each item the operation asks `is_cancelled` and, on `true`, stops
+/// with a `Cancelled` error. See `Cancellable` to pair a callback with a
+/// `CancelToken`.
+pub trait Progress: Sync {
+    fn report(&self, event: ProgressEvent);
+
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+impl<F: Fn(ProgressEvent) + Sync> Progress for F {
+    fn report(&self, event: ProgressEvent) {
+        self(event)
+    }
+}
+
+/// A `Progress` that ignores every event
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn report(&self, _event: ProgressEvent) {}
+}
+
+/// A flag another thread sets to stop an operation between items
+///
+/// Clones share the flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Reports nothing, but stops the operation once cancelled
+impl Progress for CancelToken {
+    fn report(&self, _event: ProgressEvent) {}
+
+    fn is_cancelled(&self) -> bool {
+        CancelToken::is_cancelled(self)
+    }
+}
+
+/// A progress callback that also stops once `token` is cancelled
+#[derive(Debug, Clone)]
+pub struct Cancellable<P> {
+    pub progress: P,
+    pub token: CancelToken,
+}
+
+impl<P: Progress> Progress for Cancellable<P> {
+    fn report(&self, event: ProgressEvent) {
+        self.progress.report(event)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled() || self.progress.is_cancelled()
+    }
+}
+
+/// The operation was cancelled, carried as the payload of the returned
+/// `io::Error` (kind `Interrupted`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Cancelled {
+    /// Whether `err` reports a cancellation
+    pub fn is(err: &io::Error) -> bool {
+        err.get_ref()
+            .is_some_and(|inner| inner.downcast_ref::<Self>().is_some())
+    }
+}
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+impl From<Cancelled> for io::Error {
+    fn from(err: Cancelled) -> Self {
+        io::Error::new(io::ErrorKind::Interrupted, err)
+    }
+}
+
+/// Counts finished items for one phase
+///
+/// Counting and reporting happen under one lock, so events arrive in order
+/// even when items finish on several threads.
+pub(crate) struct Tracker<'a> {
+    progress: &'a dyn Progress,
+    phase: Phase,
+    total: usize,
+    done: Mutex<(usize, u64)>,
+}
+
+impl<'a> Tracker<'a> {
+    pub(crate) fn new(progress: &'a dyn Progress, phase: Phase, total: usize) -> Self {
+        Self {
+            progress,
+            phase,
+            total,
+            done: Mutex::new((0, 0)),
+        }
+    }
+
+    /// Mark one more item finished, having written `bytes` more output,
+    /// failing with `Cancelled` if the operation should stop here
+    pub(crate) fn advance(&self, bytes: u64) -> io::Result<()> {
+        let mut done = self.done.lock().unwrap_or_else(|err| err.into_inner());
+        done.0 += 1;
+        done.1 += bytes;
+        self.progress.report(ProgressEvent {
+            phase: self.phase,
+            completed: done.0,
+            total: self.total,
+            bytes_written: done.1,
+        });
+        if self.progress.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    }
+}
+
+/// A writer that adds the bytes passing through to `count`
+pub(crate) struct CountingWriter<'a, W> {
+    pub(crate) inner: W,
+    pub(crate) count: &'a Cell<u64>,
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count.set(self.count.get() + written as u64);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
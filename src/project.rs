@@ -0,0 +1,609 @@
+// Declarative project files for building many cursors at once
+//
+// A project is a TOML file with one `[[cursor]]` table per cursor:
+//
+//   [[cursor]]
+//   name = "wait"
+//   source = "wait.gif"             # .png, .gif or a directory of .png frames
+//   sizes = [32, 48]                # Default: the source's longer side
+//   hotspot = [16, 16]              # In source pixels, "center", "top-left", or
+//   hotspot_normalized = [0.5, 0.5] # as a fraction of the source size
+//   rate = 6                        # Jiffies per frame; default: GIF delays
+//   formats = ["ani", "xcursor"]    # Any of cur, ani and xcursor
+//   output = "busy/wait"            # Default: the name
+//
+// Sources resolve against the project file's directory and outputs against
+// the directory passed to `build`; `.cur` and `.ani` are appended to the
+// output path, Xcursor files keep it as is.
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufReader, Cursor},
+    path::{Path, PathBuf},
+};
+
+use image::{AnimationDecoder, RgbaImage, codecs::gif::GifDecoder};
+use serde::{Deserialize, de::DeserializeOwned};
+
+use crate::{
+    ani::{AniFile, AniFrame, jiffies_to_ms, ms_to_jiffies},
+    cur::{CursorFile, CursorFrame},
+    hotspot::{Hotspot, HotspotSpec},
+    transform::fit_square,
+    xcursor::{self, XcursorImage},
+};
+
+/// Jiffies per frame when neither the project nor the source gives a rate
+const DEFAULT_RATE: u32 = 6;
+
+/// File formats a cursor can be built as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TargetFormat {
+    Cur,
+    Ani,
+    Xcursor,
+}
+
+impl TargetFormat {
+    /// Every format, in the order project files list them
+    pub const ALL: [TargetFormat; 3] = [Self::Cur, Self::Ani, Self::Xcursor];
+
+    /// The name a project's `formats` list uses
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cur => "cur",
+            Self::Ani => "ani",
+            Self::Xcursor => "xcursor",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Cur => "Static Windows cursor, one frame per size",
+            Self::Ani => "Animated Windows cursor",
+            Self::Xcursor => "X11 cursor file, static or animated",
+        }
+    }
+
+    /// Look a format up by its `name`
+    ///
+    /// ```
+    /// use cursor_handler::project::TargetFormat;
+    ///
+    /// for format in TargetFormat::ALL {
+    ///     assert_eq!(TargetFormat::from_name(format.name()), Some(format));
+    /// }
+    /// assert_eq!(TargetFormat::from_name("png"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.name() == name)
+    }
+
+    /// The output path for a cursor whose output is `base`
+    pub fn output_path(self, base: &Path) -> PathBuf {
+        let extension = match self {
+            Self::Cur => ".cur",
+            Self::Ani => ".ani",
+            Self::Xcursor => return base.to_path_buf(),
+        };
+        let mut path = OsString::from(base);
+        path.push(extension);
+        path.into()
+    }
+}
+
+/// One `[[cursor]]` table of a project
+#[derive(Debug, Clone)]
+pub struct CursorEntry {
+    pub name: String,
+    pub source: PathBuf, // Already resolved against the project file
+    pub sizes: Vec<u32>, // Empty for the source's own size
+    pub hotspot: HotspotSpec,
+    pub rate: Option<u32>, // Jiffies per frame
+    pub formats: Vec<TargetFormat>,
+    pub output: PathBuf, // Relative to the output root
+}
+
+/// A parsed project file
+#[derive(Debug, Clone, Default)]
+pub struct CursorProject {
+    pub cursors: Vec<CursorEntry>,
+}
+
+/// What `CursorProject::build` wrote
+#[derive(Debug, Clone, Default)]
+pub struct BuildReport {
+    pub outputs: Vec<PathBuf>,
+    pub warnings: Vec<String>,
+}
+
+impl CursorProject {
+    /// Read a project file
+    ///
+    /// ```
+    /// use cursor_handler::project::{CursorProject, TargetFormat};
+    ///
+    /// let project = CursorProject::load("assets/project/project.toml")?;
+    /// let names: Vec<_> = project.cursors.iter().map(|entry| entry.name.as_str()).collect();
+    /// assert_eq!(names, ["arrow", "wait", "spinner"]);
+    /// assert!(project.cursors[0].source.ends_with("project/../cursor.png"));
+    /// assert_eq!(project.cursors[1].formats, [TargetFormat::Ani, TargetFormat::Xcursor]);
+    ///
+    /// let out = std::env::temp_dir().join("cursor_handler_project_doc");
+    /// let report = project.build(&out)?;
+    /// assert_eq!(report.outputs.len(), 5);
+    ///
+    /// let arrow = std::fs::read(out.join("arrow.cur"))?;
+    /// let arrow = cursor_handler::CursorFile::decode(std::io::Cursor::new(arrow))?;
+    /// let sizes: Vec<_> = arrow.frames().map(|frame| frame.size.max_side()).collect();
+    /// assert_eq!(sizes, [32, 48]);
+    ///
+    /// let spinner = std::fs::read(out.join("animated/spinner.ani"))?;
+    /// let spinner = cursor_handler::AniFile::decode(std::io::Cursor::new(spinner))?;
+    /// let rates: Vec<_> = spinner.steps().map(|step| step.duration).collect();
+    /// assert_eq!(rates, [3, 6]);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let in_file =
+            |err: io::Error| io::Error::new(err.kind(), format!("{}: {err}", path.display()));
+        let text = fs::read_to_string(path).map_err(in_file)?;
+        Self::parse(&text, path.parent().unwrap_or(Path::new(""))).map_err(in_file)
+    }
+
+    /// Parse project text, resolving sources against `base_dir`
+    ///
+    /// Unknown keys are errors, so typos don't silently fall back to
+    /// defaults.
+    ///
+    /// ```
+    /// use cursor_handler::{HotspotSpec, project::CursorProject};
+    ///
+    /// let text = "[[cursor]]\nname = 'hand'\nsource = 'hand.png'\n\
+    ///             hotspot_normalized = [0.25, 0]\nformats = ['cur']\n";
+    /// let project = CursorProject::parse(text, "themes")?;
+    /// assert_eq!(project.cursors[0].hotspot, HotspotSpec::Normalized(0.25, 0.0));
+    /// assert_eq!(project.cursors[0].output, std::path::Path::new("hand"));
+    ///
+    /// let centred = text.replace("hotspot_normalized = [0.25, 0]", "hotspot = 'center'");
+    /// let project = CursorProject::parse(&centred, "themes")?;
+    /// assert_eq!(project.cursors[0].hotspot, HotspotSpec::Center);
+    ///
+    /// let typo = text.replace("formats", "format");
+    /// let err = CursorProject::parse(&typo, "themes").unwrap_err();
+    /// assert!(err.to_string().starts_with("line 5: unknown field `format`"), "{err}");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn parse(text: &str, base_dir: impl AsRef<Path>) -> io::Result<Self> {
+        let file: ProjectFile = from_toml(text)?;
+        let mut cursors = Vec::new();
+        for (index, table) in file.cursor.into_iter().enumerate() {
+            let label = match &table.name {
+                Some(name) => format!("cursor '{name}'"),
+                None => format!("cursor #{}", index + 1),
+            };
+            cursors.push(entry_from_table(table, &label, base_dir.as_ref())?);
+        }
+
+        let mut names = HashSet::new();
+        let mut outputs = HashSet::new();
+        for entry in &cursors {
+            if !names.insert(&entry.name) {
+                return Err(invalid_data(format!(
+                    "cursor '{}' is defined twice",
+                    entry.name
+                )));
+            }
+            for format in &entry.formats {
+                let path = format.output_path(&entry.output);
+                if !outputs.insert(path.clone()) {
+                    return Err(invalid_data(format!(
+                        "cursor '{}': {} is also written by another cursor",
+                        entry.name,
+                        path.display()
+                    )));
+                }
+            }
+        }
+        Ok(Self { cursors })
+    }
+
+    /// Build every cursor into `out_root`, creating directories as needed
+    ///
+    /// Stops at the first cursor that fails; the error names it, and the
+    /// frame when one is at fault.
+    pub fn build(&self, out_root: impl AsRef<Path>) -> io::Result<BuildReport> {
+        let mut report = BuildReport::default();
+        for entry in &self.cursors {
+            entry.build_into(out_root.as_ref(), &mut report)?;
+        }
+        Ok(report)
+    }
+}
+
+impl CursorEntry {
+    /// Build this cursor alone into `out_root`, as `CursorProject::build`
+    /// would
+    pub fn build(&self, out_root: impl AsRef<Path>) -> io::Result<BuildReport> {
+        let mut report = BuildReport::default();
+        self.build_into(out_root.as_ref(), &mut report)?;
+        Ok(report)
+    }
+
+    fn build_into(&self, out_root: &Path, report: &mut BuildReport) -> io::Result<()> {
+        let frames = load_source(&self.source).map_err(|err| self.error(err))?;
+        let (width, height) = frames[0].image.dimensions();
+        for (index, frame) in frames.iter().enumerate() {
+            if frame.image.dimensions() != (width, height) {
+                return Err(self.frame_error(
+                    index,
+                    invalid_data(format!(
+                        "{}x{} doesn't match the first frame's {width}x{height}",
+                        frame.image.width(),
+                        frame.image.height()
+                    )),
+                ));
+            }
+        }
+
+        if let HotspotSpec::Absolute(hotspot) = self.hotspot
+            && !hotspot.is_within(width, height)
+        {
+            return Err(self.error(invalid_data(format!(
+                "hotspot {hotspot} is outside the {width}x{height} source"
+            ))));
+        }
+
+        let sizes = if self.sizes.is_empty() {
+            vec![width.max(height)]
+        } else {
+            self.sizes.clone()
+        };
+        for &size in &sizes {
+            if size > width.max(height) {
+                report.warnings.push(format!(
+                    "cursor '{}': {size}px is upscaled from the {width}x{height} source",
+                    self.name
+                ));
+            }
+        }
+
+        // One multi-size cursor per source frame
+        let cursors = frames
+            .iter()
+            .enumerate()
+            .map(|(index, frame)| {
+                self.cursor(&frame.image, &sizes)
+                    .map_err(|err| self.frame_error(index, err))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let rates: Vec<u32> = frames
+            .iter()
+            .map(|frame| self.rate.or(frame.delay).unwrap_or(DEFAULT_RATE).max(1))
+            .collect();
+
+        for &format in &self.formats {
+            let bytes = match format {
+                TargetFormat::Cur => {
+                    if cursors.len() != 1 {
+                        return Err(self.error(invalid_data(format!(
+                            "a .cur needs a single frame, the source has {}",
+                            cursors.len()
+                        ))));
+                    }
+                    let mut bytes = Vec::new();
+                    cursors[0].encode(&mut bytes)?;
+                    bytes
+                }
+                TargetFormat::Ani => self.ani(&cursors, &rates).map_err(|err| self.error(err))?,
+                TargetFormat::Xcursor => {
+                    let mut images = Vec::with_capacity(sizes.len() * cursors.len());
+                    for &size in &sizes {
+                        for (cursor, &rate) in cursors.iter().zip(&rates) {
+                            let frame = cursor.best_frame(size).expect("one frame per size");
+                            images.push(XcursorImage {
+                                nominal_size: size,
+                                image: frame.decode_image()?,
+                                hotspot: frame.hotspot,
+                                delay_ms: if cursors.len() > 1 {
+                                    jiffies_to_ms(rate) as u32
+                                } else {
+                                    0
+                                },
+                            });
+                        }
+                    }
+                    let mut bytes = Vec::new();
+                    xcursor::encode(&images, &mut bytes).map_err(|err| self.error(err))?;
+                    bytes
+                }
+            };
+
+            let path = out_root.join(format.output_path(&self.output));
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, bytes).map_err(|err| {
+                self.error(io::Error::new(
+                    err.kind(),
+                    format!("{}: {err}", path.display()),
+                ))
+            })?;
+            report.outputs.push(path);
+        }
+        Ok(())
+    }
+
+    /// A cursor with one frame per size, each fitted onto a square canvas
+    fn cursor(&self, image: &RgbaImage, sizes: &[u32]) -> io::Result<CursorFile> {
+        let mut cursor = CursorFile::empty();
+        for &size in sizes {
+            let hotspot = self.place_hotspot(image.dimensions(), size);
+            cursor.push_frame(CursorFrame::from_image(&fit_square(image, size), hotspot)?);
+        }
+        Ok(cursor)
+    }
+
+    fn ani(&self, cursors: &[CursorFile], rates: &[u32]) -> io::Result<Vec<u8>> {
+        let mut ani = AniFile::empty();
+        for cursor in cursors {
+            let largest = cursor
+                .frames()
+                .max_by_key(|frame| frame.size.max_side())
+                .expect("cursors have at least one size");
+            let mut image_data = Vec::new();
+            cursor.encode(&mut image_data)?;
+            ani.push_frame(AniFrame::new(
+                largest.size,
+                largest.hotspot,
+                image_data,
+                None,
+            ));
+        }
+        ani = if rates.iter().all(|&rate| rate == rates[0]) {
+            ani.with_default_rate(rates[0])?
+        } else {
+            ani.with_rates(rates.to_vec())
+        };
+
+        let mut bytes = Cursor::new(Vec::new());
+        ani.encode(&mut bytes)?;
+        Ok(bytes.into_inner())
+    }
+
+    /// Where the hotspot lands on a `size` canvas made by `fit_square`
+    fn place_hotspot(&self, (width, height): (u32, u32), size: u32) -> Hotspot {
+        // Pixel centres map as `Hotspot::scaled` does
+        let (fx, fy) = self.hotspot.fraction(width, height);
+        let scale = size as f32 / width.max(height) as f32;
+        let fitted = |side: u32| ((side as f32 * scale).round() as u32).clamp(1, size);
+        let place = |fraction: f32, side: u32| {
+            let offset = (size - side) / 2;
+            let along = (fraction * side as f32) as u32;
+            (offset + along).min(size - 1).min(u16::MAX as u32) as u16
+        };
+        Hotspot::new(place(fx, fitted(width)), place(fy, fitted(height)))
+    }
+
+    fn error(&self, err: io::Error) -> io::Error {
+        io::Error::new(err.kind(), format!("cursor '{}': {err}", self.name))
+    }
+
+    fn frame_error(&self, index: usize, err: io::Error) -> io::Error {
+        io::Error::new(
+            err.kind(),
+            format!("cursor '{}', frame {index}: {err}", self.name),
+        )
+    }
+}
+
+struct SourceFrame {
+    image: RgbaImage,
+    delay: Option<u32>, // Jiffies, from a GIF
+}
+
+fn load_source(path: &Path) -> io::Result<Vec<SourceFrame>> {
+    let in_path = |err: io::Error| io::Error::new(err.kind(), format!("{}: {err}", path.display()));
+    let open = |path: &Path| -> io::Result<SourceFrame> {
+        let image = image::open(path).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {err}", path.display()),
+            )
+        })?;
+        Ok(SourceFrame {
+            image: image.to_rgba8(),
+            delay: None,
+        })
+    };
+
+    if path.is_dir() {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(path).map_err(in_path)? {
+            let path = entry?.path();
+            let is_png = path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+            if is_png {
+                paths.push(path);
+            }
+        }
+        if paths.is_empty() {
+            return Err(in_path(invalid_data("no .png frames in the directory")));
+        }
+        paths.sort();
+        return paths.iter().map(|path| open(path)).collect();
+    }
+
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "svg" => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{}: SVG sources are not supported", path.display()),
+        )),
+        "gif" => {
+            let file = File::open(path).map_err(in_path)?;
+            let decoder = GifDecoder::new(BufReader::new(file)).map_err(io::Error::other)?;
+            let frames = decoder
+                .into_frames()
+                .collect_frames()
+                .map_err(|err| in_path(io::Error::other(err)))?;
+            if frames.is_empty() {
+                return Err(in_path(invalid_data("the GIF has no frames")));
+            }
+            Ok(frames
+                .into_iter()
+                .map(|frame| {
+                    let (numer, denom) = frame.delay().numer_denom_ms();
+                    SourceFrame {
+                        delay: Some(ms_to_jiffies(numer / denom.max(1))),
+                        image: frame.into_buffer(),
+                    }
+                })
+                .collect())
+        }
+        _ => Ok(vec![open(path)?]),
+    }
+}
+
+/// Deserialize a TOML document, errors naming the line they are on
+pub(crate) fn from_toml<T: DeserializeOwned>(text: &str) -> io::Result<T> {
+    toml::from_str(text).map_err(|err| {
+        let message = err.message().trim_end();
+        match err.span() {
+            Some(span) => {
+                let before = text.get(..span.start).unwrap_or(text);
+                invalid_data(format!(
+                    "line {}: {message}",
+                    before.matches('\n').count() + 1
+                ))
+            }
+            None => invalid_data(message),
+        }
+    })
+}
+
+/// A project file as written
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProjectFile {
+    #[serde(default)]
+    cursor: Vec<EntryTable>,
+}
+
+/// One `[[cursor]]` table as written, before its values are checked
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct EntryTable {
+    pub(crate) name: Option<String>,
+    pub(crate) source: Option<String>,
+    #[serde(default)]
+    pub(crate) sizes: Vec<u32>,
+    pub(crate) hotspot: Option<HotspotValue>,
+    pub(crate) hotspot_normalized: Option<[f64; 2]>,
+    pub(crate) rate: Option<u32>,
+    #[serde(default)]
+    pub(crate) formats: Vec<String>,
+    pub(crate) output: Option<String>,
+}
+
+/// `hotspot` as written: a point or the name of one
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    untagged,
+    expecting = "'hotspot' must be [x, y], 'center' or 'top-left'"
+)]
+pub(crate) enum HotspotValue {
+    Point([u16; 2]),
+    Name(String),
+}
+
+/// A `CursorEntry` from a table, with errors prefixed by `label`
+pub(crate) fn entry_from_table(
+    table: EntryTable,
+    label: &str,
+    base_dir: &Path,
+) -> io::Result<CursorEntry> {
+    let error = |message: String| invalid_data(format!("{label}: {message}"));
+
+    let name = table
+        .name
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| error("'name' must be a non-empty string".to_string()))?;
+    let source = table
+        .source
+        .ok_or_else(|| error("'source' is missing".to_string()))?;
+
+    let max = xcursor::MAX_IMAGE_SIDE;
+    if let Some(size) = table.sizes.iter().find(|&&size| size == 0 || size > max) {
+        return Err(error(format!("sizes must be in 1..={max}, not {size}")));
+    }
+
+    let hotspot = match (table.hotspot, table.hotspot_normalized) {
+        (Some(_), Some(_)) => {
+            return Err(error(
+                "give 'hotspot' or 'hotspot_normalized', not both".to_string(),
+            ));
+        }
+        (Some(HotspotValue::Point([x, y])), None) => HotspotSpec::Absolute(Hotspot::new(x, y)),
+        (Some(HotspotValue::Name(name)), None) => match name.as_str() {
+            "center" => HotspotSpec::Center,
+            "top-left" => HotspotSpec::TopLeft,
+            _ => {
+                return Err(error(format!(
+                    "'hotspot' must be [x, y], 'center' or 'top-left', not '{name}'"
+                )));
+            }
+        },
+        (None, Some([x, y])) if (0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y) => {
+            HotspotSpec::Normalized(x as f32, y as f32)
+        }
+        (None, Some(_)) => {
+            return Err(error(
+                "'hotspot_normalized' must be [x, y], each in 0..=1".to_string(),
+            ));
+        }
+        (None, None) => HotspotSpec::default(),
+    };
+
+    if table.rate == Some(0) {
+        return Err(error("'rate' must be at least 1 jiffy".to_string()));
+    }
+
+    let mut formats = Vec::new();
+    for format in &table.formats {
+        let format = TargetFormat::from_name(format).ok_or_else(|| {
+            let names: Vec<_> = TargetFormat::ALL
+                .iter()
+                .map(|format| format!("\"{}\"", format.name()))
+                .collect();
+            error(format!("formats must be one of {}", names.join(", ")))
+        })?;
+        if !formats.contains(&format) {
+            formats.push(format);
+        }
+    }
+    if formats.is_empty() {
+        return Err(error("'formats' must list at least one format".to_string()));
+    }
+
+    Ok(CursorEntry {
+        output: PathBuf::from(table.output.as_deref().unwrap_or(&name)),
+        name,
+        source: base_dir.join(source),
+        sizes: table.sizes,
+        hotspot,
+        rate: table.rate,
+        formats,
+    })
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
@@ -0,0 +1,234 @@
+// Cursors drawn from SVG sources
+//
+// resvg renders the vector at every requested size, anti-aliased, so a
+// 32px and a 64px frame are both drawn rather than one scaled from the
+// other. The drawing's longer side fills the frame; the other is either
+// kept to its aspect ratio or centred on a square canvas.
+use std::io;
+
+use image::{Rgba, RgbaImage};
+use resvg::{
+    tiny_skia::{Pixmap, Transform},
+    usvg::{Options, Tree},
+};
+
+use crate::{
+    ani::{AniFile, MixedSizePolicy},
+    cur::{CursorFile, CursorFrame},
+    generate::cursor_frame,
+    hotspot::{Hotspot, HotspotSpec},
+};
+
+/// How `CursorFile::from_svg_with` and `AniFile::from_svgs_with` render
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SvgOptions {
+    /// Centre a non-square drawing on a transparent square canvas, as
+    /// `ResizeOptions::pad_square` does, rather than keeping its aspect ratio
+    pub pad_square: bool,
+}
+
+/// A parsed SVG with its hotspot; an `Absolute` one becomes `Normalized`
+/// against the SVG's own size
+struct Source {
+    tree: Tree,
+    hotspot: HotspotSpec,
+}
+
+impl Source {
+    fn parse(svg: &str, hotspot: HotspotSpec) -> io::Result<Self> {
+        let tree = Tree::from_str(svg, &Options::default()).map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("invalid SVG: {err}"))
+        })?;
+        let size = tree.size();
+        let (width, height) = (size.width().ceil() as u32, size.height().ceil() as u32);
+        let hotspot = match hotspot {
+            HotspotSpec::Absolute(hotspot) if !hotspot.is_within(width, height) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("hotspot {hotspot} is outside the {width}x{height} drawing"),
+                ));
+            }
+            HotspotSpec::Absolute(hotspot) => {
+                let (fx, fy) = hotspot.fraction(width, height);
+                HotspotSpec::Normalized(fx, fy)
+            }
+            spec => spec,
+        };
+        Ok(Self { tree, hotspot })
+    }
+
+    /// The drawing with its longer side `side` pixels, and its hotspot
+    fn render(&self, side: u32, options: &SvgOptions) -> io::Result<(RgbaImage, Hotspot)> {
+        if side == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame size must be at least 1",
+            ));
+        }
+        let size = self.tree.size();
+        let scale = side as f32 / size.width().max(size.height());
+        let fit = |length: f32| ((length * scale).round() as u32).clamp(1, side);
+        let (width, height) = (fit(size.width()), fit(size.height()));
+        let (canvas, x, y) = match options.pad_square {
+            true => ((side, side), (side - width) / 2, (side - height) / 2),
+            false => ((width, height), 0, 0),
+        };
+
+        let mut pixmap = Pixmap::new(canvas.0, canvas.1).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("can't draw at {side}px"),
+            )
+        })?;
+        let transform = Transform::from_row(
+            width as f32 / size.width(),
+            0.0,
+            0.0,
+            height as f32 / size.height(),
+            x as f32,
+            y as f32,
+        );
+        resvg::render(&self.tree, transform, &mut pixmap.as_mut());
+
+        // tiny-skia keeps premultiplied alpha, images are straight
+        let mut image = RgbaImage::new(canvas.0, canvas.1);
+        for (pixel, color) in image.pixels_mut().zip(pixmap.pixels()) {
+            let color = color.demultiply();
+            *pixel = Rgba([color.red(), color.green(), color.blue(), color.alpha()]);
+        }
+        let hotspot = self.hotspot.resolve(width, height);
+        Ok((
+            image,
+            Hotspot::new(hotspot.x + x as u16, hotspot.y + y as u16),
+        ))
+    }
+}
+
+impl CursorFile {
+    /// A cursor with one frame per side in `sizes`, each drawn from `svg`
+    ///
+    /// `Absolute` hotspots are in the SVG's user units; they and
+    /// `Normalized` ones land on the same point of the drawing at every
+    /// size, and `Center` is the centre of each frame's drawing.
+    ///
+    /// ```
+    /// use cursor_handler::{CursorFile, FrameSize, Hotspot, HotspotSpec};
+    ///
+    /// let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="16">
+    ///     <path d="M0 0 L16 8 L6 10 Z" fill="black"/>
+    /// </svg>"#;
+    /// let cursor = CursorFile::from_svg(svg, &[32, 48], HotspotSpec::Normalized(0.25, 0.5))?;
+    /// let sizes: Vec<_> = cursor.frames().map(|frame| frame.size).collect();
+    /// assert_eq!(sizes, [FrameSize::square(32)?, FrameSize::square(48)?]);
+    /// assert_eq!(cursor[0].hotspot, Hotspot::new(8, 16));
+    /// assert_eq!(cursor[1].hotspot, Hotspot::new(12, 24));
+    ///
+    /// assert!(CursorFile::from_svg("<svg", &[32], HotspotSpec::TopLeft).is_err());
+    /// assert!(CursorFile::from_svg(svg, &[32], (16, 0).into()).is_err());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_svg(svg: &str, sizes: &[u32], hotspot: HotspotSpec) -> io::Result<Self> {
+        Self::from_svg_with(svg, sizes, hotspot, &SvgOptions::default())
+    }
+
+    /// `from_svg`, centring non-square drawings if `options` ask for it
+    ///
+    /// ```
+    /// use cursor_handler::rasterize::SvgOptions;
+    /// use cursor_handler::{CursorFile, FrameSize, Hotspot, HotspotSpec};
+    ///
+    /// let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10">
+    ///     <rect width="20" height="10" fill="red"/>
+    /// </svg>"#;
+    /// let kept = CursorFile::from_svg(svg, &[32], HotspotSpec::Center)?;
+    /// assert_eq!((kept[0].size, kept[0].hotspot), (FrameSize::new(32, 16)?, Hotspot::new(16, 8)));
+    ///
+    /// let options = SvgOptions { pad_square: true };
+    /// let padded = CursorFile::from_svg_with(svg, &[32], HotspotSpec::Center, &options)?;
+    /// assert_eq!(padded[0].size, FrameSize::square(32)?);
+    /// assert_eq!(padded[0].hotspot, Hotspot::new(16, 16));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_svg_with(
+        svg: &str,
+        sizes: &[u32],
+        hotspot: HotspotSpec,
+        options: &SvgOptions,
+    ) -> io::Result<Self> {
+        if sizes.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no sizes to draw",
+            ));
+        }
+        let source = Source::parse(svg, hotspot)?;
+        let frames = sizes
+            .iter()
+            .map(|&side| {
+                let (image, hotspot) = source.render(side, options)?;
+                CursorFrame::from_image(&image, hotspot)
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self::new(frames))
+    }
+}
+
+impl AniFile {
+    /// An animation with one frame per SVG, each drawn at `size`
+    ///
+    /// The hotspot is placed on each drawing as in `CursorFile::from_svg`.
+    /// Frames play one step each at the default
+    /// rate; `with_default_rate` and `with_sequence` change that.
+    ///
+    /// ```
+    /// use cursor_handler::{AniFile, Hotspot, HotspotSpec};
+    ///
+    /// let frame = |fill: &str| {
+    ///     format!(
+    ///         r#"<svg xmlns="http://www.w3.org/2000/svg" width="8" height="8">
+    ///             <circle cx="4" cy="4" r="4" fill="{fill}"/>
+    ///         </svg>"#
+    ///     )
+    /// };
+    /// let (red, blue) = (frame("red"), frame("blue"));
+    /// let ani = AniFile::from_svgs(&[&red, &blue], 32, HotspotSpec::Center)?;
+    /// assert_eq!(ani.num_frames(), 2);
+    /// assert_eq!(ani.uniform_hotspot(), Some(Hotspot::new(16, 16)));
+    ///
+    /// let pixel = |index: usize| {
+    ///     let frame = ani.frames().nth(index).unwrap();
+    ///     frame.decode_image().map(|image| *image.get_pixel(16, 16))
+    /// };
+    /// assert_eq!(pixel(0)?.0, [255, 0, 0, 255]);
+    /// assert_eq!(pixel(1)?.0, [0, 0, 255, 255]);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_svgs(frames: &[&str], size: u32, hotspot: HotspotSpec) -> io::Result<Self> {
+        Self::from_svgs_with(frames, size, hotspot, &SvgOptions::default())
+    }
+
+    /// `from_svgs`, centring non-square drawings if `options` ask for it;
+    /// without `pad_square`, drawings of different aspect ratios are an
+    /// error
+    pub fn from_svgs_with(
+        frames: &[&str],
+        size: u32,
+        hotspot: HotspotSpec,
+        options: &SvgOptions,
+    ) -> io::Result<Self> {
+        if frames.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no frames to draw",
+            ));
+        }
+        let frames = frames
+            .iter()
+            .map(|svg| {
+                let (image, hotspot) = Source::parse(svg, hotspot)?.render(size, options)?;
+                cursor_frame(&image, hotspot)
+            })
+            .collect::<io::Result<_>>()?;
+        Self::from_frames(frames, MixedSizePolicy::Error)
+    }
+}
@@ -0,0 +1,265 @@
+// Plain RGBA buffers for callers that upload pixels themselves
+//
+// Decoding to a `RawImage` goes through the png crate and our own DIB
+// reader rather than the image crate, so the bytes can be handed straight to
+// a GPU upload.
+use std::io;
+
+use image::RgbaImage;
+use png::{Decoder, Transformations};
+
+use crate::{
+    ani::AniFile,
+    cur::CursorFrame,
+    dib::encode_dib32,
+    export::rgba_pixels,
+    hotspot::{Hotspot, HotspotSpec},
+    manifest::PayloadKind,
+    optimize::encode_png,
+    size::FrameSize,
+};
+
+/// Straight-alpha RGBA pixels, row-major, `width * 4` bytes per row with no
+/// padding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl RawImage {
+    /// Check that the dimensions are non-zero and `data` holds exactly
+    /// `height` rows of `width * 4` bytes
+    pub fn validate(&self) -> io::Result<()> {
+        let stride = self.width as usize * 4;
+        let expected = stride * self.height as usize;
+        if self.width == 0 || self.height == 0 {
+            return Err(invalid_input(format!(
+                "{}x{} image has no pixels",
+                self.width, self.height
+            )));
+        }
+        if self.data.len() != expected {
+            return Err(invalid_input(format!(
+                "{}x{} RGBA needs {expected} bytes ({} rows of {stride}), got {}",
+                self.width,
+                self.height,
+                self.height,
+                self.data.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// One playback step of `AniFile::to_raw_frames`
+#[derive(Debug, Clone)]
+pub struct RawStep {
+    pub image: RawImage,
+    pub hotspot: Hotspot,
+    pub duration_ns: u64,
+}
+
+impl CursorFrame {
+    /// Decode the payload to straight-alpha RGBA without the image crate
+    ///
+    /// DIBs without alpha take it from the AND mask; masked pixels come out
+    /// fully transparent.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, CursorFrame, PayloadKind, raw::RawImage};
+    ///
+    /// let raw = RawImage { width: 2, height: 1, data: vec![255, 0, 0, 255, 0, 0, 255, 128] };
+    /// for payload in [PayloadKind::Dib, PayloadKind::Png] {
+    ///     let frame = CursorFrame::from_raw_rgba(&raw, (1, 0), payload)?;
+    ///     assert_eq!(frame.to_raw_rgba()?, raw);
+    /// }
+    ///
+    /// // The same pixels the image crate decodes
+    /// let ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// for frame in ani.extract_frame(0)?.frames() {
+    ///     assert_eq!(frame.to_raw_rgba()?.data, frame.decode_image()?.into_raw());
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn to_raw_rgba(&self) -> io::Result<RawImage> {
+        if let Some(png) = self.png_data() {
+            return png_to_raw(png);
+        }
+        dib_to_raw(self)
+    }
+
+    /// A frame holding `raw`, stored as a 32-bit DIB or PNG
+    ///
+    /// ```
+    /// use cursor_handler::{CursorFrame, Hotspot, HotspotSpec, PayloadKind, raw::RawImage};
+    ///
+    /// // A row short: 2x2 RGBA is 16 bytes
+    /// let short = RawImage { width: 2, height: 2, data: vec![0; 12] };
+    /// let err = CursorFrame::from_raw_rgba(&short, (0, 0), PayloadKind::Png).unwrap_err();
+    /// assert_eq!(err.to_string(), "2x2 RGBA needs 16 bytes (2 rows of 8), got 12");
+    ///
+    /// // The hotspot is resolved at the image's size
+    /// let odd = RawImage { width: 31, height: 20, data: vec![0; 31 * 20 * 4] };
+    /// let frame = CursorFrame::from_raw_rgba(&odd, HotspotSpec::Center, PayloadKind::Dib)?;
+    /// assert_eq!(frame.hotspot, Hotspot::new(15, 10));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_raw_rgba(
+        raw: &RawImage,
+        hotspot: impl Into<HotspotSpec>,
+        payload: PayloadKind,
+    ) -> io::Result<Self> {
+        raw.validate()?;
+        let hotspot = hotspot.into().resolve(raw.width, raw.height);
+        let size = FrameSize::new(raw.width, raw.height)?;
+        let image =
+            RgbaImage::from_raw(raw.width, raw.height, raw.data.clone()).expect("validated length");
+        let image_data = match payload {
+            PayloadKind::Png => encode_png(&image)?,
+            PayloadKind::Dib if size.fits_directory() => encode_dib32(&image),
+            PayloadKind::Dib => {
+                return Err(invalid_input(format!(
+                    "{size} is too large for a DIB payload, use PNG"
+                )));
+            }
+            PayloadKind::Cursor => {
+                return Err(invalid_input(
+                    "a cursor frame can't hold a cursor container",
+                ));
+            }
+        };
+        Ok(Self::new(size, hotspot, image_data))
+    }
+}
+
+impl AniFile {
+    /// Every playback step as raw RGBA, with its hotspot and duration
+    ///
+    /// Frames holding several resolutions give their largest. Each stored
+    /// frame is decoded once however often the sequence shows it.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::AniFile;
+    ///
+    /// let ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// let steps = ani.to_raw_frames()?;
+    /// assert_eq!(steps.len(), 18);
+    /// assert_eq!((steps[0].image.width, steps[0].image.height), (64, 64));
+    /// assert_eq!(steps[0].duration_ns, 50_000_000); // 3 jiffies
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn to_raw_frames(&self) -> io::Result<Vec<RawStep>> {
+        let mut decoded: Vec<Option<(RawImage, Hotspot)>> = vec![None; self.num_frames() as usize];
+        let mut steps = Vec::new();
+        for step in self.steps() {
+            let (image, hotspot) = match &mut decoded[step.frame_index] {
+                Some(frame) => &*frame,
+                slot => &*slot.insert(self.raw_frame(step.frame_index)?),
+            };
+            steps.push(RawStep {
+                image: image.clone(),
+                hotspot: *hotspot,
+                duration_ns: u64::from(step.duration) * 1_000_000_000 / 60,
+            });
+        }
+        Ok(steps)
+    }
+
+    /// The largest resolution of stored frame `index`
+    fn raw_frame(&self, index: usize) -> io::Result<(RawImage, Hotspot)> {
+        let cursor = self.extract_frame(index)?;
+        let frame = cursor
+            .frames()
+            .max_by_key(|frame| frame.size.max_side())
+            .ok_or_else(|| invalid_data(format!("frame {index} is empty")))?;
+        Ok((frame.to_raw_rgba()?, frame.hotspot))
+    }
+}
+
+fn png_to_raw(data: &[u8]) -> io::Result<RawImage> {
+    let mut decoder = Decoder::new(data);
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .map_err(|err| invalid_data(err.to_string()))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let output = reader
+        .next_frame(&mut buffer)
+        .map_err(|err| invalid_data(err.to_string()))?;
+    Ok(RawImage {
+        width: output.width,
+        height: output.height,
+        data: rgba_pixels(&buffer, &output)?,
+    })
+}
+
+fn dib_to_raw(frame: &CursorFrame) -> io::Result<RawImage> {
+    let (Some(info), Some(color), Some(mask)) =
+        (frame.dib_info(), frame.xor_data(), frame.and_mask())
+    else {
+        return Err(invalid_data("payload is not a readable uncompressed DIB"));
+    };
+    let palette_start = info.xor_offset().unwrap_or_default() - info.palette_len() * 4;
+    let palette = &frame.image_data[palette_start..palette_start + info.palette_len() * 4];
+
+    // A 32-bit image whose alpha is all zero relies on its mask
+    let uses_alpha = color.bit_count == 32
+        && color.rows().any(|row| {
+            row[..color.width as usize * 4]
+                .chunks_exact(4)
+                .any(|px| px[3] != 0)
+        });
+
+    let bits = color.bit_count as usize;
+    let mut data = Vec::with_capacity(color.width as usize * color.height as usize * 4);
+    for (row, mask_row) in color.rows().zip(mask.rows()) {
+        for x in 0..color.width as usize {
+            let [r, g, b, a] = match bits {
+                32 => [row[x * 4 + 2], row[x * 4 + 1], row[x * 4], row[x * 4 + 3]],
+                24 => [row[x * 3 + 2], row[x * 3 + 1], row[x * 3], 255],
+                16 => {
+                    // X1R5G5B5
+                    let value = u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]);
+                    let channel = |shift: u16| (((value >> shift) & 0x1f) as u32 * 255 / 31) as u8;
+                    [channel(10), channel(5), channel(0), 255]
+                }
+                1 | 4 | 8 => {
+                    let per_byte = 8 / bits;
+                    let shift = 8 - bits * (x % per_byte + 1);
+                    let index = (row[x / per_byte] >> shift) as usize & ((1 << bits) - 1);
+                    match palette.get(index * 4..index * 4 + 3) {
+                        Some(&[b, g, r]) => [r, g, b, 255],
+                        _ => [0, 0, 0, 255],
+                    }
+                }
+                other => return Err(invalid_data(format!("unsupported {other}-bit DIB"))),
+            };
+
+            let masked = mask_row[x / 8] & (0x80 >> (x % 8)) != 0;
+            data.extend_from_slice(&match (uses_alpha, masked) {
+                (true, _) => [r, g, b, a],
+                (false, true) => [0, 0, 0, 0],
+                (false, false) => [r, g, b, 255],
+            });
+        }
+    }
+    Ok(RawImage {
+        width: color.width,
+        height: color.height,
+        data,
+    })
+}
+
+fn invalid_input(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
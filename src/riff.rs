@@ -0,0 +1,454 @@
+// RIFF chunk reading and writing, shared by the ANI codec
+//
+// A chunk is a fourcc, a little-endian u32 size and that many bytes of data,
+// followed by a pad byte when the size is odd. `RIFF` and `LIST` chunks start
+// their data with a fourcc list type, followed by nested chunks.
+use std::{
+    fmt,
+    io::{self, Read, Seek, SeekFrom, Write},
+};
+
+pub type FourCC = [u8; 4];
+
+/// Walks the chunks between the current position and the end of the stream
+/// (or of the enclosing list)
+///
+/// Pad bytes are skipped, and a chunk's data does not need to be read before
+/// asking for the next one. A trailing partial chunk header ends the walk.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::riff::{ChunkReader, ChunkWriter};
+///
+/// let mut riff = ChunkWriter::new(Cursor::new(Vec::new()));
+/// riff.begin_list(*b"RIFF", *b"DEMO")?;
+/// riff.write_chunk(*b"odd ", b"abc")?;
+/// riff.begin_list(*b"LIST", *b"INFO")?;
+/// riff.write_chunk(*b"INAM", b"x")?;
+/// riff.end_list()?;
+/// let bytes = riff.finish()?.into_inner();
+///
+/// let mut chunks = ChunkReader::new(Cursor::new(&bytes[12..]))?;
+/// let chunk = chunks.next_chunk()?.unwrap();
+/// assert_eq!((chunk.id(), chunk.size()), (*b"odd ", 3));
+///
+/// let (list_type, mut items) = chunks.next_chunk()?.unwrap().list()?;
+/// assert_eq!(list_type, *b"INFO");
+/// assert_eq!(items.next_chunk()?.unwrap().read_data()?, b"x");
+/// assert!(items.next_chunk()?.is_none());
+/// assert!(chunks.next_chunk()?.is_none());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct ChunkReader<R> {
+    reader: R,
+    next: u64,
+    end: u64,
+}
+
+impl<R: Read + Seek> ChunkReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let next = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(next))?;
+        Ok(Self { reader, next, end })
+    }
+
+    /// The next chunk, or `None` once fewer than 8 bytes are left
+    pub fn next_chunk(&mut self) -> io::Result<Option<Chunk<'_, R>>> {
+        if self.end.saturating_sub(self.next) < 8 {
+            return Ok(None);
+        }
+        let offset = self.next;
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let mut header = [0u8; 8];
+        self.reader.read_exact(&mut header)?;
+
+        let id = [header[0], header[1], header[2], header[3]];
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let data_start = offset + 8;
+        let available = (size as u64).min(self.end - data_start);
+        self.next = data_start + size as u64 + (size % 2) as u64;
+
+        Ok(Some(Chunk {
+            reader: &mut self.reader,
+            id,
+            offset,
+            size,
+            available,
+            remaining: available,
+            end: self.end,
+        }))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// One chunk from a `ChunkReader`; reading it yields the chunk's data
+pub struct Chunk<'a, R> {
+    reader: &'a mut R,
+    id: FourCC,
+    offset: u64,
+    size: u32,
+    available: u64,
+    remaining: u64,
+    end: u64,
+}
+
+impl<'a, R: Read + Seek> Chunk<'a, R> {
+    pub fn id(&self) -> FourCC {
+        self.id
+    }
+
+    /// Stream position of the chunk header
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Data size from the header, excluding the pad byte
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Bytes of data actually present, less than `size` if the stream ends early
+    pub fn available(&self) -> u64 {
+        self.available
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.available < self.size as u64
+    }
+
+    /// Read the rest of the data that is present
+    pub fn read_data(mut self) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    /// Read the list type of a `RIFF` or `LIST` chunk and walk its contents
+    pub fn list(mut self) -> io::Result<(FourCC, ChunkReader<&'a mut R>)> {
+        let mut list_type = [0u8; 4];
+        if self.remaining < 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "'{}' chunk has no list type",
+                    String::from_utf8_lossy(&self.id)
+                ),
+            ));
+        }
+        self.read_exact(&mut list_type)?;
+
+        let next = self.offset + 12;
+        let end = (self.offset + 8 + self.size as u64).min(self.end);
+        let reader = ChunkReader {
+            reader: self.reader,
+            next,
+            end,
+        };
+        Ok((list_type, reader))
+    }
+}
+
+impl<R: Read> Read for Chunk<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let limit = buf
+            .len()
+            .min(self.remaining.try_into().unwrap_or(usize::MAX));
+        let read = self.reader.read(&mut buf[..limit])?;
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+/// Writes chunks, filling in the sizes of `RIFF` and `LIST` chunks when they
+/// are closed
+///
+/// Lists still open are closed by `finish`, or on drop, where errors are
+/// lost.
+pub struct ChunkWriter<W: Write + Seek> {
+    writer: Option<W>,
+    open: Vec<u64>, // Positions of the size fields of open lists
+}
+
+impl<W: Write + Seek> ChunkWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Some(writer),
+            open: Vec::new(),
+        }
+    }
+
+    fn writer(&mut self) -> &mut W {
+        self.writer
+            .as_mut()
+            .expect("writer is only taken by finish")
+    }
+
+    /// Start a `RIFF` or `LIST` chunk; everything written until the matching
+    /// `end_list` goes inside it
+    pub fn begin_list(&mut self, id: FourCC, list_type: FourCC) -> io::Result<()> {
+        let writer = self.writer();
+        writer.write_all(&id)?;
+        let size_pos = writer.stream_position()?;
+        writer.write_all(&[0; 4])?;
+        writer.write_all(&list_type)?;
+        self.open.push(size_pos);
+        Ok(())
+    }
+
+    /// Close the innermost open list, writing its size
+    pub fn end_list(&mut self) -> io::Result<()> {
+        let Some(size_pos) = self.open.pop() else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No open list"));
+        };
+        let writer = self.writer();
+        let end = writer.stream_position()?;
+        let size = chunk_size(end - size_pos - 4)?;
+        writer.seek(SeekFrom::Start(size_pos))?;
+        writer.write_all(&size.to_le_bytes())?;
+        writer.seek(SeekFrom::Start(end))?;
+        Ok(())
+    }
+
+    /// Write a whole chunk, with its pad byte if `data` has an odd length
+    pub fn write_chunk(&mut self, id: FourCC, data: &[u8]) -> io::Result<()> {
+        let size = chunk_size(data.len() as u64)?;
+        let writer = self.writer();
+        writer.write_all(&id)?;
+        writer.write_all(&size.to_le_bytes())?;
+        writer.write_all(data)?;
+        if size % 2 != 0 {
+            writer.write_all(&[0])?;
+        }
+        Ok(())
+    }
+
+    /// Close every open list and return the writer, positioned at the end
+    pub fn finish(mut self) -> io::Result<W> {
+        while !self.open.is_empty() {
+            self.end_list()?;
+        }
+        Ok(self.writer.take().expect("finish is only called once"))
+    }
+}
+
+impl<W: Write + Seek> Drop for ChunkWriter<W> {
+    fn drop(&mut self) {
+        while self.writer.is_some() && !self.open.is_empty() {
+            if self.end_list().is_err() {
+                break;
+            }
+        }
+    }
+}
+
+fn chunk_size(len: u64) -> io::Result<u32> {
+    u32::try_from(len).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{len} bytes do not fit in a RIFF chunk"),
+        )
+    })
+}
+
+/// One chunk found by `list_chunks`, as stored, with whatever looked wrong
+/// about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkNode {
+    pub id: FourCC,
+    pub list_type: Option<FourCC>, // For `RIFF` and `LIST` chunks
+    pub offset: u64,
+    pub size: u32,      // As declared, excluding the pad byte
+    pub available: u64, // Bytes of data actually present
+    pub padded: bool,
+    pub children: Vec<ChunkNode>,
+    pub problems: Vec<String>,
+}
+
+/// The chunks of a stream, as `list_chunks` found them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChunkTree {
+    pub nodes: Vec<ChunkNode>,
+    /// Problems with the stream as a whole, such as stray trailing bytes
+    pub problems: Vec<String>,
+}
+
+impl ChunkTree {
+    /// Whether any node, or the stream itself, has a problem
+    pub fn has_problems(&self) -> bool {
+        fn any(nodes: &[ChunkNode]) -> bool {
+            nodes
+                .iter()
+                .any(|node| !node.problems.is_empty() || any(&node.children))
+        }
+        !self.problems.is_empty() || any(&self.nodes)
+    }
+}
+
+/// Walk the RIFF structure from the reader's position to the end, without
+/// interpreting any chunk's contents
+///
+/// Unlike the decoders this never gives up on bad data: truncated chunks
+/// keep what is present, a missing pad byte is detected when the next chunk
+/// only lines up without it, and each inconsistency is noted on the node it
+/// concerns. Only an I/O error fails the walk.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::{AniFile, riff::list_chunks};
+///
+/// // Cut short in the middle of the last frame, which decoding rejects
+/// let bytes = std::fs::read("tests/fixtures/truncated/spinner.ani")?;
+/// assert!(AniFile::decode(Cursor::new(&bytes)).is_err());
+///
+/// let tree = list_chunks(Cursor::new(&bytes))?;
+/// assert!(tree.has_problems());
+/// let frames = &tree.nodes[0].children[1];
+/// assert_eq!(frames.list_type, Some(*b"fram"));
+/// let last = frames.children.last().unwrap();
+/// assert_eq!((last.size as u64 - last.available, frames.children.len()), (7, 4));
+/// assert_eq!(tree.to_string().lines().last(), Some("      ! truncated, 7 bytes missing"));
+///
+/// // A writer that left out the pad byte after an odd-sized chunk
+/// let mut bytes = b"RIFF\0\0\0\0ACONodd \x03\0\0\0abcanih\x04\0\0\0wxyz".to_vec();
+/// bytes[4] = (bytes.len() - 8) as u8;
+/// let tree = list_chunks(Cursor::new(&bytes))?;
+/// let [odd, anih] = &tree.nodes[0].children[..] else { panic!("expected two chunks") };
+/// assert_eq!(odd.problems, ["missing pad byte"]);
+/// assert_eq!((anih.id, anih.offset), (*b"anih", 0x17));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn list_chunks<R: Read + Seek>(mut reader: R) -> io::Result<ChunkTree> {
+    let start = reader.stream_position()?;
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    let mut tree = ChunkTree::default();
+    if !bytes.starts_with(b"RIFF") {
+        tree.problems
+            .push("Stream does not start with a RIFF chunk".to_string());
+    }
+    tree.nodes = walk(&bytes, start, 0, bytes.len(), &mut tree.problems);
+    if tree.nodes.len() > 1 {
+        let extra = tree.nodes.len() - 1;
+        tree.problems
+            .push(format!("{extra} chunks after the first"));
+    }
+    Ok(tree)
+}
+
+/// The chunks of `bytes[at..end]`; `base` is the stream position of `bytes`
+fn walk(
+    bytes: &[u8],
+    base: u64,
+    mut at: usize,
+    end: usize,
+    problems: &mut Vec<String>,
+) -> Vec<ChunkNode> {
+    // A printable fourcc and a size header fit at `at`
+    let plausible = |at: usize| {
+        at + 8 <= end
+            && bytes[at..at + 4]
+                .iter()
+                .all(|byte| byte.is_ascii_graphic() || *byte == b' ')
+    };
+
+    let mut nodes = Vec::new();
+    while at < end {
+        if end - at < 8 {
+            let stray = end - at;
+            problems.push(format!("{stray} stray bytes at {:#X}", base + at as u64));
+            break;
+        }
+        let id = [bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]];
+        let size = u32::from_le_bytes([bytes[at + 4], bytes[at + 5], bytes[at + 6], bytes[at + 7]]);
+        let data_start = at + 8;
+        let available = (size as usize).min(end - data_start);
+        let mut node = ChunkNode {
+            id,
+            list_type: None,
+            offset: base + at as u64,
+            size,
+            available: available as u64,
+            padded: false,
+            children: Vec::new(),
+            problems: Vec::new(),
+        };
+        if !plausible(at) {
+            node.problems.push("id is not printable ASCII".to_string());
+        }
+        if available < size as usize {
+            let missing = size as usize - available;
+            node.problems
+                .push(format!("truncated, {missing} bytes missing"));
+        }
+
+        let data_end = data_start + available;
+        if &id == b"RIFF" || &id == b"LIST" {
+            if available < 4 {
+                node.problems
+                    .push("too short to hold a list type".to_string());
+            } else {
+                let list_type = &bytes[data_start..data_start + 4];
+                node.list_type = Some(list_type.try_into().unwrap());
+                node.children = walk(bytes, base, data_start + 4, data_end, &mut node.problems);
+            }
+        }
+
+        at = data_end;
+        if !size.is_multiple_of(2) && available == size as usize {
+            if at == end {
+                node.problems
+                    .push("missing pad byte at the end".to_string());
+            } else if plausible(at) && !plausible(at + 1) {
+                node.problems.push("missing pad byte".to_string());
+            } else {
+                node.padded = true;
+                if bytes[at] != 0 {
+                    node.problems
+                        .push(format!("pad byte is {:#04X}, not 0", bytes[at]));
+                }
+                at += 1;
+            }
+        }
+        nodes.push(node);
+    }
+    nodes
+}
+
+impl fmt::Display for ChunkTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_node(f: &mut fmt::Formatter<'_>, node: &ChunkNode, depth: usize) -> fmt::Result {
+            let indent = "  ".repeat(depth);
+            write!(f, "{indent}{}", node.id.escape_ascii())?;
+            if let Some(list_type) = node.list_type {
+                write!(f, " '{}'", list_type.escape_ascii())?;
+            }
+            write!(f, " at {:#X}, {} bytes", node.offset, node.size)?;
+            if node.padded {
+                f.write_str(" + pad")?;
+            }
+            writeln!(f)?;
+            for problem in &node.problems {
+                writeln!(f, "{indent}  ! {problem}")?;
+            }
+            for child in &node.children {
+                write_node(f, child, depth + 1)?;
+            }
+            Ok(())
+        }
+
+        for root in &self.nodes {
+            write_node(f, root, 0)?;
+        }
+        for problem in &self.problems {
+            writeln!(f, "! {problem}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,449 @@
+// Automatic repair of the defects `validate` finds most often
+//
+// Only fixes with one obvious answer are applied: counts and flags that can
+// be recomputed from the tables, headers that can be read off the frames, and
+// hotspots a single pixel past the edge. Anything that needs a judgement
+// call, such as a step naming a missing frame, is left for `validate`.
+use std::{
+    fmt::{self, Display},
+    io::{self, Cursor},
+};
+
+use crate::{
+    ani::{AniFile, AniFlags, AniFrame, AniHeader},
+    cur::{CursorFile, CursorFrame, payload_bit_count, payload_size},
+    size::FrameSize,
+    validate::{chunk_padding, directory_fields},
+};
+
+/// Which fixes `sanitize_with` applies; all are on by default
+#[derive(Debug, Clone)]
+pub struct SanitizeOptions {
+    /// Set the `anih` icon and sequence flags to match the frames and
+    /// sequence (ANI007, ANI008)
+    pub flags: bool,
+    /// Make the `anih` size and bit depth describe the first frame (ANI013)
+    pub header: bool,
+    /// Drop frame and step counts a decoded header declared that disagree
+    /// with the tables (ANI002, ANI003)
+    pub counts: bool,
+    /// Pad or truncate the rate table to one rate per step and replace zero
+    /// durations with the default rate (ANI005, ANI006)
+    pub rates: bool,
+    /// Pull hotspots one pixel past the right or bottom edge back inside
+    /// (CUR001)
+    pub hotspots: bool,
+    /// Make directory sizes match what the payload headers say (CUR002)
+    pub sizes: bool,
+    /// Re-encode files with unpadded odd-sized chunks (ANI011), see
+    /// `AniFile::sanitize_bytes`
+    pub padding: bool,
+    /// Zero reserved fields and recompute colors bytes (CUR011, CUR012,
+    /// CUR013), see `CursorFile::sanitize_bytes`
+    pub reserved: bool,
+    /// Write standard chunk ids in place of variants kept from a decoded
+    /// file (ANI014)
+    pub ids: bool,
+}
+
+impl Default for SanitizeOptions {
+    fn default() -> Self {
+        Self {
+            flags: true,
+            header: true,
+            counts: true,
+            rates: true,
+            hotspots: true,
+            sizes: true,
+            padding: true,
+            reserved: true,
+            ids: true,
+        }
+    }
+}
+
+/// One change `sanitize` or `downgrade` made, under the code of the issue
+/// it resolves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    pub code: &'static str,
+    pub frame: Option<usize>,
+    pub message: String,
+}
+
+impl Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fixed[{}]", self.code)?;
+        if let Some(frame) = self.frame {
+            write!(f, " frame {frame}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// What a sanitize pass changed
+#[derive(Debug, Clone, Default)]
+pub struct SanitizeReport {
+    pub fixes: Vec<Fix>,
+}
+
+impl SanitizeReport {
+    /// Whether nothing needed fixing
+    pub fn is_empty(&self) -> bool {
+        self.fixes.is_empty()
+    }
+
+    fn push(&mut self, code: &'static str, frame: Option<usize>, message: impl Into<String>) {
+        self.fixes.push(Fix {
+            code,
+            frame,
+            message: message.into(),
+        });
+    }
+}
+
+impl CursorFile {
+    /// Apply every safe fix, see `SanitizeOptions`
+    ///
+    /// ```
+    /// use std::fs::File;
+    ///
+    /// use cursor_handler::{CursorFile, FrameSize};
+    ///
+    /// let mut cursor = CursorFile::decode(File::open("assets/windows_theme/Link.cur")?)?;
+    /// let (width, height) = (cursor[0].size.width(), cursor[0].size.height());
+    /// let codes = |cursor: &CursorFile| -> Vec<_> {
+    ///     cursor.validate().iter().map(|issue| issue.code).collect()
+    /// };
+    /// assert!(!codes(&cursor).contains(&"CUR001"));
+    ///
+    /// // A hotspot one pixel out is pulled back in, one far out is left alone
+    /// cursor[0].hotspot.x = width as u16;
+    /// cursor[0].size = FrameSize::new(width + 8, height)?;
+    /// cursor.push_frame(cursor[0].clone());
+    /// let last = cursor.frames().count() - 1;
+    /// cursor[last].hotspot.y = 1000;
+    /// assert_eq!(codes(&cursor).iter().filter(|&&code| code == "CUR002").count(), 2);
+    ///
+    /// let report = cursor.sanitize();
+    /// assert_eq!(report.fixes.len(), 3);
+    /// assert_eq!(cursor[0].hotspot.x as u32, width - 1);
+    /// assert_eq!(cursor[0].size, FrameSize::new(width, height)?);
+    /// assert_eq!(codes(&cursor), ["CUR001"]);
+    /// assert!(cursor.sanitize().is_empty());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn sanitize(&mut self) -> SanitizeReport {
+        self.sanitize_with(&SanitizeOptions::default())
+    }
+
+    /// Apply the fixes `options` enables
+    pub fn sanitize_with(&mut self, options: &SanitizeOptions) -> SanitizeReport {
+        let mut report = SanitizeReport::default();
+        for (index, frame) in self.frames.iter_mut().enumerate() {
+            sanitize_frame(frame, Some(index), options, &mut report);
+        }
+        report
+    }
+
+    /// Decode `bytes` and sanitize, also reporting the directory fields that
+    /// encoding the result rewrites, see `CursorFile::validate_bytes`
+    pub fn sanitize_bytes(
+        bytes: &[u8],
+        options: &SanitizeOptions,
+    ) -> io::Result<(Self, SanitizeReport)> {
+        let mut cursor = Self::decode(Cursor::new(bytes))?;
+        let mut report = SanitizeReport::default();
+        if options.reserved {
+            let mut issues = Vec::new();
+            directory_fields(bytes, &mut issues);
+            for issue in issues {
+                let action = if issue.code == "CUR013" {
+                    "recomputed"
+                } else {
+                    "zeroed"
+                };
+                report.push(
+                    issue.code,
+                    issue.frame,
+                    format!("{}; {action}", issue.message),
+                );
+            }
+        }
+        report.fixes.extend(cursor.sanitize_with(options).fixes);
+        Ok((cursor, report))
+    }
+}
+
+/// Sizes run before hotspots, so a hotspot is judged against the real size
+fn sanitize_frame(
+    frame: &mut CursorFrame,
+    index: Option<usize>,
+    options: &SanitizeOptions,
+    report: &mut SanitizeReport,
+) {
+    if options.sizes
+        && let Some((width, height)) = payload_size(&frame.image_data)
+        && (width, height) != (frame.size.width(), frame.size.height())
+        && let Ok(size) = FrameSize::new(width, height)
+    {
+        report.push(
+            "CUR002",
+            index,
+            format!("directory size {} set to the payload's {size}", frame.size),
+        );
+        frame.size = size;
+    }
+
+    let (width, height) = (frame.size.width(), frame.size.height());
+    if options.hotspots && !frame.hotspot.is_within(width, height) {
+        let clamped = frame.hotspot.clamp_to(width, height);
+        let off_by_one =
+            u32::from(frame.hotspot.x) <= width && u32::from(frame.hotspot.y) <= height;
+        if off_by_one {
+            report.push(
+                "CUR001",
+                index,
+                format!("hotspot {} moved to {clamped}", frame.hotspot),
+            );
+            frame.hotspot = clamped;
+        }
+    }
+}
+
+impl AniFile {
+    /// Apply every safe fix, see `SanitizeOptions`
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::AniFile;
+    ///
+    /// // aero_busy.ani with the flags cleared and a header claiming 40 steps
+    /// // of 7x7 at 8 bits
+    /// let mut bytes = std::fs::read("assets/aero_busy.ani")?;
+    /// let anih = bytes.windows(4).position(|id| id == b"anih").unwrap() + 8;
+    /// for (field, value) in [(2, 40), (3, 7), (4, 7), (5, 8), (8, 0)] {
+    ///     bytes[anih + field * 4..][..4].copy_from_slice(&u32::to_le_bytes(value));
+    /// }
+    /// let mut ani = AniFile::decode(Cursor::new(bytes))?;
+    /// ani.rates = vec![3; 5];
+    /// ani.sequence.push(99);
+    /// let codes = |ani: &AniFile| -> Vec<_> {
+    ///     ani.validate().iter().map(|issue| issue.code).collect()
+    /// };
+    /// assert_eq!(codes(&ani), ["ANI003", "ANI004", "ANI005", "ANI007", "ANI008", "ANI013"]);
+    ///
+    /// let report = ani.sanitize();
+    /// let fixed: Vec<_> = report.fixes.iter().map(|fix| fix.code).collect();
+    /// assert_eq!(fixed, ["ANI007", "ANI008", "ANI013", "ANI003", "ANI005"]);
+    /// assert_eq!(ani.rates.len(), ani.sequence.len());
+    /// assert_eq!((ani.header.width(), ani.header.bit_count()), (64, 32));
+    ///
+    /// // A step naming a missing frame has no single right answer
+    /// assert_eq!(codes(&ani), ["ANI004"]);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn sanitize(&mut self) -> SanitizeReport {
+        self.sanitize_with(&SanitizeOptions::default())
+    }
+
+    /// Apply the fixes `options` enables
+    pub fn sanitize_with(&mut self, options: &SanitizeOptions) -> SanitizeReport {
+        let mut report = SanitizeReport::default();
+
+        if options.flags {
+            let mut flags = self.header.ani_flags();
+            if !flags.contains(AniFlags::FRAMES_ARE_ICONS)
+                && self.frames().all(AniFrame::is_container)
+            {
+                flags.insert(AniFlags::FRAMES_ARE_ICONS);
+                report.push("ANI007", None, "set the AF_ICON flag");
+            }
+            let custom_sequence = !self.sequence.iter().copied().eq(0..self.num_frames());
+            if custom_sequence && !flags.contains(AniFlags::HAS_SEQUENCE) {
+                flags.insert(AniFlags::HAS_SEQUENCE);
+                report.push("ANI008", None, "set the AF_SEQUENCE flag");
+            }
+            self.header.set_ani_flags(flags);
+        }
+
+        if options.header
+            && let Some((width, height, bit_count)) = self.header_disagreement()
+        {
+            report.push(
+                "ANI013",
+                None,
+                format!(
+                    "header {}x{} at {} bits set to {width}x{height} at {bit_count} bits",
+                    self.header.width, self.header.height, self.header.bit_count
+                ),
+            );
+            self.header.width = width;
+            self.header.height = height;
+            self.header.bit_count = bit_count;
+            self.header.planes = 1;
+        }
+
+        if options.counts {
+            let (frames, steps) = (self.num_frames(), self.num_steps());
+            if let Some(declared) = self.header.num_frames.take_if(|n| *n != frames) {
+                report.push(
+                    "ANI002",
+                    None,
+                    format!("dropped the declared {declared} frames"),
+                );
+            }
+            if let Some(declared) = self.header.num_steps.take_if(|n| *n != steps) {
+                report.push(
+                    "ANI003",
+                    None,
+                    format!("dropped the declared {declared} steps"),
+                );
+            }
+        }
+
+        if options.rates {
+            let default_rate = match self.header.default_rate {
+                0 => AniHeader::default().default_rate,
+                rate => rate,
+            };
+            if self.header.default_rate == 0 {
+                self.header.default_rate = default_rate;
+                report.push(
+                    "ANI006",
+                    None,
+                    format!("default rate 0 set to {default_rate}"),
+                );
+            }
+            let steps = self.sequence.len();
+            if !self.rates.is_empty() && self.rates.len() != steps {
+                report.push(
+                    "ANI005",
+                    None,
+                    format!("{} rates fitted to {steps} steps", self.rates.len()),
+                );
+                self.rates.resize(steps, default_rate);
+            }
+            let zeros = self.rates.iter().filter(|&&rate| rate == 0).count();
+            if zeros > 0 {
+                self.rates
+                    .iter_mut()
+                    .filter(|rate| **rate == 0)
+                    .for_each(|rate| *rate = default_rate);
+                report.push(
+                    "ANI006",
+                    None,
+                    format!("{zeros} zero rates set to {default_rate}"),
+                );
+            }
+        }
+
+        if options.ids {
+            for variant in self.id_variants.drain(..) {
+                report.push(
+                    "ANI014",
+                    None,
+                    format!(
+                        "'{}' written as '{}'",
+                        variant.raw.escape_ascii(),
+                        variant.id.escape_ascii()
+                    ),
+                );
+            }
+        }
+
+        if options.hotspots || options.sizes {
+            for (index, frame) in self.frames.iter_mut().enumerate() {
+                // Frames that aren't cursor containers stay as ANI010
+                let Ok(mut cursor) = CursorFile::decode(Cursor::new(&frame.image_data)) else {
+                    continue;
+                };
+                let before = report.fixes.len();
+                for embedded in &mut cursor.frames {
+                    sanitize_frame(embedded, Some(index), options, &mut report);
+                }
+                let mut image_data = Vec::new();
+                if report.fixes.len() == before || cursor.encode(&mut image_data).is_err() {
+                    report.fixes.truncate(before);
+                    continue;
+                }
+                frame.image_data = image_data;
+                if let Some(first) = cursor.frames.first() {
+                    frame.size = first.size;
+                    frame.hotspot = first.hotspot;
+                }
+            }
+        }
+
+        report
+    }
+
+    /// Decode `bytes` and sanitize, also reporting the chunk padding that
+    /// encoding the result restores
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::AniFile;
+    ///
+    /// // An odd-sized INFO list at the end, written without pad bytes
+    /// let mut ani = AniFile::decode(std::fs::File::open("assets/aero_busy.ani")?)?;
+    /// ani.info.clear();
+    /// let mut bytes = Cursor::new(Vec::new());
+    /// ani.encode(&mut bytes)?;
+    /// let mut bytes = bytes.into_inner();
+    /// bytes.extend_from_slice(b"LIST\x0f\0\0\0INFOINAM\x03\0\0\0abc");
+    /// let size = bytes.len() as u32 - 8;
+    /// bytes[4..8].copy_from_slice(&size.to_le_bytes());
+    /// let codes = |issues: Vec<cursor_handler::Issue>| -> Vec<_> {
+    ///     issues.iter().map(|issue| issue.code).collect()
+    /// };
+    /// assert_eq!(codes(AniFile::validate_bytes(&bytes)?), ["ANI011", "ANI011"]);
+    ///
+    /// let (sanitized, report) = AniFile::sanitize_bytes(&bytes, &Default::default())?;
+    /// assert_eq!(report.fixes.len(), 2);
+    /// assert_eq!(sanitized.title().as_deref(), Some("abc"));
+    /// let mut out = Cursor::new(Vec::new());
+    /// sanitized.encode(&mut out)?;
+    /// assert!(AniFile::validate_bytes(&out.into_inner())?.is_empty());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn sanitize_bytes(
+        bytes: &[u8],
+        options: &SanitizeOptions,
+    ) -> io::Result<(Self, SanitizeReport)> {
+        let mut ani = Self::decode(Cursor::new(bytes))?;
+        let mut report = SanitizeReport::default();
+        if options.padding
+            && let Some(body) = bytes.get(12..)
+        {
+            let mut issues = Vec::new();
+            chunk_padding(body, 12, &mut issues);
+            for issue in issues {
+                report.push(
+                    issue.code,
+                    None,
+                    format!("{}; pad byte restored", issue.message),
+                );
+            }
+        }
+        report.fixes.extend(ani.sanitize_with(options).fixes);
+        Ok((ani, report))
+    }
+
+    /// The size and bit depth the header should hold, when what it holds
+    /// is set but describes something other than the first frame
+    pub(crate) fn header_disagreement(&self) -> Option<(u32, u32, u32)> {
+        let first = self.frames.first()?;
+        let cursor = CursorFile::decode(Cursor::new(&first.image_data)).ok()?;
+        let bit_count = payload_bit_count(&cursor.frames.first()?.image_data);
+        let expected = (first.size.width(), first.size.height(), bit_count);
+
+        let header = &self.header;
+        let size_wrong =
+            header.width != 0 && (header.width, header.height) != (expected.0, expected.1);
+        let bits_wrong = header.bit_count != 0 && bit_count != 0 && header.bit_count != bit_count;
+        (size_wrong || bits_wrong).then_some(expected)
+    }
+}
@@ -0,0 +1,342 @@
+// Building a whole Windows cursor scheme from one config file
+//
+// A scheme config is a TOML file with the scheme's name and one table per
+// role, keyed by the role names of `convert::roles`:
+//
+//   name = "Ocean"
+//   missing = "related"      # omit, arrow or related, as in MissingRole
+//
+//   [roles.Arrow]
+//   source = "arrow.png"     # Built like a project cursor, with its keys
+//   sizes = [32, 48]
+//   hotspot = [2, 1]
+//
+//   [roles.Wait]
+//   source = "wait.gif"      # A .gif or a directory of .png frames makes
+//   rate = 4                 # an .ani
+//
+//   [roles.Hand]
+//   source = "hand.cur"      # .cur and .ani files are checked and copied
+//
+// Sources resolve against the config's directory. Each role's cursor is
+// written as `<Role>.cur` or `<Role>.ani` next to the install.inf.
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    ani::AniFile,
+    convert::{self, MissingRole, SchemePackage, SchemeRole},
+    cur::CursorFile,
+    project::{self, CursorEntry, EntryTable, HotspotValue, TargetFormat},
+};
+
+/// Where a role's cursor comes from
+#[derive(Debug, Clone)]
+pub enum RoleSource {
+    /// A .cur or .ani file, copied once it decodes
+    File(PathBuf),
+    /// An image, GIF or directory of frames, built as the entry says
+    Build(CursorEntry),
+}
+
+/// One `[roles.<Role>]` table of a scheme config
+#[derive(Debug, Clone)]
+pub struct RoleConfig {
+    pub role: &'static str,
+    pub source: RoleSource,
+}
+
+/// A parsed scheme config
+#[derive(Debug, Clone, Default)]
+pub struct SchemeConfig {
+    pub name: String,
+    /// What roles without a table get
+    pub missing: MissingRole,
+    /// In `convert::roles` order
+    pub roles: Vec<RoleConfig>,
+}
+
+/// What `SchemeConfig::build` wrote
+#[derive(Debug, Clone, Default)]
+pub struct SchemeBuildReport {
+    pub inf: PathBuf,
+    pub package: SchemePackage,
+    /// The cursor files, in role order
+    pub outputs: Vec<PathBuf>,
+    /// Roles left out of the scheme
+    pub missing: Vec<&'static str>,
+    /// Upscaled sources, and roles that fell back or were left out
+    pub warnings: Vec<String>,
+}
+
+impl SchemeConfig {
+    /// Read a scheme config
+    ///
+    /// ```
+    /// use cursor_handler::scheme::{RoleSource, SchemeConfig};
+    ///
+    /// let config = SchemeConfig::load("assets/scheme/scheme.toml")?;
+    /// let roles: Vec<_> = config.roles.iter().map(|role| role.role).collect();
+    /// assert_eq!(roles, ["Arrow", "AppStarting", "Wait", "Hand"]); // Scheme slot order
+    /// assert!(matches!(config.roles[3].source, RoleSource::File(_)));
+    ///
+    /// let out = std::env::temp_dir().join("cursor_handler_scheme_config_doc");
+    /// let report = config.build(&out)?;
+    /// assert_eq!(report.outputs.len(), 4);
+    /// assert!(report.missing.is_empty());
+    /// assert!(report.warnings.iter().any(|warning| warning.contains("'IBeam'")));
+    ///
+    /// let inf = std::fs::read_to_string(&report.inf)?;
+    /// assert!(inf.contains("SCHEME_NAME = \"Sample\""));
+    /// assert!(inf.contains("ibeam = \"Arrow.cur\""));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let in_file =
+            |err: io::Error| io::Error::new(err.kind(), format!("{}: {err}", path.display()));
+        let text = fs::read_to_string(path).map_err(in_file)?;
+        Self::parse(&text, path.parent().unwrap_or(Path::new(""))).map_err(in_file)
+    }
+
+    /// Parse config text, resolving sources against `base_dir`
+    ///
+    /// Unknown keys and roles are errors.
+    ///
+    /// ```
+    /// use cursor_handler::scheme::SchemeConfig;
+    ///
+    /// let text = "name = 'Ocean'\n[roles.hand]\nsource = 'hand.png'\nhotspot = [5, 0]\n";
+    /// let config = SchemeConfig::parse(text, "themes")?;
+    /// assert_eq!(config.roles[0].role, "Hand");
+    ///
+    /// let typo = text.replace("roles.hand", "roles.hands");
+    /// let err = SchemeConfig::parse(&typo, "themes").unwrap_err();
+    /// assert_eq!(err.to_string(), "unknown role 'hands'");
+    ///
+    /// let copied = "name = 'Ocean'\n[roles.Hand]\nsource = 'hand.cur'\nsizes = [32]\n";
+    /// let err = SchemeConfig::parse(copied, "themes").unwrap_err();
+    /// assert_eq!(err.to_string(), "role 'Hand': 'sizes' doesn't apply to a .cur source");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn parse(text: &str, base_dir: impl AsRef<Path>) -> io::Result<Self> {
+        let file: SchemeFile = project::from_toml(text)?;
+        let name = match file.name {
+            Some(name) if !name.is_empty() => name,
+            Some(_) => return Err(invalid_data("'name' must be a non-empty string")),
+            None => return Err(invalid_data("'name' is missing")),
+        };
+        let missing = match file.missing.as_deref() {
+            None => MissingRole::default(),
+            Some("omit") => MissingRole::Omit,
+            Some("arrow") => MissingRole::Arrow,
+            Some("related") => MissingRole::Related,
+            Some(_) => {
+                return Err(invalid_data(
+                    "'missing' must be \"omit\", \"arrow\" or \"related\"",
+                ));
+            }
+        };
+        let mut roles = Vec::new();
+        for (role, table) in file.roles {
+            roles.push(parse_role(&role, table, base_dir.as_ref())?);
+        }
+
+        let order = |role: &RoleConfig| {
+            convert::roles()
+                .iter()
+                .position(|known| known.name == role.role)
+        };
+        roles.sort_by_key(order);
+        if let Some(pair) = roles.windows(2).find(|pair| pair[0].role == pair[1].role) {
+            return Err(invalid_data(format!(
+                "role '{}' is defined twice",
+                pair[0].role
+            )));
+        }
+        Ok(Self {
+            name,
+            missing,
+            roles,
+        })
+    }
+
+    /// Build every role's cursor into `out_dir` and write the install.inf
+    ///
+    /// Roles without a table follow `missing`, each with a warning. Stops at
+    /// the first role that fails to build or whose file doesn't decode.
+    pub fn build(&self, out_dir: impl AsRef<Path>) -> io::Result<SchemeBuildReport> {
+        let out_dir = out_dir.as_ref();
+        fs::create_dir_all(out_dir)?;
+        let mut report = SchemeBuildReport::default();
+
+        let mut assigned: Vec<Option<SchemeRole>> = vec![None; convert::roles().len()];
+        for config in &self.roles {
+            let (path, source) = match &config.source {
+                RoleSource::File(source) => {
+                    let path = copy_cursor(config.role, source, out_dir)?;
+                    (path, source)
+                }
+                RoleSource::Build(entry) => {
+                    let built = entry.build(out_dir)?;
+                    report.warnings.extend(built.warnings);
+                    let path = built.outputs.into_iter().next().expect("one format");
+                    (path, &entry.source)
+                }
+            };
+            let index = convert::roles()
+                .iter()
+                .position(|role| role.name == config.role)
+                .expect("parsed roles are known");
+            assigned[index] = Some(SchemeRole {
+                role: config.role,
+                source: source.display().to_string(),
+                file: file_name(&path),
+                fallback: false,
+            });
+            report.outputs.push(path);
+        }
+
+        convert::fill_missing(&mut assigned, self.missing);
+        for (role, slot) in convert::roles().iter().zip(&assigned) {
+            match slot {
+                None => {
+                    report.missing.push(role.name);
+                    report.warnings.push(format!(
+                        "role '{}' has no source and is left out",
+                        role.name
+                    ));
+                }
+                Some(slot) if slot.fallback => report.warnings.push(format!(
+                    "role '{}' has no source, using {}",
+                    role.name, slot.file
+                )),
+                Some(_) => {}
+            }
+        }
+
+        report.package = SchemePackage {
+            name: self.name.clone(),
+            comment: format!("{}, built from a scheme config", self.name),
+            roles: assigned.into_iter().flatten().collect(),
+        };
+        report.inf = report.package.write_inf(out_dir)?;
+        Ok(report)
+    }
+}
+
+/// A scheme config as written
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SchemeFile {
+    name: Option<String>,
+    missing: Option<String>,
+    #[serde(default)]
+    roles: BTreeMap<String, RoleTable>,
+}
+
+/// One `[roles.<Role>]` table as written; only `source` for .cur and .ani
+/// files
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RoleTable {
+    source: String,
+    sizes: Option<Vec<u32>>,
+    hotspot: Option<HotspotValue>,
+    hotspot_normalized: Option<[f64; 2]>,
+    rate: Option<u32>,
+}
+
+fn parse_role(name: &str, table: RoleTable, base_dir: &Path) -> io::Result<RoleConfig> {
+    let role = convert::roles()
+        .iter()
+        .find(|role| role.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| invalid_data(format!("unknown role '{name}'")))?
+        .name;
+    let label = format!("role '{role}'");
+    let source = base_dir.join(&table.source);
+
+    let extension = source
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if extension == "cur" || extension == "ani" {
+        let given = [
+            ("sizes", table.sizes.is_some()),
+            ("hotspot", table.hotspot.is_some()),
+            ("hotspot_normalized", table.hotspot_normalized.is_some()),
+            ("rate", table.rate.is_some()),
+        ];
+        if let Some((key, _)) = given.iter().find(|(_, given)| *given) {
+            return Err(invalid_data(format!(
+                "{label}: '{key}' doesn't apply to a .{extension} source"
+            )));
+        }
+        return Ok(RoleConfig {
+            role,
+            source: RoleSource::File(source),
+        });
+    }
+
+    // The rest of the table is a project cursor entry
+    let format = if extension == "gif" || source.is_dir() {
+        TargetFormat::Ani
+    } else {
+        TargetFormat::Cur
+    };
+    let table = EntryTable {
+        name: Some(role.to_string()),
+        source: Some(table.source),
+        sizes: table.sizes.unwrap_or_default(),
+        hotspot: table.hotspot,
+        hotspot_normalized: table.hotspot_normalized,
+        rate: table.rate,
+        formats: vec![format.name().to_string()],
+        output: None,
+    };
+    let mut entry = project::entry_from_table(table, &label, base_dir)?;
+    entry.source = source;
+    Ok(RoleConfig {
+        role,
+        source: RoleSource::Build(entry),
+    })
+}
+
+/// Copy a .cur or .ani to `out_dir/<role>.<extension>` once it decodes
+fn copy_cursor(role: &str, source: &Path, out_dir: &Path) -> io::Result<PathBuf> {
+    let in_source = |err: io::Error| {
+        io::Error::new(
+            err.kind(),
+            format!("role '{role}': {}: {err}", source.display()),
+        )
+    };
+    let bytes = fs::read(source).map_err(in_source)?;
+    let extension = if bytes.starts_with(b"RIFF") {
+        AniFile::decode(Cursor::new(&bytes)).map_err(in_source)?;
+        "ani"
+    } else {
+        CursorFile::decode(Cursor::new(&bytes)).map_err(in_source)?;
+        "cur"
+    };
+    let path = out_dir.join(format!("{role}.{extension}"));
+    fs::write(&path, bytes)?;
+    Ok(path)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
@@ -0,0 +1,449 @@
+// Sprite sheets: every frame of an animation laid out on one image, with a
+// JSON manifest for what the image can't hold
+//
+//   {
+//     "cell": [32, 32],
+//     "columns": 4,
+//     "direction": "horizontal",
+//     "default_rate": 6,
+//     "frames": [
+//       {"x": 0, "y": 0, "hotspot": [16, 16]},
+//       ...
+//     ],
+//     "sequence": [0, 1, 2, 1],
+//     "rates": [6, 6, 12, 6]
+//   }
+//
+// `frames` holds one cell per stored frame, by its top-left pixel. An empty
+// or missing `sequence` plays the frames in order and `rates` is only written
+// when some step differs from `default_rate`.
+use std::io;
+
+use image::{RgbaImage, imageops};
+
+use crate::{
+    ani::AniFile,
+    generate::cursor_frame,
+    hotspot::{Hotspot, HotspotSpec},
+    transform::{fit_hotspot, fit_square},
+};
+
+/// The order cells fill the sheet in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum SheetDirection {
+    /// Left to right, then down a row
+    #[default]
+    Horizontal,
+    /// Top to bottom, then across a column
+    Vertical,
+}
+
+/// Options for `AniFile::to_sheet`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SheetOptions {
+    /// Columns of the grid; `None` puts every frame in one row, or with
+    /// `Vertical` in one column
+    pub columns: Option<u32>,
+    /// Render every frame at this square size, picking and scaling as
+    /// `AniFile::steps_at_size` does; `None` uses each frame's largest image,
+    /// at the top-left of a cell fitting the largest one
+    pub cell_size: Option<u32>,
+    pub direction: SheetDirection,
+}
+
+/// One frame's cell on a sheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct SheetCell {
+    pub x: u32,
+    pub y: u32,
+    /// Relative to the cell
+    #[cfg_attr(feature = "serde", serde(with = "hotspot_pair"))]
+    pub hotspot: Hotspot,
+}
+
+/// Everything about a sprite sheet animation except its pixels
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct SheetManifest {
+    /// Width and height of every cell
+    pub cell: (u32, u32),
+    pub columns: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub direction: SheetDirection,
+    pub default_rate: u32,
+    pub frames: Vec<SheetCell>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub sequence: Vec<u32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub rates: Vec<u32>,
+}
+
+impl SheetManifest {
+    /// The manifest of a strip without one: `frames` cells of one size in a
+    /// single row or column, each playing once for `rate` jiffies
+    ///
+    /// The cells have to come out square, which catches most wrong counts.
+    ///
+    /// ```
+    /// use cursor_handler::sheet::{SheetDirection, SheetManifest};
+    /// use cursor_handler::{Hotspot, HotspotSpec};
+    ///
+    /// let across = SheetDirection::Horizontal;
+    /// let strip = |frames| SheetManifest::strip((96, 32), frames, across, 6, HotspotSpec::Center);
+    /// let row = strip(3)?;
+    /// assert_eq!((row.cell, row.frames[2].x), ((32, 32), 64));
+    /// assert_eq!(row.frames[0].hotspot, Hotspot::new(16, 16));
+    ///
+    /// assert_eq!(
+    ///     strip(2).unwrap_err().to_string(),
+    ///     "a 96x32 strip doesn't split into 2 square frames; check the frame count"
+    /// );
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn strip(
+        (width, height): (u32, u32),
+        frames: u32,
+        direction: SheetDirection,
+        rate: u32,
+        hotspot: impl Into<HotspotSpec>,
+    ) -> io::Result<Self> {
+        if frames == 0 {
+            return Err(invalid_input("a strip needs frames"));
+        }
+        let (length, side) = match direction {
+            SheetDirection::Horizontal => (width, height),
+            SheetDirection::Vertical => (height, width),
+        };
+        if side == 0 || side.checked_mul(frames) != Some(length) {
+            return Err(invalid_input(format!(
+                "a {width}x{height} strip doesn't split into {frames} square frames; \
+                 check the frame count"
+            )));
+        }
+        let hotspot = hotspot.into().resolve(side, side);
+        if !hotspot.is_within(side, side) {
+            return Err(invalid_input(format!(
+                "hotspot {hotspot} is outside the {side}x{side} frames"
+            )));
+        }
+
+        let cells = (0..frames)
+            .map(|index| {
+                let (x, y) = match direction {
+                    SheetDirection::Horizontal => (index * side, 0),
+                    SheetDirection::Vertical => (0, index * side),
+                };
+                SheetCell { x, y, hotspot }
+            })
+            .collect();
+        Ok(Self {
+            cell: (side, side),
+            columns: match direction {
+                SheetDirection::Horizontal => frames,
+                SheetDirection::Vertical => 1,
+            },
+            direction,
+            default_rate: rate,
+            frames: cells,
+            sequence: Vec::new(),
+            rates: Vec::new(),
+        })
+    }
+
+    /// The manifest as pretty-printed JSON
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(self).expect("manifests always serialize");
+        json.push('\n');
+        json
+    }
+
+    /// Read a manifest `to_json` wrote; unknown keys are errors
+    ///
+    /// ```
+    /// use cursor_handler::sheet::{SheetDirection, SheetManifest};
+    /// use cursor_handler::HotspotSpec;
+    ///
+    /// let down = SheetDirection::Vertical;
+    /// let manifest = SheetManifest::strip((32, 96), 3, down, 6, HotspotSpec::TopLeft)?;
+    /// assert_eq!(SheetManifest::parse(&manifest.to_json())?, manifest);
+    ///
+    /// let typo = manifest.to_json().replace("\"columns\"", "\"colums\"");
+    /// let err = SheetManifest::parse(&typo).unwrap_err();
+    /// assert!(err.to_string().starts_with("unknown field `colums`"), "{err}");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn parse(text: &str) -> io::Result<Self> {
+        serde_json::from_str(text).map_err(io::Error::from)
+    }
+}
+
+/// A `Hotspot` as the `[x, y]` pair manifests hold
+#[cfg(feature = "serde")]
+mod hotspot_pair {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::hotspot::Hotspot;
+
+    pub fn serialize<S: Serializer>(hotspot: &Hotspot, serializer: S) -> Result<S::Ok, S::Error> {
+        [hotspot.x, hotspot.y].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Hotspot, D::Error> {
+        let [x, y] = <[u16; 2]>::deserialize(deserializer)?;
+        Ok(Hotspot::new(x, y))
+    }
+}
+
+impl AniFile {
+    /// Lay every stored frame out on one image, with the manifest that
+    /// `from_sheet` rebuilds the animation from
+    ///
+    /// The manifest's sequence and rates are the resolved `steps`, so the
+    /// rebuilt animation plays the same frames for the same durations.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::AniFile;
+    /// use cursor_handler::sheet::{SheetDirection, SheetOptions};
+    ///
+    /// let ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// let options = SheetOptions {
+    ///     columns: Some(6),
+    ///     cell_size: Some(32),
+    ///     direction: SheetDirection::Vertical,
+    /// };
+    /// let (sheet, manifest) = ani.to_sheet(&options)?;
+    /// assert_eq!(sheet.dimensions(), (6 * 32, 3 * 32));
+    /// // Down the first column, then the next
+    /// assert_eq!((manifest.frames[2].x, manifest.frames[2].y), (0, 64));
+    /// assert_eq!((manifest.frames[3].x, manifest.frames[3].y), (32, 0));
+    ///
+    /// let rebuilt = AniFile::from_sheet(&sheet, &manifest)?;
+    /// ani.check_sheet(&options, &rebuilt)?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn to_sheet(&self, options: &SheetOptions) -> io::Result<(RgbaImage, SheetManifest)> {
+        let cells = self.sheet_cells(options)?;
+        let cell = cells[0].0.dimensions();
+        let count = cells.len() as u32;
+        let columns = match options.columns {
+            Some(0) => return Err(invalid_input("a sheet needs at least one column")),
+            Some(columns) => columns.min(count),
+            None if options.direction == SheetDirection::Horizontal => count,
+            None => 1,
+        };
+        let rows = count.div_ceil(columns);
+
+        let mut sheet = RgbaImage::new(cell.0 * columns, cell.1 * rows);
+        let mut frames = Vec::with_capacity(cells.len());
+        for (index, (image, hotspot)) in cells.iter().enumerate() {
+            let index = index as u32;
+            let (column, row) = match options.direction {
+                SheetDirection::Horizontal => (index % columns, index / columns),
+                SheetDirection::Vertical => (index / rows, index % rows),
+            };
+            let (x, y) = (column * cell.0, row * cell.1);
+            imageops::replace(&mut sheet, image, x as i64, y as i64);
+            frames.push(SheetCell {
+                x,
+                y,
+                hotspot: *hotspot,
+            });
+        }
+
+        let default_rate = self.header.default_rate();
+        let steps: Vec<_> = self.steps().collect();
+        let rates = if steps.iter().all(|step| step.duration == default_rate) {
+            Vec::new()
+        } else {
+            steps.iter().map(|step| step.duration).collect()
+        };
+        let sequence: Vec<_> = steps.iter().map(|step| step.frame_index as u32).collect();
+        let sequence = if sequence.iter().copied().eq(0..count) {
+            Vec::new()
+        } else {
+            sequence
+        };
+        let manifest = SheetManifest {
+            cell,
+            columns,
+            direction: options.direction,
+            default_rate,
+            frames,
+            sequence,
+            rates,
+        };
+        Ok((sheet, manifest))
+    }
+
+    /// Rebuild an animation from a sheet and its manifest
+    ///
+    /// Each cell becomes a single-image frame. Cells outside the sheet,
+    /// hotspots outside their cell and sequence entries past the last frame
+    /// are errors.
+    pub fn from_sheet(sheet: &RgbaImage, manifest: &SheetManifest) -> io::Result<Self> {
+        let (width, height) = manifest.cell;
+        let mut ani = AniFile::empty().with_default_rate(manifest.default_rate)?;
+        for (index, cell) in manifest.frames.iter().enumerate() {
+            let fits = |at: u32, side: u32, limit: u32| {
+                at.checked_add(side).is_some_and(|end| end <= limit)
+            };
+            if !fits(cell.x, width, sheet.width()) || !fits(cell.y, height, sheet.height()) {
+                return Err(invalid_data(format!(
+                    "frame {index}'s {width}x{height} cell at ({}, {}) is outside the {}x{} sheet",
+                    cell.x,
+                    cell.y,
+                    sheet.width(),
+                    sheet.height()
+                )));
+            }
+            if !cell.hotspot.is_within(width, height) {
+                return Err(invalid_data(format!(
+                    "frame {index}'s hotspot {} is outside its {width}x{height} cell",
+                    cell.hotspot
+                )));
+            }
+            let image = imageops::crop_imm(sheet, cell.x, cell.y, width, height).to_image();
+            ani.push_frame(cursor_frame(&image, cell.hotspot)?);
+        }
+        if ani.frames.is_empty() {
+            return Err(invalid_data("the manifest lists no frames"));
+        }
+
+        if let Some(&index) = manifest
+            .sequence
+            .iter()
+            .find(|&&index| index as usize >= manifest.frames.len())
+        {
+            return Err(invalid_data(format!(
+                "the sequence shows frame {index}, but there are only {}",
+                manifest.frames.len()
+            )));
+        }
+        if !manifest.sequence.is_empty() {
+            ani.sequence = manifest.sequence.clone();
+        }
+        if !manifest.rates.is_empty() {
+            if manifest.rates.len() != ani.sequence.len() {
+                return Err(invalid_data(format!(
+                    "{} rates for {} steps",
+                    manifest.rates.len(),
+                    ani.sequence.len()
+                )));
+            }
+            ani.rates = manifest.rates.clone();
+        }
+        Ok(ani)
+    }
+
+    /// Check that `rebuilt`, read back from the sheet `to_sheet` made with
+    /// `options`, plays the same steps: the same frames, durations, hotspots
+    /// and pixels as the sheet shows them
+    ///
+    /// The error names the first step that differs.
+    pub fn check_sheet(&self, options: &SheetOptions, rebuilt: &AniFile) -> io::Result<()> {
+        let cells = self.sheet_cells(options)?;
+        let mut images = Vec::with_capacity(rebuilt.frames.len());
+        for frame in &rebuilt.frames {
+            images.push(frame.decode_image()?);
+        }
+
+        let (expected, actual): (Vec<_>, Vec<_>) =
+            (self.steps().collect(), rebuilt.steps().collect());
+        if expected.len() != actual.len() {
+            return Err(invalid_data(format!(
+                "the sheet plays {} steps instead of {}",
+                actual.len(),
+                expected.len()
+            )));
+        }
+        for (step, (expected, actual)) in expected.iter().zip(&actual).enumerate() {
+            let differs =
+                |what: &str| Err(invalid_data(format!("step {step}: the {what} differs")));
+            let (image, hotspot) = &cells[expected.frame_index];
+            if (expected.frame_index, expected.duration) != (actual.frame_index, actual.duration) {
+                return differs("frame or duration");
+            }
+            if *hotspot != actual.frame.hotspot {
+                return differs("hotspot");
+            }
+            if *image != images[actual.frame_index] {
+                return differs("image");
+            }
+        }
+        Ok(())
+    }
+
+    /// Each stored frame as it goes on a sheet, every image the cell's size
+    fn sheet_cells(&self, options: &SheetOptions) -> io::Result<Vec<(RgbaImage, Hotspot)>> {
+        if options.cell_size == Some(0) {
+            return Err(invalid_input("the cell size must be non-zero"));
+        }
+        let mut cells = Vec::with_capacity(self.frames.len());
+        for index in 0..self.frames.len() {
+            let cursor = self.extract_frame(index)?;
+            let frame = match options.cell_size {
+                Some(size) => cursor.best_frame(size),
+                None => cursor.largest_frame(),
+            }
+            .ok_or_else(|| invalid_data(format!("frame {index} is empty")))?;
+            let image = frame.decode_image()?;
+            cells.push(match options.cell_size {
+                Some(size) if image.dimensions() != (size, size) => {
+                    let hotspot = fit_hotspot(frame.hotspot, image.dimensions(), size);
+                    (fit_square(&image, size), hotspot)
+                }
+                _ => (image, frame.hotspot),
+            });
+        }
+        if cells.is_empty() {
+            return Err(invalid_input("no frames"));
+        }
+
+        let cell = match options.cell_size {
+            Some(size) => (size, size),
+            None => (
+                cells
+                    .iter()
+                    .map(|(image, _)| image.width())
+                    .max()
+                    .unwrap_or(1),
+                cells
+                    .iter()
+                    .map(|(image, _)| image.height())
+                    .max()
+                    .unwrap_or(1),
+            ),
+        };
+        for (image, _) in &mut cells {
+            if image.dimensions() != cell {
+                let mut canvas = RgbaImage::new(cell.0, cell.1);
+                imageops::replace(&mut canvas, image, 0, 0);
+                *image = canvas;
+            }
+        }
+        Ok(cells)
+    }
+}
+
+fn invalid_input(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
@@ -0,0 +1,168 @@
+// Frame dimensions, and how they are stored in a .cur/.ico directory
+use std::{
+    fmt::{self, Display},
+    io,
+};
+
+/// Largest side a directory entry can describe
+pub const MAX_DIR_SIZE: u32 = 256;
+
+/// Largest side `encode` writes and decoding takes from a payload header
+///
+/// Windows scales anything bigger down anyway, so a larger frame is far more
+/// likely a mistake than a cursor anyone meant to ship.
+///
+/// ```
+/// use cursor_handler::{CursorFile, CursorFrame, FrameSize, Hotspot};
+/// use image::{Rgba, RgbaImage};
+///
+/// let image = |side| RgbaImage::from_pixel(side, side, Rgba([0, 0, 255, 255]));
+///
+/// let frame = CursorFrame::from_image(&image(512), Hotspot::new(300, 40))?;
+/// let mut bytes = Vec::new();
+/// CursorFile::single(frame).encode(&mut bytes)?;
+/// assert_eq!(bytes[6..8], [0, 0]);
+/// let decoded = CursorFile::decode(std::io::Cursor::new(&bytes))?;
+/// assert_eq!(decoded[0].size, FrameSize::square(512)?);
+/// assert_eq!(decoded[0].hotspot, Hotspot::new(300, 40));
+/// assert!(decoded.validate().iter().all(|issue| issue.code != "CUR002"));
+///
+/// // The payload has to carry the size, and past the cap nothing is written
+/// let mut frame = CursorFrame::from_image(&image(300), Hotspot::default())?;
+/// frame.size = FrameSize::square(512)?;
+/// assert!(CursorFile::single(frame).encode(&mut Vec::new()).is_err());
+/// let frame = CursorFrame::from_image(&image(2048), Hotspot::default())?;
+/// assert!(CursorFile::single(frame).encode(&mut Vec::new()).is_err());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub const MAX_FRAME_SIZE: u32 = 1024;
+
+/// Square sides Windows ships cursors in, one per DPI step
+pub const STANDARD_SIZES: [u32; 8] = [16, 24, 32, 48, 64, 96, 128, 256];
+
+/// Which way `CursorFile::snap_to_standard_sizes` rounds an odd size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SnapPolicy {
+    /// The smallest standard size at least as large, so detail is only
+    /// ever scaled up
+    #[default]
+    NearestUp,
+    /// The largest standard size no larger, so nothing is upscaled
+    NearestDown,
+}
+
+impl SnapPolicy {
+    /// The standard side a frame whose longer side is `side` snaps to; past
+    /// either end of `STANDARD_SIZES` that end
+    ///
+    /// ```
+    /// use cursor_handler::size::SnapPolicy;
+    ///
+    /// assert_eq!(SnapPolicy::NearestUp.snap(40), 48);
+    /// assert_eq!(SnapPolicy::NearestDown.snap(40), 32);
+    /// assert_eq!((SnapPolicy::NearestUp.snap(300), SnapPolicy::NearestDown.snap(8)), (256, 16));
+    /// ```
+    pub fn snap(self, side: u32) -> u32 {
+        let snapped = match self {
+            Self::NearestUp => STANDARD_SIZES.iter().find(|&&size| size >= side),
+            Self::NearestDown => STANDARD_SIZES.iter().rev().find(|&&size| size <= side),
+        };
+        match (snapped, self) {
+            (Some(&size), _) => size,
+            (None, Self::NearestUp) => STANDARD_SIZES[STANDARD_SIZES.len() - 1],
+            (None, Self::NearestDown) => STANDARD_SIZES[0],
+        }
+    }
+}
+
+/// Width and height of a frame, both non-zero
+///
+/// A directory entry stores each side in one byte, with 0 meaning 256, so
+/// larger frames, up to `MAX_FRAME_SIZE`, store 0 and rely on the PNG or DIB
+/// header of their payload for the real size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FrameSize {
+    width: u32,
+    height: u32,
+}
+
+impl FrameSize {
+    pub fn new(width: u32, height: u32) -> io::Result<Self> {
+        if width == 0 || height == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{width}x{height} frame has no pixels"),
+            ));
+        }
+        Ok(Self { width, height })
+    }
+
+    /// A square frame of `side` pixels
+    pub fn square(side: u32) -> io::Result<Self> {
+        Self::new(side, side)
+    }
+
+    pub fn width(self) -> u32 {
+        self.width
+    }
+
+    pub fn height(self) -> u32 {
+        self.height
+    }
+
+    /// The longer side, used to pick the frame closest to a requested size
+    pub fn max_side(self) -> u32 {
+        self.width.max(self.height)
+    }
+
+    /// Whether the frame is square with a side from `STANDARD_SIZES`
+    pub fn is_standard(self) -> bool {
+        self.width == self.height && STANDARD_SIZES.contains(&self.width)
+    }
+
+    /// Whether a directory entry can describe this size
+    pub fn fits_directory(self) -> bool {
+        self.width <= MAX_DIR_SIZE && self.height <= MAX_DIR_SIZE
+    }
+
+    /// The width and height bytes of a directory entry, or `None` past 256
+    ///
+    /// ```
+    /// use cursor_handler::FrameSize;
+    ///
+    /// let dir_bytes = |side| FrameSize::square(side).unwrap().to_dir_bytes();
+    /// assert_eq!(dir_bytes(255), Some([255, 255]));
+    /// assert_eq!(dir_bytes(256), Some([0, 0]));
+    /// assert_eq!(dir_bytes(257), None);
+    /// assert_eq!(dir_bytes(512), None);
+    /// ```
+    pub fn to_dir_bytes(self) -> Option<[u8; 2]> {
+        let byte = |side: u32| match side {
+            MAX_DIR_SIZE => Some(0),
+            side => u8::try_from(side).ok(),
+        };
+        Some([byte(self.width)?, byte(self.height)?])
+    }
+
+    /// Read the width and height bytes of a directory entry
+    ///
+    /// ```
+    /// use cursor_handler::FrameSize;
+    ///
+    /// assert_eq!(FrameSize::from_dir_bytes([255, 0]), FrameSize::new(255, 256).unwrap());
+    /// ```
+    pub fn from_dir_bytes([width, height]: [u8; 2]) -> Self {
+        let side = |byte: u8| if byte == 0 { MAX_DIR_SIZE } else { byte as u32 };
+        Self {
+            width: side(width),
+            height: side(height),
+        }
+    }
+}
+
+impl Display for FrameSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
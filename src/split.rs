@@ -0,0 +1,222 @@
+// Splitting an animation into one .cur file per frame and joining it back
+//
+// The directory holds frame_000.cur, frame_001.cur, ... and manifest.json, a
+// JSON description of everything else:
+//
+//   {
+//     "frames": 3,
+//     "default_rate": 6,
+//     "sequence": [0, 1, 2, 1],
+//     "rates": [6, 6, 12, 6],
+//     "info": [{"id": "INAM", "value": [77, 121, 32, 99, 117, 114, 115, 111, 114, 0]}]
+//   }
+//
+// INFO values are byte arrays so any bytes survive.
+#[cfg(feature = "json")]
+use std::io;
+#[cfg(all(feature = "fs", feature = "json"))]
+use std::{fs, io::Cursor, path::Path};
+
+use crate::ani::{AniFile, InfoEntry};
+#[cfg(all(feature = "fs", feature = "json"))]
+use crate::{ani::AniFrame, cur::CursorFile};
+
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// File name of frame `index` in a split directory
+pub fn frame_file_name(index: usize) -> String {
+    format!("frame_{index:03}.cur")
+}
+
+/// Everything about a split animation except its frames
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct SplitManifest {
+    pub frames: usize,
+    pub default_rate: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sequence: Vec<u32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub rates: Vec<u32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty", with = "info_entries")
+    )]
+    pub info: Vec<InfoEntry>,
+}
+
+impl SplitManifest {
+    /// The manifest as pretty-printed JSON
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(self).expect("manifests always serialize");
+        json.push('\n');
+        json
+    }
+
+    /// Read a manifest `to_json` wrote; unknown keys are errors
+    ///
+    /// ```
+    /// use cursor_handler::{SplitManifest, ani::InfoEntry};
+    ///
+    /// let manifest = SplitManifest {
+    ///     frames: 2,
+    ///     default_rate: 6,
+    ///     sequence: vec![0, 1, 0],
+    ///     rates: Vec::new(),
+    ///     info: vec![InfoEntry::new(*b"INAM", [0xff, 0])],
+    /// };
+    /// assert_eq!(SplitManifest::parse(&manifest.to_json())?, manifest);
+    ///
+    /// let short_id = manifest.to_json().replace("INAM", "INA");
+    /// let err = SplitManifest::parse(&short_id).unwrap_err();
+    /// assert!(err.to_string().starts_with("INFO id must be 4 bytes"), "{err}");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn parse(text: &str) -> io::Result<Self> {
+        serde_json::from_str(text).map_err(io::Error::from)
+    }
+}
+
+/// `InfoEntry`s as `{"id": "INAM", "value": [bytes]}` objects
+#[cfg(feature = "serde")]
+mod info_entries {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error as _};
+
+    use crate::ani::InfoEntry;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Entry {
+        id: String,
+        value: Vec<u8>,
+    }
+
+    pub fn serialize<S: Serializer>(info: &[InfoEntry], serializer: S) -> Result<S::Ok, S::Error> {
+        info.iter()
+            .map(|entry| Entry {
+                id: String::from_utf8_lossy(&entry.id).into_owned(),
+                value: entry.value.clone(),
+            })
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<InfoEntry>, D::Error> {
+        Vec::<Entry>::deserialize(deserializer)?
+            .into_iter()
+            .map(|entry| {
+                let id: [u8; 4] = entry
+                    .id
+                    .as_bytes()
+                    .try_into()
+                    .map_err(|_| D::Error::custom("INFO id must be 4 bytes"))?;
+                Ok(InfoEntry::new(id, entry.value))
+            })
+            .collect()
+    }
+}
+
+impl AniFile {
+    /// Write every frame to `dir` as its own .cur, plus a manifest
+    ///
+    /// Frames are written as `extract_frame` returns them, so embedded
+    /// cursors keep all their sizes and hotspots. `dir` is created if needed.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::AniFile;
+    ///
+    /// let ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// let dir = std::env::temp_dir().join(format!("split-doctest-{}", std::process::id()));
+    /// ani.split_to_dir(&dir)?;
+    /// let joined = AniFile::join_from_dir(&dir)?;
+    /// std::fs::remove_dir_all(&dir)?;
+    ///
+    /// let stream = |ani: &AniFile| -> Vec<_> {
+    ///     ani.steps()
+    ///         .map(|step| (step.frame_index, step.duration, step.frame.image_data.clone()))
+    ///         .collect()
+    /// };
+    /// assert_eq!(stream(&joined), stream(&ani));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[cfg(all(feature = "fs", feature = "json"))]
+    pub fn split_to_dir(&self, dir: &Path) -> io::Result<SplitManifest> {
+        fs::create_dir_all(dir)?;
+        for index in 0..self.frames.len() {
+            let mut bytes = Vec::new();
+            self.extract_frame(index)?.encode(&mut bytes)?;
+            fs::write(dir.join(frame_file_name(index)), bytes)?;
+        }
+
+        let manifest = SplitManifest {
+            frames: self.frames.len(),
+            default_rate: self.header.default_rate(),
+            sequence: self.sequence.clone(),
+            rates: self.rates.clone(),
+            info: self.info.clone(),
+        };
+        fs::write(dir.join(MANIFEST_NAME), manifest.to_json())?;
+        Ok(manifest)
+    }
+
+    /// Rebuild an animation written by `split_to_dir`
+    ///
+    /// Sizes and hotspots are read from the .cur files, which may have been
+    /// edited since; their bytes are embedded unchanged. A frame file the
+    /// manifest expects but is missing, or one past the manifest's count, is
+    /// an error naming the file.
+    #[cfg(all(feature = "fs", feature = "json"))]
+    pub fn join_from_dir(dir: &Path) -> io::Result<AniFile> {
+        let manifest = SplitManifest::parse(&fs::read_to_string(dir.join(MANIFEST_NAME))?)?;
+
+        for entry in fs::read_dir(dir)? {
+            let name = entry?.file_name();
+            let Some(index) = name
+                .to_str()
+                .and_then(|name| name.strip_prefix("frame_")?.strip_suffix(".cur"))
+                .and_then(|index| index.parse::<usize>().ok())
+            else {
+                continue;
+            };
+            if index >= manifest.frames {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "{} is not in {MANIFEST_NAME}, which lists {} frames",
+                        name.to_string_lossy(),
+                        manifest.frames
+                    ),
+                ));
+            }
+        }
+
+        let mut frames = Vec::with_capacity(manifest.frames);
+        for index in 0..manifest.frames {
+            let name = frame_file_name(index);
+            let context = |err: io::Error| io::Error::new(err.kind(), format!("{name}: {err}"));
+
+            let bytes = fs::read(dir.join(&name)).map_err(context)?;
+            let cursor = CursorFile::decode(Cursor::new(&bytes)).map_err(context)?;
+            // Decoding an ANI reads the size and hotspot of the first entry
+            let first = &cursor.frames[0];
+            frames.push(AniFrame::new(first.size, first.hotspot, bytes, None));
+        }
+
+        let mut ani = AniFile::new(frames)
+            .with_sequence(manifest.sequence)
+            .with_rates(manifest.rates)
+            .with_default_rate(manifest.default_rate)?;
+        ani.info = manifest.info;
+        Ok(ani)
+    }
+}
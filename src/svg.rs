@@ -0,0 +1,147 @@
+// Animated SVG previews, for pages that can't show GIFs
+//
+// Each stored frame becomes one embedded PNG in `<defs>`, and every playback
+// step a `<use>` of it whose visibility a looping SMIL `<animate>` switches
+// on for its slice of the cycle. Only SVG 1.1 features are used.
+use std::{fmt::Write, io};
+
+use crate::{ani::AniFile, hotspot::Hotspot, optimize::encode_png};
+
+impl AniFile {
+    /// Render the animation as an SVG that loops forever
+    ///
+    /// Frames holding several resolutions give their largest, at the top-left
+    /// of a canvas fitting the largest frame. With `hotspot_marker` each frame
+    /// also shows a small crosshair at its hotspot.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::AniFile;
+    ///
+    /// let ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// let svg = ani.to_svg_animation(true)?;
+    /// assert!(svg.contains(r#"viewBox="0 0 64 64""#));
+    /// assert_eq!(svg.matches("<image ").count(), 18);
+    /// assert_eq!(svg.matches("<use ").count(), 18);
+    /// assert!(svg.contains(r#"dur="0.900s" repeatCount="indefinite""#));
+    ///
+    /// // Steps showing the same frame share its image
+    /// let mut ani = ani;
+    /// ani.sequence = vec![0, 1, 0, 1];
+    /// ani.rates.clear();
+    /// let svg = ani.to_svg_animation(false)?;
+    /// assert_eq!(svg.matches("<image ").count(), 2);
+    /// assert_eq!(svg.matches("<use ").count(), 4);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn to_svg_animation(&self, hotspot_marker: bool) -> io::Result<String> {
+        let steps: Vec<_> = self.steps().collect();
+        let total: u64 = steps.iter().map(|step| u64::from(step.duration)).sum();
+        if total == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "No steps with a duration",
+            ));
+        }
+
+        // Only frames the sequence shows are embedded, each once
+        let mut used: Vec<usize> = steps.iter().map(|step| step.frame_index).collect();
+        used.sort_unstable();
+        used.dedup();
+
+        let mut defs = String::new();
+        let (mut width, mut height) = (1, 1);
+        for &index in &used {
+            let cursor = self.extract_frame(index)?;
+            let frame = cursor
+                .frames()
+                .max_by_key(|frame| frame.size.max_side())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Frame {index} is empty"),
+                    )
+                })?;
+            let png = match frame.png_data() {
+                Some(png) => png.to_vec(),
+                None => encode_png(&frame.decode_image()?)?,
+            };
+            let (w, h) = (frame.size.width(), frame.size.height());
+            width = width.max(w);
+            height = height.max(h);
+
+            let _ = write!(
+                defs,
+                "<g id=\"f{index}\"><image width=\"{w}\" height=\"{h}\" \
+                 xlink:href=\"data:image/png;base64,{}\"/>",
+                base64(&png)
+            );
+            if hotspot_marker {
+                defs.push_str(&marker(frame.hotspot));
+            }
+            defs.push_str("</g>\n");
+        }
+
+        let mut svg = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <svg xmlns=\"http://www.w3.org/2000/svg\" \
+             xmlns:xlink=\"http://www.w3.org/1999/xlink\" version=\"1.1\" \
+             width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n\
+             <defs>\n{defs}</defs>\n"
+        );
+        // Durations are in jiffies, 1/60 s
+        let cycle = format!("{:.3}s", total as f64 / 60.0);
+        let mut begin = 0;
+        for step in &steps {
+            let end = begin + u64::from(step.duration);
+            // A discrete animation holds each value from its key time to the
+            // next, so the step is visible between `begin` and `end`
+            let _ = writeln!(
+                svg,
+                "<use xlink:href=\"#f{}\" visibility=\"hidden\">\
+                 <animate attributeName=\"visibility\" values=\"hidden;visible;hidden\" \
+                 keyTimes=\"0;{:.6};{:.6}\" calcMode=\"discrete\" dur=\"{cycle}\" \
+                 repeatCount=\"indefinite\"/></use>",
+                step.frame_index,
+                begin as f64 / total as f64,
+                end as f64 / total as f64,
+            );
+            begin = end;
+        }
+        svg.push_str("</svg>\n");
+        Ok(svg)
+    }
+}
+
+/// A crosshair centred on the hotspot pixel
+fn marker(hotspot: Hotspot) -> String {
+    let (x, y) = (hotspot.x as f32 + 0.5, hotspot.y as f32 + 0.5);
+    format!(
+        "<path d=\"M{} {y}H{}M{x} {}V{}\" stroke=\"#f0f\" stroke-width=\"1\" fill=\"none\"/>",
+        x - 3.0,
+        x + 3.0,
+        y - 3.0,
+        y + 3.0
+    )
+}
+
+/// Standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(group >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
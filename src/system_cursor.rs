@@ -0,0 +1,116 @@
+// The fifteen Windows cursor roles and the ids and names Windows knows them by
+//
+// Kept apart from `windows` so the mapping builds, and is tested, on every
+// platform and without the `windows-apply` feature.
+
+/// A system cursor role, in the slot order of a Windows cursor scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SystemCursor {
+    Arrow,
+    Help,
+    AppStarting,
+    Wait,
+    Crosshair,
+    Text,
+    Handwriting,
+    No,
+    SizeNS,
+    SizeWE,
+    SizeNWSE,
+    SizeNESW,
+    SizeAll,
+    UpArrow,
+    Hand,
+}
+
+impl SystemCursor {
+    /// Every role, in scheme slot order
+    pub const ALL: [SystemCursor; 15] = [
+        Self::Arrow,
+        Self::Help,
+        Self::AppStarting,
+        Self::Wait,
+        Self::Crosshair,
+        Self::Text,
+        Self::Handwriting,
+        Self::No,
+        Self::SizeNS,
+        Self::SizeWE,
+        Self::SizeNWSE,
+        Self::SizeNESW,
+        Self::SizeAll,
+        Self::UpArrow,
+        Self::Hand,
+    ];
+
+    /// The `OCR_*` id passed to SetSystemCursor
+    ///
+    /// ```
+    /// use std::collections::HashSet;
+    ///
+    /// use cursor_handler::system_cursor::SystemCursor;
+    ///
+    /// assert_eq!(SystemCursor::Arrow.ocr_id(), 32512); // OCR_NORMAL
+    /// assert_eq!(SystemCursor::Text.ocr_id(), 32513); // OCR_IBEAM
+    /// assert_eq!(SystemCursor::Hand.ocr_id(), 32649); // OCR_HAND
+    /// let ids: HashSet<_> = SystemCursor::ALL.iter().map(|role| role.ocr_id()).collect();
+    /// assert_eq!(ids.len(), 15);
+    /// ```
+    pub fn ocr_id(self) -> u32 {
+        match self {
+            Self::Arrow => 32512,
+            Self::Text => 32513,
+            Self::Wait => 32514,
+            Self::Crosshair => 32515,
+            Self::UpArrow => 32516,
+            Self::Handwriting => 32631,
+            Self::SizeNWSE => 32642,
+            Self::SizeNESW => 32643,
+            Self::SizeWE => 32644,
+            Self::SizeNS => 32645,
+            Self::SizeAll => 32646,
+            Self::No => 32648,
+            Self::Hand => 32649,
+            Self::AppStarting => 32650,
+            Self::Help => 32651,
+        }
+    }
+
+    /// The value name under `HKCU\Control Panel\Cursors`
+    pub fn registry_name(self) -> &'static str {
+        match self {
+            Self::Arrow => "Arrow",
+            Self::Help => "Help",
+            Self::AppStarting => "AppStarting",
+            Self::Wait => "Wait",
+            Self::Crosshair => "Crosshair",
+            Self::Text => "IBeam",
+            Self::Handwriting => "NWPen",
+            Self::No => "No",
+            Self::SizeNS => "SizeNS",
+            Self::SizeWE => "SizeWE",
+            Self::SizeNWSE => "SizeNWSE",
+            Self::SizeNESW => "SizeNESW",
+            Self::SizeAll => "SizeAll",
+            Self::UpArrow => "UpArrow",
+            Self::Hand => "Hand",
+        }
+    }
+
+    /// Look a role up by its registry value name, ignoring case
+    ///
+    /// ```
+    /// use cursor_handler::system_cursor::SystemCursor;
+    ///
+    /// assert_eq!(SystemCursor::from_registry_name("ibeam"), Some(SystemCursor::Text));
+    /// assert_eq!(SystemCursor::from_registry_name("Text"), None);
+    /// for role in SystemCursor::ALL {
+    ///     assert_eq!(SystemCursor::from_registry_name(role.registry_name()), Some(role));
+    /// }
+    /// ```
+    pub fn from_registry_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|role| role.registry_name().eq_ignore_ascii_case(name))
+    }
+}
@@ -0,0 +1,959 @@
+// Throwaway cursors for tests, well-formed and deliberately broken
+use std::{
+    fmt::Write as _,
+    io::{self, Cursor},
+};
+
+use image::{Rgba, RgbaImage};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ani::{AF_SEQUENCE, AniFile, InfoEntry, UnknownChunk},
+    cur::{CursorFile, CursorFrame},
+    decode::DecodeOptions,
+    diff,
+    generate::cursor_frame,
+    hotspot::Hotspot,
+    optimize::encode_png,
+    riff::{ChunkReader, ChunkWriter, FourCC},
+    size::FrameSize,
+};
+
+/// Where `fixtures` are committed, relative to the crate root
+pub const FIXTURE_DIR: &str = "tests/fixtures";
+
+/// Name of the `sha256sum`-style manifest in `FIXTURE_DIR`
+pub const FIXTURE_MANIFEST: &str = "SHA256SUMS";
+
+/// What `synthetic_cur` draws
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GlyphStyle {
+    /// The whole frame in one opaque colour
+    #[default]
+    Solid,
+    /// Opaque and transparent squares of an eighth of the side each
+    Checker,
+    /// A right triangle with its tip on the hotspot, transparent elsewhere
+    Arrow,
+}
+
+/// Where every synthetic frame of `side` pixels has its hotspot, (4, 4) at
+/// 32px
+pub fn synthetic_hotspot(side: u32) -> Hotspot {
+    let offset = (side / 8).min(u16::MAX as u32) as u16;
+    Hotspot::new(offset, offset)
+}
+
+/// A cursor with one frame per entry of `sizes`, in that order, each
+/// drawing `style` in black with its hotspot at `synthetic_hotspot`
+///
+/// Frames below 256px are 32-bit DIBs and larger ones PNG. The cursor
+/// validates without errors, and decoding its encoding and encoding again
+/// gives the same bytes.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::test_utils::{
+///     GlyphStyle, assert_cursor_bytes_eq, synthetic_cur, synthetic_hotspot,
+/// };
+/// use cursor_handler::{CursorFile, Severity};
+///
+/// for style in [GlyphStyle::Solid, GlyphStyle::Checker, GlyphStyle::Arrow] {
+///     let cursor = synthetic_cur(&[32, 48, 256], style)?;
+///     assert_eq!(cursor[1].hotspot, synthetic_hotspot(48));
+///     assert!(cursor.validate().iter().all(|issue| issue.severity != Severity::Error));
+///
+///     let mut bytes = Vec::new();
+///     cursor.encode(&mut bytes)?;
+///     let mut again = Vec::new();
+///     CursorFile::decode(Cursor::new(&bytes))?.encode(&mut again)?;
+///     assert_cursor_bytes_eq(&bytes, &again);
+/// }
+/// assert!(synthetic_cur(&[0], GlyphStyle::Solid).is_err());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn synthetic_cur(sizes: &[u32], style: GlyphStyle) -> io::Result<CursorFile> {
+    let frames = sizes
+        .iter()
+        .map(|&side| {
+            let image = glyph(side, style, Rgba([0, 0, 0, 255]));
+            CursorFrame::from_image(&image, synthetic_hotspot(side))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    Ok(CursorFile::new(frames))
+}
+
+/// An animation of `frames` solid `size` px frames, each a different colour,
+/// playing in order for `rate` jiffies each with the hotspot at
+/// `synthetic_hotspot`
+///
+/// It validates without errors, and decoding its encoding and encoding again
+/// gives the same bytes.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::test_utils::{assert_cursor_bytes_eq, assert_steps_eq, synthetic_ani};
+/// use cursor_handler::{AniFile, Hotspot, Severity};
+///
+/// let ani = synthetic_ani(3, 32, 6)?;
+/// assert_eq!((ani.num_frames(), ani.uniform_hotspot()), (3, Some(Hotspot::new(4, 4))));
+/// assert!(ani.steps().all(|step| step.duration == 6));
+/// let colors: Vec<_> = ani
+///     .frames()
+///     .map(|frame| frame.decode_image().map(|image| *image.get_pixel(0, 0)))
+///     .collect::<std::io::Result<_>>()?;
+/// assert!(colors[0] != colors[1] && colors[1] != colors[2] && colors[0] != colors[2]);
+/// assert!(ani.validate().iter().all(|issue| issue.severity != Severity::Error));
+///
+/// let encode = |ani: &AniFile| -> std::io::Result<Vec<u8>> {
+///     let mut bytes = Cursor::new(Vec::new());
+///     ani.encode(&mut bytes)?;
+///     Ok(bytes.into_inner())
+/// };
+/// let bytes = encode(&ani)?;
+/// let decoded = AniFile::decode(Cursor::new(&bytes))?;
+/// assert_steps_eq(&ani, &decoded);
+/// assert_cursor_bytes_eq(&bytes, &encode(&decoded)?);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn synthetic_ani(frames: u32, size: u32, rate: u32) -> io::Result<AniFile> {
+    let frames = (0..frames)
+        .map(|index| {
+            // 97 and 57 are odd, so the first 256 frames all differ
+            let [r, g] = [index * 97, index * 57 + 128].map(|c| c as u8);
+            let image = glyph(size, GlyphStyle::Solid, Rgba([r, g, 160, 255]));
+            cursor_frame(&image, synthetic_hotspot(size))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    AniFile::new(frames).with_default_rate(rate)
+}
+
+/// A frame holding `image` as PNG at any size, where
+/// `CursorFrame::from_image` would store one below 256px as a DIB
+///
+/// ```
+/// use cursor_handler::Hotspot;
+/// use cursor_handler::test_utils::png_frame;
+/// use image::{Rgba, RgbaImage};
+///
+/// let image = RgbaImage::from_pixel(4, 2, Rgba([0, 0, 255, 255]));
+/// let frame = png_frame(&image, Hotspot::new(1, 1))?;
+/// assert!(frame.is_png());
+/// assert_eq!(frame.decode_image()?, image);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn png_frame(image: &RgbaImage, hotspot: Hotspot) -> io::Result<CursorFrame> {
+    let size = FrameSize::new(image.width(), image.height())?;
+    Ok(CursorFrame::new(size, hotspot, encode_png(image)?))
+}
+
+/// The first `at` bytes of `bytes`, as a file cut short in transit
+///
+/// Decoding fails for any cut inside the header or the frames; in lenient
+/// mode a cut past the directory may still decode, with warnings.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::test_utils::{GlyphStyle, synthetic_cur, truncated};
+/// use cursor_handler::CursorFile;
+///
+/// let mut bytes = Vec::new();
+/// synthetic_cur(&[32], GlyphStyle::Arrow)?.encode(&mut bytes)?;
+/// for at in [0, 5, 21, bytes.len() - 1] {
+///     assert!(CursorFile::decode(Cursor::new(truncated(&bytes, at))).is_err());
+/// }
+/// assert_eq!(truncated(&bytes, bytes.len() + 10), bytes);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn truncated(bytes: &[u8], at: usize) -> Vec<u8> {
+    bytes[..at.min(bytes.len())].to_vec()
+}
+
+/// `ani` encoded with its last step pointing one past the last frame
+///
+/// The file decodes, keeping the bad step, and `validate` reports ANI004.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::AniFile;
+/// use cursor_handler::test_utils::{synthetic_ani, with_bad_seq};
+///
+/// let bytes = with_bad_seq(&synthetic_ani(3, 32, 6)?)?;
+/// let decoded = AniFile::decode(Cursor::new(bytes))?;
+/// assert_eq!(decoded.sequence, [0, 1, 3]);
+/// assert!(decoded.validate().iter().any(|issue| issue.code == "ANI004"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn with_bad_seq(ani: &AniFile) -> io::Result<Vec<u8>> {
+    let mut sequence = ani.sequence.clone();
+    let missing = ani.num_frames();
+    match sequence.last_mut() {
+        Some(last) => *last = missing,
+        None => sequence.push(missing),
+    }
+    // The encoder refuses the bad step, so the table is swapped in after
+    let mut bytes = Cursor::new(Vec::new());
+    ani.encode(&mut bytes)?;
+    let bytes = bytes.into_inner();
+    let mut input = Cursor::new(&bytes);
+    input.set_position(12); // Past the RIFF header and ACON
+    let mut reader = ChunkReader::new(input)?;
+    let mut out = Cursor::new(Vec::new());
+    let mut riff = ChunkWriter::new(&mut out);
+    riff.begin_list(*b"RIFF", *b"ACON")?;
+    while let Some(chunk) = reader.next_chunk()? {
+        let id = chunk.id();
+        let mut data = chunk.read_data()?;
+        match &id {
+            b"seq " => continue,
+            b"anih" => {
+                // Flags are the last of the nine fields
+                let flags = u32::from_le_bytes(data[32..36].try_into().unwrap()) | AF_SEQUENCE;
+                data[32..36].copy_from_slice(&flags.to_le_bytes());
+                riff.write_chunk(id, &data)?;
+                let table = sequence.iter().flat_map(|index| index.to_le_bytes());
+                riff.write_chunk(*b"seq ", &table.collect::<Vec<_>>())?;
+            }
+            _ => riff.write_chunk(id, &data)?,
+        }
+    }
+    riff.finish()?;
+    Ok(out.into_inner())
+}
+
+/// `cursor` encoded with the first directory entry claiming a side one pixel
+/// larger than its payload's (wrapping 255 to 0, which reads as 256)
+///
+/// The file decodes with the directory's size, and `validate` reports
+/// CUR002 for the payload that disagrees.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::test_utils::{GlyphStyle, synthetic_cur, with_lying_directory};
+/// use cursor_handler::{CursorFile, FrameSize};
+///
+/// let cursor = synthetic_cur(&[32], GlyphStyle::Checker)?;
+/// let bytes = with_lying_directory(&cursor)?;
+/// assert_eq!(bytes[6..8], [33, 33]);
+/// let decoded = CursorFile::decode(Cursor::new(&bytes))?;
+/// assert_eq!(decoded[0].size, FrameSize::square(33)?);
+/// assert!(decoded.validate().iter().any(|issue| issue.code == "CUR002"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn with_lying_directory(cursor: &CursorFile) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    cursor.encode(&mut bytes)?;
+    // Width and height bytes of the first 16-byte entry after the 6-byte header
+    for side in bytes.get_mut(6..8).into_iter().flatten() {
+        *side = side.wrapping_add(1);
+    }
+    Ok(bytes)
+}
+
+/// `cursor` encoded with the first directory entry's size `by` bytes off
+/// from its payload's: a positive `by` pads the payload with that many
+/// zeros, a negative one declares fewer bytes than the image needs
+///
+/// Both decode, with a warning at the payload, and strict decoding fails.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use cursor_handler::test_utils::{GlyphStyle, synthetic_cur, with_misdeclared_payload};
+/// use cursor_handler::{CursorFile, DecodeOptions};
+///
+/// let cursor = synthetic_cur(&[32], GlyphStyle::Checker)?;
+/// let padded = CursorFile::decode(Cursor::new(with_misdeclared_payload(&cursor, 8)?))?;
+/// assert_eq!(padded[0].image_data.len(), cursor[0].image_data.len() + 8);
+/// let short = with_misdeclared_payload(&cursor, -8)?;
+/// assert!(!CursorFile::decode(Cursor::new(&short))?[0].payload_len_consistent());
+/// assert!(CursorFile::decode_with(Cursor::new(&short), &DecodeOptions::strict()).is_err());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn with_misdeclared_payload(cursor: &CursorFile, by: i32) -> io::Result<Vec<u8>> {
+    let mut cursor = cursor.clone();
+    if by > 0 {
+        cursor[0].image_data.extend(vec![0; by as usize]);
+    }
+    let mut bytes = Vec::new();
+    cursor.encode(&mut bytes)?;
+    if by < 0 {
+        // The size is the third field of the entry, after width, height,
+        // colour count, a reserved byte and the hotspot
+        let size = u32::from_le_bytes(bytes[14..18].try_into().unwrap());
+        let declared = size.saturating_sub(by.unsigned_abs());
+        bytes[14..18].copy_from_slice(&declared.to_le_bytes());
+    }
+    Ok(bytes)
+}
+
+/// Every fixture file, as a path under `FIXTURE_DIR` and its bytes
+///
+/// The same bytes come out on every run and platform: nothing is random and
+/// every encoder involved is deterministic. `gen-fixtures` writes them with
+/// a `manifest`, and tests/fixtures_current.rs checks the committed copies
+/// against a fresh build.
+///
+/// ```
+/// use cursor_handler::test_utils::{fixtures, manifest};
+///
+/// let fixtures = fixtures()?;
+/// assert!(fixtures.iter().any(|(path, _)| *path == "normal/arrow.cur"));
+/// assert_eq!(manifest(&fixtures).lines().count(), fixtures.len());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn fixtures() -> io::Result<Vec<(&'static str, Vec<u8>)>> {
+    let cur = |cursor: &CursorFile| -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        cursor.encode(&mut bytes)?;
+        Ok(bytes)
+    };
+    let ani = |ani: &AniFile| -> io::Result<Vec<u8>> {
+        let mut bytes = Cursor::new(Vec::new());
+        ani.encode(&mut bytes)?;
+        Ok(bytes.into_inner())
+    };
+
+    let arrow = synthetic_cur(&[32, 48], GlyphStyle::Arrow)?;
+    let spinner = synthetic_ani(4, 32, 6)?;
+    let dib = |bits: u16| -> io::Result<Vec<u8>> {
+        let size = FrameSize::square(32)?;
+        let payload = encode_dib(&two_tone(32), bits)?;
+        cur(&CursorFile::single(CursorFrame::new(
+            size,
+            synthetic_hotspot(32),
+            payload,
+        )))
+    };
+    let png_32 = {
+        let png = encode_png(&glyph(32, GlyphStyle::Arrow, Rgba([0, 0, 0, 255])))?;
+        CursorFile::single(CursorFrame::new(
+            FrameSize::square(32)?,
+            synthetic_hotspot(32),
+            png,
+        ))
+    };
+
+    let mut odd_info = spinner.clone();
+    odd_info.info = vec![
+        InfoEntry::new(*b"INAM", *b"Spin\0"),
+        InfoEntry::new(*b"IART", *b"A\0"),
+    ];
+    let mut junk = spinner.clone();
+    junk.unknown_chunks.push(UnknownChunk {
+        id: *b"JUNK",
+        data: b"padding".to_vec(),
+        before_frame: 1,
+    });
+    let mut lying_anih = ani(&spinner)?;
+    // nFrames is the second field of the anih data, after RIFF, ACON and
+    // the anih chunk header
+    lying_anih[24..28].copy_from_slice(&(spinner.num_frames() + 2).to_le_bytes());
+    let mut sequenced = spinner
+        .clone()
+        .with_sequence(vec![0, 1, 2, 3, 2, 1])
+        .with_rates(vec![6, 6, 12, 12, 6, 6]);
+    sequenced.info = vec![InfoEntry::new(*b"INAM", *b"Order\0")];
+    let sequenced_bytes = ani(&sequenced)?;
+    let reversed = spinner.clone().with_sequence(vec![3, 2, 1, 0]);
+    let mut padded_icon = spinner.clone();
+    if let Some(frame) = padded_icon.frames_mut().nth(1) {
+        frame.image_data.extend([0; 16]);
+    }
+
+    let arrow_bytes = cur(&arrow)?;
+    let spinner_bytes = ani(&spinner)?;
+    Ok(vec![
+        ("normal/arrow.cur", arrow_bytes.clone()),
+        ("normal/spinner.ani", spinner_bytes.clone()),
+        ("dib/1bit.cur", dib(1)?),
+        ("dib/4bit.cur", dib(4)?),
+        ("dib/8bit.cur", dib(8)?),
+        ("dib/24bit.cur", dib(24)?),
+        ("png/arrow_32.cur", cur(&png_32)?),
+        (
+            "png/arrow_256.cur",
+            cur(&synthetic_cur(&[256], GlyphStyle::Arrow)?)?,
+        ),
+        ("metadata/odd_info.ani", ani(&odd_info)?),
+        (
+            "truncated/arrow.cur",
+            truncated(&arrow_bytes, arrow_bytes.len() / 2),
+        ),
+        (
+            "truncated/spinner.ani",
+            truncated(&spinner_bytes, spinner_bytes.len() - 7),
+        ),
+        ("lying/directory.cur", with_lying_directory(&arrow)?),
+        ("lying/anih_frames.ani", lying_anih),
+        ("lying/bad_seq.ani", with_bad_seq(&spinner)?),
+        (
+            "lying/payload_over.cur",
+            with_misdeclared_payload(&png_32, 16)?,
+        ),
+        (
+            "lying/payload_under.cur",
+            with_misdeclared_payload(&synthetic_cur(&[32], GlyphStyle::Arrow)?, -64)?,
+        ),
+        ("lying/icon_over.ani", ani(&padded_icon)?),
+        (
+            "riff/multi_list.ani",
+            split_frame_list(&ani(&reversed)?, 2)?,
+        ),
+        ("riff/junk.ani", ani(&junk)?),
+        ("riff/ordered.ani", sequenced_bytes.clone()),
+        (
+            "riff/frames_first.ani",
+            reorder_chunks(
+                &sequenced_bytes,
+                &[*b"fram", *b"INFO", *b"anih", *b"seq ", *b"rate"],
+            )?,
+        ),
+        (
+            "riff/rate_before_seq.ani",
+            reorder_chunks(
+                &sequenced_bytes,
+                &[*b"rate", *b"seq ", *b"anih", *b"INFO", *b"fram"],
+            )?,
+        ),
+        ("cursorfx/tiny.CursorFX", cursorfx_theme()?),
+    ])
+}
+
+/// `sha256sum`-style lines for `fixtures`, in order
+pub fn manifest(fixtures: &[(&str, Vec<u8>)]) -> String {
+    fixtures
+        .iter()
+        .map(|(path, bytes)| {
+            let hash: String = Sha256::digest(bytes)
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect();
+            format!("{hash}  {path}\n")
+        })
+        .collect()
+}
+
+/// Panic with a `bytes_report` unless `actual` is exactly `expected`
+///
+/// ```should_panic
+/// use cursor_handler::test_utils::{GlyphStyle, assert_cursor_bytes_eq, synthetic_cur};
+///
+/// let mut expected = Vec::new();
+/// synthetic_cur(&[32], GlyphStyle::Arrow)?.encode(&mut expected)?;
+/// let mut actual = Vec::new();
+/// synthetic_cur(&[32], GlyphStyle::Checker)?.encode(&mut actual)?;
+/// assert_cursor_bytes_eq(&expected, &actual);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[track_caller]
+pub fn assert_cursor_bytes_eq(expected: &[u8], actual: &[u8]) {
+    if let Some(report) = bytes_report(expected, actual) {
+        panic!("encoded cursors differ\n{report}");
+    }
+}
+
+/// Where two encoded cursors part ways, or `None` if they don't
+///
+/// The report gives the lengths, the first differing offset with a line of
+/// hex from each side, the chunk or directory layout of each, and the
+/// `diff` of what each decodes to leniently.
+///
+/// ```
+/// use cursor_handler::test_utils::{bytes_report, synthetic_ani, synthetic_cur, GlyphStyle};
+///
+/// let encode = |cursor: cursor_handler::CursorFile| -> std::io::Result<Vec<u8>> {
+///     let mut bytes = Vec::new();
+///     cursor.encode(&mut bytes)?;
+///     Ok(bytes)
+/// };
+/// let arrow = encode(synthetic_cur(&[32], GlyphStyle::Arrow)?)?;
+/// assert_eq!(bytes_report(&arrow, &arrow), None);
+///
+/// // Only the hotspot's x byte differs
+/// let mut moved = synthetic_cur(&[32], GlyphStyle::Arrow)?;
+/// moved.set_hotspot_all((5, 4).into())?;
+/// let report = bytes_report(&arrow, &encode(moved)?).unwrap();
+/// assert!(report.starts_with("expected 4286 bytes, actual 4286\nfirst difference at 0xA\n"));
+/// assert!(report.contains("  expected 0x0: 00 00 02 00 01 00 20 20 00 00 [04] 00 04 00 a8 10\n"));
+/// assert!(report.contains("  actual   0x0: 00 00 02 00 01 00 20 20 00 00 [05] 00 04 00 a8 10\n"));
+/// assert!(report.contains("layout of both: cur, 1 entry (32x32 at 0x16, 4264 bytes)\n"));
+/// assert!(report.ends_with("decoded: 32x32 hotspot (4, 4) -> (5, 4)\n"));
+///
+/// // A cut file still gets its layout, and the side that won't decode says so
+/// let report = bytes_report(&arrow, &arrow[..30]).unwrap();
+/// assert!(report.contains("first difference at 0x1E, where actual ends\n"));
+/// let layout = "layout of actual: cur, 1 entry (32x32 at 0x16, 4264 bytes, cut short)\n";
+/// assert!(report.contains(layout));
+/// assert!(report.contains("actual doesn't decode: "));
+///
+/// // Animations show their chunks
+/// let bytes = |frames| -> std::io::Result<Vec<u8>> {
+///     let mut out = std::io::Cursor::new(Vec::new());
+///     synthetic_ani(frames, 32, 6)?.encode(&mut out)?;
+///     Ok(out.into_inner())
+/// };
+/// let report = bytes_report(&bytes(2)?, &bytes(3)?).unwrap();
+/// assert!(report.contains(
+///     "layout of expected: RIFF ACON (anih 36, LIST fram (icon 127, icon 128))\n"
+/// ));
+/// assert!(report.ends_with("decoded: frame 2 added\nstep 2 added: frame 2 for 6 jiffies\n"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn bytes_report(expected: &[u8], actual: &[u8]) -> Option<String> {
+    let at = expected
+        .iter()
+        .zip(actual)
+        .position(|(a, b)| a != b)
+        .or_else(|| (expected.len() != actual.len()).then(|| expected.len().min(actual.len())))?;
+
+    let mut report = format!(
+        "expected {} bytes, actual {}\n",
+        expected.len(),
+        actual.len()
+    );
+    let ends = match (at == expected.len(), at == actual.len()) {
+        (true, _) => ", where expected ends",
+        (_, true) => ", where actual ends",
+        _ => "",
+    };
+    let _ = writeln!(report, "first difference at {at:#X}{ends}");
+    for (side, bytes) in [("expected", expected), ("actual  ", actual)] {
+        let line = at / 16 * 16;
+        let hex: Vec<String> = (line..(line + 16).min(bytes.len()))
+            .map(|offset| match offset == at {
+                true => format!("[{:02x}]", bytes[offset]),
+                false => format!("{:02x}", bytes[offset]),
+            })
+            .collect();
+        let _ = writeln!(report, "  {side} {line:#X}: {}", hex.join(" "));
+    }
+
+    let layouts = [layout(expected), layout(actual)];
+    if layouts[0] == layouts[1] {
+        let _ = writeln!(report, "layout of both: {}", layouts[0]);
+    } else {
+        let _ = writeln!(report, "layout of expected: {}", layouts[0]);
+        let _ = writeln!(report, "layout of actual: {}", layouts[1]);
+    }
+
+    let options = DecodeOptions::lenient();
+    let decoded = if expected.starts_with(b"RIFF") || actual.starts_with(b"RIFF") {
+        let decode = |bytes| AniFile::decode_with(Cursor::new(bytes), &options);
+        match (decode(expected), decode(actual)) {
+            (Ok(a), Ok(b)) => Ok(diff::compare_ani(&a.value, &b.value).to_string()),
+            (a, b) => Err([a.err(), b.err()]),
+        }
+    } else {
+        let decode = |bytes| CursorFile::decode_with(Cursor::new(bytes), &options);
+        match (decode(expected), decode(actual)) {
+            (Ok(a), Ok(b)) => Ok(diff::compare_cur(&a.value, &b.value).to_string()),
+            (a, b) => Err([a.err(), b.err()]),
+        }
+    };
+    match decoded {
+        Ok(diff) => report.push_str(&format!("decoded: {diff}")),
+        Err(errors) => {
+            for (side, err) in ["expected", "actual"].iter().zip(errors) {
+                if let Some(err) = err {
+                    let _ = writeln!(report, "{side} doesn't decode: {err}");
+                }
+            }
+        }
+    }
+    Some(report)
+}
+
+/// Panic with a `steps_report` unless `a` and `b` play the same
+///
+/// ```should_panic
+/// use cursor_handler::test_utils::{assert_steps_eq, synthetic_ani};
+///
+/// assert_steps_eq(&synthetic_ani(3, 32, 6)?, &synthetic_ani(3, 32, 8)?);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[track_caller]
+pub fn assert_steps_eq(a: &AniFile, b: &AniFile) {
+    if let Some(report) = steps_report(a, b) {
+        panic!("animations play differently\n{report}");
+    }
+}
+
+/// How the playback of `b` strays from `a`, step by step, or `None` if
+/// every step shows the same pixels and hotspot for the same time
+///
+/// Frame indices, payload encodings and metadata don't matter, so an
+/// animation with its repeated frames shared plays the same as before.
+///
+/// ```
+/// use cursor_handler::test_utils::{steps_report, synthetic_ani};
+///
+/// let ani = synthetic_ani(3, 32, 6)?;
+/// assert_eq!(steps_report(&ani, &ani), None);
+///
+/// // Playing frame 0 again instead of frame 2, for longer
+/// let mut looped = ani.clone();
+/// looped.sequence = vec![0, 1, 0];
+/// looped.rates = vec![6, 6, 9];
+/// let report = steps_report(&ani, &looped).unwrap();
+/// assert_eq!(report, "step 2: pixels differ, 6 -> 9 jiffies\n");
+///
+/// let report = steps_report(&ani, &synthetic_ani(2, 32, 6)?).unwrap();
+/// assert_eq!(report, "3 steps -> 2\nstep 2 removed\n");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn steps_report(a: &AniFile, b: &AniFile) -> Option<String> {
+    let resolve = |ani: &AniFile| {
+        ani.steps()
+            .map(|step| {
+                let image = step.frame.decode_image().ok();
+                (image, step.frame.hotspot, step.duration)
+            })
+            .collect::<Vec<_>>()
+    };
+    let (a, b) = (resolve(a), resolve(b));
+
+    let mut report = String::new();
+    if a.len() != b.len() {
+        let _ = writeln!(report, "{} steps -> {}", a.len(), b.len());
+    }
+    for index in 0..a.len().max(b.len()) {
+        let (from, to) = match (a.get(index), b.get(index)) {
+            (Some(from), Some(to)) => (from, to),
+            (None, _) => {
+                let _ = writeln!(report, "step {index} added");
+                continue;
+            }
+            (_, None) => {
+                let _ = writeln!(report, "step {index} removed");
+                continue;
+            }
+        };
+        let mut changes = Vec::new();
+        match (&from.0, &to.0) {
+            (Some(x), Some(y)) if x == y => {}
+            (Some(_), Some(_)) => changes.push("pixels differ".to_string()),
+            _ => changes.push("a frame doesn't decode".to_string()),
+        }
+        if from.1 != to.1 {
+            changes.push(format!("hotspot {} -> {}", from.1, to.1));
+        }
+        if from.2 != to.2 {
+            changes.push(format!("{} -> {} jiffies", from.2, to.2));
+        }
+        if !changes.is_empty() {
+            let _ = writeln!(report, "step {index}: {}", changes.join(", "));
+        }
+    }
+    (!report.is_empty()).then_some(report)
+}
+
+/// One line describing how `bytes` is laid out, as far as it can be read
+fn layout(bytes: &[u8]) -> String {
+    let word = |at: usize| {
+        bytes
+            .get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+    let dword = |at: usize| {
+        bytes
+            .get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    if bytes.starts_with(b"RIFF") {
+        let mut out = String::new();
+        chunks(bytes, 0, bytes.len(), &mut out);
+        return out;
+    }
+    let (Some(0), Some(kind @ (1 | 2)), Some(count)) = (word(0), word(2), word(4)) else {
+        return format!(
+            "{} bytes of neither RIFF nor a cursor directory",
+            bytes.len()
+        );
+    };
+    let entries: Vec<String> = (0..count as usize)
+        .map(|index| {
+            let entry = 6 + index * 16;
+            let (Some(&[w, h]), Some(len), Some(offset)) = (
+                bytes.get(entry..entry + 2),
+                dword(entry + 8),
+                dword(entry + 12),
+            ) else {
+                return "cut short".to_string();
+            };
+            let side = |byte: u8| if byte == 0 { 256 } else { byte as u32 };
+            let cut = if offset as usize + len as usize > bytes.len() {
+                ", cut short"
+            } else {
+                ""
+            };
+            format!("{}x{} at {offset:#X}, {len} bytes{cut}", side(w), side(h))
+        })
+        .collect();
+    let kind = if kind == 2 { "cur" } else { "ico" };
+    let noun = if count == 1 { "entry" } else { "entries" };
+    format!("{kind}, {count} {noun} ({})", entries.join("; "))
+}
+
+/// The chunks in `bytes[start..end]`, nested lists in parentheses
+fn chunks(bytes: &[u8], start: usize, end: usize, out: &mut String) {
+    let mut at = start;
+    let mut first = true;
+    while at + 8 <= end {
+        if !first {
+            out.push_str(", ");
+        }
+        first = false;
+        let id = String::from_utf8_lossy(&bytes[at..at + 4]).into_owned();
+        let size = u32::from_le_bytes([bytes[at + 4], bytes[at + 5], bytes[at + 6], bytes[at + 7]]);
+        let data_end = at + 8 + size as usize;
+        let cut = data_end > end;
+        if (id == "RIFF" || id == "LIST") && at + 12 <= end {
+            let _ = write!(
+                out,
+                "{id} {} (",
+                String::from_utf8_lossy(&bytes[at + 8..at + 12])
+            );
+            chunks(bytes, at + 12, data_end.min(end), out);
+            out.push(')');
+        } else {
+            let _ = write!(out, "{} {size}", id.trim_end());
+        }
+        if cut {
+            out.push_str(" cut short");
+            return;
+        }
+        at = data_end + size as usize % 2;
+    }
+    if at < end {
+        let _ = write!(
+            out,
+            "{}{} stray bytes",
+            if first { "" } else { ", " },
+            end - at
+        );
+    }
+}
+
+/// An encoded animation re-laid with its frames in two `LIST fram` chunks,
+/// the first holding `first` of them, after its header and sequence
+fn split_frame_list(bytes: &[u8], first: usize) -> io::Result<Vec<u8>> {
+    let ani = AniFile::decode(Cursor::new(bytes))?;
+    let mut out = Cursor::new(Vec::new());
+    let mut riff = ChunkWriter::new(&mut out);
+    riff.begin_list(*b"RIFF", *b"ACON")?;
+    // The anih chunk follows the RIFF header: 8 bytes of header, 36 of data
+    riff.write_chunk(*b"anih", &bytes[20..56])?;
+    let seq: Vec<u8> = ani
+        .sequence
+        .iter()
+        .flat_map(|index| index.to_le_bytes())
+        .collect();
+    riff.write_chunk(*b"seq ", &seq)?;
+    for frames in [&ani.frames[..first], &ani.frames[first..]] {
+        riff.begin_list(*b"LIST", *b"fram")?;
+        for frame in frames {
+            riff.write_chunk(*b"icon", &frame.image_data)?;
+        }
+        riff.end_list()?;
+    }
+    riff.finish()?;
+    Ok(out.into_inner())
+}
+
+/// An encoded animation with its top-level chunks written in `order`, each
+/// named by its id or, for a `LIST`, its list type
+fn reorder_chunks(bytes: &[u8], order: &[FourCC]) -> io::Result<Vec<u8>> {
+    let mut input = Cursor::new(bytes);
+    input.set_position(12); // Past the RIFF header and ACON
+    let mut reader = ChunkReader::new(input)?;
+    let mut chunks = Vec::new();
+    while let Some(chunk) = reader.next_chunk()? {
+        let id = chunk.id();
+        let data = chunk.read_data()?;
+        let key = match &id {
+            b"LIST" => data[..4].try_into().unwrap(),
+            _ => id,
+        };
+        chunks.push((key, id, data));
+    }
+    if chunks.len() != order.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} chunks to put in {} places", chunks.len(), order.len()),
+        ));
+    }
+
+    let mut out = Cursor::new(Vec::new());
+    let mut riff = ChunkWriter::new(&mut out);
+    riff.begin_list(*b"RIFF", *b"ACON")?;
+    for key in order {
+        let (_, id, data) = chunks
+            .iter()
+            .find(|(found, ..)| found == key)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no such chunk"))?;
+        riff.write_chunk(*id, data)?;
+    }
+    riff.finish()?;
+    Ok(out.into_inner())
+}
+
+/// A CursorFX theme with a still 16px `[Arrow]` and a `[Busy]` strip of
+/// four 16px frames stacked vertically, red, green, blue and white from the
+/// top. The script also carries keys the importer doesn't know.
+fn cursorfx_theme() -> io::Result<Vec<u8>> {
+    let script = "\
+        ; Written by gen-fixtures\r\n\
+        [General]\r\nName=Tiny\r\nAuthor=Fixtures\r\nVersion=2\r\n\
+        [Arrow]\r\nImage=1\r\nHotspotX=3\r\nHotspotY=5\r\nGlow=1\r\n\
+        [Busy]\r\nImage=2\r\nFrames=4\r\nInterval=50\r\nHotspotX=8\r\n\
+        HotspotY=7\r\nDirection=Vertical\r\nTrail=Fade\r\n";
+    let colors = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]];
+    let strip = RgbaImage::from_fn(16, 64, |_, y| {
+        let [r, g, b] = colors[(y / 16) as usize];
+        Rgba([r, g, b, 255])
+    });
+    let arrow = glyph(16, GlyphStyle::Arrow, Rgba([0, 0, 0, 255]));
+
+    let mut records = Vec::new();
+    for (kind, id, payload) in [
+        (1u32, 0u32, script.as_bytes().to_vec()),
+        (2, 1, encode_png(&arrow)?),
+        (2, 2, encode_png(&strip)?),
+        (3, 9, b"a sound".to_vec()), // Skipped by the importer
+    ] {
+        records.extend(
+            [kind, id, payload.len() as u32]
+                .map(u32::to_le_bytes)
+                .concat(),
+        );
+        records.extend(payload);
+    }
+
+    // Version 2, a 16-byte header with 4 reserved bytes, then the zlib stream
+    let mut bytes = [2, 16, records.len() as u32, 0]
+        .map(u32::to_le_bytes)
+        .concat();
+    bytes.extend(zlib_stored(&records));
+    Ok(bytes)
+}
+
+/// `data` as a zlib stream of stored (uncompressed) deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks: Vec<_> = data.chunks(u16::MAX as usize).collect();
+    if blocks.is_empty() {
+        blocks.push(data); // Still needs one final block
+    }
+    for (index, block) in blocks.iter().enumerate() {
+        let len = block.len() as u16;
+        stream.push(u8::from(index + 1 == blocks.len()));
+        stream.extend(len.to_le_bytes());
+        stream.extend((!len).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    stream.extend(((b << 16) | a).to_be_bytes());
+    stream
+}
+
+/// Transparent, black and white squares, for the palette payloads
+fn two_tone(side: u32) -> RgbaImage {
+    let cell = (side / 8).max(1);
+    RgbaImage::from_fn(side, side, |x, y| match (x / cell + y / cell) % 3 {
+        0 => Rgba([0, 0, 0, 0]),
+        1 => Rgba([0, 0, 0, 255]),
+        _ => Rgba([255, 255, 255, 255]),
+    })
+}
+
+/// A bottom-up BI_RGB payload of `bits` bits per pixel; below 24 bits the
+/// palette holds the opaque colours in order of appearance, and fully
+/// transparent pixels are masked
+fn encode_dib(image: &RgbaImage, bits: u16) -> io::Result<Vec<u8>> {
+    let (width, height) = image.dimensions();
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    if bits < 24 {
+        for pixel in image.pixels().filter(|pixel| pixel[3] > 0) {
+            let [r, g, b, _] = pixel.0;
+            if !palette.contains(&[b, g, r, 0]) {
+                palette.push([b, g, r, 0]);
+            }
+        }
+        if palette.len() > 1 << bits {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} colours don't fit {bits} bits", palette.len()),
+            ));
+        }
+        palette.resize(1 << bits, [0; 4]);
+    }
+
+    let stride = (width as usize * bits as usize).div_ceil(32) * 4;
+    let mask_stride = (width as usize).div_ceil(32) * 4;
+    let mut dib = [
+        40,
+        width,
+        height * 2,
+        1 | ((bits as u32) << 16),
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+    ]
+    .map(u32::to_le_bytes)
+    .concat();
+    dib.extend(palette.iter().flatten());
+    for row in image.rows().rev() {
+        let mut packed = vec![0u8; stride];
+        for (x, pixel) in row.enumerate() {
+            let [r, g, b, _] = pixel.0;
+            if bits == 24 {
+                packed[x * 3..x * 3 + 3].copy_from_slice(&[b, g, r]);
+                continue;
+            }
+            let index = palette.iter().position(|&entry| entry == [b, g, r, 0]);
+            let index = if pixel[3] > 0 { index.unwrap_or(0) } else { 0 } as u8;
+            let bit = x * bits as usize;
+            packed[bit / 8] |= index << (8 - bits as usize - bit % 8);
+        }
+        dib.extend_from_slice(&packed);
+    }
+    for row in image.rows().rev() {
+        let mut mask = vec![0u8; mask_stride];
+        for (x, pixel) in row.enumerate() {
+            if pixel[3] == 0 {
+                mask[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        dib.extend_from_slice(&mask);
+    }
+    Ok(dib)
+}
+
+/// `style` in `color` on a `side` px canvas
+fn glyph(side: u32, style: GlyphStyle, color: Rgba<u8>) -> RgbaImage {
+    let cell = (side / 8).max(1);
+    let tip = synthetic_hotspot(side);
+    let (tip_x, tip_y) = (tip.x as u32, tip.y as u32);
+    RgbaImage::from_fn(side, side, |x, y| {
+        let drawn = match style {
+            GlyphStyle::Solid => true,
+            GlyphStyle::Checker => (x / cell + y / cell).is_multiple_of(2),
+            GlyphStyle::Arrow => x >= tip_x && y >= tip_y && x - tip_x <= y - tip_y,
+        };
+        if drawn { color } else { Rgba([0, 0, 0, 0]) }
+    })
+}
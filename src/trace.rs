@@ -0,0 +1,61 @@
+// Instrumentation that compiles to nothing without the `tracing` feature
+//
+// The codecs call these macros instead of `tracing`'s own, so the feature
+// can stay optional. Arguments use `tracing` syntax either way.
+
+/// An entered span that lasts until the returned guard is dropped
+#[cfg(feature = "tracing")]
+macro_rules! span {
+    ($level:ident, $($arg:tt)*) => {
+        tracing::span!(tracing::Level::$level, $($arg)*).entered()
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! span {
+    ($($arg:tt)*) => {
+        $crate::trace::NoSpan
+    };
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        tracing::trace!($($arg)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {};
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        tracing::debug!($($arg)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! debug {
+    ($($arg:tt)*) => {};
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        tracing::warn!($($arg)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! warning {
+    ($($arg:tt)*) => {};
+}
+
+pub(crate) use {debug, span, trace, warning};
+
+/// Stands in for a span guard when the feature is off
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;
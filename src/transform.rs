@@ -0,0 +1,928 @@
+// Pixel transforms used by the generators and exporters
+use std::io;
+
+use image::{Rgba, RgbaImage, imageops};
+
+use crate::{
+    analysis::{self, Rect},
+    ani::AniFile,
+    cur::{CursorFile, CursorFrame},
+    hotspot::Hotspot,
+    size::{MAX_FRAME_SIZE, SnapPolicy},
+};
+
+/// What `translate` does with pixels pushed past the canvas edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgePolicy {
+    /// Keep the canvas size and drop what falls outside; the hotspot must
+    /// stay on the canvas
+    #[default]
+    Clip,
+    /// Widen the canvas on the side the glyph moves away from, so nothing
+    /// is lost, up to `MAX_FRAME_SIZE`
+    Grow,
+}
+
+/// Shift `image` by `dx`, `dy` pixels, moving `hotspot` along so it stays on
+/// the same pixel of the glyph
+///
+/// ```
+/// use cursor_handler::Hotspot;
+/// use cursor_handler::transform::{EdgePolicy, translate};
+/// use image::{Rgba, RgbaImage};
+///
+/// let mut image = RgbaImage::new(4, 4);
+/// image.put_pixel(1, 1, Rgba([255, 0, 0, 255]));
+/// let hotspot = Hotspot::new(1, 1);
+///
+/// let (clipped, moved) = translate(&image, hotspot, 2, -1, EdgePolicy::Clip)?;
+/// assert_eq!((clipped.dimensions(), moved), ((4, 4), Hotspot::new(3, 0)));
+/// assert_eq!(clipped.get_pixel(3, 0)[3], 255);
+/// // Clipping can't put the hotspot off the canvas
+/// assert!(translate(&image, hotspot, -2, 0, EdgePolicy::Clip).is_err());
+///
+/// let (grown, moved) = translate(&image, hotspot, -2, 3, EdgePolicy::Grow)?;
+/// assert_eq!((grown.dimensions(), moved), ((6, 7), Hotspot::new(1, 4)));
+/// assert_eq!(grown.get_pixel(1, 4)[3], 255);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn translate(
+    image: &RgbaImage,
+    hotspot: Hotspot,
+    dx: i32,
+    dy: i32,
+    policy: EdgePolicy,
+) -> io::Result<(RgbaImage, Hotspot)> {
+    let (width, height) = image.dimensions();
+    let (canvas, (x, y)) = match policy {
+        EdgePolicy::Clip => ((width, height), (dx as i64, dy as i64)),
+        EdgePolicy::Grow => {
+            let canvas = (width + dx.unsigned_abs(), height + dy.unsigned_abs());
+            if canvas.0.max(canvas.1) > MAX_FRAME_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{}x{} canvas is over the {MAX_FRAME_SIZE}px limit",
+                        canvas.0, canvas.1
+                    ),
+                ));
+            }
+            (canvas, (dx.max(0) as i64, dy.max(0) as i64))
+        }
+    };
+
+    let (hx, hy) = (hotspot.x as i64 + x, hotspot.y as i64 + y);
+    if hx < 0 || hy < 0 || hx >= canvas.0 as i64 || hy >= canvas.1 as i64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("moving by ({dx}, {dy}) takes hotspot {hotspot} off the canvas"),
+        ));
+    }
+    let mut translated = RgbaImage::new(canvas.0, canvas.1);
+    imageops::replace(&mut translated, image, x, y);
+    Ok((translated, Hotspot::new(hx as u16, hy as u16)))
+}
+
+impl CursorFile {
+    /// `translate` every frame, re-encoding each from its pixels
+    ///
+    /// `dx` and `dy` are pixels of the largest frame; smaller frames move by
+    /// the same share of their size. On error the cursor is left unchanged.
+    pub fn translate(&mut self, dx: i32, dy: i32, policy: EdgePolicy) -> io::Result<()> {
+        let largest = self
+            .frames()
+            .map(|frame| frame.size.max_side())
+            .max()
+            .unwrap_or(1);
+        let mut frames = Vec::with_capacity(self.frames.len());
+        for frame in self.frames() {
+            let factor = frame.size.max_side() as f32 / largest as f32;
+            let offset = |d: i32| (d as f32 * factor).round() as i32;
+            let image = frame.decode_image()?;
+            let (image, hotspot) =
+                translate(&image, frame.hotspot, offset(dx), offset(dy), policy)?;
+            frames.push(CursorFrame::from_image(&image, hotspot)?);
+        }
+        self.frames = frames;
+        Ok(())
+    }
+}
+
+impl AniFile {
+    /// `CursorFile::translate` the cursor in every frame
+    ///
+    /// Timing, sequence, rates and metadata are kept, and the header follows
+    /// the first frame. On error the animation is left unchanged.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::transform::EdgePolicy;
+    /// use cursor_handler::{AniFile, Hotspot};
+    ///
+    /// let bytes = std::fs::read("assets/aero_busy.ani")?;
+    /// let original = AniFile::decode(Cursor::new(bytes))?;
+    ///
+    /// let mut clipped = original.clone();
+    /// clipped.translate(-8, 4, EdgePolicy::Clip)?;
+    /// assert_eq!((clipped[0].size.width(), clipped[0].hotspot), (64, Hotspot::new(24, 36)));
+    /// let cursor = clipped.extract_frame(0)?;
+    /// assert_eq!((cursor[2].size.width(), cursor[2].hotspot), (32, Hotspot::new(12, 18)));
+    ///
+    /// let mut grown = original.clone();
+    /// grown.translate(-8, 4, EdgePolicy::Grow)?;
+    /// assert_eq!((grown[0].size.width(), grown[0].size.height()), (72, 68));
+    /// assert_eq!(grown[0].hotspot, Hotspot::new(32, 36));
+    /// let durations = |ani: &AniFile| ani.steps().map(|step| step.duration).collect::<Vec<_>>();
+    /// assert_eq!(durations(&grown), durations(&original));
+    ///
+    /// // The hotspot at (32, 32) can't be clipped 40px to the left
+    /// let mut unchanged = original.clone();
+    /// assert!(unchanged.translate(-40, 0, EdgePolicy::Clip).is_err());
+    /// assert_eq!(unchanged[0].image_data, original[0].image_data);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn translate(&mut self, dx: i32, dy: i32, policy: EdgePolicy) -> io::Result<()> {
+        let mut frames = self.frames.clone();
+        for (frame, translated) in self.frames().zip(&mut frames) {
+            let mut cursor = CursorFile::decode(io::Cursor::new(&frame.image_data))?;
+            cursor.translate(dx, dy, policy)?;
+            translated.image_data.clear();
+            cursor.encode(&mut translated.image_data)?;
+            translated.size = cursor[0].size;
+            translated.hotspot = cursor[0].hotspot;
+        }
+        self.frames = frames;
+        self.refresh_header();
+        Ok(())
+    }
+}
+
+impl CursorFile {
+    /// Cut every frame down to `rect`, given in pixels of the largest frame
+    ///
+    /// Smaller frames are cut to the same share of their size, and each
+    /// hotspot moves with its pixel. Fails if a hotspot would fall outside
+    /// the cut or `rect` leaves the largest frame; the cursor is then left
+    /// unchanged.
+    pub fn crop_to(&mut self, rect: Rect) -> io::Result<()> {
+        let largest = analysis::largest_side(std::slice::from_ref(self));
+        self.crop_scaled(rect, largest)
+    }
+
+    /// `crop_to` the `analysis::content_bounds` of the cursor, returning
+    /// them; `None` leaves a fully transparent cursor as it is
+    pub fn autocrop(&mut self) -> io::Result<Option<Rect>> {
+        let bounds = analysis::content_bounds(&*self)?;
+        if let Some(bounds) = bounds {
+            self.crop_to(bounds)?;
+        }
+        Ok(bounds)
+    }
+
+    /// `crop_to` with `rect` measured on a `largest` px frame
+    fn crop_scaled(&mut self, rect: Rect, largest: u32) -> io::Result<()> {
+        let mut frames = Vec::with_capacity(self.frames.len());
+        for frame in self.frames() {
+            let (width, height) = (frame.size.width(), frame.size.height());
+            let cut = rect.scaled(frame.size.max_side() as f32 / largest as f32);
+            if rect.width == 0 || rect.height == 0 || cut.right() > width || cut.bottom() > height {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("crop {cut} doesn't fit the {} frame", frame.size),
+                ));
+            }
+            let hotspot = frame.hotspot;
+            let (x, y) = (hotspot.x as u32, hotspot.y as u32);
+            if x < cut.x || y < cut.y || x >= cut.right() || y >= cut.bottom() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "hotspot {hotspot} of the {} frame is outside crop {cut}",
+                        frame.size
+                    ),
+                ));
+            }
+            let image = frame.decode_image()?;
+            let image = imageops::crop_imm(&image, cut.x, cut.y, cut.width, cut.height).to_image();
+            let hotspot = Hotspot::new((x - cut.x) as u16, (y - cut.y) as u16);
+            frames.push(CursorFrame::from_image(&image, hotspot)?);
+        }
+        self.frames = frames;
+        Ok(())
+    }
+}
+
+impl AniFile {
+    /// `CursorFile::crop_to` the cursor in every frame with the same `rect`,
+    /// in pixels of the animation's largest frame
+    ///
+    /// Frames stay the same size as each other and in register. Timing,
+    /// sequence, rates and metadata are kept, and the header follows the
+    /// first frame. On error the animation is left unchanged.
+    pub fn crop_to(&mut self, rect: Rect) -> io::Result<()> {
+        let mut cursors = self
+            .frames()
+            .map(|frame| frame.to_cursor_file())
+            .collect::<io::Result<Vec<_>>>()?;
+        let largest = analysis::largest_side(&cursors);
+        let mut frames = self.frames.clone();
+        for (cursor, cropped) in cursors.iter_mut().zip(&mut frames) {
+            cursor.crop_scaled(rect, largest)?;
+            cropped.image_data.clear();
+            cursor.encode(&mut cropped.image_data)?;
+            cropped.size = cursor[0].size;
+            cropped.hotspot = cursor[0].hotspot;
+        }
+        self.frames = frames;
+        self.refresh_header();
+        Ok(())
+    }
+
+    /// `crop_to` the `analysis::content_bounds` of the whole animation,
+    /// returning them; `None` leaves a fully transparent animation as it is
+    pub fn autocrop(&mut self) -> io::Result<Option<Rect>> {
+        let bounds = analysis::content_bounds(&*self)?;
+        if let Some(bounds) = bounds {
+            self.crop_to(bounds)?;
+        }
+        Ok(bounds)
+    }
+}
+
+/// How `upscale_pixel_art` fills in the enlarged pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PxAlgo {
+    /// Each pixel becomes a `factor` x `factor` block
+    #[default]
+    Nearest,
+    /// Scale2x (AdvMAME2x): corners of a block take a neighbour's colour
+    /// where two edges meet, rounding off diagonals without new colours
+    Scale2x,
+    /// Eagle: a corner takes the colour its three outer neighbours share
+    Eagle,
+}
+
+/// Enlarge `image` by a whole `factor` without blurring it, for pixel art
+/// that Lanczos would smear
+///
+/// `Nearest` takes any factor; `Scale2x` and `Eagle` double, so they take
+/// powers of two and run repeatedly. Neither adds colours. Fractional sizes
+/// are for `imageops::resize`.
+///
+/// ```
+/// use cursor_handler::transform::{PxAlgo, upscale_pixel_art};
+/// use image::{Rgba, RgbaImage};
+///
+/// let art = |rows: &[&str]| {
+///     RgbaImage::from_fn(rows[0].len() as u32, rows.len() as u32, |x, y| {
+///         match rows[y as usize].as_bytes()[x as usize] {
+///             b'#' => Rgba([0, 0, 0, 255]),
+///             _ => Rgba([0, 0, 0, 0]),
+///         }
+///     })
+/// };
+/// let arrow = art(&["#...", "##..", "###.", "#..."]);
+/// let upscale = |algo| upscale_pixel_art(&arrow, 2, algo);
+///
+/// #[rustfmt::skip]
+/// let nearest = art(&[
+///     "##......",
+///     "##......",
+///     "####....",
+///     "####....",
+///     "######..",
+///     "######..",
+///     "##......",
+///     "##......",
+/// ]);
+/// assert_eq!(upscale(PxAlgo::Nearest)?, nearest);
+/// // Both round off the steps along the diagonal edge
+/// #[rustfmt::skip]
+/// let scale2x = art(&[
+///     "##......",
+///     "###.....",
+///     "###.....",
+///     "#####...",
+///     "######..",
+///     "######..",
+///     "###.....",
+///     "##......",
+/// ]);
+/// assert_eq!(upscale(PxAlgo::Scale2x)?, scale2x);
+/// #[rustfmt::skip]
+/// let eagle = art(&[
+///     "##......",
+///     "###.....",
+///     "###.....",
+///     "#####...",
+///     "#####...",
+///     "#####...",
+///     "###.....",
+///     "##......",
+/// ]);
+/// assert_eq!(upscale(PxAlgo::Eagle)?, eagle);
+///
+/// assert!(upscale_pixel_art(&nearest, 3, PxAlgo::Scale2x).is_err());
+/// assert_eq!(upscale_pixel_art(&nearest, 3, PxAlgo::Nearest)?.dimensions(), (24, 24));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn upscale_pixel_art(image: &RgbaImage, factor: u32, algo: PxAlgo) -> io::Result<RgbaImage> {
+    let (width, height) = image.dimensions();
+    if factor == 0 || (algo != PxAlgo::Nearest && !factor.is_power_of_two()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{algo:?} can't scale by {factor}"),
+        ));
+    }
+    if width.max(height).saturating_mul(factor) > MAX_FRAME_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{width}x{height} scaled by {factor} is over the {MAX_FRAME_SIZE}px limit"),
+        ));
+    }
+
+    match algo {
+        PxAlgo::Nearest => Ok(imageops::resize(
+            image,
+            width * factor,
+            height * factor,
+            imageops::FilterType::Nearest,
+        )),
+        PxAlgo::Scale2x | PxAlgo::Eagle => {
+            let mut image = image.clone();
+            for _ in 0..factor.trailing_zeros() {
+                image = double(&image, algo);
+            }
+            Ok(image)
+        }
+    }
+}
+
+/// One Scale2x or Eagle pass; pixels past the edge repeat the border
+fn double(image: &RgbaImage, algo: PxAlgo) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let at = |x: u32, dx: i32, y: u32, dy: i32| {
+        let x = x.saturating_add_signed(dx).min(width - 1);
+        let y = y.saturating_add_signed(dy).min(height - 1);
+        *image.get_pixel(x, y)
+    };
+
+    let mut doubled = RgbaImage::new(width * 2, height * 2);
+    for (x, y, &e) in image.enumerate_pixels() {
+        let corners = if algo == PxAlgo::Scale2x {
+            // B above, D left, F right, H below
+            let (b, d, f, h) = (
+                at(x, 0, y, -1),
+                at(x, -1, y, 0),
+                at(x, 1, y, 0),
+                at(x, 0, y, 1),
+            );
+            if b != h && d != f {
+                [
+                    if d == b { d } else { e },
+                    if b == f { f } else { e },
+                    if d == h { d } else { e },
+                    if h == f { f } else { e },
+                ]
+            } else {
+                [e; 4]
+            }
+        } else {
+            let corner = |dx: i32, dy: i32| {
+                let (side, vertical, diagonal) =
+                    (at(x, dx, y, 0), at(x, 0, y, dy), at(x, dx, y, dy));
+                if side == vertical && vertical == diagonal {
+                    side
+                } else {
+                    e
+                }
+            };
+            [corner(-1, -1), corner(1, -1), corner(-1, 1), corner(1, 1)]
+        };
+        for (i, pixel) in corners.into_iter().enumerate() {
+            doubled.put_pixel(x * 2 + i as u32 % 2, y * 2 + i as u32 / 2, pixel);
+        }
+    }
+    doubled
+}
+
+impl CursorFile {
+    /// `upscale_pixel_art` every frame, multiplying each hotspot by `factor`
+    ///
+    /// Frames reaching 256 pixels are stored as PNG. On error the cursor is
+    /// left unchanged.
+    pub fn upscale_pixel_art(&mut self, factor: u32, algo: PxAlgo) -> io::Result<()> {
+        let mut frames = Vec::with_capacity(self.frames.len());
+        for frame in self.frames() {
+            let image = upscale_pixel_art(&frame.decode_image()?, factor, algo)?;
+            let hotspot = frame.hotspot.scaled_by(factor).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("hotspot {} scaled by {factor} is too large", frame.hotspot),
+                )
+            })?;
+            frames.push(CursorFrame::from_image(&image, hotspot)?);
+        }
+        self.frames = frames;
+        Ok(())
+    }
+}
+
+impl AniFile {
+    /// `CursorFile::upscale_pixel_art` the cursor in every frame
+    ///
+    /// Timing, sequence, rates and metadata are kept, and the header follows
+    /// the first frame. On error the animation is left unchanged.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::transform::PxAlgo;
+    /// use cursor_handler::{AniFile, FrameSize, Hotspot};
+    ///
+    /// let bytes = std::fs::read("assets/aero_busy.ani")?;
+    /// let mut ani = AniFile::decode(Cursor::new(bytes))?;
+    /// ani.upscale_pixel_art(4, PxAlgo::Scale2x)?;
+    /// assert_eq!(ani[0].size, FrameSize::square(256)?);
+    /// assert_eq!(ani[0].hotspot, Hotspot::new(128, 128));
+    /// let cursor = ani.extract_frame(0)?;
+    /// assert!(cursor[0].is_png() && !cursor[1].is_png());
+    /// assert!(ani.upscale_pixel_art(32, PxAlgo::Nearest).is_err());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn upscale_pixel_art(&mut self, factor: u32, algo: PxAlgo) -> io::Result<()> {
+        let mut frames = self.frames.clone();
+        for (frame, scaled) in self.frames().zip(&mut frames) {
+            let mut cursor = frame.to_cursor_file()?;
+            cursor.upscale_pixel_art(factor, algo)?;
+            scaled.image_data.clear();
+            cursor.encode(&mut scaled.image_data)?;
+            scaled.size = cursor[0].size;
+            scaled.hotspot = cursor[0].hotspot;
+        }
+        self.frames = frames;
+        self.refresh_header();
+        Ok(())
+    }
+}
+
+/// Rotate clockwise about the center, keeping the canvas size
+///
+/// Uses bilinear sampling; pixels rotated in from outside the canvas are
+/// transparent.
+pub fn rotate(image: &RgbaImage, degrees: f32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        // Map the destination pixel center back into the source
+        let dx = x as f32 + 0.5 - cx;
+        let dy = y as f32 + 0.5 - cy;
+        let sx = cos * dx + sin * dy + cx - 0.5;
+        let sy = -sin * dx + cos * dy + cy - 0.5;
+        sample_bilinear(image, sx, sy)
+    })
+}
+
+/// Scale about the center, keeping the canvas size
+pub fn scale(image: &RgbaImage, factor: f32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let new_width = ((width as f32 * factor).round() as u32).max(1);
+    let new_height = ((height as f32 * factor).round() as u32).max(1);
+    let scaled = imageops::resize(image, new_width, new_height, imageops::FilterType::Triangle);
+
+    let mut canvas = RgbaImage::new(width, height);
+    let x = (width as i64 - new_width as i64) / 2;
+    let y = (height as i64 - new_height as i64) / 2;
+    imageops::overlay(&mut canvas, &scaled, x, y);
+    canvas
+}
+
+impl CursorFile {
+    /// Resize every frame `FrameSize::is_standard` turns down to the
+    /// standard size `policy` picks, scaling its hotspot along
+    ///
+    /// A non-square frame is fitted into the square. When frames land on the
+    /// same size one is kept: a frame that was already that size, else the
+    /// one resized from the most pixels, else the higher bit depth. On error
+    /// the cursor is left unchanged.
+    ///
+    /// ```
+    /// use cursor_handler::size::SnapPolicy;
+    /// use cursor_handler::{CursorFile, CursorFrame, FrameSize, Hotspot};
+    /// use image::{Rgba, RgbaImage};
+    ///
+    /// let render = |side: u32, red: u8| {
+    ///     let image = RgbaImage::from_pixel(side, side, Rgba([red, 0, 0, 255]));
+    ///     CursorFrame::from_image(&image, Hotspot::new(20, 10))
+    /// };
+    ///
+    /// let mut cursor = CursorFile::new(vec![render(32, 0)?, render(40, 1)?]);
+    /// cursor.snap_to_standard_sizes(SnapPolicy::NearestUp)?;
+    /// assert_eq!(cursor[1].size, FrameSize::square(48)?);
+    /// assert_eq!(cursor[1].hotspot, Hotspot::new(24, 12));
+    ///
+    /// // 40 and 44 both snap to 48; the 44px frame has more to scale from
+    /// let mut cursor = CursorFile::new(vec![render(40, 1)?, render(44, 2)?]);
+    /// cursor.snap_to_standard_sizes(SnapPolicy::NearestUp)?;
+    /// assert_eq!(cursor.frames().len(), 1);
+    /// assert_eq!(cursor[0].decode_image()?.get_pixel(24, 24)[0], 2);
+    ///
+    /// // Down, 40 lands on 32, where the frame already there wins
+    /// let mut cursor = CursorFile::new(vec![render(32, 0)?, render(40, 1)?]);
+    /// cursor.snap_to_standard_sizes(SnapPolicy::NearestDown)?;
+    /// assert_eq!(cursor.frames().len(), 1);
+    /// assert_eq!(cursor[0].decode_image()?.get_pixel(16, 16)[0], 0);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn snap_to_standard_sizes(&mut self, policy: SnapPolicy) -> io::Result<()> {
+        // Each frame with what it was resized from, if it was
+        let mut snapped: Vec<(CursorFrame, Option<u32>)> = Vec::with_capacity(self.frames.len());
+        for frame in self.frames() {
+            let (frame, source) = if frame.size.is_standard() {
+                (frame.clone(), None)
+            } else {
+                let side = policy.snap(frame.size.max_side());
+                let image = fit_square(&frame.decode_image()?, side);
+                let dimensions = (frame.size.width(), frame.size.height());
+                let hotspot = fit_hotspot(frame.hotspot, dimensions, side);
+                let source = frame.size.width() * frame.size.height();
+                (CursorFrame::from_image(&image, hotspot)?, Some(source))
+            };
+            let rank = |frame: &CursorFrame, source: Option<u32>| {
+                (source.is_none(), source, frame.bit_count)
+            };
+            match snapped.iter_mut().find(|(kept, _)| kept.size == frame.size) {
+                Some((kept, kept_source)) if rank(kept, *kept_source) >= rank(&frame, source) => {}
+                Some(kept) => *kept = (frame, source),
+                None => snapped.push((frame, source)),
+            }
+        }
+        self.frames = snapped.into_iter().map(|(frame, _)| frame).collect();
+        Ok(())
+    }
+}
+
+/// How `resize_with` samples the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleFilter {
+    /// The image crate's Lanczos3; sharp, but it averages the undefined
+    /// colour of transparent pixels into the edges
+    Lanczos3,
+    /// Each target pixel is the alpha-weighted mean of the source area it
+    /// covers, in linear light, so edges keep their colour
+    AreaAlphaAware,
+    /// Each target pixel copies the source pixel under its centre, for
+    /// pixel art
+    Nearest,
+}
+
+/// Resize to `width` x `height`: `AreaAlphaAware` when shrinking on both
+/// axes, `Lanczos3` otherwise
+pub fn resize(image: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    let filter = if width <= image.width() && height <= image.height() {
+        ScaleFilter::AreaAlphaAware
+    } else {
+        ScaleFilter::Lanczos3
+    };
+    resize_with(image, width, height, filter)
+}
+
+/// Resize to `width` x `height` with `filter`
+///
+/// ```
+/// use cursor_handler::transform::{ScaleFilter, resize_with};
+/// use image::{Rgba, RgbaImage};
+///
+/// // A red disc on a transparent background whose colour happens to be black
+/// let disc = RgbaImage::from_fn(64, 64, |x, y| {
+///     let (dx, dy) = (x as f32 - 31.5, y as f32 - 31.5);
+///     if dx * dx + dy * dy < 24.0 * 24.0 {
+///         Rgba([255, 0, 0, 255])
+///     } else {
+///         Rgba([0, 0, 0, 0])
+///     }
+/// });
+///
+/// // How far the visible pixels stray from pure red
+/// let fringe = |filter| {
+///     let small = resize_with(&disc, 20, 20, filter);
+///     small
+///         .pixels()
+///         .filter(|pixel| pixel[3] > 16)
+///         .map(|pixel| (255 - pixel[0]).max(pixel[1]).max(pixel[2]))
+///         .max()
+///         .unwrap()
+/// };
+/// assert_eq!(fringe(ScaleFilter::AreaAlphaAware), 0);
+/// assert!(fringe(ScaleFilter::Lanczos3) > 64);
+/// ```
+pub fn resize_with(image: &RgbaImage, width: u32, height: u32, filter: ScaleFilter) -> RgbaImage {
+    match filter {
+        ScaleFilter::Lanczos3 => {
+            imageops::resize(image, width, height, imageops::FilterType::Lanczos3)
+        }
+        ScaleFilter::AreaAlphaAware => area_average(image, width, height),
+        ScaleFilter::Nearest => {
+            imageops::resize(image, width, height, imageops::FilterType::Nearest)
+        }
+    }
+}
+
+/// Box-filter `image` to `width` x `height`, weighting by coverage and alpha
+/// in linear light
+fn area_average(image: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    let to_linear: Vec<f32> = (0..=255u8)
+        .map(|c| {
+            let c = c as f32 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        })
+        .collect();
+    let to_srgb = |c: f32| {
+        let c = if c <= 0.003_130_8 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        };
+        (c * 255.0).round().clamp(0.0, 255.0) as u8
+    };
+
+    // The source pixels a target pixel covers, with how much of each
+    let spans = |source: u32, target: u32| -> Vec<Vec<(u32, f32)>> {
+        let step = source as f64 / target as f64;
+        (0..target)
+            .map(|i| {
+                let (start, end) = (i as f64 * step, (i + 1) as f64 * step);
+                (start.floor() as u32..(end.ceil() as u32).min(source))
+                    .map(|s| {
+                        let overlap = end.min(s as f64 + 1.0) - start.max(s as f64);
+                        (s, overlap as f32)
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+    let (columns, rows) = (spans(image.width(), width), spans(image.height(), height));
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let (mut premultiplied, mut alpha, mut area) = ([0.0f32; 3], 0.0f32, 0.0f32);
+        for &(sy, wy) in &rows[y as usize] {
+            for &(sx, wx) in &columns[x as usize] {
+                let Rgba([r, g, b, a]) = *image.get_pixel(sx, sy);
+                let weight = wx * wy;
+                let coverage = weight * a as f32 / 255.0;
+                for (sum, c) in premultiplied.iter_mut().zip([r, g, b]) {
+                    *sum += to_linear[c as usize] * coverage;
+                }
+                alpha += coverage;
+                area += weight;
+            }
+        }
+        if alpha <= 0.0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+        let [r, g, b] = premultiplied.map(|sum| to_srgb(sum / alpha));
+        let a = (alpha / area * 255.0).round().clamp(0.0, 255.0) as u8;
+        Rgba([r, g, b, a])
+    })
+}
+
+/// Resize to a `size` x `size` canvas, fitting the longer side and centring
+/// the other (see `resize`)
+pub fn fit_square(image: &RgbaImage, size: u32) -> RgbaImage {
+    if image.dimensions() == (size, size) {
+        return image.clone();
+    }
+    let scale = size as f32 / image.width().max(image.height()) as f32;
+    let width = ((image.width() as f32 * scale).round() as u32).clamp(1, size);
+    let height = ((image.height() as f32 * scale).round() as u32).clamp(1, size);
+    let scaled = resize(image, width, height);
+
+    let mut canvas = RgbaImage::new(size, size);
+    let (x, y) = ((size - width) / 2, (size - height) / 2);
+    imageops::overlay(&mut canvas, &scaled, x as i64, y as i64);
+    canvas
+}
+
+/// Where `hotspot` in a `width` x `height` image lands after `fit_square`
+pub(crate) fn fit_hotspot(hotspot: Hotspot, (width, height): (u32, u32), size: u32) -> Hotspot {
+    let scale = size as f32 / width.max(height) as f32;
+    let hotspot = hotspot.scaled(scale);
+    let offset =
+        |side: u32| ((size - ((side as f32 * scale).round() as u32).clamp(1, size)) / 2) as u16;
+    Hotspot::new(hotspot.x + offset(width), hotspot.y + offset(height)).clamp_to(size, size)
+}
+
+/// Where `CursorFile::resized` puts the hotspot of each new frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResizeHotspot {
+    /// The same point of the glyph, as a fraction of the frame (see
+    /// `Hotspot::fraction`)
+    #[default]
+    KeepFraction,
+    /// The same pixel coordinates, clamped into smaller frames
+    KeepPixel,
+    /// This pixel at every size; it must fall inside each frame
+    Absolute(Hotspot),
+}
+
+/// How `CursorFile::resized` and `AniFile::resize` scale frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResizeOptions {
+    /// `None` picks the filter as `resize` does
+    pub filter: Option<ScaleFilter>,
+    pub hotspot: ResizeHotspot,
+    /// Centre a non-square frame on a transparent square canvas, rather
+    /// than keeping its aspect ratio with the longer side at the target size
+    pub pad_square: bool,
+}
+
+/// Scale `image` so its longer side is `side`, placing `hotspot` as
+/// `options` says
+fn resize_frame(
+    image: &RgbaImage,
+    hotspot: Hotspot,
+    side: u32,
+    options: &ResizeOptions,
+) -> io::Result<(RgbaImage, Hotspot)> {
+    let (width, height) = image.dimensions();
+    let scale = side as f32 / width.max(height) as f32;
+    let fit = |length: u32| ((length as f32 * scale).round() as u32).clamp(1, side);
+    let (scaled_width, scaled_height) = (fit(width), fit(height));
+    let scaled = match options.filter {
+        Some(filter) => resize_with(image, scaled_width, scaled_height, filter),
+        None => resize(image, scaled_width, scaled_height),
+    };
+
+    let (canvas, (x, y)) = if options.pad_square {
+        let mut canvas = RgbaImage::new(side, side);
+        let offset = ((side - scaled_width) / 2, (side - scaled_height) / 2);
+        imageops::overlay(&mut canvas, &scaled, offset.0 as i64, offset.1 as i64);
+        (canvas, offset)
+    } else {
+        (scaled, (0, 0))
+    };
+
+    let (canvas_width, canvas_height) = canvas.dimensions();
+    let hotspot = match options.hotspot {
+        ResizeHotspot::KeepFraction => {
+            let placed = Hotspot::from_fraction(
+                hotspot.fraction(width, height),
+                scaled_width,
+                scaled_height,
+            );
+            Hotspot::new(placed.x + x as u16, placed.y + y as u16)
+        }
+        ResizeHotspot::KeepPixel => hotspot.clamp_to(canvas_width, canvas_height),
+        ResizeHotspot::Absolute(hotspot) if hotspot.is_within(canvas_width, canvas_height) => {
+            hotspot
+        }
+        ResizeHotspot::Absolute(hotspot) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("hotspot {hotspot} is outside the {canvas_width}x{canvas_height} frame"),
+            ));
+        }
+    };
+    Ok((canvas, hotspot))
+}
+
+impl CursorFile {
+    /// The frame with the longest side, the higher bit depth on a tie; what
+    /// `resized` scales from
+    pub fn largest_frame(&self) -> Option<&CursorFrame> {
+        self.frames()
+            .max_by_key(|frame| (frame.size.max_side(), frame.bit_count))
+    }
+
+    /// A cursor with one frame per entry of `sides`, each scaled from
+    /// `largest_frame` so its longer side is that many pixels
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::transform::{ResizeHotspot, ResizeOptions};
+    /// use cursor_handler::{CursorFile, Hotspot};
+    ///
+    /// let bytes = std::fs::read("assets/windows_theme/Link.cur")?;
+    /// let cursor = CursorFile::decode(Cursor::new(bytes))?;
+    /// assert_eq!(cursor[0].hotspot, Hotspot::new(6, 1));
+    ///
+    /// let resized = cursor.resized(&[48, 64], &ResizeOptions::default())?;
+    /// let hotspots: Vec<_> = resized.frames().map(|frame| frame.hotspot).collect();
+    /// assert_eq!(hotspots, [Hotspot::new(9, 2), Hotspot::new(13, 3)]);
+    ///
+    /// let pixel = ResizeOptions {
+    ///     hotspot: ResizeHotspot::KeepPixel,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(cursor.resized(&[16], &pixel)?[0].hotspot, Hotspot::new(6, 1));
+    ///
+    /// // An absolute hotspot has to fit every size
+    /// let absolute = ResizeOptions {
+    ///     hotspot: ResizeHotspot::Absolute(Hotspot::new(20, 20)),
+    ///     ..Default::default()
+    /// };
+    /// assert!(cursor.resized(&[16, 32], &absolute).is_err());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn resized(&self, sides: &[u32], options: &ResizeOptions) -> io::Result<CursorFile> {
+        let source = self.largest_frame().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "the cursor has no frames")
+        })?;
+        let image = source.decode_image()?;
+        let mut resized = CursorFile::empty();
+        for &side in sides {
+            let (image, hotspot) = resize_frame(&image, source.hotspot, side, options)?;
+            resized.push_frame(CursorFrame::from_image(&image, hotspot)?);
+        }
+        Ok(resized)
+    }
+}
+
+impl AniFile {
+    /// Replace the cursor in every frame with its `CursorFile::resized` to
+    /// `side`
+    ///
+    /// Timing, sequence, rates and metadata are kept, and the header follows
+    /// the first frame. On error the animation is left unchanged.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::transform::ResizeOptions;
+    /// use cursor_handler::{AniFile, FrameSize, Hotspot};
+    ///
+    /// let bytes = std::fs::read("assets/aero_busy.ani")?;
+    /// let mut ani = AniFile::decode(Cursor::new(bytes))?;
+    /// ani.resize(32, &ResizeOptions::default())?;
+    /// assert_eq!(ani[0].size, FrameSize::square(32)?);
+    /// assert_eq!(ani[0].hotspot, Hotspot::new(16, 16));
+    /// assert_eq!(ani.extract_frame(0)?.frames().len(), 1);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn resize(&mut self, side: u32, options: &ResizeOptions) -> io::Result<()> {
+        let mut frames = self.frames.clone();
+        for (frame, resized) in self.frames().zip(&mut frames) {
+            let cursor = frame.to_cursor_file()?.resized(&[side], options)?;
+            resized.image_data.clear();
+            cursor.encode(&mut resized.image_data)?;
+            resized.size = cursor[0].size;
+            resized.hotspot = cursor[0].hotspot;
+        }
+        self.frames = frames;
+        self.refresh_header();
+        Ok(())
+    }
+}
+
+fn sample_bilinear(image: &RgbaImage, x: f32, y: f32) -> Rgba<u8> {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (x0, y0) = (x0 as i64, y0 as i64);
+
+    let pixel = |x: i64, y: i64| -> [f32; 4] {
+        if x < 0 || y < 0 || x >= image.width() as i64 || y >= image.height() as i64 {
+            return [0.0; 4];
+        }
+        let Rgba([r, g, b, a]) = *image.get_pixel(x as u32, y as u32);
+        // Premultiply so transparent neighbours don't darken edges
+        let alpha = a as f32 / 255.0;
+        [
+            r as f32 * alpha,
+            g as f32 * alpha,
+            b as f32 * alpha,
+            a as f32,
+        ]
+    };
+
+    let mut out = [0.0f32; 4];
+    for (px, py, weight) in [
+        (x0, y0, (1.0 - fx) * (1.0 - fy)),
+        (x0 + 1, y0, fx * (1.0 - fy)),
+        (x0, y0 + 1, (1.0 - fx) * fy),
+        (x0 + 1, y0 + 1, fx * fy),
+    ] {
+        for (channel, value) in out.iter_mut().zip(pixel(px, py)) {
+            *channel += value * weight;
+        }
+    }
+
+    let alpha = out[3];
+    if alpha <= 0.0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let unpremultiply = |value: f32| (value * 255.0 / alpha).round().clamp(0.0, 255.0) as u8;
+    Rgba([
+        unpremultiply(out[0]),
+        unpremultiply(out[1]),
+        unpremultiply(out[2]),
+        alpha.round().clamp(0.0, 255.0) as u8,
+    ])
+}
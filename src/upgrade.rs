@@ -0,0 +1,199 @@
+// Upgrading legacy 1/4/8-bit cursors to 32-bit frames at modern sizes
+use std::io;
+
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    cur::{CursorFile, CursorFrame, payload_bit_count},
+    dib::encode_dib32,
+    manifest::PayloadKind,
+    optimize::encode_png,
+    size::{FrameSize, MAX_DIR_SIZE},
+    transform::{fit_hotspot, fit_square},
+};
+
+/// How `CursorFile::upgrade` rebuilds a cursor
+#[derive(Debug, Clone)]
+pub struct UpgradeOptions {
+    /// Square sizes to produce, in output order
+    pub target_sizes: Vec<u32>,
+    /// Feather the hard edges of frames whose alpha is all-or-nothing, as
+    /// every AND-mask cursor's is, before resampling
+    pub smooth_alpha: bool,
+    /// `Png` or `Dib` (32-bit); frames of 256px and up are PNG either way
+    pub payload: PayloadKind,
+    /// Keep the original frames after the new ones, except where a new
+    /// frame has the same size
+    pub keep_original: bool,
+}
+
+impl Default for UpgradeOptions {
+    fn default() -> Self {
+        Self {
+            target_sizes: vec![32, 48, 64, 96, 128],
+            smooth_alpha: true,
+            payload: PayloadKind::Png,
+            keep_original: false,
+        }
+    }
+}
+
+impl CursorFile {
+    /// A copy with one 32-bit or PNG frame per target size
+    ///
+    /// Each size is drawn from the frame of exactly that size when there is
+    /// one, otherwise from the largest frame, preferring the deepest payload
+    /// among equals. Non-square frames are fitted and centred, and hotspots
+    /// are carried over in proportion.
+    ///
+    /// ```
+    /// use cursor_handler::{
+    ///     CursorFile, CursorFrame, FrameSize, Hotspot, PayloadKind, UpgradeOptions,
+    /// };
+    ///
+    /// // A 16x16 1-bit cursor: a black square with a white border
+    /// let mut dib = [40u32, 16, 32, 0x0001_0001, 0, 0, 0, 0, 0, 0]
+    ///     .map(u32::to_le_bytes)
+    ///     .concat();
+    /// dib.extend_from_slice(&[0, 0, 0, 0, 255, 255, 255, 0]); // Palette
+    /// let row = |bits: u16| [(bits >> 8) as u8, bits as u8, 0, 0];
+    /// for y in 0..16 {
+    ///     dib.extend_from_slice(&row(match y {
+    ///         3 | 12 => 0x1ff8,
+    ///         4..12 => 0x1008,
+    ///         _ => 0,
+    ///     }));
+    /// }
+    /// for y in 0..16 {
+    ///     dib.extend_from_slice(&row(if (3..13).contains(&y) { 0xe007 } else { 0xffff }));
+    /// }
+    /// let legacy = CursorFile::single(CursorFrame::new(FrameSize::square(16)?, (3, 5), dib));
+    ///
+    /// let options = UpgradeOptions { target_sizes: vec![32, 48], ..Default::default() };
+    /// let upgraded = legacy.upgrade(&options)?;
+    /// let formats: Vec<_> = upgraded.frames().map(|frame| frame.payload_format()).collect();
+    /// assert_eq!(formats, ["PNG", "PNG"]);
+    /// assert_eq!(upgraded[0].size, FrameSize::square(32)?);
+    /// assert_eq!(upgraded[0].hotspot, Hotspot::new(7, 11));
+    /// assert_eq!(upgraded[1].hotspot, Hotspot::new(10, 16));
+    ///
+    /// // Mask edges come out partly transparent
+    /// let image = upgraded[0].decode_image()?;
+    /// assert!(image.pixels().any(|pixel| (1..255).contains(&pixel[3])));
+    ///
+    /// let options = UpgradeOptions {
+    ///     target_sizes: vec![32],
+    ///     payload: PayloadKind::Dib,
+    ///     keep_original: true,
+    ///     ..Default::default()
+    /// };
+    /// let upgraded = legacy.upgrade(&options)?;
+    /// let formats: Vec<_> = upgraded.frames().map(|frame| frame.payload_format()).collect();
+    /// assert_eq!(formats, ["DIB-32", "DIB-1"]);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn upgrade(&self, options: &UpgradeOptions) -> io::Result<CursorFile> {
+        if options.payload == PayloadKind::Cursor {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Upgraded frames need a PNG or DIB payload",
+            ));
+        }
+        if self.frames.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No frames"));
+        }
+
+        let depth = |frame: &CursorFrame| payload_bit_count(&frame.image_data);
+        let largest = self
+            .frames
+            .iter()
+            .max_by_key(|frame| (frame.size.max_side(), depth(frame)))
+            .expect("checked above");
+
+        let mut upgraded = CursorFile::empty();
+        for &size in &options.target_sizes {
+            let source = self
+                .frames
+                .iter()
+                .filter(|frame| frame.size.width() == size && frame.size.height() == size)
+                .max_by_key(|frame| depth(frame))
+                .unwrap_or(largest);
+
+            let mut image = source.decode_image()?;
+            if options.smooth_alpha {
+                image = feather_edges(&image);
+            }
+            let hotspot = fit_hotspot(source.hotspot, image.dimensions(), size);
+            let image = fit_square(&image, size);
+            let image_data = if options.payload == PayloadKind::Dib && size < MAX_DIR_SIZE {
+                encode_dib32(&image)
+            } else {
+                encode_png(&image)?
+            };
+            upgraded.push_frame(CursorFrame::new(
+                FrameSize::square(size)?,
+                hotspot,
+                image_data,
+            ));
+        }
+
+        if options.keep_original {
+            for frame in &self.frames {
+                if !upgraded.frames.iter().any(|new| new.size == frame.size) {
+                    upgraded.push_frame(frame.clone());
+                }
+            }
+        }
+        Ok(upgraded)
+    }
+}
+
+/// Soften an all-or-nothing alpha channel into a roughly one pixel ramp
+///
+/// Each pixel's alpha follows its distance to the nearest pixel on the other
+/// side of the edge, so the ramp follows diagonals as well as straight runs.
+/// Pixels newly made visible outside the edge take the nearest opaque
+/// pixel's color. Images with any partial alpha are returned unchanged.
+fn feather_edges(image: &RgbaImage) -> RgbaImage {
+    if image.pixels().any(|pixel| !matches!(pixel[3], 0 | 255)) {
+        return image.clone();
+    }
+
+    const RADIUS: i64 = 2;
+    let (width, height) = (image.width() as i64, image.height() as i64);
+    let opaque = |x: i64, y: i64| {
+        (0..width).contains(&x) && (0..height).contains(&y) && image[(x as u32, y as u32)][3] == 255
+    };
+
+    RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        let (x, y) = (x as i64, y as i64);
+        let inside = opaque(x, y);
+        let mut nearest: Option<(i64, i64, i64)> = None;
+        for dy in -RADIUS..=RADIUS {
+            for dx in -RADIUS..=RADIUS {
+                let distance = dx * dx + dy * dy;
+                if opaque(x + dx, y + dy) != inside
+                    && nearest.is_none_or(|(best, _, _)| distance < best)
+                {
+                    nearest = Some((distance, x + dx, y + dy));
+                }
+            }
+        }
+        let pixel = image[(x as u32, y as u32)];
+        let Some((distance, nx, ny)) = nearest else {
+            return pixel;
+        };
+
+        // Half the distance to the other side is the distance to the edge,
+        // signed positive inside; the ramp is two pixels wide
+        let edge = (distance as f32).sqrt() / 2.0;
+        let edge = if inside { edge } else { -edge };
+        let alpha = ((0.5 + edge / 2.0).clamp(0.0, 1.0) * 255.0).round() as u8;
+        let [r, g, b, _] = if inside {
+            pixel.0
+        } else {
+            image[(nx as u32, ny as u32)].0
+        };
+        Rgba([r, g, b, alpha])
+    })
+}
@@ -0,0 +1,623 @@
+// Lint pass over decoded cursors, reporting problems that do not stop decoding
+use std::{
+    fmt::{self, Display},
+    io::{self, Cursor, Seek, Write},
+};
+
+use crate::{
+    ani::{AniFile, AniFlags, AniFrame},
+    cur::{CursorFile, CursorFrame, colors_byte, payload_bit_count, payload_size},
+    hotspot::Hotspot,
+    size::MAX_FRAME_SIZE,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// A single finding; `code` is stable and safe to match on
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Issue {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub frame: Option<usize>,
+    pub message: String,
+}
+
+impl Issue {
+    pub(crate) fn new(
+        severity: Severity,
+        code: &'static str,
+        frame: Option<usize>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity,
+            code,
+            frame,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]", self.severity, self.code)?;
+        if let Some(frame) = self.frame {
+            write!(f, " frame {frame}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl CursorFile {
+    /// Check every frame, see the CUR* codes in `frame_issues`
+    pub fn validate(&self) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        if self.frames.is_empty() {
+            issues.push(Issue::new(
+                Severity::Error,
+                "CUR000",
+                None,
+                "cursor has no frames",
+            ));
+        }
+        for (index, frame) in self.frames.iter().enumerate() {
+            frame_issues(frame, Some(index), &mut issues);
+        }
+        drift_issues(&self.frames, None, &mut issues);
+        issues
+    }
+
+    /// Decode `bytes` and validate, adding checks on the directory fields
+    /// decoding doesn't keep
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{CursorFile, DecodeOptions};
+    ///
+    /// let bytes = std::fs::read("assets/windows_theme/Link.cur")?;
+    /// assert!(CursorFile::validate_bytes(&bytes)?.is_empty());
+    ///
+    /// // Junk in the reserved header word, the reserved directory byte and the
+    /// // colors byte of a 32-bit payload
+    /// for (at, junk, code) in [(0, 0xaa, "CUR011"), (9, 7, "CUR012"), (8, 16, "CUR013")] {
+    ///     let mut corrupt = bytes.clone();
+    ///     corrupt[at] = junk;
+    ///     let issues = CursorFile::validate_bytes(&corrupt)?;
+    ///     assert_eq!(issues.iter().map(|issue| issue.code).collect::<Vec<_>>(), [code]);
+    ///
+    ///     // Lenient decoding records a warning, strict decoding names the offset
+    ///     let decode = |options| CursorFile::decode_with(Cursor::new(&corrupt), &options);
+    ///     assert_eq!(decode(DecodeOptions::lenient())?.warnings[0].offset, at as u64);
+    ///     let strict = decode(DecodeOptions::strict()).unwrap_err();
+    ///     assert!(strict.to_string().contains(&format!("at {at:#X}")));
+    ///
+    ///     // Sanitizing reports the fix and encoding writes clean fields
+    ///     let (cursor, report) = CursorFile::sanitize_bytes(&corrupt, &Default::default())?;
+    ///     assert_eq!(report.fixes[0].code, code);
+    ///     let mut clean = Vec::new();
+    ///     cursor.encode(&mut clean)?;
+    ///     assert_eq!(clean, bytes);
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn validate_bytes(bytes: &[u8]) -> io::Result<Vec<Issue>> {
+        let mut issues = Self::decode(Cursor::new(bytes))?.validate();
+        directory_fields(bytes, &mut issues);
+        Ok(issues)
+    }
+
+    /// Encode, refusing to write a file with error-level issues
+    pub fn encode_strict<W: Write>(&self, writer: W) -> io::Result<()> {
+        reject_errors(self.validate())?;
+        self.encode(writer)
+    }
+}
+
+impl AniFile {
+    pub fn validate(&self) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        let frame_count = self.frames.len();
+
+        if frame_count == 0 {
+            issues.push(Issue::new(
+                Severity::Error,
+                "ANI001",
+                None,
+                "animation has no frames",
+            ));
+        }
+        if let Some(declared) = self.header.declared_frames()
+            && declared as usize != frame_count
+        {
+            issues.push(Issue::new(
+                Severity::Warning,
+                "ANI002",
+                None,
+                format!("header declares {declared} frames, found {frame_count}"),
+            ));
+        }
+        if let Some(declared) = self.header.declared_steps()
+            && declared as usize != self.sequence.len()
+        {
+            issues.push(Issue::new(
+                Severity::Warning,
+                "ANI003",
+                None,
+                format!(
+                    "header declares {declared} steps, sequence has {}",
+                    self.sequence.len()
+                ),
+            ));
+        }
+        for (step, &index) in self.sequence.iter().enumerate() {
+            if index as usize >= frame_count {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    "ANI004",
+                    None,
+                    format!("step {step} refers to missing frame {index}"),
+                ));
+            }
+        }
+        if !self.rates.is_empty() && self.rates.len() != self.sequence.len() {
+            issues.push(Issue::new(
+                Severity::Warning,
+                "ANI005",
+                None,
+                format!(
+                    "{} rates for {} steps",
+                    self.rates.len(),
+                    self.sequence.len()
+                ),
+            ));
+        }
+        if self.header.default_rate() == 0 || self.rates.contains(&0) {
+            issues.push(Issue::new(
+                Severity::Warning,
+                "ANI006",
+                None,
+                "a step has a zero duration",
+            ));
+        }
+        if !self.header.ani_flags().contains(AniFlags::FRAMES_ARE_ICONS)
+            && self.frames().all(AniFrame::is_container)
+        {
+            issues.push(Issue::new(
+                Severity::Warning,
+                "ANI007",
+                None,
+                "frames are cursor containers but the AF_ICON flag is not set",
+            ));
+        }
+        let custom_sequence = self.sequence != (0..frame_count as u32).collect::<Vec<_>>();
+        if custom_sequence && !self.header.ani_flags().contains(AniFlags::HAS_SEQUENCE) {
+            issues.push(Issue::new(
+                Severity::Warning,
+                "ANI008",
+                None,
+                "sequence is reordered but the AF_SEQUENCE flag is not set",
+            ));
+        }
+        if let Some((width, height, bit_count)) = self.header_disagreement() {
+            issues.push(Issue::new(
+                Severity::Warning,
+                "ANI013",
+                None,
+                format!(
+                    "header says {}x{} at {} bits, first frame is {width}x{height} at {bit_count}",
+                    self.header.width(),
+                    self.header.height(),
+                    self.header.bit_count()
+                ),
+            ));
+        }
+
+        for variant in &self.id_variants {
+            issues.push(Issue::new(
+                Severity::Warning,
+                "ANI014",
+                None,
+                format!(
+                    "'{}' is written where '{}' belongs",
+                    variant.raw.escape_ascii(),
+                    variant.id.escape_ascii()
+                ),
+            ));
+        }
+
+        if let Some(first) = self.steps().next() {
+            let first = first.frame.hotspot;
+            if let Some(step) = self.steps().find(|step| step.frame.hotspot != first) {
+                issues.push(Issue::new(
+                    Severity::Warning,
+                    "ANI012",
+                    Some(step.frame_index),
+                    format!(
+                        "hotspot {} differs from {first} in earlier steps",
+                        step.frame.hotspot
+                    ),
+                ));
+            }
+        }
+
+        for (index, frame) in self.frames.iter().enumerate() {
+            if !self.sequence.contains(&(index as u32)) {
+                issues.push(Issue::new(
+                    Severity::Info,
+                    "ANI009",
+                    Some(index),
+                    "frame is never played",
+                ));
+            }
+            if let Some(payload) = frame.payload_hotspot()
+                && payload != frame.hotspot
+            {
+                issues.push(Issue::new(
+                    Severity::Warning,
+                    "ANI015",
+                    Some(index),
+                    format!(
+                        "hotspot {} differs from {payload} in the payload, which Windows uses; \
+                         see `set_hotspot`",
+                        frame.hotspot
+                    ),
+                ));
+            }
+            match CursorFile::decode(Cursor::new(&frame.image_data)) {
+                Ok(cursor) => {
+                    for embedded in &cursor.frames {
+                        frame_issues(embedded, Some(index), &mut issues);
+                    }
+                    drift_issues(&cursor.frames, Some(index), &mut issues);
+                }
+                Err(err) => issues.push(Issue::new(
+                    if frame.image_data.starts_with(&[0, 0, 1, 0]) {
+                        Severity::Info
+                    } else {
+                        Severity::Error
+                    },
+                    "ANI010",
+                    Some(index),
+                    format!("frame is not a cursor container: {err}"),
+                )),
+            }
+        }
+
+        issues
+    }
+
+    /// Decode `bytes` and validate, adding checks that need the raw chunk layout
+    pub fn validate_bytes(bytes: &[u8]) -> io::Result<Vec<Issue>> {
+        let mut issues = Self::decode(Cursor::new(bytes))?.validate();
+        if let Some(body) = bytes.get(12..) {
+            chunk_padding(body, 12, &mut issues);
+        }
+        Ok(issues)
+    }
+
+    /// Encode, refusing to write a file with error-level issues
+    pub fn encode_strict<W: Write + Seek>(&self, writer: W) -> io::Result<()> {
+        reject_errors(self.validate())?;
+        self.encode(writer)
+    }
+}
+
+/// CUR001 hotspot outside the frame, CUR002 directory size disagrees with the
+/// payload, CUR003 non-standard size, CUR004 256px+ frame stored as a DIB,
+/// CUR005 frame over `MAX_FRAME_SIZE`, CUR006 unrecognised payload,
+/// CUR007 payload length disagrees with its own headers, CUR008 AND mask
+/// disagrees with the alpha channel, CUR009 top-down DIB, CUR010 RLE DIB
+fn frame_issues(frame: &CursorFrame, index: Option<usize>, issues: &mut Vec<Issue>) {
+    let (width, height) = (frame.size.width(), frame.size.height());
+    if !frame.hotspot.is_within(width, height) {
+        issues.push(Issue::new(
+            Severity::Error,
+            "CUR001",
+            index,
+            format!(
+                "hotspot {} is outside the {} frame",
+                frame.hotspot, frame.size
+            ),
+        ));
+    }
+
+    match (frame.dib_info(), payload_size(&frame.image_data)) {
+        (Some(info), _) if !info.consistent_with(width, height) => issues.push(Issue::new(
+            Severity::Warning,
+            "CUR002",
+            index,
+            format!("directory says {}, DIB header is {info}", frame.size),
+        )),
+        (None, Some(payload)) if payload != (width, height) => issues.push(Issue::new(
+            Severity::Warning,
+            "CUR002",
+            index,
+            format!(
+                "directory says {}, payload is {}x{}",
+                frame.size, payload.0, payload.1
+            ),
+        )),
+        (Some(_), _) | (None, Some(_)) => {}
+        (None, None) => issues.push(Issue::new(
+            Severity::Error,
+            "CUR006",
+            index,
+            "payload is neither PNG nor a DIB",
+        )),
+    }
+
+    if !frame.size.is_standard() {
+        issues.push(Issue::new(
+            Severity::Info,
+            "CUR003",
+            index,
+            format!("{} is not a standard cursor size", frame.size),
+        ));
+    }
+    if frame.size.max_side() >= 256 && !frame.is_png() {
+        issues.push(Issue::new(
+            Severity::Warning,
+            "CUR004",
+            index,
+            "frames of 256px and up should use PNG payloads",
+        ));
+    }
+    if frame.size.max_side() > MAX_FRAME_SIZE {
+        issues.push(Issue::new(
+            Severity::Error,
+            "CUR005",
+            index,
+            format!("frames over {MAX_FRAME_SIZE}px can't be encoded"),
+        ));
+    }
+    if let Some(expected) = frame.expected_len()
+        && expected != frame.image_data.len()
+    {
+        issues.push(Issue::new(
+            Severity::Warning,
+            "CUR007",
+            index,
+            format!(
+                "payload is {} bytes, its headers describe {expected}",
+                frame.image_data.len()
+            ),
+        ));
+    }
+    if frame.dib_info().is_some_and(|info| info.top_down()) {
+        issues.push(Issue::new(
+            Severity::Warning,
+            "CUR009",
+            index,
+            "DIB rows are stored top-down, which older Windows versions reject",
+        ));
+    }
+    if frame.dib_info().is_some_and(|info| info.is_rle()) {
+        issues.push(Issue::new(
+            Severity::Warning,
+            "CUR010",
+            index,
+            "DIB is RLE-compressed, which few programs besides Windows read",
+        ));
+    }
+    if let Some(drifted) = mask_drift(frame)
+        && drifted > 0
+    {
+        issues.push(Issue::new(
+            Severity::Warning,
+            "CUR008",
+            index,
+            format!("AND mask disagrees with the alpha channel at {drifted} pixels"),
+        ));
+    }
+}
+
+/// CUR014 for each size of a cursor, or of the cursor in `.ani` frame
+/// `ani_frame`, whose hotspot drifts from the others
+fn drift_issues(frames: &[CursorFrame], ani_frame: Option<usize>, issues: &mut Vec<Issue>) {
+    for (index, expected) in hotspot_drift(frames) {
+        let frame = &frames[index];
+        issues.push(Issue::new(
+            Severity::Warning,
+            "CUR014",
+            ani_frame.or(Some(index)),
+            format!(
+                "hotspot {} at {} is off the point the other sizes mark, {expected} \
+                 (see `normalize_hotspots`)",
+                frame.hotspot, frame.size
+            ),
+        ));
+    }
+}
+
+/// Frames whose hotspot is more than a pixel off the point most frames
+/// agree on, with where it should be
+///
+/// Each frame's hotspot is tried as the reference; the one the most frames
+/// agree with wins, the first on a tie. Hotspots outside their frame are
+/// left to CUR001.
+fn hotspot_drift(frames: &[CursorFrame]) -> Vec<(usize, Hotspot)> {
+    let frames: Vec<_> = frames
+        .iter()
+        .enumerate()
+        .filter(|(_, frame)| {
+            frame
+                .hotspot
+                .is_within(frame.size.width(), frame.size.height())
+        })
+        .collect();
+    let expected = |reference: &CursorFrame, frame: &CursorFrame| {
+        let fraction = reference
+            .hotspot
+            .fraction(reference.size.width(), reference.size.height());
+        Hotspot::from_fraction(fraction, frame.size.width(), frame.size.height())
+    };
+    let agrees = |reference: &CursorFrame, frame: &CursorFrame| {
+        let expected = expected(reference, frame);
+        expected.x.abs_diff(frame.hotspot.x) <= 1 && expected.y.abs_diff(frame.hotspot.y) <= 1
+    };
+    let Some(&(_, reference)) = frames.iter().rev().max_by_key(|(_, reference)| {
+        frames
+            .iter()
+            .filter(|(_, frame)| agrees(reference, frame))
+            .count()
+    }) else {
+        return Vec::new();
+    };
+    frames
+        .iter()
+        .filter(|(_, frame)| !agrees(reference, frame))
+        .map(|&(index, frame)| (index, expected(reference, frame)))
+        .collect()
+}
+
+/// Pixels of a 32-bit DIB that are opaque but masked, or fully transparent
+/// but unmasked; `None` when there is no alpha to compare with
+fn mask_drift(frame: &CursorFrame) -> Option<usize> {
+    let color = frame.xor_data().filter(|plane| plane.bit_count == 32)?;
+    let mask = frame.and_mask()?;
+    // Older 32-bit cursors leave alpha at 0 and rely on the mask alone
+    if color
+        .rows()
+        .all(|row| row.iter().skip(3).step_by(4).all(|&alpha| alpha == 0))
+    {
+        return None;
+    }
+
+    let mut drifted = 0;
+    for (y, row) in color.rows().enumerate() {
+        for x in 0..color.width {
+            let alpha = row[x as usize * 4 + 3];
+            let masked = mask.get(x, y as u32);
+            if (alpha == 0 && !masked) || (alpha == 255 && masked) {
+                drifted += 1;
+            }
+        }
+    }
+    Some(drifted)
+}
+
+/// CUR011 nonzero reserved header word, CUR012 nonzero reserved directory
+/// byte, CUR013 colors byte that disagrees with the payload's bit depth
+pub(crate) fn directory_fields(bytes: &[u8], issues: &mut Vec<Issue>) {
+    if let Some(reserved) = bytes.get(0..2).filter(|reserved| *reserved != [0, 0]) {
+        issues.push(Issue::new(
+            Severity::Warning,
+            "CUR011",
+            None,
+            format!(
+                "reserved header word is {:#06x}",
+                u16::from_le_bytes([reserved[0], reserved[1]])
+            ),
+        ));
+    }
+
+    let count = bytes
+        .get(4..6)
+        .map_or(0, |count| u16::from_le_bytes([count[0], count[1]]));
+    for index in 0..count as usize {
+        let Some(entry) = bytes.get(6 + index * 16..6 + (index + 1) * 16) else {
+            break;
+        };
+        if entry[3] != 0 {
+            issues.push(Issue::new(
+                Severity::Warning,
+                "CUR012",
+                Some(index),
+                format!("reserved directory byte is {}", entry[3]),
+            ));
+        }
+
+        let word = |at: usize| u32::from_le_bytes(entry[at..at + 4].try_into().unwrap()) as usize;
+        let (size, offset) = (word(8), word(12));
+        let payload = bytes
+            .get(offset..offset.saturating_add(size))
+            .unwrap_or_default();
+        let depth = payload_bit_count(payload);
+        if entry[2] != 0 && entry[2] != colors_byte(depth) {
+            issues.push(Issue::new(
+                Severity::Warning,
+                "CUR013",
+                Some(index),
+                format!(
+                    "colors byte is {}, a {depth}-bit payload needs {}",
+                    entry[2],
+                    colors_byte(depth)
+                ),
+            ));
+        }
+    }
+}
+
+/// ANI011: an odd-sized chunk is not followed by its pad byte
+pub(crate) fn chunk_padding(data: &[u8], base: usize, issues: &mut Vec<Issue>) {
+    let mut pos = 0;
+    while let Some(header) = data.get(pos..pos + 8) {
+        let id = &header[0..4];
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let end = pos + 8 + size;
+        if id == b"LIST"
+            && let Some(list) = data.get(pos + 12..end.min(data.len()))
+        {
+            chunk_padding(list, base + pos + 12, issues);
+        }
+
+        if size.is_multiple_of(2) {
+            pos = end;
+            continue;
+        }
+        let padded = data.get(end + 1..end + 5).is_some_and(is_chunk_id);
+        let unpadded = end == data.len() || data.get(end..end + 4).is_some_and(is_chunk_id);
+        if unpadded && !padded {
+            issues.push(Issue::new(
+                Severity::Warning,
+                "ANI011",
+                None,
+                format!(
+                    "{} chunk at offset {} is missing its pad byte",
+                    String::from_utf8_lossy(id),
+                    base + pos
+                ),
+            ));
+            pos = end;
+        } else {
+            pos = end + 1;
+        }
+    }
+}
+
+fn is_chunk_id(id: &[u8]) -> bool {
+    id.iter()
+        .all(|&byte| byte.is_ascii_alphanumeric() || byte == b' ')
+}
+
+fn reject_errors(issues: Vec<Issue>) -> io::Result<()> {
+    match issues
+        .into_iter()
+        .find(|issue| issue.severity == Severity::Error)
+    {
+        Some(issue) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            issue.to_string(),
+        )),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,63 @@
+// wasm-bindgen entry points for browser use
+//
+// Everything here works on byte slices; no filesystem or threads are used.
+use std::io::Cursor;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{ani::AniFile, cur::CursorFile, export, manifest::Manifest};
+
+/// An RGBA frame shaped like the browser's `ImageData`
+#[wasm_bindgen]
+pub struct FrameImage {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl FrameImage {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Straight-alpha RGBA bytes, row-major, for `new ImageData(...)`
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+/// Parse a .cur (or .ani) file and return its manifest as a JS object
+#[wasm_bindgen]
+pub fn parse_cur(bytes: &[u8]) -> Result<JsValue, JsError> {
+    let manifest = if bytes.starts_with(b"RIFF") {
+        Manifest::from(&AniFile::decode(Cursor::new(bytes))?)
+    } else {
+        Manifest::from(&CursorFile::decode(Cursor::new(bytes))?)
+    };
+    Ok(serde_wasm_bindgen::to_value(&manifest)?)
+}
+
+/// Convert an .ani file into an APNG
+#[wasm_bindgen]
+pub fn ani_to_apng(bytes: &[u8]) -> Result<Vec<u8>, JsError> {
+    Ok(AniFile::decode(Cursor::new(bytes))?.to_apng()?)
+}
+
+/// Decode one stored frame of a .cur or .ani file to RGBA
+#[wasm_bindgen]
+pub fn frame_rgba(bytes: &[u8], index: usize) -> Result<FrameImage, JsError> {
+    let image = export::frame_rgba(bytes, index)?;
+    Ok(FrameImage {
+        width: image.width(),
+        height: image.height(),
+        data: image.into_raw(),
+    })
+}
@@ -0,0 +1,864 @@
+// Live system cursor access through user32 and the registry
+//
+// Only Windows has a real implementation; elsewhere every entry point
+// returns an `Unsupported` error so the crate builds on all platforms.
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use image::RgbaImage;
+
+use crate::{
+    ani::AniFile,
+    cur::{CursorFile, CursorFrame},
+    file::TempCursorFile,
+    hotspot::Hotspot,
+    optimize::encode_png,
+    size::FrameSize,
+};
+pub use crate::{file::CursorRef, system_cursor::SystemCursor};
+
+/// Where a role's current cursor comes from
+#[derive(Debug, Clone)]
+pub enum CursorSource {
+    /// A static cursor; `path` is `None` for the built-in default
+    Static {
+        path: Option<PathBuf>,
+        cursor: CursorFile,
+    },
+    /// An animated cursor file
+    Animated { path: PathBuf, cursor: AniFile },
+}
+
+impl CursorSource {
+    fn load(path: PathBuf) -> io::Result<Self> {
+        let bytes = fs::read(&path)?;
+        if bytes.starts_with(b"RIFF") {
+            let cursor = AniFile::decode(io::Cursor::new(bytes))?;
+            Ok(Self::Animated { path, cursor })
+        } else {
+            let cursor = CursorFile::decode(io::Cursor::new(bytes))?;
+            Ok(Self::Static {
+                path: Some(path),
+                cursor,
+            })
+        }
+    }
+
+    /// The file the registry points at, if any
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Static { path, .. } => path.as_deref(),
+            Self::Animated { path, .. } => Some(path),
+        }
+    }
+
+    pub fn cursor(&self) -> CursorRef<'_> {
+        match self {
+            Self::Static { cursor, .. } => CursorRef::Cur(cursor),
+            Self::Animated { cursor, .. } => CursorRef::Ani(cursor),
+        }
+    }
+}
+
+/// Read the user's current cursor for every role
+///
+/// Roles set to a file under `HKCU\Control Panel\Cursors` are decoded from
+/// that file; roles left at the built-in default are captured from the live
+/// cursor and rebuilt as a single-frame PNG cursor.
+pub fn current_scheme() -> io::Result<Vec<(SystemCursor, CursorSource)>> {
+    sys::check_supported()?;
+
+    SystemCursor::ALL
+        .iter()
+        .map(|&role| {
+            let source = match sys::registry_cursor_path(role.registry_name())? {
+                Some(path) => CursorSource::load(path)?,
+                None => {
+                    let captured = sys::capture_cursor(role.ocr_id())?;
+                    CursorSource::Static {
+                        path: None,
+                        cursor: captured.into_cursor()?,
+                    }
+                }
+            };
+            Ok((role, source))
+        })
+        .collect()
+}
+
+/// Which registry hive a scheme is installed into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// `HKCU`, visible to the current user only
+    User,
+    /// `HKLM`, visible to every user; needs administrator rights
+    Machine,
+}
+
+impl Scope {
+    fn schemes_key(self) -> RegistryKey {
+        match self {
+            Self::User => RegistryKey::user("Control Panel\\Cursors\\Schemes"),
+            Self::Machine => RegistryKey::machine(
+                "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Control Panel\\Cursors\\Schemes",
+            ),
+        }
+    }
+
+    /// The `Scheme Source` value Control Panel uses to find the scheme
+    fn scheme_source(self) -> u32 {
+        match self {
+            Self::User => 1,
+            Self::Machine => 2,
+        }
+    }
+}
+
+/// Options for [`install_scheme`] and [`uninstall_scheme`]
+#[derive(Debug, Clone)]
+pub struct InstallOptions {
+    /// Directory the scheme folder is created in; defaults to `%APPDATA%\Cursors`
+    pub install_dir: Option<PathBuf>,
+    /// Make the scheme the active one after installing
+    pub activate: bool,
+    /// Only compute the changes, without touching files or the registry
+    pub dry_run: bool,
+}
+
+impl Default for InstallOptions {
+    fn default() -> Self {
+        Self {
+            install_dir: None,
+            activate: true,
+            dry_run: false,
+        }
+    }
+}
+
+impl InstallOptions {
+    fn scheme_dir(&self, name: &str) -> io::Result<PathBuf> {
+        let base = match &self.install_dir {
+            Some(dir) => dir.clone(),
+            None => std::env::var_os("APPDATA")
+                .map(|appdata| PathBuf::from(appdata).join("Cursors"))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "APPDATA is not set; pass an install dir",
+                    )
+                })?,
+        };
+        Ok(base.join(name))
+    }
+}
+
+/// A registry hive and subkey
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryKey {
+    pub machine: bool,
+    pub path: String,
+}
+
+impl RegistryKey {
+    fn user(path: &str) -> Self {
+        Self {
+            machine: false,
+            path: path.into(),
+        }
+    }
+
+    fn machine(path: &str) -> Self {
+        Self {
+            machine: true,
+            path: path.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for RegistryKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hive = if self.machine { "HKLM" } else { "HKCU" };
+        write!(f, "{hive}\\{}", self.path)
+    }
+}
+
+/// A registry value to write
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryValue {
+    String(String),
+    Dword(u32),
+}
+
+/// One step of installing or removing a scheme
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemeChange {
+    CopyFile {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    RemoveDir(PathBuf),
+    SetValue {
+        key: RegistryKey,
+        name: String,
+        value: RegistryValue,
+    },
+    DeleteValue {
+        key: RegistryKey,
+        name: String,
+    },
+    /// Reload the cursors from the registry and notify running programs
+    ReloadCursors,
+}
+
+impl std::fmt::Display for SchemeChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CopyFile { from, to } => write!(f, "copy {} -> {}", from.display(), to.display()),
+            Self::RemoveDir(dir) => write!(f, "remove {}", dir.display()),
+            Self::SetValue { key, name, value } => {
+                let name = if name.is_empty() { "(Default)" } else { name };
+                match value {
+                    RegistryValue::String(value) => write!(f, "set {key}\\{name} = \"{value}\""),
+                    RegistryValue::Dword(value) => write!(f, "set {key}\\{name} = {value}"),
+                }
+            }
+            Self::DeleteValue { key, name } => write!(f, "delete {key}\\{name}"),
+            Self::ReloadCursors => write!(f, "reload system cursors"),
+        }
+    }
+}
+
+/// Install a cursor scheme, returning the changes made
+///
+/// Each mapped file is copied into the scheme folder and the scheme is
+/// registered under `Cursors\Schemes` as the 15 comma-separated paths
+/// Windows expects, in [`SystemCursor::ALL`] order. Unmapped roles leave their
+/// slot empty, which means the system default. A source mapped to several
+/// roles is copied once; different sources sharing a file name are told
+/// apart by naming the later ones after their role, as in `IBeam.cur`.
+/// `HKLM` is only written for [`Scope::Machine`]. With `dry_run` the changes
+/// are returned but not made.
+pub fn install_scheme(
+    name: &str,
+    mapping: &[(SystemCursor, PathBuf)],
+    scope: Scope,
+    options: &InstallOptions,
+) -> io::Result<Vec<SchemeChange>> {
+    validate_scheme_name(name)?;
+    let dir = options.scheme_dir(name)?;
+
+    let mut changes = Vec::new();
+    let mut slots = vec![String::new(); SystemCursor::ALL.len()];
+    // File name -> source of each copy
+    let mut copied: Vec<(String, &Path)> = Vec::new();
+    for (role, source) in mapping {
+        let file_name = target_name(*role, source, &copied)?;
+        let target = dir.join(&file_name);
+
+        let slot = SystemCursor::ALL.iter().position(|r| r == role).unwrap();
+        slots[slot] = target.display().to_string();
+        if !copied.iter().any(|(name, _)| *name == file_name) {
+            copied.push((file_name, source));
+            changes.push(SchemeChange::CopyFile {
+                from: source.clone(),
+                to: target,
+            });
+        }
+    }
+
+    changes.push(SchemeChange::SetValue {
+        key: scope.schemes_key(),
+        name: name.into(),
+        value: RegistryValue::String(slots.join(",")),
+    });
+
+    if options.activate {
+        let cursors = RegistryKey::user("Control Panel\\Cursors");
+        changes.push(SchemeChange::SetValue {
+            key: cursors.clone(),
+            name: String::new(),
+            value: RegistryValue::String(name.into()),
+        });
+        changes.push(SchemeChange::SetValue {
+            key: cursors.clone(),
+            name: "Scheme Source".into(),
+            value: RegistryValue::Dword(scope.scheme_source()),
+        });
+        for (role, path) in SystemCursor::ALL.iter().zip(slots) {
+            changes.push(SchemeChange::SetValue {
+                key: cursors.clone(),
+                name: role.registry_name().into(),
+                value: RegistryValue::String(path),
+            });
+        }
+        changes.push(SchemeChange::ReloadCursors);
+    }
+
+    if !options.dry_run {
+        apply_changes(&changes)?;
+    }
+    Ok(changes)
+}
+
+/// Remove a scheme registered by [`install_scheme`] along with its folder
+///
+/// The active cursors are left alone; switch schemes first if it is in use.
+pub fn uninstall_scheme(
+    name: &str,
+    scope: Scope,
+    options: &InstallOptions,
+) -> io::Result<Vec<SchemeChange>> {
+    validate_scheme_name(name)?;
+
+    let changes = vec![
+        SchemeChange::DeleteValue {
+            key: scope.schemes_key(),
+            name: name.into(),
+        },
+        SchemeChange::RemoveDir(options.scheme_dir(name)?),
+    ];
+
+    if !options.dry_run {
+        apply_changes(&changes)?;
+    }
+    Ok(changes)
+}
+
+/// The file name `source` is copied to for `role`, given the files copied
+/// so far; names are compared ignoring case, as Windows does
+fn target_name(
+    role: SystemCursor,
+    source: &Path,
+    copied: &[(String, &Path)],
+) -> io::Result<String> {
+    if let Some((name, _)) = copied.iter().find(|(_, from)| *from == source) {
+        return Ok(name.clone());
+    }
+    let file_name = source
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a file", source.display()),
+            )
+        })?;
+
+    let taken_by = |name: &str| {
+        let name = name.to_lowercase();
+        copied
+            .iter()
+            .find(|(copied, _)| copied.to_lowercase() == name)
+            .map(|(_, from)| *from)
+    };
+    if taken_by(&file_name).is_none() {
+        return Ok(file_name);
+    }
+
+    let extension = source
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let renamed = format!("{}{extension}", role.registry_name());
+    match taken_by(&renamed) {
+        None => Ok(renamed),
+        Some(from) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} and {} would both be installed as {renamed}",
+                from.display(),
+                source.display()
+            ),
+        )),
+    }
+}
+
+fn validate_scheme_name(name: &str) -> io::Result<()> {
+    if name.is_empty() || name.contains(['\\', '/', ',']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid scheme name {name:?}"),
+        ));
+    }
+    Ok(())
+}
+
+fn apply_changes(changes: &[SchemeChange]) -> io::Result<()> {
+    sys::check_supported()?;
+
+    for change in changes {
+        match change {
+            SchemeChange::CopyFile { from, to } => {
+                if let Some(parent) = to.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(from, to)?;
+            }
+            SchemeChange::RemoveDir(dir) => match fs::remove_dir_all(dir) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            },
+            SchemeChange::SetValue { key, name, value } => {
+                sys::set_registry_value(key, name, value)?
+            }
+            SchemeChange::DeleteValue { key, name } => sys::delete_registry_value(key, name)?,
+            SchemeChange::ReloadCursors => sys::reload_cursors()?,
+        }
+    }
+    Ok(())
+}
+
+/// Raw planes of a live cursor as returned by GetDIBits (32-bit BGRA, top-down)
+struct CapturedCursor {
+    width: u32,
+    height: u32,
+    hotspot: Hotspot,
+    /// Color plane; `None` for monochrome cursors
+    color: Option<Vec<u8>>,
+    /// AND mask, followed by the XOR plane for monochrome cursors
+    mask: Vec<u8>,
+}
+
+impl CapturedCursor {
+    fn into_cursor(self) -> io::Result<CursorFile> {
+        let image = self.merge_planes();
+        Ok(CursorFile::single(CursorFrame::new(
+            FrameSize::new(self.width, self.height)?,
+            self.hotspot,
+            encode_png(&image)?,
+        )))
+    }
+
+    /// Combine the color and mask planes into straight RGBA
+    ///
+    /// Pixels the cursor would invert on screen come out opaque black.
+    fn merge_planes(&self) -> RgbaImage {
+        let pixels = (self.width * self.height) as usize;
+        let set = |plane: &[u8], index: usize| plane[index * 4..index * 4 + 3] != [0, 0, 0];
+        let has_alpha = self
+            .color
+            .as_ref()
+            .is_some_and(|color| color.chunks_exact(4).any(|bgra| bgra[3] != 0));
+
+        let mut image = RgbaImage::new(self.width, self.height);
+        for (index, pixel) in image.pixels_mut().enumerate() {
+            let and = set(&self.mask, index);
+            pixel.0 = match &self.color {
+                Some(color) if has_alpha => {
+                    let bgra = &color[index * 4..index * 4 + 4];
+                    [bgra[2], bgra[1], bgra[0], bgra[3]]
+                }
+                Some(color) => {
+                    let bgra = &color[index * 4..index * 4 + 4];
+                    if and {
+                        [0, 0, 0, 0]
+                    } else {
+                        [bgra[2], bgra[1], bgra[0], 255]
+                    }
+                }
+                None => match (and, set(&self.mask, pixels + index)) {
+                    (true, false) => [0, 0, 0, 0],
+                    (false, true) => [255, 255, 255, 255],
+                    _ => [0, 0, 0, 255],
+                },
+            };
+        }
+        image
+    }
+}
+
+/// A cursor set with [`apply`]; dropping it restores the user's scheme
+#[derive(Debug)]
+pub struct AppliedCursor {
+    role: SystemCursor,
+    file: TempCursorFile,
+}
+
+impl AppliedCursor {
+    pub fn role(&self) -> SystemCursor {
+        self.role
+    }
+
+    /// The temporary file Windows loaded the cursor from
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+}
+
+impl Drop for AppliedCursor {
+    fn drop(&mut self) {
+        // Nothing useful to do if reloading fails while dropping
+        let _ = sys::restore_scheme();
+    }
+}
+
+/// Make `file` the active cursor for `role` until the guard is dropped
+///
+/// The cursor is written to a temporary file and loaded with
+/// LoadCursorFromFileW, which handles both static and animated cursors.
+pub fn apply<'a>(file: impl Into<CursorRef<'a>>, role: SystemCursor) -> io::Result<AppliedCursor> {
+    sys::check_supported()?;
+
+    let file = TempCursorFile::write(file.into())?;
+    sys::set_system_cursor(file.path(), role.ocr_id())?;
+    Ok(AppliedCursor { role, file })
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::{
+        ffi::OsString,
+        io, mem,
+        os::windows::ffi::{OsStrExt, OsStringExt},
+        path::{Path, PathBuf},
+        ptr,
+    };
+
+    use windows_sys::Win32::{
+        Foundation::{ERROR_FILE_NOT_FOUND, ERROR_SUCCESS},
+        Graphics::Gdi::{
+            BI_RGB, BITMAP, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, DeleteObject, GetDC,
+            GetDIBits, GetObjectW, HBITMAP, HDC, ReleaseDC,
+        },
+        System::Registry::{
+            HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_SET_VALUE, REG_DWORD, REG_EXPAND_SZ,
+            REG_OPTION_NON_VOLATILE, RRF_RT_REG_SZ, RegCloseKey, RegCreateKeyExW, RegDeleteValueW,
+            RegGetValueW, RegOpenKeyExW, RegSetValueExW,
+        },
+        UI::WindowsAndMessaging::{
+            CopyIcon, DestroyCursor, DestroyIcon, GetIconInfo, ICONINFO, LoadCursorFromFileW,
+            LoadCursorW, SPI_SETCURSORS, SPIF_SENDCHANGE, SPIF_UPDATEINIFILE, SetSystemCursor,
+            SystemParametersInfoW,
+        },
+    };
+
+    use super::{CapturedCursor, RegistryKey, RegistryValue};
+
+    const CURSORS_KEY: &str = "Control Panel\\Cursors";
+
+    fn wide(value: impl AsRef<std::ffi::OsStr>) -> Vec<u16> {
+        value.as_ref().encode_wide().chain(Some(0)).collect()
+    }
+
+    pub fn check_supported() -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn set_system_cursor(path: &Path, id: u32) -> io::Result<()> {
+        let path = wide(path);
+
+        let cursor = unsafe { LoadCursorFromFileW(path.as_ptr()) };
+        if cursor.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        // On success the system owns the cursor and destroys it itself
+        if unsafe { SetSystemCursor(cursor, id) } == 0 {
+            let err = io::Error::last_os_error();
+            unsafe { DestroyCursor(cursor) };
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Reload every system cursor from the registry
+    pub fn restore_scheme() -> io::Result<()> {
+        if unsafe { SystemParametersInfoW(SPI_SETCURSORS, 0, ptr::null_mut(), 0) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Reload cursors from the registry, persisting and broadcasting the change
+    pub fn reload_cursors() -> io::Result<()> {
+        let flags = SPIF_UPDATEINIFILE | SPIF_SENDCHANGE;
+        if unsafe { SystemParametersInfoW(SPI_SETCURSORS, 0, ptr::null_mut(), flags) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn check(status: u32) -> io::Result<()> {
+        if status != ERROR_SUCCESS {
+            return Err(io::Error::from_raw_os_error(status as i32));
+        }
+        Ok(())
+    }
+
+    fn hive(key: &RegistryKey) -> HKEY {
+        if key.machine {
+            HKEY_LOCAL_MACHINE
+        } else {
+            HKEY_CURRENT_USER
+        }
+    }
+
+    pub fn set_registry_value(
+        key: &RegistryKey,
+        name: &str,
+        value: &RegistryValue,
+    ) -> io::Result<()> {
+        let (path, name) = (wide(&key.path), wide(name));
+
+        let mut handle: HKEY = ptr::null_mut();
+        check(unsafe {
+            RegCreateKeyExW(
+                hive(key),
+                path.as_ptr(),
+                0,
+                ptr::null(),
+                REG_OPTION_NON_VOLATILE,
+                KEY_SET_VALUE,
+                ptr::null(),
+                &mut handle,
+                ptr::null_mut(),
+            )
+        })?;
+
+        let (kind, data) = match value {
+            // Expandable so paths under %APPDATA% or %SystemRoot% keep working
+            RegistryValue::String(text) => {
+                let text = wide(text);
+                let bytes = text
+                    .iter()
+                    .flat_map(|c| c.to_le_bytes())
+                    .collect::<Vec<_>>();
+                (REG_EXPAND_SZ, bytes)
+            }
+            RegistryValue::Dword(number) => (REG_DWORD, number.to_le_bytes().to_vec()),
+        };
+
+        let status = unsafe {
+            RegSetValueExW(
+                handle,
+                name.as_ptr(),
+                0,
+                kind,
+                data.as_ptr(),
+                data.len() as u32,
+            )
+        };
+        unsafe { RegCloseKey(handle) };
+        check(status)
+    }
+
+    pub fn delete_registry_value(key: &RegistryKey, name: &str) -> io::Result<()> {
+        let (path, name) = (wide(&key.path), wide(name));
+
+        let mut handle: HKEY = ptr::null_mut();
+        let status =
+            unsafe { RegOpenKeyExW(hive(key), path.as_ptr(), 0, KEY_SET_VALUE, &mut handle) };
+        if status == ERROR_FILE_NOT_FOUND {
+            return Ok(());
+        }
+        check(status)?;
+
+        let status = unsafe { RegDeleteValueW(handle, name.as_ptr()) };
+        unsafe { RegCloseKey(handle) };
+        if status == ERROR_FILE_NOT_FOUND {
+            return Ok(());
+        }
+        check(status)
+    }
+
+    /// The expanded file path stored for a role, or `None` for the default
+    pub fn registry_cursor_path(name: &str) -> io::Result<Option<PathBuf>> {
+        let (key, name) = (wide(CURSORS_KEY), wide(name));
+
+        let mut size = 0u32;
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                key.as_ptr(),
+                name.as_ptr(),
+                RRF_RT_REG_SZ,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &mut size,
+            )
+        };
+        if status == ERROR_FILE_NOT_FOUND {
+            return Ok(None);
+        }
+        if status != ERROR_SUCCESS {
+            return Err(io::Error::from_raw_os_error(status as i32));
+        }
+
+        let mut buf = vec![0u16; size as usize / 2 + 1];
+        let mut size = (buf.len() * 2) as u32;
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                key.as_ptr(),
+                name.as_ptr(),
+                RRF_RT_REG_SZ,
+                ptr::null_mut(),
+                buf.as_mut_ptr().cast(),
+                &mut size,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return Err(io::Error::from_raw_os_error(status as i32));
+        }
+
+        let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+        if len == 0 {
+            return Ok(None);
+        }
+        Ok(Some(PathBuf::from(OsString::from_wide(&buf[..len]))))
+    }
+
+    /// Read the live cursor for an `OCR_*` id back into pixel planes
+    pub fn capture_cursor(id: u32) -> io::Result<CapturedCursor> {
+        // MAKEINTRESOURCE: the id travels in the pointer value
+        let shared = unsafe { LoadCursorW(ptr::null_mut(), id as usize as *const u16) };
+        if shared.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        let cursor = unsafe { CopyIcon(shared) };
+        if cursor.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut info: ICONINFO = unsafe { mem::zeroed() };
+        let result = if unsafe { GetIconInfo(cursor, &mut info) } == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            read_planes(&info)
+        };
+
+        unsafe {
+            if !info.hbmMask.is_null() {
+                DeleteObject(info.hbmMask);
+            }
+            if !info.hbmColor.is_null() {
+                DeleteObject(info.hbmColor);
+            }
+            DestroyIcon(cursor);
+        }
+        result
+    }
+
+    fn read_planes(info: &ICONINFO) -> io::Result<CapturedCursor> {
+        let dc = unsafe { GetDC(ptr::null_mut()) };
+        if dc.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        let planes = (|| {
+            let (width, mask_height, mask) = bitmap_bgra(dc, info.hbmMask)?;
+            let (height, color) = if info.hbmColor.is_null() {
+                // Monochrome: the mask holds the AND plane above the XOR plane
+                (mask_height / 2, None)
+            } else {
+                let (_, height, color) = bitmap_bgra(dc, info.hbmColor)?;
+                (height, Some(color))
+            };
+
+            Ok(CapturedCursor {
+                width,
+                height,
+                hotspot: Hotspot::new(info.xHotspot as u16, info.yHotspot as u16),
+                color,
+                mask,
+            })
+        })();
+
+        unsafe { ReleaseDC(ptr::null_mut(), dc) };
+        planes
+    }
+
+    /// Fetch a bitmap as top-down 32-bit BGRA
+    fn bitmap_bgra(dc: HDC, bitmap: HBITMAP) -> io::Result<(u32, u32, Vec<u8>)> {
+        let mut header: BITMAP = unsafe { mem::zeroed() };
+        let size = mem::size_of::<BITMAP>() as i32;
+        if unsafe { GetObjectW(bitmap, size, (&mut header as *mut BITMAP).cast()) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let (width, height) = (header.bmWidth as u32, header.bmHeight.unsigned_abs());
+        let mut info: BITMAPINFO = unsafe { mem::zeroed() };
+        info.bmiHeader = BITMAPINFOHEADER {
+            biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width as i32,
+            biHeight: -(height as i32), // Negative for top-down rows
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB,
+            ..unsafe { mem::zeroed() }
+        };
+
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        let lines = unsafe {
+            GetDIBits(
+                dc,
+                bitmap,
+                0,
+                height,
+                pixels.as_mut_ptr().cast(),
+                &mut info,
+                DIB_RGB_COLORS,
+            )
+        };
+        if lines == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok((width, height, pixels))
+    }
+}
+
+#[cfg(not(windows))]
+mod sys {
+    use std::{
+        io,
+        path::{Path, PathBuf},
+    };
+
+    use super::{CapturedCursor, RegistryKey, RegistryValue};
+
+    fn unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "system cursors are only accessible on Windows",
+        )
+    }
+
+    pub fn check_supported() -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn set_system_cursor(_path: &Path, _id: u32) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn restore_scheme() -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn registry_cursor_path(_name: &str) -> io::Result<Option<PathBuf>> {
+        Err(unsupported())
+    }
+
+    pub fn capture_cursor(_id: u32) -> io::Result<CapturedCursor> {
+        Err(unsupported())
+    }
+
+    pub fn reload_cursors() -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn set_registry_value(
+        _key: &RegistryKey,
+        _name: &str,
+        _value: &RegistryValue,
+    ) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn delete_registry_value(_key: &RegistryKey, _name: &str) -> io::Result<()> {
+        Err(unsupported())
+    }
+}
@@ -0,0 +1,352 @@
+// Live cursor previews on a running X11 session
+//
+// Xlib, Xcursor and XFixes are loaded at runtime, so nothing links against
+// X11 at build time and no Xlib types appear in the public API.
+use std::{io, io::Cursor};
+
+use image::RgbaImage;
+
+use crate::{
+    ani::{AniFile, jiffies_to_ms},
+    cur::CursorFile,
+    file::CursorRef,
+    hotspot::Hotspot,
+};
+
+/// Nominal size used when the server doesn't report one
+const DEFAULT_SIZE: u32 = 24;
+
+/// A cursor set with [`apply`] or [`apply_to_window`]; dropping it restores
+/// the previous cursor and closes the display connection
+pub struct AppliedCursor {
+    // Restores the previous cursor on drop
+    _guard: sys::Applied,
+}
+
+impl AppliedCursor {
+    /// Restore the previous cursor now instead of on drop
+    pub fn restore(self) {
+        drop(self);
+    }
+}
+
+/// Replace every cursor named `shape` (e.g. `left_ptr`, `watch`) on the display
+///
+/// Animated cursors are handed to Xcursor with per-frame delays so the server
+/// animates them. On restore the theme's cursor for `shape` is reloaded.
+pub fn apply<'a>(file: impl Into<CursorRef<'a>>, shape: &str) -> io::Result<AppliedCursor> {
+    let display = sys::Display::open()?;
+    let frames = xcursor_frames(file.into(), display.default_size())?;
+    let inner = display.change_cursor_by_name(&frames, shape)?;
+    Ok(AppliedCursor { _guard: inner })
+}
+
+/// Define the cursor on one window, or on the root window when `window` is `None`
+pub fn apply_to_window<'a>(
+    file: impl Into<CursorRef<'a>>,
+    window: Option<u64>,
+) -> io::Result<AppliedCursor> {
+    let display = sys::Display::open()?;
+    let frames = xcursor_frames(file.into(), display.default_size())?;
+    let inner = display.define_cursor(&frames, window)?;
+    Ok(AppliedCursor { _guard: inner })
+}
+
+/// One Xcursor image: premultiplied ARGB pixels, row-major
+struct XFrame {
+    width: u32,
+    height: u32,
+    hotspot: Hotspot,
+    delay_ms: u32,
+    pixels: Vec<u32>,
+}
+
+impl XFrame {
+    fn new(image: &RgbaImage, hotspot: Hotspot, delay_ms: u32) -> Self {
+        let pixels = image
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b, a] = pixel.0;
+                let premultiply = |c: u8| (c as u32 * a as u32 + 127) / 255;
+                (a as u32) << 24 | premultiply(r) << 16 | premultiply(g) << 8 | premultiply(b)
+            })
+            .collect();
+
+        Self {
+            width: image.width(),
+            height: image.height(),
+            hotspot: hotspot.clamp_to(image.width(), image.height()),
+            delay_ms,
+            pixels,
+        }
+    }
+}
+
+/// Convert a cursor into Xcursor images at the size closest to `size`
+fn xcursor_frames(cursor: CursorRef<'_>, size: u32) -> io::Result<Vec<XFrame>> {
+    match cursor {
+        CursorRef::Cur(cur) => {
+            let frame = cur
+                .best_frame(size)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No frames"))?;
+            let image = frame.decode_image()?;
+            Ok(vec![XFrame::new(&image, frame.hotspot, 0)])
+        }
+        CursorRef::Ani(ani) => ani_frames(ani, size),
+    }
+}
+
+fn ani_frames(ani: &AniFile, size: u32) -> io::Result<Vec<XFrame>> {
+    let frames = ani
+        .steps()
+        .map(|step| {
+            // Durations come from the file, so clamp rather than overflow
+            let delay_ms = jiffies_to_ms(step.duration).min(u32::MAX.into()) as u32;
+            match CursorFile::decode(Cursor::new(&step.frame.image_data)) {
+                Ok(cur) => {
+                    let frame = cur.best_frame(size).expect("decoded cursors have frames");
+                    Ok(XFrame::new(&frame.decode_image()?, frame.hotspot, delay_ms))
+                }
+                Err(_) => {
+                    let hotspot = step.frame.hotspot;
+                    Ok(XFrame::new(&step.frame.decode_image()?, hotspot, delay_ms))
+                }
+            }
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    if frames.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "No steps"));
+    }
+    Ok(frames)
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::{
+        env,
+        ffi::CString,
+        io,
+        os::raw::{c_int, c_ulong},
+        ptr,
+    };
+
+    use x11_dl::{xcursor::Xcursor, xfixes::Xlib as XFixes, xlib};
+
+    use super::{DEFAULT_SIZE, XFrame};
+
+    fn error(kind: io::ErrorKind, message: impl Into<String>) -> io::Error {
+        io::Error::new(kind, message.into())
+    }
+
+    pub struct Display {
+        xlib: xlib::Xlib,
+        xcursor: Xcursor,
+        display: *mut xlib::Display,
+    }
+
+    impl Display {
+        pub fn open() -> io::Result<Self> {
+            if env::var_os("DISPLAY").is_none_or(|display| display.is_empty()) {
+                return Err(error(
+                    io::ErrorKind::NotConnected,
+                    "no X display: DISPLAY is not set",
+                ));
+            }
+
+            let xlib = xlib::Xlib::open()
+                .map_err(|err| error(io::ErrorKind::Unsupported, err.to_string()))?;
+            let xcursor = Xcursor::open()
+                .map_err(|err| error(io::ErrorKind::Unsupported, err.to_string()))?;
+
+            let display = unsafe { (xlib.XOpenDisplay)(ptr::null()) };
+            if display.is_null() {
+                return Err(error(
+                    io::ErrorKind::NotConnected,
+                    "could not connect to the X display",
+                ));
+            }
+
+            Ok(Self {
+                xlib,
+                xcursor,
+                display,
+            })
+        }
+
+        pub fn default_size(&self) -> u32 {
+            let size = unsafe { (self.xcursor.XcursorGetDefaultSize)(self.display) };
+            if size > 0 { size as u32 } else { DEFAULT_SIZE }
+        }
+
+        fn load_cursor(&self, frames: &[XFrame]) -> io::Result<c_ulong> {
+            unsafe {
+                let images = (self.xcursor.XcursorImagesCreate)(frames.len() as c_int);
+                if images.is_null() {
+                    return Err(io::Error::from(io::ErrorKind::OutOfMemory));
+                }
+
+                for frame in frames {
+                    let image = (self.xcursor.XcursorImageCreate)(
+                        frame.width as c_int,
+                        frame.height as c_int,
+                    );
+                    if image.is_null() {
+                        (self.xcursor.XcursorImagesDestroy)(images);
+                        return Err(io::Error::from(io::ErrorKind::OutOfMemory));
+                    }
+
+                    (*image).size = frame.width.max(frame.height);
+                    (*image).xhot = frame.hotspot.x.into();
+                    (*image).yhot = frame.hotspot.y.into();
+                    (*image).delay = frame.delay_ms;
+                    ptr::copy_nonoverlapping(
+                        frame.pixels.as_ptr(),
+                        (*image).pixels,
+                        frame.pixels.len(),
+                    );
+
+                    *(*images).images.add((*images).nimage as usize) = image;
+                    (*images).nimage += 1;
+                }
+
+                let cursor = (self.xcursor.XcursorImagesLoadCursor)(self.display, images);
+                (self.xcursor.XcursorImagesDestroy)(images);
+
+                if cursor == 0 {
+                    return Err(error(io::ErrorKind::Other, "X server rejected the cursor"));
+                }
+                Ok(cursor)
+            }
+        }
+
+        pub fn change_cursor_by_name(self, frames: &[XFrame], shape: &str) -> io::Result<Applied> {
+            let name = CString::new(shape)
+                .map_err(|_| error(io::ErrorKind::InvalidInput, "shape contains a NUL byte"))?;
+            let xfixes =
+                XFixes::open().map_err(|err| error(io::ErrorKind::Unsupported, err.to_string()))?;
+
+            let cursor = self.load_cursor(frames)?;
+            unsafe {
+                (xfixes.XFixesChangeCursorByName)(self.display, cursor, name.as_ptr());
+                (self.xlib.XFlush)(self.display);
+            }
+
+            Ok(Applied {
+                display: self,
+                cursor,
+                target: Target::Named {
+                    xfixes: Box::new(xfixes),
+                    name,
+                },
+            })
+        }
+
+        pub fn define_cursor(self, frames: &[XFrame], window: Option<u64>) -> io::Result<Applied> {
+            let window = match window {
+                Some(window) => window as c_ulong,
+                None => unsafe { (self.xlib.XDefaultRootWindow)(self.display) },
+            };
+
+            let cursor = self.load_cursor(frames)?;
+            unsafe {
+                (self.xlib.XDefineCursor)(self.display, window, cursor);
+                (self.xlib.XFlush)(self.display);
+            }
+
+            Ok(Applied {
+                display: self,
+                cursor,
+                target: Target::Window(window),
+            })
+        }
+    }
+
+    impl Drop for Display {
+        fn drop(&mut self) {
+            unsafe { (self.xlib.XCloseDisplay)(self.display) };
+        }
+    }
+
+    enum Target {
+        Named { xfixes: Box<XFixes>, name: CString },
+        Window(c_ulong),
+    }
+
+    pub struct Applied {
+        display: Display,
+        cursor: c_ulong,
+        target: Target,
+    }
+
+    impl Drop for Applied {
+        fn drop(&mut self) {
+            let display = &self.display;
+            unsafe {
+                match &self.target {
+                    Target::Named { xfixes, name } => {
+                        // Reload the theme's cursor and copy it back over the name
+                        let original = (display.xcursor.XcursorLibraryLoadCursor)(
+                            display.display,
+                            name.as_ptr(),
+                        );
+                        if original != 0 {
+                            (xfixes.XFixesChangeCursorByName)(
+                                display.display,
+                                original,
+                                name.as_ptr(),
+                            );
+                            (display.xlib.XFreeCursor)(display.display, original);
+                        }
+                    }
+                    Target::Window(window) => {
+                        (display.xlib.XUndefineCursor)(display.display, *window);
+                    }
+                }
+                (display.xlib.XFreeCursor)(display.display, self.cursor);
+                (display.xlib.XFlush)(display.display);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod sys {
+    use std::io;
+
+    use super::XFrame;
+
+    fn unsupported() -> io::Error {
+        io::Error::new(io::ErrorKind::Unsupported, "X11 is only available on Unix")
+    }
+
+    pub struct Display;
+
+    pub struct Applied;
+
+    impl Display {
+        pub fn open() -> io::Result<Self> {
+            Err(unsupported())
+        }
+
+        pub fn default_size(&self) -> u32 {
+            super::DEFAULT_SIZE
+        }
+
+        pub fn change_cursor_by_name(
+            self,
+            _frames: &[XFrame],
+            _shape: &str,
+        ) -> io::Result<Applied> {
+            Err(unsupported())
+        }
+
+        pub fn define_cursor(
+            self,
+            _frames: &[XFrame],
+            _window: Option<u64>,
+        ) -> io::Result<Applied> {
+            Err(unsupported())
+        }
+    }
+}
@@ -0,0 +1,345 @@
+// Reading and writing X11 Xcursor files, the format of Linux cursor themes
+//
+// A file is a table of contents followed by image chunks. Each image has a
+// nominal size; libXcursor picks the nominal size closest to the one asked
+// for and plays every image of that size in order as an animation.
+use std::io::{self, Write};
+
+use image::RgbaImage;
+
+use crate::{
+    ani::{AniFile, AniFrame, jiffies_to_ms, ms_to_jiffies},
+    cur::{CursorFile, CursorFrame},
+    file::CursorRef,
+    hotspot::Hotspot,
+    size::FrameSize,
+};
+
+const MAGIC: &[u8; 4] = b"Xcur";
+const FILE_HEADER_LEN: u32 = 16;
+const FILE_VERSION: u32 = 0x0001_0000;
+const IMAGE_TYPE: u32 = 0xfffd_0002;
+const IMAGE_HEADER_LEN: u32 = 36;
+const IMAGE_VERSION: u32 = 1;
+/// Largest width or height libXcursor accepts
+pub const MAX_IMAGE_SIDE: u32 = 0x7fff;
+
+/// One image of an Xcursor file
+#[derive(Debug, Clone)]
+pub struct XcursorImage {
+    pub nominal_size: u32,
+    pub image: RgbaImage,
+    pub hotspot: Hotspot,
+    pub delay_ms: u32, // 0 for a static cursor
+}
+
+/// Write `images` as an Xcursor file, in the given order
+pub fn encode<W: Write>(images: &[XcursorImage], mut writer: W) -> io::Result<()> {
+    if images.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "No images"));
+    }
+
+    let mut header = MAGIC.to_vec();
+    for word in [FILE_HEADER_LEN, FILE_VERSION, images.len() as u32] {
+        header.extend_from_slice(&word.to_le_bytes());
+    }
+
+    let mut position = FILE_HEADER_LEN as usize + images.len() * 12;
+    for image in images {
+        let (width, height) = image.image.dimensions();
+        if width > MAX_IMAGE_SIDE || height > MAX_IMAGE_SIDE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{width}x{height} is too large for an Xcursor image"),
+            ));
+        }
+        let position_word = u32::try_from(position)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Xcursor file too large"))?;
+        for word in [IMAGE_TYPE, image.nominal_size, position_word] {
+            header.extend_from_slice(&word.to_le_bytes());
+        }
+        position += IMAGE_HEADER_LEN as usize + (width * height) as usize * 4;
+    }
+    writer.write_all(&header)?;
+
+    for image in images {
+        let (width, height) = image.image.dimensions();
+        let hotspot = image.hotspot.clamp_to(width, height);
+        let mut chunk =
+            Vec::with_capacity(IMAGE_HEADER_LEN as usize + (width * height) as usize * 4);
+        for word in [
+            IMAGE_HEADER_LEN,
+            IMAGE_TYPE,
+            image.nominal_size,
+            IMAGE_VERSION,
+            width,
+            height,
+            hotspot.x as u32,
+            hotspot.y as u32,
+            image.delay_ms,
+        ] {
+            chunk.extend_from_slice(&word.to_le_bytes());
+        }
+        // Premultiplied ARGB, one little-endian word per pixel
+        for pixel in image.image.pixels() {
+            let [r, g, b, a] = pixel.0;
+            let premultiply = |c: u8| (c as u32 * a as u32 + 127) / 255;
+            let argb =
+                (a as u32) << 24 | premultiply(r) << 16 | premultiply(g) << 8 | premultiply(b);
+            chunk.extend_from_slice(&argb.to_le_bytes());
+        }
+        writer.write_all(&chunk)?;
+    }
+    Ok(())
+}
+
+/// Read every image of an Xcursor file, in table of contents order
+///
+/// Chunks other than images (comments) are skipped. Pixels are returned
+/// straight, not premultiplied.
+pub fn decode(bytes: &[u8]) -> io::Result<Vec<XcursorImage>> {
+    let word = |offset: usize| -> io::Result<u32> {
+        bytes
+            .get(offset..offset + 4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+            .ok_or_else(|| invalid_data(format!("truncated at byte {offset}")))
+    };
+
+    if !bytes.starts_with(MAGIC) {
+        return Err(invalid_data("not an Xcursor file"));
+    }
+    let header_len = word(4)? as usize;
+    let count = word(12)? as usize;
+    if count > (bytes.len() - header_len.min(bytes.len())) / 12 {
+        return Err(invalid_data(format!(
+            "{count} table entries don't fit the file"
+        )));
+    }
+
+    let mut images = Vec::new();
+    for entry in 0..count {
+        let entry = header_len + entry * 12;
+        if word(entry)? != IMAGE_TYPE {
+            continue;
+        }
+        let position = word(entry + 8)? as usize;
+        let chunk_header = word(position)? as usize;
+        let [kind, nominal_size, _, width, height, x, y, delay_ms] =
+            [4, 8, 12, 16, 20, 24, 28, 32].map(|offset| word(position + offset));
+        if kind? != IMAGE_TYPE {
+            return Err(invalid_data(format!("chunk at {position} is not an image")));
+        }
+        let (width, height) = (width?, height?);
+        if width == 0 || height == 0 || width > MAX_IMAGE_SIDE || height > MAX_IMAGE_SIDE {
+            return Err(invalid_data(format!("bad image size {width}x{height}")));
+        }
+
+        let start = position + chunk_header;
+        let pixels = bytes
+            .get(start..start + (width * height) as usize * 4)
+            .ok_or_else(|| invalid_data(format!("image at {position} is truncated")))?;
+        let image = RgbaImage::from_fn(width, height, |x, y| {
+            let index = (y * width + x) as usize * 4;
+            let argb = u32::from_le_bytes(pixels[index..index + 4].try_into().unwrap());
+            let a = argb >> 24;
+            let unpremultiply = |c: u32| match a {
+                0 => 0,
+                a => (((c & 0xff) * 255 + a / 2) / a).min(255) as u8,
+            };
+            image::Rgba([
+                unpremultiply(argb >> 16),
+                unpremultiply(argb >> 8),
+                unpremultiply(argb),
+                a as u8,
+            ])
+        });
+
+        let (x, y) = (x?.min(width - 1), y?.min(height - 1));
+        images.push(XcursorImage {
+            nominal_size: nominal_size?,
+            image,
+            hotspot: Hotspot::new(x.min(u16::MAX as u32) as u16, y.min(u16::MAX as u32) as u16),
+            delay_ms: delay_ms?,
+        });
+    }
+    if images.is_empty() {
+        return Err(invalid_data("no images"));
+    }
+    Ok(images)
+}
+
+/// An Xcursor converted for Windows: a .cur when static, an .ani when the
+/// images of a size animate
+#[derive(Debug, Clone)]
+pub enum WindowsCursor {
+    Static(CursorFile),
+    Animated(AniFile),
+}
+
+impl WindowsCursor {
+    /// Convert decoded Xcursor images
+    ///
+    /// The nominal size closest to `preferred_size` (the larger on a tie)
+    /// sets the frame count and timing; every other size becomes an extra
+    /// resolution of each frame, as long as it has as many images.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::{AniFile, xcursor::{self, WindowsCursor}};
+    ///
+    /// let ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// let images = xcursor::decode(&ani.to_xcursor()?)?;
+    /// let WindowsCursor::Animated(back) = WindowsCursor::from_images(&images, 32)? else {
+    ///     panic!("expected an animation");
+    /// };
+    /// assert_eq!((back.num_frames(), back.header.default_rate()), (18, 3));
+    /// let sizes: Vec<_> = back.extract_frame(0)?.frames().map(|f| f.size.max_side()).collect();
+    /// assert_eq!(sizes, [32, 48, 64]);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_images(images: &[XcursorImage], preferred_size: u32) -> io::Result<Self> {
+        let mut sizes: Vec<u32> = images.iter().map(|image| image.nominal_size).collect();
+        sizes.sort_unstable();
+        sizes.dedup();
+        let Some(&primary) = sizes
+            .iter()
+            .min_by_key(|&&size| (size.abs_diff(preferred_size), u32::MAX - size))
+        else {
+            return Err(invalid_data("no images"));
+        };
+        sizes.retain(|&size| size != primary);
+        sizes.insert(0, primary);
+
+        let of_size = |size: u32| {
+            images
+                .iter()
+                .filter(move |image| image.nominal_size == size)
+        };
+        let steps: Vec<&XcursorImage> = of_size(primary).collect();
+        let frame_sizes: Vec<Vec<&XcursorImage>> = sizes
+            .iter()
+            .map(|&size| of_size(size).collect::<Vec<_>>())
+            .filter(|group| group.len() == steps.len())
+            .collect();
+
+        let cursor_at = |step: usize| -> io::Result<CursorFile> {
+            let mut cursor = CursorFile::empty();
+            for group in &frame_sizes {
+                let image = group[step];
+                cursor.push_frame(CursorFrame::from_image(&image.image, image.hotspot)?);
+            }
+            Ok(cursor)
+        };
+
+        if steps.len() == 1 {
+            return Ok(Self::Static(cursor_at(0)?));
+        }
+        let mut ani = AniFile::empty();
+        for (step, image) in steps.iter().enumerate() {
+            let mut image_data = Vec::new();
+            cursor_at(step)?.encode(&mut image_data)?;
+            let size = FrameSize::new(image.image.width(), image.image.height())?;
+            ani.push_frame(AniFrame::new(size, image.hotspot, image_data, None));
+        }
+        let rates: Vec<u32> = steps
+            .iter()
+            .map(|image| ms_to_jiffies(image.delay_ms))
+            .collect();
+        Ok(Self::Animated(
+            if rates.iter().all(|&rate| rate == rates[0]) {
+                ani.with_default_rate(rates[0])?
+            } else {
+                ani.with_rates(rates)
+            },
+        ))
+    }
+
+    pub fn cursor(&self) -> CursorRef<'_> {
+        match self {
+            Self::Static(cursor) => CursorRef::Cur(cursor),
+            Self::Animated(cursor) => CursorRef::Ani(cursor),
+        }
+    }
+}
+
+impl CursorFile {
+    /// Encode as a static Xcursor file, one image per frame at its own size
+    pub fn to_xcursor(&self) -> io::Result<Vec<u8>> {
+        let images = self
+            .frames
+            .iter()
+            .map(|frame| {
+                Ok(XcursorImage {
+                    nominal_size: frame.size.max_side(),
+                    image: frame.decode_image()?,
+                    hotspot: frame.hotspot,
+                    delay_ms: 0,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut bytes = Vec::new();
+        encode(&images, &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl AniFile {
+    /// Encode as an animated Xcursor file
+    ///
+    /// Every size found in the frames gets one image per playback step;
+    /// steps whose frame lacks that size have its closest one resampled, see
+    /// `steps_at_size`.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use cursor_handler::AniFile;
+    ///
+    /// let ani = AniFile::decode(Cursor::new(std::fs::read("assets/aero_busy.ani")?))?;
+    /// let xcursor = ani.to_xcursor()?;
+    /// assert_eq!(&xcursor[..4], b"Xcur");
+    /// // 3 sizes of 18 steps
+    /// assert_eq!(u32::from_le_bytes(xcursor[12..16].try_into().unwrap()), 54);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn to_xcursor(&self) -> io::Result<Vec<u8>> {
+        let frames = self.extract_all()?;
+        let mut sizes: Vec<u32> = frames
+            .iter()
+            .flat_map(|cursor| cursor.frames.iter().map(|frame| frame.size.max_side()))
+            .collect();
+        sizes.sort_unstable();
+        sizes.dedup();
+
+        self.xcursor_at_sizes(&sizes)
+    }
+
+    /// Encode as an animated Xcursor file holding only `size`
+    pub fn to_xcursor_at_size(&self, size: u32) -> io::Result<Vec<u8>> {
+        self.xcursor_at_sizes(&[size])
+    }
+
+    fn xcursor_at_sizes(&self, sizes: &[u32]) -> io::Result<Vec<u8>> {
+        let mut images = Vec::with_capacity(sizes.len() * self.sequence.len());
+        for &size in sizes {
+            for step in self.steps_at_size(size)? {
+                images.push(XcursorImage {
+                    nominal_size: size,
+                    image: step.image,
+                    hotspot: step.hotspot,
+                    delay_ms: jiffies_to_ms(step.duration) as u32,
+                });
+            }
+        }
+
+        let mut bytes = Vec::new();
+        encode(&images, &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
@@ -0,0 +1,65 @@
+// The `anih` counts are derived from the frames and sequence on every
+// encode, so no edit can write a header that disagrees with them; what a
+// decoded file claimed is only reported
+use std::{fs, io::Cursor};
+
+use cursor_handler::AniFile;
+
+fn encode(ani: &AniFile) -> Vec<u8> {
+    let mut bytes = Cursor::new(Vec::new());
+    ani.encode(&mut bytes).unwrap();
+    bytes.into_inner()
+}
+
+/// nFrames and nSteps as written, after RIFF, ACON, the anih chunk header
+/// and cbSize
+fn written_counts(bytes: &[u8]) -> (u32, u32) {
+    let word = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+    (word(24), word(28))
+}
+
+#[test]
+fn decoded_lie_is_not_written_back() {
+    let bytes = fs::read("tests/fixtures/lying/anih_frames.ani").unwrap();
+    assert_eq!(written_counts(&bytes), (6, 4));
+    let ani = AniFile::decode(Cursor::new(&bytes)).unwrap();
+    assert_eq!(ani.header.declared_frames(), Some(6));
+    assert_eq!(ani.num_frames(), 4);
+
+    let encoded = encode(&ani);
+    assert_eq!(written_counts(&encoded), (4, 4));
+    let again = AniFile::decode(Cursor::new(&encoded)).unwrap();
+    assert_eq!(again.header.declared_frames(), Some(4));
+    assert_eq!(again.header.declared_steps(), Some(4));
+}
+
+#[test]
+fn edits_change_the_counts_written() {
+    let bytes = fs::read("tests/fixtures/normal/spinner.ani").unwrap();
+    let mut ani = AniFile::decode(Cursor::new(&bytes)).unwrap();
+
+    // A new frame is counted, and forgets what the file declared
+    let extra = ani.frames().next().unwrap().clone();
+    ani.push_frame(extra);
+    assert_eq!(ani.header.declared_frames(), None);
+    assert_eq!(written_counts(&encode(&ani)), (5, 5));
+
+    // So is a sequence of a different length
+    ani.sequence = vec![0, 4, 0];
+    ani.rates.clear();
+    assert_eq!(written_counts(&encode(&ani)), (5, 3));
+}
+
+#[test]
+fn zero_default_rate_is_rejected() {
+    let bytes = fs::read("tests/fixtures/normal/spinner.ani").unwrap();
+    let mut ani = AniFile::decode(Cursor::new(&bytes)).unwrap();
+    let err = ani.header.set_default_rate(0).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(ani.header.default_rate(), 6);
+    assert!(ani.clone().with_default_rate(0).is_err());
+
+    ani.header.set_default_rate(10).unwrap();
+    let again = AniFile::decode(Cursor::new(encode(&ani))).unwrap();
+    assert_eq!(again.header.default_rate(), 10);
+}
@@ -0,0 +1,154 @@
+#![cfg(feature = "json")]
+// Aseprite JSON exports read through `AniFile::from_aseprite`: escaped names,
+// hash frames kept in document order, members the importer ignores, and
+// documents that parse but aren't exports it can use
+use std::io::{self, ErrorKind};
+
+use cursor_handler::{AniFile, aseprite::AsepriteOptions};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+/// Two 8x8 cells side by side, red then blue
+fn sheet() -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(16, 8, |x, _| match x < 8 {
+        true => Rgba([255, 0, 0, 255]),
+        false => Rgba([0, 0, 255, 255]),
+    }))
+}
+
+fn import(json: &str, tag: Option<&str>) -> io::Result<AniFile> {
+    let options = AsepriteOptions {
+        tag: tag.map(String::from),
+        ..Default::default()
+    };
+    AniFile::from_aseprite(&sheet(), json, &options)
+}
+
+/// A hash-layout document: the two cells, then `meta` as given
+fn document(meta: &str) -> String {
+    format!(
+        r#"{{"frames": {{
+            "a.png": {{"frame": {{"x": 0, "y": 0, "w": 8, "h": 8}}, "duration": 100}},
+            "b.png": {{"frame": {{"x": 8, "y": 0, "w": 8, "h": 8}}, "duration": 100}}
+        }}, "meta": {meta}}}"#
+    )
+}
+
+fn tagged(name: &str) -> String {
+    document(&format!(
+        r#"{{"frameTags": [{{"name": "{name}", "from": 1, "to": 1, "direction": "forward"}}]}}"#
+    ))
+}
+
+fn error(json: &str) -> String {
+    let err = import(json, None).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData, "{err}");
+    err.to_string()
+}
+
+#[test]
+fn string_escapes() {
+    let cases = [
+        (r#"sp\u00efn"#, "spïn"),
+        (r#"\"quoted\" \\ \/"#, r#""quoted" \ /"#),
+        (r#"tab\there\nnew\r\b\f"#, "tab\there\nnew\r\u{8}\u{c}"),
+        (r#"\ud83c\udf00 spiral"#, "🌀 spiral"),
+        (r#"\uD83C\uDF00"#, "🌀"),
+        ("raw ünïcode", "raw ünïcode"),
+    ];
+    for (escaped, name) in cases {
+        let ani =
+            import(&tagged(escaped), Some(name)).unwrap_or_else(|err| panic!("{escaped}: {err}"));
+        assert_eq!(ani.num_frames(), 1, "{escaped}");
+    }
+
+    // A lone surrogate is not a string
+    let err = import(&tagged(r#"\ud83c!"#), Some("\u{fffd}!")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    // Escapes in keys are decoded too: both frames are still found
+    let json = r#"{"frames": {
+        "a.png": {"frame": {"x": 0, "y": 0, "w": 8, "h": 8}, "duration": 100},
+        "b\"png": {"frame": {"x": 8, "y": 0, "w": 8, "h": 8}, "duration": 100}
+    }}"#;
+    assert_eq!(import(json, None).unwrap().num_frames(), 2);
+}
+
+#[test]
+fn hash_layout_keeps_document_order() {
+    // Keys that sort differently from their order in the file
+    let json = r#"{"frames": {
+        "cursor 10.png": {"frame": {"x": 8, "y": 0, "w": 8, "h": 8}, "duration": 50},
+        "cursor 2.png": {"frame": {"x": 0, "y": 0, "w": 8, "h": 8}, "duration": 100}
+    }}"#;
+    let ani = import(json, None).unwrap();
+    let blue = ani.extract_frame(0).unwrap()[0].decode_image().unwrap();
+    assert_eq!(blue.get_pixel(0, 0).0, [0, 0, 255, 255]);
+    let rates: Vec<_> = ani.steps().map(|step| step.duration).collect();
+    assert_eq!(rates, [3, 6]);
+}
+
+#[test]
+fn ignored_members() {
+    // Members the importer doesn't read may hold anything
+    let meta = r#"{"app": "http://www.aseprite.org/", "layers": [{"name": "bg",
+        "cels": [[{"a": [[], {}]}], []]}], "slices": null, "frameTags": []}"#;
+    assert_eq!(import(&document(meta), None).unwrap().num_frames(), 2);
+}
+
+#[test]
+fn unusable_documents() {
+    let cases = [
+        (r#"{"frames": []} x"#, "trailing characters"),
+        ("3", "invalid type: integer"),
+        (r#"{"frames": "abc"}"#, "expected a frames array or object"),
+        (r#"{"meta": {}}"#, "missing field `frames`"),
+    ];
+    for (json, message) in cases {
+        let error = error(json);
+        assert!(error.contains(message), "{json}: {error}");
+    }
+
+    assert_eq!(error(r#"{"frames": []}"#), "no frames");
+    // serde_json reports a cut-off document as an unexpected end
+    let cut = import(r#"{"frames": ["#, None).unwrap_err();
+    assert_eq!(cut.kind(), ErrorKind::UnexpectedEof);
+    let error_w = error(&document("{}").replacen(r#""w": 8, "#, "", 1));
+    assert!(error_w.starts_with("missing field `w`"), "{error_w}");
+    let fraction = error(&document("{}").replacen(r#""w": 8"#, r#""w": 7.5"#, 1));
+    assert!(
+        fraction.starts_with("invalid type: floating point"),
+        "{fraction}"
+    );
+
+    let rotated = document("{}").replacen(r#""duration""#, r#""rotated": true, "duration""#, 1);
+    assert_eq!(error(&rotated), "rotated frames are not supported");
+    let trimmed = document("{}").replacen(r#""duration""#, r#""trimmed": true, "duration""#, 1);
+    assert_eq!(
+        error(&trimmed),
+        "trimmed frame is missing 'spriteSourceSize' or 'sourceSize'"
+    );
+}
+
+#[test]
+fn tag_errors() {
+    let tag = |body: &str| document(&format!(r#"{{"frameTags": [{{"name": "t", {body}}}]}}"#));
+    let cases = [
+        (r#""from": 0, "to": 2"#, "tag 't' has a bad 'to'"),
+        (r#""from": 1, "to": 0"#, "tag 't' ends before it starts"),
+    ];
+    for (body, message) in cases {
+        let err = import(&tag(body), Some("t")).unwrap_err();
+        assert_eq!(err.to_string(), message, "{body}");
+    }
+    let err = import(&tag(r#""from": 0, "to": 1"#), Some("u")).unwrap_err();
+    assert_eq!(err.to_string(), "no tag named 'u'");
+    let err = import(
+        &tag(r#""from": 0, "to": 1, "direction": "sideways""#),
+        Some("t"),
+    );
+    let message = err.unwrap_err().to_string();
+    assert!(
+        message.starts_with("unknown variant `sideways`"),
+        "{message}"
+    );
+}
@@ -0,0 +1,136 @@
+// Batch runs over a directory tree: one bad file fails on its own, shows up
+// in the summary and sets the exit code without stopping the others
+#![cfg(feature = "cli")]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn temp_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "cursor_handler_batch_{test}_{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// `dir/in` holding a good .cur, and a good .ani beside a corrupt one in a
+/// subdirectory
+fn tree(dir: &Path) -> PathBuf {
+    let input = dir.join("in");
+    fs::create_dir_all(input.join("sub")).unwrap();
+    fs::copy("tests/fixtures/normal/arrow.cur", input.join("arrow.cur")).unwrap();
+    fs::copy(
+        "tests/fixtures/normal/spinner.ani",
+        input.join("sub/spinner.ani"),
+    )
+    .unwrap();
+    fs::write(input.join("sub/broken.ani"), b"RIFF\x10\0\0\0ACONgarbage").unwrap();
+    input
+}
+
+fn run(args: &[&str], paths: &[&Path]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cursor_handler"))
+        .args(args)
+        .args(paths)
+        .output()
+        .unwrap()
+}
+
+/// The ok, warn and fail counts of the summary table, and the skipped count
+fn summary(output: &Output) -> [usize; 4] {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let count = |status: &str| {
+        stderr
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(status)?.trim().parse().ok())
+            .unwrap_or(0)
+    };
+    [count("ok"), count("warn"), count("fail"), count("skipped")]
+}
+
+#[test]
+fn validate_tree() {
+    let dir = temp_dir("validate");
+    let input = tree(&dir);
+    let out = dir.join("passed");
+
+    let output = run(
+        &["validate", "--recursive", "--jobs", "2", "--out-dir"],
+        &[&out, &input],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(summary(&output), [2, 0, 1, 0]);
+
+    // Reports come in input order, and only passing files are copied
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let files: Vec<_> = stdout
+        .lines()
+        .filter_map(|line| line.split(": ").next())
+        .collect();
+    let mut sorted = files.clone();
+    sorted.sort();
+    assert_eq!(files, sorted);
+    assert!(
+        stdout.contains("broken.ani: error: can't decode"),
+        "{stdout}"
+    );
+    assert!(out.join("arrow.cur").exists());
+    assert!(out.join("sub/spinner.ani").exists());
+    assert!(!out.join("sub/broken.ani").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn optimize_tree_mirrors_input() {
+    let dir = temp_dir("optimize");
+    let input = tree(&dir);
+    let out = dir.join("out");
+
+    let output = run(&["optimize", "--recursive", "--out-dir"], &[&out, &input]);
+    assert_eq!(output.status.code(), Some(1));
+    // Both good files are already optimal, which counts as a warning
+    assert_eq!(summary(&output), [0, 2, 1, 0]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("total bytes saved: 0"), "{stderr}");
+    assert_eq!(
+        fs::read(out.join("sub/spinner.ani")).unwrap(),
+        fs::read(input.join("sub/spinner.ani")).unwrap()
+    );
+    assert!(!out.join("sub/broken.ani").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn resize_fail_fast() {
+    let dir = temp_dir("resize");
+    let input = tree(&dir);
+    let out = dir.join("out");
+
+    // Without --fail-fast every good file is resized
+    let resize = ["resize", "--sizes", "48", "--recursive"];
+    let output = run(&[&resize[..], &["--out-dir"]].concat(), &[&out, &input]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(summary(&output), [2, 0, 1, 0]);
+    assert!(out.join("sub/spinner.ani").exists());
+
+    // With one job at a time the files after broken.ani are skipped
+    fs::remove_dir_all(&out).unwrap();
+    let output = run(
+        &[&resize[..], &["--fail-fast", "--jobs", "1", "--out-dir"]].concat(),
+        &[&out, &input],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(summary(&output), [1, 0, 1, 1]);
+    assert!(out.join("arrow.cur").exists());
+    assert!(!out.join("sub/spinner.ani").exists());
+
+    // Several inputs need somewhere to go
+    let output = run(&resize, &[&input]);
+    assert!(!output.status.success());
+    fs::remove_dir_all(&dir).unwrap();
+}
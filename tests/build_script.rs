@@ -0,0 +1,98 @@
+// `build::validate_and_copy` as a dependent crate's build script calls it:
+// good cursors are copied and get a static each, and a bad directory fails
+// with a message naming the files
+#![cfg(feature = "fs")]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use cursor_handler::build::validate_and_copy;
+
+fn temp_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "cursor_handler_build_{test}_{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::create_dir_all(dir.join("out")).unwrap();
+    dir
+}
+
+fn copy_fixture(fixture: &str, to: &Path) {
+    fs::copy(Path::new("tests/fixtures").join(fixture), to).unwrap();
+}
+
+#[test]
+fn generates_one_static_per_cursor() {
+    let dir = temp_dir("module");
+    let (src, out) = (dir.join("src"), dir.join("out"));
+    copy_fixture("normal/arrow.cur", &src.join("arrow.cur"));
+    copy_fixture("normal/spinner.ani", &src.join("busy-2.ANI"));
+    copy_fixture("png/arrow_32.cur", &src.join("2x.cur"));
+    fs::write(src.join("notes.txt"), "not a cursor").unwrap();
+
+    let module = validate_and_copy(&src, &out).unwrap();
+    assert_eq!(module, out.join("cursors.rs"));
+    let include = |file: &str| format!("{:?}", out.join(file).display().to_string());
+    let expected = format!(
+        "pub mod cursors {{\n\
+         \x20   pub static ARROW: ::cursor_handler::embed::CursorBytes =\n\
+         \x20       ::cursor_handler::embed::CursorBytes::new(include_bytes!({}));\n\
+         \x20   pub static BUSY_2: ::cursor_handler::embed::CursorBytes =\n\
+         \x20       ::cursor_handler::embed::CursorBytes::new(include_bytes!({}));\n\
+         \x20   pub static _2X: ::cursor_handler::embed::CursorBytes =\n\
+         \x20       ::cursor_handler::embed::CursorBytes::new(include_bytes!({}));\n\
+         }}\n",
+        include("arrow.cur"),
+        include("busy-2.ANI"),
+        include("2x.cur"),
+    );
+    assert_eq!(fs::read_to_string(&module).unwrap(), expected);
+
+    // The copies are byte for byte, and nothing else is copied
+    assert_eq!(
+        fs::read(out.join("busy-2.ANI")).unwrap(),
+        fs::read(src.join("busy-2.ANI")).unwrap()
+    );
+    assert!(!out.join("notes.txt").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn corrupt_cursor_fails() {
+    let dir = temp_dir("corrupt");
+    let (src, out) = (dir.join("src"), dir.join("out"));
+    copy_fixture("normal/arrow.cur", &src.join("arrow.cur"));
+    copy_fixture("truncated/spinner.ani", &src.join("wait.ani"));
+
+    let err = validate_and_copy(&src, &out).unwrap_err();
+    let message = err.to_string();
+    let prefix = format!("invalid cursor {}: ", src.join("wait.ani").display());
+    assert!(message.starts_with(&prefix), "{message}");
+    assert!(!out.join("cursors.rs").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn clashing_names_fail() {
+    let dir = temp_dir("clash");
+    let (src, out) = (dir.join("src"), dir.join("out"));
+    copy_fixture("normal/arrow.cur", &src.join("link-select.cur"));
+    copy_fixture("normal/spinner.ani", &src.join("link_select.ani"));
+
+    let err = validate_and_copy(&src, &out).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "{} and {} would both be the static LINK_SELECT",
+            src.join("link-select.cur").display(),
+            src.join("link_select.ani").display()
+        )
+    );
+    assert!(!out.join("cursors.rs").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
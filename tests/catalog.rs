@@ -0,0 +1,89 @@
+// The `formats` and `roles` commands list exactly what the library accepts
+#![cfg(feature = "cli")]
+
+use std::{fs, process::Command};
+
+use cursor_handler::{convert, project::CursorProject};
+use image::{Rgba, RgbaImage};
+
+/// The first column of a listing command's table
+fn listed(command: &str) -> Vec<String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_cursor_handler"))
+        .arg(command)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().next().unwrap().to_string())
+        .collect()
+}
+
+fn project(format: &str) -> String {
+    format!(
+        "[[cursor]]\nname = 'dot'\nsource = 'dot.png'\nhotspot = [0, 0]\nformats = ['{format}']\n"
+    )
+}
+
+#[test]
+fn listed_formats_build() {
+    let dir = std::env::temp_dir().join(format!("cursor_handler_formats_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    RgbaImage::from_pixel(16, 16, Rgba([0, 0, 0, 255]))
+        .save(dir.join("dot.png"))
+        .unwrap();
+
+    let formats = listed("formats");
+    assert_eq!(formats, ["cur", "ani", "xcursor"]);
+    for format in &formats {
+        let project = CursorProject::parse(&project(format), &dir).unwrap();
+        let report = project.build(dir.join(format)).unwrap();
+        assert_eq!(report.outputs.len(), 1, "{format}");
+    }
+
+    let err = CursorProject::parse(&project("png"), &dir).unwrap_err();
+    for format in &formats {
+        assert!(err.to_string().contains(&format!("\"{format}\"")), "{err}");
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn listed_roles() {
+    let names: Vec<_> = convert::roles().iter().map(|role| role.name).collect();
+    assert_eq!(listed("roles"), names);
+}
+
+#[test]
+fn json_listings() {
+    let json = |args: &[&str]| -> serde_json::Value {
+        let output = Command::new(env!("CARGO_BIN_EXE_cursor_handler"))
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{args:?}");
+        serde_json::from_slice(&output.stdout).unwrap()
+    };
+
+    let formats = json(&["formats", "--jsonl"]);
+    let names: Vec<_> = formats
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|format| format["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, listed("formats"));
+
+    let roles = json(&["roles", "arrow", "--format", "json"]);
+    let arrow = convert::roles()
+        .iter()
+        .find(|role| role.name == "Arrow")
+        .unwrap();
+    assert_eq!(roles[0]["name"], "Arrow");
+    assert_eq!(roles[0]["xcursor"], arrow.xcursor.unwrap());
+    assert_eq!(roles[0]["aliases"], serde_json::json!(arrow.aliases));
+    assert_eq!(roles[0]["file_stems"], serde_json::json!(arrow.file_stems));
+}
@@ -0,0 +1,132 @@
+// Hand-built .ani files with chunk sizes that lie: too large for what is
+// left of the stream, zero, or wrong inside a frame list. Each one decodes
+// or fails with the offending offset, and none of them hangs.
+use std::{fs, io::Cursor};
+
+use cursor_handler::{AniFile, DecodeError, DecodeOptions};
+
+fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = [&id[..], &(data.len() as u32).to_le_bytes()].concat();
+    chunk.extend_from_slice(data);
+    if !data.len().is_multiple_of(2) {
+        chunk.push(0);
+    }
+    chunk
+}
+
+/// A chunk header claiming `size` bytes, without the data
+fn header(id: &[u8; 4], size: u32) -> Vec<u8> {
+    [&id[..], &size.to_le_bytes()].concat()
+}
+
+fn anih(frames: u32) -> Vec<u8> {
+    let fields = [36, frames, frames, 0, 0, 0, 0, 6, 1];
+    chunk(b"anih", &fields.map(u32::to_le_bytes).concat())
+}
+
+fn icon() -> Vec<u8> {
+    chunk(
+        b"icon",
+        &fs::read("tests/fixtures/png/arrow_32.cur").unwrap(),
+    )
+}
+
+fn list(list_type: &[u8; 4], items: &[Vec<u8>]) -> Vec<u8> {
+    chunk(b"LIST", &[&list_type[..], &items.concat()].concat())
+}
+
+/// `chunks` in a RIFF ACON container whose size covers them
+fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let body = chunks.concat();
+    let mut bytes = header(b"RIFF", body.len() as u32 + 4);
+    bytes.extend_from_slice(b"ACON");
+    bytes.extend(body);
+    bytes
+}
+
+fn decode_error(bytes: &[u8], options: &DecodeOptions) -> DecodeError {
+    let err = AniFile::decode_with(Cursor::new(bytes), options).unwrap_err();
+    DecodeError::find(&err)
+        .unwrap_or_else(|| panic!("no offset in {err}"))
+        .clone()
+}
+
+#[test]
+fn chunk_larger_than_the_stream() {
+    // Right after the 12-byte RIFF header, a chunk claiming nearly 4 GiB
+    let mut bytes = riff(&[anih(1), header(b"JUNK", u32::MAX)]);
+    bytes.extend_from_slice(b"tail");
+    assert_eq!(
+        decode_error(&bytes, &DecodeOptions::default()),
+        DecodeError::TruncatedChunk {
+            id: "JUNK".into(),
+            index: None,
+            at: 56,
+            expected: u32::MAX.into(),
+            got: 4,
+        }
+    );
+
+    // The same for a table chunk and an item of the frame list
+    let bytes = riff(&[header(b"seq ", 0x7FFF_FFFF), anih(1)]);
+    assert_eq!(decode_error(&bytes, &DecodeOptions::default()).offset(), 12);
+    let mut items = header(b"icon", u32::MAX - 1);
+    items.extend_from_slice(&[0; 6]);
+    let bytes = riff(&[anih(1), list(b"fram", &[items])]);
+    let err = decode_error(&bytes, &DecodeOptions::default());
+    assert_eq!(err.offset(), 56 + 12, "{err}");
+}
+
+#[test]
+fn zero_size_chunks_advance() {
+    let bytes = riff(&[
+        header(b"JUNK", 0),
+        anih(2),
+        header(b"fact", 0),
+        header(b"JUNK", 0),
+        list(b"fram", &[icon(), header(b"JUNK", 0), icon()]),
+        header(b"zero", 0),
+    ]);
+    let ani = AniFile::decode(Cursor::new(&bytes)).unwrap();
+    assert_eq!(ani.num_frames(), 2);
+    assert_eq!(ani.unknown_chunks.len(), 1);
+    assert_eq!(
+        (
+            ani.unknown_chunks[0].before_frame,
+            &ani.unknown_chunks[0].data[..]
+        ),
+        (1, &[][..])
+    );
+
+    // Strict mode names the first one it meets
+    let err = decode_error(&bytes, &DecodeOptions::strict());
+    assert_eq!(err.offset(), 12, "{err}");
+}
+
+#[test]
+fn frame_list_always_moves_on() {
+    // A run of empty items, then one claiming more than the list holds
+    let mut items = vec![header(b"JUNK", 0); 1000];
+    items.insert(0, icon());
+    items.push(header(b"icon", 64));
+    let bytes = riff(&[anih(2), list(b"fram", &items)]);
+    let last = bytes.len() as u64 - 8;
+    let err = decode_error(&bytes, &DecodeOptions::default());
+    assert_eq!(err.offset(), last, "{err}");
+    assert!(matches!(
+        err,
+        DecodeError::TruncatedChunk { index: Some(1), .. }
+    ));
+
+    // A LIST too small for its own type is rejected where it starts
+    let bytes = riff(&[anih(1), header(b"LIST", 0), list(b"fram", &[icon()])]);
+    assert_eq!(
+        decode_error(&bytes, &DecodeOptions::default()),
+        DecodeError::InvalidChunk {
+            id: "LIST".into(),
+            index: None,
+            at: 56,
+            message: "Missing LIST type".into(),
+        }
+    );
+}
@@ -0,0 +1,62 @@
+// The same animation with its top-level chunks in other orders: frames
+// ahead of the header, and the rate table ahead of the sequence and the
+// header. Each decodes to the same steps, without a warning.
+use std::{fs, io::Cursor};
+
+use cursor_handler::{
+    AniFile, DecodeOptions,
+    test_utils::{assert_cursor_bytes_eq, assert_steps_eq},
+};
+
+fn decode(path: &str) -> AniFile {
+    let bytes = fs::read(format!("tests/fixtures/{path}")).unwrap();
+    let decoded = AniFile::decode_with(Cursor::new(&bytes), &DecodeOptions::lenient()).unwrap();
+    assert!(
+        decoded.warnings.is_empty(),
+        "{path}: {:?}",
+        decoded.warnings
+    );
+    assert!(
+        AniFile::decode_with(Cursor::new(&bytes), &DecodeOptions::strict()).is_ok(),
+        "{path}"
+    );
+    decoded.value
+}
+
+fn steps(ani: &AniFile) -> Vec<(usize, u32)> {
+    ani.steps()
+        .map(|step| (step.frame_index, step.duration))
+        .collect()
+}
+
+#[test]
+fn permuted_chunks_decode_alike() {
+    let ordered = decode("riff/ordered.ani");
+    assert_eq!(
+        steps(&ordered),
+        [(0, 6), (1, 6), (2, 12), (3, 12), (2, 6), (1, 6)]
+    );
+
+    for path in ["riff/frames_first.ani", "riff/rate_before_seq.ani"] {
+        let permuted = decode(path);
+        assert_eq!(steps(&permuted), steps(&ordered), "{path}");
+        assert_steps_eq(&ordered, &permuted);
+        assert_eq!(permuted.info, ordered.info, "{path}");
+        assert_eq!(permuted.header.default_rate(), 6, "{path}");
+        assert_eq!(permuted.header.declared_frames(), Some(4), "{path}");
+        let payloads = |ani: &AniFile| -> Vec<Vec<u8>> {
+            ani.frames().map(|frame| frame.image_data.clone()).collect()
+        };
+        assert_eq!(payloads(&permuted), payloads(&ordered), "{path}");
+    }
+}
+
+#[test]
+fn reencoding_restores_the_order() {
+    let ordered = fs::read("tests/fixtures/riff/ordered.ani").unwrap();
+    for path in ["riff/frames_first.ani", "riff/rate_before_seq.ani"] {
+        let mut bytes = Cursor::new(Vec::new());
+        decode(path).encode(&mut bytes).unwrap();
+        assert_cursor_bytes_eq(&ordered, &bytes.into_inner());
+    }
+}
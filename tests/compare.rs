@@ -0,0 +1,123 @@
+// `compare --exit-code --diff-image` against edited copies of a fixture
+#![cfg(feature = "cli")]
+
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use cursor_handler::{AniFile, CursorFile, CursorFrame, diff::DIFF_HIGHLIGHT};
+use image::Rgba;
+
+const FIXTURE: &str = "tests/fixtures/normal/arrow.cur";
+
+fn temp_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "cursor_handler_compare_{test}_{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write_cur(cursor: &CursorFile, path: &Path) {
+    let mut bytes = Vec::new();
+    cursor.encode(&mut bytes).unwrap();
+    fs::write(path, bytes).unwrap();
+}
+
+/// Exit code, and the coordinates of highlighted pixels in the diff image
+fn compare(new: &Path, diff_image: &Path) -> (i32, Vec<(u32, u32)>) {
+    let status = Command::new(env!("CARGO_BIN_EXE_cursor_handler"))
+        .args(["compare", FIXTURE])
+        .arg(new)
+        .arg("--exit-code")
+        .arg("--diff-image")
+        .arg(diff_image)
+        .output()
+        .unwrap()
+        .status;
+    let image = image::open(diff_image).unwrap().into_rgba8();
+    let highlighted = image
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| **pixel == DIFF_HIGHLIGHT)
+        .map(|(x, y, _)| (x, y))
+        .collect();
+    (status.code().unwrap(), highlighted)
+}
+
+#[test]
+fn exit_codes_and_diff_image() {
+    let dir = temp_dir("image");
+    let original = CursorFile::decode(Cursor::new(fs::read(FIXTURE).unwrap())).unwrap();
+    let image = dir.join("diff.png");
+
+    let same = dir.join("same.cur");
+    write_cur(&original, &same);
+    let (code, highlighted) = compare(&same, &image);
+    assert_eq!((code, highlighted.len()), (0, 0));
+
+    // A moved hotspot differs, but no pixel does
+    let mut moved = original.clone();
+    moved[0].hotspot.x += 1;
+    let hotspot = dir.join("hotspot.cur");
+    write_cur(&moved, &hotspot);
+    let (code, highlighted) = compare(&hotspot, &image);
+    assert_eq!((code, highlighted.len()), (1, 0));
+
+    // Repaint a 4x4 block of the first frame, which is 32x32
+    let mut pixels = original[0].decode_image().unwrap();
+    for y in 10..14 {
+        for x in 4..8 {
+            pixels.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+        }
+    }
+    let mut painted = original.clone();
+    painted[0] = CursorFrame::from_image(&pixels, original[0].hotspot).unwrap();
+    let repainted = dir.join("painted.cur");
+    write_cur(&painted, &repainted);
+    let (code, highlighted) = compare(&repainted, &image);
+    assert_eq!(code, 1);
+
+    // Only the overlay cell of the first row, two 32 pixel cells and gaps in
+    let overlay = 2 * (32 + 2);
+    assert_eq!(highlighted.len(), 16);
+    assert!(
+        highlighted
+            .iter()
+            .all(|&(x, y)| (overlay + 4..overlay + 8).contains(&x) && (10..14).contains(&y)),
+        "{highlighted:?}"
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cur_against_single_frame_ani() {
+    let dir = temp_dir("ani");
+    let original = CursorFile::decode(Cursor::new(fs::read(FIXTURE).unwrap())).unwrap();
+    let ani = AniFile::from_cursor_static(&original, 6).unwrap();
+    let path = dir.join("static.ani");
+    let mut bytes = Cursor::new(Vec::new());
+    ani.encode(&mut bytes).unwrap();
+    fs::write(&path, bytes.into_inner()).unwrap();
+
+    let (code, highlighted) = compare(&path, &dir.join("diff.png"));
+    assert_eq!((code, highlighted.len()), (0, 0));
+
+    // A multi-frame animation has no single cursor to compare with
+    let status = Command::new(env!("CARGO_BIN_EXE_cursor_handler"))
+        .args([
+            "compare",
+            FIXTURE,
+            "tests/fixtures/normal/spinner.ani",
+            "--exit-code",
+        ])
+        .output()
+        .unwrap()
+        .status;
+    assert_eq!(status.code(), Some(2));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,119 @@
+// Importing the tiny CursorFX theme gen-fixtures writes: the strips sliced
+// into frames, the script's hotspots and intervals, and the keys the
+// importer doesn't know kept for the caller
+#![cfg(feature = "cursorfx")]
+
+use std::{collections::BTreeMap, fs, io::Cursor};
+
+use cursor_handler::{Hotspot, cursorfx::CursorFxTheme};
+use image::Rgba;
+
+fn theme() -> CursorFxTheme {
+    let bytes = fs::read("tests/fixtures/cursorfx/tiny.CursorFX").unwrap();
+    CursorFxTheme::decode(Cursor::new(bytes)).unwrap()
+}
+
+fn keys(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|&(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn decodes_the_theme() {
+    let theme = theme();
+    assert_eq!(theme.name.as_deref(), Some("Tiny"));
+    assert_eq!(theme.author.as_deref(), Some("Fixtures"));
+    let roles: Vec<_> = theme
+        .cursors
+        .iter()
+        .map(|cursor| cursor.role.as_str())
+        .collect();
+    assert_eq!(roles, ["Arrow", "Busy"]);
+
+    let arrow = theme.cursor("arrow").unwrap();
+    assert!(!arrow.is_animated());
+    assert_eq!(arrow.frames[0].dimensions(), (16, 16));
+
+    // The vertical strip is cut top to bottom
+    let busy = theme.cursor("BUSY").unwrap();
+    let colors: Vec<_> = busy
+        .frames
+        .iter()
+        .map(|frame| *frame.get_pixel(8, 8))
+        .collect();
+    assert_eq!(
+        colors,
+        [
+            Rgba([255, 0, 0, 255]),
+            Rgba([0, 255, 0, 255]),
+            Rgba([0, 0, 255, 255]),
+            Rgba([255, 255, 255, 255]),
+        ]
+    );
+    assert!(
+        busy.frames
+            .iter()
+            .all(|frame| frame.dimensions() == (16, 16))
+    );
+}
+
+#[test]
+fn hotspots_and_intervals() {
+    let theme = theme();
+    let arrow = theme.cursor("Arrow").unwrap();
+    assert_eq!(arrow.hotspot, Hotspot::new(3, 5));
+    assert_eq!(arrow.interval_ms, 0);
+    let cur = arrow.to_cursor_file().unwrap();
+    assert_eq!(cur[0].hotspot, Hotspot::new(3, 5));
+
+    // 50 ms is 3 jiffies, and every frame keeps the section's hotspot
+    let busy = theme.cursor("Busy").unwrap();
+    assert_eq!(busy.interval_ms, 50);
+    let ani = busy.to_ani().unwrap();
+    assert_eq!(ani.num_frames(), 4);
+    assert_eq!(ani.header.default_rate(), 3);
+    assert!(
+        ani.frames()
+            .all(|frame| frame.hotspot == Hotspot::new(8, 7))
+    );
+    let durations: Vec<_> = ani.steps().map(|step| step.duration).collect();
+    assert_eq!(durations, [3, 3, 3, 3]);
+
+    // A zero interval still plays, at the shortest rate
+    assert_eq!(arrow.to_ani().unwrap().header.default_rate(), 1);
+}
+
+#[test]
+fn unknown_keys_are_kept() {
+    let theme = theme();
+    assert_eq!(theme.extra, keys(&[("version", "2")]));
+    assert_eq!(theme.cursor("Arrow").unwrap().extra, keys(&[("glow", "1")]));
+    assert_eq!(
+        theme.cursor("Busy").unwrap().extra,
+        keys(&[("trail", "Fade")])
+    );
+}
+
+#[test]
+fn damaged_themes_are_errors() {
+    let bytes = fs::read("tests/fixtures/cursorfx/tiny.CursorFX").unwrap();
+    let error = |bytes: &[u8]| {
+        CursorFxTheme::decode(Cursor::new(bytes))
+            .unwrap_err()
+            .to_string()
+    };
+
+    // The header's unpacked size no longer matches the stream
+    let mut wrong_size = bytes.clone();
+    wrong_size[8] ^= 1;
+    assert_eq!(
+        error(&wrong_size),
+        "CursorFX payload size does not match header"
+    );
+    let mut short_header = bytes.clone();
+    short_header[4..8].copy_from_slice(&8u32.to_le_bytes());
+    assert_eq!(error(&short_header), "CursorFX header is too short");
+    assert!(CursorFxTheme::decode(Cursor::new(&bytes[..bytes.len() / 2])).is_err());
+}
@@ -0,0 +1,66 @@
+// `{:?}` and `{:#?}` of large files stay short: payloads print as a
+// summary and long frame lists as their ends, however many bytes they hold
+use cursor_handler::{AniFile, AniFrame, CursorFile, CursorFrame, FrameSize, UnknownChunk};
+
+/// A frame list of `count` frames, each with a `payload`-byte image
+fn cursor(count: usize, payload: usize) -> CursorFile {
+    let size = FrameSize::square(256).unwrap();
+    let frames = (0..count)
+        .map(|index| CursorFrame::new(size, (index as u16, 0), vec![index as u8; payload]))
+        .collect();
+    CursorFile::new(frames)
+}
+
+fn ani(count: usize, payload: usize) -> AniFile {
+    let size = FrameSize::square(256).unwrap();
+    let frames = (0..count)
+        .map(|index| AniFrame::new(size, (0, 0), vec![index as u8; payload], Some(6)))
+        .collect();
+    let mut ani = AniFile::new(frames);
+    ani.unknown_chunks.push(UnknownChunk {
+        id: *b"JUNK",
+        data: vec![0; payload],
+        before_frame: 0,
+    });
+    ani
+}
+
+fn lengths(value: &impl std::fmt::Debug) -> (usize, usize) {
+    (format!("{value:?}").len(), format!("{value:#?}").len())
+}
+
+#[test]
+fn debug_is_bounded() {
+    // A megabyte per frame prints no longer than a byte per frame, give or
+    // take the digits of the sizes
+    let (small, pretty_small) = lengths(&cursor(64, 1));
+    let (large, pretty_large) = lengths(&cursor(64, 1 << 20));
+    assert!(
+        large < 1_000 && pretty_large < 2_000,
+        "{large} {pretty_large}"
+    );
+    assert!(large - small < 40 && pretty_large - pretty_small < 40);
+
+    let (small, pretty_small) = lengths(&ani(64, 1));
+    let (large, pretty_large) = lengths(&ani(64, 1 << 20));
+    assert!(
+        large < 2_000 && pretty_large < 4_000,
+        "{large} {pretty_large}"
+    );
+    assert!(large - small < 60 && pretty_large - pretty_small < 60);
+}
+
+#[test]
+fn debug_summarises_payloads() {
+    let text = format!("{:?}", cursor(1, 4096));
+    assert!(
+        text.contains("image_data: [4096 bytes, dib, fnv:"),
+        "{text}"
+    );
+    assert!(text.contains("hotspot: "), "{text}");
+
+    // Past eight frames only the first and last are listed
+    let text = format!("{:?}", cursor(9, 16));
+    assert!(text.contains("... 7 more ..."), "{text}");
+    assert_eq!(text.matches("CursorFrame").count(), 2, "{text}");
+}
@@ -0,0 +1,82 @@
+// Collecting `DecodeStats` must leave every decode exactly as it was
+use std::{fs, io::Cursor, path::Path};
+
+use cursor_handler::{AniFile, CursorFile, DecodeOptions, FrameErrorPolicy};
+
+/// Everything a decode produced, as comparable text and bytes
+fn outcome(bytes: &[u8], options: &DecodeOptions) -> (Result<Vec<u8>, String>, Vec<String>) {
+    let mut encoded = Cursor::new(Vec::new());
+    let decoded = if bytes.starts_with(b"RIFF") {
+        AniFile::decode_with(Cursor::new(bytes), options)
+            .and_then(|decoded| decoded.value.encode(&mut encoded).map(|_| decoded.warnings))
+    } else {
+        CursorFile::decode_with(Cursor::new(bytes), options)
+            .and_then(|decoded| decoded.value.encode(&mut encoded).map(|_| decoded.warnings))
+    };
+    match decoded {
+        Ok(warnings) => (
+            Ok(encoded.into_inner()),
+            warnings.iter().map(ToString::to_string).collect(),
+        ),
+        Err(err) => (Err(err.to_string()), Vec::new()),
+    }
+}
+
+fn fixtures(dir: &Path, found: &mut Vec<std::path::PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            fixtures(&path, found);
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "cur" || ext == "ani")
+        {
+            found.push(path);
+        }
+    }
+}
+
+#[test]
+fn stats_do_not_change_decoding() {
+    let mut paths = Vec::new();
+    fixtures(Path::new("tests/fixtures"), &mut paths);
+    assert!(paths.len() >= 15);
+
+    let policies = [
+        FrameErrorPolicy::Fail,
+        FrameErrorPolicy::Skip,
+        FrameErrorPolicy::Placeholder,
+    ];
+    let mut variants = Vec::new();
+    for base in [
+        DecodeOptions::default(),
+        DecodeOptions::strict(),
+        DecodeOptions::lenient(),
+    ] {
+        for frame_error_policy in policies {
+            for trim_payloads in [false, true] {
+                variants.push(DecodeOptions {
+                    frame_error_policy,
+                    trim_payloads,
+                    ..base.clone()
+                });
+            }
+        }
+    }
+
+    for path in paths {
+        let bytes = fs::read(&path).unwrap();
+        for options in &variants {
+            let with_stats = DecodeOptions {
+                collect_stats: true,
+                ..options.clone()
+            };
+            assert_eq!(
+                outcome(&bytes, options),
+                outcome(&bytes, &with_stats),
+                "{} with {options:?}",
+                path.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// The `Display` text of every fixture that decodes, pinned by a snapshot in
+// tests/snapshots; run with UPDATE_SNAPSHOTS=1 to rewrite it after an
+// intended format change
+use std::{
+    fmt::Write,
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use cursor_handler::{AniFile, CursorFile};
+
+fn fixtures(dir: &Path, found: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            fixtures(&path, found);
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "cur" || ext == "ani")
+        {
+            found.push(path);
+        }
+    }
+}
+
+fn check_snapshot(name: &str, actual: &str) {
+    let path = Path::new("tests/snapshots").join(name);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap(), actual, "{name}");
+}
+
+#[test]
+fn display_over_fixtures() {
+    let mut paths = Vec::new();
+    fixtures(Path::new("tests/fixtures"), &mut paths);
+    paths.sort();
+
+    let mut text = String::new();
+    for path in &paths {
+        let bytes = Cursor::new(fs::read(path).unwrap());
+        let shown = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("cur") => CursorFile::decode(bytes).map(|cursor| cursor.to_string()),
+            _ => AniFile::decode(bytes).map(|ani| ani.to_string()),
+        };
+        // Truncated fixtures don't decode and have nothing to show
+        let Ok(shown) = shown else { continue };
+        for line in shown.lines() {
+            assert!(line.chars().count() <= 100, "{}: {line}", path.display());
+        }
+        writeln!(text, "== {}\n{shown}", path.display()).unwrap();
+    }
+    check_snapshot("display.txt", &text);
+}
@@ -0,0 +1,142 @@
+// Repeated and out-of-order chunks in hand-built .ani files: the first
+// `anih`, `seq ` or `rate` wins with a warning, frame lists concatenate up
+// to the header's count, and strict mode rejects each duplicate at its
+// offset
+use std::{fs, io::Cursor};
+
+use cursor_handler::{AniFile, DecodeError, DecodeOptions, Decoded};
+
+fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = [&id[..], &(data.len() as u32).to_le_bytes()].concat();
+    chunk.extend_from_slice(data);
+    if !data.len().is_multiple_of(2) {
+        chunk.push(0);
+    }
+    chunk
+}
+
+fn words(values: &[u32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// An anih for `frames` frames and `steps` steps at `rate` jiffies
+fn anih(frames: u32, steps: u32, rate: u32) -> Vec<u8> {
+    chunk(b"anih", &words(&[36, frames, steps, 0, 0, 0, 0, rate, 1]))
+}
+
+/// A frame list of `count` copies of the 32px PNG fixture
+fn fram(count: usize) -> Vec<u8> {
+    let icon = chunk(
+        b"icon",
+        &fs::read("tests/fixtures/png/arrow_32.cur").unwrap(),
+    );
+    chunk(b"LIST", &[&b"fram"[..], &icon.repeat(count)].concat())
+}
+
+fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let body = chunks.concat();
+    let mut bytes = b"RIFF".to_vec();
+    bytes.extend((body.len() as u32 + 4).to_le_bytes());
+    bytes.extend_from_slice(b"ACON");
+    bytes.extend(body);
+    bytes
+}
+
+/// Where each of `chunks` starts in `riff(chunks)`
+fn offsets(chunks: &[Vec<u8>]) -> Vec<u64> {
+    chunks
+        .iter()
+        .scan(12, |at, chunk| {
+            let start = *at;
+            *at += chunk.len() as u64;
+            Some(start)
+        })
+        .collect()
+}
+
+fn lenient(bytes: &[u8]) -> Decoded<AniFile> {
+    AniFile::decode_with(Cursor::new(bytes), &DecodeOptions::lenient()).unwrap()
+}
+
+fn strict_offset(bytes: &[u8]) -> u64 {
+    let err = AniFile::decode_with(Cursor::new(bytes), &DecodeOptions::strict()).unwrap_err();
+    DecodeError::find(&err).unwrap().offset()
+}
+
+fn durations(ani: &AniFile) -> Vec<(usize, u32)> {
+    ani.steps()
+        .map(|step| (step.frame_index, step.duration))
+        .collect()
+}
+
+#[test]
+fn first_anih_wins() {
+    let chunks = [anih(2, 2, 4), fram(2), anih(3, 5, 9)];
+    let bytes = riff(&chunks);
+    let decoded = lenient(&bytes);
+    let ani = decoded.value;
+    assert_eq!(ani.header.declared_frames(), Some(2));
+    assert_eq!(ani.header.default_rate(), 4);
+    assert_eq!(durations(&ani), [(0, 4), (1, 4)]);
+
+    let at = offsets(&chunks)[2];
+    let warning = &decoded.warnings[0];
+    assert_eq!(
+        (warning.offset, &warning.message[..]),
+        (at, "Duplicate 'anih' chunk")
+    );
+    assert_eq!(strict_offset(&bytes), at);
+}
+
+#[test]
+fn first_seq_and_rate_win_wherever_they_are() {
+    // Both tables after the frames, then a second of each
+    let chunks = [
+        anih(3, 4, 6),
+        fram(3),
+        chunk(b"seq ", &words(&[2, 1, 0, 1])),
+        chunk(b"rate", &words(&[1, 2, 3, 4])),
+        chunk(b"seq ", &words(&[0, 0])),
+        chunk(b"rate", &words(&[9, 9])),
+    ];
+    let bytes = riff(&chunks);
+    let decoded = lenient(&bytes);
+    assert_eq!(durations(&decoded.value), [(2, 1), (1, 2), (0, 3), (1, 4)]);
+    let warned: Vec<_> = decoded.warnings.iter().map(|w| w.offset).collect();
+    let at = offsets(&chunks);
+    assert_eq!(warned, [at[4], at[5]]);
+    assert_eq!(strict_offset(&bytes), at[4]);
+
+    // The same tables ahead of the header decode the same way
+    let early = [chunks[2].clone(), chunks[3].clone(), anih(3, 4, 6), fram(3)];
+    assert_eq!(
+        durations(&lenient(&riff(&early)).value),
+        durations(&decoded.value)
+    );
+}
+
+#[test]
+fn frame_lists_concatenate_up_to_the_header() {
+    // Two lists holding exactly the declared frames are one animation
+    let bytes = riff(&[anih(3, 3, 6), fram(2), fram(1)]);
+    let decoded = lenient(&bytes);
+    assert_eq!(decoded.value.num_frames(), 3);
+    assert!(decoded.warnings.is_empty(), "{:?}", decoded.warnings);
+
+    // A second list past the count is reported there and cut off
+    let chunks = [anih(2, 2, 6), fram(2), fram(2)];
+    let bytes = riff(&chunks);
+    let decoded = lenient(&bytes);
+    assert_eq!(decoded.value.num_frames(), 2);
+    assert_eq!(decoded.value.sequence, [0, 1]);
+    let warning = &decoded.warnings[0];
+    assert_eq!(warning.offset, offsets(&chunks)[2]);
+    assert_eq!(
+        warning.message,
+        "2 frame lists hold 4 frames, anih declares 2"
+    );
+    assert_eq!(strict_offset(&bytes), offsets(&chunks)[2]);
+}
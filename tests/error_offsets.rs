@@ -0,0 +1,123 @@
+// Decode errors point into the file: checked against the layout of the
+// intact fixture, walked here by hand rather than with the crate's reader
+use std::{fs, io::Cursor};
+
+use cursor_handler::{AniFile, CursorFile, DecodeError, DecodeOptions};
+
+fn fixture(path: &str) -> Vec<u8> {
+    fs::read(format!("tests/fixtures/{path}")).unwrap()
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+/// Offset, id and size of every item in the frame lists of an .ani
+fn frame_list_items(bytes: &[u8]) -> Vec<(usize, [u8; 4], u32)> {
+    let mut items = Vec::new();
+    let mut at = 12;
+    while at + 8 <= bytes.len() {
+        let size = u32_at(bytes, at + 4) as usize;
+        if &bytes[at..at + 4] == b"LIST" && &bytes[at + 8..at + 12] == b"fram" {
+            let end = at + 8 + size;
+            let mut item = at + 12;
+            while item + 8 <= end {
+                let item_size = u32_at(bytes, item + 4);
+                items.push((item, bytes[item..item + 4].try_into().unwrap(), item_size));
+                item += 8 + item_size.next_multiple_of(2) as usize;
+            }
+        }
+        at += 8 + size.next_multiple_of(2);
+    }
+    items
+}
+
+fn ani_error(bytes: &[u8], options: &DecodeOptions) -> DecodeError {
+    let err = AniFile::decode_with(Cursor::new(bytes), options).unwrap_err();
+    DecodeError::find(&err).unwrap().clone()
+}
+
+#[test]
+fn truncated_ani_names_the_last_frame() {
+    let intact = fixture("normal/spinner.ani");
+    let truncated = fixture("truncated/spinner.ani");
+    let icons = frame_list_items(&intact);
+    assert_eq!(icons.len(), 4);
+    let (at, _, size) = icons[3];
+    let got = truncated.len() - at - 8;
+
+    let err = ani_error(&truncated, &DecodeOptions::default());
+    assert_eq!(
+        err,
+        DecodeError::TruncatedChunk {
+            id: "icon".into(),
+            index: Some(3),
+            at: at as u64,
+            expected: size.into(),
+            got: got as u64,
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        format!("'icon' #3 at {at:#X}: truncated, expected {size} bytes, got {got}")
+    );
+    assert_eq!(ani_error(&truncated, &DecodeOptions::strict()), err);
+}
+
+#[test]
+fn truncated_cur_names_the_image() {
+    let truncated = fixture("truncated/arrow.cur");
+    // The second 16-byte directory entry, after the 6-byte header
+    let entry = 6 + 16;
+    let size = u32_at(&truncated, entry + 8);
+    let at = u32_at(&truncated, entry + 12);
+    assert!(
+        (at as usize) < truncated.len(),
+        "the cut is inside the image"
+    );
+
+    let err = CursorFile::decode(Cursor::new(&truncated)).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert_eq!(
+        DecodeError::find(&err),
+        Some(&DecodeError::TruncatedChunk {
+            id: "image".into(),
+            index: Some(1),
+            at: at.into(),
+            expected: size.into(),
+            got: (truncated.len() - at as usize) as u64,
+        })
+    );
+}
+
+#[test]
+fn strict_mode_points_at_the_stray_chunk() {
+    let bytes = fixture("riff/junk.ani");
+    let items = frame_list_items(&bytes);
+    let ids: Vec<_> = items.iter().map(|(_, id, _)| id).collect();
+    assert_eq!(ids, [b"icon", b"JUNK", b"icon", b"icon", b"icon"]);
+
+    assert!(AniFile::decode(Cursor::new(&bytes)).is_ok());
+    let err = ani_error(&bytes, &DecodeOptions::strict());
+    assert_eq!(err.offset(), items[1].0 as u64);
+    assert!(
+        err.to_string()
+            .ends_with("Unexpected 'JUNK' chunk in frame list"),
+        "{err}"
+    );
+}
+
+#[test]
+fn damaged_frame_is_reported_by_index() {
+    let mut bytes = fixture("normal/spinner.ani");
+    let (at, _, _) = frame_list_items(&bytes)[2];
+    // The third icon's payload no longer claims to be a cursor
+    bytes[at + 8 + 2] = 9;
+
+    let err = ani_error(&bytes, &DecodeOptions::default());
+    assert!(
+        matches!(err, DecodeError::InvalidChunk { index: Some(2), .. }),
+        "{err}"
+    );
+    assert_eq!(err.offset(), at as u64, "the icon chunk holding it");
+}
@@ -0,0 +1,99 @@
+// The committed fixtures match a fresh build: `test_utils::fixtures` is
+// written to a scratch directory the way `gen-fixtures` writes it, and the
+// tree under tests/fixtures must hold exactly those files and manifest. A
+// failure here means the generator changed; run `gen-fixtures` and commit
+// the result.
+#![cfg(not(target_os = "wasi"))]
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use cursor_handler::{
+    AniFile, CursorFile,
+    test_utils::{FIXTURE_DIR, FIXTURE_MANIFEST, assert_cursor_bytes_eq, fixtures, manifest},
+};
+
+/// Every file under `dir`, as a `/`-separated path relative to it
+fn files(dir: &Path) -> BTreeSet<String> {
+    let mut found = BTreeSet::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(at) = pending.pop() {
+        for entry in fs::read_dir(&at).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                let relative = path.strip_prefix(dir).unwrap();
+                let parts: Vec<_> = relative.iter().map(|part| part.to_string_lossy()).collect();
+                found.insert(parts.join("/"));
+            }
+        }
+    }
+    found
+}
+
+/// A fresh build of the fixtures and their manifest, on disk
+fn regenerate() -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fixtures_current");
+    let _ = fs::remove_dir_all(&dir);
+    let fixtures = fixtures().unwrap();
+    let manifest = (FIXTURE_MANIFEST, manifest(&fixtures).into_bytes());
+    for (path, bytes) in fixtures.iter().chain([&manifest]) {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, bytes).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn committed_fixtures_are_current() {
+    let fresh = regenerate();
+    let committed = Path::new(FIXTURE_DIR);
+    assert_eq!(
+        fs::read_to_string(committed.join(FIXTURE_MANIFEST)).unwrap(),
+        fs::read_to_string(fresh.join(FIXTURE_MANIFEST)).unwrap(),
+        "run gen-fixtures to regenerate"
+    );
+    assert_eq!(files(committed), files(&fresh), "stale or missing fixtures");
+    for path in files(&fresh) {
+        let expected = fs::read(fresh.join(&path)).unwrap();
+        let actual = fs::read(committed.join(&path)).unwrap();
+        if path.ends_with(".cur") || path.ends_with(".ani") {
+            assert_cursor_bytes_eq(&expected, &actual);
+        } else {
+            assert!(expected == actual, "{path} differs from a fresh build");
+        }
+    }
+    fs::remove_dir_all(&fresh).unwrap();
+}
+
+#[test]
+fn groups_decode_as_named() {
+    // Each group fails, or doesn't, the way its name says
+    for (path, bytes) in fixtures().unwrap() {
+        let decoded = match path.rsplit_once('.') {
+            Some((_, "cur")) => CursorFile::decode(Cursor::new(&bytes)).map(|_| ()),
+            Some((_, "ani")) => AniFile::decode(Cursor::new(&bytes)).map(|_| ()),
+            _ => continue, // Themes are covered by their importers' tests
+        };
+        assert_eq!(decoded.is_ok(), !path.starts_with("truncated/"), "{path}");
+    }
+
+    // The palette and 24-bit payloads all hold the same pixels
+    let dibs: Vec<_> = fixtures()
+        .unwrap()
+        .iter()
+        .filter(|(path, _)| path.starts_with("dib/"))
+        .map(|(_, bytes)| {
+            CursorFile::decode(Cursor::new(bytes)).unwrap()[0]
+                .decode_image()
+                .unwrap()
+        })
+        .collect();
+    assert!(dibs.len() == 4 && dibs.windows(2).all(|pair| pair[0] == pair[1]));
+}
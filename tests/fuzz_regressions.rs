@@ -0,0 +1,67 @@
+// Inputs the fuzz targets once crashed on, in tests/fuzz_regressions, run
+// through what the targets do. Each must decode or fail with an error; a
+// new crash from `cargo fuzz` goes in the directory under a name saying
+// what it broke.
+use std::{
+    fs,
+    io::Cursor,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+};
+
+use cursor_handler::{AniFile, CursorFile, CursorFrame, DecodeOptions, FrameSize};
+
+fn inputs() -> Vec<PathBuf> {
+    let mut paths: Vec<_> = fs::read_dir("tests/fuzz_regressions")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Both decoders in both modes, whatever the file claims to be, and what
+/// callers do next with a decoded file
+fn exercise(data: &[u8]) {
+    for options in [DecodeOptions::lenient(), DecodeOptions::strict()] {
+        if let Ok(decoded) = CursorFile::decode_with(Cursor::new(data), &options) {
+            let cursor = decoded.value;
+            let _ = cursor.validate();
+            let _ = format!("{cursor} {cursor:?}");
+            for frame in cursor.frames() {
+                let _ = frame.decode_image();
+            }
+        }
+        if let Ok(decoded) = AniFile::decode_with(Cursor::new(data), &options) {
+            let ani = decoded.value;
+            let _ = ani.validate();
+            let _ = format!("{ani} {ani:?}");
+            let _ = ani.steps().count();
+            for frame in ani.frames() {
+                let _ = frame.decode_image();
+            }
+        }
+    }
+    let _ = CursorFile::validate_bytes(data);
+    let _ = AniFile::validate_bytes(data);
+
+    // The decode_image target: two directory size bytes, then a payload
+    if let Some((&size, payload)) = data.split_first_chunk::<2>() {
+        let frame = CursorFrame::new(FrameSize::from_dir_bytes(size), (0, 0), payload.to_vec());
+        let _ = frame.decode_image();
+    }
+}
+
+#[test]
+fn no_input_panics() {
+    let inputs = inputs();
+    assert!(!inputs.is_empty());
+    let panicked: Vec<_> = inputs
+        .iter()
+        .filter(|path| {
+            let data = fs::read(path).unwrap();
+            panic::catch_unwind(AssertUnwindSafe(|| exercise(&data))).is_err()
+        })
+        .collect();
+    assert!(panicked.is_empty(), "panicked on {panicked:?}");
+}
@@ -0,0 +1,162 @@
+// `generate` writes exactly what the library generators build, pinned by a
+// structure snapshot in tests/snapshots; run with UPDATE_SNAPSHOTS=1 to
+// rewrite it after an intended change
+#![cfg(feature = "cli")]
+
+use std::{
+    fmt::Write,
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use cursor_handler::{
+    AniFile, GenerateOptions, Hotspot, HotspotSpec, generate, test_utils::bytes_report,
+};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+fn temp_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "cursor_handler_generate_{test}_{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A 16x16 arrow-ish source with some colour for the hue to turn
+fn source() -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(16, 16, |x, y| {
+        if x <= y {
+            Rgba([200, (x * 16) as u8, 40, 255])
+        } else {
+            Rgba([0, 0, 0, 0])
+        }
+    }))
+}
+
+fn run(args: &[&str], image: &Path, output: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cursor_handler"))
+        .arg("generate")
+        .args(args)
+        .arg(image)
+        .arg("-o")
+        .arg(output)
+        .output()
+        .unwrap()
+}
+
+fn check_snapshot(name: &str, actual: &str) {
+    let path = Path::new("tests/snapshots").join(name);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap(), actual, "{name}");
+}
+
+/// Frame count, sizes, hotspots and every step's frame and duration
+fn structure(name: &str, ani: &AniFile) -> String {
+    let mut text = format!("{name}: {} frame(s)", ani.num_frames());
+    for frame in ani.frames() {
+        write!(text, ", {} at {}", frame.size, frame.hotspot).unwrap();
+    }
+    text.push_str("\n  steps:");
+    for step in ani.steps() {
+        write!(text, " {}:{}j", step.frame_index, step.duration).unwrap();
+    }
+    text.push('\n');
+    text
+}
+
+#[test]
+fn generators_match_library() {
+    let dir = temp_dir("match");
+    let image = dir.join("arrow.png");
+    source().save(&image).unwrap();
+
+    let common = ["--steps", "4", "--hotspot", "8,9", "--rate", "100ms"];
+    let options = GenerateOptions {
+        steps: 4,
+        hotspot: HotspotSpec::Absolute(Hotspot::new(8, 9)),
+        rate: 6,
+    };
+    let cases = [
+        (
+            "hue-cycle",
+            &["--degrees", "90"][..],
+            generate::hue_cycle(&source(), 90, &options),
+        ),
+        (
+            "spin",
+            &["--degrees", "180"],
+            generate::spin(&source(), 180.0, &options),
+        ),
+        (
+            "pulse",
+            &["--min-scale", "0.5"],
+            generate::pulse(&source(), 0.5, &options),
+        ),
+        ("blink", &[], generate::blink(&source(), &options)),
+    ];
+
+    let mut snapshot = String::new();
+    for (name, args, expected) in cases {
+        let path = dir.join(format!("{name}.ani"));
+        let output = run(&[&[name], &common[..], args].concat(), &image, &path);
+        assert!(output.status.success(), "{output:?}");
+
+        let mut bytes = Cursor::new(Vec::new());
+        expected.unwrap().encode(&mut bytes).unwrap();
+        let written = fs::read(&path).unwrap();
+        if let Some(report) = bytes_report(&bytes.into_inner(), &written) {
+            panic!("{name}: the CLI and library differ\n{report}");
+        }
+
+        let ani = AniFile::decode(Cursor::new(written)).unwrap();
+        snapshot.push_str(&structure(name, &ani));
+    }
+    check_snapshot("generate.txt", &snapshot);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn parameters_are_checked() {
+    let image = source();
+    let options = |steps, rate| GenerateOptions {
+        steps,
+        hotspot: HotspotSpec::TopLeft,
+        rate,
+    };
+    let message = |result: std::io::Result<AniFile>| result.unwrap_err().to_string();
+
+    assert_eq!(
+        message(generate::hue_cycle(&image, 15, &options(1, 6))),
+        "an animation needs at least 2 steps"
+    );
+    assert_eq!(
+        message(generate::spin(&image, 360.0, &options(4, 0))),
+        "rate must be at least 1 jiffy"
+    );
+    assert_eq!(
+        message(generate::blink(&image, &options(0, 6))),
+        "an animation needs at least 2 steps"
+    );
+    assert_eq!(
+        message(generate::pulse(&image, 1.5, &options(4, 6))),
+        "min scale must be in (0, 1]"
+    );
+    assert!(generate::pulse(&image, 0.5, &options(2, 1)).is_ok());
+
+    // The CLI refuses the same before reading the image
+    let dir = temp_dir("checked");
+    let (png, out) = (dir.join("missing.png"), dir.join("out.ani"));
+    for args in [["--steps", "1"], ["--rate", "5ms"], ["--rate", "0"]] {
+        let output = run(&[&["spin"], &args[..]].concat(), &png, &out);
+        assert_eq!(output.status.code(), Some(2), "{args:?}");
+        assert!(!out.exists());
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,92 @@
+// Round trips that only ever touch memory, so they run the same on targets
+// without a filesystem such as wasm32-wasip1
+use std::io::Cursor;
+
+use cursor_handler::{
+    AniFile, CursorFile, OptimizeOptions, SanitizeOptions,
+    analysis::Rect,
+    size::SnapPolicy,
+    transform::{EdgePolicy, PxAlgo},
+    xcursor,
+};
+
+const ANI: &[u8] = include_bytes!("../assets/aero_busy.ani");
+const CUR: &[u8] = include_bytes!("../assets/windows_theme/Link.cur");
+
+fn reencode_cur(cursor: &CursorFile) -> CursorFile {
+    let mut bytes = Vec::new();
+    cursor.encode(&mut bytes).unwrap();
+    CursorFile::decode(Cursor::new(bytes)).unwrap()
+}
+
+fn reencode_ani(ani: &AniFile) -> AniFile {
+    let mut bytes = Cursor::new(Vec::new());
+    ani.encode(&mut bytes).unwrap();
+    AniFile::decode(Cursor::new(bytes.into_inner())).unwrap()
+}
+
+#[test]
+fn cur_round_trip() {
+    let cursor = CursorFile::decode(Cursor::new(CUR)).unwrap();
+    assert!(cursor.validate().is_empty());
+    assert_eq!(reencode_cur(&cursor).fingerprint(), cursor.fingerprint());
+    assert!(cursor.encodes_identically_to(CUR).unwrap());
+}
+
+#[test]
+fn ani_round_trip() {
+    let ani = AniFile::decode(Cursor::new(ANI)).unwrap();
+    assert!(ani.validate().is_empty());
+    assert_eq!(reencode_ani(&ani).fingerprint(), ani.fingerprint());
+}
+
+#[test]
+fn sanitize_and_optimize() {
+    let (mut ani, _) = AniFile::sanitize_bytes(ANI, &SanitizeOptions::default()).unwrap();
+    ani.optimize(&OptimizeOptions::default()).unwrap();
+    assert_eq!(ani.num_frames(), 18);
+    assert!(reencode_ani(&ani).validate().is_empty());
+
+    let (mut cursor, _) = CursorFile::sanitize_bytes(CUR, &SanitizeOptions::default()).unwrap();
+    let original = cursor.clone();
+    cursor.optimize(&OptimizeOptions::default()).unwrap();
+    assert!(cursor.renders_same_as(&original).unwrap());
+}
+
+#[test]
+fn transforms() {
+    let mut ani = AniFile::decode(Cursor::new(ANI)).unwrap();
+    ani.translate(2, 1, EdgePolicy::Clip).unwrap();
+    ani.crop_to(Rect::new(16, 16, 32, 32)).unwrap();
+    ani.upscale_pixel_art(2, PxAlgo::Scale2x).unwrap();
+    assert!(reencode_ani(&ani).validate().is_empty());
+
+    let mut cursor = CursorFile::decode(Cursor::new(CUR)).unwrap();
+    cursor.autocrop().unwrap();
+    cursor
+        .snap_to_standard_sizes(SnapPolicy::NearestUp)
+        .unwrap();
+    assert!(cursor.frames().all(|frame| frame.size.is_standard()));
+    assert!(reencode_cur(&cursor).validate().is_empty());
+}
+
+#[test]
+fn exports() {
+    let ani = AniFile::decode(Cursor::new(ANI)).unwrap();
+    assert!(ani.to_apng().unwrap().starts_with(b"\x89PNG"));
+    assert!(ani.to_gif().unwrap().starts_with(b"GIF89a"));
+    let images = xcursor::decode(&ani.to_xcursor().unwrap()).unwrap();
+    assert!(!images.is_empty());
+
+    let cursor = CursorFile::decode(Cursor::new(CUR)).unwrap();
+    let images = xcursor::decode(&cursor.to_xcursor().unwrap()).unwrap();
+    assert_eq!(images.len(), cursor.frames().count());
+}
+
+#[test]
+fn encode_errors_are_not_mismatches() {
+    let mut ani = AniFile::decode(Cursor::new(ANI)).unwrap();
+    // A step playing a frame that doesn't exist can't be encoded
+    ani.sequence = vec![ani.num_frames()];
+    assert!(ani.encodes_identically_to(ANI).is_err());
+}
@@ -0,0 +1,106 @@
+// `--format json` output over the fixtures, pinned by snapshots in
+// tests/snapshots; run with UPDATE_SNAPSHOTS=1 to rewrite them after an
+// intended schema change
+#![cfg(feature = "cli")]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn fixtures(dir: &Path, found: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            fixtures(&path, found);
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "cur" || ext == "ani")
+        {
+            found.push(path);
+        }
+    }
+}
+
+fn sorted_fixtures() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    fixtures(Path::new("tests/fixtures"), &mut paths);
+    paths.sort();
+    paths
+}
+
+fn run(args: &[&str], paths: &[PathBuf]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cursor_handler"))
+        .args(args)
+        .args(paths)
+        .output()
+        .unwrap()
+}
+
+fn check_snapshot(name: &str, actual: &str) {
+    let path = Path::new("tests/snapshots").join(name);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap();
+    for (line, (expected, actual)) in expected.lines().zip(actual.lines()).enumerate() {
+        assert_eq!(expected, actual, "{name} line {}", line + 1);
+    }
+    assert_eq!(expected.lines().count(), actual.lines().count(), "{name}");
+}
+
+/// Run `args` as JSON lines and as text, checking both exit the same way
+fn snapshot(name: &str, args: &[&str], paths: &[PathBuf]) {
+    let json = run(&[args, &["--jsonl"]].concat(), paths);
+    let text = run(args, paths);
+    assert_eq!(json.status.code(), text.status.code(), "{name}");
+
+    let stdout = String::from_utf8(json.stdout).unwrap();
+    for line in stdout.lines() {
+        let document: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert!(document.is_object(), "{name}: {line}");
+    }
+    check_snapshot(name, &stdout);
+}
+
+#[test]
+fn info() {
+    snapshot("info.jsonl", &["info"], &sorted_fixtures());
+}
+
+#[test]
+fn validate() {
+    snapshot("validate.jsonl", &["validate"], &sorted_fixtures());
+}
+
+#[test]
+fn compare() {
+    let pairs = [
+        ("normal/arrow.cur", "normal/arrow.cur"),
+        ("normal/arrow.cur", "png/arrow_32.cur"),
+        ("normal/spinner.ani", "metadata/odd_info.ani"),
+        ("normal/spinner.ani", "riff/junk.ani"),
+        ("normal/arrow.cur", "normal/spinner.ani"),
+        ("normal/arrow.cur", "truncated/arrow.cur"),
+    ];
+    let mut documents = String::new();
+    for (old, new) in pairs {
+        let paths = [old, new].map(|path| Path::new("tests/fixtures").join(path));
+        let text = run(&["compare"], &paths);
+        let json = run(&["compare", "--jsonl"], &paths);
+        assert_eq!(json.status.code(), text.status.code(), "{old} {new}");
+        documents.push_str(&String::from_utf8(json.stdout).unwrap());
+    }
+    check_snapshot("compare.jsonl", &documents);
+}
+
+#[test]
+fn unreadable_input() {
+    let output = run(&["info", "--format", "json"], &["tests/no_such.cur".into()]);
+    assert_eq!(output.status.code(), Some(1));
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["path"], "tests/no_such.cur");
+    assert_eq!(document["error"]["kind"], "read");
+}
@@ -0,0 +1,122 @@
+// `layout` must describe exactly the bytes the encoder writes
+use std::{fs, io::Cursor, path::Path};
+
+use cursor_handler::{AniFile, CursorFile, EncodeOptions, FileLayout, LoopBehavior, Section};
+
+fn dword(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+/// Check each entry against the encoded bytes, and that together they
+/// cover the file
+fn check(layout: &FileLayout, bytes: &[u8], riff: bool, name: &str) {
+    assert_eq!(layout.total, bytes.len() as u64, "{name}");
+    let mut at = 0;
+    for &(section, offset, len) in &layout.entries {
+        assert_eq!(
+            offset, at,
+            "{name}: {section:?} is not where the last entry ended"
+        );
+        at += len;
+        let (offset, len) = (offset as usize, len as usize);
+        let entry = &bytes[offset..offset + len];
+        match section {
+            Section::Header if riff => {
+                assert_eq!((&entry[..4], len), (&b"RIFF"[..], 12), "{name}");
+                assert_eq!(dword(entry, 4) as usize, bytes.len() - 8, "{name}");
+            }
+            Section::Header => assert_eq!((&entry[..4], len), (&[0, 0, 2, 0][..], 6), "{name}"),
+            Section::DirectoryEntry(index) => {
+                assert_eq!((offset, len), (6 + index * 16, 16), "{name}");
+                let data = layout.find(Section::Frame(index));
+                let payload = (dword(entry, 12) as u64, dword(entry, 8) as u64);
+                // Frames sharing a payload have no entry of their own
+                assert!(
+                    data.is_none_or(|data| data == payload),
+                    "{name}: frame {index}"
+                );
+            }
+            Section::Chunk(id) => {
+                assert_eq!(entry[..4], id, "{name}");
+                assert_eq!(dword(entry, 4) as usize + 8, len, "{name}");
+            }
+            Section::List(list_type) => {
+                assert_eq!(
+                    (&entry[..4], &entry[8..], len),
+                    (&b"LIST"[..], &list_type[..], 12)
+                );
+            }
+            Section::Frame(_) if riff => {
+                assert_eq!(&entry[..4], b"icon", "{name}");
+                assert_eq!(dword(entry, 4) as usize + 8, len, "{name}");
+            }
+            Section::Frame(_) => assert!(offset >= 6 + 16, "{name}"),
+            Section::Padding => assert_eq!(entry, [0], "{name}"),
+        }
+    }
+}
+
+fn fixtures(dir: &Path, found: &mut Vec<std::path::PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            fixtures(&path, found);
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "cur" || ext == "ani")
+        {
+            found.push(path);
+        }
+    }
+}
+
+#[test]
+fn layout_matches_encoding() {
+    let mut paths = vec!["assets/aero_busy.ani".into()];
+    fixtures(Path::new("tests/fixtures"), &mut paths);
+
+    let options = [
+        EncodeOptions::default(),
+        EncodeOptions {
+            write_loop_chunk: true,
+            share_payloads: true,
+            ..Default::default()
+        },
+    ];
+    let mut checked = 0;
+    for path in paths {
+        let name = path.display().to_string();
+        let bytes = fs::read(&path).unwrap();
+        for options in &options {
+            if bytes.starts_with(b"RIFF") {
+                let Ok(mut ani) = AniFile::decode(Cursor::new(&bytes)) else {
+                    continue;
+                };
+                ani.loop_behavior = LoopBehavior::Count(2);
+                let mut encoded = Cursor::new(Vec::new());
+                // A sequence playing a missing frame is refused by both
+                if ani.encode_with(&mut encoded, options).is_err() {
+                    assert!(ani.layout(options).is_err(), "{name}");
+                    continue;
+                }
+                check(
+                    &ani.layout(options).unwrap(),
+                    &encoded.into_inner(),
+                    true,
+                    &name,
+                );
+            } else {
+                let Ok(mut cursor) = CursorFile::decode(Cursor::new(&bytes)) else {
+                    continue;
+                };
+                // A repeated frame, for `share_payloads`
+                cursor.push_frame(cursor[0].clone());
+                let mut encoded = Vec::new();
+                cursor.encode_with(&mut encoded, options).unwrap();
+                check(&cursor.layout(options).unwrap(), &encoded, false, &name);
+            }
+            checked += 1;
+        }
+    }
+    assert!(checked >= 26);
+}
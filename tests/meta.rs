@@ -0,0 +1,172 @@
+// `meta` reads and edits INFO tags by rewriting only the INFO list, so every
+// other byte of the file survives, even chunks a re-encode would drop
+#![cfg(feature = "cli")]
+
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use cursor_handler::{AniFile, InfoEntry, metadata::read_info};
+
+/// Where the frame list of aero_busy.ani starts, after the header and anih
+const FRAMES_AT: usize = 56;
+
+fn temp_dir(test: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("cursor_handler_meta_{test}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = [&id[..], &(data.len() as u32).to_le_bytes()].concat();
+    chunk.extend_from_slice(data);
+    if !data.len().is_multiple_of(2) {
+        chunk.push(0);
+    }
+    chunk
+}
+
+/// aero_busy.ani with a top-level JUNK chunk and an INFO list holding a
+/// non-UTF-8 comment, and the range the INFO list takes
+fn fixture() -> (Vec<u8>, std::ops::Range<usize>) {
+    let original = fs::read("assets/aero_busy.ani").unwrap();
+    let mut info = b"INFO".to_vec();
+    info.extend(chunk(b"INAM", b"Old\0"));
+    info.extend(chunk(b"ICMT", b"caf\xe9\0"));
+    info.extend(chunk(b"IXYZ", b"vendor\0"));
+
+    let mut bytes = original[..FRAMES_AT].to_vec();
+    bytes.extend(chunk(b"JUNK", b"odd"));
+    let start = bytes.len();
+    bytes.extend(chunk(b"LIST", &info));
+    let range = start..bytes.len();
+    bytes.extend_from_slice(&original[FRAMES_AT..]);
+    let size = (bytes.len() - 8) as u32;
+    bytes[4..8].copy_from_slice(&size.to_le_bytes());
+    (bytes, range)
+}
+
+fn meta(args: &[&str], path: &Path, output: Option<&Path>) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_cursor_handler"));
+    command.arg("meta").args(args).arg(path);
+    if let Some(output) = output {
+        command.arg("-o").arg(output);
+    }
+    let output = command.output().unwrap();
+    assert!(output.status.success(), "{output:?}");
+    output
+}
+
+/// `edited` is `original` with only the INFO list at `range` and the RIFF
+/// size field changed
+fn assert_only_info_changed(original: &[u8], edited: &[u8], range: &std::ops::Range<usize>) {
+    assert_eq!(edited[..4], original[..4]);
+    assert_eq!(edited[8..range.start], original[8..range.start]);
+    assert!(edited.ends_with(&original[range.end..]));
+    let size = u32::from_le_bytes(edited[4..8].try_into().unwrap());
+    assert_eq!(size as usize, edited.len() - 8);
+}
+
+#[test]
+fn show() {
+    let dir = temp_dir("show");
+    let path = dir.join("wait.ani");
+    fs::write(&path, fixture().0).unwrap();
+
+    let output = meta(&["show"], &path, None);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout,
+        "title: Old\ncomment: caf\u{fffd} (not UTF-8)\nIXYZ: vendor\n"
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn set_touches_only_info() {
+    let dir = temp_dir("set");
+    let (path, out) = (dir.join("wait.ani"), dir.join("out.ani"));
+    let (original, range) = fixture();
+    fs::write(&path, &original).unwrap();
+
+    meta(
+        &["set", "--title", "Spinning Hourglass", "--artist", "Ayman"],
+        &path,
+        Some(&out),
+    );
+    let edited = fs::read(&out).unwrap();
+    assert_only_info_changed(&original, &edited, &range);
+
+    // The title is replaced where it was, the comment kept byte for byte and
+    // the artist added at the end
+    assert_eq!(
+        read_info(&edited).unwrap(),
+        [
+            InfoEntry::from_text(*b"INAM", "Spinning Hourglass"),
+            InfoEntry::new(*b"ICMT", &b"caf\xe9\0"[..]),
+            InfoEntry::from_text(*b"IXYZ", "vendor"),
+            InfoEntry::from_text(*b"IART", "Ayman"),
+        ]
+    );
+    let ani = AniFile::decode(Cursor::new(&edited)).unwrap();
+    assert_eq!(ani.title().as_deref(), Some("Spinning Hourglass"));
+    assert_eq!(ani.num_frames(), 18);
+
+    // Without -o the input is rewritten in place; an empty value removes a tag
+    meta(
+        &["set", "--tag", "IXYZ=", "--comment", "plain"],
+        &path,
+        None,
+    );
+    let edited = fs::read(&path).unwrap();
+    assert_only_info_changed(&original, &edited, &range);
+    assert_eq!(
+        read_info(&edited).unwrap(),
+        [
+            InfoEntry::from_text(*b"INAM", "Old"),
+            InfoEntry::from_text(*b"ICMT", "plain"),
+        ]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn set_and_strip_without_info() {
+    let dir = temp_dir("strip");
+    let path = dir.join("wait.ani");
+    let (original, range) = fixture();
+    fs::write(&path, &original).unwrap();
+
+    meta(&["strip"], &path, None);
+    let stripped = fs::read(&path).unwrap();
+    assert_only_info_changed(&original, &stripped, &range);
+    assert_eq!(stripped.len(), original.len() - range.len());
+    assert!(read_info(&stripped).unwrap().is_empty());
+    let stdout = meta(&["show"], &path, None).stdout;
+    assert_eq!(String::from_utf8(stdout).unwrap(), "no INFO tags\n");
+
+    // A file without INFO gets the list in front of its frames
+    let busy = fs::read("assets/aero_busy.ani").unwrap();
+    fs::write(&path, &busy).unwrap();
+    meta(&["set", "--title", "Busy"], &path, None);
+    let tagged = fs::read(&path).unwrap();
+    assert_only_info_changed(&busy, &tagged, &(FRAMES_AT..FRAMES_AT));
+    assert_eq!(&tagged[FRAMES_AT..FRAMES_AT + 4], b"LIST");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cur_is_refused() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cursor_handler"))
+        .args(["meta", "show", "assets/windows_theme/Link.cur"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("not an .ani file"), "{stderr}");
+}
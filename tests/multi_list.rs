@@ -0,0 +1,83 @@
+// The generated two-list fixture: four frames split over two `LIST fram`
+// chunks, played by a sequence that starts in the second. The frames join
+// up, a header declaring fewer keeps the ones the sequence plays, and
+// re-encoding writes a single list.
+use std::{fs, io::Cursor};
+
+use cursor_handler::{AniFile, DecodeOptions};
+
+fn fixture() -> Vec<u8> {
+    fs::read("tests/fixtures/riff/multi_list.ani").unwrap()
+}
+
+fn frame_indices(ani: &AniFile) -> Vec<usize> {
+    ani.steps().map(|step| step.frame_index).collect()
+}
+
+/// How many `LIST fram` chunks the bytes hold
+fn frame_lists(bytes: &[u8]) -> usize {
+    bytes.windows(4).filter(|window| window == b"fram").count()
+}
+
+#[test]
+fn lists_join_up() {
+    let bytes = fixture();
+    assert_eq!(frame_lists(&bytes), 2);
+    let decoded = AniFile::decode_with(Cursor::new(&bytes), &DecodeOptions::strict()).unwrap();
+    assert!(decoded.warnings.is_empty(), "{:?}", decoded.warnings);
+    assert_eq!(decoded.value.num_frames(), 4);
+    assert_eq!(frame_indices(&decoded.value), [3, 2, 1, 0]);
+}
+
+#[test]
+fn played_frames_outlive_the_header() {
+    // nFrames is the second field of the anih data
+    let mut bytes = fixture();
+    bytes[24..28].copy_from_slice(&1u32.to_le_bytes());
+
+    let decoded = AniFile::decode_with(Cursor::new(&bytes), &DecodeOptions::lenient()).unwrap();
+    let messages: Vec<_> = decoded
+        .warnings
+        .iter()
+        .map(|w| w.message.as_str())
+        .collect();
+    assert_eq!(messages, ["2 frame lists hold 4 frames, anih declares 1"]);
+    // Frame 3 is played, so nothing past it is a leftover
+    assert_eq!(decoded.value.num_frames(), 4);
+    assert_eq!(frame_indices(&decoded.value), [3, 2, 1, 0]);
+    assert!(AniFile::decode_with(Cursor::new(&bytes), &DecodeOptions::strict()).is_err());
+
+    // A sequence stopping short of the second list lets it go
+    let at = bytes
+        .windows(4)
+        .position(|window| window == b"seq ")
+        .unwrap()
+        + 8;
+    bytes[at..at + 16].copy_from_slice(&[0u32, 0, 0, 0].map(u32::to_le_bytes).concat());
+    let ani = AniFile::decode_with(Cursor::new(&bytes), &DecodeOptions::lenient())
+        .unwrap()
+        .value;
+    assert_eq!(ani.num_frames(), 1);
+    assert_eq!(frame_indices(&ani), [0, 0, 0, 0]);
+}
+
+#[test]
+fn reencoding_writes_one_list() {
+    let ani = AniFile::decode(Cursor::new(fixture())).unwrap();
+    let mut bytes = Cursor::new(Vec::new());
+    ani.encode(&mut bytes).unwrap();
+    let bytes = bytes.into_inner();
+    assert_eq!(frame_lists(&bytes), 1);
+    let again = AniFile::decode(Cursor::new(bytes)).unwrap();
+    assert_eq!(frame_indices(&again), frame_indices(&ani));
+    assert_eq!(again.header.declared_frames(), Some(4));
+}
+
+#[test]
+fn dangling_steps_are_not_written() {
+    let mut ani = AniFile::decode(Cursor::new(fixture())).unwrap();
+    ani.sequence = vec![1, 0, 4];
+    let err = ani.encode(&mut Cursor::new(Vec::new())).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(err.to_string(), "step 2 plays frame 4, the animation has 4");
+}
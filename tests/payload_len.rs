@@ -0,0 +1,149 @@
+// Frames whose declared size disagrees with the length their own PNG or DIB
+// headers give: padded with trailing junk, or cut short. Lenient decoding
+// warns at the payload and can trim the junk, strict decoding fails there.
+use std::{fs, io::Cursor};
+
+use cursor_handler::{AniFile, CursorFile, DecodeError, DecodeOptions};
+
+fn fixture(path: &str) -> Vec<u8> {
+    fs::read(format!("tests/fixtures/{path}")).unwrap()
+}
+
+fn warnings(bytes: &[u8]) -> Vec<(u64, String)> {
+    CursorFile::decode_with(Cursor::new(bytes), &DecodeOptions::lenient())
+        .unwrap()
+        .warnings
+        .into_iter()
+        .map(|warning| (warning.offset, warning.message))
+        .collect()
+}
+
+fn strict_error(result: std::io::Result<impl Sized>) -> DecodeError {
+    let err = result.err().expect("strict decoding should fail");
+    DecodeError::find(&err).unwrap().clone()
+}
+
+// The only payload of a one-frame .cur follows its 6-byte header and
+// 16-byte directory entry
+const PAYLOAD: u64 = 22;
+
+#[test]
+fn over_declared_png() {
+    let bytes = fixture("lying/payload_over.cur");
+    let cursor = CursorFile::decode(Cursor::new(&bytes)).unwrap();
+    let frame = &cursor[0];
+    assert!(frame.is_png());
+    assert!(!frame.payload_len_consistent());
+    assert_eq!(frame.expected_len(), Some(frame.image_data.len() - 16));
+    assert_eq!(
+        warnings(&bytes),
+        [(PAYLOAD, "16 bytes of trailing data".to_string())]
+    );
+
+    // The image itself is whole, and validate names the mismatch
+    assert_eq!(frame.decode_image().unwrap().dimensions(), (32, 32));
+    let codes: Vec<_> = cursor
+        .validate()
+        .into_iter()
+        .map(|issue| issue.code)
+        .collect();
+    assert!(codes.contains(&"CUR007"), "{codes:?}");
+
+    let err = strict_error(CursorFile::decode_with(
+        Cursor::new(&bytes),
+        &DecodeOptions::strict(),
+    ));
+    assert_eq!(
+        err,
+        DecodeError::InvalidChunk {
+            id: "image".into(),
+            index: Some(0),
+            at: PAYLOAD,
+            message: "16 bytes of trailing data".into(),
+        }
+    );
+}
+
+#[test]
+fn trimming_drops_the_junk() {
+    let bytes = fixture("lying/payload_over.cur");
+    let options = DecodeOptions {
+        trim_payloads: true,
+        ..DecodeOptions::lenient()
+    };
+    let decoded = CursorFile::decode_with(Cursor::new(&bytes), &options).unwrap();
+    assert_eq!(decoded.warnings.len(), 1);
+    let frame = &decoded.value[0];
+    assert!(frame.payload_len_consistent());
+
+    // Which leaves exactly the payload of the PNG fixture it was made from
+    let clean = CursorFile::decode(Cursor::new(fixture("png/arrow_32.cur"))).unwrap();
+    assert_eq!(frame.image_data, clean[0].image_data);
+}
+
+#[test]
+fn under_declared_dib() {
+    let bytes = fixture("lying/payload_under.cur");
+    let cursor = CursorFile::decode(Cursor::new(&bytes)).unwrap();
+    let frame = &cursor[0];
+    assert!(!frame.is_png());
+    assert!(!frame.payload_len_consistent());
+    // 32-bit pixels and a 1-bit mask, 64 bytes short of the end of the mask
+    let expected = 40 + 32 * 32 * 4 + 32 * 4;
+    assert_eq!(frame.expected_len(), Some(expected));
+    assert_eq!(frame.image_data.len(), expected - 64);
+    assert_eq!(
+        warnings(&bytes),
+        [(
+            PAYLOAD,
+            format!("{expected} byte image declared as {} bytes", expected - 64)
+        )]
+    );
+
+    let err = strict_error(CursorFile::decode_with(
+        Cursor::new(&bytes),
+        &DecodeOptions::strict(),
+    ));
+    assert_eq!(
+        (err.offset(), err.to_string().contains("declared as")),
+        (PAYLOAD, true)
+    );
+}
+
+#[test]
+fn over_declared_ani_icon() {
+    let bytes = fixture("lying/icon_over.ani");
+    let decoded = AniFile::decode_with(Cursor::new(&bytes), &DecodeOptions::lenient()).unwrap();
+    assert_eq!(decoded.value.num_frames(), 4);
+
+    // The padded container is the second icon chunk
+    let icon = bytes
+        .windows(4)
+        .enumerate()
+        .filter(|(_, window)| window == b"icon")
+        .nth(1)
+        .unwrap()
+        .0 as u64;
+    let warned: Vec<_> = decoded
+        .warnings
+        .iter()
+        .map(|warning| (warning.offset, &warning.message[..]))
+        .collect();
+    assert_eq!(warned, [(icon, "16 bytes of trailing data")]);
+
+    let err = strict_error(AniFile::decode_with(
+        Cursor::new(&bytes),
+        &DecodeOptions::strict(),
+    ));
+    assert!(
+        matches!(err, DecodeError::InvalidChunk { index: Some(1), at, .. } if at == icon),
+        "{err}"
+    );
+}
+
+#[test]
+fn well_formed_fixtures_are_consistent() {
+    for path in ["normal/arrow.cur", "png/arrow_32.cur", "dib/8bit.cur"] {
+        assert_eq!(warnings(&fixture(path)), [], "{path}");
+    }
+}
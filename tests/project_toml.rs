@@ -0,0 +1,130 @@
+// Project files through `CursorProject::parse`: any TOML document is read,
+// errors in it and keys the project doesn't know name their line, and the
+// values are checked once they are read
+#![cfg(feature = "fs")]
+
+use std::io::ErrorKind;
+
+use cursor_handler::{HotspotSpec, project::CursorProject};
+
+const CURSOR: &str = "[[cursor]]\nname = 'hand'\nsource = 'hand.png'\nformats = ['cur']\n";
+
+fn error(text: &str) -> String {
+    let err = CursorProject::parse(text, "themes").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData, "{err}");
+    err.to_string()
+}
+
+#[test]
+fn reads_toml() {
+    let text = r#"
+        # Comments, blank lines and indentation are skipped
+        [[cursor]]
+          name = "h\u00e4nd \"1\"\t\U0001F446"  # Trailing comment
+          source = 'C:\art\hand.png'
+          sizes = [
+              32,
+              4_8,  # Underscores and a trailing comma
+          ]
+          hotspot_normalized = [2.5e-1, +0.5]
+          formats = ['cur',]
+          output = """
+pointers/hand"""
+
+        [[cursor]]
+        name = "arrow"
+        source = "arrow.png"
+        hotspot = "top-left"
+        formats = ["ani"]
+    "#;
+    let project = CursorProject::parse(text, "themes").unwrap();
+    let hand = &project.cursors[0];
+    assert_eq!(hand.name, "händ \"1\"\t👆");
+    assert!(hand.source.ends_with(r"C:\art\hand.png"));
+    assert_eq!(hand.sizes, [32, 48]);
+    assert_eq!(hand.hotspot, HotspotSpec::Normalized(0.25, 0.5));
+    assert_eq!(hand.output, std::path::Path::new("pointers/hand"));
+    assert_eq!(project.cursors[1].hotspot, HotspotSpec::TopLeft);
+}
+
+#[test]
+fn syntax_errors_name_their_line() {
+    let cases = [
+        ("[[cursor]]\nname = \"hand\n", 2, "invalid basic string"),
+        (
+            "[[cursor]]\nname = 'hand'\nsizes = [\n32,\n",
+            4,
+            "unclosed array",
+        ),
+        (&format!("{CURSOR}name = 'other'\n"), 5, "duplicate key"),
+        ("a = 1\n[a]\n", 2, "duplicate key"),
+    ];
+    for (text, line, message) in cases {
+        let err = error(text);
+        assert!(err.starts_with(&format!("line {line}: {message}")), "{err}");
+    }
+}
+
+#[test]
+fn unknown_keys_are_errors() {
+    let err = error(&format!("{CURSOR}format = ['ani']\n"));
+    assert!(err.starts_with("line 5: unknown field `format`"), "{err}");
+    assert_eq!(
+        error("[other]\n"),
+        "line 1: unknown field `other`, expected `cursor`"
+    );
+
+    // `cursor` has to be an array of tables
+    for text in ["cursor = 1\n", "[cursor]\n"] {
+        assert!(error(text).starts_with("line 1: invalid type"), "{text}");
+    }
+}
+
+#[test]
+fn bad_values() {
+    let with = |line: &str| error(&format!("{CURSOR}{line}\n"));
+    assert_eq!(
+        with("hotspot = [1, -1]"),
+        "line 5: 'hotspot' must be [x, y], 'center' or 'top-left'"
+    );
+    assert_eq!(
+        with("hotspot = 'middle'"),
+        "cursor 'hand': 'hotspot' must be [x, y], 'center' or 'top-left', not 'middle'"
+    );
+    assert_eq!(
+        with("hotspot_normalized = [0.5, 2]"),
+        "cursor 'hand': 'hotspot_normalized' must be [x, y], each in 0..=1"
+    );
+    assert_eq!(
+        with("hotspot = [1, 1]\nhotspot_normalized = [0.5, 0.5]"),
+        "cursor 'hand': give 'hotspot' or 'hotspot_normalized', not both"
+    );
+    assert_eq!(
+        with("sizes = [32, 0]"),
+        "cursor 'hand': sizes must be in 1..=32767, not 0"
+    );
+    assert_eq!(
+        with("sizes = [-1]"),
+        "line 5: invalid value: integer `-1`, expected u32"
+    );
+    assert_eq!(
+        with("rate = 0"),
+        "cursor 'hand': 'rate' must be at least 1 jiffy"
+    );
+    assert_eq!(
+        error("[[cursor]]\nsource = 'x.png'\nformats = ['cur']\n"),
+        "cursor #1: 'name' must be a non-empty string"
+    );
+
+    // Two cursors of one name, or writing one file
+    let two = format!("{CURSOR}{}", CURSOR.replace("hand.png", "other.png"));
+    assert_eq!(error(&two), "cursor 'hand' is defined twice");
+    let same_output = format!(
+        "{CURSOR}{}output = 'hand'\n",
+        CURSOR.replace("'hand'", "'h2'")
+    );
+    assert_eq!(
+        error(&same_output),
+        "cursor 'h2': hand.cur is also written by another cursor"
+    );
+}
@@ -0,0 +1,196 @@
+// `resize` writes the requested sizes from the best source frame, places the
+// hotspot as asked and prints where it ended up
+#![cfg(feature = "cli")]
+
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use cursor_handler::{AniFile, CursorFile, CursorFrame, FrameSize, Hotspot};
+use image::{Rgba, RgbaImage};
+
+/// 32x32 with the hotspot at (6, 1)
+const LINK: &str = "assets/windows_theme/Link.cur";
+
+fn temp_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "cursor_handler_resize_{test}_{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn resize(input: &Path, output: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cursor_handler"))
+        .arg("resize")
+        .arg(input)
+        .args(args)
+        .arg("-o")
+        .arg(output)
+        .output()
+        .unwrap()
+}
+
+/// Each frame's size and hotspot, checking stdout reported the same
+fn frames(output: &Output, path: &Path) -> Vec<(FrameSize, Hotspot)> {
+    assert!(output.status.success(), "{output:?}");
+    let cursor = CursorFile::decode(Cursor::new(fs::read(path).unwrap())).unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let frames: Vec<_> = cursor
+        .frames()
+        .map(|frame| (frame.size, frame.hotspot))
+        .collect();
+    for (size, hotspot) in &frames {
+        assert!(
+            stdout.contains(&format!("{size}: hotspot {hotspot}\n")),
+            "{stdout}"
+        );
+    }
+    frames
+}
+
+fn square(side: u32) -> FrameSize {
+    FrameSize::square(side).unwrap()
+}
+
+#[test]
+fn sizes_and_hotspot_modes() {
+    let dir = temp_dir("modes");
+    let out = dir.join("out.cur");
+
+    let output = resize(LINK.as_ref(), &out, &["--sizes", "32,48,64"]);
+    assert_eq!(
+        frames(&output, &out),
+        [
+            (square(32), Hotspot::new(6, 1)),
+            (square(48), Hotspot::new(9, 2)),
+            (square(64), Hotspot::new(13, 3)),
+        ]
+    );
+
+    let output = resize(
+        LINK.as_ref(),
+        &out,
+        &["--sizes", "16,64", "--hotspot", "keep-pixel"],
+    );
+    assert_eq!(
+        frames(&output, &out),
+        [
+            (square(16), Hotspot::new(6, 1)),
+            (square(64), Hotspot::new(6, 1)),
+        ]
+    );
+
+    let output = resize(
+        LINK.as_ref(),
+        &out,
+        &[
+            "--sizes",
+            "16,24",
+            "--hotspot",
+            "4,4",
+            "--filter",
+            "nearest",
+        ],
+    );
+    assert_eq!(
+        frames(&output, &out),
+        [
+            (square(16), Hotspot::new(4, 4)),
+            (square(24), Hotspot::new(4, 4)),
+        ]
+    );
+
+    // An absolute hotspot outside one of the sizes is refused
+    let output = resize(
+        LINK.as_ref(),
+        &out,
+        &["--sizes", "16,48", "--hotspot", "20,2"],
+    );
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("hotspot (20, 2) is outside the 16x16 frame"),
+        "{stderr}"
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn non_square_source() {
+    let dir = temp_dir("non_square");
+    let (input, out) = (dir.join("tall.cur"), dir.join("out.cur"));
+    let image = RgbaImage::from_pixel(16, 32, Rgba([0, 0, 255, 255]));
+    let frame = CursorFrame::from_image(&image, Hotspot::new(15, 0)).unwrap();
+    let mut bytes = Vec::new();
+    CursorFile::single(frame).encode(&mut bytes).unwrap();
+    fs::write(&input, bytes).unwrap();
+
+    // The longer side becomes the size, keeping the aspect ratio...
+    let output = resize(&input, &out, &["--sizes", "64"]);
+    let tall = FrameSize::new(32, 64).unwrap();
+    assert_eq!(frames(&output, &out), [(tall, Hotspot::new(31, 1))]);
+
+    // ...or the frame is centred on a square canvas, the hotspot moving along
+    let output = resize(&input, &out, &["--sizes", "64", "--pad-square"]);
+    assert_eq!(frames(&output, &out), [(square(64), Hotspot::new(47, 1))]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn animated() {
+    let dir = temp_dir("ani");
+    let out = dir.join("out.ani");
+    let original = AniFile::decode(Cursor::new(fs::read("assets/aero_busy.ani").unwrap())).unwrap();
+
+    let output = resize("assets/aero_busy.ani".as_ref(), &out, &["--size", "32"]);
+    assert!(output.status.success(), "{output:?}");
+    let ani = AniFile::decode(Cursor::new(fs::read(&out).unwrap())).unwrap();
+    assert_eq!(ani.num_steps(), original.num_steps());
+    for (index, frame) in ani.frames().enumerate() {
+        assert_eq!(
+            (frame.size, frame.hotspot),
+            (square(32), Hotspot::new(16, 16))
+        );
+        let cursor = ani.extract_frame(index).unwrap();
+        assert_eq!(cursor.frames().len(), 1);
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("frame 17: 32x32: hotspot (16, 16)"),
+        "{stdout}"
+    );
+
+    // An .ani only has room for one size
+    let output = resize("assets/aero_busy.ani".as_ref(), &out, &["--sizes", "32,48"]);
+    assert!(!output.status.success());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn upscale_warning() {
+    let dir = temp_dir("upscale");
+    let out = dir.join("out.cur");
+    let warned = |output: &Output| {
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8_lossy(&output.stderr).contains("warning: ")
+    };
+
+    // Exactly 2x is fine; past it the output is still written, with a warning
+    assert!(!warned(&resize(LINK.as_ref(), &out, &["--sizes", "64"])));
+    let output = resize(LINK.as_ref(), &out, &["--sizes", "48,96"]);
+    assert!(warned(&output));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("96px is 3.0x the 32px source"), "{stderr}");
+    assert!(!stderr.contains("48px"), "{stderr}");
+    assert_eq!(frames(&output, &out)[1].0, square(96));
+
+    let output = resize(LINK.as_ref(), &out, &["--sizes", "96", "--allow-upscale"]);
+    assert!(!warned(&output));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,277 @@
+// Property tests: any cursor or animation this crate can build decodes back
+// to what was encoded, re-encodes to the same bytes, and validates without
+// warnings. Failing cases shrink to the fewest, smallest frames.
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    io::Cursor,
+};
+
+use cursor_handler::{
+    AniFile, AniFlags, AniFrame, CursorFile, CursorFrame, FrameSize, Hotspot, InfoEntry, Severity,
+    test_utils::{assert_cursor_bytes_eq, png_frame},
+};
+use image::{Rgba, RgbaImage};
+use proptest::{collection::vec, option, prelude::*};
+
+/// One frame to draw: its size, a colour and whether to store it as PNG
+#[derive(Debug, Clone)]
+struct FrameSpec {
+    width: u32,
+    height: u32,
+    color: [u8; 4],
+    png: bool,
+}
+
+impl FrameSpec {
+    /// The colour with a gradient over it, so rows and columns differ
+    fn image(&self) -> RgbaImage {
+        let [r, g, b, a] = self.color;
+        RgbaImage::from_fn(self.width, self.height, |x, y| {
+            Rgba([r ^ x as u8, g ^ y as u8, b, a])
+        })
+    }
+
+    fn frame(&self, hotspot: Hotspot) -> CursorFrame {
+        let image = self.image();
+        if self.png {
+            png_frame(&image, hotspot).unwrap()
+        } else {
+            let size = FrameSize::new(self.width, self.height).unwrap();
+            CursorFrame::new(size, hotspot, dib(&image))
+        }
+    }
+}
+
+/// A bottom-up 32-bit DIB, masking exactly the fully transparent pixels
+fn dib(image: &RgbaImage) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let mut dib = [40, width, height * 2, 1 | (32 << 16), 0, 0, 0, 0, 0, 0]
+        .map(u32::to_le_bytes)
+        .concat();
+    for row in image.rows().rev() {
+        for pixel in row {
+            let [r, g, b, a] = pixel.0;
+            dib.extend_from_slice(&[b, g, r, a]);
+        }
+    }
+    let stride = width.div_ceil(32) as usize * 4;
+    for row in image.rows().rev() {
+        let mut mask = vec![0u8; stride];
+        for (x, pixel) in row.enumerate() {
+            if pixel[3] == 0 {
+                mask[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        dib.extend(mask);
+    }
+    dib
+}
+
+/// Sizes 1..=256 in either direction; DIBs stop short of 256px, where
+/// only PNG is valid
+fn frame_spec() -> impl Strategy<Value = FrameSpec> {
+    (1u32..=256, 1u32..=256, any::<[u8; 4]>(), any::<bool>()).prop_map(
+        |(width, height, color, png)| FrameSpec {
+            width,
+            height,
+            color,
+            png: png || width == 256 || height == 256,
+        },
+    )
+}
+
+/// A hotspot as fractions of the frame, the same point at every size
+fn fraction() -> impl Strategy<Value = (f32, f32)> {
+    (0.0f32..1.0, 0.0f32..1.0)
+}
+
+/// Frames of every size with the hotspot placed on the first and carried
+/// over to the others the way `validate` expects
+fn cursor_frames(specs: &[FrameSpec], fraction: (f32, f32)) -> Vec<CursorFrame> {
+    let first = Hotspot::from_fraction(fraction, specs[0].width, specs[0].height);
+    let fraction = first.fraction(specs[0].width, specs[0].height);
+    specs
+        .iter()
+        .map(|spec| {
+            let hotspot = Hotspot::from_fraction(fraction, spec.width, spec.height);
+            spec.frame(hotspot)
+        })
+        .collect()
+}
+
+fn cursor_file() -> impl Strategy<Value = CursorFile> {
+    (vec(frame_spec(), 1..=20), fraction())
+        .prop_map(|(specs, fraction)| CursorFile::new(cursor_frames(&specs, fraction)))
+}
+
+/// A metadata value: text, often non-ASCII, or raw bytes of any length
+fn info_entry() -> impl Strategy<Value = InfoEntry> {
+    let id = prop_oneof![
+        Just(*b"INAM"),
+        Just(*b"IART"),
+        Just(*b"ICMT"),
+        Just(*b"ICOP")
+    ];
+    let text = (id.clone(), "\\PC{0,12}").prop_map(|(id, text)| InfoEntry::from_text(id, &text));
+    let raw = (id, vec(any::<u8>(), 0..9)).prop_map(|(id, value)| InfoEntry::new(id, value));
+    prop_oneof![text, raw]
+}
+
+/// Frames of one size and hotspot, as `validate` wants, each its own
+/// colour and payload kind
+fn ani_file() -> impl Strategy<Value = AniFile> {
+    let colors = vec((any::<[u8; 4]>(), any::<bool>()), 1..=12);
+    let steps = option::of(vec(any::<u32>(), 1..=24));
+    let rates = option::of(vec(1u32..=600, 1..=24));
+    let metadata = vec(info_entry(), 0..4);
+    (
+        frame_spec(),
+        colors,
+        fraction(),
+        steps,
+        rates,
+        1u32..=600,
+        metadata,
+    )
+        .prop_map(
+            |(first, colors, fraction, steps, rates, default_rate, info)| {
+                let hotspot = Hotspot::from_fraction(fraction, first.width, first.height);
+                let frames: Vec<_> = colors
+                    .into_iter()
+                    .map(|(color, png)| {
+                        let spec = FrameSpec {
+                            color,
+                            png: png || first.png,
+                            ..first.clone()
+                        };
+                        let frame = spec.frame(hotspot);
+                        let mut cur = Vec::new();
+                        CursorFile::single(frame.clone()).encode(&mut cur).unwrap();
+                        AniFrame::new(frame.size, hotspot, cur, None)
+                    })
+                    .collect();
+
+                let count = frames.len() as u32;
+                let mut ani = AniFile::new(frames)
+                    .with_default_rate(default_rate)
+                    .unwrap();
+                if let Some(steps) = steps {
+                    ani = ani.with_sequence(steps.iter().map(|step| step % count).collect());
+                }
+                if let Some(rates) = rates {
+                    let steps = ani.sequence.len();
+                    ani = ani.with_rates(rates.into_iter().cycle().take(steps).collect());
+                }
+                ani.info = info;
+                ani
+            },
+        )
+}
+
+/// Payloads by digest, so a mismatch doesn't print megabytes
+fn digest(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Debug, PartialEq)]
+struct FrameShape {
+    size: FrameSize,
+    hotspot: Hotspot,
+    bit_count: u16,
+    payload: (usize, u64),
+}
+
+fn cursor_shape(cursor: &CursorFile) -> Vec<FrameShape> {
+    cursor
+        .frames()
+        .map(|frame| FrameShape {
+            size: frame.size,
+            hotspot: frame.hotspot,
+            bit_count: frame.bit_count,
+            payload: (frame.image_data.len(), digest(&frame.image_data)),
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+struct AniShape {
+    header: [u32; 5], // Width, height, bit count, planes and default rate
+    frames: Vec<(FrameSize, Hotspot, (usize, u64))>,
+    sequence: Vec<u32>,
+    rates: Vec<u32>,
+    info: Vec<InfoEntry>,
+}
+
+fn ani_shape(ani: &AniFile) -> AniShape {
+    let header = &ani.header;
+    AniShape {
+        header: [
+            header.width(),
+            header.height(),
+            header.bit_count(),
+            header.planes(),
+            header.default_rate(),
+        ],
+        frames: ani
+            .frames()
+            .map(|frame| {
+                let payload = (frame.image_data.len(), digest(&frame.image_data));
+                (frame.size, frame.hotspot, payload)
+            })
+            .collect(),
+        sequence: ani.sequence.clone(),
+        rates: ani.rates.clone(),
+        info: ani.info.clone(),
+    }
+}
+
+/// Everything `validate_bytes` reports above `Info`
+fn warnings(issues: Vec<cursor_handler::validate::Issue>) -> Vec<String> {
+    issues
+        .iter()
+        .filter(|issue| issue.severity > Severity::Info)
+        .map(ToString::to_string)
+        .collect()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn cursor_round_trips(cursor in cursor_file()) {
+        let mut bytes = Vec::new();
+        cursor.encode(&mut bytes).unwrap();
+        let decoded = CursorFile::decode(Cursor::new(&bytes)).unwrap();
+        prop_assert_eq!(cursor_shape(&decoded), cursor_shape(&cursor));
+
+        let mut again = Vec::new();
+        decoded.encode(&mut again).unwrap();
+        assert_cursor_bytes_eq(&bytes, &again);
+        prop_assert_eq!(warnings(CursorFile::validate_bytes(&bytes).unwrap()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn ani_round_trips(ani in ani_file()) {
+        let mut bytes = Cursor::new(Vec::new());
+        ani.encode(&mut bytes).unwrap();
+        let bytes = bytes.into_inner();
+        let decoded = AniFile::decode(Cursor::new(&bytes)).unwrap();
+        prop_assert_eq!(ani_shape(&decoded), ani_shape(&ani));
+
+        // Counts and flags are written from the frames and tables
+        let header = &decoded.header;
+        prop_assert_eq!(header.declared_frames(), Some(ani.num_frames()));
+        prop_assert_eq!(header.declared_steps(), Some(ani.num_steps()));
+        let reordered = !ani.sequence.iter().copied().eq(0..ani.num_frames());
+        let mut flags = AniFlags::FRAMES_ARE_ICONS;
+        flags.set(AniFlags::HAS_SEQUENCE, reordered);
+        prop_assert_eq!(header.ani_flags(), flags);
+
+        let mut again = Cursor::new(Vec::new());
+        decoded.encode(&mut again).unwrap();
+        assert_cursor_bytes_eq(&bytes, &again.into_inner());
+        prop_assert_eq!(warnings(AniFile::validate_bytes(&bytes).unwrap()), Vec::<String>::new());
+    }
+}
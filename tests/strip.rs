@@ -0,0 +1,197 @@
+// `strip export` and `strip import` round-trip an animation through a sprite
+// sheet, with or without its manifest
+#![cfg(feature = "cli")]
+
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use cursor_handler::{AniFile, Hotspot};
+use image::RgbaImage;
+
+const BUSY: &str = "assets/aero_busy.ani";
+
+fn temp_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "cursor_handler_strip_{test}_{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn strip(args: &[&str], paths: &[&Path]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cursor_handler"))
+        .arg("strip")
+        .args(args)
+        .args(paths)
+        .output()
+        .unwrap()
+}
+
+fn read_ani(path: impl AsRef<Path>) -> AniFile {
+    AniFile::decode(Cursor::new(fs::read(path).unwrap())).unwrap()
+}
+
+/// What each step shows and for how long
+fn stream(ani: &AniFile) -> Vec<(usize, u32, Hotspot, RgbaImage)> {
+    ani.steps()
+        .map(|step| {
+            let cursor = ani.extract_frame(step.frame_index).unwrap();
+            let largest = cursor.largest_frame().unwrap();
+            (
+                step.frame_index,
+                step.duration,
+                largest.hotspot,
+                largest.decode_image().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn round_trip_with_manifest() {
+    let dir = temp_dir("manifest");
+    let (sheet, manifest, out) = (
+        dir.join("busy_sheet.png"),
+        dir.join("busy.json"),
+        dir.join("busy.ani"),
+    );
+
+    let output = strip(
+        &[
+            "export",
+            BUSY,
+            "--columns",
+            "6",
+            "--direction",
+            "vertical",
+            "--verify",
+            "-o",
+        ],
+        &[&sheet, "--manifest".as_ref(), &manifest],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("verified: 18 step(s) play the same"),
+        "{stdout}"
+    );
+    let image = image::open(&sheet).unwrap();
+    assert_eq!((image.width(), image.height()), (6 * 64, 3 * 64));
+
+    let output = strip(
+        &["import"],
+        &[
+            &sheet,
+            "--manifest".as_ref(),
+            &manifest,
+            "-o".as_ref(),
+            &out,
+        ],
+    );
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stream(&read_ani(&out)), stream(&read_ani(BUSY)));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn sequence_and_rates_survive() {
+    let dir = temp_dir("sequence");
+    let (input, sheet, out) = (
+        dir.join("in.ani"),
+        dir.join("sheet.png"),
+        dir.join("out.ani"),
+    );
+    let mut ani = read_ani(BUSY);
+    ani.sequence = vec![0, 1, 2, 1, 0];
+    ani.rates = vec![3, 3, 12, 3, 6];
+    let mut bytes = Cursor::new(Vec::new());
+    ani.encode(&mut bytes).unwrap();
+    fs::write(&input, bytes.into_inner()).unwrap();
+
+    let output = strip(
+        &["export", "--cell-size", "32", "--verify"],
+        &[&input, "-o".as_ref(), &sheet],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let output = strip(
+        &["import"],
+        &[
+            &sheet,
+            "--manifest".as_ref(),
+            &dir.join("sheet.json"),
+            "-o".as_ref(),
+            &out,
+        ],
+    );
+    assert!(output.status.success(), "{output:?}");
+
+    let rebuilt = read_ani(&out);
+    let steps = |ani: &AniFile| -> Vec<_> {
+        ani.steps()
+            .map(|step| (step.frame_index, step.duration))
+            .collect()
+    };
+    assert_eq!(steps(&rebuilt), steps(&ani));
+    assert!(rebuilt.frames().all(|frame| frame.size.max_side() == 32));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn import_without_manifest() {
+    let dir = temp_dir("plain");
+    let (sheet, out) = (dir.join("strip.png"), dir.join("out.ani"));
+    let output = strip(
+        &["export", BUSY, "--direction", "vertical", "-o"],
+        &[&sheet],
+    );
+    assert!(output.status.success(), "{output:?}");
+
+    let output = strip(
+        &[
+            "import",
+            "--frames",
+            "18",
+            "--direction",
+            "vertical",
+            "--rate",
+            "50ms",
+            "--hotspot",
+            "30,31",
+            "-o",
+        ],
+        &[&out, &sheet],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let ani = read_ani(&out);
+    let original = read_ani(BUSY);
+    assert_eq!(ani.num_frames(), 18);
+    for (step, expected) in stream(&ani).into_iter().zip(stream(&original)) {
+        assert_eq!((step.1, step.2), (3, Hotspot::new(30, 31)));
+        assert_eq!(step.3, expected.3);
+    }
+
+    // 18 frames of 64px don't make 4 square ones, and nothing is written
+    fs::remove_file(&out).unwrap();
+    let output = strip(
+        &["import", "--frames", "4", "--direction", "vertical", "-o"],
+        &[&out, &sheet],
+    );
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("a 64x1152 strip doesn't split into 4 square frames"),
+        "{stderr}"
+    );
+    assert!(!out.exists());
+
+    // --frames is what stands in for a manifest
+    let output = strip(&["import", "-o"], &[&out, &sheet]);
+    assert!(!output.status.success());
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,138 @@
+// Cursors and animations drawn from a small inline SVG: every size is
+// rendered from the vector with soft edges, the hotspot follows the drawing,
+// and non-square sources keep their shape or are centred on a square
+#![cfg(feature = "svg")]
+
+use std::io::{Cursor, ErrorKind};
+
+use cursor_handler::{AniFile, CursorFile, FrameSize, Hotspot, HotspotSpec, rasterize::SvgOptions};
+
+/// A black disc on a 10x10 canvas, with a white square in its top-left
+/// quarter
+const DISC: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+    <circle cx="5" cy="5" r="5" fill="black"/>
+    <rect x="2" y="2" width="2" height="2" fill="white"/>
+</svg>"#;
+
+/// A wide bar, twice as wide as it is tall
+const BAR: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 20">
+    <rect width="40" height="20" fill="#0000ff"/>
+</svg>"##;
+
+#[test]
+fn every_size_is_drawn_from_the_vector() {
+    let cursor = CursorFile::from_svg(DISC, &[16, 32, 64], HotspotSpec::Center).unwrap();
+    for (frame, side) in cursor.frames().zip([16, 32, 64]) {
+        assert_eq!(frame.size, FrameSize::square(side).unwrap());
+        let image = frame.decode_image().unwrap();
+        let at = |fx: f32, fy: f32| {
+            let (x, y) = ((fx * side as f32) as u32, (fy * side as f32) as u32);
+            image.get_pixel(x, y).0
+        };
+        assert_eq!(at(0.5, 0.5), [0, 0, 0, 255], "{side}px centre");
+        assert_eq!(at(0.3, 0.3), [255, 255, 255, 255], "{side}px square");
+        assert_eq!(at(0.0, 0.0)[3], 0, "{side}px corner");
+
+        // The disc's rim is anti-aliased, not a hard step
+        let partial = image.pixels().filter(|pixel| (1..255).contains(&pixel[3]));
+        assert!(partial.count() >= side as usize, "{side}px rim");
+    }
+}
+
+#[test]
+fn normalized_hotspot_follows_the_drawing() {
+    let spec = HotspotSpec::Normalized(0.3, 0.7);
+    let cursor = CursorFile::from_svg(DISC, &[10, 20, 40], spec).unwrap();
+    let hotspots: Vec<_> = cursor.frames().map(|frame| frame.hotspot).collect();
+    assert_eq!(
+        hotspots,
+        [
+            Hotspot::new(3, 7),
+            Hotspot::new(6, 14),
+            Hotspot::new(12, 28)
+        ]
+    );
+
+    // Absolute is in the SVG's units, the same point at every size
+    let cursor = CursorFile::from_svg(DISC, &[10, 40], (2, 2).into()).unwrap();
+    assert_eq!(cursor[0].hotspot, Hotspot::new(2, 2));
+    assert_eq!(cursor[1].hotspot, Hotspot::new(10, 10));
+}
+
+#[test]
+fn non_square_sources() {
+    // Kept to its aspect ratio, the longer side at the size asked for
+    let kept = CursorFile::from_svg(BAR, &[32], HotspotSpec::Normalized(1.0, 1.0)).unwrap();
+    assert_eq!(kept[0].size, FrameSize::new(32, 16).unwrap());
+    assert_eq!(kept[0].hotspot, Hotspot::new(31, 15));
+
+    // Or centred on a square, the hotspot moving with it
+    let options = SvgOptions { pad_square: true };
+    let spec = HotspotSpec::Normalized(1.0, 1.0);
+    let padded = CursorFile::from_svg_with(BAR, &[32], spec, &options).unwrap();
+    assert_eq!(padded[0].size, FrameSize::square(32).unwrap());
+    assert_eq!(padded[0].hotspot, Hotspot::new(31, 23));
+    let image = padded[0].decode_image().unwrap();
+    assert_eq!(image.get_pixel(16, 4)[3], 0);
+    assert_eq!(image.get_pixel(16, 16).0, [0, 0, 255, 255]);
+
+    // Frames of different shapes only animate together once padded
+    let err = AniFile::from_svgs(&[DISC, BAR], 32, HotspotSpec::Center).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let ani = AniFile::from_svgs_with(&[DISC, BAR], 32, HotspotSpec::Center, &options).unwrap();
+    assert!(
+        ani.frames()
+            .all(|frame| frame.size == FrameSize::square(32).unwrap())
+    );
+}
+
+#[test]
+fn animation_round_trips() {
+    let frames: Vec<String> = ["red", "lime", "blue"]
+        .iter()
+        .map(|fill| DISC.replace("black", fill))
+        .collect();
+    let frames: Vec<&str> = frames.iter().map(String::as_str).collect();
+    let ani = AniFile::from_svgs(&frames, 48, HotspotSpec::Normalized(0.5, 0.5))
+        .unwrap()
+        .with_default_rate(4)
+        .unwrap();
+
+    let mut bytes = Cursor::new(Vec::new());
+    ani.encode(&mut bytes).unwrap();
+    let decoded = AniFile::decode(Cursor::new(bytes.into_inner())).unwrap();
+    assert_eq!(decoded.num_frames(), 3);
+    assert_eq!(decoded.uniform_hotspot(), Some(Hotspot::new(24, 24)));
+    let centres: Vec<_> = decoded
+        .frames()
+        .map(|frame| frame.decode_image().unwrap().get_pixel(24, 24).0)
+        .collect();
+    assert_eq!(
+        centres,
+        [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]]
+    );
+    let durations: Vec<_> = decoded.steps().map(|step| step.duration).collect();
+    assert_eq!(durations, [4, 4, 4]);
+}
+
+#[test]
+fn bad_input_is_an_error() {
+    let kind = |result: std::io::Result<CursorFile>| result.unwrap_err().kind();
+    assert_eq!(
+        kind(CursorFile::from_svg("<svg", &[32], HotspotSpec::TopLeft)),
+        ErrorKind::InvalidData
+    );
+    assert_eq!(
+        kind(CursorFile::from_svg(DISC, &[], HotspotSpec::TopLeft)),
+        ErrorKind::InvalidInput
+    );
+    assert_eq!(
+        kind(CursorFile::from_svg(DISC, &[0], HotspotSpec::TopLeft)),
+        ErrorKind::InvalidInput
+    );
+    assert_eq!(
+        kind(CursorFile::from_svg(DISC, &[32], (10, 3).into())),
+        ErrorKind::InvalidInput
+    );
+    assert!(AniFile::from_svgs(&[], 32, HotspotSpec::TopLeft).is_err());
+}
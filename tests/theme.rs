@@ -0,0 +1,55 @@
+// `theme` builds the sample scheme config into a complete, installable
+// directory, with the Xcursor theme alongside
+#![cfg(feature = "cli")]
+
+use std::{fs, io::Cursor, process::Command};
+
+use cursor_handler::{AniFile, CursorFile};
+
+#[test]
+fn sample_scheme() {
+    let dist = std::env::temp_dir().join(format!("cursor_handler_theme_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dist);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cursor_handler"))
+        .args(["theme", "assets/scheme/scheme.toml", "--also", "xcursor"])
+        .arg("--out-dir")
+        .arg(&dist)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("warning: role 'IBeam' has no source, using Arrow.cur"));
+
+    let arrow = CursorFile::decode(Cursor::new(fs::read(dist.join("Arrow.cur")).unwrap())).unwrap();
+    let sizes: Vec<_> = arrow.frames().map(|frame| frame.size.max_side()).collect();
+    assert_eq!(sizes, [24, 32]);
+    for (file, frames) in [("Wait.ani", 2), ("AppStarting.ani", 3)] {
+        let ani = AniFile::decode(Cursor::new(fs::read(dist.join(file)).unwrap())).unwrap();
+        assert_eq!(ani.num_frames(), frames, "{file}");
+    }
+    assert_eq!(
+        fs::read(dist.join("Hand.cur")).unwrap(),
+        fs::read("assets/windows_theme/Link.cur").unwrap()
+    );
+
+    // The .inf copies every file and gives every role one
+    let inf = fs::read_to_string(dist.join("install.inf")).unwrap();
+    for file in ["Arrow.cur", "AppStarting.ani", "Wait.ani", "Hand.cur"] {
+        assert!(inf.contains(&format!("\r\n\"{file}\"\r\n")), "{file}");
+    }
+    for role in cursor_handler::convert::roles() {
+        let line = format!("HKCU,\"Control Panel\\Cursors\",{},", role.name);
+        assert!(inf.contains(&line), "{}", role.name);
+    }
+
+    let cursors = dist.join("xcursor/Sample/cursors");
+    for name in ["left_ptr", "watch", "left_ptr_watch", "hand2"] {
+        assert_eq!(
+            &fs::read(cursors.join(name)).unwrap()[..4],
+            b"Xcur",
+            "{name}"
+        );
+    }
+    fs::remove_dir_all(&dist).unwrap();
+}
@@ -0,0 +1,100 @@
+// The wasm-bindgen entry points over committed fixtures, embedded since the
+// browser has no filesystem. Run in a JS engine with
+//
+//   wasm-pack test --node -- --features wasm
+//
+// Natively the tests that don't create JS values run as ordinary tests.
+#![cfg(feature = "wasm")]
+
+use std::io::Cursor;
+
+use cursor_handler::{CursorFile, wasm};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const ARROW: &[u8] = include_bytes!("fixtures/normal/arrow.cur");
+const SPINNER: &[u8] = include_bytes!("fixtures/normal/spinner.ani");
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn ani_to_apng_keeps_every_step() {
+    let apng = wasm::ani_to_apng(SPINNER).unwrap_or_else(|_| panic!("spinner.ani converts"));
+    let reader = png::Decoder::new(Cursor::new(apng)).read_info().unwrap();
+    let info = reader.info();
+    assert_eq!((info.width, info.height), (32, 32));
+    assert_eq!(
+        info.animation_control.map(|control| control.num_frames),
+        Some(4)
+    );
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn frame_rgba_matches_decode() {
+    let cursor = CursorFile::decode(Cursor::new(ARROW)).unwrap();
+    for (index, frame) in cursor.frames().enumerate() {
+        let image = wasm::frame_rgba(ARROW, index).unwrap_or_else(|_| panic!("frame {index}"));
+        let expected = frame.decode_image().unwrap();
+        assert_eq!((image.width(), image.height()), expected.dimensions());
+        assert_eq!(image.data(), expected.into_raw(), "frame {index}");
+    }
+
+    // The frames of an .ani are reachable the same way
+    let image = wasm::frame_rgba(SPINNER, 3).unwrap_or_else(|_| panic!("frame 3"));
+    assert_eq!(image.data().len(), 32 * 32 * 4);
+}
+
+/// Errors and manifests are JS objects, so these only run in a JS engine
+#[cfg(target_arch = "wasm32")]
+mod js {
+    use serde::Deserialize;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::{ARROW, SPINNER, wasm};
+
+    #[derive(Deserialize)]
+    struct Manifest {
+        kind: String,
+        frames: Vec<Frame>,
+        #[serde(default)]
+        steps: Vec<Step>,
+    }
+
+    #[derive(Deserialize)]
+    struct Frame {
+        width: u32,
+        height: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct Step {
+        duration: u32,
+    }
+
+    fn manifest(bytes: &[u8]) -> Manifest {
+        let value = wasm::parse_cur(bytes).unwrap();
+        serde_wasm_bindgen::from_value(value).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_cur_returns_the_manifest() {
+        let cur = manifest(ARROW);
+        assert_eq!(cur.kind, "cur");
+        assert_eq!(cur.frames.len(), 2);
+        assert!(cur.steps.is_empty());
+
+        let ani = manifest(SPINNER);
+        assert_eq!(ani.kind, "ani");
+        let frame = &ani.frames[0];
+        assert_eq!((frame.width, frame.height), (32, 32));
+        let durations: Vec<_> = ani.steps.iter().map(|step| step.duration).collect();
+        assert_eq!(durations, [6, 6, 6, 6]);
+    }
+
+    #[wasm_bindgen_test]
+    fn bad_input_is_an_error() {
+        assert!(wasm::parse_cur(&ARROW[..20]).is_err());
+        assert!(wasm::ani_to_apng(ARROW).is_err());
+        assert!(wasm::frame_rgba(SPINNER, 4).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+// `build --watch` rebuilds a cursor when its source changes, and keeps
+// watching after a failed build
+#![cfg(feature = "watch")]
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use image::{Rgba, RgbaImage};
+
+/// Generous, since file events can be slow on busy machines
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn write_png(path: &Path, shade: u8) {
+    RgbaImage::from_pixel(16, 16, Rgba([shade, shade, shade, 255]))
+        .save(path)
+        .unwrap();
+}
+
+/// Wait until `ready` returns a value
+fn wait_for<T>(what: &str, mut ready: impl FnMut() -> Option<T>) -> T {
+    let start = Instant::now();
+    loop {
+        if let Some(value) = ready() {
+            return value;
+        }
+        assert!(start.elapsed() < TIMEOUT, "timed out waiting for {what}");
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn rebuilds_on_change() {
+    let dir = std::env::temp_dir().join(format!("cursor_handler_watch_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("dot.png");
+    write_png(&source, 0);
+    fs::write(
+        dir.join("project.toml"),
+        "[[cursor]]\nname = 'dot'\nsource = 'dot.png'\nhotspot = [0, 0]\nformats = ['cur']\n",
+    )
+    .unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cursor_handler"))
+        .arg("build")
+        .arg(dir.join("project.toml"))
+        .arg("--watch")
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let output = dir.join("dot.cur");
+    let first = wait_for("the first build", || fs::read(&output).ok());
+    // Give the watcher time to start after the first build
+    thread::sleep(Duration::from_secs(1));
+
+    // A broken source fails that build without stopping the watch
+    fs::write(&source, b"not a png").unwrap();
+    thread::sleep(Duration::from_secs(1));
+    assert_eq!(fs::read(&output).unwrap(), first);
+
+    write_png(&source, 255);
+    let rebuilt = wait_for("the rebuild", || {
+        fs::read(&output).ok().filter(|bytes| *bytes != first)
+    });
+    assert_eq!(rebuilt.len(), first.len());
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,137 @@
+// Installing a scheme as a dry run, which works on any OS: the planned
+// copies, the 15-slot scheme string and the registry values
+#![cfg(feature = "windows-apply")]
+
+use std::path::{Path, PathBuf};
+
+use cursor_handler::windows::{
+    InstallOptions, RegistryKey, RegistryValue, SchemeChange, Scope, SystemCursor, install_scheme,
+    uninstall_scheme,
+};
+
+fn options(dir: &Path, activate: bool) -> InstallOptions {
+    InstallOptions {
+        install_dir: Some(dir.to_path_buf()),
+        activate,
+        dry_run: true,
+    }
+}
+
+fn copies(changes: &[SchemeChange]) -> Vec<(PathBuf, PathBuf)> {
+    changes
+        .iter()
+        .filter_map(|change| match change {
+            SchemeChange::CopyFile { from, to } => Some((from.clone(), to.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The scheme string's slots, and the key it was written under
+fn scheme_slots(changes: &[SchemeChange], name: &str) -> (RegistryKey, Vec<String>) {
+    changes
+        .iter()
+        .find_map(|change| match change {
+            SchemeChange::SetValue {
+                key,
+                name: value_name,
+                value: RegistryValue::String(value),
+            } if value_name == name => {
+                Some((key.clone(), value.split(',').map(String::from).collect()))
+            }
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn dry_run_plans_slots_in_order() {
+    let dir = std::env::temp_dir().join("cursor_handler_scheme_dry_run");
+    let scheme = dir.join("Ocean");
+    let mapping = [
+        (SystemCursor::Hand, PathBuf::from("art/hand.cur")),
+        (SystemCursor::Arrow, PathBuf::from("art/arrow.cur")),
+        (SystemCursor::Wait, PathBuf::from("art/busy.ani")),
+    ];
+    let changes = install_scheme("Ocean", &mapping, Scope::User, &options(&dir, false)).unwrap();
+
+    assert_eq!(
+        copies(&changes),
+        [
+            ("art/hand.cur".into(), scheme.join("hand.cur")),
+            ("art/arrow.cur".into(), scheme.join("arrow.cur")),
+            ("art/busy.ani".into(), scheme.join("busy.ani")),
+        ]
+    );
+    let (key, slots) = scheme_slots(&changes, "Ocean");
+    assert_eq!(key.to_string(), "HKCU\\Control Panel\\Cursors\\Schemes");
+    assert_eq!(slots.len(), 15);
+    // Arrow, Help, AppStarting, Wait, ... Hand, whatever order they're given in
+    let path = |file: &str| scheme.join(file).display().to_string();
+    let mut expected = vec![String::new(); 15];
+    (expected[0], expected[3], expected[14]) =
+        (path("arrow.cur"), path("busy.ani"), path("hand.cur"));
+    assert_eq!(slots, expected);
+    assert_eq!(changes.len(), 4, "no activation without `activate`");
+    assert!(!dir.exists(), "a dry run doesn't touch the disk");
+
+    // Activation sets every role under HKCU and reloads the cursors
+    let changes = install_scheme("Ocean", &mapping, Scope::Machine, &options(&dir, true)).unwrap();
+    let (key, _) = scheme_slots(&changes, "Ocean");
+    assert!(key.machine);
+    let cursors: Vec<_> = changes
+        .iter()
+        .filter_map(|change| match change {
+            SchemeChange::SetValue { key, name, .. } if !key.machine => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    let roles: Vec<_> = SystemCursor::ALL
+        .iter()
+        .map(|role| role.registry_name())
+        .collect();
+    assert_eq!(cursors, [&["", "Scheme Source"][..], &roles].concat());
+    assert_eq!(changes.last(), Some(&SchemeChange::ReloadCursors));
+
+    let changes = uninstall_scheme("Ocean", Scope::User, &options(&dir, false)).unwrap();
+    assert_eq!(changes[1], SchemeChange::RemoveDir(scheme));
+}
+
+#[test]
+fn same_file_names_dont_collide() {
+    let dir = std::env::temp_dir().join("cursor_handler_scheme_collide");
+    let scheme = dir.join("Twins");
+    let mapping = [
+        (SystemCursor::Arrow, PathBuf::from("a/arrow.cur")),
+        (SystemCursor::Help, PathBuf::from("b/Arrow.cur")),
+        (SystemCursor::UpArrow, PathBuf::from("a/arrow.cur")),
+        (SystemCursor::Hand, PathBuf::from("b/Arrow.cur")),
+    ];
+    let changes = install_scheme("Twins", &mapping, Scope::User, &options(&dir, false)).unwrap();
+
+    // Each source is copied once, the second under its first role's name
+    assert_eq!(
+        copies(&changes),
+        [
+            ("a/arrow.cur".into(), scheme.join("arrow.cur")),
+            ("b/Arrow.cur".into(), scheme.join("Help.cur")),
+        ]
+    );
+    let (_, slots) = scheme_slots(&changes, "Twins");
+    let slot =
+        |role: SystemCursor| &slots[SystemCursor::ALL.iter().position(|&r| r == role).unwrap()];
+    assert_eq!(slot(SystemCursor::UpArrow), slot(SystemCursor::Arrow));
+    assert_eq!(slot(SystemCursor::Hand), slot(SystemCursor::Help));
+    assert_ne!(slot(SystemCursor::Arrow), slot(SystemCursor::Help));
+
+    // With the role's name taken as well there's nothing left to pick
+    let mapping = [
+        (SystemCursor::Arrow, PathBuf::from("a/Help.cur")),
+        (SystemCursor::Help, PathBuf::from("b/help.cur")),
+    ];
+    let err = install_scheme("Twins", &mapping, Scope::User, &options(&dir, false)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "a/Help.cur and b/help.cur would both be installed as Help.cur"
+    );
+}